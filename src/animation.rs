@@ -0,0 +1,150 @@
+//! Short animations on tiles, to see what the board did without reading the status line: a
+//! flash when a sound plays, a sweep while a level rule cools down before it can play its sound
+//! again, and a shake when a sound can't play.
+//!
+//! Animations only say how far along they are at a given moment; the tiles are drawn from that,
+//! every frame while any are running.
+
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::time::{Duration, Instant};
+
+const FLASH: Duration = Duration::from_millis(300);
+const SHAKE: Duration = Duration::from_millis(400);
+/// How far a shaking tile moves to either side, in columns
+const SHAKE_WIDTH: f32 = 2.0;
+/// Back and forth this many times while shaking
+const SHAKES: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// The sound played
+    Flash,
+    /// The sound won't be played by a level rule again for this long
+    Cooldown(Duration),
+    /// The sound couldn't play
+    Shake,
+}
+
+impl Effect {
+    fn length(self) -> Duration {
+        match self {
+            Effect::Flash => FLASH,
+            Effect::Cooldown(length) => length,
+            Effect::Shake => SHAKE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Animation {
+    effect: Effect,
+    start: Instant,
+}
+
+impl Animation {
+    /// From 0 when it starts to 1 when it is over, `None` after that.
+    fn progress(&self, now: Instant) -> Option<f32> {
+        let length = self.effect.length();
+        let elapsed = now.saturating_duration_since(self.start);
+        (elapsed < length).then(|| elapsed.as_secs_f32() / length.as_secs_f32())
+    }
+}
+
+/// Slows down towards the end, so things settle gently.
+pub fn ease_out(t: f32) -> f32 {
+    1.0 - (1.0 - t.clamp(0.0, 1.0)).powi(3)
+}
+
+/// The animations running on each tile, by index into [`crate::config::Board::sounds`].
+#[derive(Debug, Default)]
+pub struct Animations {
+    tiles: HashMap<usize, Vec<Animation>>,
+}
+
+impl Animations {
+    /// Starts `effect` on tile `idx`, in place of the same kind of effect if that was running.
+    pub fn start(&mut self, idx: usize, effect: Effect, now: Instant) {
+        if effect.length().is_zero() {
+            return;
+        }
+        // What is over is dropped here, there being no other time animations change
+        self.tiles.retain(|_, running| {
+            running.retain(|a| a.progress(now).is_some());
+            !running.is_empty()
+        });
+        let running = self.tiles.entry(idx).or_default();
+        running.retain(|a| std::mem::discriminant(&a.effect) != std::mem::discriminant(&effect));
+        running.push(Animation { effect, start: now });
+    }
+
+    /// Forgets every animation, for when tiles are no longer where they were.
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+    }
+
+    /// Whether anything is still animating at `now`.
+    pub fn active(&self, now: Instant) -> bool {
+        self.tiles.values().flatten().any(|a| a.progress(now).is_some())
+    }
+
+    fn progress(&self, idx: usize, now: Instant, matches: impl Fn(Effect) -> bool) -> Option<f32> {
+        self.tiles.get(&idx)?.iter().filter(|a| matches(a.effect)).find_map(|a| a.progress(now))
+    }
+
+    /// How bright tile `idx` flashes, from 1 right after playing down to nothing.
+    pub fn flash(&self, idx: usize, now: Instant) -> Option<f32> {
+        self.progress(idx, now, |e| e == Effect::Flash).map(|t| 1.0 - ease_out(t))
+    }
+
+    /// How much of tile `idx`'s cooldown is left, from 1 down to nothing.
+    pub fn cooldown(&self, idx: usize, now: Instant) -> Option<f32> {
+        self.progress(idx, now, |e| matches!(e, Effect::Cooldown(_))).map(|t| 1.0 - t)
+    }
+
+    /// How many columns tile `idx` is moved to the right (or left) while it shakes.
+    pub fn shake(&self, idx: usize, now: Instant) -> Option<i16> {
+        self.progress(idx, now, |e| e == Effect::Shake).map(|t| ((t * SHAKES * TAU).sin() * SHAKE_WIDTH * (1.0 - t)).round() as i16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use super::{Animations, Effect};
+
+    #[test]
+    fn effects_run_their_length() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut animations = Animations::default();
+        animations.start(0, Effect::Flash, start);
+        animations.start(1, Effect::Cooldown(Duration::from_secs(2)), start);
+        animations.start(2, Effect::Shake, start);
+
+        assert_eq!(animations.flash(0, start), Some(1.0));
+        assert!(animations.flash(0, at(150)).is_some_and(|f| f > 0.0 && f < 0.5));
+        assert_eq!(animations.flash(0, at(300)), None);
+        assert_eq!(animations.cooldown(1, at(500)), Some(0.75));
+        assert_eq!(animations.shake(2, at(50)), Some(1));
+        assert_eq!(animations.shake(2, at(400)), None);
+        // Other tiles and other effects aren't affected
+        assert_eq!(animations.flash(1, start), None);
+        assert_eq!(animations.cooldown(0, start), None);
+
+        assert!(animations.active(at(1000)));
+        assert!(!animations.active(at(2000)));
+    }
+
+    #[test]
+    fn starting_again_starts_over() {
+        let start = Instant::now();
+        let mut animations = Animations::default();
+        animations.start(0, Effect::Flash, start);
+        animations.start(0, Effect::Flash, start + Duration::from_millis(200));
+        assert!(animations.flash(0, start + Duration::from_millis(400)).is_some());
+        // No cooldown at all is nothing to show
+        animations.start(1, Effect::Cooldown(Duration::ZERO), start);
+        assert_eq!(animations.cooldown(1, start), None);
+    }
+}
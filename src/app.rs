@@ -0,0 +1,3129 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use rodio::cpal::Stream;
+use crate::animation::{Animations, Effect};
+use crate::artnet;
+use crate::assign::{self, Strategy};
+use crate::cache::Cache;
+use crate::audio::{Audio, Engine, Finished, Output, Playback, Shape};
+use crate::backup::{self, Backup};
+use crate::branding::{Art, SPLASH_TIME};
+use crate::browser::Browser;
+use crate::binding::{Binding, KeyChord, PadInput, Trigger, TriggerMap};
+use crate::check::{Check, Signal};
+use crate::clipboard::{self, Clip};
+use crate::combo::Combos;
+use crate::commands::{Command, Commands, Entry};
+use crate::config::{Board, Bus, BusLevel, Continuous, Fader, Scene, Schedule, SignalAction, Sound, Source, Then, TileLayout, MAX_VOLUME};
+use crate::conflict::{self, Conflict, Resolution};
+use crate::edit::{Edit, Field};
+use crate::fifo::{self, Fifo};
+use crate::gamepad::{self, PadEvent, Pads};
+use crate::history::{self, History, Via};
+use crate::hit::{HitMap, Target};
+use crate::input::{Caps, InputEvent};
+use crate::instance::Server;
+use crate::loader::{Decoded, Loader, State};
+use crate::locale::Locale;
+use crate::freesound;
+use crate::{fetch, mdns, merge, metrics, mqtt, rpc, sync, toml};
+use crate::mic::{self, Listener};
+use crate::midi::{self, Feedback, Midi, MidiEvent};
+use crate::names::{self, Added};
+use crate::palette::{Palette, Styles};
+use crate::paths::Paths;
+use crate::pedal::{self, Pedals};
+use crate::reader::Reader;
+use crate::scope::Mode;
+use crate::search::{self, Hit, Search, Site};
+use crate::settings::{Kind, Settings, Tab};
+use crate::signals::{self, Watcher};
+use crate::speech::{self, SpeechBounds};
+use crate::sync::Synced;
+use crate::timer::Timers;
+use crate::ui::{Grid, Redraw};
+use crate::verify::{self, Finding, Problem};
+use crate::voice::{self, Recognizer};
+use crate::shell::Shell;
+use crate::show::Shown;
+use crate::then::Hooks;
+use crate::vars::{self, Vars};
+use crate::webhook::Webhooks;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Board,
+    Trash,
+    /// Picking a strategy to reassign every sound's key with
+    Assign,
+    /// Reviewing bindings that get in each other's way
+    Conflicts,
+    /// Running through the cue list in order
+    Cues,
+    /// What was played this session
+    History,
+    /// What command tiles printed
+    Log,
+    /// Picking files to add to the board
+    Browse,
+    /// Searching the web for sounds to add
+    Search,
+    /// Checking the outputs and how they are routed
+    Check,
+    /// Finding anything the board can do by name
+    Commands,
+    /// Changing the board's options
+    Settings,
+    /// Changing one sound
+    Edit,
+    /// Going back to an earlier version of the config
+    Backups,
+    /// Mapping a MIDI controller's knobs and faders, see [`crate::midi`]
+    Learn,
+}
+
+pub struct App {
+    pub board: Board,
+    /// Where the config is, and the board's other files go
+    pub paths: Paths,
+    pub view: View,
+    pub selected: usize,
+    pub trash_selected: usize,
+    pub assign_selected: usize,
+    pub conflicts: Vec<Conflict>,
+    pub conflict_selected: usize,
+    /// Which of the selected conflict's sounds the resolution keys act on
+    pub conflict_side: usize,
+    /// The cue that GO plays next
+    pub cue_standby: usize,
+    /// The cue GO played last
+    pub cue_last: Option<usize>,
+    pub status: Option<String>,
+    pub caps: Caps,
+    /// The language to show the board in when the config doesn't pick one
+    pub system_locale: Locale,
+    /// The colors to draw the board in when the config doesn't pick them
+    pub system_palette: Palette,
+    /// Set by `--screen-reader`; the config can turn screen reader mode on as well
+    pub screen_reader: bool,
+    pub reader: Reader,
+    pub grid: Grid,
+    pub redraw: Redraw,
+    pub animations: Animations,
+    triggers: TriggerMap,
+    pub hits: HitMap,
+    pub should_quit: bool,
+    /// Set when the user asked to edit the config; the main loop suspends the TUI for the editor.
+    pub edit_config: bool,
+    /// Set on ^Z; the main loop gives the terminal back to the shell and stops.
+    pub suspend: bool,
+    /// Performance mode: the board can be played but not changed
+    pub locked: bool,
+    /// Until when a second ^L unlocks the board
+    unlocking: Option<Instant>,
+    /// The config on disk doesn't load, so the board isn't saved over it until it does again
+    pub read_only: bool,
+    /// Only the monitor plays, nobody listening on the external outputs hears anything
+    pub rehearsal: bool,
+    /// When the board last caught up in [`App::tick`], which is when events are taken to happen
+    pub now: Instant,
+    /// When someone last pressed, clicked or stepped on something, for `[idle]`
+    last_input: Instant,
+    /// Dimmed after a while without input, see [`crate::config::Idle`]
+    pub idle: bool,
+    /// The board's header, see [`crate::branding`]
+    pub header: Option<Art>,
+    /// The splash shown in place of the board, and until when
+    pub splash: Option<(Art, Instant)>,
+    /// How the scope shows what plays, when it is open, see [`crate::scope`]
+    pub scope: Option<Mode>,
+    /// The loops paused while the board is idle, to play on once it wakes
+    paused_loops: Vec<u64>,
+    /// The minute the clock was at on the last tick, to redraw tiles that show the time
+    minute: u64,
+    /// Picks which of a tile's files plays, for tiles with several
+    rng: u64,
+    pub engine: Engine,
+    /// Listens to the microphone to duck the board, while talkover is on
+    pub talkover: Option<Listener>,
+    pub history: History,
+    /// The file picker, kept after closing it so it opens where it was left
+    pub browser: Option<Browser>,
+    /// The search for sounds on the web, kept after closing it like the file picker
+    pub search: Option<Search>,
+    /// The command palette, made afresh each time it opens
+    pub commands: Option<Commands>,
+    /// The settings screen, kept on the tab it was left on
+    pub settings: Option<Settings>,
+    /// The sound being edited
+    pub edit: Option<Edit>,
+    /// The config's earlier versions, read when their list opens
+    pub backups: Vec<Backup>,
+    pub backup_selected: usize,
+    /// What the next knob or fader moved while learning is mapped to
+    pub learn_selected: usize,
+    /// The show timer and the countdowns, see [`crate::timer`]
+    pub timers: Timers,
+    /// A sound from a search being downloaded, and whether to add it to the board once it's in
+    search_download: Option<(Hit, bool, Receiver<color_eyre::Result<PathBuf>>)>,
+    /// The audio check, looked at again every time it is opened
+    pub check: Option<Check>,
+    /// The inputs level rules listen on, and where they report rules that fired
+    levels: Option<(Vec<Stream>, Receiver<usize>)>,
+    voice: Option<Recognizer>,
+    /// Sounds that were added being transcribed, to name them after what they say
+    transcribing: Vec<Receiver<(Added, color_eyre::Result<Option<String>>)>>,
+    pads: Option<Pads>,
+    midi: Option<Midi>,
+    /// What the MIDI controllers were told last
+    feedback: Feedback,
+    pedals: Option<Pedals>,
+    signals: Option<Watcher>,
+    fifo: Option<Fifo>,
+    /// Where other starts of the board with this config hand over to this one
+    pub instance: Option<Server>,
+    /// Where the board is drawn, `None` when it runs without a terminal interface
+    pub shown: Option<Shown>,
+    /// Set by `soundboard show`, to flag the terminal the board is in
+    pub raise: bool,
+    metrics: Option<metrics::Server>,
+    advertiser: Option<mdns::Advertiser>,
+    artnet: Option<artnet::Output>,
+    webhooks: Webhooks,
+    /// Runs the commands of tiles that have one instead of a sound
+    pub shell: Shell,
+    /// What sounds do once they are over
+    hooks: Hooks,
+    /// The sounds played last, for the combos they complete
+    combos: Combos,
+    mqtt: Option<mqtt::Client>,
+    /// What the clipboard last pointed at and where that is on disk, so pasting it again adds it
+    clip: Option<(Clip, PathBuf)>,
+    /// A copied URL being downloaded, to play once it is done
+    clip_download: Option<(Clip, Receiver<color_eyre::Result<PathBuf>>)>,
+    loader: Loader,
+    /// The sounds decoded so far or being decoded, which is every sound on the board
+    pub loaded: HashMap<Source, State>,
+    /// Sounds that were triggered while still loading, to play once they are loaded, and the
+    /// bus they play on when it isn't their own
+    pub waiting: Vec<(Source, Via, Option<String>)>,
+    /// When each sound last played, to know which to drop when decoded sounds take too much memory
+    last_played: HashMap<Source, Instant>,
+    /// Converted sounds, which play instead of the originals when they are there
+    cache: Option<Cache>,
+    /// Sounds being converted, with how many were converted and how many failed so far
+    converting: Option<(Receiver<color_eyre::Result<bool>>, usize, usize)>,
+    /// The check of the board's files that runs as it starts, see [`crate::verify`]
+    verifying: Option<Receiver<Vec<Finding>>>,
+    /// The sync running in the background, see [`crate::sync`], and the board as it was when it
+    /// started, to tell what changed here in the meantime
+    syncing: Option<(Receiver<color_eyre::Result<Synced>>, Board)>,
+    /// When the board syncs next, with `every` in `[sync]`
+    next_sync: Option<Instant>,
+    /// The board changed in a way that is saved once things settle down, like volume scrolling
+    save_at: Option<Instant>,
+    /// Sounds were played, which is written to the state directory once things settle down
+    plays_at: Option<Instant>,
+    /// The tile whose volume was last changed, and when, to briefly highlight its indicator
+    pub volume_changed: Option<(usize, Instant)>,
+    /// Sounds on the board that are playing, by the engine's id for them
+    pub playing: HashMap<u64, Playing>,
+    /// Loops that are playing, by the name of their sound, to stop them when they are played
+    /// again
+    looping: HashMap<String, u64>,
+    /// How long each sound decoded so far plays, also once it is no longer kept in memory
+    lengths: HashMap<Source, Duration>,
+}
+
+/// How far along a sound on the board is.
+#[derive(Debug, Clone)]
+pub struct Playing {
+    pub source: Source,
+    pub length: Duration,
+    /// How long it has played, which stands still while muted
+    pub elapsed: Duration,
+    /// Starts over at the end, so the progress does too
+    pub looped: bool,
+}
+
+impl Playing {
+    /// How much of the sound has played, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        let elapsed = if self.looped { self.elapsed.as_secs_f32() % self.length.as_secs_f32() } else { self.elapsed.as_secs_f32() };
+        (elapsed / self.length.as_secs_f32()).clamp(0.0, 1.0)
+    }
+}
+
+/// Mutes everything, in every view.
+pub const MUTE_KEY: KeyCode = KeyCode::F(12);
+/// Starts and stops recording what the monitor plays.
+pub const RECORD_KEY: KeyCode = KeyCode::F(9);
+
+const fn ctrl(c: char) -> KeyChord {
+    KeyChord { code: KeyCode::Char(c), modifiers: KeyModifiers::CONTROL }
+}
+
+/// Keys the board itself acts on, and whether they are handled before sound bindings (so a
+/// sound bound to them never plays) or after (so the sound hides the action).
+pub const ACTIONS: &[(KeyChord, &str, bool)] = &[
+    (KeyChord::new(MUTE_KEY), "mute", true),
+    (KeyChord::new(RECORD_KEY), "record", true),
+    (ctrl('e'), "edit config", true),
+    (ctrl('r'), "reassign keys", true),
+    (ctrl('k'), "binding conflicts", true),
+    (ctrl('l'), "lock", true),
+    (ctrl('p'), "rehearsal", true),
+    (ctrl('g'), "cue list", true),
+    (ctrl('t'), "talkover", true),
+    (ctrl('o'), "history", true),
+    (ctrl('u'), "command output", true),
+    (ctrl('w'), "scope", true),
+    (ctrl('v'), "play clipboard", true),
+    (ctrl('b'), "browse files", true),
+    (ctrl('f'), "search for sounds", true),
+    (ctrl('d'), "audio check", true),
+    (ctrl('n'), "midi learn", true),
+    (ctrl('y'), "reset show timer", true),
+    (ctrl('a'), "command palette", true),
+    (ctrl('s'), "settings", true),
+    (ctrl('z'), "suspend", true),
+    (KeyChord::new(KeyCode::Esc), "quit", false),
+    (KeyChord::new(KeyCode::Tab), "view trash", false),
+    (KeyChord::new(KeyCode::Enter), "play selected", false),
+    (KeyChord::new(KeyCode::Delete), "trash selected", false),
+    (KeyChord::new(KeyCode::Char('E')), "edit selected", false),
+    (KeyChord::new(KeyCode::Char('R')), "reverse selected", false),
+    (KeyChord::new(KeyCode::Left), "move left", false),
+    (KeyChord::new(KeyCode::Right), "move right", false),
+    (KeyChord::new(KeyCode::Up), "move up", false),
+    (KeyChord::new(KeyCode::Down), "move down", false),
+];
+
+/// Volume change per mouse wheel notch.
+const VOLUME_STEP: f32 = 0.05;
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+/// How long after the first ^L on a locked board the second one unlocks it.
+const UNLOCK_TIME: Duration = Duration::from_secs(3);
+/// How long stopping everything takes, short but without the click of cutting sounds off.
+const STOP_FADE: Duration = Duration::from_millis(150);
+
+impl App {
+    pub fn new(board: Board, paths: Paths, caps: Caps, engine: Engine) -> Self {
+        let mut app = Self::offline(board, paths, caps, engine);
+        app.system_locale = Locale::from_env();
+        app.system_palette = Palette::from_env();
+        if app.board.talkover.is_some() {
+            app.set_talkover(true);
+        }
+        app.watch_levels();
+        app.start_voice();
+        app.open_pads();
+        app.open_midi();
+        app.open_pedals();
+        app.watch_signals();
+        app.open_fifo();
+        app.serve_metrics();
+        app.start_artnet();
+        app.connect_mqtt();
+        app.schedule_sync();
+        app
+    }
+
+    /// The board playing on `engine`, without anything that listens to the world outside or
+    /// reaches out to it: no microphones, gamepads, MIDI controllers, pedals or signals, and no
+    /// network services.
+    /// It is in English and the default colors unless the config says otherwise, whatever the
+    /// environment asks for.
+    pub fn offline(mut board: Board, paths: Paths, caps: Caps, engine: Engine) -> Self {
+        history::load_plays(&paths, &mut board);
+        let grid = Grid::new(&board);
+        let triggers = board.triggers();
+        let mut app = Self {
+            board,
+            paths,
+            view: View::Board,
+            selected: 0,
+            trash_selected: 0,
+            assign_selected: 0,
+            conflicts: Vec::new(),
+            conflict_selected: 0,
+            conflict_side: 0,
+            cue_standby: 0,
+            cue_last: None,
+            status: None,
+            caps,
+            system_locale: Locale::English,
+            system_palette: Palette::Default,
+            screen_reader: false,
+            reader: Reader::default(),
+            grid,
+            redraw: Redraw::default(),
+            animations: Animations::default(),
+            triggers,
+            hits: HitMap::default(),
+            should_quit: false,
+            edit_config: false,
+            suspend: false,
+            locked: false,
+            read_only: false,
+            unlocking: None,
+            rehearsal: false,
+            now: Instant::now(),
+            last_input: Instant::now(),
+            idle: false,
+            header: None,
+            scope: None,
+            splash: None,
+            paused_loops: Vec::new(),
+            minute: 0,
+            rng: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.subsec_nanos() as u64) | 1,
+            engine,
+            talkover: None,
+            history: History::default(),
+            browser: None,
+            check: None,
+            search: None,
+            commands: None,
+            settings: None,
+            edit: None,
+            backups: Vec::new(),
+            backup_selected: 0,
+            learn_selected: 0,
+            timers: Timers::new(Instant::now()),
+            search_download: None,
+            loader: Loader::new(),
+            loaded: HashMap::new(),
+            waiting: Vec::new(),
+            last_played: HashMap::new(),
+            cache: None,
+            converting: None,
+            verifying: None,
+            syncing: None,
+            next_sync: None,
+            levels: None,
+            voice: None,
+            transcribing: Vec::new(),
+            pads: None,
+            midi: None,
+            feedback: Feedback::default(),
+            pedals: None,
+            signals: None,
+            fifo: None,
+            instance: None,
+            shown: None,
+            raise: false,
+            metrics: None,
+            advertiser: None,
+            artnet: None,
+            webhooks: Webhooks::default(),
+            shell: Shell::default(),
+            hooks: Hooks::default(),
+            combos: Combos::default(),
+            mqtt: None,
+            clip: None,
+            clip_download: None,
+            save_at: None,
+            plays_at: None,
+            volume_changed: None,
+            playing: HashMap::new(),
+            looping: HashMap::new(),
+            lengths: HashMap::new(),
+        };
+        app.engine.resampler = app.board.resampler;
+        app.engine.groups = app.board.groups.clone();
+        app.engine.set_buses(app.board.buses.clone());
+        app.engine.set_crossfader(app.board.crossfader.clone());
+        app.check_conflicts();
+        app.fill_cache();
+        app.load_sounds();
+        app.verify();
+        app.load_art(true);
+        app
+    }
+
+    /// Checks in the background that the board's files are all there and unchanged, to say so
+    /// before one of them is needed.
+    pub fn verify(&mut self) {
+        self.verifying = Some(verify::start(&self.board, &self.paths.config));
+    }
+
+    /// Reads the header's art, and the splash's too when the board is just starting.
+    pub fn load_art(&mut self, splash: bool) {
+        let config = self.paths.config.clone();
+        let load = |path: &Option<PathBuf>, status: &mut Option<String>| {
+            let art = Art::load(path.as_deref()?, &config);
+            art.map_err(|e| *status = Some(format!("{e:#}"))).ok()
+        };
+        self.header = load(&self.board.header, &mut self.status);
+        if splash {
+            self.splash = load(&self.board.splash, &mut self.status).map(|art| (art, self.now + SPLASH_TIME));
+        }
+    }
+
+    /// Starts decoding sounds that were added, and drops the ones that were removed.
+    fn load_sounds(&mut self) {
+        let sounds = &self.board.sounds;
+        let on_board = |source: &Source| sounds.iter().any(|s| s.sources().any(|s| s == source));
+        self.loaded.retain(|source, _| on_board(source));
+        self.waiting.retain(|(source, _, _)| on_board(source));
+        self.last_played.retain(|source, _| on_board(source));
+        self.loader.cancel(on_board);
+        // Command tiles have nothing to load
+        for source in sounds.iter().flat_map(Sound::sources).filter(|s| !matches!(s, Source::Command(_))) {
+            if !self.loaded.contains_key(source) {
+                self.loaded.insert(source.clone(), State::Loading);
+                self.loader.load(source.clone(), false);
+            }
+        }
+    }
+
+    /// How many bytes decoded sounds may take.
+    fn memory_budget(&self) -> Option<usize> {
+        self.board.memory.map(|mb| (mb as usize).saturating_mul(1024 * 1024))
+    }
+
+    /// Drops decoded sounds last played before `before` until `size` more bytes fit in the
+    /// memory budget, returning whether they do. Nothing is dropped when they wouldn't.
+    fn make_room(&mut self, size: usize, before: Option<Instant>) -> bool {
+        let Some(budget) = self.memory_budget() else { return true };
+        let mut used: usize = self.loaded.values().map(|state| if let State::Ready(d) = state { d.size() } else { 0 }).sum();
+        // Sounds that never played sort first, as played longest ago
+        let mut older: Vec<_> = self
+            .loaded
+            .iter()
+            .filter_map(|(source, state)| match state {
+                State::Ready(d) if self.last_played.get(source).copied() < before => Some((self.last_played.get(source).copied(), source.clone(), d.size())),
+                _ => None,
+            })
+            .collect();
+        let freeable: usize = older.iter().map(|(_, _, size)| size).sum();
+        if used - freeable + size > budget {
+            return false;
+        }
+        older.sort_by_key(|(played, _, _)| *played);
+        for (_, source, freed) in older {
+            if used + size <= budget {
+                break;
+            }
+            // Still playing is fine, the samples go once it is done
+            self.loaded.insert(source, State::Streaming(freed));
+            used -= freed;
+        }
+        true
+    }
+
+    /// Converts the board's sounds into the cache in the background, when it has one.
+    fn fill_cache(&mut self) {
+        self.cache = Cache::new(self.board.cache.as_ref().and_then(|c| c.dir.as_deref()).or(self.paths.cache.as_deref()), self.board.resampler);
+        self.loader.set_cache(self.cache.clone());
+        self.converting = None;
+        let (Some(cache), Some(_)) = (&self.cache, &self.board.cache) else { return };
+        let files = self.board.sounds.iter().flat_map(Sound::sources).filter_map(|s| match s {
+            Source::File(path) => Some(path.clone()),
+            Source::Builtin(_) | Source::Command(_) => None,
+        });
+        self.converting = Some((cache.convert_all(files.collect()), 0, 0));
+    }
+
+    fn connect_mqtt(&mut self) {
+        self.mqtt = self.board.mqtt.as_ref().map(|config| mqtt::connect(config, self.engine.metrics.clone()));
+    }
+
+    /// Syncs right away when the board syncs while it runs, and every so often from then on.
+    fn schedule_sync(&mut self) {
+        self.next_sync = self.board.sync.as_ref().and_then(|s| s.every).map(|_| self.now);
+    }
+
+    /// Syncs the board with the remote in `[sync]` in the background, once what is waiting to
+    /// be saved is. Nothing is synced while the board is locked, so it doesn't change mid-show.
+    pub fn sync(&mut self) {
+        let Some(settings) = self.board.sync.clone() else { return };
+        self.next_sync = settings.every.map(|every| self.now + every);
+        if self.syncing.is_some() || self.locked {
+            return;
+        }
+        self.flush();
+        self.syncing = Some((sync::start(&self.board, &self.paths, &settings), self.board.clone()));
+    }
+
+    /// Takes in what a sync did. A config that changed on the remote is loaded, merged with what
+    /// was changed here while the sync ran.
+    fn synced(&mut self, result: color_eyre::Result<Synced>, before: Board) {
+        let synced = match result {
+            Ok(synced) => synced,
+            Err(e) => {
+                self.status = Some(format!("sync: {e:#}"));
+                return;
+            }
+        };
+        let mut migrated = None;
+        if synced.config_changed() {
+            match Board::load(&self.paths.config) {
+                Ok((loaded, upgraded)) if self.board == before => {
+                    migrated = upgraded;
+                    self.replace_board(loaded);
+                    self.read_only = false;
+                }
+                Ok((loaded, upgraded)) => {
+                    migrated = upgraded;
+                    let merged = merge::boards(&before, &self.board, &loaded, true);
+                    self.replace_board(merged);
+                    self.read_only = false;
+                    self.save_at = None;
+                    self.save();
+                }
+                Err(e) => {
+                    self.read_only = true;
+                    self.status = Some(format!("the synced config is invalid, keeping the board without saving it: {e:#}"));
+                    return;
+                }
+            }
+        }
+        match (synced.plan.conflicts.len(), synced.config_changed()) {
+            (0, false) => {}
+            (0, true) => self.status = Some("synced the board with the changes made elsewhere".to_string()),
+            (conflicts, _) => self.status = Some(format!("{conflicts} file(s) changed here and on the remote, see `soundboard sync`")),
+        }
+        // A board from elsewhere written by an older soundboard
+        if let Some(migrated) = migrated {
+            self.status = Some(migrated.to_string());
+        }
+    }
+
+    fn start_artnet(&mut self) {
+        self.artnet = None;
+        let Some(config) = &self.board.artnet else { return };
+        match artnet::Output::new(config) {
+            Ok(output) => self.artnet = Some(output),
+            Err(e) => self.status = Some(format!("art-net: {e:#}")),
+        }
+    }
+
+    fn advertise(&mut self) {
+        let (Some(config), Some(server), Some(endpoint)) = (&self.board.mdns, &self.metrics, &self.board.metrics) else { return };
+        let name = config.name.clone().unwrap_or_else(mdns::hostname);
+        let mut txt = vec![format!("version={}", env!("CARGO_PKG_VERSION")), "path=/metrics".to_string()];
+        if !endpoint.tokens.is_empty() {
+            txt.push("auth=bearer".to_string());
+        }
+        match mdns::advertise(&name, server.port(), txt) {
+            Ok(advertiser) => self.advertiser = Some(advertiser),
+            Err(e) => self.status = Some(format!("mdns: {e:#}")),
+        }
+    }
+
+    fn serve_metrics(&mut self) {
+        // Stopped first, so the address is free again
+        self.advertiser = None;
+        self.metrics = None;
+        let Some(endpoint) = &self.board.metrics else { return };
+        match metrics::serve(endpoint, self.engine.metrics.clone()) {
+            Ok(server) => {
+                self.metrics = Some(server);
+                if endpoint.open_to_network() {
+                    self.status = Some(format!("metrics on {} are open to anyone on the network, set `tokens` to require one", endpoint.listen));
+                }
+                self.advertise();
+            }
+            Err(e) => self.status = Some(format!("metrics: {e:#}")),
+        }
+    }
+
+    fn open_fifo(&mut self) {
+        // Dropping the old pipe first, it may be the same path
+        self.fifo = None;
+        let Some(path) = &self.board.fifo else { return };
+        match fifo::open(path) {
+            Ok(fifo) => self.fifo = Some(fifo),
+            Err(e) => self.status = Some(format!("fifo: {e:#}")),
+        }
+    }
+
+    fn watch_signals(&mut self) {
+        self.signals = None;
+        if self.board.signals.is_empty() {
+            return;
+        }
+        match signals::watch(&self.board.signals) {
+            Ok(watcher) => self.signals = Some(watcher),
+            Err(e) => self.status = Some(format!("signals: {e:#}")),
+        }
+    }
+
+    fn start_voice(&mut self) {
+        self.voice = None;
+        let Some(command) = &self.board.voice else { return };
+        match voice::start(command) {
+            Ok(recognizer) => self.voice = Some(recognizer),
+            Err(e) => self.status = Some(format!("voice: {e:#}")),
+        }
+    }
+
+    /// Listens to gamepads, as long as any sound is bound to one.
+    fn open_pads(&mut self) {
+        self.pads = None;
+        if !self.uses_pads() {
+            return;
+        }
+        match gamepad::open() {
+            Ok(pads) => self.pads = Some(pads),
+            Err(e) => self.status = Some(format!("gamepad: {e:#}")),
+        }
+    }
+
+    /// Listens to MIDI controllers, as long as any sound is bound to a note or a fader is
+    /// mapped, or faders are being learned.
+    fn open_midi(&mut self) {
+        self.midi = None;
+        if !self.uses_midi() {
+            return;
+        }
+        match midi::open() {
+            Ok(midi) => self.midi = Some(midi),
+            Err(e) => self.status = Some(format!("midi: {e:#}")),
+        }
+    }
+
+    fn uses_midi(&self) -> bool {
+        let notes = self.board.sounds.iter().flat_map(|s| &s.bindings).any(|b| matches!(b.trigger, Trigger::MidiNote(_)));
+        notes || !self.board.faders.is_empty() || self.view == View::Learn
+    }
+
+    fn open_pedals(&mut self) {
+        self.pedals = None;
+        if self.board.pedals.is_empty() {
+            return;
+        }
+        match pedal::open(self.pedal_devices()) {
+            Ok(pedals) => self.pedals = Some(pedals),
+            Err(e) => self.status = Some(format!("pedals: {e:#}")),
+        }
+    }
+
+    /// Devices the pedal rules name, besides the pedals the board recognizes by itself.
+    fn pedal_devices(&self) -> Vec<String> {
+        self.board.pedals.iter().filter_map(|p| p.device.clone()).collect()
+    }
+
+    fn uses_pads(&self) -> bool {
+        self.board.sounds.iter().flat_map(|s| &s.bindings).any(|b| matches!(b.trigger, Trigger::Pad(_)))
+    }
+
+    fn watch_levels(&mut self) {
+        self.levels = None;
+        if self.board.levels.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        match mic::watch_levels(&self.board.levels, tx) {
+            Ok(streams) => self.levels = Some((streams, rx)),
+            Err(e) => self.status = Some(format!("level rules: {e:#}")),
+        }
+    }
+
+    /// Starts or stops ducking the board when the microphone hears speech.
+    fn set_talkover(&mut self, on: bool) {
+        self.talkover = None;
+        self.engine.duck.set(1.0);
+        if !on {
+            return;
+        }
+        let settings = self.board.talkover.clone().unwrap_or_default();
+        match mic::talkover(&settings, self.engine.duck.clone()) {
+            Ok(listener) => self.talkover = Some(listener),
+            Err(e) => self.status = Some(format!("talkover: {e:#}")),
+        }
+    }
+
+    pub fn play(&mut self, idx: usize, via: Via) {
+        self.play_source(idx, via, None, None);
+    }
+
+    /// A random number, for what is left to chance.
+    fn roll(&mut self) -> u64 {
+        // xorshift, plenty for picking a file
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+
+    /// Plays the sound at `idx`, from `source` when it is one of its files that was waited for,
+    /// or from one of them picked at random, on `bus` rather than its own when that is given.
+    fn play_source(&mut self, idx: usize, via: Via, source: Option<Source>, bus: Option<&str>) {
+        let roll = self.roll();
+        let Some(sound) = self.board.sounds.get(idx) else { return };
+        let t = self.locale();
+        self.redraw.dirty = true;
+        // A loop plays until it is played again
+        if let Some(id) = self.looping.remove(&sound.name) {
+            self.engine.stop(id, STOP_FADE);
+            return;
+        }
+        // What placeholders come to as it plays, so counting this time too
+        let vars = Vars { plays: sound.plays + 1, ..Vars::of(sound) };
+        if let Source::Command(command) = &sound.source {
+            self.history.push(&sound.name, via);
+            self.shell.run(&sound.name, &vars::expand_shell(command, &vars));
+            self.board.sounds[idx].plays += 1;
+            self.animations.start(idx, Effect::Flash, self.now);
+            self.plays_at = Some(self.now + SAVE_DELAY);
+            self.combo(idx, via);
+            return;
+        }
+        let source = source.unwrap_or_else(|| sound.pick(roll).clone());
+        let audio = match self.loaded.get(&source) {
+            Some(State::Ready(decoded)) => Ok(Audio::Decoded(decoded.clone())),
+            Some(State::Loading) => {
+                if !self.waiting.iter().any(|(s, _, _)| *s == source) {
+                    self.waiting.push((source.clone(), via, bus.map(str::to_string)));
+                }
+                self.loader.load(source, true);
+                return;
+            }
+            Some(State::Failed(e)) => Err(color_eyre::eyre::eyre!("{e}")),
+            // Not kept in memory, so decoded while it plays; the converted sound starts sooner
+            Some(State::Streaming(_)) | None => {
+                let cached = match (&source, &self.cache) {
+                    (Source::File(path), Some(cache)) => cache.get(path).and_then(|entry| fs::read(entry).ok()),
+                    _ => None,
+                };
+                cached.map(Cow::Owned).map_or_else(|| source.data(), Ok).map(Audio::Encoded)
+            }
+        };
+        match audio {
+            Ok(audio) => {
+                self.history.push(&sound.name, via);
+                if self.reading() {
+                    self.reader.say(t.format("reader-playing", &[("sound", &sound.name)]));
+                }
+                let devices = self.devices();
+                let group = sound.group.as_deref().map(|g| (g, self.board.group_fade(g)));
+                let id = self.engine.play(Playback { name: &sound.name, audio, volume: sound.volume, speakers: sound.speakers.clone(), devices, group, bus: bus.or(self.board.bus_of(sound)), shape: sound.shape });
+                if let Some(length) = self.lengths.get(&source).map(|&length| sound.shape.length(length)).filter(|length| !length.is_zero()) {
+                    self.playing.insert(id, Playing { source: source.clone(), length, elapsed: Duration::ZERO, looped: sound.shape.looped });
+                }
+                if sound.shape.looped {
+                    self.looping.insert(sound.name.clone(), id);
+                }
+                if let Some(Err(e)) = self.artnet.as_mut().map(|a| a.start(id, &sound.dmx)) {
+                    self.status = Some(format!("{e:#}"));
+                }
+                let webhooks: Vec<String> = sound.webhooks.iter().map(|url| vars::expand_url(url, &vars).into_owned()).collect();
+                self.webhooks.start(id, &sound.name, via, &webhooks);
+                self.hooks.start(id, &sound.name, via, &sound.then);
+                if let Some(mqtt) = &mut self.mqtt {
+                    mqtt.start(id, &sound.name, via);
+                }
+                self.board.sounds[idx].plays += 1;
+                self.animations.start(idx, Effect::Flash, self.now);
+                self.plays_at = Some(self.now + SAVE_DELAY);
+                self.last_played.insert(source.clone(), self.now);
+                self.combo(idx, via);
+                // Played again, so it may take the place of a sound played longer ago
+                if let Some(&State::Streaming(size)) = self.loaded.get(&source) {
+                    if self.memory_budget().is_some_and(|budget| size <= budget) {
+                        self.loader.load(source, false);
+                    }
+                }
+            }
+            Err(e) => {
+                self.status = Some(format!("{e:#}"));
+                if self.reading() {
+                    self.reader.alert(t.format("reader-cant-play", &[("sound", &sound.name), ("error", &format!("{e:#}"))]));
+                    self.reader.status = self.status.clone();
+                }
+                self.animations.start(idx, Effect::Shake, self.now);
+            }
+        }
+    }
+
+    /// The outputs to play on: the monitor, and the external outputs unless rehearsing.
+    fn devices(&self) -> Vec<Output> {
+        let outputs = &self.board.outputs;
+        let output = |device: Option<&String>| Output { mono: device.is_some_and(|d| outputs.mono.contains(d)), device: device.cloned() };
+        let mut devices = vec![output(outputs.monitor.as_ref())];
+        if !self.rehearsal {
+            devices.extend(outputs.external.iter().map(|d| output(Some(d))));
+        }
+        devices
+    }
+
+    /// Plays what `text` (from the clipboard, or pasted into the terminal) points at without it
+    /// being on the board, or adds it to the board when it was played like that just before.
+    fn paste(&mut self, text: &str) {
+        let Some(clip) = Clip::parse(text) else {
+            self.status = Some("the clipboard doesn't hold an audio file, or a URL to one".to_string());
+            return;
+        };
+        if let Some((_, path)) = self.clip.as_ref().filter(|(last, _)| *last == clip) {
+            let path = path.clone();
+            self.add_clip(&path);
+            return;
+        }
+        match &clip {
+            Clip::File(path) => {
+                let path = path.clone();
+                self.audition(clip, &path);
+            }
+            Clip::Url(url) => {
+                self.status = Some(format!("downloading {url}"));
+                self.clip_download = Some((clip.clone(), fetch::download(url, clipboard::download_path(url, &self.paths.downloads("clipboard")))));
+            }
+        }
+    }
+
+    fn audition(&mut self, clip: Clip, path: &Path) {
+        let Some(name) = self.preview(path) else { return };
+        self.status = Some(if self.locked {
+            format!("playing {name:?} from the clipboard")
+        } else {
+            format!("playing {name:?} from the clipboard, ^V again to add it to the board")
+        });
+        self.clip = Some((clip, path.to_path_buf()));
+    }
+
+    /// Plays a file that isn't (necessarily) on the board, returning its name if it played.
+    fn preview(&mut self, path: &Path) -> Option<String> {
+        let name = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+        match fs::read(path) {
+            Ok(data) => {
+                self.engine.play(Playback { name: &name, audio: Audio::Encoded(Cow::Owned(data)), volume: 1.0, speakers: Vec::new(), devices: self.devices(), group: None, bus: None, shape: Shape::default() });
+                Some(name)
+            }
+            Err(e) => {
+                self.status = Some(format!("read {}: {e}", path.display()));
+                None
+            }
+        }
+    }
+
+    /// Puts an auditioned file on the board. Downloads are moved next to the config first, the
+    /// cache they are in doesn't last.
+    fn add_clip(&mut self, path: &Path) {
+        if !self.unlocked() {
+            return;
+        }
+        let mut path = path.to_path_buf();
+        if path.starts_with(self.paths.downloads("clipboard")) {
+            let dir = self.paths.sounds();
+            let kept = dir.join(path.file_name().unwrap_or_default());
+            if let Err(e) = fs::create_dir_all(&dir).and_then(|()| fs::copy(&path, &kept)) {
+                self.status = Some(format!("copy {} to {}: {e}", path.display(), dir.display()));
+                return;
+            }
+            // The config can be loaded from anywhere, the working directory isn't where it is
+            path = fs::canonicalize(&kept).unwrap_or(kept);
+        }
+        self.clip = None;
+        self.add(&path);
+    }
+
+    /// Adds a file, or every audio file in a directory, to the board and gives them keys.
+    pub fn add(&mut self, path: &Path) {
+        let before = self.board.sounds.len();
+        if let Err(e) = self.board.import(path) {
+            self.status = Some(format!("{e:#}"));
+            return;
+        }
+        if self.board.portable {
+            if let Err(e) = self.board.carry(before, self.paths.folder(), &self.paths.sounds()) {
+                self.board.sounds.truncate(before);
+                self.status = Some(format!("{e:#}"));
+                return;
+            }
+        }
+        let added = self.board.sounds.len() - before;
+        if added == 0 {
+            self.status = Some(if path.is_dir() {
+                format!("everything in {} is already on the board", path.display())
+            } else {
+                format!("{} is already on the board", path.display())
+            });
+            return;
+        }
+        if let Some(command) = &self.board.transcribe {
+            let files = self.board.sounds[before..].iter().filter_map(|s| match &s.source {
+                Source::File(path) => Some((s.name.clone(), path.clone())),
+                _ => None,
+            });
+            self.transcribing.push(names::start(command.clone(), files.collect()));
+        }
+        let strategy = if self.board.layout == TileLayout::Numpad { Strategy::Numpad } else { Strategy::FirstLetter };
+        assign::assign(&mut self.board, strategy, false);
+        self.board_changed();
+        self.select(before);
+        let sound = &self.board.sounds[before];
+        self.status = Some(match sound.bindings.first() {
+            _ if added > 1 => format!("added {added} sounds to the board"),
+            Some(binding) => format!("added {:?} to the board on {}", sound.name, binding.label),
+            None => format!("added {:?} to the board, there was no key left for it", sound.name),
+        });
+    }
+
+    /// Renames the sound `name` that was added from `path` after the words said in it, unless
+    /// it was renamed since or something came to depend on its name.
+    fn name_after(&mut self, name: &str, path: &Path, words: String) {
+        let source = Source::File(path.to_path_buf());
+        let Some(idx) = self.board.sounds.iter().position(|s| s.name == name && s.source == source) else { return };
+        let mut board = self.board.clone();
+        let renamed = names::unique(words, |n| board.sounds.iter().chain(&board.trash).any(|s| s.name == n));
+        board.sounds[idx].name = renamed;
+        if Board::parse(&toml::to_string(&board.to_table())).is_err() {
+            return;
+        }
+        self.status = Some(format!("{name:?} says {:?}, so that is its name now", board.sounds[idx].name));
+        self.replace_board(board);
+        self.save_later();
+    }
+
+    /// Swaps in a freshly loaded board, e.g. after the config was edited.
+    pub fn replace_board(&mut self, mut board: Board) {
+        self.redraw.dirty = true;
+        // Counted here rather than in the config, so a board read from it has them from before
+        for sound in board.sounds.iter_mut().chain(&mut board.trash) {
+            if let Some(old) = self.board.sounds.iter().chain(&self.board.trash).find(|s| s.name == sound.name) {
+                sound.plays = sound.plays.max(old.plays);
+            }
+        }
+        let talkover_changed = board.talkover != self.board.talkover;
+        let levels_changed = board.levels != self.board.levels;
+        let voice_changed = board.voice != self.board.voice;
+        let signals_changed = board.signals != self.board.signals;
+        let fifo_changed = board.fifo != self.board.fifo;
+        let metrics_changed = board.metrics != self.board.metrics || board.mdns != self.board.mdns;
+        let artnet_changed = board.artnet != self.board.artnet;
+        let mqtt_changed = board.mqtt != self.board.mqtt;
+        let sync_changed = board.sync != self.board.sync;
+        let memory_changed = board.memory != self.board.memory;
+        let buses_changed = board.buses != self.board.buses;
+        let crossfader_changed = board.crossfader != self.board.crossfader;
+        let header_changed = board.header != self.board.header;
+        let files = |b: &Board| b.sounds.iter().flat_map(Sound::sources).filter_map(|s| if let Source::File(p) = s { Some(p.clone()) } else { None }).collect::<Vec<_>>();
+        let cache_changed = board.cache != self.board.cache || board.resampler != self.board.resampler || (board.cache.is_some() && files(&board) != files(&self.board));
+        let used_pads = self.uses_pads();
+        let used_midi = self.uses_midi();
+        // The rules are looked up on every press, only the devices to read matter here
+        let pedals_changed = board.pedals.is_empty() != self.board.pedals.is_empty()
+            || board.pedals.iter().filter_map(|p| p.device.as_ref()).ne(self.board.pedals.iter().filter_map(|p| p.device.as_ref()));
+        self.board = board;
+        self.engine.resampler = self.board.resampler;
+        self.engine.groups = self.board.groups.clone();
+        if buses_changed {
+            self.engine.set_buses(self.board.buses.clone());
+        }
+        if crossfader_changed {
+            self.engine.set_crossfader(self.board.crossfader.clone());
+        }
+        if talkover_changed && (self.talkover.is_some() || self.board.talkover.is_some()) {
+            self.set_talkover(self.board.talkover.is_some());
+        }
+        if levels_changed {
+            self.watch_levels();
+        }
+        if voice_changed {
+            self.start_voice();
+        }
+        if signals_changed {
+            self.watch_signals();
+        }
+        if fifo_changed {
+            self.open_fifo();
+        }
+        if metrics_changed {
+            self.serve_metrics();
+        }
+        if artnet_changed {
+            self.start_artnet();
+        }
+        if mqtt_changed {
+            self.connect_mqtt();
+        }
+        if sync_changed {
+            self.schedule_sync();
+        }
+        if cache_changed {
+            self.fill_cache();
+        }
+        if header_changed {
+            self.load_art(false);
+        }
+        if memory_changed {
+            // Loaded again, they may fit now; and what no longer fits goes
+            self.loaded.retain(|_, state| !matches!(state, State::Streaming(_)));
+            self.make_room(0, Some(Instant::now()));
+        }
+        self.load_sounds();
+        if used_pads != self.uses_pads() {
+            self.open_pads();
+        }
+        if used_midi != self.uses_midi() {
+            self.open_midi();
+        }
+        if pedals_changed {
+            self.open_pedals();
+        }
+        self.grid = Grid::new(&self.board);
+        // Tiles may have moved to other sounds
+        self.animations.clear();
+        self.triggers = self.board.triggers();
+        self.check_conflicts();
+        self.selected = self.selected.min(self.board.sounds.len().saturating_sub(1));
+        self.trash_selected = self.trash_selected.min(self.board.trash.len().saturating_sub(1));
+        self.cue_standby = self.cue_standby.min(self.board.cues.len());
+        self.cue_last = self.cue_last.filter(|&c| c < self.board.cues.len());
+    }
+
+    fn check_conflicts(&mut self) {
+        self.conflicts = conflict::detect(&self.board, &self.caps);
+        self.conflict_selected = self.conflict_selected.min(self.conflicts.len().saturating_sub(1));
+        self.conflict_side = 0;
+    }
+
+    /// Runs time based work; called every iteration of the main loop.
+    pub fn tick(&mut self, now: Instant) {
+        if self.splash.as_ref().is_some_and(|(_, until)| now >= *until) {
+            self.splash = None;
+            self.redraw.dirty = true;
+        }
+        if !self.engine.muted() {
+            let since = now.saturating_duration_since(self.now);
+            for (_, playing) in self.playing.iter_mut().filter(|(id, _)| !self.paused_loops.contains(id)) {
+                playing.elapsed += since;
+            }
+        }
+        self.now = now;
+        let status = self.status.clone();
+        if let Some(check) = &mut self.check {
+            if check.poll(now) {
+                self.status = check.result.clone();
+                self.redraw.dirty = true;
+            }
+        }
+        let loaded: Vec<_> = self.loader.done.try_iter().collect();
+        self.redraw.dirty |= !loaded.is_empty();
+        for (source, result) in loaded {
+            // Removed from the board while it was being decoded
+            if !self.loaded.contains_key(&source) {
+                continue;
+            }
+            if let Ok(decoded) = &result {
+                self.lengths.insert(source.clone(), decoded.duration());
+            }
+            let state = match result {
+                Ok(decoded) if self.make_room(decoded.size(), self.last_played.get(&source).copied()) => State::Ready(decoded),
+                Ok(decoded) => State::Streaming(decoded.size()),
+                Err(e) => State::Failed(e),
+            };
+            self.loaded.insert(source.clone(), state);
+            let (ready, waiting) = std::mem::take(&mut self.waiting).into_iter().partition(|(s, _, _)| *s == source);
+            self.waiting = waiting;
+            for (_, via, bus) in ready {
+                if let Some(idx) = self.board.sounds.iter().position(|s| s.sources().any(|s| *s == source)) {
+                    self.play_source(idx, via, Some(source.clone()), bus.as_deref());
+                }
+            }
+        }
+        if let Some((rx, converted, failed)) = &mut self.converting {
+            loop {
+                match rx.try_recv() {
+                    Ok(Ok(true)) => *converted += 1,
+                    Ok(Ok(false)) => {}
+                    // Playing the original still works, so this is just worth knowing
+                    Ok(Err(e)) => {
+                        self.status = Some(format!("cache: {e:#}"));
+                        *failed += 1;
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        match (*converted, *failed) {
+                            (0, _) => {}
+                            (converted, 0) => self.status = Some(format!("converted {converted} sound(s) to load faster")),
+                            (converted, failed) => self.status = Some(format!("converted {converted} sound(s) to load faster, {failed} couldn't be")),
+                        }
+                        self.converting = None;
+                        break;
+                    }
+                }
+            }
+        }
+        if let Some(findings) = self.verifying.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            let outside = findings.iter().filter(|f| f.problem == Problem::Outside).count();
+            match (findings.iter().filter(|f| f.is_trouble()).count() - outside, outside) {
+                (0, 0) => {}
+                (trouble, 0) => self.status = Some(format!("{trouble} sound file(s) are missing or changed, see `soundboard verify`")),
+                (trouble, outside) => self.status = Some(format!("{trouble} sound file(s) are missing or changed and {outside} aren't in the board's folder, see `soundboard verify`")),
+            }
+            self.verifying = None;
+        }
+        if self.next_sync.is_some_and(|at| now >= at) {
+            self.sync();
+        }
+        let synced = self.syncing.as_ref().map(|(rx, _)| rx.try_recv());
+        match synced {
+            Some(Ok(result)) => {
+                if let Some((_, before)) = self.syncing.take() {
+                    self.synced(result, before);
+                    self.redraw.dirty = true;
+                }
+            }
+            Some(Err(TryRecvError::Disconnected)) => self.syncing = None,
+            _ => {}
+        }
+        if let Some(search) = &mut self.search {
+            match search.pending.as_ref().map(Receiver::try_recv) {
+                Some(Ok(Ok(search::Page { count, hits }))) => {
+                    self.status = Some(match count {
+                        _ if hits.is_empty() => format!("nothing on {} matches {:?}", search.site.name(), search.query.trim()),
+                        Some(count) if count as usize > hits.len() => format!("{count} sounds found, showing the first {}", hits.len()),
+                        _ => format!("{} sounds found", hits.len()),
+                    });
+                    (search.results, search.selected) = (hits, 0);
+                    search.pending = None;
+                    self.redraw.dirty = true;
+                    // Nothing to move through, so back to changing the query
+                    search.editing = search.results.is_empty();
+                }
+                Some(Ok(Err(e))) => {
+                    self.status = Some(format!("{e:#}"));
+                    search.pending = None;
+                    search.editing = true;
+                    self.redraw.dirty = true;
+                }
+                Some(Err(TryRecvError::Disconnected)) => search.pending = None,
+                Some(Err(TryRecvError::Empty)) | None => {}
+            }
+        }
+        if let Some((_, _, rx)) = &self.search_download {
+            match rx.try_recv() {
+                Ok(result) => {
+                    let (hit, add, _) = self.search_download.take().unwrap();
+                    self.redraw.dirty = true;
+                    match result {
+                        Ok(path) => self.fetched_hit(&hit, add, path),
+                        Err(e) => self.status = Some(format!("{e:#}")),
+                    }
+                }
+                Err(TryRecvError::Disconnected) => self.search_download = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+        let fired: Vec<usize> = self.levels.iter().flat_map(|(_, rx)| rx.try_iter()).collect();
+        for rule in fired {
+            let Some(rule) = self.board.levels.get(rule) else { continue };
+            let Some(idx) = self.board.sound_named(&rule.sound) else { continue };
+            let cooldown = rule.cooldown;
+            self.play(idx, Via::Level);
+            self.animations.start(idx, Effect::Cooldown(cooldown), now);
+        }
+        let signalled: Vec<usize> = self.signals.iter().flat_map(|w| w.fired.try_iter()).collect();
+        for rule in signalled {
+            let Some(rule) = self.board.signals.get(rule) else { continue };
+            self.perform(rule.action.clone(), Via::Signal);
+        }
+        let pressed: Vec<PadEvent> = self.pads.iter().flat_map(|p| p.events.try_iter()).collect();
+        if !pressed.is_empty() {
+            self.touched();
+        }
+        for event in pressed {
+            let matching: Vec<usize> = match event {
+                PadEvent::Button(n) => self.triggers.get(&Trigger::Pad(PadInput::Button(n))).to_vec(),
+                PadEvent::Axis { axis, from, to } => self.triggers.axis_crossed(axis, from, to).flatten().copied().collect(),
+            };
+            for idx in matching {
+                self.play(idx, Via::Pad);
+            }
+        }
+        let heard: Vec<MidiEvent> = self.midi.iter().flat_map(|m| m.events.try_iter()).collect();
+        if !heard.is_empty() {
+            self.touched();
+        }
+        for event in heard {
+            match event {
+                MidiEvent::Note(n) => {
+                    self.trigger(&Trigger::MidiNote(n), Via::Midi);
+                }
+                MidiEvent::Control { channel, cc, value } => self.moved(channel, cc, value),
+            }
+        }
+        self.send_feedback();
+        let pressed: Vec<pedal::Press> = self.pedals.iter().flat_map(|p| p.presses.try_iter()).collect();
+        if !pressed.is_empty() {
+            self.touched();
+        }
+        for press in pressed {
+            let rules: Vec<SignalAction> = self
+                .board
+                .pedals
+                .iter()
+                .filter(|r| pedal::code(&r.key) == Some(press.code))
+                .filter(|r| r.device.as_ref().map_or(press.known, |d| press.device.contains(d.as_str())))
+                .map(|r| r.action.clone())
+                .collect();
+            if rules.is_empty() {
+                // Which key a pedal sends is rarely written on it
+                self.status = Some(format!("pedal key {:?} on {} isn't bound to anything", pedal::key_name(press.code), press.device));
+            }
+            for action in rules {
+                self.perform(action, Via::Pedal);
+            }
+        }
+        let commands: Vec<SignalAction> = self.mqtt.iter().flat_map(|m| m.commands.try_iter()).collect();
+        for command in commands {
+            self.perform(command, Via::Mqtt);
+        }
+        let over: Vec<Finished> = self.engine.finished.try_iter().collect();
+        let finished: Vec<u64> = over.iter().map(|f| f.id).collect();
+        if let Some(artnet) = &mut self.artnet {
+            let result = finished.iter().try_for_each(|&id| artnet.stop(id)).and_then(|()| artnet.tick(now));
+            if let Err(e) = result {
+                self.status = Some(format!("{e:#}"));
+            }
+        }
+        self.looping.retain(|_, id| !finished.contains(id));
+        for id in finished {
+            self.redraw.dirty |= self.playing.remove(&id).is_some();
+            self.webhooks.stop(id);
+            if let Some(mqtt) = &mut self.mqtt {
+                mqtt.stop(id);
+            }
+        }
+        for finished in &over {
+            let Some(e) = &finished.error else { continue };
+            self.status = Some(format!("can't play {:?}: {e}", finished.name));
+            if self.reading() {
+                self.reader.alert(self.locale().format("reader-cant-play", &[("sound", &finished.name), ("error", e)]));
+                self.reader.status = self.status.clone();
+            }
+            if let Some(idx) = self.board.sound_named(&finished.name) {
+                self.animations.start(idx, Effect::Shake, now);
+            }
+        }
+        for finished in over {
+            if let Some((sound, via, then)) = self.hooks.finish(finished) {
+                self.then(&sound, via, then);
+            }
+        }
+        let (printed, failed) = self.shell.poll();
+        self.redraw.dirty |= printed;
+        if failed.is_some() {
+            self.status = failed;
+        }
+        let errors = self.webhooks.errors.try_iter().chain(self.mqtt.iter().flat_map(|m| m.errors.try_iter()));
+        if let Some(error) = errors.chain(self.talkover.iter().flat_map(|t| t.errors.try_iter())).last() {
+            self.status = Some(error);
+        }
+        let requests: Vec<_> = self.instance.iter().flat_map(|i| i.requests.try_iter()).collect();
+        for (line, reply) in requests {
+            if let Some(response) = rpc::handle(self, &line) {
+                let _ = reply.send(response.to_string());
+            }
+        }
+        let names: Vec<String> = self.fifo.iter().flat_map(|f| f.names.try_iter()).collect();
+        for name in names {
+            if !self.play_named(&name, Via::Fifo) {
+                self.status = Some(format!("fifo: no sound or alias named {name:?}"));
+            }
+        }
+        let heard: Vec<String> = self.voice.iter().flat_map(|v| v.heard.try_iter()).collect();
+        for heard in heard {
+            let matching: Vec<usize> = self.triggers.phrases_in(&heard).flatten().copied().collect();
+            for idx in matching {
+                self.play(idx, Via::Voice);
+            }
+        }
+
+        let transcribed: Vec<_> = self.transcribing.iter().flat_map(|rx| rx.try_iter()).collect();
+        for ((name, path), words) in transcribed {
+            match words {
+                Ok(Some(words)) => self.name_after(&name, &path, words),
+                Ok(None) => {}
+                Err(e) => self.status = Some(format!("{e:#}")),
+            }
+        }
+        self.transcribing.retain(|rx| !matches!(rx.try_recv(), Err(TryRecvError::Disconnected)));
+
+        // Not while syncing, which may be writing the config; it is merged with this once it is done
+        if self.save_at.is_some_and(|at| now >= at) && self.syncing.is_none() {
+            self.save_at = None;
+            self.save();
+        }
+        if self.plays_at.is_some_and(|at| now >= at) {
+            self.plays_at = None;
+            self.save_plays();
+        }
+        let (over, changed) = self.timers.tick(now, self.board.clock);
+        self.redraw.dirty |= changed;
+        for name in over {
+            let Some(countdown) = self.board.countdowns.iter().find(|c| c.name == name).cloned() else { continue };
+            if let Some(idx) = countdown.scene.and_then(|s| self.board.scenes.iter().position(|scene| scene.name == s)) {
+                self.recall_scene(idx);
+            }
+            self.status = Some(format!("{name} is over"));
+            if let Some(idx) = countdown.sound.and_then(|s| self.board.sound_named(&s)) {
+                self.play(idx, Via::Timer);
+            }
+        }
+        let due: Vec<Schedule> = self.timers.due(&self.board.schedules, now, vars::time_of_week(SystemTime::now())).into_iter().cloned().collect();
+        for schedule in due {
+            if let Some(idx) = schedule.scene.and_then(|s| self.board.scenes.iter().position(|scene| scene.name == s)) {
+                self.recall_scene(idx);
+            }
+            if let Some(idx) = schedule.sound.and_then(|s| self.board.sound_named(&s)) {
+                self.play_source(idx, Via::Timer, None, schedule.bus.as_deref());
+            }
+        }
+        let minute = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() / 60);
+        if minute != self.minute {
+            self.minute = minute;
+            self.redraw.dirty |= self.board.sounds.iter().any(|s| vars::changes_with_time(&s.name));
+        }
+        let idle = self.board.idle.is_some_and(|idle| now.saturating_duration_since(self.last_input) >= idle.after);
+        if idle != self.idle {
+            self.set_idle(idle);
+        }
+        self.redraw.dirty |= self.status != status;
+        if self.reading() {
+            self.announce_changes();
+        }
+    }
+
+    /// Does what `sound`, played `via`, does once it has played to its end.
+    fn then(&mut self, sound: &str, via: Via, then: Vec<Then>) {
+        let plays = self.board.sound_named(sound).map_or(0, |idx| self.board.sounds[idx].plays);
+        let vars = Vars { sound, plays, now: SystemTime::now() };
+        for then in then {
+            match then {
+                Then::Play(name) => {
+                    if !self.play_named(&name, Via::Then) {
+                        self.status = Some(format!("{sound}: no sound or alias named {name:?} to play next"));
+                    }
+                }
+                Then::Run(command) => self.shell.run(sound, &vars::expand_shell(&command, &vars)),
+                Then::Webhook(url) => self.webhooks.ended(&vars::expand_url(&url, &vars), sound, via),
+            }
+        }
+    }
+
+    /// Someone is at the board: it wakes up when it was idle, and waits all over again to dim.
+    fn touched(&mut self) {
+        self.last_input = self.now;
+        if self.idle {
+            self.set_idle(false);
+        }
+    }
+
+    /// Dims the board or wakes it up, pausing its loops meanwhile when `[idle]` says so.
+    fn set_idle(&mut self, idle: bool) {
+        self.idle = idle;
+        self.redraw.dirty = true;
+        for id in self.paused_loops.drain(..) {
+            self.engine.pause(id, false);
+        }
+        if idle && self.board.idle.is_some_and(|idle| idle.pause_loops) {
+            self.paused_loops = self.looping.values().copied().collect();
+            for &id in &self.paused_loops {
+                self.engine.pause(id, true);
+            }
+        }
+    }
+
+    /// Records what the monitor plays to `path`, with markers for each sound once it stops.
+    pub fn start_recording(&mut self, path: &Path) {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            let _ = fs::create_dir_all(dir);
+        }
+        self.status = Some(match self.engine.start_recording(path) {
+            Ok(()) => format!("recording to {}", path.display()),
+            Err(e) => format!("{e:#}"),
+        });
+    }
+
+    /// Stops recording, returning where everything was written, or what went wrong.
+    pub fn stop_recording(&mut self) -> Option<String> {
+        Some(match self.engine.stop_recording()? {
+            Ok(recorded) => {
+                let markers: Vec<_> = recorded.markers.iter().map(|m| m.display().to_string()).collect();
+                let mut message = format!("recorded {}, markers in {}", recorded.path.display(), markers.join(" and "));
+                if !recorded.stems.is_empty() {
+                    let stems: Vec<_> = recorded.stems.iter().map(|s| s.display().to_string()).collect();
+                    message.push_str(&format!(", stems in {}", stems.join(", ")));
+                }
+                message
+            }
+            Err(e) => format!("{e:#}"),
+        })
+    }
+
+    /// With the board's other sounds, named after when the recording started.
+    pub fn recording_path(&self) -> PathBuf {
+        let when = history::timestamp(SystemTime::now()).replace(':', "-");
+        self.paths.data.join(format!("{}-recording-{when}.wav", self.paths.stem()))
+    }
+
+    /// Writes out changes that are still waiting for [`App::tick`].
+    pub fn flush(&mut self) {
+        // A sync that is still going would write over what is saved now, so it goes first
+        if let Some((rx, before)) = self.syncing.take() {
+            if let Ok(result) = rx.recv() {
+                self.synced(result, before);
+            }
+        }
+        if self.save_at.take().is_some() {
+            self.save();
+        }
+        if self.plays_at.take().is_some() {
+            self.save_plays();
+        }
+    }
+
+    fn save(&mut self) {
+        // Saving would throw away what was written there, which may be most of a fix
+        if self.read_only {
+            self.status = Some("not saved, the config doesn't load; fix it with ^E".to_string());
+            return;
+        }
+        // The sync may be writing the config, which would undo this; once it is done what it
+        // brought is merged with the board, which is saved then
+        if self.syncing.is_some() {
+            self.save_at = Some(self.now);
+            return;
+        }
+        if let Err(e) = self.board.save(&self.paths.config) {
+            self.status = Some(format!("{e:#}"));
+        }
+    }
+
+    /// Saves the board once things settle down, unless it is locked: then nothing it does
+    /// changes the config on disk either.
+    fn save_later(&mut self) {
+        if !self.locked {
+            self.save_at = Some(self.now + SAVE_DELAY);
+        }
+    }
+
+    fn save_plays(&mut self) {
+        if let Err(e) = history::save_plays(&self.paths, &self.board) {
+            self.status = Some(format!("{e:#}"));
+        }
+    }
+
+    /// Persists the board and rebuilds the grid after the set of sounds changed.
+    fn board_changed(&mut self) {
+        // A copy, so only what actually changed is restarted
+        self.replace_board(self.board.clone());
+        self.save_at = None;
+        self.save();
+    }
+
+    fn change_volume(&mut self, idx: usize, delta: f32) {
+        let Some(sound) = self.board.sounds.get_mut(idx) else { return };
+        // Snap to whole steps, so 100% is always reachable again
+        let steps = ((sound.volume + delta) / VOLUME_STEP).round();
+        sound.volume = (steps * VOLUME_STEP).clamp(0.0, MAX_VOLUME);
+
+        self.volume_changed = Some((idx, self.now));
+        self.save_later();
+    }
+
+    pub fn handle_event(&mut self, event: InputEvent) {
+        self.redraw.dirty = true;
+        if !matches!(event, InputEvent::Resize) {
+            self.touched();
+        }
+        // A key or a click only gets rid of the splash
+        if self.splash.is_some() && matches!(&event, InputEvent::Press(_) | InputEvent::Mouse(MouseEvent { kind: MouseEventKind::Up(_), .. })) {
+            self.splash = None;
+            return;
+        }
+        match event {
+            InputEvent::Press(key) => self.handle_key(key),
+            InputEvent::Paste(text) => self.paste(&text),
+            InputEvent::Mouse(m) => match m.kind {
+                MouseEventKind::Up(_) => match self.hits.hit(m.column, m.row) {
+                    Some(Target::Tile(idx)) => {
+                        self.selected = idx;
+                        self.play(idx, Via::Mouse);
+                    }
+                    Some(Target::TrashItem(idx)) => self.trash_selected = idx,
+                    Some(Target::Strategy(idx)) => self.assign_selected = idx,
+                    // Clicking the selected entry again opens it
+                    Some(Target::BrowserEntry(idx)) => match &mut self.browser {
+                        Some(browser) if browser.selected == idx => self.handle_browse_key(KeyCode::Enter),
+                        Some(browser) => browser.select(idx),
+                        None => {}
+                    },
+                    Some(Target::CheckOutput(idx)) => match &mut self.check {
+                        Some(check) if check.selected == idx => self.handle_check_key(KeyCode::Enter),
+                        Some(check) => check.select(idx),
+                        None => {}
+                    },
+                    Some(Target::SearchHit(idx)) => match &mut self.search {
+                        Some(search) if search.selected == idx && !search.editing => self.handle_search_key(KeyCode::Enter),
+                        Some(search) => {
+                            search.selected = idx;
+                            search.editing = false;
+                        }
+                        None => {}
+                    },
+                    Some(Target::Command(idx)) => match &mut self.commands {
+                        Some(commands) if commands.selected == idx => self.handle_commands_key(KeyCode::Enter),
+                        Some(commands) => commands.selected = idx,
+                        None => {}
+                    },
+                    Some(Target::Backup(idx)) if self.backup_selected == idx => self.handle_backups_key(KeyCode::Enter),
+                    Some(Target::Backup(idx)) => self.backup_selected = idx,
+                    Some(Target::SettingsTab(idx)) => match &mut self.settings {
+                        Some(settings) if settings.tab != Tab::ALL[idx] => {
+                            (settings.tab, settings.selected, settings.editing) = (Tab::ALL[idx], 0, None);
+                        }
+                        _ => {}
+                    },
+                    Some(Target::Setting(idx)) => match &mut self.settings {
+                        Some(settings) if settings.selected == idx && settings.editing.is_none() => self.handle_settings_key(KeyCode::Enter),
+                        Some(settings) if settings.selected != idx => (settings.selected, settings.editing) = (idx, None),
+                        _ => {}
+                    },
+                    Some(Target::EditField(idx)) => match &mut self.edit {
+                        Some(edit) if edit.selected == idx && edit.editing.is_none() => self.handle_edit_key(KeyCode::Enter),
+                        Some(edit) if edit.selected != idx => (edit.selected, edit.editing) = (idx, None),
+                        _ => {}
+                    },
+                    Some(Target::Cue(idx)) => self.cue_standby = idx,
+                    Some(Target::Conflict(idx)) => {
+                        self.conflict_selected = idx;
+                        self.conflict_side = 0;
+                    }
+                    Some(Target::Backdrop) => {
+                        self.view = View::Board;
+                        self.status = None;
+                    }
+                    Some(Target::Inert) | None => {}
+                },
+                // Over a tile the wheel sets its volume, elsewhere it scrolls the grid
+                MouseEventKind::ScrollDown | MouseEventKind::ScrollUp if self.view == View::Board => {
+                    let up = m.kind == MouseEventKind::ScrollUp;
+                    match self.hits.hit(m.column, m.row) {
+                        Some(Target::Tile(idx)) if self.unlocked() => self.change_volume(idx, if up { VOLUME_STEP } else { -VOLUME_STEP }),
+                        Some(Target::Tile(_)) => {}
+                        _ => self.grid.scroll_by(if up { -3 } else { 3 }),
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.code == MUTE_KEY && key.modifiers.is_empty() {
+            self.engine.set_muted(!self.engine.muted());
+            self.status = None;
+            return;
+        }
+        if key.code == RECORD_KEY && key.modifiers.is_empty() {
+            if self.engine.recorder().is_some() {
+                self.status = self.stop_recording();
+            } else {
+                let path = self.recording_path();
+                self.start_recording(&path);
+            }
+            return;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('l') => {
+                    self.toggle_lock();
+                    return;
+                }
+                KeyCode::Char('p') => {
+                    self.rehearsal = !self.rehearsal;
+                    self.status = Some(match (self.rehearsal, self.board.outputs.external.is_empty()) {
+                        (true, false) => "rehearsing, only the monitor plays".to_string(),
+                        (true, true) => "rehearsing, though there are no external outputs configured".to_string(),
+                        (false, _) => "rehearsal over, playing on every output".to_string(),
+                    });
+                    return;
+                }
+                KeyCode::Char('e') => {
+                    self.edit_config = self.unlocked();
+                    return;
+                }
+                KeyCode::Char('z') => {
+                    self.suspend = true;
+                    return;
+                }
+                KeyCode::Char('r') => {
+                    if self.unlocked() {
+                        self.view = View::Assign;
+                        self.status = None;
+                    }
+                    return;
+                }
+                KeyCode::Char('t') => {
+                    let on = self.talkover.is_none();
+                    self.set_talkover(on);
+                    // When it fails to start, set_talkover already said why
+                    if !on {
+                        self.status = Some("talkover off".to_string());
+                    } else if self.talkover.is_some() {
+                        self.status = Some("talkover on, the board ducks while you speak".to_string());
+                    }
+                    return;
+                }
+                KeyCode::Char('o') => {
+                    self.view = if self.view == View::History { View::Board } else { View::History };
+                    self.status = None;
+                    return;
+                }
+                KeyCode::Char('u') => {
+                    self.view = if self.view == View::Log { View::Board } else { View::Log };
+                    self.status = None;
+                    return;
+                }
+                KeyCode::Char('w') => {
+                    self.scope = Mode::next(self.scope);
+                    self.engine.scope().watch(self.scope.is_some());
+                    self.status = None;
+                    return;
+                }
+                KeyCode::Char('v') => {
+                    match clipboard::read() {
+                        Ok(text) => self.paste(&text),
+                        Err(e) => self.status = Some(format!("{e:#}")),
+                    }
+                    return;
+                }
+                KeyCode::Char('b') => {
+                    if self.view == View::Browse {
+                        self.view = View::Board;
+                        self.status = None;
+                    } else {
+                        self.browse();
+                    }
+                    return;
+                }
+                KeyCode::Char('f') => {
+                    if self.view == View::Search {
+                        self.view = View::Board;
+                        self.status = None;
+                    } else {
+                        // Freesound has the better sounds, but needs a key
+                        let has_key = freesound::key(self.board.freesound.as_deref()).is_some();
+                        self.search.get_or_insert_with(|| Search::new(if has_key { Site::Freesound } else { Site::MyInstants }));
+                        self.view = View::Search;
+                        self.status = None;
+                    }
+                    return;
+                }
+                KeyCode::Char('d') => {
+                    if self.view == View::Check {
+                        self.view = View::Board;
+                    } else {
+                        self.check = Some(Check::open(&self.board, &self.engine, self.devices()));
+                        self.view = View::Check;
+                    }
+                    self.status = None;
+                    return;
+                }
+                KeyCode::Char('y') => {
+                    self.timers.show = self.now;
+                    self.status = Some("show timer started over".to_string());
+                    return;
+                }
+                KeyCode::Char('n') => {
+                    self.status = None;
+                    if self.view == View::Learn {
+                        self.view = View::Board;
+                    } else {
+                        self.view = View::Learn;
+                        if self.midi.is_none() {
+                            self.open_midi();
+                        }
+                    }
+                    return;
+                }
+                KeyCode::Char('g') => {
+                    self.view = if self.view == View::Cues { View::Board } else { View::Cues };
+                    self.status = None;
+                    return;
+                }
+                KeyCode::Char('k') => {
+                    self.view = View::Conflicts;
+                    self.status = None;
+                    return;
+                }
+                KeyCode::Char('s') => {
+                    if self.view == View::Settings {
+                        self.view = View::Board;
+                    } else {
+                        self.settings.get_or_insert_with(Settings::default).editing = None;
+                        self.view = View::Settings;
+                    }
+                    self.status = None;
+                    return;
+                }
+                KeyCode::Char('a') => {
+                    if self.view == View::Commands {
+                        self.view = View::Board;
+                    } else {
+                        self.commands = Some(Commands::new(self.command_entries()));
+                        self.view = View::Commands;
+                    }
+                    self.status = None;
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        match self.view {
+            View::Board => self.handle_board_key(key),
+            View::Cues => self.handle_cue_key(key),
+            _ if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {}
+            View::Trash => self.handle_trash_key(key.code),
+            View::Assign => self.handle_assign_key(key.code),
+            View::Conflicts => self.handle_conflicts_key(key.code),
+            View::History => self.handle_history_key(key.code),
+            View::Log => self.handle_log_key(key.code),
+            View::Browse => self.handle_browse_key(key.code),
+            View::Search => self.handle_search_key(key.code),
+            View::Check => self.handle_check_key(key.code),
+            View::Commands => self.handle_commands_key(key.code),
+            View::Settings => self.handle_settings_key(key.code),
+            View::Edit => self.handle_edit_key(key.code),
+            View::Backups => self.handle_backups_key(key.code),
+            View::Learn => self.handle_learn_key(key.code),
+        }
+    }
+
+    fn handle_board_key(&mut self, key: KeyEvent) {
+        let chord = KeyChord::from_event(&key);
+        if self.trigger(&Trigger::Key(chord), Via::Key) {
+            return;
+        }
+        // A scene's key recalls it, and saves over it with alt
+        let scene = |chord: KeyChord| self.board.scenes.iter().position(|s| s.key.as_ref().is_some_and(|k| k.trigger == Trigger::Key(chord)));
+        let (recall, save) = (scene(chord), scene(KeyChord { modifiers: chord.modifiers - KeyModifiers::ALT, ..chord }).filter(|_| chord.modifiers.contains(KeyModifiers::ALT)));
+        if let Some(idx) = recall {
+            self.recall_scene(idx);
+            return;
+        }
+        if let Some(idx) = save {
+            if self.unlocked() {
+                let name = self.board.scenes[idx].name.clone();
+                self.save_scene(&name);
+            }
+            return;
+        }
+        if let Some((crossfader, position)) = self.engine.crossfader() {
+            let pressed = |key: &Option<Binding>| key.as_ref().is_some_and(|k| k.trigger == Trigger::Key(chord));
+            let moved = if pressed(&crossfader.left_key) { Some(-crossfader.step) } else if pressed(&crossfader.right_key) { Some(crossfader.step) } else { None };
+            if let Some(moved) = moved {
+                self.engine.crossfade(position + moved);
+                self.redraw.dirty = true;
+                return;
+            }
+        }
+        if let Some(countdown) = self.board.countdowns.iter().find(|c| c.key.as_ref().is_some_and(|k| k.trigger == Trigger::Key(chord))) {
+            let name = countdown.name.clone();
+            let started = self.timers.toggle(countdown, self.now);
+            self.status = Some(if started { format!("{name} started") } else { format!("{name} stopped") });
+            return;
+        }
+        if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            return;
+        }
+
+        let len = self.board.sounds.len();
+        match key.code {
+            KeyCode::Esc => self.should_quit = true,
+            KeyCode::Tab => {
+                self.view = View::Trash;
+                self.status = None;
+            }
+            KeyCode::Char('E') if self.unlocked() => {
+                self.edit = Edit::new(&self.board, self.selected);
+                if self.edit.is_some() {
+                    self.view = View::Edit;
+                    self.status = None;
+                }
+            }
+            KeyCode::Char('R') if self.unlocked() => {
+                if let Some(sound) = self.board.sounds.get_mut(self.selected) {
+                    sound.shape.reversed = !sound.shape.reversed;
+                    self.status = Some(format!("{:?} plays {}", sound.name, if sound.shape.reversed { "backwards" } else { "forwards again" }));
+                    self.save_later();
+                }
+            }
+            KeyCode::Left => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Right if self.selected + 1 < len => self.select(self.selected + 1),
+            // Screen readers get a list, where up and down are the sounds before and after
+            KeyCode::Up if self.reading() => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down if self.reading() => self.select((self.selected + 1).min(len.saturating_sub(1))),
+            KeyCode::Up | KeyCode::Down => {
+                if let Some(idx) = self.grid.vertical_neighbour(self.selected, key.code == KeyCode::Down) {
+                    self.select(idx);
+                }
+            }
+            KeyCode::Enter => self.play(self.selected, Via::Key),
+            KeyCode::Delete if self.selected < len && self.unlocked() => {
+                let name = self.board.sounds[self.selected].name.clone();
+                self.board.trash(self.selected);
+                self.board_changed();
+                self.status = Some(format!("moved {name:?} to the trash (Tab to view)"));
+            }
+            _ => {}
+        }
+    }
+
+    /// Plays everything bound to `trigger`, returning whether there was anything.
+    pub fn trigger(&mut self, trigger: &Trigger, via: Via) -> bool {
+        let matching = self.triggers.get(trigger).to_vec();
+        for &idx in &matching {
+            self.play(idx, via);
+        }
+        !matching.is_empty()
+    }
+
+    /// Moves what the controller `cc` on `channel` is mapped to, to where `value` says; or, while
+    /// learning, maps it to what is selected first.
+    pub fn moved(&mut self, channel: u8, cc: u8, value: u8) {
+        if self.view == View::Learn {
+            self.learn(channel, cc);
+        }
+        let position = midi::position(value);
+        let controls: Vec<Continuous> = self.board.faders.iter().filter(|f| f.moved_by(channel, cc)).map(|f| f.control.clone()).collect();
+        for control in controls {
+            match control {
+                Continuous::Volume(bus) => {
+                    let level = self.engine.bus(&bus);
+                    level.set(position, level.muted());
+                }
+                Continuous::Wet(bus) => self.engine.bus(&bus).wet.set(position),
+                Continuous::Crossfader => self.engine.crossfade(position),
+            }
+        }
+        self.redraw.dirty = true;
+    }
+
+    /// What faders can be learned for: every bus's volume, the wet of those with effects, and the
+    /// crossfader, if there is one.
+    pub fn learn_targets(&self) -> Vec<Continuous> {
+        let mut names: Vec<String> = self.engine.buses().into_iter().map(|(name, _)| name).filter(|n| n != Bus::MASTER).collect();
+        names.insert(0, Bus::MASTER.to_string());
+        let mut targets = Vec::new();
+        for name in names {
+            let effects = self.board.buses.iter().any(|b| b.name == name && !b.effects.is_empty());
+            targets.push(Continuous::Volume(name.clone()));
+            if effects {
+                targets.push(Continuous::Wet(name));
+            }
+        }
+        if self.board.crossfader.is_some() {
+            targets.push(Continuous::Crossfader);
+        }
+        targets
+    }
+
+    /// Where `control` is, from 0 to 1.
+    pub fn control_position(&self, control: &Continuous) -> f32 {
+        match control {
+            Continuous::Volume(bus) => self.engine.bus(bus).volume.get(),
+            Continuous::Wet(bus) => self.engine.bus(bus).wet.get(),
+            Continuous::Crossfader => self.engine.crossfader().map_or(0.5, |(_, position)| position),
+        }
+    }
+
+    /// Lights up the pads of the sounds bound to MIDI notes, blinking while they play, and sends
+    /// the faders where what they move is, when `feedback` in `[midi]` is on, see [`crate::midi`].
+    fn send_feedback(&mut self) {
+        let Some(midi) = &self.midi else { return };
+        let (mut lights, mut faders) = (BTreeMap::new(), BTreeMap::new());
+        if self.board.midi_feedback {
+            for sound in &self.board.sounds {
+                let playing = self.playing.values().any(|p| sound.sources().any(|s| *s == p.source));
+                for binding in &sound.bindings {
+                    if let Trigger::MidiNote(note) = binding.trigger {
+                        lights.insert(note, (midi::pad_color(sound.color), playing));
+                    }
+                }
+            }
+            for fader in &self.board.faders {
+                let value = (self.control_position(&fader.control).clamp(0.0, 1.0) * 127.0).round() as u8;
+                faders.insert((fader.channel.unwrap_or(1), fader.cc), value);
+            }
+        }
+        let bytes = self.feedback.update(lights, faders, midi.connected());
+        if !bytes.is_empty() {
+            midi.send(&bytes);
+        }
+    }
+
+    /// What `control` is called in the list of what faders can be learned for.
+    pub fn control_label(&self, control: &Continuous) -> String {
+        let t = self.locale();
+        match control {
+            Continuous::Volume(bus) => t.format("learn-volume", &[("bus", bus)]),
+            Continuous::Wet(bus) => t.format("learn-wet", &[("bus", bus)]),
+            Continuous::Crossfader => t.text("learn-crossfader").to_string(),
+        }
+    }
+
+    /// Maps the controller `cc` on `channel` to what is selected, instead of whatever it moved
+    /// before, and goes on to the next.
+    fn learn(&mut self, channel: u8, cc: u8) {
+        let targets = self.learn_targets();
+        let Some(control) = targets.get(self.learn_selected).cloned() else { return };
+        self.board.faders.retain(|f| f.control != control && !f.moved_by(channel, cc));
+        self.board.faders.push(Fader { cc, channel: Some(channel), control });
+        self.learn_selected = (self.learn_selected + 1).min(targets.len() - 1);
+        self.status = Some(format!("cc {cc} on channel {channel} learned"));
+        self.save_later();
+    }
+
+    /// Does what the combos the sound at `idx` just completed do. A sound that a combo played
+    /// doesn't count towards another, so combos can't keep each other going.
+    fn combo(&mut self, idx: usize, via: Via) {
+        if via == Via::Combo {
+            return;
+        }
+        let name = self.board.sounds[idx].name.clone();
+        for action in self.combos.played(&name, self.now, &self.board.combos) {
+            self.perform(action, Via::Combo);
+        }
+    }
+
+    /// Does what a signal or a remote command asked for.
+    fn perform(&mut self, action: SignalAction, via: Via) {
+        self.redraw.dirty = true;
+        match action {
+            SignalAction::Play(name) => {
+                if !self.play_named(&name, via) {
+                    self.status = Some(format!("{}: no sound or alias named {name:?}", via.name()));
+                }
+            }
+            SignalAction::Stop => self.engine.stop_all(STOP_FADE),
+            SignalAction::Mute => self.engine.set_muted(!self.engine.muted()),
+            SignalAction::Go => self.go(),
+        }
+    }
+
+    /// Sets the buses to what the scene at `idx` has them at, and plays its loops and only those.
+    pub fn recall_scene(&mut self, idx: usize) {
+        let Some(scene) = self.board.scenes.get(idx).cloned() else { return };
+        for level in &scene.buses {
+            self.engine.bus(&level.bus).set(level.volume, level.muted);
+        }
+        let stopping: Vec<String> = self.looping.keys().filter(|name| !scene.loops.contains(name)).cloned().collect();
+        for name in stopping {
+            if let Some(id) = self.looping.remove(&name) {
+                self.engine.stop(id, STOP_FADE);
+            }
+        }
+        for name in &scene.loops {
+            if !self.looping.contains_key(name) {
+                self.play_named(name, Via::Scene);
+            }
+        }
+        self.status = Some(match &scene.key {
+            Some(key) => format!("scene {:?}, alt+{} saves over it", scene.name, key.label),
+            None => format!("scene {:?}", scene.name),
+        });
+        self.redraw.dirty = true;
+    }
+
+    /// Saves what the buses are at and the loops that play into the scene called `name`, which is
+    /// added when there is none.
+    pub fn save_scene(&mut self, name: &str) {
+        let buses = self.engine.buses().into_iter().map(|(bus, level)| BusLevel { bus, volume: level.volume.get(), muted: level.muted() }).collect();
+        let loops = self.board.sounds.iter().filter(|s| self.looping.contains_key(&s.name)).map(|s| s.name.clone()).collect();
+        match self.board.scenes.iter_mut().find(|s| s.name == name) {
+            Some(scene) => (scene.buses, scene.loops) = (buses, loops),
+            None => self.board.scenes.push(Scene { name: name.to_string(), key: None, buses, loops }),
+        }
+        self.status = Some(format!("saved scene {name:?}"));
+        self.save_later();
+    }
+
+    /// Plays the sound called `name`, or else every sound with `name` as an alias. Returns
+    /// whether anything played.
+    pub fn play_named(&mut self, name: &str, via: Via) -> bool {
+        match self.board.sound_named(name) {
+            Some(idx) => {
+                self.play(idx, via);
+                true
+            }
+            None => self.trigger(&Trigger::Name(name.to_string()), via),
+        }
+    }
+
+    /// Locks the board, or unlocks it once ^L is pressed twice, so a stray ^L mid-show doesn't.
+    fn toggle_lock(&mut self) {
+        if !self.locked {
+            self.locked = true;
+            self.status = Some("locked, nothing can be changed until ^L".to_string());
+        } else if self.unlocking.is_some_and(|until| self.now < until) {
+            self.locked = false;
+            self.unlocking = None;
+            self.status = Some("unlocked".to_string());
+        } else {
+            self.unlocking = Some(self.now + UNLOCK_TIME);
+            self.status = Some("^L again to unlock".to_string());
+        }
+    }
+
+    /// Whether the board may be changed, telling the user why not when it is locked.
+    fn unlocked(&mut self) -> bool {
+        if self.locked {
+            self.status = Some("locked for performance, ^L to unlock".to_string());
+        }
+        !self.locked
+    }
+
+    fn select(&mut self, idx: usize) {
+        self.selected = idx;
+        self.grid.scroll_to(idx);
+    }
+
+    /// Opens the file picker where it was left, or else next to the config.
+    fn browse(&mut self) {
+        let dir = match &self.browser {
+            Some(browser) => browser.dir.clone(),
+            None => self.paths.config.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf(),
+        };
+        // The directory may be gone by now, or never have been readable
+        match Browser::open(&dir).or_else(|_| Browser::open(Path::new("."))) {
+            Ok(mut browser) => {
+                if let Some(old) = self.browser.as_ref().filter(|b| b.dir == browser.dir) {
+                    browser.select(old.selected);
+                }
+                browser.mark(&self.board);
+                self.browser = Some(browser);
+                self.view = View::Browse;
+                self.status = None;
+            }
+            Err(e) => self.status = Some(format!("{e:#}")),
+        }
+    }
+
+    fn handle_browse_key(&mut self, code: KeyCode) {
+        let Some(browser) = &mut self.browser else { return };
+        let len = browser.entries.len();
+        match code {
+            KeyCode::Esc => {
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Up => browser.select(browser.selected.saturating_sub(1)),
+            KeyCode::Down if browser.selected + 1 < len => browser.select(browser.selected + 1),
+            KeyCode::PageUp => browser.select(browser.selected.saturating_sub(10)),
+            KeyCode::PageDown => browser.select(browser.selected + 10),
+            KeyCode::Home => browser.select(0),
+            KeyCode::End => browser.select(len.saturating_sub(1)),
+            KeyCode::Backspace | KeyCode::Left => {
+                if let Some(parent) = browser.parent() {
+                    self.show_dir(parent);
+                }
+            }
+            KeyCode::Enter | KeyCode::Right => {
+                let Some(entry) = browser.selected().cloned() else { return };
+                if entry.dir {
+                    self.show_dir(Browser::open(&entry.path));
+                } else if code == KeyCode::Enter {
+                    if let Some(name) = self.preview(&entry.path) {
+                        self.status = Some(format!("previewing {name:?}, a to add it"));
+                    }
+                }
+            }
+            KeyCode::Char('a') => {
+                let Some(entry) = browser.selected().cloned() else { return };
+                if entry.name != ".." && self.unlocked() {
+                    self.add(&entry.path);
+                    if let Some(browser) = &mut self.browser {
+                        browser.mark(&self.board);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_check_key(&mut self, code: KeyCode) {
+        let Some(check) = &mut self.check else { return };
+        match code {
+            KeyCode::Esc => {
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Up => check.select(check.selected.saturating_sub(1)),
+            KeyCode::Down => check.select(check.selected + 1),
+            KeyCode::Char('i') if !check.listening() => check.next_input(),
+            KeyCode::Enter | KeyCode::Char('t') => self.play_signal(Signal::Tone),
+            KeyCode::Char('n') => self.play_signal(Signal::Noise),
+            KeyCode::Char('v') if !check.listening() => {
+                let Some(probe) = check.selected() else { return };
+                let name = probe.name().to_string();
+                match check.listen(&name) {
+                    Ok(()) => {
+                        self.play_signal(Signal::Tone);
+                        self.status = Some(format!("listening for the tone on {}", self.check.as_ref().map_or("", |c| c.input_name())));
+                    }
+                    Err(e) => self.status = Some(format!("{e:#}")),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_commands_key(&mut self, code: KeyCode) {
+        let Some(commands) = &mut self.commands else { return };
+        let len = commands.len();
+        match code {
+            KeyCode::Esc => {
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Char(c) => commands.push(c),
+            KeyCode::Backspace => commands.pop(),
+            KeyCode::Up => commands.selected = commands.selected.saturating_sub(1),
+            KeyCode::Down if commands.selected + 1 < len => commands.selected += 1,
+            KeyCode::PageUp => commands.selected = commands.selected.saturating_sub(10),
+            KeyCode::PageDown => commands.selected = (commands.selected + 10).min(len.saturating_sub(1)),
+            KeyCode::Enter => {
+                if let Some(entry) = commands.selected() {
+                    let command = entry.command.clone();
+                    self.run_command(command);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Everything the command palette lists: the board's own actions, playing each sound, and
+    /// what has no key at all.
+    fn command_entries(&self) -> Vec<Entry> {
+        let t = self.locale();
+        let actions = ACTIONS
+            .iter()
+            // Moving and playing the selection are no use from a list, nor is the palette itself
+            .filter(|(chord, _, early)| (*early || matches!(chord.code, KeyCode::Tab | KeyCode::Esc)) && *chord != ctrl('a'))
+            .map(|(chord, name, _)| Entry {
+                label: t.text(&format!("action-{}", name.replace(' ', "-"))).to_string(),
+                key: Some(chord.to_string()),
+                tags: Vec::new(),
+                command: Command::Key(*chord),
+            });
+        let sounds = self.board.sounds.iter().enumerate().map(|(idx, sound)| Entry {
+            label: t.format("command-play", &[("sound", &sound.name)]),
+            key: sound.shortcut_label().map(|key| key.into_owned()),
+            tags: sound.tags.clone(),
+            command: Command::Play(idx),
+        });
+        let reload = Entry { label: t.text("command-reload").to_string(), key: None, tags: Vec::new(), command: Command::Reload };
+        let backups = Entry { label: t.text("command-backups").to_string(), key: None, tags: vec![t.text("command-backups-tag").to_string()], command: Command::Backups };
+        sounds.chain(actions).chain([reload, backups]).collect()
+    }
+
+    /// Does what a command palette entry says, back on the board.
+    fn run_command(&mut self, command: Command) {
+        self.view = View::Board;
+        self.status = None;
+        match command {
+            Command::Key(chord) => self.handle_key(KeyEvent::new(chord.code, chord.modifiers)),
+            Command::Play(idx) => {
+                self.select(idx);
+                self.play(idx, Via::Key);
+            }
+            Command::Reload => self.reload_config(),
+            Command::Backups => {
+                self.backups = backup::list(&self.paths.config);
+                self.backup_selected = 0;
+                self.view = View::Backups;
+            }
+        }
+    }
+
+    /// Reads the config again, for when it was changed outside the board. A config that doesn't
+    /// load leaves the board as it is.
+    fn reload_config(&mut self) {
+        if !self.unlocked() {
+            return;
+        }
+        match Board::load(&self.paths.config) {
+            Ok((board, migrated)) => {
+                self.replace_board(board);
+                self.read_only = false;
+                self.status = Some(migrated.map_or_else(|| "reloaded config".to_string(), |m| m.to_string()));
+            }
+            Err(e) => {
+                self.read_only = true;
+                self.status = Some(format!("config is invalid, keeping the board without saving it: {e:#}"));
+            }
+        }
+    }
+
+    fn handle_settings_key(&mut self, code: KeyCode) {
+        let Some(settings) = &mut self.settings else { return };
+        let setting = settings.setting();
+        if let Some(text) = &mut settings.editing {
+            match code {
+                KeyCode::Char(c) => text.push(c),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Enter => {
+                    let text = settings.editing.take().unwrap_or_default();
+                    self.change_setting(|board| setting.set(board, &text));
+                }
+                KeyCode::Esc => settings.editing = None,
+                _ => {}
+            }
+            return;
+        }
+        let len = settings.tab.settings().len();
+        match code {
+            KeyCode::Esc => {
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Tab => settings.switch(false),
+            KeyCode::BackTab => settings.switch(true),
+            KeyCode::Up => settings.selected = settings.selected.saturating_sub(1),
+            KeyCode::Down if settings.selected + 1 < len => settings.selected += 1,
+            KeyCode::Enter if setting.kind() == Kind::Text && !self.locked => settings.editing = Some(setting.text(&self.board)),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char(' ') if setting.kind() != Kind::Text => {
+                self.change_setting(|board| {
+                    setting.cycle(board, false);
+                    Ok(())
+                });
+            }
+            KeyCode::Left if setting.kind() != Kind::Text => {
+                self.change_setting(|board| {
+                    setting.cycle(board, true);
+                    Ok(())
+                });
+            }
+            // Says why it can't be edited
+            KeyCode::Enter => {
+                self.unlocked();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_edit_key(&mut self, code: KeyCode) {
+        let Some(edit) = &mut self.edit else { return };
+        let field = edit.field();
+        if let Some(text) = &mut edit.editing {
+            match code {
+                KeyCode::Char(c) => text.push(c),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Enter => {
+                    let text = edit.editing.take().unwrap_or_default();
+                    if let Err(e) = field.set(&mut edit.draft, &text) {
+                        self.status = Some(e);
+                    }
+                }
+                KeyCode::Esc => edit.editing = None,
+                _ => {}
+            }
+            return;
+        }
+        match code {
+            KeyCode::Esc => {
+                self.edit = None;
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Up => edit.selected = edit.selected.saturating_sub(1),
+            KeyCode::Down => edit.selected = (edit.selected + 1).min(Field::ALL.len() - 1),
+            KeyCode::Enter if field.kind() == Kind::Text => edit.editing = Some(field.text(&edit.draft)),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char(' ') => field.cycle(&mut edit.draft, false),
+            KeyCode::Left => field.cycle(&mut edit.draft, true),
+            KeyCode::Char('s') => self.save_edit(),
+            KeyCode::Char('t') => self.trim_to_speech(),
+            _ => {}
+        }
+    }
+
+    /// Proposes a start and end for the sound being edited around the speech in it, see
+    /// [`crate::speech`].
+    fn trim_to_speech(&mut self) {
+        let Some(edit) = &mut self.edit else { return };
+        let source = edit.draft.source.clone();
+        let decoded = match self.loaded.get(&source) {
+            Some(State::Ready(decoded)) => Ok(decoded.clone()),
+            _ => source.data().and_then(Decoded::decode),
+        };
+        self.status = Some(match decoded {
+            Ok(decoded) => match speech::bounds(&decoded) {
+                Some(SpeechBounds { start, end }) => {
+                    edit.draft.shape.start = start;
+                    edit.draft.shape.end = (end < decoded.duration()).then_some(end);
+                    format!("speech from {:.2} s to {:.2} s, s saves it", start.as_secs_f64(), end.as_secs_f64())
+                }
+                None => format!("nothing in {:?} stands out from the noise", edit.draft.name),
+            },
+            Err(e) => format!("{e:#}"),
+        });
+    }
+
+    /// Puts the edited sound on the board and writes the config, unless the config it makes
+    /// wouldn't load again.
+    fn save_edit(&mut self) {
+        let Some(edit) = &self.edit else { return };
+        let Some(sound) = self.board.sounds.get(edit.sound) else { return };
+        // Played while the modal was open, which is no edit
+        let draft = Sound { plays: sound.plays, ..edit.draft.clone() };
+        let idx = edit.sound;
+        let mut board = self.board.clone();
+        board.sounds[idx] = draft;
+        if let Err(e) = Board::parse(&toml::to_string(&board.to_table())) {
+            self.status = Some(e.message);
+            return;
+        }
+        self.replace_board(board);
+        self.save_at = None;
+        self.save();
+        self.edit = None;
+        self.view = View::Board;
+        self.status = None;
+    }
+
+    /// Applies a change from the settings screen and writes it to the config, unless the config
+    /// it makes wouldn't load again.
+    fn change_setting(&mut self, change: impl FnOnce(&mut Board) -> Result<(), String>) {
+        if !self.unlocked() {
+            return;
+        }
+        let mut board = self.board.clone();
+        if let Err(e) = change(&mut board) {
+            self.status = Some(e);
+            return;
+        }
+        if let Err(e) = Board::parse(&toml::to_string(&board.to_table())) {
+            self.status = Some(e.message);
+            return;
+        }
+        self.replace_board(board);
+        self.save_at = None;
+        self.save();
+        self.status = None;
+    }
+
+    /// Plays a test signal on the output selected in the audio check, and only there.
+    fn play_signal(&mut self, signal: Signal) {
+        let Some(probe) = self.check.as_ref().and_then(Check::selected) else { return };
+        let devices = vec![probe.output.clone()];
+        self.status = Some(format!("playing {} on {}", signal.name(), probe.name()));
+        let audio = Audio::Decoded(signal.audio());
+        self.engine.play(Playback { name: signal.name(), audio, volume: 1.0, speakers: Vec::new(), devices, group: None, bus: None, shape: Shape::default() });
+    }
+
+    fn show_dir(&mut self, browser: color_eyre::Result<Browser>) {
+        match browser {
+            Ok(mut browser) => {
+                browser.mark(&self.board);
+                self.browser = Some(browser);
+                self.status = None;
+            }
+            Err(e) => self.status = Some(format!("{e:#}")),
+        }
+    }
+
+    fn handle_search_key(&mut self, code: KeyCode) {
+        let Some(search) = &mut self.search else { return };
+        if code == KeyCode::Tab {
+            search.site = search.site.next();
+            (search.results, search.selected, search.pending) = (Vec::new(), 0, None);
+            search.editing = true;
+            self.status = None;
+            if !search.query.trim().is_empty() {
+                self.run_search();
+            }
+            return;
+        }
+        if search.editing {
+            match code {
+                KeyCode::Char(c) => search.query.push(c),
+                KeyCode::Backspace => {
+                    search.query.pop();
+                }
+                KeyCode::Enter if !search.query.trim().is_empty() => self.run_search(),
+                KeyCode::Down | KeyCode::Esc if !search.results.is_empty() => search.editing = false,
+                KeyCode::Esc => {
+                    self.view = View::Board;
+                    self.status = None;
+                }
+                _ => {}
+            }
+            return;
+        }
+        let len = search.results.len();
+        match code {
+            KeyCode::Esc => {
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Char('/') => search.editing = true,
+            KeyCode::Up if search.selected == 0 => search.editing = true,
+            KeyCode::Up => search.selected -= 1,
+            KeyCode::Down if search.selected + 1 < len => search.selected += 1,
+            KeyCode::PageUp => search.selected = search.selected.saturating_sub(10),
+            KeyCode::PageDown => search.selected = (search.selected + 10).min(len.saturating_sub(1)),
+            KeyCode::Enter => {
+                if let Some(hit) = search.selected().cloned() {
+                    self.fetch_hit(hit, false);
+                }
+            }
+            KeyCode::Char('a') => {
+                if let Some(hit) = search.selected().cloned() {
+                    if self.unlocked() {
+                        self.fetch_hit(hit, true);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn run_search(&mut self) {
+        let Some(search) = &mut self.search else { return };
+        let key = freesound::key(self.board.freesound.as_deref());
+        if search.site == Site::Freesound && key.is_none() {
+            self.status = Some(freesound::KEY_HINT.to_string());
+            return;
+        }
+        let query = search.query.trim().to_string();
+        search.pending = Some(search::run(search.site, key, &query));
+        search.editing = false;
+        self.status = Some(format!("searching {} for {query:?}", search.site.name()));
+    }
+
+    /// Previews a sound from a search, or adds it, once it is downloaded.
+    fn fetch_hit(&mut self, hit: Hit, add: bool) {
+        let cached = hit.cached(&self.paths.downloads("search"));
+        if cached.is_file() {
+            self.fetched_hit(&hit, add, cached);
+            return;
+        }
+        self.status = Some(format!("downloading {:?} from {}", hit.name, hit.site.name()));
+        let rx = fetch::download(&hit.url, cached);
+        self.search_download = Some((hit, add, rx));
+    }
+
+    fn fetched_hit(&mut self, hit: &Hit, add: bool, path: PathBuf) {
+        if !add {
+            if self.preview(&path).is_some() {
+                self.status = Some(match hit.credit() {
+                    Some(credit) => format!("previewing {:?} {credit}, a to add it", hit.name),
+                    None => format!("previewing {:?}, a to add it", hit.name),
+                });
+            }
+            return;
+        }
+        let dir = self.paths.sounds();
+        let kept = hit.file_in(&dir);
+        if let Err(e) = fs::create_dir_all(&dir).and_then(|()| fs::copy(&path, &kept)) {
+            self.status = Some(format!("copy {} to {}: {e}", path.display(), dir.display()));
+            return;
+        }
+        self.add(&fs::canonicalize(&kept).unwrap_or(kept));
+        if let (Some(status), Some(credit)) = (&mut self.status, hit.credit()) {
+            status.push_str(&format!(", {credit}"));
+        }
+    }
+
+    fn handle_trash_key(&mut self, code: KeyCode) {
+        let len = self.board.trash.len();
+        match code {
+            KeyCode::Esc | KeyCode::Tab => {
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Up => self.trash_selected = self.trash_selected.saturating_sub(1),
+            KeyCode::Down if self.trash_selected + 1 < len => self.trash_selected += 1,
+            KeyCode::Enter | KeyCode::Char('r') if self.trash_selected < len && self.unlocked() => {
+                let name = self.board.trash[self.trash_selected].name.clone();
+                self.board.restore(self.trash_selected);
+                self.board_changed();
+                self.status = Some(format!("restored {name:?}"));
+            }
+            KeyCode::Delete if self.trash_selected < len && self.unlocked() => {
+                let name = self.board.trash[self.trash_selected].name.clone();
+                match self.board.purge(self.trash_selected) {
+                    Ok(()) => {
+                        self.board_changed();
+                        self.status = Some(format!("purged {name:?}"));
+                    }
+                    Err(e) => self.status = Some(format!("{e:#}")),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_assign_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Esc | KeyCode::Tab => self.view = View::Board,
+            KeyCode::Up => self.assign_selected = self.assign_selected.saturating_sub(1),
+            KeyCode::Down if self.assign_selected + 1 < Strategy::ALL.len() => self.assign_selected += 1,
+            KeyCode::Enter => {
+                let strategy = Strategy::ALL[self.assign_selected];
+                let count = assign::assign(&mut self.board, strategy, true);
+                self.board_changed();
+                self.view = View::Board;
+                let unbound = self.board.sounds.len() - count;
+                self.status = Some(match unbound {
+                    0 => format!("reassigned keys ({})", strategy.name()),
+                    _ => format!("reassigned keys ({}), {unbound} sound(s) left without one", strategy.name()),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// The language the board is shown in.
+    pub fn locale(&self) -> Locale {
+        self.board.locale.unwrap_or(self.system_locale)
+    }
+
+    /// What the board is drawn with.
+    pub fn styles(&self) -> Styles {
+        self.board.palette.unwrap_or(self.system_palette).styles()
+    }
+
+    /// Whether the board is drawn and announced for a screen reader, see [`crate::reader`].
+    pub fn reading(&self) -> bool {
+        self.screen_reader || self.board.screen_reader
+    }
+
+    /// Announces where the user is and what the status line says, when that changed since the
+    /// last time.
+    fn announce_changes(&mut self) {
+        let t = self.locale();
+        let place = match self.view {
+            View::Board => match self.board.sounds.get(self.selected) {
+                Some(sound) => {
+                    let (position, count) = (self.selected + 1, self.board.sounds.len());
+                    match sound.shortcut_label() {
+                        Some(key) => t.format("reader-sound-key", &[("sound", &sound.name), ("key", &key), ("position", &position), ("count", &count)]),
+                        None => t.format("reader-sound", &[("sound", &sound.name), ("position", &position), ("count", &count)]),
+                    }
+                }
+                None => t.text("reader-board-empty").to_string(),
+            },
+            View::Trash => t.text("trash-title").to_string(),
+            View::Assign => t.text("assign-title").to_string(),
+            View::Conflicts => t.text("conflicts-title").to_string(),
+            View::Cues => t.text("cues-title").to_string(),
+            View::History => t.text("history-title").to_string(),
+            View::Learn => match self.learn_targets().get(self.learn_selected) {
+                Some(control) => self.control_label(control),
+                None => t.text("learn-title").to_string(),
+            },
+            View::Log => match self.shell.log.back() {
+                Some(line) => format!("{}: {}", line.tile, line.text),
+                None => t.text("log-empty").to_string(),
+            },
+            View::Check => t.text("check-title").to_string(),
+            View::Settings => match &self.settings {
+                Some(settings) => {
+                    let setting = settings.setting();
+                    t.format("reader-setting", &[("setting", &t.text(&format!("setting-{}", setting.id()))), ("value", &setting.value(&self.board).shown(t))])
+                }
+                None => String::new(),
+            },
+            View::Edit => match &self.edit {
+                Some(edit) => {
+                    let field = edit.field();
+                    t.format("reader-setting", &[("setting", &t.text(&format!("edit-{}", field.id()))), ("value", &field.value(&edit.draft).shown(t))])
+                }
+                None => String::new(),
+            },
+            View::Backups => match self.backups.get(self.backup_selected) {
+                Some(backup) => backup.label(t),
+                None => t.text("backups-none").to_string(),
+            },
+            View::Commands => match self.commands.as_ref().and_then(Commands::selected) {
+                Some(entry) => entry.label.clone(),
+                None => t.text("commands-none").to_string(),
+            },
+            View::Search => match &self.search {
+                Some(search) => t.format("search-title", &[("site", &search.site.name())]),
+                None => String::new(),
+            },
+            View::Browse => match &self.browser {
+                Some(browser) => t.format("browser-title", &[("dir", &browser.dir.display())]),
+                None => String::new(),
+            },
+        };
+        if self.reader.place.as_ref() != Some(&place) {
+            self.reader.say(place.clone());
+            self.reader.place = Some(place);
+        }
+        if self.reader.status != self.status {
+            if let Some(status) = &self.status {
+                self.reader.say(status.clone());
+            }
+            self.reader.status = self.status.clone();
+        }
+    }
+
+    /// The sound binding the conflict screen's resolution keys currently act on.
+    pub fn conflict_target(&self) -> Option<(usize, usize)> {
+        let conflict = self.conflicts.get(self.conflict_selected)?;
+        let sounds: Vec<_> = conflict.sounds().collect();
+        // Unless another was picked, the last one: it is the one that loses the trigger
+        let side = sounds.len().checked_sub(1 + self.conflict_side % sounds.len().max(1))?;
+        sounds.get(side).copied()
+    }
+
+    fn handle_conflicts_key(&mut self, code: KeyCode) {
+        let len = self.conflicts.len();
+        match code {
+            KeyCode::Esc | KeyCode::Tab => {
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Up if self.conflict_selected > 0 => {
+                self.conflict_selected -= 1;
+                self.conflict_side = 0;
+            }
+            KeyCode::Down if self.conflict_selected + 1 < len => {
+                self.conflict_selected += 1;
+                self.conflict_side = 0;
+            }
+            KeyCode::Left | KeyCode::Right => self.conflict_side += 1,
+            KeyCode::Char(c @ ('u' | 'm' | 's')) if self.unlocked() => {
+                let Some((sound, binding)) = self.conflict_target() else { return };
+                let name = self.board.sounds[sound].name.clone();
+                let resolution = match c {
+                    'u' => Resolution::Unbind,
+                    'm' => Resolution::Rebind,
+                    _ => Resolution::Swap,
+                };
+                let before = self.board.clone();
+                let moved = conflict::resolve(&mut self.board, &self.caps, sound, binding, resolution);
+                let changed = self.board != before;
+                self.board_changed();
+                if self.conflicts.is_empty() {
+                    self.view = View::Board;
+                }
+                self.status = Some(match (resolution, moved) {
+                    (Resolution::Unbind, _) => format!("unbound it from {name:?}"),
+                    (Resolution::Rebind, Some((_, chord))) => format!("moved {name:?} to {chord}"),
+                    (Resolution::Rebind, None) => format!("no free key left, unbound it from {name:?}"),
+                    (Resolution::Swap, Some((other, chord))) => format!("{name:?} keeps it, moved {:?} to {chord}", self.board.sounds[other].name),
+                    (Resolution::Swap, None) if changed => format!("no free key left, unbound it from the sound {name:?} shared it with"),
+                    (Resolution::Swap, None) => "the board needs this key, only a sound can give it up".to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Plays the cue in standby and stands by on the one after it.
+    pub fn go(&mut self) {
+        let Some(cue) = self.board.cues.get(self.cue_standby) else {
+            self.status = Some("end of the cue list".to_string());
+            return;
+        };
+        match self.board.sound_named(&cue.sound) {
+            Some(idx) => self.play(idx, Via::Cue),
+            None => self.status = Some(format!("cue {}: {:?} is in the trash, skipped", self.cue_standby + 1, cue.sound)),
+        }
+        self.cue_last = Some(self.cue_standby);
+        self.cue_standby += 1;
+    }
+
+    fn handle_cue_key(&mut self, key: KeyEvent) {
+        let len = self.board.cues.len();
+        // GO comes first, so a sound bound to space cannot take it
+        match key.code {
+            _ if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                self.trigger(&Trigger::Key(KeyChord::from_event(&key)), Via::Key);
+            }
+            KeyCode::Char(' ') | KeyCode::Enter => self.go(),
+            KeyCode::Esc | KeyCode::Tab => {
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Up => self.cue_standby = self.cue_standby.saturating_sub(1),
+            KeyCode::Down => self.cue_standby = (self.cue_standby + 1).min(len),
+            KeyCode::Home => {
+                self.cue_standby = 0;
+                self.cue_last = None;
+            }
+            // Every other key plays what it is bound to, like on the board
+            _ => {
+                self.trigger(&Trigger::Key(KeyChord::from_event(&key)), Via::Key);
+            }
+        }
+    }
+
+    fn handle_backups_key(&mut self, code: KeyCode) {
+        let len = self.backups.len();
+        match code {
+            KeyCode::Esc | KeyCode::Tab => {
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Up => self.backup_selected = self.backup_selected.saturating_sub(1),
+            KeyCode::Down if self.backup_selected + 1 < len => self.backup_selected += 1,
+            KeyCode::Enter if self.backup_selected < len && self.unlocked() => {
+                let backup = &self.backups[self.backup_selected];
+                match backup.board() {
+                    Ok(board) => {
+                        let when = backup.when();
+                        self.replace_board(board);
+                        self.view = View::Board;
+                        self.status = Some(format!("restored the config from {when}"));
+                        self.save_at = None;
+                        // The version the backup replaces is itself kept, so this can be undone
+                        self.read_only = false;
+                        self.save();
+                    }
+                    Err(e) => self.status = Some(format!("{e:#}")),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_log_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Esc | KeyCode::Tab => self.view = View::Board,
+            KeyCode::Delete => self.shell.log.clear(),
+            _ => {}
+        }
+    }
+
+    fn handle_learn_key(&mut self, code: KeyCode) {
+        let targets = self.learn_targets();
+        match code {
+            KeyCode::Esc => {
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Up => self.learn_selected = self.learn_selected.saturating_sub(1),
+            KeyCode::Down => self.learn_selected = (self.learn_selected + 1).min(targets.len().saturating_sub(1)),
+            KeyCode::Delete | KeyCode::Backspace => {
+                let Some(control) = targets.get(self.learn_selected) else { return };
+                let before = self.board.faders.len();
+                self.board.faders.retain(|f| f.control != *control);
+                if self.board.faders.len() != before {
+                    self.status = Some("forgot its fader".to_string());
+                    self.save_later();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_history_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Esc | KeyCode::Tab => {
+                self.view = View::Board;
+                self.status = None;
+            }
+            KeyCode::Char(c @ ('c' | 'j')) => {
+                self.status = Some(match self.history.export(&self.paths, c == 'j') {
+                    Ok(path) => format!("exported the history to {}", path.display()),
+                    Err(e) => format!("{e:#}"),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+    use crate::binding::{Binding, KeyChord, Trigger};
+    use crate::config::{Board, BusLevel, Idle, Order, Scene, Source};
+    use crate::harness::{self, Harness};
+    use crate::history::Via;
+    use crate::input::{Caps, Input, InputEvent, Platform};
+    use crate::toml;
+
+    fn plays(h: &Harness) -> Vec<u32> {
+        h.app.board.sounds.iter().map(|s| s.plays).collect()
+    }
+
+    /// Pressing a key plays every sound bound to it and nothing else, also when the terminal
+    /// reports shift along with a capital.
+    #[test]
+    fn keys_play_what_they_are_bound_to() {
+        // Not E, which edits the selected sound when nothing is bound to it
+        let keys: Vec<KeyCode> = ('a'..='z').chain('A'..='Z').filter(|&c| c != 'E').chain('0'..='9').map(KeyCode::Char).chain((1..=8).map(KeyCode::F)).collect();
+        harness::check(24, |rng| {
+            let mut board = harness::board(1 + rng.below(12));
+            let mut bound = Vec::new();
+            for sound in &mut board.sounds {
+                for _ in 0..rng.below(3) {
+                    let alt = if rng.chance(0.2) { KeyModifiers::ALT } else { KeyModifiers::NONE };
+                    let chord = KeyChord { code: *rng.pick(&keys), modifiers: alt };
+                    sound.bindings.push(Binding::key(chord));
+                    bound.push(chord);
+                }
+            }
+            let mut h = Harness::new(board, 80, 24).loaded();
+            for _ in 0..8 {
+                let chord = if !bound.is_empty() && rng.chance(0.7) {
+                    *rng.pick(&bound)
+                } else {
+                    KeyChord { code: *rng.pick(&keys), modifiers: KeyModifiers::NONE }
+                };
+                let expected: Vec<u32> = h
+                    .app
+                    .board
+                    .sounds
+                    .iter()
+                    .map(|s| s.plays + s.bindings.iter().filter(|b| b.trigger == Trigger::Key(chord)).count() as u32)
+                    .collect();
+                let shift = matches!(chord.code, KeyCode::Char(c) if c.is_uppercase() && rng.chance(0.5));
+                h.press_with(chord.code, if shift { chord.modifiers | KeyModifiers::SHIFT } else { chord.modifiers });
+                assert_eq!(plays(&h), expected, "after pressing {chord}");
+            }
+        });
+    }
+
+    /// Terminals that report releases report holding a key as more presses, which don't play
+    /// the sound again.
+    #[test]
+    fn held_keys_play_once() {
+        let mut h = Harness::new(Board::builtin(), 80, 24).loaded();
+        h.input = Input::new(Caps { platform: Platform::Windows, key_release: true, kitty: false, mouse: true });
+        let g = KeyCode::Char('g');
+        for kind in [KeyEventKind::Press, KeyEventKind::Press, KeyEventKind::Press, KeyEventKind::Release, KeyEventKind::Press] {
+            h.send(harness::key(g, KeyModifiers::NONE, kind));
+        }
+        assert_eq!(h.app.board.sounds[0].plays, 2);
+        let name = &h.app.board.sounds[0].name;
+        assert_eq!(h.audio.take().iter().map(|p| &p.name).collect::<Vec<_>>(), [name, name]);
+    }
+
+    /// Until a console has sent a release, every press plays: it may be one that never sends them.
+    #[test]
+    fn releases_are_trusted_once_seen() {
+        let mut h = Harness::new(Board::builtin(), 80, 24).loaded();
+        h.input = Input::new(Caps { platform: Platform::Windows, ..Caps::detect() });
+        let g = KeyCode::Char('g');
+        for kind in [KeyEventKind::Press, KeyEventKind::Press, KeyEventKind::Release, KeyEventKind::Press, KeyEventKind::Press] {
+            h.send(harness::key(g, KeyModifiers::NONE, kind));
+        }
+        assert!(h.input.caps.key_release);
+        assert_eq!(h.app.board.sounds[0].plays, 3);
+    }
+
+    /// A locked board leaves its config alone, and a single stray ^L doesn't unlock it.
+    #[test]
+    fn unlocking_takes_a_second_press() {
+        let mut h = Harness::new(harness::board(2), 80, 24).loaded();
+        h.press_with(KeyCode::Char('l'), KeyModifiers::CONTROL);
+        h.press(KeyCode::Enter);
+        h.app.tick(Instant::now() + Duration::from_secs(5));
+        assert!(!h.app.paths.config.exists());
+
+        h.press_with(KeyCode::Char('l'), KeyModifiers::CONTROL);
+        assert_eq!((h.app.locked, h.app.status.as_deref()), (true, Some("^L again to unlock")));
+        h.press_with(KeyCode::Char('l'), KeyModifiers::CONTROL);
+        assert!(!h.app.locked);
+    }
+
+    /// A config that doesn't load isn't saved over, so what was being fixed in it isn't lost.
+    #[test]
+    fn an_invalid_config_is_left_alone() {
+        let mut h = Harness::new(harness::board(2), 80, 24).loaded();
+        std::fs::write(&h.app.paths.config, "[[sound]\n").unwrap();
+        h.app.reload_config();
+        assert!(h.app.read_only);
+        h.press(KeyCode::Delete);
+        assert_eq!(std::fs::read_to_string(&h.app.paths.config).unwrap(), "[[sound]\n");
+        assert!(h.screen().contains("NOT SAVING"));
+
+        // Until it loads again
+        harness::board(1).save(&h.app.paths.config).unwrap();
+        h.app.reload_config();
+        assert!(!h.app.read_only);
+    }
+
+    /// An output that fails while the sound plays says so the way a sound that can't start does.
+    #[test]
+    fn failing_outputs_are_shown() {
+        struct Unplugged;
+        impl crate::audio::AudioBackend for Unplugged {
+            fn start(&self, sound: crate::audio::Sound) {
+                sound.fail(color_eyre::eyre::eyre!("the device was unplugged"));
+            }
+            fn format(&self, _device: Option<&str>) -> color_eyre::Result<(u16, u32)> {
+                Ok((2, 48_000))
+            }
+        }
+
+        let mut h = Harness::new(Board::builtin(), 80, 24).loaded();
+        h.app.engine = crate::audio::Engine::new(std::sync::Arc::new(Unplugged));
+        h.press(KeyCode::Char('g'));
+        let now = Instant::now();
+        h.app.tick(now);
+        assert_eq!(h.app.status.as_deref(), Some("can't play \"geen-grote-blij\": the device was unplugged"));
+        let idx = h.app.board.sound_named("geen-grote-blij").unwrap();
+        assert!(h.app.animations.shake(idx, now).is_some());
+        assert_eq!(h.app.engine.metrics.errors(), 1);
+    }
+
+    /// Playing a sound counts it in the state directory and leaves the config as it is.
+    #[test]
+    fn plays_are_counted_outside_the_config() {
+        let mut h = Harness::new(Board::builtin(), 80, 24).loaded();
+        h.app.board.save(&h.app.paths.config).unwrap();
+        let config = std::fs::read_to_string(&h.app.paths.config).unwrap();
+        h.press(KeyCode::Char('g'));
+        h.app.tick(Instant::now() + Duration::from_secs(5));
+        assert_eq!(std::fs::read_to_string(&h.app.paths.config).unwrap(), config);
+
+        // A board started again has them, and so does one read again from the config
+        let mut again = Board::load(&h.app.paths.config).unwrap().0;
+        crate::history::load_plays(&h.app.paths, &mut again);
+        assert_eq!(again.sounds[0].plays, 1);
+        h.app.replace_board(Board::load(&h.app.paths.config).unwrap().0);
+        assert_eq!(h.app.board.sounds[0].plays, 1);
+    }
+
+    /// The board dims after a while without input, and the key that wakes it still does what
+    /// it always does.
+    #[test]
+    fn idle_boards_dim_until_touched() {
+        let mut board = harness::board(3);
+        board.idle = Some(Idle { after: Duration::from_secs(60), pause_loops: true });
+        let mut h = Harness::new(board, 80, 24).loaded();
+        let start = Instant::now();
+        h.app.tick(start + Duration::from_secs(30));
+        assert!(!h.app.idle);
+        h.app.tick(start + Duration::from_secs(61));
+        assert!(h.app.idle);
+        h.terminal.draw(|frame| crate::ui::draw(frame, &mut h.app, start + Duration::from_secs(61))).unwrap();
+        assert!(h.screen().contains("IDLE"));
+
+        h.app.handle_event(InputEvent::Press(KeyEvent::new(KeyCode::Right, KeyModifiers::NONE)));
+        assert!(!h.app.idle);
+        assert_eq!(h.app.selected, 1);
+        // Waiting starts over from the key
+        h.app.tick(start + Duration::from_secs(100));
+        assert!(!h.app.idle);
+    }
+
+    /// A tile with several files plays one of them each time, never one weighing nothing.
+    #[test]
+    fn weights_pick_the_file_that_plays() {
+        let mut board = harness::board(1);
+        let builtin = |name: &str| Source::Builtin(name.to_string());
+        board.sounds[0].source = builtin("puree");
+        board.sounds[0].variants = vec![builtin("windy"), builtin("erg")];
+        board.sounds[0].weights = vec![1, 0, 3];
+        let mut h = Harness::new(board, 80, 24).loaded();
+        for _ in 0..40 {
+            h.press(KeyCode::Enter);
+        }
+        assert_eq!(h.audio.take().len(), 40);
+        assert!(h.app.last_played.contains_key(&builtin("puree")) && h.app.last_played.contains_key(&builtin("erg")));
+        assert!(!h.app.last_played.contains_key(&builtin("windy")));
+
+        let src = "version = 1\n[[sound]]\nname = \"hit\"\nfile = [\"a.wav\", \"b.wav\"]\nweights = [2, 1]\n";
+        let (board, _) = Board::parse(src).unwrap();
+        assert_eq!(board.sounds[0].sources().count(), 2);
+        let (again, _) = Board::parse(&toml::to_string(&board.to_table())).unwrap();
+        assert_eq!(again.sounds[0], board.sounds[0]);
+        assert!(Board::parse(&src.replace("[2, 1]", "[2]")).unwrap_err().to_string().contains("one weight for each file"));
+        assert!(Board::parse(&src.replace("[2, 1]", "[0, 0]")).is_err());
+    }
+
+    /// R turns the selected sound around, which sticks.
+    #[test]
+    fn r_plays_the_selected_sound_backwards() {
+        let mut h = Harness::new(harness::board(2), 80, 24).loaded();
+        h.press(KeyCode::Char('R'));
+        assert!(h.app.board.sounds[0].shape.reversed && !h.app.board.sounds[1].shape.reversed);
+        assert!(h.screen().contains('◀'));
+        assert!(toml::to_string(&h.app.board.to_table()).contains("reverse = true"));
+        let (again, _) = Board::parse(&toml::to_string(&h.app.board.to_table())).unwrap();
+        assert!(again.sounds[0].shape.reversed);
+
+        h.press(KeyCode::Char('R'));
+        assert!(!h.app.board.sounds[0].shape.reversed);
+        assert!(h.app.status.as_deref().is_some_and(|s| s.contains("forwards")));
+    }
+
+    #[test]
+    fn a_scene_sets_the_buses_and_loops() {
+        let mut board = harness::board(3);
+        board.sounds[0].shape.looped = true;
+        board.sounds[0].bus = Some("music".to_string());
+        board.sounds[1].shape.looped = true;
+        let music = BusLevel { bus: "music".to_string(), volume: 0.5, muted: false };
+        board.scenes = vec![Scene { name: "intro".to_string(), key: Binding::parse_key("F1"), buses: vec![music.clone()], loops: vec!["sound 0".to_string()] }];
+        let mut h = Harness::new(board, 80, 24).loaded();
+        // Sounds are over at once without a sound card, so this looks before the board notices
+        h.app.play(1, Via::Key);
+        h.app.recall_scene(0);
+        assert_eq!(h.app.looping.keys().collect::<Vec<_>>(), ["sound 0"]);
+        assert_eq!(h.app.history.entries.last().map(|e| e.via), Some(Via::Scene));
+        h.app.save_scene("loops");
+        assert_eq!(h.app.board.scenes[1].loops, ["sound 0"]);
+
+        h.audio.take();
+        h.press(KeyCode::F(1));
+        let played = h.audio.take();
+        assert!(!played.is_empty() && played.iter().all(|p| p.name == "sound 0" && p.volume == 0.5), "{played:?}");
+
+        // With alt it takes what the mixer is at now
+        h.app.engine.bus("sfx").set(0.25, true);
+        h.press_with(KeyCode::F(1), KeyModifiers::ALT);
+        assert!(h.app.status.as_deref().is_some_and(|s| s.contains("saved scene \"intro\"")));
+        let sfx = BusLevel { bus: "sfx".to_string(), volume: 0.25, muted: true };
+        let master = BusLevel { bus: "master".to_string(), volume: 1.0, muted: false };
+        assert_eq!(h.app.board.scenes[0].buses, [master, music, sfx]);
+        let (again, _) = Board::parse(&toml::to_string(&h.app.board.to_table())).unwrap();
+        assert_eq!(again.scenes, h.app.board.scenes);
+
+        let mut clashing = again;
+        clashing.sounds[2].bindings = vec![Binding::parse_key("F1").unwrap()];
+        let err = Board::parse(&toml::to_string(&clashing.to_table())).unwrap_err().to_string();
+        assert!(err.contains("already plays \"sound 2\""), "{err}");
+    }
+
+    #[test]
+    fn round_robin_plays_each_file_in_turn() {
+        let mut board = harness::board(1);
+        let builtin = |name: &str| Source::Builtin(name.to_string());
+        board.sounds[0].source = builtin("puree");
+        board.sounds[0].variants = vec![builtin("windy"), builtin("erg")];
+        board.sounds[0].order = Order::RoundRobin;
+        let mut h = Harness::new(board, 80, 24).loaded();
+        let mut played = Vec::new();
+        for _ in 0..4 {
+            h.press(KeyCode::Enter);
+            let last = h.app.last_played.iter().max_by_key(|(_, &at)| at).map(|(source, _)| source.clone());
+            played.push(last.unwrap());
+            h.app.now += Duration::from_millis(1);
+        }
+        assert_eq!(played, [builtin("puree"), builtin("windy"), builtin("erg"), builtin("puree")]);
+
+        let src = "version = 1\n[[sound]]\nname = \"hit\"\nfile = [\"a.wav\", \"b.wav\"]\norder = \"round-robin\"\n";
+        let (board, _) = Board::parse(src).unwrap();
+        let (again, _) = Board::parse(&toml::to_string(&board.to_table())).unwrap();
+        assert_eq!(again.sounds[0].order, Order::RoundRobin);
+        assert!(Board::parse(&format!("{src}weights = [1, 2]\n")).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Art-Net output: sets DMX channels while sounds play, so lights can flash in sync with them.
+
+use std::collections::HashMap;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+use color_eyre::eyre::{eyre, Context};
+use crate::config::{ArtNet, Dmx};
+
+const PORT: u16 = 6454;
+/// Nodes drop back to their own state when they stop hearing from a controller, so the frame
+/// is sent again every so often even when nothing changes.
+const REFRESH: Duration = Duration::from_secs(1);
+
+pub struct Output {
+    socket: UdpSocket,
+    target: SocketAddr,
+    universe: u16,
+    /// Starts at 1, 0 would turn off the node's reordering
+    sequence: u8,
+    /// The channels of the sounds playing, by the id the engine gave them
+    active: HashMap<u64, Vec<Dmx>>,
+    sent_at: Instant,
+}
+
+impl Output {
+    pub fn new(config: &ArtNet) -> color_eyre::Result<Self> {
+        let with_port = if config.target.contains(':') { config.target.clone() } else { format!("{}:{PORT}", config.target) };
+        let target = with_port
+            .to_socket_addrs()
+            .wrap_err_with(|| format!("resolve {}", config.target))?
+            .next()
+            .ok_or_else(|| eyre!("{} has no address", config.target))?;
+        let socket = UdpSocket::bind(("0.0.0.0", 0)).wrap_err("art-net socket")?;
+        // Sending to a whole network is the usual setup
+        socket.set_broadcast(true).wrap_err("art-net socket")?;
+
+        let mut output = Self { socket, target, universe: config.universe, sequence: 0, active: HashMap::new(), sent_at: Instant::now() };
+        // Without any sounds playing everything is off, which is what the node should show too
+        output.send()?;
+        Ok(output)
+    }
+
+    pub fn start(&mut self, id: u64, dmx: &[Dmx]) -> color_eyre::Result<()> {
+        if dmx.is_empty() {
+            return Ok(());
+        }
+        self.active.insert(id, dmx.to_vec());
+        self.send()
+    }
+
+    pub fn stop(&mut self, id: u64) -> color_eyre::Result<()> {
+        if self.active.remove(&id).is_none() {
+            return Ok(());
+        }
+        self.send()
+    }
+
+    pub fn tick(&mut self, now: Instant) -> color_eyre::Result<()> {
+        if now.duration_since(self.sent_at) >= REFRESH {
+            self.send()?;
+        }
+        Ok(())
+    }
+
+    /// The channels of every sound playing, the highest value winning where they overlap like
+    /// lighting desks do.
+    fn frame(&self) -> [u8; 512] {
+        let mut frame = [0; 512];
+        for dmx in self.active.values().flatten() {
+            let slot = &mut frame[dmx.channel as usize - 1];
+            *slot = (*slot).max(dmx.value);
+        }
+        frame
+    }
+
+    fn send(&mut self) -> color_eyre::Result<()> {
+        self.sequence = self.sequence.wrapping_add(1).max(1);
+        let frame = self.frame();
+
+        // An ArtDmx packet, see the Art-Net 4 specification
+        let mut packet = Vec::with_capacity(18 + frame.len());
+        packet.extend(b"Art-Net\0");
+        packet.extend(0x5000u16.to_le_bytes());
+        packet.extend(14u16.to_be_bytes());
+        packet.push(self.sequence);
+        packet.push(0); // physical port
+        packet.extend(self.universe.to_le_bytes());
+        packet.extend((frame.len() as u16).to_be_bytes());
+        packet.extend(frame);
+
+        self.sent_at = Instant::now();
+        self.socket.send_to(&packet, self.target).wrap_err_with(|| format!("send art-net to {}", self.target))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//! Picks keys for sounds automatically, so a freshly imported folder of clips is playable
+//! without binding each one by hand.
+
+use std::collections::HashSet;
+use crossterm::event::KeyCode;
+use crate::binding::{Binding, KeyChord, Trigger};
+use crate::config::{Board, TileLayout};
+
+/// Keys roughly in order of how easy they are to hit without looking: the home row first, then
+/// the rows above and below it, then the number row.
+pub const EASY_KEYS: &str = "asdfjkl;ghqweruioptyzxcvm,./bn1234567890";
+/// The numeric keypad, 3x3 digits from the top row down, then the bottom row and operators.
+pub const NUMPAD_KEYS: &str = "7894561230./*-+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// The first letter of the name that is still free, so `airhorn` gets `a`
+    FirstLetter,
+    /// Board order onto the home row and outwards from there
+    HomeRow,
+    /// The most played sounds get the easiest keys
+    Frequency,
+    /// Board order onto the numeric keypad, laid out like one on screen too
+    Numpad,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 4] = [Strategy::FirstLetter, Strategy::HomeRow, Strategy::Frequency, Strategy::Numpad];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::FirstLetter => "first-letter",
+            Strategy::HomeRow => "home-row",
+            Strategy::Frequency => "frequency",
+            Strategy::Numpad => "numpad",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|st| st.name() == s)
+    }
+}
+
+/// Gives sounds keys using `strategy`. With `all` every sound's keys are replaced, otherwise
+/// only sounds without a key get one and existing keys are left alone. MIDI notes and aliases
+/// are never touched. Returns how many sounds got a key; when the easy keys run out the rest
+/// stay unbound.
+///
+/// The numpad strategy switches the board to the numpad layout, and replacing every key with
+/// another strategy switches it back.
+pub fn assign(board: &mut Board, strategy: Strategy, all: bool) -> usize {
+    if strategy == Strategy::Numpad {
+        board.layout = TileLayout::Numpad;
+    } else if all {
+        board.layout = TileLayout::Grid;
+    }
+    let sounds = &mut board.sounds;
+
+    let is_key = |b: &Binding| matches!(b.trigger, Trigger::Key(_));
+    if all {
+        for sound in sounds.iter_mut() {
+            sound.bindings.retain(|b| !is_key(b));
+        }
+    }
+
+    let mut taken: HashSet<KeyChord> = sounds
+        .iter()
+        .flat_map(|s| &s.bindings)
+        .filter_map(|b| match b.trigger {
+            Trigger::Key(chord) => Some(chord),
+            _ => None,
+        })
+        .collect();
+
+    let mut order: Vec<usize> = (0..sounds.len()).filter(|&i| !sounds[i].bindings.iter().any(is_key)).collect();
+    if strategy == Strategy::Frequency {
+        // Stable, so equally played sounds keep their board order
+        order.sort_by_key(|&i| std::cmp::Reverse(sounds[i].plays));
+    }
+
+    let mut assigned = 0;
+    for idx in order {
+        let preferred: Vec<char> = match strategy {
+            Strategy::FirstLetter => sounds[idx].name.to_lowercase().chars().filter(|c| c.is_ascii_alphanumeric()).collect(),
+            Strategy::HomeRow | Strategy::Frequency | Strategy::Numpad => Vec::new(),
+        };
+        let keys = if strategy == Strategy::Numpad { NUMPAD_KEYS } else { EASY_KEYS };
+        let key = preferred
+            .into_iter()
+            .chain(keys.chars())
+            .map(|c| KeyChord::new(KeyCode::Char(c)))
+            .find(|chord| !taken.contains(chord));
+
+        let Some(chord) = key else { break };
+        taken.insert(chord);
+        // Keys go before the other triggers, like they are read from the config
+        sounds[idx].bindings.insert(0, Binding::key(chord));
+        assigned += 1;
+    }
+    assigned
+}
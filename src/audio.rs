@@ -0,0 +1,784 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use color_eyre::eyre::{eyre, Context};
+use rodio::cpal::traits::HostTrait;
+use rodio::source::UniformSourceIterator;
+use rodio::{Decoder, DeviceTrait, OutputStream, OutputStreamHandle, Sink, Source};
+use crate::bus::{self, Level};
+use crate::channels::{Matrix, Remix, Speaker};
+use crate::config::{Bus, Crossfader, Effect, Group};
+use crate::loader::Decoded;
+use crate::metrics::Metrics;
+use crate::record::{self, Recorded, Recorder, Tap, Tapped};
+use crate::resample::Resampler;
+use crate::scope::{Probed, Scope};
+use crate::sidechain::{Follower, Key, Keyed};
+use crate::stretch::Stretch;
+
+/// How often playing sounds pick up gain changes.
+const GAIN_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A volume factor that can be changed while sounds are playing.
+#[derive(Debug, Clone)]
+pub struct Gain(Arc<AtomicU32>);
+
+impl Gain {
+    pub fn new(gain: f32) -> Self {
+        Self(Arc::new(AtomicU32::new(gain.to_bits())))
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, gain: f32) {
+        self.0.store(gain.to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl Default for Gain {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+/// Lets the engine stop or pause a sound that is playing.
+#[derive(Debug, Default)]
+struct Control {
+    /// When the sound started fading out, and how long the fade takes
+    fade: Mutex<Option<(Instant, Duration)>>,
+    /// Paused on its own, like a loop while the board is idle
+    paused: AtomicBool,
+}
+
+impl Control {
+    fn fade_out(&self, duration: Duration) {
+        let mut fade = self.fade.lock().unwrap();
+        // A fade that is already running keeps going, restarting it would make the sound jump up
+        if fade.is_none() {
+            *fade = Some((Instant::now(), duration));
+        }
+    }
+
+    /// How loud the sound still is, `None` once it has faded out completely.
+    fn gain(&self) -> Option<f32> {
+        match *self.fade.lock().unwrap() {
+            None => Some(1.0),
+            Some((start, duration)) => {
+                let done = start.elapsed().as_secs_f32() / duration.as_secs_f32().max(f32::EPSILON);
+                (done < 1.0).then_some(1.0 - done)
+            }
+        }
+    }
+}
+
+struct Playing {
+    id: u64,
+    group: Option<String>,
+    control: Arc<Control>,
+}
+
+/// What a sound plays.
+#[derive(Debug, Clone)]
+pub enum Audio {
+    /// A file as it is on disk, decoded while it plays
+    Encoded(Cow<'static, [u8]>),
+    Decoded(Decoded),
+}
+
+/// What is done to a sound before it plays: the part of it that plays, whether it repeats,
+/// and its effects.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shape {
+    /// Where in the file the sound starts
+    pub start: Duration,
+    /// Where it ends, the end of the file when unset
+    pub end: Option<Duration>,
+    /// Plays over and over until stopped
+    pub looped: bool,
+    /// Plays from `end` back to `start`
+    pub reversed: bool,
+    pub fade_in: Duration,
+    /// How fast it plays, which changes the pitch as well
+    pub speed: f32,
+    /// How fast it plays at the same pitch, see [`crate::stretch`]
+    pub stretch: f32,
+    /// How long it plays at most, fading out at the end of that, for a long file of which only
+    /// the start is wanted
+    pub max_length: Option<Duration>,
+}
+
+/// How long a sound fades out over when it reaches its `max_length`, at most a quarter of it.
+const MAX_LENGTH_FADE: Duration = Duration::from_secs(1);
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self { start: Duration::ZERO, end: None, looped: false, reversed: false, fade_in: Duration::ZERO, speed: 1.0, stretch: 1.0, max_length: None }
+    }
+}
+
+impl Shape {
+    fn apply<S: Source<Item = f32> + Send + 'static>(self, source: S) -> Box<dyn Source<Item = f32> + Send> {
+        let mut source: Box<dyn Source<Item = f32> + Send> = Box::new(source.skip_duration(self.start));
+        if let Some(end) = self.end {
+            source = Box::new(source.take_duration(end.saturating_sub(self.start)));
+        }
+        if self.looped {
+            source = Box::new(source.buffered().repeat_infinite());
+        }
+        if self.stretch != 1.0 {
+            source = Box::new(Stretch::new(source, self.stretch));
+        }
+        if self.speed != 1.0 {
+            source = Box::new(source.speed(self.speed));
+        }
+        if !self.fade_in.is_zero() {
+            source = Box::new(source.fade_in(self.fade_in));
+        }
+        if let Some(max) = self.max_length {
+            source = Box::new(Capped::new(source, max, MAX_LENGTH_FADE.min(max / 4)));
+        }
+        source
+    }
+
+    /// How long one time through the sound takes, for a file of `length`.
+    pub fn length(self, length: Duration) -> Duration {
+        let end = self.end.map_or(length, |end| end.min(length));
+        let length = end.saturating_sub(self.start).div_f32((self.speed * self.stretch).max(f32::EPSILON));
+        self.max_length.map_or(length, |max| length.min(max))
+    }
+}
+
+/// A sound that ends after `max`, fading out over the last `fade` of it.
+struct Capped<S> {
+    inner: S,
+    max: Duration,
+    /// Samples left to play, and the number of them the fade takes
+    left: u64,
+    fade: u64,
+}
+
+impl<S: Source<Item = f32>> Capped<S> {
+    fn new(inner: S, max: Duration, fade: Duration) -> Self {
+        let samples = |d: Duration| (d.as_secs_f64() * f64::from(inner.sample_rate()) * f64::from(inner.channels())) as u64;
+        Self { left: samples(max), fade: samples(fade).max(1), inner, max }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Capped<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.left == 0 {
+            return None;
+        }
+        self.left -= 1;
+        let gain = (self.left as f32 / self.fade as f32).min(1.0);
+        self.inner.next().map(|s| s * gain)
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Capped<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        let left = usize::try_from(self.left).unwrap_or(usize::MAX);
+        Some(self.inner.current_frame_len().map_or(left, |len| len.min(left)))
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.inner.total_duration().map_or(self.max, |d| d.min(self.max)))
+    }
+}
+
+/// A device to play on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    /// `None` being the system's default output
+    pub device: Option<String>,
+    /// Sums sounds to mono and plays that on every channel, for voice chats that take only one
+    pub mono: bool,
+}
+
+/// A sound to play, and how.
+pub struct Playback<'a> {
+    pub name: &'a str,
+    pub audio: Audio,
+    pub volume: f32,
+    /// The speakers to play on, or each channel on its own when empty
+    pub speakers: Vec<Speaker>,
+    /// Devices to play on, the first being the monitor
+    pub devices: Vec<Output>,
+    /// The sound's exclusive group, and how long others in it take to fade out
+    pub group: Option<(&'a str, Duration)>,
+    /// The bus it plays on before the master bus
+    pub bus: Option<&'a str>,
+    pub shape: Shape,
+}
+
+/// Where sounds end up: [`Rodio`] plays them on the sound hardware, [`Null`] plays nothing, for
+/// tests, simulations and machines without any.
+pub trait AudioBackend: Send + Sync {
+    /// Starts playing a sound on its output, calling [`Sound::end`] once it is over there, or
+    /// [`Sound::fail`] when it can't play there.
+    fn start(&self, sound: Sound);
+    /// How many channels `device`, or the system's default output, has and at what rate, as
+    /// sounds are played on it.
+    fn format(&self, device: Option<&str>) -> color_eyre::Result<(u16, u32)>;
+}
+
+/// A sound that is over, see [`Engine::finished`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finished {
+    /// What [`Engine::play`] returned for it
+    pub id: u64,
+    pub name: String,
+    /// Whether it was stopped, or faded out by another in its group, before it got to its end
+    pub stopped: bool,
+    /// Why it couldn't play, on the first output it couldn't play on
+    pub error: Option<String>,
+}
+
+/// Plays sounds, and owns the gains that apply to everything playing.
+pub struct Engine {
+    /// Lowered while someone talks over the board
+    pub duck: Gain,
+    /// How sounds are brought to the device's sample rate
+    pub resampler: Resampler,
+    /// The board's groups, for the ones that are ducked by others
+    pub groups: Vec<Group>,
+    /// How loud the groups that duck others are, by name
+    keys: Mutex<HashMap<String, Arc<Key>>>,
+    /// The board's buses, for their effects
+    buses: Vec<Bus>,
+    /// How loud each bus plays, by name
+    levels: Mutex<HashMap<String, Arc<Level>>>,
+    /// The crossfader between two of them, and where it is, see [`Engine::crossfade`]
+    crossfader: Option<(Crossfader, f32)>,
+    /// Sounds that are playing, with their group
+    playing: Arc<Mutex<Vec<Playing>>>,
+    /// Master mute: everything is paused where it is, including sounds started meanwhile
+    paused: Arc<AtomicBool>,
+    /// Master recording of what the monitor plays
+    recorder: Option<Arc<Recorder>>,
+    /// What the monitor played last, for the scope
+    scope: Arc<Scope>,
+    next_id: AtomicU64,
+    pub metrics: Arc<Metrics>,
+    /// The sounds [`Engine::play`] started, once they stopped on every device
+    pub finished: Receiver<Finished>,
+    finished_tx: Sender<Finished>,
+    backend: Arc<dyn AudioBackend>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new(Arc::new(Rodio))
+    }
+}
+
+impl Engine {
+    pub fn new(backend: Arc<dyn AudioBackend>) -> Self {
+        let (finished_tx, finished) = mpsc::channel();
+        Self {
+            duck: Gain::default(),
+            resampler: Resampler::default(),
+            groups: Vec::new(),
+            keys: Mutex::default(),
+            buses: Vec::new(),
+            levels: Mutex::default(),
+            crossfader: None,
+            playing: Arc::default(),
+            paused: Arc::default(),
+            recorder: None,
+            scope: Arc::default(),
+            next_id: AtomicU64::new(0),
+            metrics: Arc::default(),
+            finished,
+            finished_tx,
+            backend,
+        }
+    }
+
+    /// How many channels `device` has and at what rate, see [`AudioBackend::format`].
+    pub fn format(&self, device: Option<&str>) -> color_eyre::Result<(u16, u32)> {
+        self.backend.format(device)
+    }
+
+    pub fn muted(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_muted(&self, muted: bool) {
+        self.paused.store(muted, Ordering::Relaxed);
+    }
+
+    pub fn recorder(&self) -> Option<&Recorder> {
+        self.recorder.as_deref()
+    }
+
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    pub fn start_recording(&mut self, path: &Path) -> color_eyre::Result<()> {
+        let stems: Vec<String> = self.buses.iter().filter(|b| b.stem).map(|b| b.name.clone()).collect();
+        self.recorder = Some(Arc::new(Recorder::start(path, &stems)?));
+        Ok(())
+    }
+
+    /// Stops recording, returning what was written.
+    pub fn stop_recording(&mut self) -> Option<color_eyre::Result<Recorded>> {
+        let recorder = self.recorder.take()?;
+        // Sounds still playing hold on to the recorder, but can no longer add to it
+        Some(recorder.finish())
+    }
+
+    /// Fades out everything that is playing.
+    pub fn stop_all(&self, fade: Duration) {
+        for p in self.playing.lock().unwrap().iter() {
+            p.control.fade_out(fade);
+        }
+    }
+
+    /// Fades out the sound [`Engine::play`] returned `id` for.
+    pub fn stop(&self, id: u64, fade: Duration) {
+        for p in self.playing.lock().unwrap().iter().filter(|p| p.id == id) {
+            p.control.fade_out(fade);
+        }
+    }
+
+    /// Pauses the sound [`Engine::play`] returned `id` for where it is, or plays it on from there.
+    pub fn pause(&self, id: u64, paused: bool) {
+        for p in self.playing.lock().unwrap().iter().filter(|p| p.id == id) {
+            p.control.paused.store(paused, Ordering::Relaxed);
+        }
+    }
+
+    /// Fades out everything and waits for it to finish, so nothing is cut off when exiting.
+    pub fn fade_out_all(&self, fade: Duration) {
+        self.stop_all(fade);
+        let deadline = Instant::now() + fade + 10 * GAIN_INTERVAL;
+        while !self.playing.lock().unwrap().is_empty() && Instant::now() < deadline {
+            thread::sleep(GAIN_INTERVAL);
+        }
+    }
+
+    /// Takes the board's buses, which puts their volume and mute back to what the board says.
+    pub fn set_buses(&mut self, buses: Vec<Bus>) {
+        for (name, level) in self.levels.lock().unwrap().iter() {
+            match buses.iter().find(|b| b.name == *name) {
+                Some(bus) => {
+                    level.set(bus.volume, bus.muted);
+                    level.wet.set(bus.wet);
+                }
+                None => {
+                    level.set(1.0, false);
+                    level.wet.set(1.0);
+                }
+            }
+        }
+        self.buses = buses;
+    }
+
+    /// How loud the bus `name` plays, which sounds on it pick up while they play.
+    pub fn bus(&self, name: &str) -> Arc<Level> {
+        let mut levels = self.levels.lock().unwrap();
+        levels
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                let level = Level::default();
+                if let Some(bus) = self.buses.iter().find(|b| b.name == name) {
+                    level.set(bus.volume, bus.muted);
+                    level.wet.set(bus.wet);
+                }
+                Arc::new(level)
+            })
+            .clone()
+    }
+
+    /// Takes the board's crossfader, which starts in the middle unless it is between the same
+    /// buses as before.
+    pub fn set_crossfader(&mut self, crossfader: Option<Crossfader>) {
+        let position = match (&self.crossfader, &crossfader) {
+            (Some((old, position)), Some(new)) if (&old.left, &old.right) == (&new.left, &new.right) => *position,
+            _ => 0.5,
+        };
+        if let Some((old, _)) = self.crossfader.take() {
+            self.bus(&old.left).crossfade.set(1.0);
+            self.bus(&old.right).crossfade.set(1.0);
+        }
+        self.crossfader = crossfader.map(|c| (c, position));
+        self.crossfade(position);
+    }
+
+    pub fn crossfader(&self) -> Option<(&Crossfader, f32)> {
+        self.crossfader.as_ref().map(|(c, position)| (c, *position))
+    }
+
+    /// Moves the crossfader to `position`, from 0 for only its left bus to 1 for only its right.
+    /// On the way the two add up to the same power, so a blend is as loud as either one alone.
+    pub fn crossfade(&mut self, position: f32) {
+        let Some((crossfader, at)) = &mut self.crossfader else { return };
+        *at = position.clamp(0.0, 1.0);
+        let angle = *at * std::f32::consts::FRAC_PI_2;
+        let (left, right) = (crossfader.left.clone(), crossfader.right.clone());
+        // Which isn't quite 0 at the end of its way in floating point
+        self.bus(&left).crossfade.set(angle.cos().max(0.0));
+        self.bus(&right).crossfade.set(angle.sin());
+    }
+
+    /// Every bus there is so far, the master and the board's among them, by name.
+    pub fn buses(&self) -> Vec<(String, Arc<Level>)> {
+        for name in [Bus::MASTER].into_iter().chain(self.buses.iter().map(|b| b.name.as_str())) {
+            self.bus(name);
+        }
+        let mut buses: Vec<_> = self.levels.lock().unwrap().iter().map(|(name, level)| (name.clone(), level.clone())).collect();
+        buses.sort_by(|a, b| a.0.cmp(&b.0));
+        buses
+    }
+
+    fn key(&self, group: &str) -> Arc<Key> {
+        self.keys.lock().unwrap().entry(group.to_string()).or_default().clone()
+    }
+
+    /// Starting a sound in a group fades out whatever else in that group is still playing.
+    /// Returns an id that is sent on [`Engine::finished`] once the sound is done.
+    pub fn play(&self, playback: Playback) -> u64 {
+        let control = Arc::new(Control::default());
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut playing = self.playing.lock().unwrap();
+        if let Some((name, fade)) = playback.group {
+            for other in playing.iter().filter(|p| p.group.as_deref() == Some(name)) {
+                other.control.fade_out(fade);
+            }
+        }
+        playing.push(Playing { id, group: playback.group.map(|(name, _)| name.to_string()), control: control.clone() });
+        drop(playing);
+
+        self.metrics.played(playback.name);
+        let triggered = Instant::now();
+        let remaining = Arc::new(AtomicUsize::new(playback.devices.len()));
+        let failure: Arc<Mutex<Option<String>>> = Arc::default();
+        let group = playback.group.map(|(name, _)| name);
+        // Other groups make room for this one, or it makes room for another
+        let ducks = group.is_some_and(|g| self.groups.iter().any(|o| o.sidechain.as_ref().is_some_and(|s| s.by == g)));
+        let sidechain = group.and_then(|g| self.groups.iter().find(|o| o.name == g)).and_then(|o| o.sidechain.as_ref());
+        let buses: Vec<&str> = playback.bus.into_iter().filter(|&b| b != Bus::MASTER).chain([Bus::MASTER]).collect();
+        let levels: Vec<Arc<Level>> = buses.iter().map(|name| self.bus(name)).collect();
+        let effects: Vec<(Vec<Effect>, Arc<Level>)> = buses.iter().zip(&levels).filter_map(|(&name, level)| Some((self.buses.iter().find(|b| b.name == name)?.effects.clone(), level.clone()))).collect();
+        for (i, output) in playback.devices.into_iter().enumerate() {
+            let playing = self.playing.clone();
+            let remaining = remaining.clone();
+            let (finished, failure, name) = (self.finished_tx.clone(), failure.clone(), playback.name.to_string());
+            // Only the monitor is recorded and scoped, the other outputs play the same thing
+            let recorder = self.recorder.clone().filter(|_| i == 0);
+            let heard = Gain::default();
+            let tap = recorder.as_ref().map(|r| r.tap(id, playback.name, buses.first().copied().filter(|&b| b != Bus::MASTER), heard.clone()));
+            let ended = control.clone();
+            self.backend.start(Sound {
+                id,
+                name: playback.name.to_string(),
+                audio: playback.audio.clone(),
+                output,
+                volume: playback.volume,
+                speakers: playback.speakers.clone(),
+                shape: playback.shape,
+                resampler: self.resampler,
+                duck: self.duck.clone(),
+                paused: self.paused.clone(),
+                control: control.clone(),
+                tap,
+                scope: Some(self.scope.clone()).filter(|_| i == 0),
+                key: group.filter(|_| ducks && i == 0).map(|g| self.key(g)),
+                ducked: sidechain.map(|s| Follower::new(self.key(&s.by), s.duck, s.release)),
+                buses: levels.clone(),
+                effects: effects.clone(),
+                heard,
+                metrics: self.metrics.clone(),
+                triggered,
+                on_end: Box::new(move |error| {
+                    if let Some(recorder) = recorder {
+                        recorder.ended(id);
+                    }
+                    if let Some(error) = error {
+                        failure.lock().unwrap().get_or_insert(error);
+                    }
+                    // The last device to finish takes the sound out of what is playing
+                    if remaining.fetch_sub(1, Ordering::Relaxed) == 1 {
+                        playing.lock().unwrap().retain(|p| !Arc::ptr_eq(&p.control, &ended));
+                        let stopped = ended.fade.lock().unwrap().is_some();
+                        let error = failure.lock().unwrap().take();
+                        let _ = finished.send(Finished { id, name, stopped, error });
+                    }
+                }),
+            });
+        }
+        id
+    }
+}
+
+/// A sound to play on one output, and everything that decides how loud it is while it plays.
+pub struct Sound {
+    id: u64,
+    name: String,
+    audio: Audio,
+    output: Output,
+    volume: f32,
+    speakers: Vec<Speaker>,
+    shape: Shape,
+    resampler: Resampler,
+    duck: Gain,
+    paused: Arc<AtomicBool>,
+    control: Arc<Control>,
+    /// Where to send the sound when recording
+    tap: Option<Tap>,
+    scope: Option<Arc<Scope>>,
+    /// Where to say how loud the sound is, for the groups it ducks
+    key: Option<Arc<Key>>,
+    ducked: Option<Follower>,
+    /// The buses it plays on, and what they do to it
+    buses: Vec<Arc<Level>>,
+    effects: Vec<(Vec<Effect>, Arc<Level>)>,
+    /// How loud the recording and the scope hear the sound
+    heard: Gain,
+    metrics: Arc<Metrics>,
+    triggered: Instant,
+    /// Called with why it couldn't play, if it couldn't
+    on_end: Box<dyn FnOnce(Option<String>) + Send>,
+}
+
+impl Sound {
+    /// Lets the engine know the sound is over on its output.
+    pub fn end(self) {
+        (self.on_end)(None);
+    }
+
+    /// Lets the engine know the sound couldn't play on its output, and why.
+    pub fn fail(self, e: color_eyre::Report) {
+        self.metrics.failed();
+        (self.on_end)(Some(format!("{e:#}")));
+    }
+
+    /// How loud its buses play it.
+    fn bus_gain(&self) -> f32 {
+        self.buses.iter().map(|b| b.gain()).product()
+    }
+
+    /// Whether the sound should be paused, with everything or on its own.
+    fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed) || self.control.paused.load(Ordering::Relaxed)
+    }
+}
+
+/// The sound hardware, through rodio. A sound plays on a thread of its own on every output.
+pub struct Rodio;
+
+impl AudioBackend for Rodio {
+    fn start(&self, sound: Sound) {
+        thread::spawn(move || {
+            let mut sound = sound;
+            match play_sound(&mut sound) {
+                Ok(()) => sound.end(),
+                Err(e) => sound.fail(e),
+            }
+        });
+    }
+
+    fn format(&self, device: Option<&str>) -> color_eyre::Result<(u16, u32)> {
+        let (device, name) = find(device)?;
+        let config = device.default_output_config().wrap_err_with(|| format!("configure {name}"))?;
+        Ok((config.channels(), config.sample_rate().0))
+    }
+}
+
+/// What [`Null`] was asked to play, on one output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Played {
+    /// The id [`Engine::play`] returned
+    pub id: u64,
+    pub name: String,
+    /// With its buses' volumes
+    pub volume: f32,
+    pub output: Output,
+}
+
+/// Plays nothing: sounds are over as soon as they start, and what was asked to play is kept
+/// until [`Null::take`] is called.
+#[derive(Debug, Default)]
+pub struct Null {
+    played: Mutex<Vec<Played>>,
+}
+
+impl Null {
+    /// What was asked to play since this was last called, in order.
+    pub fn take(&self) -> Vec<Played> {
+        std::mem::take(&mut *self.played.lock().unwrap())
+    }
+}
+
+impl AudioBackend for Null {
+    fn start(&self, sound: Sound) {
+        let played = Played { id: sound.id, name: sound.name.clone(), volume: sound.volume * sound.bus_gain(), output: sound.output.clone() };
+        self.played.lock().unwrap().push(played);
+        sound.end();
+    }
+
+    /// Stereo at the rate recordings are made at, which is as likely as any.
+    fn format(&self, _device: Option<&str>) -> color_eyre::Result<(u16, u32)> {
+        Ok((2, record::RATE))
+    }
+}
+
+/// Finds `device`, or the system's default output, with how to name it in errors.
+fn find(device: Option<&str>) -> color_eyre::Result<(rodio::cpal::Device, String)> {
+    let host = rodio::cpal::default_host();
+    match device {
+        None => Ok((host.default_output_device().ok_or_else(|| eyre!("no default output device"))?, "the default output".to_string())),
+        Some(name) => {
+            let device = host
+                .output_devices()
+                .wrap_err("list output devices")?
+                .find(|d| d.name().is_ok_and(|n| n == name))
+                .ok_or_else(|| eyre!("no output device named {name:?}"))?;
+            Ok((device, format!("{name:?}")))
+        }
+    }
+}
+
+/// Opens a stream to `device`, or to the system's default output, and says how many channels
+/// it has and at what rate.
+fn open(device: Option<&str>) -> color_eyre::Result<(OutputStream, OutputStreamHandle, u16, u32)> {
+    let (device, name) = find(device)?;
+    // What rodio opens the stream with
+    let config = device.default_output_config().wrap_err_with(|| format!("configure {name}"))?;
+    let (stream, handle) = OutputStream::try_from_device_config(&device, config.clone()).wrap_err_with(|| format!("stream to {name}"))?;
+    Ok((stream, handle, config.channels(), config.sample_rate().0))
+}
+
+fn play_sound(sound: &mut Sound) -> color_eyre::Result<()> {
+    let output = &sound.output;
+    let opened = open(output.device.as_deref());
+    sound.metrics.output(output.device.as_deref(), opened.is_ok());
+    let (_stream, stream_handle, channels, rate) = opened?;
+    let source = match sound.audio.clone() {
+        // Backwards needs all of it, so a file that streams is decoded first
+        audio if sound.shape.reversed => {
+            let decoded = match audio {
+                Audio::Encoded(data) => Decoded::decode(data)?,
+                Audio::Decoded(decoded) => decoded,
+            };
+            let (start, end) = (sound.shape.start, sound.shape.end);
+            Shape { start: Duration::ZERO, end: None, ..sound.shape }.apply(decoded.reversed(start, end))
+        }
+        Audio::Encoded(data) => sound.shape.apply(Decoder::new(Cursor::new(data)).wrap_err("decoder")?.convert_samples::<f32>()),
+        Audio::Decoded(decoded) => sound.shape.apply(decoded.source()),
+    };
+    let source = bus::apply(&sound.effects, sound.resampler.apply(source, rate));
+    let matrix = if output.mono { Matrix::mono(source.channels(), channels) } else { Matrix::new(source.channels(), &sound.speakers, channels) };
+    let source = Remix::new(source, matrix);
+    let source: Box<dyn Source<Item = f32> + Send> = match sound.scope.take() {
+        Some(scope) => Box::new(Probed::new(source, scope.probe(rate, sound.heard.clone()))),
+        None => Box::new(source),
+    };
+    let source: Box<dyn Source<Item = f32> + Send> = match sound.key.take() {
+        Some(key) => Box::new(Keyed::new(source, key, sound.heard.clone())),
+        None => source,
+    };
+
+    let sink = Sink::try_new(&stream_handle).wrap_err("get sink")?;
+    let sidechain = |sound: &mut Sound| sound.ducked.as_mut().map_or(1.0, |f| f.gain(Instant::now()));
+    let volume = sound.volume * sound.bus_gain() * sound.duck.get() * sidechain(sound);
+    sink.set_volume(volume);
+    if sound.paused() {
+        sink.pause();
+    }
+    sound.heard.set(volume);
+    match sound.tap.take() {
+        Some(tap) => {
+            let uniform = UniformSourceIterator::<_, f32>::new(source, channels, record::RATE);
+            sink.append(Tapped::new(uniform, tap));
+        }
+        None => sink.append(source),
+    }
+
+    sound.metrics.sink_started(sound.triggered.elapsed());
+    while !sink.empty() {
+        thread::sleep(GAIN_INTERVAL);
+        match (sound.paused(), sink.is_paused()) {
+            (true, false) => sink.pause(),
+            (false, true) => sink.play(),
+            _ => {}
+        }
+        let Some(gain) = sound.control.gain() else {
+            sink.stop();
+            break;
+        };
+        let volume = sound.volume * sound.bus_gain() * sound.duck.get() * sidechain(sound) * gain;
+        sink.set_volume(volume);
+        sound.heard.set(volume);
+    }
+    sound.metrics.sink_stopped();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use rodio::buffer::SamplesBuffer;
+    use crate::config::Board;
+    use crate::loader::Decoded;
+    use crate::toml;
+    use super::{Audio, Engine, Finished, Null, Output, Playback, Shape};
+
+    /// A sound on several outputs plays on each, and finishes once they all have.
+    #[test]
+    fn null_plays_on_every_output() {
+        let null = Arc::new(Null::default());
+        let engine = Engine::new(null.clone());
+        let devices = vec![Output { device: None, mono: false }, Output { device: Some("cable".to_string()), mono: true }];
+        let audio = Audio::Decoded(Decoded { channels: 1, rate: 48_000, samples: vec![0.0; 480].into() });
+        let id = engine.play(Playback { name: "beep", audio, volume: 0.5, speakers: Vec::new(), devices: devices.clone(), group: None, bus: None, shape: Shape::default() });
+
+        let played = null.take();
+        assert_eq!(played.iter().map(|p| (p.id, p.name.as_str(), p.volume)).collect::<Vec<_>>(), [(id, "beep", 0.5), (id, "beep", 0.5)]);
+        assert_eq!(played.into_iter().map(|p| p.output).collect::<Vec<_>>(), devices);
+        assert_eq!(engine.finished.try_recv(), Ok(Finished { id, name: "beep".to_string(), stopped: false, error: None }));
+        assert!(engine.finished.recv_timeout(Duration::from_millis(50)).is_err());
+        assert!(null.take().is_empty());
+    }
+
+    #[test]
+    fn a_long_sound_stops_at_its_max_length() {
+        let shape = Shape { max_length: Some(Duration::from_secs(2)), ..Shape::default() };
+        let capped: Vec<f32> = shape.apply(SamplesBuffer::new(1, 1000, vec![1.0; 5000])).collect();
+        assert_eq!(capped.len(), 2000);
+        // Fading out over its last half second
+        assert_eq!((capped[0], capped[1499]), (1.0, 1.0));
+        assert!((capped[1750] - 0.5).abs() < 0.01 && capped[1999] < 0.01, "{} {}", capped[1750], capped[1999]);
+        assert_eq!(shape.length(Duration::from_secs(5)), Duration::from_secs(2));
+        assert_eq!(shape.length(Duration::from_secs(1)), Duration::from_secs(1));
+
+        let src = "version = 1\n[[sound]]\nname = \"song\"\nbuiltin = \"puree\"\nmax-length = 10\n";
+        let (board, _) = Board::parse(src).unwrap();
+        assert_eq!(board.sounds[0].shape.max_length, Some(Duration::from_secs(10)));
+        let (again, _) = Board::parse(&toml::to_string(&board.to_table())).unwrap();
+        assert_eq!(again.sounds, board.sounds);
+        assert!(Board::parse(&src.replace("= 10", "= 0")).unwrap_err().to_string().contains("more than 0 seconds"));
+    }
+}
@@ -0,0 +1,188 @@
+//! Writing the config so a crash or a full disk halfway through can't leave it broken: the new
+//! version is written next to it and then takes its place in one go. The versions it replaces
+//! are kept, the last [`KEEP`] of them, to go back to from the board.
+
+use std::fs::{self, File};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use color_eyre::eyre::Context;
+use crate::config::Board;
+use crate::history;
+use crate::locale::Locale;
+
+/// How many old versions of the config are kept.
+pub const KEEP: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// Counts up with every backup, so the newest has the highest
+    pub number: u64,
+    pub modified: Option<SystemTime>,
+    /// How many sounds are on its board, or `None` when it isn't a config that loads
+    pub sounds: Option<usize>,
+}
+
+impl Backup {
+    /// When it was made, or which one it is when that isn't known.
+    pub fn when(&self) -> String {
+        self.modified.map_or_else(|| format!("#{}", self.number), history::timestamp)
+    }
+
+    /// When it was made and what is on it, for the list of backups.
+    pub fn label(&self, t: Locale) -> String {
+        match self.sounds {
+            Some(count) => t.format("backup", &[("time", &self.when()), ("count", &count)]),
+            None => t.format("backup-broken", &[("time", &self.when())]),
+        }
+    }
+
+    pub fn board(&self) -> color_eyre::Result<Board> {
+        let src = fs::read_to_string(&self.path).wrap_err_with(|| format!("read {}", self.path.display()))?;
+        let (board, _) = Board::parse(&src).map_err(|e| e.in_file(&self.path))?;
+        Ok(board)
+    }
+}
+
+/// Where the old versions of the config at `config` are kept.
+pub fn folder(config: &Path) -> PathBuf {
+    let name = config.file_name().map_or("soundboard.toml".into(), |n| n.to_string_lossy());
+    config.with_file_name(format!("{name}.backups"))
+}
+
+/// Writes `contents` to the config at `config`, first keeping what was there as a backup when
+/// it is different.
+pub fn write(config: &Path, contents: &str) -> color_eyre::Result<()> {
+    match fs::read_to_string(config) {
+        Ok(old) if old != contents => keep(config, &old)?,
+        _ => {}
+    }
+    replace(config, contents)
+}
+
+/// Writes `contents` to `path` without ever leaving it half written: it goes to a file next to
+/// it first, which is renamed over it once it is safely on disk. A symlink stays one, the file
+/// it points to is the one replaced, and that keeps its permissions.
+pub fn replace(path: &Path, contents: &str) -> color_eyre::Result<()> {
+    // Like a config kept with the rest of someone's dotfiles
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let name = target.file_name().map_or("soundboard".into(), |n| n.to_string_lossy());
+    let partial = target.with_file_name(format!(".{name}.part"));
+    let write = || -> std::io::Result<()> {
+        // The platform's config directory isn't there before the first save
+        if let Some(dir) = target.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = File::create(&partial)?;
+        if let Ok(old) = fs::metadata(&target) {
+            file.set_permissions(old.permissions())?;
+        }
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    };
+    if let Err(e) = write() {
+        let _ = fs::remove_file(&partial);
+        return Err(e).wrap_err_with(|| format!("write {}", path.display()));
+    }
+    fs::rename(&partial, &target).wrap_err_with(|| format!("write {}", path.display()))
+}
+
+/// The backups of the config at `config`, newest first.
+pub fn list(config: &Path) -> Vec<Backup> {
+    let Ok(entries) = fs::read_dir(folder(config)) else { return Vec::new() };
+    let mut backups: Vec<Backup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let number = path.file_stem()?.to_str()?.parse().ok()?;
+            let modified = entry.metadata().and_then(|m| m.modified()).ok();
+            let sounds = fs::read_to_string(&path).ok().and_then(|src| Board::parse(&src).ok()).map(|(board, _)| board.sounds.len());
+            Some(Backup { path, number, modified, sounds })
+        })
+        .collect();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.number));
+    backups
+}
+
+/// Keeps `old` as the newest backup, dropping the oldest beyond [`KEEP`].
+fn keep(config: &Path, old: &str) -> color_eyre::Result<()> {
+    let folder = folder(config);
+    fs::create_dir_all(&folder).wrap_err_with(|| format!("create {}", folder.display()))?;
+    let backups = list(config);
+    let number = backups.first().map_or(1, |b| b.number + 1);
+    replace(&folder.join(format!("{number}.toml")), old)?;
+    for stale in backups.iter().skip(KEEP - 1) {
+        let _ = fs::remove_file(&stale.path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use crate::app::View;
+    use crate::harness::{self, Harness};
+    use super::{folder, list, replace, write, KEEP};
+
+    #[test]
+    fn old_versions_are_kept() {
+        let dir = harness::scratch("backup");
+        let config = dir.join("soundboard.toml");
+        write(&config, "version = 1\n").unwrap();
+        assert!(list(&config).is_empty());
+        // The same again isn't a new version
+        write(&config, "version = 1\n").unwrap();
+        assert!(list(&config).is_empty());
+
+        for n in 0..KEEP + 3 {
+            write(&config, &format!("# {n}\n")).unwrap();
+        }
+        let backups = list(&config);
+        assert_eq!(backups.len(), KEEP);
+        assert_eq!(fs::read_to_string(&backups[0].path).unwrap(), format!("# {}\n", KEEP + 1));
+        assert_eq!(fs::read_to_string(&config).unwrap(), format!("# {}\n", KEEP + 2));
+        // Nothing is left over from writing
+        assert!(!dir.join(".soundboard.toml.part").exists());
+        assert_eq!(fs::read_dir(folder(&config)).unwrap().count(), KEEP);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_and_permissions_are_kept() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = harness::scratch("replace");
+        let (target, link) = (dir.join("dotfiles.toml"), dir.join("soundboard.toml"));
+        fs::write(&target, "version = 1\n").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        replace(&link, "version = 2\n").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "version = 2\n");
+        assert_eq!(fs::metadata(&target).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn a_backup_is_restored_from_the_board() {
+        let mut h = Harness::new(harness::board(2), 80, 24).loaded();
+        h.app.board.save(&h.app.paths.config).unwrap();
+        h.press(KeyCode::Delete);
+        assert_eq!(h.app.board.sounds.len(), 1);
+
+        h.press_with(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        for c in "earlier".chars() {
+            h.press(KeyCode::Char(c));
+        }
+        h.press(KeyCode::Enter);
+        assert_eq!(h.app.view, View::Backups);
+        assert!(h.screen().contains("2 sound(s)"));
+        h.press(KeyCode::Enter);
+        assert_eq!(h.app.view, View::Board);
+        assert_eq!(h.app.board.sounds.len(), 2);
+        // And the board it replaced is a backup now
+        assert_eq!(list(&h.app.paths.config)[0].sounds, Some(1));
+    }
+}
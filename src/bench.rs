@@ -0,0 +1,173 @@
+//! `soundboard bench`: how long triggering, decoding and laying out take on this machine, for
+//! comparing before and after a change that is meant to make things faster.
+
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+use ratatui::layout::Rect;
+use rodio::{Decoder, Source as _};
+use crate::audio::{Audio, Engine, Output, Playback, Shape};
+use crate::config::{Board, Sound};
+use crate::loader::Decoded;
+use crate::ui::Grid;
+
+/// How often the warm measurements are repeated, their median is reported.
+const RUNS: usize = 10;
+/// Terminal sizes the tiles are laid out in.
+const SIZES: [(u16, u16); 2] = [(120, 40), (240, 60)];
+
+pub fn run(board: &Board, tiles: usize) {
+    println!("trigger latency, until the first sample is ready");
+    println!("  {:<24} {:>10} {:>10} {:>10}", "sound", "cold", "warm", "in memory");
+    for sound in &board.sounds {
+        match trigger(sound) {
+            Ok((cold, warm, memory)) => println!("  {:<24} {:>10} {:>10} {:>10}", clip(&sound.name, 24), ms(cold), ms(warm), ms(memory)),
+            Err(e) => println!("  {:<24} {e:#}", clip(&sound.name, 24)),
+        }
+    }
+    if board.sounds.is_empty() {
+        println!("  the board has no sounds");
+    }
+
+    let monitor = board.outputs.monitor.as_deref().unwrap_or("the default output");
+    let engine = Engine::default();
+    match engine.format(board.outputs.monitor.as_deref()).map(|_| output(&engine, board)) {
+        Ok(Some((cold, warm))) => println!("\noutput: {} cold, {} warm from triggering to queued on {monitor}", ms(cold), ms(warm)),
+        Ok(None) => println!("\noutput: couldn't play on {monitor}"),
+        Err(e) => println!("\noutput: {e:#}"),
+    }
+
+    println!("\ndecode throughput");
+    println!("  {:<8} {:>6} {:>10} {:>10} {:>12} {:>10}", "format", "files", "audio", "took", "speed", "read");
+    for (format, totals) in decode(board) {
+        let speed = totals.audio.as_secs_f64() / totals.took.as_secs_f64().max(f64::EPSILON);
+        let read = totals.bytes as f64 / 1e6 / totals.took.as_secs_f64().max(f64::EPSILON);
+        println!(
+            "  {format:<8} {:>6} {:>9.1}s {:>10} {:>11.0}x {:>6.1} MB/s",
+            totals.files,
+            totals.audio.as_secs_f64(),
+            ms(totals.took),
+            speed,
+            read
+        );
+    }
+
+    println!("\nlayout of {tiles} tiles");
+    let (build, layouts) = layout(board, tiles);
+    print!("  build {}", ms(build));
+    for ((width, height), took) in SIZES.iter().zip(layouts) {
+        print!(", {width}x{height} {}", ms(took));
+    }
+    println!();
+}
+
+fn ms(d: Duration) -> String {
+    format!("{:.3} ms", d.as_secs_f64() * 1000.0)
+}
+
+fn clip(name: &str, width: usize) -> String {
+    name.chars().take(width).collect()
+}
+
+fn median(mut runs: Vec<Duration>) -> Duration {
+    runs.sort();
+    runs[runs.len() / 2]
+}
+
+/// Reading and starting to decode the sound the first time and again, and starting it from
+/// memory the way preloaded sounds do.
+fn trigger(sound: &Sound) -> color_eyre::Result<(Duration, Duration, Duration)> {
+    let stream = || -> color_eyre::Result<Duration> {
+        let start = Instant::now();
+        let mut decoder = Decoder::new(Cursor::new(sound.data()?))?.convert_samples::<f32>();
+        decoder.next();
+        Ok(start.elapsed())
+    };
+    let cold = stream()?;
+    let warm = median((0..RUNS).map(|_| stream()).collect::<color_eyre::Result<_>>()?);
+
+    let decoded = Decoded::decode(sound.data()?)?;
+    let memory = median(
+        (0..RUNS)
+            .map(|_| {
+                let start = Instant::now();
+                decoded.source().next();
+                start.elapsed()
+            })
+            .collect(),
+    );
+    Ok((cold, warm, memory))
+}
+
+/// Playing a moment of silence on the monitor, `None` when that fails.
+fn output(engine: &Engine, board: &Board) -> Option<(Duration, Duration)> {
+    let silence = Decoded { channels: 1, rate: 48_000, samples: vec![0.0; 2400].into() };
+    let play = || {
+        let output = Output { device: board.outputs.monitor.clone(), mono: false };
+        let audio = Audio::Decoded(silence.clone());
+        engine.play(Playback { name: "bench", audio, volume: 0.0, speakers: Vec::new(), devices: vec![output], group: None, bus: None, shape: Shape::default() });
+        engine.finished.recv_timeout(Duration::from_secs(5)).ok()?;
+        (engine.metrics.errors() == 0).then(|| engine.metrics.latency())
+    };
+    let cold = play()?;
+    let warm = median((0..RUNS).map(|_| play()).collect::<Option<_>>()?);
+    Some((cold, warm))
+}
+
+#[derive(Default)]
+struct Totals {
+    files: usize,
+    bytes: usize,
+    audio: Duration,
+    took: Duration,
+}
+
+/// Decoding every sound on the board completely, by format.
+fn decode(board: &Board) -> BTreeMap<&'static str, Totals> {
+    let mut formats: BTreeMap<_, Totals> = BTreeMap::new();
+    for sound in &board.sounds {
+        let Ok(data) = sound.data() else { continue };
+        let (format, bytes) = (format(&data), data.len());
+        let start = Instant::now();
+        let Ok(decoded) = Decoded::decode(data) else { continue };
+        let took = start.elapsed();
+        let totals = formats.entry(format).or_default();
+        totals.files += 1;
+        totals.bytes += bytes;
+        totals.audio += decoded.source().total_duration().unwrap_or_default();
+        totals.took += took;
+    }
+    formats
+}
+
+/// What a file is, by what it starts with rather than its name, which builtins don't have.
+fn format(data: &[u8]) -> &'static str {
+    match data {
+        [b'R', b'I', b'F', b'F', ..] => "wav",
+        [b'f', b'L', b'a', b'C', ..] => "flac",
+        [b'O', b'g', b'g', b'S', ..] => "ogg",
+        [b'I', b'D', b'3', ..] => "mp3",
+        [0xff, b, ..] if b & 0xe0 == 0xe0 => "mp3",
+        _ => "other",
+    }
+}
+
+/// Building the grid for a board of `tiles` sounds, and laying it out in each of the sizes.
+fn layout(board: &Board, tiles: usize) -> (Duration, Vec<Duration>) {
+    let template = board.sounds.first().cloned().unwrap_or_else(|| Board::builtin().sounds.remove(0));
+    let mut many = board.clone();
+    many.sounds = (0..tiles).map(|i| Sound { name: format!("sound {i}"), ..template.clone() }).collect();
+
+    let start = Instant::now();
+    let mut grid = Grid::new(&many);
+    let build = start.elapsed();
+    let layouts = SIZES
+        .iter()
+        .map(|&(width, height)| {
+            let start = Instant::now();
+            grid.compute_layout(Rect::new(0, 0, width, height));
+            start.elapsed()
+        })
+        .collect();
+    (build, layouts)
+}
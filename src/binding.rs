@@ -0,0 +1,379 @@
+//! What triggers a sound, and how that is shown to the user.
+
+use std::collections::HashMap;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A key together with the modifiers that have to be held for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub const fn new(code: KeyCode) -> Self {
+        Self { code, modifiers: KeyModifiers::NONE }
+    }
+
+    /// Parses chords like `g`, `F13`, `ctrl+1` or `ctrl+shift+pageup`.
+    pub fn parse(s: &str) -> Option<Self> {
+        // Split off modifiers, but keep a literal `+` key working (`+`, `ctrl++`)
+        let (mods, key) = match s.strip_suffix("++") {
+            Some(rest) => (rest, "+"),
+            None => match s.rsplit_once('+') {
+                Some((mods, key)) if !key.is_empty() => (mods, key),
+                _ => ("", s),
+            },
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for m in mods.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match m.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                "super" | "cmd" | "win" => KeyModifiers::SUPER,
+                _ => return None,
+            };
+        }
+
+        Some(Self { code: parse_key(key)?, modifiers }.normalized())
+    }
+
+    /// The chord a key event represents, in the same form [`KeyChord::parse`] produces.
+    pub fn from_event(key: &KeyEvent) -> Self {
+        Self { code: key.code, modifiers: key.modifiers }.normalized()
+    }
+
+    fn normalized(mut self) -> Self {
+        // Shift is already part of a character key (`A` rather than shift+`a`), and terminals
+        // disagree on whether they report it too
+        if let KeyCode::Char(c) = self.code {
+            if self.modifiers.contains(KeyModifiers::SHIFT) && c.is_lowercase() {
+                self.code = KeyCode::Char(c.to_uppercase().next().unwrap_or(c));
+            }
+            self.modifiers -= KeyModifiers::SHIFT;
+        }
+        self.modifiers -= KeyModifiers::HYPER | KeyModifiers::META;
+        self
+    }
+}
+
+impl std::fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (m, name) in [(KeyModifiers::CONTROL, "ctrl"), (KeyModifiers::ALT, "alt"), (KeyModifiers::SHIFT, "shift"), (KeyModifiers::SUPER, "super")] {
+            if self.modifiers.contains(m) {
+                write!(f, "{name}+")?;
+            }
+        }
+        write!(f, "{}", key_to_string(self.code))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    Key(KeyChord),
+    /// A note-on for this note number, on any channel, from the controllers [`crate::midi`]
+    /// reads; `midi` takes a number like `60` or a name like `"C4"`
+    MidiNote(u8),
+    /// A name remote controls can trigger the sound by
+    Name(String),
+    /// Words that trigger the sound when they are said, normalized by [`normalize_phrase`]
+    Phrase(String),
+    /// A button or stick on any gamepad, see [`crate::gamepad`]
+    Pad(PadInput),
+}
+
+/// Something on a gamepad that can be pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PadInput {
+    Button(u8),
+    /// An axis being pushed past `threshold`, towards the side its sign says. Kept in the
+    /// driver's units (from -32767 to 32767) so triggers compare exactly.
+    Axis { axis: u8, threshold: i16 },
+}
+
+impl PadInput {
+    /// Parses `3` or `button 3`, names like `a` or `start` (numbered like Xbox pads are on
+    /// Linux), and axes like `axis 1 > 0.5` or `axis 2 < -0.8`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let lower = s.trim().to_ascii_lowercase();
+        if let Some(axis) = lower.strip_prefix("axis") {
+            let (axis, threshold, sign) = match axis.split_once('>') {
+                Some((axis, threshold)) => (axis, threshold, 1.0),
+                None => axis.split_once('<').map(|(axis, threshold)| (axis, threshold, -1.0))?,
+            };
+            let threshold: f32 = threshold.trim().parse().ok()?;
+            // Pushing past the middle, or the other way, isn't a press
+            if threshold * sign <= 0.0 || threshold.abs() > 1.0 {
+                return None;
+            }
+            return Some(Self::Axis { axis: axis.trim().parse().ok()?, threshold: (threshold * 32767.0) as i16 });
+        }
+        let button = lower.strip_prefix("button").unwrap_or(&lower).trim();
+        let named = ["a", "b", "x", "y", "lb", "rb", "back", "start", "guide", "ls", "rs"].iter().position(|&n| n == button);
+        match named {
+            Some(n) => Some(Self::Button(n as u8)),
+            None => button.parse().ok().map(Self::Button),
+        }
+    }
+
+    /// Whether an axis moving from `from` to `to` pushes it past this one's threshold.
+    pub fn crossed(self, axis: u8, from: i16, to: i16) -> bool {
+        match self {
+            Self::Axis { axis: a, threshold } if a == axis && threshold > 0 => from < threshold && to >= threshold,
+            Self::Axis { axis: a, threshold } if a == axis => from > threshold && to <= threshold,
+            _ => false,
+        }
+    }
+}
+
+/// A trigger and how it is labelled, exactly as the user wrote it in the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub trigger: Trigger,
+    pub label: String,
+}
+
+impl Binding {
+    pub fn key(chord: KeyChord) -> Self {
+        Self { trigger: Trigger::Key(chord), label: chord.to_string() }
+    }
+
+    pub fn parse_key(s: &str) -> Option<Self> {
+        let s = s.trim();
+        Some(Self { trigger: Trigger::Key(KeyChord::parse(s)?), label: s.to_string() })
+    }
+
+    pub fn parse_midi(s: &str) -> Option<Self> {
+        let s = s.trim();
+        Some(Self { trigger: Trigger::MidiNote(parse_note(s)?), label: s.to_string() })
+    }
+
+    pub fn parse_pad(s: &str) -> Option<Self> {
+        let s = s.trim();
+        Some(Self { trigger: Trigger::Pad(PadInput::parse(s)?), label: s.to_string() })
+    }
+
+    pub fn alias(name: &str) -> Self {
+        Self { trigger: Trigger::Name(name.to_string()), label: name.to_string() }
+    }
+
+    pub fn phrase(phrase: &str) -> Option<Self> {
+        let normalized = normalize_phrase(phrase);
+        (!normalized.is_empty()).then(|| Self { trigger: Trigger::Phrase(normalized), label: phrase.trim().to_string() })
+    }
+
+    /// Whether the label belongs on the tile; aliases and phrases are words, not shortcuts.
+    pub fn is_shortcut(&self) -> bool {
+        !matches!(self.trigger, Trigger::Name(_) | Trigger::Phrase(_))
+    }
+}
+
+/// Which sounds each trigger plays. Several triggers can play the same sound, and (though the
+/// board warns about it) one trigger can play several sounds.
+#[derive(Debug, Default)]
+pub struct TriggerMap {
+    map: HashMap<Trigger, Vec<usize>>,
+}
+
+impl TriggerMap {
+    pub fn new<'a>(bindings: impl IntoIterator<Item = (usize, &'a [Binding])>) -> Self {
+        let mut map: HashMap<Trigger, Vec<usize>> = HashMap::new();
+        for (idx, bindings) in bindings {
+            for binding in bindings {
+                map.entry(binding.trigger.clone()).or_default().push(idx);
+            }
+        }
+        Self { map }
+    }
+
+    pub fn get(&self, trigger: &Trigger) -> &[usize] {
+        self.map.get(trigger).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Every phrase said in `heard` (normalized), with the sounds it plays.
+    pub fn phrases_in<'a>(&'a self, heard: &'a str) -> impl Iterator<Item = &'a [usize]> + 'a {
+        self.map.iter().filter_map(move |(trigger, sounds)| match trigger {
+            Trigger::Phrase(phrase) if self::heard(heard, phrase) => Some(sounds.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Every gamepad axis trigger that an axis moving from `from` to `to` set off.
+    pub fn axis_crossed(&self, axis: u8, from: i16, to: i16) -> impl Iterator<Item = &[usize]> + '_ {
+        self.map.iter().filter_map(move |(trigger, sounds)| match trigger {
+            Trigger::Pad(input) if input.crossed(axis, from, to) => Some(sounds.as_slice()),
+            _ => None,
+        })
+    }
+}
+
+/// Lowercases `s` and reduces it to single spaced words, so what a speech recognizer heard can
+/// be compared with what the config says.
+pub fn normalize_phrase(s: &str) -> String {
+    s.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether `phrase` was said somewhere in `heard`, both normalized.
+pub fn heard(heard: &str, phrase: &str) -> bool {
+    format!(" {heard} ").contains(&format!(" {phrase} "))
+}
+
+/// Parses a note number (`60`) or name (`C4`, `D#3`, `Eb-1`), with C4 being middle C (60).
+pub fn parse_note(s: &str) -> Option<u8> {
+    if let Ok(n) = s.parse::<u8>() {
+        return (n < 128).then_some(n);
+    }
+
+    let mut chars = s.chars();
+    let base = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = match rest.as_bytes().first() {
+        Some(b'#') => (1, &rest[1..]),
+        Some(b'b') => (-1, &rest[1..]),
+        _ => (0, rest),
+    };
+    let octave: i32 = octave.parse().ok()?;
+    u8::try_from((octave + 1) * 12 + base + accidental).ok().filter(|n| *n < 128)
+}
+
+pub fn parse_key(s: &str) -> Option<KeyCode> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    let lower = s.to_ascii_lowercase();
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+        return Some(KeyCode::F(n));
+    }
+
+    Some(match lower.as_str() {
+        "space" => KeyCode::Char(' '),
+        "plus" => KeyCode::Char('+'),
+        "enter" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "insert" => KeyCode::Insert,
+        "delete" => KeyCode::Delete,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        _ => return None,
+    })
+}
+
+pub fn key_to_string(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{n}"),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Insert => "insert".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        other => format!("{other:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use crate::harness::{self, Rng};
+    use super::{Binding, KeyChord, Trigger, TriggerMap};
+
+    const NAMED: &[KeyCode] = &[
+        KeyCode::Enter, KeyCode::Tab, KeyCode::Backspace, KeyCode::Insert, KeyCode::Delete, KeyCode::Home, KeyCode::End,
+        KeyCode::PageUp, KeyCode::PageDown, KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+    ];
+    const MODIFIERS: [KeyModifiers; 4] = [KeyModifiers::CONTROL, KeyModifiers::ALT, KeyModifiers::SHIFT, KeyModifiers::SUPER];
+
+    fn code(rng: &mut Rng) -> KeyCode {
+        match rng.below(3) {
+            // Every printable character, and space
+            0 => KeyCode::Char((b' ' + rng.below(95) as u8) as char),
+            1 => KeyCode::F(rng.below(25) as u8),
+            _ => *rng.pick(NAMED),
+        }
+    }
+
+    fn modifiers(rng: &mut Rng) -> KeyModifiers {
+        MODIFIERS.into_iter().filter(|_| rng.chance(0.3)).fold(KeyModifiers::NONE, |all, m| all | m)
+    }
+
+    #[test]
+    fn chords_read_back_as_written() {
+        harness::check(2000, |rng| {
+            let chord = KeyChord::from_event(&KeyEvent::new(code(rng), modifiers(rng)));
+            assert_eq!(KeyChord::parse(&chord.to_string()), Some(chord), "{chord}");
+        });
+    }
+
+    #[test]
+    fn shift_is_part_of_characters() {
+        harness::check(500, |rng| {
+            let c = (b'!' + rng.below(94) as u8) as char;
+            let modifiers = modifiers(rng) - KeyModifiers::SHIFT;
+            let plain = KeyChord::from_event(&KeyEvent::new(KeyCode::Char(c), modifiers));
+            // Shifted, a letter is the capital one
+            let shifted = KeyChord { code: KeyCode::Char(c.to_ascii_uppercase()), modifiers: plain.modifiers };
+            assert_eq!(KeyChord::from_event(&KeyEvent::new(KeyCode::Char(c), modifiers | KeyModifiers::SHIFT)), shifted);
+            assert_eq!(KeyChord::parse(&format!("shift+{plain}")), Some(shifted));
+        });
+    }
+
+    /// `shift+a` is the key that types `A`, not `a` with shift forgotten.
+    #[test]
+    fn shifted_letters_are_capitals() {
+        let chord = |code, modifiers| Some(KeyChord { code: KeyCode::Char(code), modifiers });
+        assert_eq!(KeyChord::parse("shift+a"), chord('A', KeyModifiers::NONE));
+        assert_eq!(KeyChord::parse("ctrl+shift+a"), chord('A', KeyModifiers::CONTROL));
+        assert_eq!(KeyChord::parse("shift+A"), chord('A', KeyModifiers::NONE));
+        assert_eq!(KeyChord::parse("a"), chord('a', KeyModifiers::NONE));
+        assert_eq!(KeyChord::from_event(&KeyEvent::new(KeyCode::Char('a'), KeyModifiers::SHIFT)), KeyChord::parse("A").unwrap());
+    }
+
+    /// Each trigger plays every sound bound to it, once for every time it is bound.
+    #[test]
+    fn triggers_play_what_is_bound_to_them() {
+        harness::check(300, |rng| {
+            let chords: Vec<KeyChord> = (0..1 + rng.below(12)).map(|_| KeyChord::from_event(&KeyEvent::new(code(rng), modifiers(rng)))).collect();
+            let sounds: Vec<Vec<Binding>> = (0..rng.below(20)).map(|_| (0..rng.below(4)).map(|_| Binding::key(*rng.pick(&chords))).collect()).collect();
+            let map = TriggerMap::new(sounds.iter().enumerate().map(|(idx, b)| (idx, b.as_slice())));
+            for chord in &chords {
+                let trigger = Trigger::Key(*chord);
+                let expected: Vec<usize> =
+                    sounds.iter().enumerate().flat_map(|(idx, b)| std::iter::repeat_n(idx, b.iter().filter(|b| b.trigger == trigger).count())).collect();
+                assert_eq!(map.get(&trigger), expected, "{chord}");
+            }
+        });
+    }
+}
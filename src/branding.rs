@@ -0,0 +1,77 @@
+//! Branding, for boards that are streamed: `header` in `[ui]` names a text file of ASCII art
+//! drawn above the tiles, and `splash` one shown when the board starts, until a key is pressed
+//! or [`SPLASH_TIME`] is over. Relative paths are next to the config.
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use color_eyre::eyre::Context;
+use unicode_width::UnicodeWidthStr;
+
+/// How long the splash is shown when nobody presses a key.
+pub const SPLASH_TIME: Duration = Duration::from_secs(3);
+
+/// ASCII art, as it is drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Art {
+    pub lines: Vec<String>,
+}
+
+impl Art {
+    pub fn parse(text: &str) -> Self {
+        // Tabs would be as wide as the terminal likes, and blank lines at the end are only room
+        let mut lines: Vec<String> = text.lines().map(|line| line.replace('\t', "    ").trim_end().to_string()).collect();
+        while lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+        Self { lines }
+    }
+
+    /// Reads the art at `path`, which is next to the config at `config` when it is relative.
+    pub fn load(path: &Path, config: &Path) -> color_eyre::Result<Self> {
+        let path = config.parent().unwrap_or(Path::new("")).join(path);
+        let text = fs::read_to_string(&path).wrap_err_with(|| format!("read {}", path.display()))?;
+        Ok(Self::parse(&text))
+    }
+
+    pub fn width(&self) -> u16 {
+        self.lines.iter().map(|line| line.width()).max().unwrap_or(0).min(u16::MAX as usize) as u16
+    }
+
+    pub fn height(&self) -> u16 {
+        self.lines.len().min(u16::MAX as usize) as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Instant;
+    use crossterm::event::KeyCode;
+    use crate::harness::{self, Harness};
+    use super::{Art, SPLASH_TIME};
+
+    #[test]
+    fn the_splash_shows_until_a_key_and_the_header_stays() {
+        let dir = harness::scratch("branding");
+        fs::write(dir.join("header.txt"), "  ~ THE BOARD ~\n\n").unwrap();
+        fs::write(dir.join("splash.txt"), "\tWELCOME\n").unwrap();
+        let mut board = harness::board(2);
+        board.header = Some(dir.join("header.txt"));
+        board.splash = Some(dir.join("splash.txt"));
+        let mut h = Harness::new(board, 80, 24).loaded();
+        assert_eq!(h.app.header, Some(Art { lines: vec!["  ~ THE BOARD ~".to_string()] }));
+        assert!(h.screen().contains("    WELCOME") && !h.screen().contains("sound 0"));
+
+        // The key that gets rid of it doesn't play anything
+        h.press(KeyCode::Enter);
+        assert!(h.audio.take().is_empty());
+        let screen = h.screen();
+        assert!(screen.lines().next().unwrap().contains("~ THE BOARD ~") && screen.contains("sound 0"), "{screen}");
+
+        // Or it goes by itself
+        h.app.load_art(true);
+        h.app.tick(Instant::now() + SPLASH_TIME);
+        assert!(h.app.splash.is_none());
+    }
+}
@@ -0,0 +1,84 @@
+//! A file picker inside the TUI, for finding sounds to add without leaving the board or typing
+//! paths. It only lists directories and the audio files the board can play.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use color_eyre::eyre::Context;
+use crate::config::{self, Board, Source};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub path: PathBuf,
+    pub dir: bool,
+    /// A file the board already plays
+    pub on_board: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Browser {
+    pub dir: PathBuf,
+    /// The parent directory first (when there is one), then directories, then audio files
+    pub entries: Vec<Entry>,
+    pub selected: usize,
+}
+
+impl Browser {
+    pub fn open(dir: &Path) -> color_eyre::Result<Self> {
+        // Absolute, so going up past where the board was started from works
+        let dir = fs::canonicalize(dir).wrap_err_with(|| format!("open {}", dir.display()))?;
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&dir).wrap_err_with(|| format!("read {}", dir.display()))? {
+            let Ok(entry) = entry else { continue };
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') {
+                continue;
+            }
+            // Following symlinks, a link to a directory is browsed like one
+            let path = entry.path();
+            let is_dir = path.is_dir();
+            if is_dir || config::is_audio(&path) {
+                entries.push(Entry { name, path, dir: is_dir, on_board: false });
+            }
+        }
+        entries.sort_by(|a, b| b.dir.cmp(&a.dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+        if let Some(parent) = dir.parent() {
+            entries.insert(0, Entry { name: "..".to_string(), path: parent.to_path_buf(), dir: true, on_board: false });
+        }
+        Ok(Self { dir, entries, selected: 0 })
+    }
+
+    /// Marks the files that are on `board`, after it changed.
+    pub fn mark(&mut self, board: &Board) {
+        // Sounds are stored with the path they were added by, which may be relative
+        let files: Vec<PathBuf> = board
+            .sounds
+            .iter()
+            .flat_map(|s| s.sources())
+            .filter_map(|s| match s {
+                Source::File(path) => fs::canonicalize(path).ok(),
+                Source::Builtin(_) | Source::Command(_) => None,
+            })
+            .collect();
+        for entry in &mut self.entries {
+            entry.on_board = !entry.dir && files.contains(&entry.path);
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        self.entries.get(self.selected)
+    }
+
+    /// The browser one directory up, with the directory that was left selected.
+    pub fn parent(&self) -> Option<color_eyre::Result<Self>> {
+        let parent = self.dir.parent()?;
+        Some(Self::open(parent).map(|mut browser| {
+            browser.selected = browser.entries.iter().position(|e| e.path == self.dir).unwrap_or(0);
+            browser
+        }))
+    }
+
+    pub fn select(&mut self, idx: usize) {
+        self.selected = idx.min(self.entries.len().saturating_sub(1));
+    }
+}
@@ -0,0 +1,247 @@
+//! Buses, `[[bus]]`: every sound plays on the bus its `bus` names (or its group's), and then on
+//! the `master` bus, each with a volume, a mute and effects of its own. A bus's volume, mute and
+//! how much of its effects is heard can be changed while sounds play on it, over `bus` in
+//! [`crate::rpc`] or with a [`crate::midi`] fader.
+//!
+//! Sounds play on their own, so each goes through its buses' effects by itself; for filters that
+//! comes to the same as running them over what the bus plays all together.
+
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use rodio::Source;
+use crate::audio::Gain;
+use crate::config::Effect;
+
+/// How loud a bus plays right now.
+#[derive(Debug, Default)]
+pub struct Level {
+    pub volume: Gain,
+    pub muted: AtomicBool,
+    /// Its share of the crossfader, when it is on one
+    pub crossfade: Gain,
+    /// How much of its effects is heard
+    pub wet: Gain,
+}
+
+impl Level {
+    pub fn set(&self, volume: f32, muted: bool) {
+        self.volume.set(volume);
+        self.muted.store(muted, Ordering::Relaxed);
+    }
+
+    pub fn muted(&self) -> bool {
+        self.muted.load(Ordering::Relaxed)
+    }
+
+    /// The gain the sounds on the bus get.
+    pub fn gain(&self) -> f32 {
+        if self.muted() { 0.0 } else { self.volume.get() * self.crossfade.get() }
+    }
+}
+
+/// Runs `source` through each bus's effects, in order, as much as that bus's `wet` says.
+pub fn apply(buses: &[(Vec<Effect>, Arc<Level>)], source: Box<dyn Source<Item = f32> + Send>) -> Box<dyn Source<Item = f32> + Send> {
+    buses.iter().filter(|(effects, _)| !effects.is_empty()).fold(source, |source, (effects, level)| Box::new(Effects::new(source, effects, level.clone())))
+}
+
+/// A high or low pass filter, each channel on its own: a biquad, as in the Audio EQ Cookbook,
+/// that rolls off at 12 dB an octave past the frequency.
+struct Filter {
+    /// b0, b1, b2, a1 and a2, divided by a0
+    coefficients: [f32; 5],
+    /// Each channel's last two samples in and out
+    history: Vec<[f32; 4]>,
+}
+
+impl Filter {
+    fn new(effect: Effect, rate: u32, channels: usize) -> Self {
+        let rate = rate.max(1) as f32;
+        let (Effect::HighPass(frequency) | Effect::LowPass(frequency)) = effect;
+        // Past half the rate there is nothing to filter
+        let w0 = 2.0 * PI * frequency.min(rate * 0.45) / rate;
+        let alpha = w0.sin() / (2.0 * FRAC_1_SQRT_2);
+        let cos = w0.cos();
+        let (b0, b1) = match effect {
+            Effect::HighPass(_) => ((1.0 + cos) / 2.0, -(1.0 + cos)),
+            Effect::LowPass(_) => ((1.0 - cos) / 2.0, 1.0 - cos),
+        };
+        let a0 = 1.0 + alpha;
+        let coefficients = [b0 / a0, b1 / a0, b0 / a0, -2.0 * cos / a0, (1.0 - alpha) / a0];
+        Self { coefficients, history: vec![[0.0; 4]; channels] }
+    }
+
+    fn filter(&mut self, x: f32, channel: usize) -> f32 {
+        let [b0, b1, b2, a1, a2] = self.coefficients;
+        let h = &mut self.history[channel];
+        let y = b0 * x + b1 * h[0] + b2 * h[1] - a1 * h[2] - a2 * h[3];
+        *h = [x, h[0], y, h[2]];
+        y
+    }
+}
+
+/// A sound through one bus's effects, blended with how it was by the bus's `wet`.
+pub struct Effects<S> {
+    inner: S,
+    filters: Vec<Filter>,
+    level: Arc<Level>,
+    channels: usize,
+    channel: usize,
+}
+
+impl<S: Source<Item = f32>> Effects<S> {
+    pub fn new(inner: S, effects: &[Effect], level: Arc<Level>) -> Self {
+        let channels = inner.channels().max(1) as usize;
+        let filters = effects.iter().map(|&effect| Filter::new(effect, inner.sample_rate(), channels)).collect();
+        Self { inner, filters, level, channels, channel: 0 }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Effects<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let x = self.inner.next()?;
+        let channel = self.channel;
+        // Filtered even when none of it is heard, so turning it up doesn't start from a click
+        let y = self.filters.iter_mut().fold(x, |y, filter| filter.filter(y, channel));
+        self.channel = (channel + 1) % self.channels;
+        let wet = self.level.wet.get();
+        Some(x + wet * (y - x))
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Effects<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use std::sync::Arc;
+    use rodio::buffer::SamplesBuffer;
+    use crossterm::event::KeyCode;
+    use crate::binding::Binding;
+    use crate::config::{Board, Bus, Crossfader, Effect};
+    use crate::harness::{self, Harness};
+    use crate::history::Via;
+    use crate::json::Value;
+    use crate::{rpc, toml};
+    use super::{Effects, Level};
+
+    /// How loud a full scale tone of `tone` Hz is once it went through `effect` and settled, from
+    /// its RMS, with the other channel at half of that.
+    fn through(effect: Effect, tone: f32) -> f32 {
+        through_wet(effect, tone, 1.0)
+    }
+
+    /// The same, with only `wet` of the effect heard.
+    fn through_wet(effect: Effect, tone: f32, wet: f32) -> f32 {
+        let samples: Vec<f32> = (0..48_000).map(|i| (2.0 * PI * tone * i as f32 / 48_000.0).sin()).flat_map(|s| [s, 0.5 * s]).collect();
+        let level = Arc::new(Level::default());
+        level.wet.set(wet);
+        let filtered: Vec<f32> = Effects::new(SamplesBuffer::new(2, 48_000, samples), &[effect], level).collect();
+        let settled = &filtered[filtered.len() / 2..];
+        let rms = |channel: usize| (settled.iter().skip(channel).step_by(2).map(|s| s * s).sum::<f32>() / (settled.len() / 2) as f32).sqrt() * 2f32.sqrt();
+        assert!((rms(1) - rms(0) / 2.0).abs() < 1e-3);
+        rms(0)
+    }
+
+    #[test]
+    fn filters_keep_what_they_pass() {
+        assert!(through(Effect::LowPass(1000.0), 100.0) > 0.98);
+        assert!(through(Effect::LowPass(1000.0), 8000.0) < 0.02);
+        assert!(through(Effect::HighPass(1000.0), 100.0) < 0.02);
+        assert!(through(Effect::HighPass(1000.0), 8000.0) > 0.98);
+        // 3 dB down right at the frequency
+        assert!((through(Effect::LowPass(1000.0), 1000.0) - 0.707).abs() < 0.02);
+
+        // Half wet is half of what the filter takes out
+        assert!((through_wet(Effect::LowPass(1000.0), 8000.0, 0.5) - 0.5).abs() < 0.02);
+        assert!(through_wet(Effect::LowPass(1000.0), 8000.0, 0.0) > 0.999);
+    }
+
+    #[test]
+    fn sounds_and_groups_route_to_buses() {
+        let src = "version = 1\n[[group]]\nname = \"beds\"\nbus = \"music\"\n[[bus]]\nname = \"music\"\nvolume = 0.5\nmute = true\neffects = [\"high-pass 120\", \"low-pass 8000 Hz\"]\nwet = 0.25\nstem = true\n[[sound]]\nname = \"bed\"\nbuiltin = \"puree\"\ngroup = \"beds\"\n[[sound]]\nname = \"horn\"\nbuiltin = \"puree\"\ngroup = \"beds\"\nbus = \"sfx\"\n";
+        let (board, _) = Board::parse(src).unwrap();
+        assert_eq!(board.buses, [Bus { name: "music".to_string(), volume: 0.5, muted: true, effects: vec![Effect::HighPass(120.0), Effect::LowPass(8000.0)], wet: 0.25, stem: true }]);
+        assert_eq!(board.sounds.iter().map(|s| board.bus_of(s)).collect::<Vec<_>>(), [Some("music"), Some("sfx")]);
+        let (again, _) = Board::parse(&toml::to_string(&board.to_table())).unwrap();
+        assert_eq!(again.buses, board.buses);
+        assert_eq!(again.sounds, board.sounds);
+
+        let err = Board::parse(&src.replace("low-pass 8000 Hz", "reverb")).unwrap_err().to_string();
+        assert!(err.contains("no effect like \"reverb\""), "{err}");
+    }
+
+    #[test]
+    fn buses_can_be_muted_while_the_board_runs() {
+        let mut board = harness::board(2);
+        board.sounds[0].bus = Some("music".to_string());
+        board.buses = vec![Bus { name: Bus::MASTER.to_string(), volume: 0.5, muted: false, effects: Vec::new(), wet: 1.0, stem: false }];
+        let mut h = Harness::new(board, 80, 24).loaded();
+        let play = |h: &mut Harness, sound: &str| {
+            rpc::handle(&mut h.app, &format!(r#"{{"jsonrpc": "2.0", "id": 1, "method": "play", "params": {{"sound": "{sound}"}}}}"#));
+            h.audio.take().into_iter().map(|p| p.volume).collect::<Vec<_>>()
+        };
+        assert!(play(&mut h, "sound 0").iter().all(|&v| v == 0.5));
+
+        let response = rpc::handle(&mut h.app, r#"{"jsonrpc": "2.0", "id": 2, "method": "bus", "params": {"bus": "music", "muted": true}}"#).unwrap();
+        let result = response.get("result").unwrap();
+        assert_eq!((result.get("volume"), result.get("muted")), (Some(&Value::Number(1.0)), Some(&Value::Boolean(true))));
+        assert!(play(&mut h, "sound 0").iter().all(|&v| v == 0.0));
+        assert!(play(&mut h, "sound 1").iter().all(|&v| v == 0.5));
+
+        // Until the board says otherwise
+        let board = h.app.board.clone();
+        h.app.replace_board(Board { buses: Vec::new(), ..board });
+        assert!(play(&mut h, "sound 0").iter().all(|&v| v == 1.0));
+    }
+
+    #[test]
+    fn the_crossfader_blends_between_two_buses() {
+        let mut board = harness::board(2);
+        board.sounds[0].bus = Some("music".to_string());
+        board.sounds[1].bus = Some("voice".to_string());
+        let key = |k: &str| Binding::parse_key(k);
+        board.crossfader = Some(Crossfader { left: "music".to_string(), right: "voice".to_string(), left_key: key("F7"), right_key: key("F8"), step: 0.25 });
+        let mut h = Harness::new(board, 100, 24).loaded();
+        let volumes = |h: &mut Harness| {
+            h.app.play(0, Via::Key);
+            h.app.play(1, Via::Key);
+            let played = h.audio.take();
+            let volume = |name: &str| played.iter().find(|p| p.name == name).unwrap().volume;
+            (volume("sound 0"), volume("sound 1"))
+        };
+        let (music, voice) = volumes(&mut h);
+        assert!((music - 0.707).abs() < 0.001 && (voice - 0.707).abs() < 0.001, "{music} {voice}");
+        assert!(h.screen().contains("music ────●──── voice"));
+
+        h.press(KeyCode::F(7));
+        h.press(KeyCode::F(7));
+        h.press(KeyCode::F(7));
+        assert_eq!(volumes(&mut h), (1.0, 0.0));
+        assert!(h.screen().contains("music ●──"));
+
+        rpc::handle(&mut h.app, r#"{"jsonrpc": "2.0", "id": 1, "method": "crossfade", "params": {"position": 1}}"#);
+        assert_eq!(volumes(&mut h), (0.0, 1.0));
+        let (again, _) = Board::parse(&toml::to_string(&h.app.board.to_table())).unwrap();
+        assert_eq!(again.crossfader, h.app.board.crossfader);
+    }
+}
@@ -0,0 +1,117 @@
+//! Sounds converted ahead of time to 48 kHz, 32-bit float WAV. Decoding that is little more than
+//! copying it, where an MP3 or FLAC is decoded again on every play, so big boards and slow
+//! machines start sounds sooner.
+//!
+//! Entries are named after the file's path, size and modification time, so editing a sound
+//! makes a new entry instead of playing the old one. The cache is used whenever it has a sound;
+//! filling it happens in the background when the config has a `[cache]` section, or at once
+//! with `soundboard cache`.
+
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::UNIX_EPOCH;
+use color_eyre::eyre::{eyre, Context};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rodio::source::UniformSourceIterator;
+use rodio::{Decoder, Source};
+use crate::resample::Resampler;
+
+pub const RATE: u32 = 48_000;
+
+/// A cache of converted sounds in one directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cache {
+    pub dir: PathBuf,
+    pub resampler: Resampler,
+}
+
+impl Cache {
+    /// The cache in `dir`, converting with `resampler`, or none without anywhere to put it.
+    pub fn new(dir: Option<&Path>, resampler: Resampler) -> Option<Self> {
+        Some(Self { dir: dir?.to_path_buf(), resampler })
+    }
+
+    /// Where the converted `source` goes, `None` when the file can't be read.
+    fn entry(&self, source: &Path) -> Option<PathBuf> {
+        let path = fs::canonicalize(source).ok()?;
+        let meta = fs::metadata(&path).ok()?;
+        let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+        // Linear was the only resampler once, its entries keep the names they had then
+        let resampler = match self.resampler {
+            Resampler::Linear => &[][..],
+            other => other.name().as_bytes(),
+        };
+        let key = [path.to_string_lossy().as_bytes(), &meta.len().to_le_bytes(), &modified.to_le_bytes(), &RATE.to_le_bytes(), resampler].concat();
+        let hash = crate::fnv::fnv1a(key);
+        Some(self.dir.join(format!("{hash:016x}.wav")))
+    }
+
+    /// The converted `source`, if it was converted since it last changed.
+    pub fn get(&self, source: &Path) -> Option<PathBuf> {
+        self.entry(source).filter(|e| e.is_file())
+    }
+
+    /// Converts `source` unless that was already done, returning whether it had to.
+    pub fn convert(&self, source: &Path) -> color_eyre::Result<bool> {
+        let entry = self.entry(source).ok_or_else(|| eyre!("read {}", source.display()))?;
+        if entry.is_file() {
+            return Ok(false);
+        }
+        fs::create_dir_all(&self.dir).wrap_err_with(|| format!("create {}", self.dir.display()))?;
+        let file = File::open(source).wrap_err_with(|| format!("read {}", source.display()))?;
+        let decoder = Decoder::new(BufReader::new(file)).wrap_err_with(|| format!("decode {}", source.display()))?;
+        let channels = decoder.channels();
+        let samples = UniformSourceIterator::<_, f32>::new(self.resampler.apply(decoder.convert_samples::<f32>(), RATE), channels, RATE);
+
+        // Written under another name first, a half written entry would play half the sound
+        let partial = entry.with_extension("part");
+        let spec = WavSpec { channels, sample_rate: RATE, bits_per_sample: 32, sample_format: SampleFormat::Float };
+        let write = || -> hound::Result<()> {
+            let mut writer = WavWriter::create(&partial, spec)?;
+            for sample in samples {
+                writer.write_sample(sample)?;
+            }
+            writer.finalize()
+        };
+        write().wrap_err_with(|| format!("write {}", partial.display()))?;
+        fs::rename(&partial, &entry).wrap_err_with(|| format!("write {}", entry.display()))?;
+        Ok(true)
+    }
+
+    /// Converts every one of `sources` in the background, reporting how [`Cache::convert`] went
+    /// for each.
+    pub fn convert_all(&self, sources: Vec<PathBuf>) -> Receiver<color_eyre::Result<bool>> {
+        let (tx, rx) = mpsc::channel();
+        let cache = self.clone();
+        thread::spawn(move || {
+            for source in sources {
+                if tx.send(cache.convert(&source)).is_err() {
+                    break;
+                }
+            }
+        });
+        rx
+    }
+
+    /// Removes every entry, returning how many there were.
+    pub fn clear(&self) -> color_eyre::Result<usize> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e).wrap_err_with(|| format!("read {}", self.dir.display())),
+        };
+        let mut removed = 0;
+        for entry in entries.flatten().map(|e| e.path()) {
+            // Only what the cache wrote, in case it was pointed at a directory with other things
+            let ours = entry.file_stem().and_then(|s| s.to_str()).is_some_and(|s| s.len() == 16 && s.bytes().all(|b| b.is_ascii_hexdigit()));
+            if ours && entry.extension().is_some_and(|e| e == "wav" || e == "part") {
+                fs::remove_file(&entry).wrap_err_with(|| format!("remove {}", entry.display()))?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
@@ -0,0 +1,219 @@
+//! Getting a sound's channels onto a device's speakers. rodio would copy the right channel onto
+//! every extra speaker of a surround device and drop the extra channels a stereo device doesn't
+//! have, so sounds are mixed onto the device's layout here instead: channels go to the speaker
+//! they are for, and speakers the device lacks are folded into the nearest ones it has.
+//!
+//! Sounds can also name the speakers they play on with `speakers`, like `["lfe"]` for a rumble.
+
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::time::Duration;
+use rodio::Source;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speaker {
+    Left,
+    Right,
+    Center,
+    Lfe,
+    RearLeft,
+    RearRight,
+    SideLeft,
+    SideRight,
+}
+
+impl Speaker {
+    pub const ALL: [Self; 8] = [Self::Left, Self::Right, Self::Center, Self::Lfe, Self::RearLeft, Self::RearRight, Self::SideLeft, Self::SideRight];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Right => "right",
+            Self::Center => "center",
+            Self::Lfe => "lfe",
+            Self::RearLeft => "rear-left",
+            Self::RearRight => "rear-right",
+            Self::SideLeft => "side-left",
+            Self::SideRight => "side-right",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|speaker| speaker.name().eq_ignore_ascii_case(s.trim()))
+    }
+
+    /// Where this speaker's signal goes on a device without it, and how loud.
+    fn fold(self, layout: &[Option<Speaker>]) -> Vec<(Self, f32)> {
+        let has = |speaker| layout.contains(&Some(speaker));
+        match self {
+            // Only a mono device has neither, which sums the two
+            Self::Left | Self::Right => vec![(Self::Center, 0.5)],
+            // Kept rather than dropped like a proper downmix would, a rumble should still be heard
+            Self::Center | Self::Lfe => vec![(Self::Left, FRAC_1_SQRT_2), (Self::Right, FRAC_1_SQRT_2)],
+            Self::RearLeft if has(Self::SideLeft) => vec![(Self::SideLeft, 1.0)],
+            Self::RearRight if has(Self::SideRight) => vec![(Self::SideRight, 1.0)],
+            Self::SideLeft if has(Self::RearLeft) => vec![(Self::RearLeft, 1.0)],
+            Self::SideRight if has(Self::RearRight) => vec![(Self::RearRight, 1.0)],
+            Self::RearLeft | Self::SideLeft => vec![(Self::Left, FRAC_1_SQRT_2)],
+            Self::RearRight | Self::SideRight => vec![(Self::Right, FRAC_1_SQRT_2)],
+        }
+    }
+}
+
+impl std::fmt::Display for Speaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The speakers of each channel, in the order WAV files and most systems use. Channels past the
+/// ones known for that many get no speaker.
+pub fn layout(channels: u16) -> Vec<Option<Speaker>> {
+    use Speaker::*;
+    let known: &[Speaker] = match channels {
+        1 => &[Center],
+        2 => &[Left, Right],
+        3 => &[Left, Right, Center],
+        4 => &[Left, Right, RearLeft, RearRight],
+        5 => &[Left, Right, Center, RearLeft, RearRight],
+        6 => &[Left, Right, Center, Lfe, RearLeft, RearRight],
+        // 6.1, whose back center has no speaker here
+        7 => return vec![Some(Left), Some(Right), Some(Center), Some(Lfe), None, Some(SideLeft), Some(SideRight)],
+        _ => &[Left, Right, Center, Lfe, RearLeft, RearRight, SideLeft, SideRight],
+    };
+    (0..channels as usize).map(|i| known.get(i).copied()).collect()
+}
+
+/// How much of each input channel goes into each output channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    inputs: usize,
+    /// Row per output channel, column per input channel
+    gains: Vec<f32>,
+}
+
+impl Matrix {
+    /// Mixes a sound with `channels` onto a device with `outputs`, playing it on `speakers` when
+    /// it names any. A sound naming as many speakers as it has channels plays each channel on
+    /// its speaker, any other sound is mixed to mono and plays on all of them.
+    pub fn new(channels: u16, speakers: &[Speaker], outputs: u16) -> Self {
+        let inputs = channels.max(1) as usize;
+        let device = layout(outputs.max(1));
+        let mut matrix = Self { inputs, gains: vec![0.0; inputs * device.len()] };
+        let mono = match speakers {
+            [] if inputs == 1 => Some(&[Speaker::Left, Speaker::Right][..]),
+            [] => None,
+            _ if speakers.len() == inputs => None,
+            named => Some(named),
+        };
+        match mono {
+            Some(targets) => {
+                for input in 0..inputs {
+                    for &speaker in targets {
+                        matrix.route(input, speaker, 1.0 / inputs as f32, &device);
+                    }
+                }
+            }
+            None => {
+                let own = if speakers.is_empty() { layout(channels) } else { speakers.iter().copied().map(Some).collect() };
+                for (input, speaker) in own.into_iter().enumerate() {
+                    if let Some(speaker) = speaker {
+                        matrix.route(input, speaker, 1.0, &device);
+                    }
+                }
+            }
+        }
+        matrix
+    }
+
+    /// Sums a sound with `channels` to mono, and plays that on all of a device's `outputs`.
+    pub fn mono(channels: u16, outputs: u16) -> Self {
+        let inputs = channels.max(1) as usize;
+        let gains = vec![1.0 / inputs as f32; inputs * outputs.max(1) as usize];
+        Self { inputs, gains }
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.gains.len() / self.inputs
+    }
+
+    fn route(&mut self, input: usize, speaker: Speaker, gain: f32, device: &[Option<Speaker>]) {
+        self.route_within(input, speaker, gain, device, 3);
+    }
+
+    fn route_within(&mut self, input: usize, speaker: Speaker, gain: f32, device: &[Option<Speaker>], depth: u8) {
+        if let Some(output) = device.iter().position(|s| *s == Some(speaker)) {
+            self.gains[output * self.inputs + input] += gain;
+        } else if depth > 0 {
+            for (next, g) in speaker.fold(device) {
+                self.route_within(input, next, gain * g, device, depth - 1);
+            }
+        }
+    }
+
+    /// Mixes one frame of input into one frame of output.
+    pub fn apply(&self, frame: &[f32], out: &mut [f32]) {
+        for (output, row) in out.iter_mut().zip(self.gains.chunks(self.inputs)) {
+            *output = row.iter().zip(frame).map(|(g, s)| g * s).sum();
+        }
+    }
+}
+
+/// A source mixed onto a device's channels through a [`Matrix`].
+pub struct Remix<S> {
+    inner: S,
+    matrix: Matrix,
+    frame: Vec<f32>,
+    out: Vec<f32>,
+    /// The next sample of `out` to hand out
+    pos: usize,
+}
+
+impl<S: Source<Item = f32>> Remix<S> {
+    /// Mixes `inner` through `matrix`, which must be made for its channels.
+    pub fn new(inner: S, matrix: Matrix) -> Self {
+        let out = vec![0.0; matrix.outputs()];
+        Self { frame: Vec::with_capacity(matrix.inputs), pos: out.len(), inner, matrix, out }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Remix<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.pos == self.out.len() {
+            self.frame.clear();
+            self.frame.extend(self.inner.by_ref().take(self.matrix.inputs));
+            if self.frame.is_empty() {
+                return None;
+            }
+            // A cut off last frame is padded with silence
+            self.frame.resize(self.matrix.inputs, 0.0);
+            self.matrix.apply(&self.frame, &mut self.out);
+            self.pos = 0;
+        }
+        self.pos += 1;
+        Some(self.out[self.pos - 1])
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Remix<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.out.len() as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
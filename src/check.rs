@@ -0,0 +1,195 @@
+//! The audio check: what each output is, a test tone or pink noise to hear it by, and a check
+//! that what is played on an output comes back in on an input, for finding out whether a
+//! virtual microphone is routed the way the voice chat expects before going live.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::audio::{Engine, Output};
+use crate::config::Board;
+use crate::loader::Decoded;
+use crate::mic::{self, Capture};
+
+const RATE: u32 = 48_000;
+/// Not a divisor of the usual rates, so it doesn't line up with the sample grid
+const TONE: f32 = 997.0;
+const LENGTH: Duration = Duration::from_millis(1500);
+/// -12 dBFS, loud enough to hear without startling anyone who has it on headphones
+const LEVEL: f32 = 0.25;
+/// How long to keep listening after the tone, for the latency of the path back
+const TAIL: Duration = Duration::from_millis(500);
+/// Quieter than this coming back counts as nothing, in dBFS
+const FLOOR: f32 = -50.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Tone,
+    Noise,
+}
+
+impl Signal {
+    pub fn name(self) -> &'static str {
+        match self {
+            Signal::Tone => "test tone",
+            Signal::Noise => "pink noise",
+        }
+    }
+
+    /// The signal, mono, faded in and out so it doesn't click.
+    pub fn audio(self) -> Decoded {
+        let frames = (LENGTH.as_secs_f32() * RATE as f32) as usize;
+        let fade = RATE as usize / 100;
+        // Paul Kellet's filter over white noise from a xorshift, which is plenty for this
+        let (mut state, mut b) = (0x2545_f491_u32, [0.0f32; 3]);
+        let samples = (0..frames).map(|i| {
+            let sample = match self {
+                Signal::Tone => (std::f32::consts::TAU * TONE * i as f32 / RATE as f32).sin(),
+                Signal::Noise => {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    let white = state as f32 / u32::MAX as f32 * 2.0 - 1.0;
+                    b = [0.99765 * b[0] + white * 0.0990, 0.96300 * b[1] + white * 0.2965, 0.57000 * b[2] + white * 1.0526];
+                    (b[0] + b[1] + b[2] + white * 0.1848) * 0.3
+                }
+            };
+            let edge = i.min(frames - 1 - i);
+            sample * LEVEL * (edge as f32 / fade as f32).min(1.0)
+        });
+        Decoded { channels: 1, rate: RATE, samples: samples.collect::<Arc<[f32]>>() }
+    }
+}
+
+/// An output and what it reported about itself.
+#[derive(Debug, Clone)]
+pub struct Probe {
+    /// "monitor" or "output"
+    pub role: &'static str,
+    pub output: Output,
+    /// Channels and sample rate, or why they couldn't be asked
+    pub format: Result<(u16, u32), String>,
+}
+
+impl Probe {
+    pub fn name(&self) -> &str {
+        self.output.device.as_deref().unwrap_or("the default output")
+    }
+}
+
+struct Listening {
+    capture: Capture,
+    /// On which output the tone was played
+    output: String,
+    until: Instant,
+}
+
+pub struct Check {
+    pub probes: Vec<Probe>,
+    pub selected: usize,
+    /// Inputs to listen on for the routing check, `None` being the system's default
+    pub inputs: Vec<Option<String>>,
+    pub input: usize,
+    listening: Option<Listening>,
+    /// What the last routing check found
+    pub result: Option<String>,
+}
+
+impl Check {
+    /// Looks at the board's outputs, `devices` being what they play on through `engine`.
+    pub fn open(board: &Board, engine: &Engine, devices: Vec<Output>) -> Self {
+        let probes = devices
+            .into_iter()
+            .enumerate()
+            .map(|(i, output)| Probe {
+                role: if i == 0 { "monitor" } else { "output" },
+                format: engine.format(output.device.as_deref()).map_err(|e| format!("{e:#}")),
+                output,
+            })
+            .collect();
+        let mut inputs = vec![None];
+        inputs.extend(mic::inputs().into_iter().map(Some));
+        // The talkover microphone is the likeliest to be where a virtual microphone is heard
+        let talkover = board.talkover.as_ref().and_then(|t| t.input.clone());
+        let input = inputs.iter().position(|i| talkover.is_some() && *i == talkover).unwrap_or(0);
+        Self { probes, selected: 0, inputs, input, listening: None, result: None }
+    }
+
+    pub fn selected(&self) -> Option<&Probe> {
+        self.probes.get(self.selected)
+    }
+
+    pub fn select(&mut self, idx: usize) {
+        self.selected = idx.min(self.probes.len().saturating_sub(1));
+    }
+
+    pub fn input_name(&self) -> &str {
+        self.inputs[self.input].as_deref().unwrap_or("the default input")
+    }
+
+    pub fn next_input(&mut self) {
+        self.input = (self.input + 1) % self.inputs.len();
+    }
+
+    pub fn listening(&self) -> bool {
+        self.listening.is_some()
+    }
+
+    /// Starts listening on the input for the tone played on `output`. Listening starts first,
+    /// so the tone is to be played right after; how long it takes to come in is measured from
+    /// here.
+    pub fn listen(&mut self, output: &str) -> color_eyre::Result<()> {
+        let capture = mic::capture(self.inputs[self.input].as_deref())?;
+        self.result = None;
+        self.listening = Some(Listening { capture, output: output.to_string(), until: Instant::now() + LENGTH + TAIL });
+        Ok(())
+    }
+
+    /// Finishes the routing check once it has listened long enough, returning whether it did.
+    pub fn poll(&mut self, now: Instant) -> bool {
+        if self.listening.as_ref().is_none_or(|l| now < l.until) {
+            return false;
+        }
+        let listening = self.listening.take().unwrap();
+        let samples = listening.capture.samples.lock().unwrap().clone();
+        self.result = Some(match heard(&samples, listening.capture.channels, listening.capture.rate) {
+            Some((at, level)) => {
+                format!("heard {} on {} at {level:.0} dBFS, about {} ms after it was played", listening.output, self.input_name(), at.as_millis())
+            }
+            None => format!("nothing from {} came in on {}, it isn't routed there", listening.output, self.input_name()),
+        });
+        true
+    }
+}
+
+/// When the tone first comes in on a capture, and how loud it is at its loudest.
+fn heard(samples: &[f32], channels: u16, rate: u32) -> Option<(Duration, f32)> {
+    let channels = channels.max(1) as usize;
+    let mono: Vec<f32> = samples.chunks(channels).map(|f| f.iter().sum::<f32>() / channels as f32).collect();
+    // Windows of 10 ms, each checked for how much of the tone is in it
+    let window = (rate as usize / 100).max(1);
+    let mut first = None;
+    let mut loudest = f32::MIN;
+    for (i, chunk) in mono.chunks_exact(window).enumerate() {
+        let tone = goertzel(chunk, TONE / rate as f32);
+        let level = mic::db(tone);
+        // Most of what is in the window has to be the tone, or it's just something loud
+        let rms = (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt();
+        if level >= FLOOR && tone >= rms * std::f32::consts::SQRT_2 * 0.5 {
+            first.get_or_insert(Duration::from_secs_f64((i * window) as f64 / rate as f64));
+            loudest = loudest.max(level);
+        }
+    }
+    first.map(|at| (at, loudest))
+}
+
+/// The amplitude of the sine at `frequency` (in cycles per sample) in `samples`.
+fn goertzel(samples: &[f32], frequency: f32) -> f32 {
+    let coefficient = 2.0 * (std::f32::consts::TAU * frequency).cos();
+    let (mut s1, mut s2) = (0.0f32, 0.0f32);
+    for &x in samples {
+        let s = x + coefficient * s1 - s2;
+        s2 = s1;
+        s1 = s;
+    }
+    let power = s1 * s1 + s2 * s2 - coefficient * s1 * s2;
+    2.0 * power.max(0.0).sqrt() / samples.len() as f32
+}
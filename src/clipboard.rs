@@ -0,0 +1,82 @@
+//! Playing audio straight from the clipboard, to audition files before they go on the board.
+//! There is no clipboard API that works everywhere without a window, so this asks the tools
+//! each platform has for it.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use color_eyre::eyre::bail;
+use crate::config;
+
+/// Clipboard readers to try, in order, with their arguments.
+const TOOLS: &[(&str, &[&str])] = if cfg!(windows) {
+    &[("powershell", &["-NoProfile", "-Command", "Get-Clipboard"])]
+} else if cfg!(target_os = "macos") {
+    &[("pbpaste", &[])]
+} else {
+    &[("wl-paste", &["--no-newline"]), ("xclip", &["-selection", "clipboard", "-o"]), ("xsel", &["--clipboard", "--output"])]
+};
+
+/// The text on the clipboard.
+pub fn read() -> color_eyre::Result<String> {
+    for (tool, args) in TOOLS {
+        // wl-paste only works under Wayland and xclip only under X, so a tool that runs but fails
+        // just means trying the next one
+        let Ok(output) = Command::new(tool).args(*args).stdin(Stdio::null()).stderr(Stdio::null()).output() else { continue };
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+    }
+    let names: Vec<_> = TOOLS.iter().map(|(tool, _)| *tool).collect();
+    bail!("couldn't read the clipboard, this needs {}", names.join(" or "))
+}
+
+/// Audio the clipboard points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Clip {
+    File(PathBuf),
+    Url(String),
+}
+
+impl Clip {
+    /// What `text` (copied from a file manager, a terminal or a browser) points at, if it is an
+    /// audio file.
+    pub fn parse(text: &str) -> Option<Self> {
+        // Only the first file when several were copied, and without the quotes shells add
+        let text = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+        let text = text.trim_matches(|c| c == '"' || c == '\'');
+        if text.starts_with("http://") || text.starts_with("https://") {
+            let path = text.split(['?', '#']).next().unwrap_or(text);
+            return config::is_audio(Path::new(path)).then(|| Self::Url(text.to_string()));
+        }
+        let path = match text.strip_prefix("file://") {
+            Some(path) => PathBuf::from(percent_decode(path)),
+            None => PathBuf::from(text),
+        };
+        (config::is_audio(&path) && path.is_file()).then_some(Self::File(path))
+    }
+}
+
+/// File managers copy `file://` URLs, with spaces and such escaped.
+fn percent_decode(s: &str) -> String {
+    let mut out = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        let hex = |b: Option<u8>| b.and_then(|b| (b as char).to_digit(16));
+        if b == b'%' {
+            let mut ahead = bytes.clone();
+            if let (Some(hi), Some(lo)) = (hex(ahead.next()), hex(ahead.next())) {
+                out.push((hi * 16 + lo) as u8);
+                bytes = ahead;
+                continue;
+            }
+        }
+        out.push(b);
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Where the file a URL points at is downloaded to, in `dir`.
+pub fn download_path(url: &str, dir: &Path) -> PathBuf {
+    let name = url.split(['?', '#']).next().and_then(|u| u.rsplit('/').next()).map(percent_decode).unwrap_or_default();
+    dir.join(name)
+}
@@ -0,0 +1,97 @@
+//! Combos, `[[combo]]` sections: playing a few sounds one after the other quickly enough does
+//! something more, like a bonus sound when chat gets the board to play three sounds in a row.
+
+use std::collections::VecDeque;
+use std::time::Instant;
+use crate::config::{Combo, SignalAction};
+
+/// The sounds played last, to find the combos they complete.
+#[derive(Default)]
+pub struct Combos {
+    recent: VecDeque<(String, Instant)>,
+}
+
+impl Combos {
+    /// Takes note that the sound called `sound` played at `now`, returning what the combos it
+    /// completes do. What was played before then doesn't count towards the next combo.
+    pub fn played(&mut self, sound: &str, now: Instant, combos: &[Combo]) -> Vec<SignalAction> {
+        let Some(longest) = combos.iter().map(|c| c.sequence.len()).max() else { return Vec::new() };
+        self.recent.push_back((sound.to_string(), now));
+        while self.recent.len() > longest {
+            self.recent.pop_front();
+        }
+        let done: Vec<SignalAction> = combos.iter().filter(|c| self.completes(c, now)).map(|c| c.action.clone()).collect();
+        if !done.is_empty() {
+            self.recent.clear();
+        }
+        done
+    }
+
+    fn completes(&self, combo: &Combo, now: Instant) -> bool {
+        let Some(start) = self.recent.len().checked_sub(combo.sequence.len()) else { return false };
+        self.recent.range(start..).map(|(name, _)| name).eq(&combo.sequence) && now.duration_since(self.recent[start].1) <= combo.within
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crossterm::event::KeyCode;
+    use crate::config::{Board, Combo, SignalAction};
+    use crate::harness::{self, Harness};
+    use crate::history::Via;
+    use crate::toml;
+    use super::Combos;
+
+    fn combo(sequence: &[&str], action: SignalAction) -> Combo {
+        Combo { sequence: sequence.iter().map(|s| s.to_string()).collect(), within: Duration::from_secs(5), action }
+    }
+
+    #[test]
+    fn sounds_in_a_row_complete_a_combo() {
+        let combos = [combo(&["a", "b", "c"], SignalAction::Play("bonus".to_string())), combo(&["c", "c"], SignalAction::Stop)];
+        let mut recent = Combos::default();
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        assert!(recent.played("a", at(0), &combos).is_empty());
+        assert!(recent.played("b", at(1), &combos).is_empty());
+        assert_eq!(recent.played("c", at(2), &combos), [SignalAction::Play("bonus".to_string())]);
+        // That c went into the combo, so another doesn't make two
+        assert!(recent.played("c", at(3), &combos).is_empty());
+        assert_eq!(recent.played("c", at(4), &combos), [SignalAction::Stop]);
+
+        // Too slow
+        for (i, name) in ["a", "b", "c"].into_iter().enumerate() {
+            assert!(recent.played(name, at(10 + 3 * i as u64), &combos).is_empty());
+        }
+        // Out of order
+        for name in ["b", "a", "c"] {
+            assert!(recent.played(name, at(20), &combos).is_empty());
+        }
+    }
+
+    #[test]
+    fn a_combo_plays_its_sound() {
+        let mut board = harness::board(3);
+        board.combos = vec![combo(&["sound 0", "sound 1"], SignalAction::Play("sound 2".to_string()))];
+        let mut h = Harness::new(board, 80, 24).loaded();
+        h.press(KeyCode::Enter);
+        h.press(KeyCode::Right);
+        h.press(KeyCode::Enter);
+        assert_eq!(h.audio.take().iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["sound 0", "sound 1", "sound 2"]);
+        assert_eq!(h.app.history.entries.last().map(|e| e.via), Some(Via::Combo));
+    }
+
+    #[test]
+    fn combos_round_trip_through_the_config() {
+        let sounds = "version = 1\n[[sound]]\nname = \"a\"\nbuiltin = \"puree\"\n[[sound]]\nname = \"b\"\nbuiltin = \"erg\"\n";
+        let src = format!("{sounds}[[combo]]\nsequence = [\"a\", \"b\", \"a\"]\nwithin = 2.5\naction = \"mute\"\n");
+        let (board, _) = Board::parse(&src).unwrap();
+        assert_eq!(board.combos, [Combo { within: Duration::from_secs_f64(2.5), ..combo(&["a", "b", "a"], SignalAction::Mute) }]);
+        let (again, _) = Board::parse(&toml::to_string(&board.to_table())).unwrap();
+        assert_eq!(again.combos, board.combos);
+
+        assert!(Board::parse(&src.replace("\"b\", \"a\"]", "\"c\"]")).unwrap_err().to_string().contains("no sound named \"c\""));
+        assert!(Board::parse(&src.replace(", \"b\", \"a\"]", "]")).unwrap_err().to_string().contains("at least two"));
+    }
+}
@@ -0,0 +1,154 @@
+//! The command palette, ^A: everything the board can do in one list, found by typing a few
+//! letters of its name, for when the key for it doesn't come to mind.
+
+use crate::binding::KeyChord;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Whatever this key does on the board
+    Key(KeyChord),
+    Play(usize),
+    Reload,
+    /// Picking an earlier version of the config to go back to
+    Backups,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub label: String,
+    /// The key that does the same, shown next to the label
+    pub key: Option<String>,
+    /// Other words it is found by
+    pub tags: Vec<String>,
+    pub command: Command,
+}
+
+impl Entry {
+    fn words(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.label.as_str()).chain(self.tags.iter().map(String::as_str))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Commands {
+    pub query: String,
+    /// Into what matches, not into every entry
+    pub selected: usize,
+    entries: Vec<Entry>,
+    /// The entries that match the query, best first
+    matches: Vec<usize>,
+}
+
+impl Commands {
+    pub fn new(entries: Vec<Entry>) -> Self {
+        let mut commands = Self { entries, ..Self::default() };
+        commands.filter();
+        commands
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.filter();
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.filter();
+    }
+
+    pub fn matches(&self) -> impl Iterator<Item = &Entry> + '_ {
+        self.matches.iter().map(|&idx| &self.entries[idx])
+    }
+
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        self.matches.get(self.selected).map(|&idx| &self.entries[idx])
+    }
+
+    fn filter(&mut self) {
+        let mut scored: Vec<_> = self.entries.iter().enumerate().filter_map(|(idx, e)| Some((e.words().filter_map(|words| score(&self.query, words)).max()?, idx))).collect();
+        // Stable, so equally good matches stay in the order they were listed in
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        self.matches = scored.into_iter().map(|(_, idx)| idx).collect();
+        self.selected = 0;
+    }
+}
+
+/// How well `query` matches `label`, or `None` when its letters don't all appear in it in order.
+/// Case and spaces in the query don't matter, and letters that follow each other or start a word
+/// count for more, so "pc" finds "play clipboard" before "replace".
+pub fn score(query: &str, label: &str) -> Option<u32> {
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    let Some((&first, rest)) = query.split_first() else { return Some(0) };
+    let starts_word = |at: usize| at == 0 || !label[at - 1].is_alphanumeric();
+    let letter = |at: usize, after: Option<usize>| match after {
+        Some(before) if before + 1 == at => 4,
+        _ if starts_word(at) => 3,
+        _ => 1,
+    };
+    // The best score so far for each place in the label the last letter matched could be at, as
+    // the first place a letter appears isn't always the one that matches best
+    let mut best: Vec<Option<u32>> = (0..label.len()).map(|at| (label[at] == first).then(|| letter(at, None))).collect();
+    for &c in rest {
+        best = (0..label.len())
+            .map(|at| {
+                if label[at] != c {
+                    return None;
+                }
+                (0..at).filter_map(|before| Some(best[before]? + letter(at, Some(before)))).max()
+            })
+            .collect();
+    }
+    best.into_iter().flatten().max()
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use crate::app::View;
+    use crate::harness::{self, Harness};
+    use super::score;
+
+    #[test]
+    fn fuzzy_matches() {
+        assert!(score("pc", "play clipboard") > score("pc", "replace"));
+        assert!(score("air", "play airhorn") > score("air", "play a trumpet, ir"));
+        assert_eq!(score("HORN", "play airhorn"), score("horn", "play airhorn"));
+        assert_eq!(score("hron", "play airhorn"), None);
+        assert_eq!(score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn the_palette_runs_what_is_picked() {
+        let mut h = Harness::new(harness::board(3), 80, 24).loaded();
+        h.press_with(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        assert_eq!(h.app.view, View::Commands);
+        for c in "sound 2".chars() {
+            h.press(KeyCode::Char(c));
+        }
+        assert_eq!(h.app.commands.as_ref().and_then(|c| c.selected()).map(|e| e.label.as_str()), Some("play sound 2"));
+        assert!(h.screen().contains("play sound 2"));
+        h.press(KeyCode::Enter);
+        assert_eq!(h.app.view, View::Board);
+        assert_eq!(h.app.selected, 2);
+        assert_eq!(h.app.board.sounds[2].plays, 1);
+
+        // Actions go through the key that does them
+        h.press_with(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        for c in "trash".chars() {
+            h.press(KeyCode::Char(c));
+        }
+        h.press(KeyCode::Enter);
+        assert_eq!(h.app.view, View::Trash);
+
+        h.press(KeyCode::Esc);
+        h.press_with(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        h.press(KeyCode::Esc);
+        assert_eq!(h.app.view, View::Board);
+        assert!(!h.app.should_quit);
+    }
+}
@@ -0,0 +1,2868 @@
+use std::borrow::Cow;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use color_eyre::eyre::{bail, eyre, Context};
+use crossterm::event::KeyCode;
+use ratatui::style::Color;
+use crate::audio::Shape;
+use crate::backup;
+use crate::channels::Speaker;
+use crate::binding::{Binding, KeyChord, Trigger, TriggerMap};
+use crate::locale::Locale;
+use crate::migrate::{self, CURRENT_VERSION};
+use crate::names;
+use crate::palette::Palette;
+use crate::pedal;
+use crate::resample::Resampler;
+use crate::sha256;
+use crate::toml::{self, Table, Value};
+use crate::webhook::Url;
+
+mod error;
+
+pub use error::ConfigError;
+
+macro_rules! data {
+    ($name: literal $(,)?) => {
+        ($name, include_bytes!(concat!("../assets/", $name, ".wav")))
+    };
+
+    ($name: literal, $ext: literal, $(,)?) => {
+        ($name, include_bytes!(concat!("../assets/", $name, $ext)))
+    };
+}
+
+/// Sounds compiled into the binary, usable from a config as `builtin = "<name>"`.
+pub const BUILTIN: &[(&str, &[u8])] = &[
+    data!("geen-grote-blij"),
+    data!("grote-blij"),
+    data!("puree"),
+    data!("windy"),
+    data!("administratiekosten"),
+    data!("erg"),
+    data!("betalen"),
+];
+
+const DEFAULT_KEYS: &[(char, &str)] = &[
+    ('g', "geen-grote-blij"),
+    ('b', "grote-blij"),
+    ('p', "puree"),
+    ('w', "windy"),
+    ('a', "administratiekosten"),
+    ('e', "erg"),
+    ('c', "betalen"),
+];
+
+pub const DEFAULT_CONFIG_PATH: &str = "soundboard.toml";
+
+pub const MAX_VOLUME: f32 = 2.0;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Source {
+    Builtin(String),
+    File(PathBuf),
+    /// Not a sound at all: a shell command the tile runs, see [`crate::shell`]
+    Command(String),
+}
+
+impl Source {
+    pub fn data(&self) -> color_eyre::Result<Cow<'static, [u8]>> {
+        match self {
+            Source::Builtin(name) => BUILTIN
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, data)| Cow::Borrowed(*data))
+                .ok_or_else(|| eyre!("no builtin sound named {name:?}")),
+            Source::File(path) => fs::read(path)
+                .map(Cow::Owned)
+                .wrap_err_with(|| format!("read {}", path.display())),
+            Source::Command(command) => Err(eyre!("{command:?} is a command, not a sound")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sound {
+    pub name: String,
+    /// Everything that plays this sound, in config order: keys, MIDI notes, aliases, phrases
+    pub bindings: Vec<Binding>,
+    /// Shown on the tile instead of the bindings' own labels
+    pub label: Option<String>,
+    pub source: Source,
+    /// More files, when it has several: each time one of them plays at random, or `source`
+    pub variants: Vec<Source>,
+    /// How likely each file is to be picked, `source` first; all equally likely when empty
+    pub weights: Vec<u32>,
+    /// The SHA-256 of each file, `source` first, to tell when one went missing or changed before
+    /// it is needed, see [`crate::verify`]; empty, or an empty one, when it wasn't recorded
+    pub checksums: Vec<String>,
+    pub order: Order,
+    /// Playback volume, 1.0 being the file's own level
+    pub volume: f32,
+    /// The speakers to play on, see [`crate::channels`]
+    pub speakers: Vec<Speaker>,
+    /// How often the sound was played, for ranking sounds by use; kept in the state directory
+    /// rather than the config, see [`crate::history::load_plays`]
+    pub plays: u32,
+    /// Only one sound of a group plays at a time
+    pub group: Option<String>,
+    /// The bus it plays on, its group's when unset, see [`crate::bus`]
+    pub bus: Option<String>,
+    /// DMX channels to set over Art-Net while the sound plays
+    pub dmx: Vec<Dmx>,
+    /// URLs that are POSTed to when the sound starts and stops
+    pub webhooks: Vec<String>,
+    /// The part of the file that plays, whether it loops, and its effects
+    pub shape: Shape,
+    /// The tile's border, the palette's when unset
+    pub color: Option<Color>,
+    /// Words to find the sound by in the command palette
+    pub tags: Vec<String>,
+    /// What happens once the sound has played to its end
+    pub then: Vec<Then>,
+}
+
+/// Which of its files a tile with several plays next.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Order {
+    /// One at random, going by the weights
+    #[default]
+    Random,
+    /// Each in turn, the first again after the last
+    RoundRobin,
+}
+
+impl Order {
+    pub const ALL: [Order; 2] = [Order::Random, Order::RoundRobin];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Order::Random => "random",
+            Order::RoundRobin => "round-robin",
+        }
+    }
+}
+
+/// Something a sound does once it has played to its end, set with `then-play`, `then-run` and
+/// `then-webhook`. A sound that is stopped, or a loop, never gets there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Then {
+    /// Plays the sound, or the sounds with the alias, of this name
+    Play(String),
+    /// Runs a shell command
+    Run(String),
+    /// POSTs to the URL that the sound ended
+    Webhook(String),
+}
+
+impl Then {
+    const KEYS: [&'static str; 3] = ["then-play", "then-run", "then-webhook"];
+
+    /// What it is set with on the sound.
+    fn key(&self) -> &'static str {
+        match self {
+            Then::Play(_) => "then-play",
+            Then::Run(_) => "then-run",
+            Then::Webhook(_) => "then-webhook",
+        }
+    }
+
+    fn value(&self) -> &str {
+        match self {
+            Then::Play(s) | Then::Run(s) | Then::Webhook(s) => s,
+        }
+    }
+}
+
+/// A DMX channel and the value it is set to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dmx {
+    /// From 1 to 512, like lighting desks count them
+    pub channel: u16,
+    pub value: u8,
+}
+
+impl Dmx {
+    /// Parses `channel=value`, like `12=255`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (channel, value) = s.split_once('=')?;
+        let channel = channel.trim().parse().ok().filter(|c| (1..=512).contains(c))?;
+        Some(Self { channel, value: value.trim().parse().ok()? })
+    }
+}
+
+impl std::fmt::Display for Dmx {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.channel, self.value)
+    }
+}
+
+/// Where sounds are played.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outputs {
+    /// The device you listen on yourself, the system default if unset
+    pub monitor: Option<String>,
+    /// Devices other people hear, like a virtual microphone or a stream's audio input. Muted
+    /// while rehearsing.
+    pub external: Vec<String>,
+    /// Outputs that get sounds summed to mono, by name
+    pub mono: Vec<String>,
+}
+
+/// Ducks the board while the microphone picks up speech.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Talkover {
+    /// The microphone, the system default input if unset
+    pub input: Option<String>,
+    /// Input level in dBFS above which someone is talking
+    pub threshold: f32,
+    /// How many dB to lower the board by while they talk
+    pub duck: f32,
+    /// How long to stay ducked after the level drops, so pauses between words don't pump
+    pub hold: Duration,
+}
+
+impl Default for Talkover {
+    fn default() -> Self {
+        Self { input: None, threshold: -35.0, duck: 12.0, hold: Duration::from_millis(800) }
+    }
+}
+
+impl Talkover {
+    fn from_table(table: &Table) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("talkover", table, &["input", "threshold", "duck", "hold"])?;
+
+        let defaults = Self::default();
+        let range = |key: &str, default: f64, range: std::ops::RangeInclusive<f64>, hint: &str| {
+            let Some(v) = number("talkover", table, key)? else { return Ok(default) };
+            if range.contains(&v) {
+                return Ok(v);
+            }
+            let line = table.entry(key).map_or(table.line, |e| e.line);
+            Err(ConfigError::new(format!("`{key}` must be between {} and {}", range.start(), range.end()))
+                .line(line)
+                .field(format!("talkover.{key}"))
+                .expected("number")
+                .suggest(hint.to_string()))
+        };
+
+        Ok(Self {
+            input: string("talkover", table, "input")?,
+            threshold: range("threshold", defaults.threshold as f64, -100.0..=0.0, "speech is usually between -40 and -20 dBFS")? as f32,
+            duck: range("duck", defaults.duck as f64, 0.0..=100.0, "12 lowers the board to about a quarter of its volume")? as f32,
+            hold: Duration::from_secs_f64(range("hold", defaults.hold.as_secs_f64(), 0.0..=60.0, "use the number of seconds, like 0.8")?),
+        })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        if let Some(input) = &self.input {
+            table.insert("input", input.as_str());
+        }
+        // Round away the noise of going through f32
+        table.insert("threshold", (self.threshold as f64 * 100.0).round() / 100.0);
+        table.insert("duck", (self.duck as f64 * 100.0).round() / 100.0);
+        table.insert("hold", self.hold.as_secs_f64());
+        table
+    }
+}
+
+/// Settings for sounds that share a `group`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub name: String,
+    /// How long sounds already playing take to fade out when another one in the group starts
+    pub fade: Duration,
+    pub sidechain: Option<Sidechain>,
+    /// The bus its sounds play on, unless they name one of their own
+    pub bus: Option<String>,
+}
+
+/// Lowering a group's sounds while those of another group play, see [`crate::sidechain`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sidechain {
+    /// The group that ducks this one
+    pub by: String,
+    /// How many dB down the group goes while the other plays at full scale
+    pub duck: f32,
+    /// How long it takes to come back up
+    pub release: Duration,
+}
+
+impl Sidechain {
+    pub const DUCK: f32 = 12.0;
+    pub const RELEASE: Duration = Duration::from_millis(300);
+}
+
+impl Group {
+    fn from_table(table: &Table) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("group", table, &["name", "fade", "ducked-by", "duck", "release", "bus"])?;
+
+        let name = string("group", table, "name")?.ok_or_else(|| ConfigError::missing("group", table, "name", "string"))?;
+        let fade = match number("group", table, "fade")? {
+            None => Duration::ZERO,
+            Some(v) if v >= 0.0 => Duration::from_secs_f64(v),
+            Some(_) => {
+                let line = table.entry("fade").map_or(table.line, |e| e.line);
+                return Err(ConfigError::new("`fade` must not be negative")
+                    .line(line)
+                    .field("group.fade")
+                    .suggest("use 0 to stop the other sounds right away"));
+            }
+        };
+
+        let range = |key: &str, range: std::ops::RangeInclusive<f64>, hint: &str| {
+            let Some(v) = number("group", table, key)? else { return Ok(None) };
+            if range.contains(&v) {
+                return Ok(Some(v));
+            }
+            Err(ConfigError::new(format!("`{key}` must be between {} and {}", range.start(), range.end()))
+                .line(table.entry(key).map_or(table.line, |e| e.line))
+                .field(format!("group.{key}"))
+                .expected("number")
+                .suggest(hint.to_string()))
+        };
+        let duck = range("duck", 0.0..=100.0, "12 lowers the group to about a quarter of its volume")?;
+        let release = range("release", 0.0..=60.0, "use the number of seconds, like 0.3")?;
+        let sidechain = match string("group", table, "ducked-by")? {
+            Some(by) if by == name => {
+                return Err(ConfigError::new(format!("group {name:?} can't be ducked by itself"))
+                    .line(table.entry("ducked-by").map_or(table.line, |e| e.line))
+                    .field("group.ducked-by")
+                    .suggest("name the group of the sounds it should make room for"));
+            }
+            Some(by) => Some(Sidechain {
+                by,
+                duck: duck.map_or(Sidechain::DUCK, |d| d as f32),
+                release: release.map_or(Sidechain::RELEASE, Duration::from_secs_f64),
+            }),
+            None => {
+                if let Some(key) = ["duck", "release"].into_iter().find(|key| table.entry(key).is_some()) {
+                    return Err(ConfigError::new(format!("`{key}` only goes with `ducked-by`"))
+                        .line(table.entry(key).map_or(table.line, |e| e.line))
+                        .field(format!("group.{key}"))
+                        .suggest("add `ducked-by` with the group that lowers this one"));
+                }
+                None
+            }
+        };
+        Ok(Self { name, fade, sidechain, bus: string("group", table, "bus")? })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("name", self.name.as_str());
+        table.insert("fade", self.fade.as_secs_f64());
+        if let Some(sidechain) = &self.sidechain {
+            table.insert("ducked-by", sidechain.by.as_str());
+            table.insert("duck", (sidechain.duck as f64 * 100.0).round() / 100.0);
+            table.insert("release", sidechain.release.as_secs_f64());
+        }
+        if let Some(bus) = &self.bus {
+            table.insert("bus", bus.as_str());
+        }
+        table
+    }
+}
+
+/// Settings for a bus that sounds play on, see [`crate::bus`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bus {
+    pub name: String,
+    /// 1.0 leaving the sounds on it as loud as they are
+    pub volume: f32,
+    pub muted: bool,
+    /// What is done to the sounds on it, in order
+    pub effects: Vec<Effect>,
+    /// How much of the effects is heard, from 0 for the sounds as they are to 1 for only what
+    /// the effects make of them
+    pub wet: f32,
+    /// Recordings get a file with only the sounds on this bus, see [`crate::record`]
+    pub stem: bool,
+}
+
+impl Bus {
+    /// The bus every sound plays on, after the one it names.
+    pub const MASTER: &'static str = "master";
+
+    fn from_table(table: &Table) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("bus", table, &["name", "volume", "mute", "effects", "wet", "stem"])?;
+
+        let name = string("bus", table, "name")?.ok_or_else(|| ConfigError::missing("bus", table, "name", "string"))?;
+        let volume = match number("bus", table, "volume")? {
+            None => 1.0,
+            Some(v) if (0.0..=MAX_VOLUME as f64).contains(&v) => v as f32,
+            Some(_) => {
+                return Err(ConfigError::new(format!("`volume` must be between 0.0 and {MAX_VOLUME:.1}"))
+                    .line(table.entry("volume").map_or(table.line, |e| e.line))
+                    .field("bus.volume")
+                    .expected("number")
+                    .suggest("use 1.0 to leave the sounds on it as they are, 0.5 for half"));
+            }
+        };
+        let flag = |key: &str| match table.entry(key) {
+            None => Ok(false),
+            Some(e) => match e.value {
+                Value::Boolean(on) => Ok(on),
+                _ => Err(ConfigError::wrong_type("bus", e, "boolean")),
+            },
+        };
+        let muted = flag("mute")?;
+        let stem = flag("stem")?;
+        if stem && name == Self::MASTER {
+            return Err(ConfigError::new("the master bus has everything on it, which the recording is already")
+                .line(table.entry("stem").map_or(table.line, |e| e.line))
+                .field("bus.stem")
+                .suggest("take out `stem = true`, or put it on the buses to get a file of"));
+        }
+        let effects = strings("bus", table, "effects")?
+            .into_iter()
+            .map(|(effect, line)| {
+                Effect::parse(&effect).ok_or_else(|| {
+                    ConfigError::new(format!("there is no effect like {effect:?}"))
+                        .line(line)
+                        .field("bus.effects")
+                        .suggest(format!("use \"high-pass 120\" or \"low-pass 8000\", with a frequency between {} and {} Hz", Effect::LOWEST, Effect::HIGHEST))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let wet = match number("bus", table, "wet")? {
+            None => 1.0,
+            Some(v) if (0.0..=1.0).contains(&v) => v as f32,
+            Some(_) => {
+                return Err(ConfigError::new("`wet` must be between 0.0 and 1.0")
+                    .line(table.entry("wet").map_or(table.line, |e| e.line))
+                    .field("bus.wet")
+                    .expected("number")
+                    .suggest("use 1.0 for only the effects, 0.5 for half of them and half of the sounds as they are"));
+            }
+        };
+        Ok(Self { name, volume, muted, effects, wet, stem })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("name", self.name.as_str());
+        if self.volume != 1.0 {
+            table.insert("volume", (self.volume as f64 * 100.0).round() / 100.0);
+        }
+        if self.muted {
+            table.insert("mute", true);
+        }
+        if !self.effects.is_empty() {
+            table.insert("effects", self.effects.iter().map(Effect::to_string).collect::<Vec<_>>());
+        }
+        if self.wet != 1.0 {
+            table.insert("wet", (self.wet as f64 * 100.0).round() / 100.0);
+        }
+        if self.stem {
+            table.insert("stem", true);
+        }
+        table
+    }
+}
+
+/// Something a bus does to the sounds on it, see [`crate::bus::Effects`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// Takes out what is lower than this many Hz, like rumble
+    HighPass(f32),
+    /// Takes out what is higher than this many Hz, like hiss, or to the sound of a telephone
+    LowPass(f32),
+}
+
+impl Effect {
+    const LOWEST: f32 = 10.0;
+    const HIGHEST: f32 = 20_000.0;
+
+    /// Like `high-pass 120`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (kind, frequency) = s.trim().split_once(char::is_whitespace)?;
+        let frequency = frequency.trim().trim_end_matches("Hz").trim_end().parse::<f32>().ok().filter(|f| (Self::LOWEST..=Self::HIGHEST).contains(f))?;
+        match kind {
+            "high-pass" => Some(Self::HighPass(frequency)),
+            "low-pass" => Some(Self::LowPass(frequency)),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Effect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HighPass(frequency) => write!(f, "high-pass {frequency}"),
+            Self::LowPass(frequency) => write!(f, "low-pass {frequency}"),
+        }
+    }
+}
+
+/// A config that [`Board::load`] upgraded from an older version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migrated {
+    pub path: PathBuf,
+    pub from: i64,
+    /// Where the config as it was is kept
+    pub backup: PathBuf,
+}
+
+impl std::fmt::Display for Migrated {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "upgraded {} from config version {} to {CURRENT_VERSION} (backup at {})", self.path.display(), self.from, self.backup.display())
+    }
+}
+
+/// Plays a sound when an input gets loud, like applause on a clap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelRule {
+    pub sound: String,
+    /// Peak level in dBFS that fires the rule
+    pub above: f32,
+    /// How long after firing the rule stays quiet, so one clap doesn't fire it for every block
+    pub cooldown: Duration,
+    /// The microphone, the system default input if unset
+    pub input: Option<String>,
+}
+
+/// Something the board serves over the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    /// Like `127.0.0.1:9898`
+    pub listen: String,
+    /// Bearer tokens that are let in, one per client; without any everyone is
+    pub tokens: Vec<String>,
+}
+
+impl Endpoint {
+    fn from_table(section: &str, table: &Table) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown(section, table, &["listen", "tokens"])?;
+
+        let listen = string(section, table, "listen")?.ok_or_else(|| ConfigError::missing(section, table, "listen", "string"))?;
+        let tokens = strings(section, table, "tokens")?;
+        if let Some((_, line)) = tokens.iter().find(|(t, _)| t.trim().is_empty()) {
+            return Err(ConfigError::new("tokens must not be empty").line(*line).field(format!("{section}.tokens")));
+        }
+        Ok(Self { listen, tokens: tokens.into_iter().map(|(t, _)| t).collect() })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("listen", self.listen.as_str());
+        match self.tokens.as_slice() {
+            [] => {}
+            [token] => table.insert("tokens", token.as_str()),
+            tokens => table.insert("tokens", tokens.to_vec()),
+        }
+        table
+    }
+
+    /// Whether a request with this `Authorization` header is let in.
+    pub fn authorized(&self, authorization: Option<&str>) -> bool {
+        if self.tokens.is_empty() {
+            return true;
+        }
+        let Some(token) = authorization.and_then(|a| a.trim().strip_prefix("Bearer ")) else { return false };
+        // Compares every byte of every token, so timing doesn't tell how close a guess was
+        let matches = |t: &String| t.len() == token.len() && t.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0;
+        self.tokens.iter().fold(false, |ok, t| ok | matches(t))
+    }
+
+    /// Whether anyone who can reach the machine can use the endpoint.
+    pub fn open_to_network(&self) -> bool {
+        let host = self.listen.rsplit_once(':').map_or(self.listen.as_str(), |(host, _)| host);
+        let loopback = matches!(host, "localhost" | "[::1]") || host.starts_with("127.");
+        self.tokens.is_empty() && !loopback
+    }
+}
+
+/// An Art-Net node (or a whole network, when broadcasting) to send DMX to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtNet {
+    /// `host` or `host:port`, like `2.0.0.10` or a broadcast address like `2.255.255.255`
+    pub target: String,
+    /// From 0 to 32767, the net, subnet and universe together
+    pub universe: u16,
+}
+
+impl ArtNet {
+    fn from_table(table: &Table) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("artnet", table, &["target", "universe"])?;
+
+        let target = string("artnet", table, "target")?.ok_or_else(|| ConfigError::missing("artnet", table, "target", "string"))?;
+        let universe = match table.entry("universe") {
+            None => 0,
+            Some(e) => match e.value {
+                Value::Integer(n) if (0..=0x7fff).contains(&n) => n as u16,
+                Value::Integer(_) => {
+                    return Err(ConfigError::new("`universe` must be between 0 and 32767").line(e.line).field("artnet.universe"));
+                }
+                _ => return Err(ConfigError::wrong_type("artnet", e, "integer")),
+            },
+        };
+        Ok(Self { target, universe })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("target", self.target.as_str());
+        if self.universe != 0 {
+            table.insert("universe", self.universe as i64);
+        }
+        table
+    }
+}
+
+/// An MQTT broker to publish sound events to and take commands from, see [`crate::mqtt`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mqtt {
+    /// `host` or `host:port`
+    pub broker: String,
+    /// What every topic the board uses starts with
+    pub topic: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Mqtt {
+    fn from_table(table: &Table) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("mqtt", table, &["broker", "topic", "username", "password"])?;
+
+        let broker = string("mqtt", table, "broker")?.ok_or_else(|| ConfigError::missing("mqtt", table, "broker", "string"))?;
+        let topic = string("mqtt", table, "topic")?.unwrap_or_else(|| "soundboard".to_string());
+        if let Some(e) = table.entry("topic").filter(|_| topic.is_empty() || topic.contains(['+', '#'])) {
+            return Err(ConfigError::new("`topic` must be a plain topic name")
+                .line(e.line)
+                .field("mqtt.topic")
+                .suggest("leave out the `+` and `#` wildcards, like \"home/soundboard\""));
+        }
+        let username = string("mqtt", table, "username")?;
+        let password = string("mqtt", table, "password")?;
+        if let (None, Some(e)) = (&username, table.entry("password")) {
+            return Err(ConfigError::new("a password needs a username").line(e.line).field("mqtt.password"));
+        }
+        Ok(Self { broker, topic: topic.trim_end_matches('/').to_string(), username, password })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("broker", self.broker.as_str());
+        if self.topic != "soundboard" {
+            table.insert("topic", self.topic.as_str());
+        }
+        if let Some(username) = &self.username {
+            table.insert("username", username.as_str());
+        }
+        if let Some(password) = &self.password {
+            table.insert("password", password.as_str());
+        }
+        table
+    }
+}
+
+/// Where the board's folder is synced to, see [`crate::sync`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncSettings {
+    /// An rclone remote like `drive:soundboard`, a WebDAV URL, or a directory
+    pub remote: String,
+    /// To log in to a WebDAV remote with
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Set to sync while the board runs, this long after the last time, so several people can
+    /// work on the board at once
+    pub every: Option<Duration>,
+}
+
+impl SyncSettings {
+    fn from_table(table: &Table) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("sync", table, &["remote", "username", "password", "every"])?;
+
+        let remote = string("sync", table, "remote")?.ok_or_else(|| ConfigError::missing("sync", table, "remote", "string"))?;
+        let username = string("sync", table, "username")?;
+        let password = string("sync", table, "password")?;
+        if let (None, Some(e)) = (&username, table.entry("password")) {
+            return Err(ConfigError::new("a password needs a username").line(e.line).field("sync.password"));
+        }
+        let every = match number("sync", table, "every")? {
+            None => None,
+            Some(v) if v >= 1.0 => Some(Duration::from_secs_f64(v)),
+            Some(_) => {
+                let line = table.entry("every").map_or(table.line, |e| e.line);
+                return Err(ConfigError::new("`every` must be at least a second").line(line).field("sync.every").suggest("use the number of seconds, like 30"));
+            }
+        };
+        Ok(Self { remote, username, password, every })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("remote", self.remote.as_str());
+        if let Some(username) = &self.username {
+            table.insert("username", username.as_str());
+        }
+        if let Some(password) = &self.password {
+            table.insert("password", password.as_str());
+        }
+        if let Some(every) = self.every {
+            table.insert("every", every.as_secs_f64());
+        }
+        table
+    }
+}
+
+/// Converting sounds ahead of time, see [`crate::cache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheSettings {
+    /// Where converted sounds go, the user's cache directory if unset
+    pub dir: Option<PathBuf>,
+}
+
+/// Dimming the board when nobody has touched it for a while, so an always-on screen doesn't
+/// burn in and anyone walking by sees it is idle. Any input wakes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Idle {
+    /// How long without input until the board dims
+    pub after: Duration,
+    /// Whether looping sounds pause while it is dimmed, to play on from there once it wakes
+    pub pause_loops: bool,
+}
+
+impl Idle {
+    pub const DEFAULT_MINUTES: u64 = 10;
+}
+
+impl Default for Idle {
+    fn default() -> Self {
+        Self { after: Duration::from_secs(Self::DEFAULT_MINUTES * 60), pause_loops: false }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mdns {
+    /// What the board is listed as, the machine's name if unset
+    pub name: Option<String>,
+}
+
+/// What a signal does when the board gets it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignalAction {
+    Play(String),
+    /// Stops everything that is playing
+    Stop,
+    /// Toggles the master mute
+    Mute,
+    /// Plays the next cue
+    Go,
+}
+
+/// Runs an action when the process gets a Unix signal, so scripts can `kill -USR1` the board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignalRule {
+    /// Like `USR1` or `RTMIN+2`, see [`signal_name`]
+    pub signal: String,
+    pub action: SignalAction,
+}
+
+/// Runs an action when a foot pedal is pressed, see [`crate::pedal`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PedalRule {
+    /// The key the pedal sends, like `b` or `F13`
+    pub key: String,
+    /// Only pedals whose device name contains this; pedals the board recognizes if unset
+    pub device: Option<String>,
+    pub action: SignalAction,
+}
+
+/// Runs an action when sounds are played one after the other, quickly enough, see
+/// [`crate::combo`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Combo {
+    /// The names of the sounds, in the order they are played in
+    pub sequence: Vec<String>,
+    /// How long playing all of them may take
+    pub within: Duration,
+    pub action: SignalAction,
+}
+
+/// Blends between two buses, like a DJ's crossfader: all the way to one side only that bus
+/// plays, and in the middle both do, each 3 dB down.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crossfader {
+    pub left: String,
+    pub right: String,
+    /// Move it a `step` to the left and to the right
+    pub left_key: Option<Binding>,
+    pub right_key: Option<Binding>,
+    /// How far a key moves it, the whole way being 1
+    pub step: f32,
+}
+
+impl Crossfader {
+    pub const STEP: f32 = 0.1;
+
+    fn from_table(table: &Table, sounds: &[Sound]) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("crossfader", table, &["left", "right", "left-key", "right-key", "step"])?;
+
+        let bus = |key: &str| string("crossfader", table, key)?.ok_or_else(|| ConfigError::missing("crossfader", table, key, "bus name"));
+        let (left, right) = (bus("left")?, bus("right")?);
+        if left == right {
+            return Err(ConfigError::new(format!("the crossfader goes from {left:?} to itself"))
+                .line(table.entry("right").map_or(table.line, |e| e.line))
+                .field("crossfader.right")
+                .suggest("name the two buses to blend between, like \"music\" and \"voice\""));
+        }
+        let left_key = unbound_key("crossfader", table, "left-key", sounds)?;
+        let right_key = unbound_key("crossfader", table, "right-key", sounds)?;
+        if left_key.is_some() && left_key.as_ref().map(|k| &k.trigger) == right_key.as_ref().map(|k| &k.trigger) {
+            return Err(ConfigError::new("`left-key` and `right-key` are the same key")
+                .line(table.entry("right-key").map_or(table.line, |e| e.line))
+                .field("crossfader.right-key"));
+        }
+        let step = match number("crossfader", table, "step")? {
+            None => Self::STEP,
+            Some(v) if v > 0.0 && v <= 1.0 => v as f32,
+            Some(_) => {
+                return Err(ConfigError::new("`step` must be more than 0 and at most 1")
+                    .line(table.entry("step").map_or(table.line, |e| e.line))
+                    .field("crossfader.step")
+                    .suggest("0.1 takes ten presses to go from one side to the other"));
+            }
+        };
+        Ok(Self { left, right, left_key, right_key, step })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("left", self.left.as_str());
+        table.insert("right", self.right.as_str());
+        if let Some(key) = &self.left_key {
+            table.insert("left-key", key.label.as_str());
+        }
+        if let Some(key) = &self.right_key {
+            table.insert("right-key", key.label.as_str());
+        }
+        if self.step != Self::STEP {
+            table.insert("step", (self.step as f64 * 100.0).round() / 100.0);
+        }
+        table
+    }
+}
+
+/// A knob or fader of a MIDI controller, see [`crate::midi`], moving something on the board
+/// from nothing at the bottom to all the way at the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fader {
+    /// The controller number it sends, from 0 to 127
+    pub cc: u8,
+    /// Only on this channel, from 1 to 16; on any if unset
+    pub channel: Option<u8>,
+    pub control: Continuous,
+}
+
+/// What a [`Fader`] moves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Continuous {
+    /// A bus's volume, up to leaving the sounds on it as loud as they are
+    Volume(String),
+    /// How much of a bus's effects is heard
+    Wet(String),
+    Crossfader,
+}
+
+impl Fader {
+    /// Whether the controller `cc` on `channel` moves it.
+    pub fn moved_by(&self, channel: u8, cc: u8) -> bool {
+        self.cc == cc && self.channel.is_none_or(|c| c == channel)
+    }
+
+    fn from_table(table: &Table) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("fader", table, &["cc", "channel", "volume", "wet", "crossfader"])?;
+
+        let integer = |key: &str, range: std::ops::RangeInclusive<i64>| match table.entry(key) {
+            None => Ok(None),
+            Some(e) => match e.value {
+                Value::Integer(n) if range.contains(&n) => Ok(Some(n as u8)),
+                Value::Integer(_) => Err(ConfigError::new(format!("`{key}` must be between {} and {}", range.start(), range.end())).line(e.line).field(format!("fader.{key}"))),
+                _ => Err(ConfigError::wrong_type("fader", e, "integer")),
+            },
+        };
+        let cc = integer("cc", 0..=127)?.ok_or_else(|| ConfigError::missing("fader", table, "cc", "integer").suggest("moving the fader while ^N is open fills it in"))?;
+        let channel = integer("channel", 1..=16)?;
+        let control = match (string("fader", table, "volume")?, string("fader", table, "wet")?, table.entry("crossfader")) {
+            (Some(bus), None, None) => Continuous::Volume(bus),
+            (None, Some(bus), None) => Continuous::Wet(bus),
+            (None, None, Some(e)) => match e.value {
+                Value::Boolean(true) => Continuous::Crossfader,
+                Value::Boolean(false) => return Err(ConfigError::new("a fader has to move something").line(e.line).field("fader.crossfader").suggest("take out the fader, or set `crossfader = true`")),
+                _ => return Err(ConfigError::wrong_type("fader", e, "boolean")),
+            },
+            (None, None, None) => {
+                return Err(ConfigError::new("a fader has to move something")
+                    .line(table.line)
+                    .field("fader")
+                    .suggest("set `volume` or `wet` to a bus's name, or `crossfader = true`"));
+            }
+            _ => {
+                return Err(ConfigError::new("a fader can only move one thing")
+                    .line(table.line)
+                    .field("fader")
+                    .suggest("use a `[[fader]]` section for each, with a `cc` of its own"));
+            }
+        };
+        Ok(Self { cc, channel, control })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("cc", self.cc as i64);
+        if let Some(channel) = self.channel {
+            table.insert("channel", channel as i64);
+        }
+        match &self.control {
+            Continuous::Volume(bus) => table.insert("volume", bus.as_str()),
+            Continuous::Wet(bus) => table.insert("wet", bus.as_str()),
+            Continuous::Crossfader => table.insert("crossfader", true),
+        }
+        table
+    }
+}
+
+/// A state of the mixer to go back to with one key, like the intro, the main show or the outro
+/// of a stream. Recalling it sets the volume and mute of its buses, starts its loops and stops
+/// the loops that aren't in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub name: String,
+    /// Recalls the scene, and saves what the mixer is at into it with alt held
+    pub key: Option<Binding>,
+    pub buses: Vec<BusLevel>,
+    /// The names of the loops that play
+    pub loops: Vec<String>,
+}
+
+/// A bus's volume and mute in a [`Scene`], like `music 0.8` or `sfx 1 muted`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BusLevel {
+    pub bus: String,
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl BusLevel {
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let (rest, muted) = match s.strip_suffix("muted") {
+            Some(rest) if rest.ends_with(char::is_whitespace) => (rest.trim_end(), true),
+            _ => (s, false),
+        };
+        let (bus, volume) = rest.rsplit_once(char::is_whitespace)?;
+        let volume = volume.parse::<f32>().ok().filter(|v| (0.0..=MAX_VOLUME).contains(v))?;
+        Some(Self { bus: bus.trim_end().to_string(), volume, muted })
+    }
+}
+
+impl std::fmt::Display for BusLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.bus, (self.volume * 100.0).round() / 100.0)?;
+        if self.muted {
+            write!(f, " muted")?;
+        }
+        Ok(())
+    }
+}
+
+/// The key at `key`, which no sound may be bound to.
+fn unbound_key(section: &str, table: &Table, key: &str, sounds: &[Sound]) -> Result<Option<Binding>, ConfigError> {
+    let Some(chord) = string(section, table, key)? else { return Ok(None) };
+    let line = table.entry(key).map_or(table.line, |e| e.line);
+    let binding = Binding::parse_key(&chord).ok_or_else(|| {
+        ConfigError::new(format!("unknown key {chord:?}"))
+            .line(line)
+            .field(format!("{section}.{key}"))
+            .expected("a key, optionally with modifiers")
+            .suggest("use a name like \"F5\", or a chord like \"ctrl+1\"")
+    })?;
+    if let Some(sound) = sounds.iter().find(|s| s.bindings.iter().any(|b| b.trigger == binding.trigger)) {
+        return Err(ConfigError::new(format!("{chord:?} already plays {:?}", sound.name))
+            .line(line)
+            .field(format!("{section}.{key}"))
+            .suggest("use a key no sound is bound to"));
+    }
+    Ok(Some(binding))
+}
+
+impl Scene {
+    fn from_table(table: &Table, sounds: &[Sound], trash: &[Sound]) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("scene", table, &["name", "key", "buses", "loops"])?;
+
+        let name = string("scene", table, "name")?.ok_or_else(|| ConfigError::missing("scene", table, "name", "string"))?;
+        let key = unbound_key("scene", table, "key", sounds)?;
+        let buses = strings("scene", table, "buses")?
+            .into_iter()
+            .map(|(level, line)| {
+                BusLevel::parse(&level).ok_or_else(|| {
+                    ConfigError::new(format!("{level:?} is not a bus with its volume"))
+                        .line(line)
+                        .field("scene.buses")
+                        .suggest(format!("use the bus's name and a volume from 0 to {MAX_VOLUME:.1}, like \"music 0.8\", with \"muted\" after it to mute it"))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let mut loops = Vec::new();
+        for (name, line) in strings("scene", table, "loops")? {
+            if !sounds.iter().chain(trash).any(|s| s.name == name) {
+                let err = ConfigError::new(format!("there is no sound named {name:?}")).line(line).field("scene.loops");
+                return Err(match error::closest(&name, sounds.iter().map(|s| s.name.as_str())) {
+                    Some(c) => err.suggest(format!("did you mean {c:?}?")),
+                    None => err.suggest("use the `name` of one of the `[[sound]]` sections"),
+                });
+            }
+            loops.push(name);
+        }
+        Ok(Self { name, key, buses, loops })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("name", self.name.as_str());
+        if let Some(key) = &self.key {
+            table.insert("key", key.label.as_str());
+        }
+        table.insert("buses", self.buses.iter().map(BusLevel::to_string).collect::<Vec<_>>());
+        table.insert("loops", self.loops.clone());
+        table
+    }
+}
+
+/// A timer that counts down from its `duration` once its key is pressed, in the status bar, and
+/// plays its sound and recalls its scene when it runs out, see [`crate::timer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Countdown {
+    pub name: String,
+    /// Starts it, and stops it while it runs
+    pub key: Option<Binding>,
+    pub duration: Duration,
+    pub sound: Option<String>,
+    pub scene: Option<String>,
+}
+
+impl Countdown {
+    fn from_table(table: &Table, sounds: &[Sound], trash: &[Sound], scenes: &[Scene]) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("countdown", table, &["name", "key", "duration", "sound", "scene"])?;
+
+        let name = string("countdown", table, "name")?.ok_or_else(|| ConfigError::missing("countdown", table, "name", "string"))?;
+        let key = unbound_key("countdown", table, "key", sounds)?;
+        let duration = match number("countdown", table, "duration")? {
+            Some(secs) if secs > 0.0 && secs < 100.0 * 3600.0 => Duration::from_secs_f64(secs),
+            Some(_) => {
+                return Err(ConfigError::new("`duration` must be more than 0 seconds and less than 100 hours")
+                    .line(table.entry("duration").map_or(table.line, |e| e.line))
+                    .field("countdown.duration")
+                    .suggest("it is in seconds, so 300 for five minutes"));
+            }
+            None => return Err(ConfigError::missing("countdown", table, "duration", "number").suggest("it is in seconds, so 300 for five minutes")),
+        };
+        let sound = match table.entry("sound") {
+            None => None,
+            Some(_) => Some(sound_ref("countdown", table, sounds, trash)?),
+        };
+        let scene = scene_ref("countdown", table, scenes)?;
+        Ok(Self { name, key, duration, sound, scene })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("name", self.name.as_str());
+        if let Some(key) = &self.key {
+            table.insert("key", key.label.as_str());
+        }
+        table.insert("duration", self.duration.as_secs_f64());
+        if let Some(sound) = &self.sound {
+            table.insert("sound", sound.as_str());
+        }
+        if let Some(scene) = &self.scene {
+            table.insert("scene", scene.as_str());
+        }
+        table
+    }
+}
+
+/// Recalls a scene or plays a sound by itself, at a time of day or once the board ran for a
+/// while, see [`crate::timer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub when: When,
+    /// The days of the week it goes off on with `at`, 0 for Monday, or every day when empty
+    pub days: Vec<u8>,
+    pub scene: Option<String>,
+    pub sound: Option<String>,
+    /// Where the sound plays instead of on its own bus
+    pub bus: Option<String>,
+}
+
+/// The days of the week as `days` names them, from Monday.
+const DAYS: [&str; 7] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum When {
+    /// Every day at this many seconds past midnight, in local time
+    At(u32),
+    /// Once, this long after the board started
+    After(Duration),
+}
+
+impl Schedule {
+    fn from_table(table: &Table, sounds: &[Sound], trash: &[Sound], scenes: &[Scene]) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("schedule", table, &["at", "after", "days", "scene", "sound", "bus"])?;
+
+        let line = |key: &str| table.entry(key).map_or(table.line, |e| e.line);
+        let at = match string("schedule", table, "at")? {
+            None => None,
+            Some(at) => Some(time_of_day(&at).ok_or_else(|| {
+                ConfigError::new(format!("{at:?} is not a time of day")).line(line("at")).field("schedule.at").expected("a time like \"20:30\" or \"20:30:15\"")
+            })?),
+        };
+        let after = match number("schedule", table, "after")? {
+            None => None,
+            Some(secs) if secs > 0.0 && secs < 100.0 * 3600.0 => Some(Duration::from_secs_f64(secs)),
+            Some(_) => {
+                return Err(ConfigError::new("`after` must be more than 0 seconds and less than 100 hours")
+                    .line(line("after"))
+                    .field("schedule.after")
+                    .suggest("it is in seconds since the board started, so 1800 for half an hour"));
+            }
+        };
+        let when = match (at, after) {
+            (Some(at), None) => When::At(at),
+            (None, Some(after)) => When::After(after),
+            (Some(_), Some(_)) => return Err(ConfigError::new("a schedule has either `at` or `after`, not both").line(line("after")).field("schedule.after")),
+            (None, None) => return Err(ConfigError::missing("schedule", table, "at", "string").suggest("give it `at = \"20:30\"`, or `after` a number of seconds")),
+        };
+        let mut days = Vec::new();
+        for (day, line) in strings("schedule", table, "days")? {
+            let err = || ConfigError::new(format!("{day:?} is not a day of the week")).line(line).field("schedule.days");
+            let day = day.to_lowercase();
+            match day.as_str() {
+                "weekdays" => days.extend(0..5),
+                "weekend" => days.extend(5..7),
+                _ if day.len() < 3 => return Err(err().suggest("write it out, like \"friday\", or as \"fri\"")),
+                _ => days.push(DAYS.iter().position(|d| d.starts_with(&day)).ok_or_else(|| err().expected("a day like \"friday\", \"weekdays\" or \"weekend\""))? as u8),
+            }
+        }
+        days.sort_unstable();
+        days.dedup();
+        if !days.is_empty() && matches!(when, When::After(_)) {
+            return Err(ConfigError::new("`days` only go with `at`").line(line("days")).field("schedule.days").suggest("a schedule with `after` goes off once, the day the board starts"));
+        }
+        let scene = scene_ref("schedule", table, scenes)?;
+        let sound = match table.entry("sound") {
+            None => None,
+            Some(_) => Some(sound_ref("schedule", table, sounds, trash)?),
+        };
+        let bus = string("schedule", table, "bus")?;
+        if bus.is_some() && sound.is_none() {
+            return Err(ConfigError::new("`bus` is where the schedule's `sound` plays, and it has none").line(line("bus")).field("schedule.bus"));
+        }
+        if scene.is_none() && sound.is_none() {
+            return Err(ConfigError::missing("schedule", table, "sound", "string").suggest("give it a `sound` to play, a `scene` to recall, or both"));
+        }
+        Ok(Self { when, days, scene, sound, bus })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        match self.when {
+            When::At(secs) => {
+                let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+                table.insert("at", if s == 0 { format!("{h:02}:{m:02}") } else { format!("{h:02}:{m:02}:{s:02}") });
+            }
+            When::After(after) => table.insert("after", after.as_secs_f64()),
+        }
+        if !self.days.is_empty() {
+            table.insert("days", self.days.iter().map(|&d| DAYS[usize::from(d)].to_string()).collect::<Vec<_>>());
+        }
+        if let Some(scene) = &self.scene {
+            table.insert("scene", scene.as_str());
+        }
+        if let Some(sound) = &self.sound {
+            table.insert("sound", sound.as_str());
+        }
+        if let Some(bus) = &self.bus {
+            table.insert("bus", bus.as_str());
+        }
+        table
+    }
+}
+
+/// The seconds past midnight of a time like `20:30` or `20:30:15`.
+fn time_of_day(s: &str) -> Option<u32> {
+    // Only the hour may leave out its leading zero
+    let parts: Vec<u32> = s.trim().split(':').enumerate().map(|(i, p)| if p.len() == 2 || (i == 0 && p.len() == 1) { p.parse().ok() } else { None }).collect::<Option<_>>()?;
+    match parts[..] {
+        [h, m] if h < 24 && m < 60 => Some(h * 3600 + m * 60),
+        [h, m, s] if h < 24 && m < 60 && s < 60 => Some(h * 3600 + m * 60 + s),
+        _ => None,
+    }
+}
+
+/// The scene at `scene`, which must be one of the `[[scene]]`s.
+fn scene_ref(section: &str, table: &Table, scenes: &[Scene]) -> Result<Option<String>, ConfigError> {
+    let Some(name) = string(section, table, "scene")? else { return Ok(None) };
+    if scenes.iter().any(|s| s.name == name) {
+        return Ok(Some(name));
+    }
+    let err = ConfigError::new(format!("there is no scene named {name:?}")).line(table.entry("scene").map_or(table.line, |e| e.line)).field(format!("{section}.scene"));
+    Err(match error::closest(&name, scenes.iter().map(|s| s.name.as_str())) {
+        Some(c) => err.suggest(format!("did you mean {c:?}?")),
+        None => err.suggest("use the `name` of one of the `[[scene]]` sections"),
+    })
+}
+
+/// A step in a scripted show, played in order with GO rather than by its key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    /// The name of the sound to play
+    pub sound: String,
+    /// Shown alongside the cue, like the line that sets it off
+    pub note: Option<String>,
+}
+
+/// How the board's tiles are arranged on screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TileLayout {
+    /// As many columns as fit, in board order
+    #[default]
+    Grid,
+    /// Mirroring a numeric keypad, each sound bound to a keypad key in that key's place
+    Numpad,
+}
+
+impl TileLayout {
+    pub const ALL: [TileLayout; 2] = [TileLayout::Grid, TileLayout::Numpad];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TileLayout::Grid => "grid",
+            TileLayout::Numpad => "numpad",
+        }
+    }
+}
+
+/// What tiles do with names too long for them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LongNames {
+    /// Cut off with an ellipsis
+    #[default]
+    Truncate,
+    /// On two lines, and cut off after that
+    Wrap,
+    /// Cut off, except on the selected tile, where the name scrolls by
+    Scroll,
+}
+
+impl LongNames {
+    pub const ALL: [LongNames; 3] = [LongNames::Truncate, LongNames::Wrap, LongNames::Scroll];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LongNames::Truncate => "truncate",
+            LongNames::Wrap => "wrap",
+            LongNames::Scroll => "scroll",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Board {
+    pub sounds: Vec<Sound>,
+    /// Sounds removed from the board. They stay here, restorable, until purged.
+    pub trash: Vec<Sound>,
+    pub outputs: Outputs,
+    /// How many megabytes decoded sounds may take; past that the ones played longest ago are
+    /// dropped and decoded while they play instead. No limit when unset.
+    pub memory: Option<u64>,
+    /// How sounds are brought to the output's sample rate, see [`crate::resample`]
+    pub resampler: Resampler,
+    pub cues: Vec<Cue>,
+    pub talkover: Option<Talkover>,
+    pub levels: Vec<LevelRule>,
+    /// The speech recognizer to run for sounds with phrases, see [`crate::voice`]
+    pub voice: Option<String>,
+    /// The one that names sounds added from files after what they say, see [`crate::names`]
+    pub transcribe: Option<String>,
+    /// Settings for groups; groups that sounds use without settings stop other sounds at once
+    pub groups: Vec<Group>,
+    /// Settings for buses; buses that sounds use without settings play them as they are
+    pub buses: Vec<Bus>,
+    pub scenes: Vec<Scene>,
+    pub crossfader: Option<Crossfader>,
+    pub faders: Vec<Fader>,
+    /// Lights up the pads of MIDI controllers that sounds are bound to, see [`crate::midi`]
+    pub midi_feedback: bool,
+    pub signals: Vec<SignalRule>,
+    pub pedals: Vec<PedalRule>,
+    pub combos: Vec<Combo>,
+    /// A named pipe that plays the sounds whose names are written to it, see [`crate::fifo`]
+    pub fifo: Option<PathBuf>,
+    /// Where to serve Prometheus metrics
+    pub metrics: Option<Endpoint>,
+    /// Advertises the board's endpoint on the local network, see [`crate::mdns`]
+    pub mdns: Option<Mdns>,
+    /// Where sounds' `dmx` channels are sent
+    pub artnet: Option<ArtNet>,
+    pub mqtt: Option<Mqtt>,
+    /// The API key to search freesound.org with, see [`crate::freesound`]
+    pub freesound: Option<String>,
+    /// Where the index of sound packs to install is, see [`crate::packs`]
+    pub packs: Option<String>,
+    pub sync: Option<SyncSettings>,
+    /// Set to convert every sound for faster loading when the board starts
+    pub cache: Option<CacheSettings>,
+    /// Dims the board after a while without input
+    pub idle: Option<Idle>,
+    pub layout: TileLayout,
+    pub long_names: LongNames,
+    /// How many frames a second to draw while something on screen moves, see
+    /// [`crate::ui::Redraw`]; [`crate::ui::DEFAULT_FPS`] when unset
+    pub fps: Option<u32>,
+    /// The language the board is shown in, the environment's when unset
+    pub locale: Option<Locale>,
+    /// The colors the board is drawn in, the environment's when unset
+    pub palette: Option<Palette>,
+    /// Draws the board for screen readers, see [`crate::reader`]
+    pub screen_reader: bool,
+    /// Shows the time and the show timer in the status bar, see [`crate::timer`]
+    pub clock: bool,
+    pub countdowns: Vec<Countdown>,
+    pub schedules: Vec<Schedule>,
+    /// ASCII art to draw above the tiles, see [`crate::branding`]
+    pub header: Option<PathBuf>,
+    /// ASCII art to show when the board starts
+    pub splash: Option<PathBuf>,
+    /// Keeps what the board makes in the config's folder too, to take the folder to other
+    /// machines, see [`crate::paths`]
+    pub portable: bool,
+}
+
+impl Board {
+    pub fn builtin() -> Self {
+        let sounds = DEFAULT_KEYS
+            .iter()
+            .map(|(key, name)| Sound {
+                name: name.to_string(),
+                bindings: vec![Binding::key(KeyChord::new(KeyCode::Char(*key)))],
+                label: None,
+                source: Source::Builtin(name.to_string()),
+                variants: Vec::new(),
+                weights: Vec::new(),
+                checksums: Vec::new(),
+                order: Order::Random,
+                volume: 1.0,
+                speakers: Vec::new(),
+                plays: 0,
+                group: None,
+                bus: None,
+                dmx: Vec::new(),
+                webhooks: Vec::new(),
+                shape: Shape::default(),
+                color: None,
+                tags: Vec::new(),
+                then: Vec::new(),
+            })
+            .collect();
+
+        Self { sounds, trash: Vec::new(), outputs: Outputs::default(), memory: None, resampler: Resampler::Linear, cues: Vec::new(), talkover: None, levels: Vec::new(), voice: None, transcribe: None, groups: Vec::new(), buses: Vec::new(), scenes: Vec::new(), crossfader: None, faders: Vec::new(), midi_feedback: false, signals: Vec::new(), pedals: Vec::new(), combos: Vec::new(), fifo: None, metrics: None, mdns: None, artnet: None, mqtt: None, freesound: None, packs: None, sync: None, cache: None, idle: None, layout: TileLayout::Grid, long_names: LongNames::Truncate, fps: None, locale: None, palette: None, screen_reader: false, clock: false, countdowns: Vec::new(), schedules: Vec::new(), header: None, splash: None, portable: false }
+    }
+
+    /// Loads the board at `path`, falling back to the builtin board if there is no config yet.
+    /// Relative file paths in it are found from the config's folder. An older config is upgraded
+    /// in place, which is returned too, for the caller to tell wherever it can.
+    pub fn load(path: &Path) -> color_eyre::Result<(Self, Option<Migrated>)> {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok((Self::builtin(), None)),
+            Err(e) => return Err(e).wrap_err_with(|| format!("read {}", path.display())),
+        };
+
+        let (mut board, from) = Self::parse(&src).map_err(|e| e.in_file(path))?;
+        // Before version 2 they were found from the working directory, which they already are
+        if from >= 2 {
+            let dir = path.parent().unwrap_or(Path::new(""));
+            let portable = board.portable;
+            for file in board.files_mut() {
+                // Written on Windows, and read anywhere
+                if portable && !cfg!(windows) && file.is_relative() {
+                    *file = PathBuf::from(file.to_string_lossy().replace('\\', "/"));
+                }
+                *file = dir.join(&*file);
+            }
+        }
+
+        if from == CURRENT_VERSION {
+            return Ok((board, None));
+        }
+        let backup = PathBuf::from(format!("{}.v{from}.bak", path.display()));
+        backup::replace(&backup, &src)?;
+        board.save(path)?;
+        Ok((board, Some(Migrated { path: path.to_path_buf(), from, backup })))
+    }
+
+    /// The paths of the files the board plays, and of the cache and the named pipe.
+    fn files_mut(&mut self) -> impl Iterator<Item = &mut PathBuf> {
+        let sounds = self.sounds.iter_mut().chain(&mut self.trash).flat_map(|s| std::iter::once(&mut s.source).chain(&mut s.variants));
+        let sounds = sounds.filter_map(|source| if let Source::File(path) = source { Some(path) } else { None });
+        sounds.chain(self.cache.as_mut().and_then(|c| c.dir.as_mut())).chain(&mut self.fifo)
+    }
+
+    pub fn triggers(&self) -> TriggerMap {
+        TriggerMap::new(self.sounds.iter().enumerate().map(|(idx, s)| (idx, s.bindings.as_slice())))
+    }
+
+    /// Saves the board to `path`, with the files in the config's folder written down relative to
+    /// it, so the folder can be moved, and the others in full.
+    pub fn save(&self, path: &Path) -> color_eyre::Result<()> {
+        let mut board = self.clone();
+        let dir = std::path::absolute(path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new(".")))?;
+        for file in board.files_mut() {
+            let Ok(full) = std::path::absolute(&*file) else { continue };
+            *file = match full.strip_prefix(&dir) {
+                // With forward slashes, which every platform reads
+                Ok(inside) => PathBuf::from(inside.iter().map(|part| part.to_string_lossy()).collect::<Vec<_>>().join("/")),
+                Err(_) => full,
+            };
+        }
+        backup::write(path, &toml::to_string(&board.to_table()))
+    }
+
+    /// Parses and migrates a config, returning the board and the version the source was in.
+    pub fn parse(src: &str) -> Result<(Self, i64), ConfigError> {
+        let mut table = toml::parse(src)?;
+        let from = migrate::migrate(&mut table)?;
+        Ok((Self::from_table(&table)?, from))
+    }
+
+    pub fn from_table(table: &Table) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("the config root", table, &["version", "sound", "trash", "audio", "talkover", "voice", "group", "bus", "scene", "crossfader", "fader", "midi", "countdown", "schedule", "cue", "level", "signal", "pedal", "combo", "fifo", "metrics", "mdns", "artnet", "mqtt", "freesound", "packs", "sync", "cache", "idle", "ui", "portable"])?;
+
+        let (outputs, memory, resampler) = match table.entry("audio") {
+            None => (Outputs::default(), None, Resampler::default()),
+            Some(e) => match &e.value {
+                Value::Table(audio) => {
+                    ConfigError::check_unknown("audio", audio, &["monitor", "outputs", "mono", "memory", "resampler"])?;
+                    let monitor = string("audio", audio, "monitor")?;
+                    let external: Vec<_> = strings("audio", audio, "outputs")?.into_iter().map(|(name, _)| name).collect();
+                    let mut mono = Vec::new();
+                    for (name, line) in strings("audio", audio, "mono")? {
+                        let named: Vec<_> = monitor.iter().chain(&external).map(String::as_str).collect();
+                        if !named.contains(&name.as_str()) {
+                            let err = ConfigError::new(format!("{name:?} is not one of the outputs")).line(line).field("audio.mono");
+                            return Err(match error::closest(&name, named.iter().copied()) {
+                                Some(c) => err.suggest(format!("did you mean {c:?}?")),
+                                None => err.suggest("name the `monitor` or one of the `outputs`"),
+                            });
+                        }
+                        mono.push(name);
+                    }
+                    let outputs = Outputs { monitor, external, mono };
+                    let memory = match audio.entry("memory") {
+                        None => None,
+                        Some(e) => match e.value {
+                            Value::Integer(n) if n > 0 => Some(n as u64),
+                            Value::Integer(_) => {
+                                return Err(ConfigError::new("`memory` must be at least 1")
+                                    .line(e.line)
+                                    .field("audio.memory")
+                                    .suggest("leave it out to keep every sound in memory"));
+                            }
+                            _ => return Err(ConfigError::wrong_type("audio", e, "integer")),
+                        },
+                    };
+                    let resampler = match string("audio", audio, "resampler")? {
+                        None => Resampler::default(),
+                        Some(name) => Resampler::parse(&name).ok_or_else(|| {
+                            let line = audio.entry("resampler").map_or(audio.line, |e| e.line);
+                            ConfigError::new(format!("there is no resampler named {name:?}"))
+                                .line(line)
+                                .field("audio.resampler")
+                                .suggest("use \"linear\" to save CPU or \"sinc\" for quality")
+                        })?,
+                    };
+                    (outputs, memory, resampler)
+                }
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as an `[audio]` section")),
+            },
+        };
+
+        let sounds = sounds_from(table, "sound")?;
+        let trash = sounds_from(table, "trash")?;
+        let cues = sections(table, "cue")?.into_iter().map(|t| Cue::from_table(t, &sounds, &trash)).collect::<Result<_, _>>()?;
+
+        let talkover = match table.entry("talkover") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(t) => Some(Talkover::from_table(t)?),
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as a `[talkover]` section")),
+            },
+        };
+
+        let levels = sections(table, "level")?.into_iter().map(|t| LevelRule::from_table(t, &sounds, &trash)).collect::<Result<_, _>>()?;
+
+        let (voice, transcribe) = match table.entry("voice") {
+            None => (None, None),
+            Some(e) => match &e.value {
+                Value::Table(voice) => {
+                    ConfigError::check_unknown("voice", voice, &["command", "transcribe"])?;
+                    match (string("voice", voice, "command")?, string("voice", voice, "transcribe")?) {
+                        (None, None) => return Err(ConfigError::missing("voice", voice, "command", "string")),
+                        both => both,
+                    }
+                }
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as a `[voice]` section")),
+            },
+        };
+
+        let fifo = match table.entry("fifo") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(fifo) => {
+                    ConfigError::check_unknown("fifo", fifo, &["path"])?;
+                    Some(string("fifo", fifo, "path")?.ok_or_else(|| ConfigError::missing("fifo", fifo, "path", "string"))?.into())
+                }
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as a `[fifo]` section")),
+            },
+        };
+
+        let metrics = match table.entry("metrics") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(metrics) => Some(Endpoint::from_table("metrics", metrics)?),
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as a `[metrics]` section")),
+            },
+        };
+
+        let mdns = match table.entry("mdns") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(mdns) => {
+                    ConfigError::check_unknown("mdns", mdns, &["name"])?;
+                    if metrics.is_none() {
+                        return Err(ConfigError::new("mDNS advertises the board's endpoint, but there is none")
+                            .line(e.line)
+                            .field("mdns")
+                            .suggest("add a `[metrics]` section with `listen` to serve one"));
+                    }
+                    Some(Mdns { name: string("mdns", mdns, "name")? })
+                }
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as an `[mdns]` section")),
+            },
+        };
+
+        let artnet = match table.entry("artnet") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(t) => Some(ArtNet::from_table(t)?),
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as an `[artnet]` section")),
+            },
+        };
+
+        let mqtt = match table.entry("mqtt") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(t) => Some(Mqtt::from_table(t)?),
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as an `[mqtt]` section")),
+            },
+        };
+
+        let freesound = match table.entry("freesound") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(freesound) => {
+                    ConfigError::check_unknown("freesound", freesound, &["key"])?;
+                    Some(string("freesound", freesound, "key")?.ok_or_else(|| ConfigError::missing("freesound", freesound, "key", "string"))?)
+                }
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as a `[freesound]` section")),
+            },
+        };
+        let packs = match table.entry("packs") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(packs) => {
+                    ConfigError::check_unknown("packs", packs, &["index"])?;
+                    Some(string("packs", packs, "index")?.ok_or_else(|| ConfigError::missing("packs", packs, "index", "string"))?)
+                }
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as a `[packs]` section")),
+            },
+        };
+        let sync = match table.entry("sync") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(t) => Some(SyncSettings::from_table(t)?),
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as a `[sync]` section")),
+            },
+        };
+
+        let cache = match table.entry("cache") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(cache) => {
+                    ConfigError::check_unknown("cache", cache, &["dir"])?;
+                    Some(CacheSettings { dir: string("cache", cache, "dir")?.map(PathBuf::from) })
+                }
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as a `[cache]` section")),
+            },
+        };
+
+        let idle = match table.entry("idle") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(idle) => {
+                    ConfigError::check_unknown("idle", idle, &["minutes", "pause-loops"])?;
+                    let minutes = match idle.entry("minutes") {
+                        None => Idle::DEFAULT_MINUTES,
+                        Some(m) => match m.value {
+                            Value::Integer(n) if (1..=1440).contains(&n) => n as u64,
+                            Value::Integer(_) => {
+                                return Err(ConfigError::new("`minutes` must be between 1 and 1440")
+                                    .line(m.line)
+                                    .field("idle.minutes")
+                                    .suggest("leave the `[idle]` section out to never dim the board"));
+                            }
+                            _ => return Err(ConfigError::wrong_type("idle", m, "integer")),
+                        },
+                    };
+                    let pause_loops = match idle.entry("pause-loops") {
+                        None => false,
+                        Some(p) => match p.value {
+                            Value::Boolean(on) => on,
+                            _ => return Err(ConfigError::wrong_type("idle", p, "boolean")),
+                        },
+                    };
+                    Some(Idle { after: Duration::from_secs(minutes * 60), pause_loops })
+                }
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as an `[idle]` section")),
+            },
+        };
+
+        let (layout, long_names, fps, locale, palette, screen_reader, clock, header, splash) = match table.entry("ui") {
+            None => (TileLayout::Grid, LongNames::Truncate, None, None, None, false, false, None, None),
+            Some(e) => match &e.value {
+                Value::Table(ui) => {
+                    ConfigError::check_unknown("ui", ui, &["layout", "long-names", "fps", "locale", "palette", "screen-reader", "clock", "header", "splash"])?;
+                    let layout = match string("ui", ui, "layout")?.as_deref() {
+                        None | Some("grid") => TileLayout::Grid,
+                        Some("numpad") => TileLayout::Numpad,
+                        Some(other) => {
+                            return Err(ConfigError::new(format!("unknown layout {other:?}"))
+                                .line(ui.entry("layout").map_or(e.line, |l| l.line))
+                                .field("ui.layout")
+                                .expected("\"grid\" or \"numpad\""));
+                        }
+                    };
+                    let long_names = match string("ui", ui, "long-names")? {
+                        None => LongNames::Truncate,
+                        Some(name) => LongNames::ALL.into_iter().find(|l| l.name() == name).ok_or_else(|| {
+                            ConfigError::new(format!("unknown way to show long names {name:?}"))
+                                .line(ui.entry("long-names").map_or(e.line, |l| l.line))
+                                .field("ui.long-names")
+                                .expected("\"truncate\", \"wrap\" or \"scroll\"")
+                        })?,
+                    };
+                    let fps = match ui.entry("fps") {
+                        None => None,
+                        Some(f) => match f.value {
+                            Value::Integer(n) if (1..=240).contains(&n) => Some(n as u32),
+                            Value::Integer(_) => {
+                                return Err(ConfigError::new("`fps` must be between 1 and 240")
+                                    .line(f.line)
+                                    .field("ui.fps")
+                                    .suggest("leave it out to draw at 60 frames a second"));
+                            }
+                            _ => return Err(ConfigError::wrong_type("ui", f, "integer")),
+                        },
+                    };
+                    let locale = match string("ui", ui, "locale")? {
+                        None => None,
+                        Some(tag) => Some(Locale::parse(&tag).ok_or_else(|| {
+                            ConfigError::new(format!("no translation for {tag:?}"))
+                                .line(ui.entry("locale").map_or(e.line, |l| l.line))
+                                .field("ui.locale")
+                                .expected("\"en\" or \"nl\"")
+                        })?),
+                    };
+                    let palette = match string("ui", ui, "palette")? {
+                        None => None,
+                        Some(name) => Some(Palette::parse(&name).ok_or_else(|| {
+                            ConfigError::new(format!("unknown palette {name:?}"))
+                                .line(ui.entry("palette").map_or(e.line, |l| l.line))
+                                .field("ui.palette")
+                                .expected("\"default\", \"high-contrast\", \"colorblind\" or \"none\"")
+                        })?),
+                    };
+                    let flag = |key: &str| match ui.entry(key) {
+                        None => Ok(false),
+                        Some(r) => match r.value {
+                            Value::Boolean(on) => Ok(on),
+                            _ => Err(ConfigError::wrong_type("ui", r, "boolean")),
+                        },
+                    };
+                    let screen_reader = flag("screen-reader")?;
+                    let clock = flag("clock")?;
+                    let header = string("ui", ui, "header")?.map(PathBuf::from);
+                    let splash = string("ui", ui, "splash")?.map(PathBuf::from);
+                    (layout, long_names, fps, locale, palette, screen_reader, clock, header, splash)
+                }
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as a `[ui]` section")),
+            },
+        };
+
+        let groups = sections(table, "group")?.into_iter().map(Group::from_table).collect::<Result<_, _>>()?;
+        let buses = sections(table, "bus")?.into_iter().map(Bus::from_table).collect::<Result<_, _>>()?;
+        let scenes: Vec<Scene> = sections(table, "scene")?.into_iter().map(|t| Scene::from_table(t, &sounds, &trash)).collect::<Result<_, _>>()?;
+        let crossfader = match table.entry("crossfader") {
+            None => None,
+            Some(e) => match &e.value {
+                Value::Table(t) => Some(Crossfader::from_table(t, &sounds)?),
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as a `[crossfader]` section")),
+            },
+        };
+        let mut faders: Vec<Fader> = Vec::new();
+        for t in sections(table, "fader")? {
+            let fader = Fader::from_table(t)?;
+            if faders.iter().any(|f| f.cc == fader.cc && f.channel == fader.channel) {
+                return Err(ConfigError::new(format!("two faders are on cc {}", fader.cc))
+                    .line(t.entry("cc").map_or(t.line, |e| e.line))
+                    .field("fader.cc")
+                    .suggest("give each its own `cc`, or set `channel` on them when the controller sends the same one on several"));
+            }
+            faders.push(fader);
+        }
+        let midi_feedback = match table.entry("midi") {
+            None => false,
+            Some(e) => match &e.value {
+                Value::Table(midi) => {
+                    ConfigError::check_unknown("midi", midi, &["feedback"])?;
+                    match midi.entry("feedback") {
+                        None => false,
+                        Some(f) => match f.value {
+                            Value::Boolean(on) => on,
+                            _ => return Err(ConfigError::wrong_type("midi", f, "boolean")),
+                        },
+                    }
+                }
+                _ => return Err(ConfigError::wrong_type("", e, "table").suggest("write it as a `[midi]` section")),
+            },
+        };
+        let portable = match table.entry("portable") {
+            None => false,
+            Some(e) => match e.value {
+                Value::Boolean(on) => on,
+                _ => return Err(ConfigError::wrong_type("", e, "boolean")),
+            },
+        };
+        let countdowns = sections(table, "countdown")?.into_iter().map(|t| Countdown::from_table(t, &sounds, &trash, &scenes)).collect::<Result<_, _>>()?;
+        let schedules = sections(table, "schedule")?.into_iter().map(|t| Schedule::from_table(t, &sounds, &trash, &scenes)).collect::<Result<_, _>>()?;
+        let signals = sections(table, "signal")?.into_iter().map(|t| SignalRule::from_table(t, &sounds, &trash)).collect::<Result<_, _>>()?;
+        let pedals = sections(table, "pedal")?.into_iter().map(|t| PedalRule::from_table(t, &sounds, &trash)).collect::<Result<_, _>>()?;
+        let combos = sections(table, "combo")?.into_iter().map(|t| Combo::from_table(t, &sounds, &trash)).collect::<Result<_, _>>()?;
+
+        Ok(Self { sounds, trash, outputs, memory, resampler, cues, talkover, levels, voice, transcribe, groups, buses, scenes, crossfader, faders, midi_feedback, signals, pedals, combos, fifo, metrics, mdns, artnet, mqtt, freesound, packs, sync, cache, idle, layout, long_names, fps, locale, palette, screen_reader, clock, countdowns, schedules, header, splash, portable })
+    }
+
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("version", CURRENT_VERSION);
+        if self.portable {
+            table.insert("portable", true);
+        }
+        if self.outputs != Outputs::default() || self.memory.is_some() || self.resampler != Resampler::default() {
+            let mut audio = Table::new();
+            if let Some(monitor) = &self.outputs.monitor {
+                audio.insert("monitor", monitor.as_str());
+            }
+            if !self.outputs.external.is_empty() {
+                audio.insert("outputs", self.outputs.external.clone());
+            }
+            if !self.outputs.mono.is_empty() {
+                audio.insert("mono", self.outputs.mono.clone());
+            }
+            if let Some(memory) = self.memory {
+                audio.insert("memory", memory as i64);
+            }
+            if self.resampler != Resampler::default() {
+                audio.insert("resampler", self.resampler.name());
+            }
+            table.insert("audio", audio);
+        }
+        if self.voice.is_some() || self.transcribe.is_some() {
+            let mut voice = Table::new();
+            if let Some(command) = &self.voice {
+                voice.insert("command", command.as_str());
+            }
+            if let Some(transcribe) = &self.transcribe {
+                voice.insert("transcribe", transcribe.as_str());
+            }
+            table.insert("voice", voice);
+        }
+        if let Some(path) = &self.fifo {
+            let mut fifo = Table::new();
+            fifo.insert("path", path.to_string_lossy().into_owned());
+            table.insert("fifo", fifo);
+        }
+        if let Some(metrics) = &self.metrics {
+            table.insert("metrics", metrics.to_table());
+        }
+        if let Some(artnet) = &self.artnet {
+            table.insert("artnet", artnet.to_table());
+        }
+        if let Some(mqtt) = &self.mqtt {
+            table.insert("mqtt", mqtt.to_table());
+        }
+        if let Some(key) = &self.freesound {
+            let mut freesound = Table::new();
+            freesound.insert("key", key.as_str());
+            table.insert("freesound", freesound);
+        }
+        if let Some(index) = &self.packs {
+            let mut packs = Table::new();
+            packs.insert("index", index.as_str());
+            table.insert("packs", packs);
+        }
+        if let Some(sync) = &self.sync {
+            table.insert("sync", sync.to_table());
+        }
+        if let Some(settings) = &self.cache {
+            let mut cache = Table::new();
+            if let Some(dir) = &settings.dir {
+                cache.insert("dir", dir.to_string_lossy().into_owned());
+            }
+            table.insert("cache", cache);
+        }
+        if let Some(settings) = self.idle {
+            let mut idle = Table::new();
+            if settings.after != Idle::default().after {
+                idle.insert("minutes", (settings.after.as_secs() / 60) as i64);
+            }
+            if settings.pause_loops {
+                idle.insert("pause-loops", true);
+            }
+            table.insert("idle", idle);
+        }
+        if self.layout != TileLayout::Grid || self.long_names != LongNames::Truncate || self.fps.is_some() || self.locale.is_some() || self.palette.is_some() || self.screen_reader || self.clock || self.header.is_some() || self.splash.is_some() {
+            let mut ui = Table::new();
+            if self.layout != TileLayout::Grid {
+                ui.insert("layout", self.layout.name());
+            }
+            if self.long_names != LongNames::Truncate {
+                ui.insert("long-names", self.long_names.name());
+            }
+            if let Some(fps) = self.fps {
+                ui.insert("fps", fps as i64);
+            }
+            if let Some(locale) = self.locale {
+                ui.insert("locale", locale.name());
+            }
+            if let Some(palette) = self.palette {
+                ui.insert("palette", palette.name());
+            }
+            if self.screen_reader {
+                ui.insert("screen-reader", true);
+            }
+            if self.clock {
+                ui.insert("clock", true);
+            }
+            if let Some(header) = &self.header {
+                ui.insert("header", header.to_string_lossy().into_owned());
+            }
+            if let Some(splash) = &self.splash {
+                ui.insert("splash", splash.to_string_lossy().into_owned());
+            }
+            table.insert("ui", ui);
+        }
+        if let Some(mdns) = &self.mdns {
+            let mut section = Table::new();
+            if let Some(name) = &mdns.name {
+                section.insert("name", name.as_str());
+            }
+            table.insert("mdns", section);
+        }
+        if let Some(talkover) = &self.talkover {
+            table.insert("talkover", talkover.to_table());
+        }
+        table.insert("sound", Value::Array(self.sounds.iter().map(|s| Value::Table(s.to_table())).collect()));
+        if !self.trash.is_empty() {
+            table.insert("trash", Value::Array(self.trash.iter().map(|s| Value::Table(s.to_table())).collect()));
+        }
+        if !self.groups.is_empty() {
+            table.insert("group", Value::Array(self.groups.iter().map(|g| Value::Table(g.to_table())).collect()));
+        }
+        if !self.buses.is_empty() {
+            table.insert("bus", Value::Array(self.buses.iter().map(|b| Value::Table(b.to_table())).collect()));
+        }
+        if !self.scenes.is_empty() {
+            table.insert("scene", Value::Array(self.scenes.iter().map(|s| Value::Table(s.to_table())).collect()));
+        }
+        if let Some(crossfader) = &self.crossfader {
+            table.insert("crossfader", crossfader.to_table());
+        }
+        if !self.faders.is_empty() {
+            table.insert("fader", Value::Array(self.faders.iter().map(|f| Value::Table(f.to_table())).collect()));
+        }
+        if self.midi_feedback {
+            let mut midi = Table::new();
+            midi.insert("feedback", true);
+            table.insert("midi", midi);
+        }
+        if !self.countdowns.is_empty() {
+            table.insert("countdown", Value::Array(self.countdowns.iter().map(|c| Value::Table(c.to_table())).collect()));
+        }
+        if !self.schedules.is_empty() {
+            table.insert("schedule", Value::Array(self.schedules.iter().map(|s| Value::Table(s.to_table())).collect()));
+        }
+        if !self.cues.is_empty() {
+            table.insert("cue", Value::Array(self.cues.iter().map(|c| Value::Table(c.to_table())).collect()));
+        }
+        if !self.levels.is_empty() {
+            table.insert("level", Value::Array(self.levels.iter().map(|l| Value::Table(l.to_table())).collect()));
+        }
+        if !self.signals.is_empty() {
+            table.insert("signal", Value::Array(self.signals.iter().map(|s| Value::Table(s.to_table())).collect()));
+        }
+        if !self.pedals.is_empty() {
+            table.insert("pedal", Value::Array(self.pedals.iter().map(|p| Value::Table(p.to_table())).collect()));
+        }
+        if !self.combos.is_empty() {
+            table.insert("combo", Value::Array(self.combos.iter().map(|c| Value::Table(c.to_table())).collect()));
+        }
+        table
+    }
+
+    pub fn trash(&mut self, idx: usize) {
+        if idx < self.sounds.len() {
+            let sound = self.sounds.remove(idx);
+            self.trash.push(sound);
+        }
+    }
+
+    pub fn restore(&mut self, idx: usize) {
+        if idx < self.trash.len() {
+            let sound = self.trash.remove(idx);
+            self.sounds.push(sound);
+        }
+    }
+
+    /// Adds the audio file at `path` as a new, unbound sound, or every audio file in it if it
+    /// is a directory. Files already on the board are skipped.
+    pub fn import(&mut self, path: &Path) -> color_eyre::Result<()> {
+        if path.is_dir() {
+            let mut entries: Vec<_> = fs::read_dir(path)
+                .wrap_err_with(|| format!("read {}", path.display()))?
+                .map(|e| e.map(|e| e.path()))
+                .collect::<Result<_, _>>()
+                .wrap_err_with(|| format!("read {}", path.display()))?;
+            entries.sort();
+            for entry in entries.iter().filter(|p| is_audio(p)) {
+                self.import(entry)?;
+            }
+            return Ok(());
+        }
+
+        if !path.is_file() {
+            bail!("{} does not exist", path.display());
+        }
+        let source = Source::File(path.to_path_buf());
+        if self.sounds.iter().any(|s| s.source == source) {
+            return Ok(());
+        }
+        let name = names::unique(names::from_file(path), |n| self.sounds.iter().chain(&self.trash).any(|s| s.name == n));
+        let checksum = sha256::hex(&fs::read(path).wrap_err_with(|| format!("read {}", path.display()))?);
+        self.sounds.push(Sound { name, bindings: Vec::new(), label: None, source, variants: Vec::new(), weights: Vec::new(), checksums: vec![checksum], order: Order::Random, volume: 1.0, speakers: Vec::new(), plays: 0, group: None, bus: None, dmx: Vec::new(), webhooks: Vec::new(), shape: Shape::default(), color: None, tags: Vec::new(), then: Vec::new() });
+        Ok(())
+    }
+
+    /// Copies the files of the sounds from `from` on that aren't in the folder `folder` into
+    /// `into`, which is in it, so a portable board has them wherever it is taken.
+    pub fn carry(&mut self, from: usize, folder: &Path, into: &Path) -> color_eyre::Result<()> {
+        let folder = std::path::absolute(folder)?;
+        for sound in self.sounds.iter_mut().skip(from) {
+            let Source::File(path) = &sound.source else { continue };
+            if std::path::absolute(path)?.starts_with(&folder) {
+                continue;
+            }
+            fs::create_dir_all(into).wrap_err_with(|| format!("create {}", into.display()))?;
+            let data = fs::read(path).wrap_err_with(|| format!("read {}", path.display()))?;
+            let (stem, ext) = (path.file_stem().unwrap_or_default().to_string_lossy(), path.extension().map(|e| e.to_string_lossy()));
+            // A file of the same name that is another sound gets a number
+            let copy = (1..)
+                .map(|n| {
+                    let name = if n == 1 { stem.to_string() } else { format!("{stem} {n}") };
+                    into.join(ext.as_ref().map_or(name.clone(), |ext| format!("{name}.{ext}")))
+                })
+                .find(|copy| fs::read(copy).map_or(true, |there| there == data))
+                .unwrap_or_else(|| into.join(&*stem));
+            fs::write(&copy, &data).wrap_err_with(|| format!("write {}", copy.display()))?;
+            sound.source = Source::File(copy);
+        }
+        Ok(())
+    }
+
+    /// Permanently removes a trashed sound, deleting its file from disk.
+    pub fn purge(&mut self, idx: usize) -> color_eyre::Result<()> {
+        if idx >= self.trash.len() {
+            return Ok(());
+        }
+
+        if let Source::File(path) = &self.trash[idx].source {
+            // Another sound may still use the same file, on the board or to be restored to it
+            let others = self.sounds.iter().chain(self.trash.iter().enumerate().filter(|(i, _)| *i != idx).map(|(_, s)| s));
+            let in_use = others.flat_map(Sound::sources).any(|s| *s == self.trash[idx].source);
+            if !in_use {
+                match fs::remove_file(path) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == ErrorKind::NotFound => {}
+                    Err(e) => return Err(e).wrap_err_with(|| format!("delete {}", path.display())),
+                }
+            }
+        }
+
+        let sound = self.trash.remove(idx);
+        // Cues can only point at sounds that still exist somewhere
+        if !self.sounds.iter().chain(&self.trash).any(|s| s.name == sound.name) {
+            self.cues.retain(|c| c.sound != sound.name);
+            self.levels.retain(|l| l.sound != sound.name);
+            self.signals.retain(|s| s.action != SignalAction::Play(sound.name.clone()));
+            self.pedals.retain(|p| p.action != SignalAction::Play(sound.name.clone()));
+            self.combos.retain(|c| c.action != SignalAction::Play(sound.name.clone()) && !c.sequence.contains(&sound.name));
+            for scene in &mut self.scenes {
+                scene.loops.retain(|l| *l != sound.name);
+            }
+            for countdown in &mut self.countdowns {
+                if countdown.sound.as_ref() == Some(&sound.name) {
+                    countdown.sound = None;
+                }
+            }
+            self.schedules.retain(|s| s.sound.as_ref() != Some(&sound.name) || s.scene.is_some());
+            for schedule in &mut self.schedules {
+                if schedule.sound.as_ref() == Some(&sound.name) {
+                    (schedule.sound, schedule.bus) = (None, None);
+                }
+            }
+            for other in self.sounds.iter_mut().chain(&mut self.trash) {
+                other.then.retain(|t| *t != Then::Play(sound.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// How long sounds in `group` fade out for when another one starts.
+    pub fn group_fade(&self, group: &str) -> Duration {
+        self.groups.iter().find(|g| g.name == group).map_or(Duration::ZERO, |g| g.fade)
+    }
+
+    /// The bus `sound` plays on before the master bus, if any.
+    pub fn bus_of<'a>(&'a self, sound: &'a Sound) -> Option<&'a str> {
+        sound.bus.as_deref().or_else(|| sound.group.as_ref().and_then(|g| self.groups.iter().find(|o| o.name == *g)).and_then(|g| g.bus.as_deref()))
+    }
+
+    /// The sound called `name`, if it is on the board rather than in the trash.
+    pub fn sound_named(&self, name: &str) -> Option<usize> {
+        self.sounds.iter().position(|s| s.name == name)
+    }
+
+    pub fn purge_all(&mut self) -> color_eyre::Result<usize> {
+        let count = self.trash.len();
+        while !self.trash.is_empty() {
+            self.purge(0)?;
+        }
+        Ok(count)
+    }
+}
+
+fn sounds_from(table: &Table, key: &str) -> Result<Vec<Sound>, ConfigError> {
+    sections(table, key)?.into_iter().map(|t| Sound::from_table(key, t)).collect()
+}
+
+/// The tables of a `[[key]]` array.
+fn sections<'a>(table: &'a Table, key: &str) -> Result<Vec<&'a Table>, ConfigError> {
+    let wrong = |entry| {
+        ConfigError::wrong_type("", entry, "array of tables").suggest(format!("write each entry as a `[[{key}]]` section"))
+    };
+    match table.entry(key) {
+        None => Ok(Vec::new()),
+        Some(entry) => match &entry.value {
+            Value::Array(items) => items
+                .iter()
+                .map(|item| match item {
+                    Value::Table(t) => Ok(t),
+                    _ => Err(wrong(entry)),
+                })
+                .collect(),
+            _ => Err(wrong(entry)),
+        },
+    }
+}
+
+/// The `sound` of a section that refers to a sound by name.
+fn sound_ref(section: &str, table: &Table, sounds: &[Sound], trash: &[Sound]) -> Result<String, ConfigError> {
+    let sound = string(section, table, "sound")?.ok_or_else(|| ConfigError::missing(section, table, "sound", "string"))?;
+    // Trashed sounds can still be restored, so references to them are kept
+    if !sounds.iter().chain(trash).any(|s| s.name == sound) {
+        let line = table.entry("sound").map_or(table.line, |e| e.line);
+        let err = ConfigError::new(format!("there is no sound named {sound:?}")).line(line).field(format!("{section}.sound"));
+        return Err(match error::closest(&sound, sounds.iter().map(|s| s.name.as_str())) {
+            Some(c) => err.suggest(format!("did you mean {c:?}?")),
+            None => err.suggest("use the `name` of one of the `[[sound]]` sections"),
+        });
+    }
+    Ok(sound)
+}
+
+impl LevelRule {
+    fn from_table(table: &Table, sounds: &[Sound], trash: &[Sound]) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("level", table, &["sound", "above", "cooldown", "input"])?;
+
+        let sound = sound_ref("level", table, sounds, trash)?;
+        let above = match number("level", table, "above")? {
+            Some(v) if (-100.0..=0.0).contains(&v) => v as f32,
+            Some(_) => {
+                let line = table.entry("above").map_or(table.line, |e| e.line);
+                return Err(ConfigError::new("`above` must be between -100 and 0")
+                    .line(line)
+                    .field("level.above")
+                    .expected("a level in dBFS")
+                    .suggest("0 is the loudest the input can go, a clap close by is around -6"));
+            }
+            None => return Err(ConfigError::missing("level", table, "above", "number")),
+        };
+        let cooldown = match number("level", table, "cooldown")? {
+            None => Duration::from_secs(1),
+            Some(v) if v >= 0.0 => Duration::from_secs_f64(v),
+            Some(_) => {
+                let line = table.entry("cooldown").map_or(table.line, |e| e.line);
+                return Err(ConfigError::new("`cooldown` must not be negative").line(line).field("level.cooldown"));
+            }
+        };
+
+        Ok(Self { sound, above, cooldown, input: string("level", table, "input")? })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("sound", self.sound.as_str());
+        table.insert("above", (self.above as f64 * 100.0).round() / 100.0);
+        table.insert("cooldown", self.cooldown.as_secs_f64());
+        if let Some(input) = &self.input {
+            table.insert("input", input.as_str());
+        }
+        table
+    }
+}
+
+impl SignalAction {
+    /// Reads what a `section` rule does: play a `sound`, or run an `action`.
+    fn from_table(section: &str, table: &Table, sounds: &[Sound], trash: &[Sound]) -> Result<Self, ConfigError> {
+        match (table.entry("sound"), string(section, table, "action")?) {
+            (Some(_), None) => Ok(Self::Play(sound_ref(section, table, sounds, trash)?)),
+            (None, Some(action)) => match action.as_str() {
+                "stop" => Ok(Self::Stop),
+                "mute" => Ok(Self::Mute),
+                "go" => Ok(Self::Go),
+                _ => {
+                    let line = table.entry("action").map_or(table.line, |e| e.line);
+                    Err(ConfigError::new(format!("unknown action {action:?}"))
+                        .line(line)
+                        .field(format!("{section}.action"))
+                        .expected("stop, mute or go")
+                        .suggest("to play a sound, set `sound` instead"))
+                }
+            },
+            (Some(e), Some(_)) => Err(ConfigError::new(format!("a {section} can either play a `sound` or run an `action`, not both"))
+                .line(e.line)
+                .field(section)),
+            (None, None) => Err(ConfigError::missing(section, table, "sound", "string")
+                .suggest("add `sound = ...` to play a sound, or `action = ...` to stop, mute or go")),
+        }
+    }
+
+    fn insert_into(&self, table: &mut Table) {
+        match self {
+            SignalAction::Play(sound) => table.insert("sound", sound.as_str()),
+            SignalAction::Stop => table.insert("action", "stop"),
+            SignalAction::Mute => table.insert("action", "mute"),
+            SignalAction::Go => table.insert("action", "go"),
+        }
+    }
+}
+
+impl SignalRule {
+    fn from_table(table: &Table, sounds: &[Sound], trash: &[Sound]) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("signal", table, &["signal", "sound", "action"])?;
+
+        let name = string("signal", table, "signal")?.ok_or_else(|| ConfigError::missing("signal", table, "signal", "string"))?;
+        let Some(signal) = signal_name(&name) else {
+            let line = table.entry("signal").map_or(table.line, |e| e.line);
+            return Err(ConfigError::new(format!("{name:?} is not a signal the board can listen for"))
+                .line(line)
+                .field("signal.signal")
+                .expected("USR1, USR2, or RTMIN up to RTMIN+15")
+                .suggest("other signals already mean something, like TERM stopping the board"));
+        };
+        Ok(Self { signal, action: SignalAction::from_table("signal", table, sounds, trash)? })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("signal", self.signal.as_str());
+        self.action.insert_into(&mut table);
+        table
+    }
+}
+
+impl Combo {
+    pub const DEFAULT_SECONDS: u64 = 5;
+
+    fn from_table(table: &Table, sounds: &[Sound], trash: &[Sound]) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("combo", table, &["sequence", "within", "sound", "action"])?;
+
+        let mut sequence = Vec::new();
+        for (name, line) in strings("combo", table, "sequence")? {
+            if !sounds.iter().chain(trash).any(|s| s.name == name) {
+                let err = ConfigError::new(format!("there is no sound named {name:?}")).line(line).field("combo.sequence");
+                return Err(match error::closest(&name, sounds.iter().map(|s| s.name.as_str())) {
+                    Some(c) => err.suggest(format!("did you mean {c:?}?")),
+                    None => err.suggest("use the `name` of one of the `[[sound]]` sections"),
+                });
+            }
+            sequence.push(name);
+        }
+        match (sequence.len(), table.entry("sequence")) {
+            (_, None) => return Err(ConfigError::missing("combo", table, "sequence", "array of sound names")),
+            (0 | 1, Some(e)) => {
+                return Err(ConfigError::new("a combo's `sequence` needs at least two sounds")
+                    .line(e.line)
+                    .field("combo.sequence")
+                    .suggest("a combo is played by playing its sounds one after the other"));
+            }
+            _ => {}
+        }
+        let within = match number("combo", table, "within")? {
+            None => Duration::from_secs(Self::DEFAULT_SECONDS),
+            Some(v) if v > 0.0 && v <= 3600.0 => Duration::from_secs_f64(v),
+            Some(_) => {
+                let line = table.entry("within").map_or(table.line, |e| e.line);
+                return Err(ConfigError::new("`within` must be more than 0 seconds and at most an hour").line(line).field("combo.within"));
+            }
+        };
+        Ok(Self { sequence, within, action: SignalAction::from_table("combo", table, sounds, trash)? })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("sequence", Value::Array(self.sequence.iter().map(|s| Value::from(s.as_str())).collect()));
+        table.insert("within", self.within.as_secs_f64());
+        self.action.insert_into(&mut table);
+        table
+    }
+}
+
+impl PedalRule {
+    fn from_table(table: &Table, sounds: &[Sound], trash: &[Sound]) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("pedal", table, &["key", "device", "sound", "action"])?;
+
+        let key = string("pedal", table, "key")?.ok_or_else(|| ConfigError::missing("pedal", table, "key", "string"))?;
+        if pedal::code(&key).is_none() {
+            let line = table.entry("key").map_or(table.line, |e| e.line);
+            return Err(ConfigError::new(format!("unknown pedal key {key:?}"))
+                .line(line)
+                .field("pedal.key")
+                .expected("the key the pedal sends")
+                .suggest("most pedals send a letter like \"b\" or a key like \"F13\"; pressing an unbound pedal shows its key"));
+        }
+        let device = string("pedal", table, "device")?;
+        Ok(Self { key, device, action: SignalAction::from_table("pedal", table, sounds, trash)? })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("key", self.key.as_str());
+        if let Some(device) = &self.device {
+            table.insert("device", device.as_str());
+        }
+        self.action.insert_into(&mut table);
+        table
+    }
+}
+
+/// Normalizes a signal name (`SIGUSR1`, `usr1`, `RTMIN+2`) to how the board writes it, if it is
+/// one the board can listen for.
+pub fn signal_name(name: &str) -> Option<String> {
+    let name = name.trim().to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    match name {
+        "USR1" | "USR2" | "RTMIN" => Some(name.to_string()),
+        _ => {
+            let n: u8 = name.strip_prefix("RTMIN+")?.parse().ok()?;
+            match n {
+                0 => Some("RTMIN".to_string()),
+                1..=15 => Some(format!("RTMIN+{n}")),
+                _ => None,
+            }
+        }
+    }
+}
+
+impl Cue {
+    fn from_table(table: &Table, sounds: &[Sound], trash: &[Sound]) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown("cue", table, &["sound", "note"])?;
+
+        let sound = sound_ref("cue", table, sounds, trash)?;
+        Ok(Self { sound, note: string("cue", table, "note")? })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("sound", self.sound.as_str());
+        if let Some(note) = &self.note {
+            table.insert("note", note.as_str());
+        }
+        table
+    }
+}
+
+impl Sound {
+    /// What to show as the shortcut on the sound's tile.
+    /// Every file it may play, `source` first.
+    pub fn sources(&self) -> impl Iterator<Item = &Source> {
+        std::iter::once(&self.source).chain(&self.variants)
+    }
+
+    /// The file to play: the next in turn going by how often it played, or one picked by `roll`
+    /// (any number, at random) according to the weights.
+    pub fn pick(&self, roll: u64) -> &Source {
+        if self.order == Order::RoundRobin {
+            return self.sources().nth(self.plays as usize % (self.variants.len() + 1)).unwrap_or(&self.source);
+        }
+        let weight = |i: usize| self.weights.get(i).map_or(1, |&w| w as u64);
+        let total: u64 = (0..=self.variants.len()).map(weight).sum();
+        let mut at = roll % total.max(1);
+        for (i, source) in self.sources().enumerate() {
+            if at < weight(i) {
+                return source;
+            }
+            at -= weight(i);
+        }
+        &self.source
+    }
+
+    pub fn shortcut_label(&self) -> Option<Cow<'_, str>> {
+        if let Some(label) = &self.label {
+            return Some(Cow::Borrowed(label));
+        }
+        let labels: Vec<_> = self.bindings.iter().filter(|b| b.is_shortcut()).map(|b| b.label.as_str()).collect();
+        (!labels.is_empty()).then(|| Cow::Owned(labels.join(" · ")))
+    }
+
+    pub fn data(&self) -> color_eyre::Result<Cow<'static, [u8]>> {
+        self.source.data()
+    }
+
+    fn from_table(section: &str, table: &Table) -> Result<Self, ConfigError> {
+        ConfigError::check_unknown(section, table, &["name", "key", "midi", "pad", "alias", "say", "label", "file", "sha256", "weights", "order", "builtin", "volume", "speakers", "plays", "group", "bus", "dmx", "webhook", "start", "end", "loop", "reverse", "fade-in", "speed", "stretch", "max-length", "color", "tags", "run", "then-play", "then-run", "then-webhook"])?;
+
+        let name = string(section, table, "name")?.ok_or_else(|| ConfigError::missing(section, table, "name", "string"))?;
+
+        let mut bindings = Vec::new();
+        for (key, line) in strings(section, table, "key")? {
+            bindings.push(Binding::parse_key(&key).ok_or_else(|| {
+                ConfigError::new(format!("unknown key {key:?}"))
+                    .line(line)
+                    .field(format!("{section}.key"))
+                    .expected("a key, optionally with modifiers")
+                    .suggest("use a character like \"a\", a name like \"F5\", \"space\" or \"pageup\", or a chord like \"ctrl+1\"")
+            })?);
+        }
+        for (note, line) in notes(section, table)? {
+            bindings.push(Binding::parse_midi(&note).ok_or_else(|| {
+                ConfigError::new(format!("unknown MIDI note {note:?}"))
+                    .line(line)
+                    .field(format!("{section}.midi"))
+                    .expected("a note name or number")
+                    .suggest("use a name like \"C4\" or \"F#2\", or a number from 0 to 127")
+            })?);
+        }
+        for (input, line) in strings(section, table, "pad")? {
+            bindings.push(Binding::parse_pad(&input).ok_or_else(|| {
+                ConfigError::new(format!("unknown gamepad input {input:?}"))
+                    .line(line)
+                    .field(format!("{section}.pad"))
+                    .expected("a button or an axis threshold")
+                    .suggest("use a button like \"a\", \"start\" or \"button 4\", or an axis like \"axis 1 > 0.5\"")
+            })?);
+        }
+        for (alias, _) in strings(section, table, "alias")? {
+            bindings.push(Binding::alias(&alias));
+        }
+        for (phrase, line) in strings(section, table, "say")? {
+            bindings.push(Binding::phrase(&phrase).ok_or_else(|| {
+                ConfigError::new(format!("{phrase:?} has no words to listen for"))
+                    .line(line)
+                    .field(format!("{section}.say"))
+                    .suggest("use the words that should trigger the sound, like \"air horn\"")
+            })?);
+        }
+        let label = string(section, table, "label")?;
+
+        let mut files = strings(section, table, "file")?.into_iter().map(|(file, _)| Source::File(PathBuf::from(file)));
+        let (file, variants): (Option<Source>, Vec<Source>) = (files.next(), files.collect());
+        let source = match (file, string(section, table, "builtin")?, string(section, table, "run")?) {
+            (Some(file), None, None) => file,
+            (None, Some(builtin), None) => {
+                if !BUILTIN.iter().any(|(n, _)| *n == builtin) {
+                    let line = table.entry("builtin").map_or(table.line, |e| e.line);
+                    let err = ConfigError::new(format!("there is no builtin sound named {builtin:?}"))
+                        .line(line)
+                        .field(format!("{section}.builtin"));
+                    return Err(match error::closest(&builtin, BUILTIN.iter().map(|(n, _)| *n)) {
+                        Some(c) => err.suggest(format!("did you mean {c:?}?")),
+                        None => err.suggest(format!("builtin sounds are: {}", BUILTIN.iter().map(|(n, _)| *n).collect::<Vec<_>>().join(", "))),
+                    });
+                }
+                Source::Builtin(builtin)
+            }
+            (None, None, Some(command)) => Source::Command(command),
+            (None, None, None) => {
+                return Err(ConfigError::new(format!("sound {name:?} has no audio"))
+                    .line(table.line)
+                    .field(section)
+                    .expected("either `file` or `builtin`, or `run` for a command")
+                    .suggest("add a line like `file = \"sounds/airhorn.wav\"`"));
+            }
+            (file, builtin, _) => {
+                let both = if file.is_some() && builtin.is_some() { "both `file` and `builtin`" } else { "`run` as well as audio" };
+                return Err(ConfigError::new(format!("sound {name:?} has {both}"))
+                    .line(table.line)
+                    .field(section)
+                    .suggest("remove one of them"));
+            }
+        };
+
+        let volume = match table.entry("volume") {
+            None => 1.0,
+            Some(e) => match number(section, table, "volume")? {
+                Some(v) if (0.0..=MAX_VOLUME as f64).contains(&v) => v as f32,
+                _ => {
+                    return Err(ConfigError::new(format!("`volume` must be between 0.0 and {MAX_VOLUME:.1}"))
+                        .line(e.line)
+                        .field(format!("{section}.volume"))
+                        .expected("number")
+                        .suggest("use 1.0 for the original level, 0.5 for half"));
+                }
+            },
+        };
+
+        let mut speakers = Vec::new();
+        for (name, line) in strings(section, table, "speakers")? {
+            speakers.push(Speaker::parse(&name).ok_or_else(|| {
+                let err = ConfigError::new(format!("there is no speaker named {name:?}")).line(line).field(format!("{section}.speakers"));
+                match error::closest(&name, Speaker::ALL.iter().map(|s| s.name())) {
+                    Some(c) => err.suggest(format!("did you mean {c:?}?")),
+                    None => err.suggest(format!("speakers are: {}", Speaker::ALL.map(Speaker::name).join(", "))),
+                }
+            })?);
+        }
+
+        let weights = match table.entry("weights") {
+            None => Vec::new(),
+            Some(e) => {
+                let weights = match &e.value {
+                    Value::Array(items) => items.iter().map(|v| if let Value::Integer(n) = v { u32::try_from(*n).ok() } else { None }).collect::<Option<Vec<_>>>(),
+                    _ => None,
+                };
+                let error = |message: &str| ConfigError::new(message).line(e.line).field(format!("{section}.weights"));
+                match weights {
+                    None => return Err(ConfigError::wrong_type(section, e, "array of whole numbers")),
+                    Some(w) if w.len() != variants.len() + 1 => {
+                        return Err(error("`weights` needs one weight for each file").suggest(format!("the sound has {} file(s)", variants.len() + 1)));
+                    }
+                    Some(w) if w.iter().all(|&w| w == 0) => return Err(error("at least one of the `weights` must be more than 0")),
+                    Some(w) => w,
+                }
+            }
+        };
+
+        let checksums: Vec<String> = strings(section, table, "sha256")?.into_iter().map(|(hash, _)| hash.to_lowercase()).collect();
+        if let Some(e) = table.entry("sha256") {
+            let error = |message: &str| ConfigError::new(message).line(e.line).field(format!("{section}.sha256"));
+            if !matches!(source, Source::File(_)) {
+                return Err(error("only sounds with a `file` have a `sha256`").suggest("remove it"));
+            }
+            if checksums.len() != variants.len() + 1 {
+                return Err(error("`sha256` needs one checksum for each file").suggest(format!("the sound has {} file(s)", variants.len() + 1)));
+            }
+            if checksums.iter().any(|hash| !hash.is_empty() && (hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()))) {
+                return Err(error("`sha256` must be the 64 hex digits of a SHA-256").suggest("run `soundboard verify --update` to record the files as they are"));
+            }
+        }
+
+        let order = match string(section, table, "order")? {
+            None => Order::Random,
+            Some(name) => Order::ALL.into_iter().find(|o| o.name() == name).ok_or_else(|| {
+                ConfigError::new(format!("unknown order {name:?}"))
+                    .line(table.entry("order").map_or(table.line, |e| e.line))
+                    .field(format!("{section}.order"))
+                    .expected("\"random\" or \"round-robin\"")
+            })?,
+        };
+        if order == Order::RoundRobin && !weights.is_empty() {
+            let line = table.entry("weights").map_or(table.line, |e| e.line);
+            return Err(ConfigError::new("`weights` only go with files picked at random").line(line).field(format!("{section}.weights")).suggest("leave out `order = \"round-robin\"` or the weights"));
+        }
+
+        // Only in configs from before the counts were kept in the state directory, see
+        // [`crate::history::load_plays`]
+        let plays = match table.entry("plays") {
+            None => 0,
+            Some(e) => match e.value {
+                Value::Integer(n) => u32::try_from(n).map_err(|_| {
+                    ConfigError::new("`plays` must not be negative").line(e.line).field(format!("{section}.plays"))
+                })?,
+                _ => return Err(ConfigError::wrong_type(section, e, "integer")),
+            },
+        };
+
+        let mut dmx = Vec::new();
+        for (value, line) in strings(section, table, "dmx")? {
+            dmx.push(Dmx::parse(&value).ok_or_else(|| {
+                ConfigError::new(format!("{value:?} is not a DMX channel and value"))
+                    .line(line)
+                    .field(format!("{section}.dmx"))
+                    .expected("channel=value")
+                    .suggest("use a channel from 1 to 512 and a value from 0 to 255, like \"12=255\"")
+            })?);
+        }
+
+        let webhook = |key: &str| {
+            strings(section, table, key)?
+                .into_iter()
+                .map(|(url, line)| {
+                    if Url::parse(&url).is_some() {
+                        return Ok(url);
+                    }
+                    let error = ConfigError::new(format!("{url:?} is not a webhook URL")).line(line).field(format!("{section}.{key}"));
+                    Err(if url.starts_with("https://") {
+                        error.suggest("only plain http:// is supported, put a local proxy in front of https receivers")
+                    } else {
+                        error.expected("http://host[:port]/path")
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let webhooks = webhook("webhook")?;
+
+        let range = |key: &str, range: std::ops::RangeInclusive<f64>, hint: &str| {
+            let Some(v) = number(section, table, key)? else { return Ok(None) };
+            if range.contains(&v) {
+                return Ok(Some(v));
+            }
+            let line = table.entry(key).map_or(table.line, |e| e.line);
+            Err(ConfigError::new(format!("`{key}` must be between {} and {}", range.start(), range.end()))
+                .line(line)
+                .field(format!("{section}.{key}"))
+                .expected("number")
+                .suggest(hint.to_string()))
+        };
+        let seconds = |key: &str| range(key, 0.0..=86400.0, "use the number of seconds, like 1.5").map(|v| v.map(Duration::from_secs_f64));
+        let start = seconds("start")?.unwrap_or_default();
+        let end = seconds("end")?;
+        if end.is_some_and(|end| end <= start) {
+            return Err(ConfigError::new("`end` must be after `start`")
+                .line(table.entry("end").map_or(table.line, |e| e.line))
+                .field(format!("{section}.end")));
+        }
+        let flag = |key: &str| match table.entry(key) {
+            None => Ok(false),
+            Some(e) => match e.value {
+                Value::Boolean(on) => Ok(on),
+                _ => Err(ConfigError::wrong_type(section, e, "boolean")),
+            },
+        };
+        let (looped, reversed) = (flag("loop")?, flag("reverse")?);
+        let fade_in = seconds("fade-in")?.unwrap_or_default();
+        let speed = range("speed", 0.25..=4.0, "1.0 plays at the original speed, 2.0 twice as fast and an octave higher")?.unwrap_or(1.0) as f32;
+        let stretch = range("stretch", 0.25..=4.0, "1.0 plays at the original speed, 2.0 twice as fast at the same pitch")?.unwrap_or(1.0) as f32;
+        let max_length = seconds("max-length")?;
+        if max_length.is_some_and(|max| max.is_zero()) {
+            return Err(ConfigError::new("`max-length` must be more than 0 seconds")
+                .line(table.entry("max-length").map_or(table.line, |e| e.line))
+                .field(format!("{section}.max-length"))
+                .suggest("leave it out to play the sound all the way through"));
+        }
+        let shape = Shape { start, end, looped, reversed, fade_in, speed, stretch, max_length };
+
+        let color = match string(section, table, "color")? {
+            None => None,
+            Some(name) => Some(name.parse::<Color>().map_err(|_| {
+                ConfigError::new(format!("unknown color {name:?}"))
+                    .line(table.entry("color").map_or(table.line, |e| e.line))
+                    .field(format!("{section}.color"))
+                    .suggest("use a name like \"red\" or \"lightblue\", or a hex color like \"#ff8800\"")
+            })?),
+        };
+        let tags = strings(section, table, "tags")?.into_iter().map(|(tag, _)| tag).collect();
+
+        let mut then: Vec<Then> = strings(section, table, "then-play")?.into_iter().map(|(name, _)| Then::Play(name)).collect();
+        then.extend(strings(section, table, "then-run")?.into_iter().map(|(command, _)| Then::Run(command)));
+        then.extend(webhook("then-webhook")?.into_iter().map(Then::Webhook));
+        if looped && !then.is_empty() {
+            let key = Then::KEYS.into_iter().find(|key| table.entry(key).is_some()).unwrap_or("then-play");
+            return Err(ConfigError::new("a sound that loops never ends, so it can't do anything after")
+                .line(table.entry(key).map_or(table.line, |e| e.line))
+                .field(format!("{section}.{key}"))
+                .suggest("take out `loop = true`, or have whatever stops the loop do it instead"));
+        }
+
+        Ok(Self { name, bindings, label, source, variants, weights, checksums, order, volume, speakers, plays, group: string(section, table, "group")?, bus: string(section, table, "bus")?, dmx, webhooks, shape, color, tags, then })
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("name", self.name.as_str());
+        for (key, kind) in [("key", 0), ("midi", 1), ("alias", 2), ("say", 3), ("pad", 4)] {
+            let labels: Vec<_> = self
+                .bindings
+                .iter()
+                .filter(|b| match b.trigger {
+                    Trigger::Key(_) => kind == 0,
+                    Trigger::MidiNote(_) => kind == 1,
+                    Trigger::Name(_) => kind == 2,
+                    Trigger::Phrase(_) => kind == 3,
+                    Trigger::Pad(_) => kind == 4,
+                })
+                // A note written as a number stays one
+                .map(|b| match b.label.parse() {
+                    Ok(n) if kind == 1 => Value::Integer(n),
+                    _ => Value::from(b.label.as_str()),
+                })
+                .collect();
+            // A single trigger stays a plain string, the way most configs write it
+            match labels.len() {
+                0 => {}
+                1 => table.insert(key, labels[0].clone()),
+                _ => table.insert(key, labels),
+            }
+        }
+        if let Some(label) = &self.label {
+            table.insert("label", label.as_str());
+        }
+        match &self.source {
+            Source::Builtin(name) => table.insert("builtin", name.as_str()),
+            Source::File(path) if self.variants.is_empty() => table.insert("file", path.to_string_lossy().into_owned()),
+            Source::File(_) => {
+                let files: Vec<String> = self.sources().filter_map(|s| if let Source::File(p) = s { Some(p.to_string_lossy().into_owned()) } else { None }).collect();
+                table.insert("file", files);
+            }
+            Source::Command(command) => table.insert("run", command.as_str()),
+        }
+        match self.checksums.as_slice() {
+            hashes if hashes.iter().all(String::is_empty) => {}
+            [hash] => table.insert("sha256", hash.as_str()),
+            hashes => table.insert("sha256", hashes.to_vec()),
+        }
+        if self.order != Order::Random {
+            table.insert("order", self.order.name());
+        }
+        if !self.weights.is_empty() {
+            table.insert("weights", Value::Array(self.weights.iter().map(|&w| Value::Integer(w as i64)).collect()));
+        }
+        if self.volume != 1.0 {
+            // Round away float noise from adjusting in steps
+            table.insert("volume", (self.volume as f64 * 100.0).round() / 100.0);
+        }
+        match self.speakers.as_slice() {
+            [] => {}
+            [speaker] => table.insert("speakers", speaker.name()),
+            speakers => table.insert("speakers", speakers.iter().map(|s| s.name().to_string()).collect::<Vec<_>>()),
+        }
+        if let Some(group) = &self.group {
+            table.insert("group", group.as_str());
+        }
+        if let Some(bus) = &self.bus {
+            table.insert("bus", bus.as_str());
+        }
+        match self.dmx.as_slice() {
+            [] => {}
+            [dmx] => table.insert("dmx", dmx.to_string()),
+            dmx => table.insert("dmx", dmx.iter().map(Dmx::to_string).collect::<Vec<_>>()),
+        }
+        match self.webhooks.as_slice() {
+            [] => {}
+            [url] => table.insert("webhook", url.as_str()),
+            urls => table.insert("webhook", urls.to_vec()),
+        }
+        if !self.shape.start.is_zero() {
+            table.insert("start", self.shape.start.as_secs_f64());
+        }
+        if let Some(end) = self.shape.end {
+            table.insert("end", end.as_secs_f64());
+        }
+        if self.shape.looped {
+            table.insert("loop", true);
+        }
+        if self.shape.reversed {
+            table.insert("reverse", true);
+        }
+        if !self.shape.fade_in.is_zero() {
+            table.insert("fade-in", self.shape.fade_in.as_secs_f64());
+        }
+        if self.shape.speed != 1.0 {
+            table.insert("speed", (self.shape.speed as f64 * 100.0).round() / 100.0);
+        }
+        if self.shape.stretch != 1.0 {
+            table.insert("stretch", (self.shape.stretch as f64 * 100.0).round() / 100.0);
+        }
+        if let Some(max) = self.shape.max_length {
+            table.insert("max-length", max.as_secs_f64());
+        }
+        if let Some(color) = self.color {
+            table.insert("color", color.to_string().to_lowercase());
+        }
+        match self.tags.as_slice() {
+            [] => {}
+            [tag] => table.insert("tags", tag.as_str()),
+            tags => table.insert("tags", tags.to_vec()),
+        }
+        for key in Then::KEYS {
+            let values: Vec<String> = self.then.iter().filter(|then| then.key() == key).map(|then| then.value().to_string()).collect();
+            match values.as_slice() {
+                [] => {}
+                [value] => table.insert(key, value.as_str()),
+                values => table.insert(key, values.to_vec()),
+            }
+        }
+        table
+    }
+}
+
+pub fn is_audio(path: &Path) -> bool {
+    let ext = path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase());
+    matches!(ext.as_deref(), Some("wav" | "mp3" | "ogg" | "flac"))
+}
+
+fn number(section: &str, table: &Table, key: &str) -> Result<Option<f64>, ConfigError> {
+    match table.entry(key) {
+        None => Ok(None),
+        Some(e) => match e.value {
+            Value::Float(f) => Ok(Some(f)),
+            Value::Integer(i) => Ok(Some(i as f64)),
+            _ => Err(ConfigError::wrong_type(section, e, "number")),
+        },
+    }
+}
+
+fn string(section: &str, table: &Table, key: &str) -> Result<Option<String>, ConfigError> {
+    match table.entry(key) {
+        None => Ok(None),
+        Some(e) => match &e.value {
+            Value::String(s) => Ok(Some(s.clone())),
+            _ => Err(ConfigError::wrong_type(section, e, "string")),
+        },
+    }
+}
+
+/// A string, or an array of them, along with the line each one is on.
+fn strings(section: &str, table: &Table, key: &str) -> Result<Vec<(String, usize)>, ConfigError> {
+    let Some(e) = table.entry(key) else { return Ok(Vec::new()) };
+    match &e.value {
+        Value::String(s) => Ok(vec![(s.clone(), e.line)]),
+        Value::Array(items) => items
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => Ok((s.clone(), e.line)),
+                _ => Err(ConfigError::wrong_type(section, e, "string or array of strings")),
+            })
+            .collect(),
+        _ => Err(ConfigError::wrong_type(section, e, "string or array of strings")),
+    }
+}
+
+/// The notes in `midi`, names or numbers, alone or in an array, as written.
+fn notes(section: &str, table: &Table) -> Result<Vec<(String, usize)>, ConfigError> {
+    let Some(e) = table.entry("midi") else { return Ok(Vec::new()) };
+    let note = |v: &Value| match v {
+        Value::String(s) => Ok((s.clone(), e.line)),
+        Value::Integer(n) => Ok((n.to_string(), e.line)),
+        _ => Err(ConfigError::wrong_type(section, e, "note or array of notes")),
+    };
+    match &e.value {
+        Value::Array(items) => items.iter().map(note).collect(),
+        v => note(v).map(|n| vec![n]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use crate::binding::Trigger;
+    use crate::harness;
+    use crate::toml;
+    use super::{Board, Source};
+
+    #[test]
+    fn notes_are_names_or_numbers() {
+        let (board, _) = Board::parse("[[sound]]\nname = \"a\"\nbuiltin = \"puree\"\nmidi = [60, \"D4\"]\n").unwrap();
+        let triggers: Vec<_> = board.sounds[0].bindings.iter().map(|b| b.trigger.clone()).collect();
+        assert_eq!(triggers, [Trigger::MidiNote(60), Trigger::MidiNote(62)]);
+        // And they are written back the way they were
+        let written = toml::to_string(&board.to_table());
+        assert!(written.contains("60, \"D4\""), "{written}");
+        assert_eq!(Board::parse(&written).unwrap().0, board);
+
+        let err = Board::parse("[[sound]]\nname = \"a\"\nbuiltin = \"puree\"\nmidi = 128\n").unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("sound.midi"));
+    }
+
+    /// The upgrade is handed back rather than printed, as the board may own the terminal.
+    #[test]
+    fn upgrades_are_reported() {
+        let dir = harness::scratch("upgrade");
+        let config = dir.join("soundboard.toml");
+        fs::write(&config, "version = 1\n").unwrap();
+        let (_, migrated) = Board::load(&config).unwrap();
+        let migrated = migrated.unwrap();
+        assert_eq!((migrated.from, &migrated.backup), (1, &dir.join("soundboard.toml.v1.bak")));
+        assert_eq!(fs::read_to_string(&migrated.backup).unwrap(), "version = 1\n");
+        assert!(Board::load(&config).unwrap().1.is_none());
+    }
+
+    #[test]
+    fn purging_keeps_files_that_other_sounds_use() {
+        let dir = harness::scratch("purge");
+        let (horn, bell) = (dir.join("horn.wav"), dir.join("bell.wav"));
+        fs::write(&horn, super::BUILTIN[0].1).unwrap();
+        fs::write(&bell, super::BUILTIN[1].1).unwrap();
+        let mut board = Board::builtin();
+        board.sounds.clear();
+        board.import(&horn).unwrap();
+        board.import(&bell).unwrap();
+        // Two sounds for each file
+        let copies: Vec<_> = board.sounds.iter().map(|s| super::Sound { name: format!("{} again", s.name), ..s.clone() }).collect();
+        board.sounds.insert(1, copies[0].clone());
+        board.sounds.push(copies[1].clone());
+        // One of each in the trash, the other horn too, and the other bell on the board
+        for idx in [2, 1, 0] {
+            board.trash(idx);
+        }
+
+        board.purge(0).unwrap();
+        assert!(bell.exists(), "the bell on the board still plays it");
+        board.purge(0).unwrap();
+        assert!(horn.exists(), "the horn in the trash can still be restored");
+        board.restore(0);
+        assert!(board.sounds.iter().any(|s| s.source == Source::File(horn.clone())));
+        assert!(board.sounds.iter().all(|s| s.data().is_ok()));
+
+        board.trash(0);
+        board.trash(0);
+        board.purge_all().unwrap();
+        assert!(!horn.exists() && !bell.exists());
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::toml::{Entry, ParseError, Table, Value};
+
+/// A problem with the contents of a config file, detailed enough to show the user exactly what
+/// to change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub path: Box<Path>,
+    pub line: Option<usize>,
+    /// Dotted path of the offending field, e.g. `sound.key`
+    pub field: Option<String>,
+    pub message: String,
+    pub expected: Option<&'static str>,
+    pub suggestion: Option<String>,
+}
+
+impl ConfigError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            path: PathBuf::new().into_boxed_path(),
+            line: None,
+            field: None,
+            message: message.into(),
+            expected: None,
+            suggestion: None,
+        }
+    }
+
+    pub fn line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    pub fn expected(mut self, expected: &'static str) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    pub fn suggest(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    pub fn in_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = path.into().into_boxed_path();
+        self
+    }
+
+    /// `entry` was found in table `section` but has the wrong type.
+    pub fn wrong_type(section: &str, entry: &Entry, expected: &'static str) -> Self {
+        let mut err = Self::new(format!("`{}` must be {}, found {}", entry.key, article(expected), entry.value.type_name()))
+            .line(entry.line)
+            .field(field_path(section, &entry.key))
+            .expected(expected);
+
+        if expected.starts_with("string") {
+            let shown = match &entry.value {
+                Value::Integer(i) => Some(i.to_string()),
+                Value::Float(f) => Some(f.to_string()),
+                Value::Boolean(b) => Some(b.to_string()),
+                _ => None,
+            };
+            if let Some(shown) = shown {
+                err = err.suggest(format!("put the value in quotes: {} = \"{shown}\"", entry.key));
+            }
+        } else if expected == "float" || expected == "number" {
+            if let Value::String(s) = &entry.value {
+                if s.parse::<f64>().is_ok() {
+                    err = err.suggest(format!("remove the quotes: {} = {s}", entry.key));
+                }
+            }
+        } else if expected == "boolean" {
+            if let Value::String(s) = &entry.value {
+                if s == "true" || s == "false" {
+                    err = err.suggest(format!("remove the quotes: {} = {s}", entry.key));
+                }
+            }
+        }
+
+        err
+    }
+
+    pub fn missing(section: &str, table: &Table, key: &str, expected: &'static str) -> Self {
+        Self::new(format!("{section} is missing `{key}`"))
+            .line(table.line)
+            .field(field_path(section, key))
+            .expected(expected)
+            .suggest(format!("add a line like `{key} = ...` below line {}", table.line))
+    }
+
+    /// Rejects keys in `table` other than `known`, suggesting the closest known key.
+    pub fn check_unknown(section: &str, table: &Table, known: &[&str]) -> Result<(), Self> {
+        for entry in &table.entries {
+            if !known.contains(&entry.key.as_str()) {
+                let mut err = Self::new(format!("unknown field `{}` in {section}", entry.key))
+                    .line(entry.line)
+                    .field(field_path(section, &entry.key));
+                err = match closest(&entry.key, known.iter().copied()) {
+                    Some(c) => err.suggest(format!("did you mean `{c}`?")),
+                    None => err.suggest(format!("known fields are: {}", known.join(", "))),
+                };
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<ParseError> for ConfigError {
+    fn from(e: ParseError) -> Self {
+        let err = Self::new(e.message.clone()).line(e.line);
+        let suggestion = if e.message.contains("unterminated string") {
+            Some("add the closing `\"` at the end of the string")
+        } else if e.message.contains("expected a value") {
+            Some("strings need quotes, e.g. name = \"airhorn\"")
+        } else if e.message.contains("duplicate key") {
+            Some("remove one of the two definitions")
+        } else if e.message.contains("after value") {
+            Some("put each `key = value` on its own line")
+        } else {
+            None
+        };
+        match suggestion {
+            Some(s) => err.suggest(s),
+            None => err,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(field) = &self.field {
+            write!(f, "\n  field:    {field}")?;
+        }
+        if let Some(expected) = &self.expected {
+            write!(f, "\n  expected: {expected}")?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n  hint:     {suggestion}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn field_path(section: &str, key: &str) -> String {
+    if section.is_empty() {
+        key.to_string()
+    } else {
+        format!("{section}.{key}")
+    }
+}
+
+fn article(ty: &str) -> String {
+    match ty.chars().next() {
+        Some('a' | 'e' | 'i' | 'o' | 'u') => format!("an {ty}"),
+        _ => format!("a {ty}"),
+    }
+}
+
+/// The candidate closest to `word` by edit distance, if any is close enough to be a likely typo.
+pub fn closest<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(word, c), c))
+        .filter(|(d, c)| *d <= (c.len() / 3).max(1))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+/// Optimal string alignment distance, so swapped letters (`nmae`) count as a single edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
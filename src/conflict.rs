@@ -0,0 +1,216 @@
+//! Finds bindings that cannot all work at once: sounds sharing a trigger, sounds bound to keys
+//! the board itself uses, and chords the terminal cannot tell apart from another key.
+
+use std::collections::HashMap;
+use crossterm::event::{KeyCode, KeyModifiers};
+use crate::app::ACTIONS;
+use crate::assign;
+use crate::binding::{Binding, KeyChord, Trigger};
+use crate::config::Board;
+use crate::input::Caps;
+
+/// Something that wants a trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Owner {
+    /// `bindings[binding]` of `sounds[sound]`
+    Sound { sound: usize, binding: usize },
+    Action(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// What the owners fight over, as the terminal will report it
+    pub trigger: Trigger,
+    /// In the order they are checked, so only the first one ever gets the trigger
+    pub owners: Vec<Owner>,
+}
+
+impl Conflict {
+    pub fn sounds(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.owners.iter().filter_map(|o| match *o {
+            Owner::Sound { sound, binding } => Some((sound, binding)),
+            Owner::Action(_) => None,
+        })
+    }
+}
+
+/// Without the kitty protocol these chords arrive as the same bytes as another key.
+fn terminal_alias(chord: KeyChord) -> Option<KeyChord> {
+    if chord.modifiers != KeyModifiers::CONTROL {
+        return None;
+    }
+    let code = match chord.code {
+        KeyCode::Char('i') => KeyCode::Tab,
+        KeyCode::Char('m') | KeyCode::Char('j') => KeyCode::Enter,
+        KeyCode::Char('[') => KeyCode::Esc,
+        KeyCode::Char('h') => KeyCode::Backspace,
+        _ => return None,
+    };
+    Some(KeyChord::new(code))
+}
+
+/// The trigger as it will actually be seen by the app.
+fn effective(trigger: &Trigger, caps: &Caps) -> Trigger {
+    match trigger {
+        Trigger::Key(chord) if !caps.kitty => Trigger::Key(terminal_alias(*chord).unwrap_or(*chord)),
+        other => other.clone(),
+    }
+}
+
+pub fn detect(board: &Board, caps: &Caps) -> Vec<Conflict> {
+    let mut by_trigger: HashMap<Trigger, Vec<Owner>> = HashMap::new();
+    let mut order = Vec::new();
+    let mut add = |trigger: Trigger, owner| {
+        let owners = by_trigger.entry(trigger.clone()).or_default();
+        if owners.is_empty() {
+            order.push(trigger);
+        }
+        owners.push(owner);
+    };
+
+    // Actions that are checked before sound bindings come first, the rest after them
+    for &(chord, name, before_sounds) in ACTIONS {
+        if before_sounds {
+            add(Trigger::Key(chord), Owner::Action(name));
+        }
+    }
+    for (sound, s) in board.sounds.iter().enumerate() {
+        for (binding, b) in s.bindings.iter().enumerate() {
+            add(effective(&b.trigger, caps), Owner::Sound { sound, binding });
+        }
+    }
+    for &(chord, name, before_sounds) in ACTIONS {
+        if !before_sounds {
+            add(Trigger::Key(chord), Owner::Action(name));
+        }
+    }
+
+    order
+        .into_iter()
+        .filter_map(|trigger| {
+            let owners = by_trigger.remove(&trigger)?;
+            (owners.len() > 1).then_some(Conflict { trigger, owners })
+        })
+        .collect()
+}
+
+/// How to settle a conflict for one of the sounds in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Drop the sound's binding
+    Unbind,
+    /// Give the sound a free key instead
+    Rebind,
+    /// Let the sound have the trigger, and give the other sound in the conflict the free key
+    /// it would have gotten: the two trade their triggers
+    Swap,
+}
+
+/// Applies `resolution` to `bindings[binding]` of `sounds[sound]`, returning which sound got a
+/// new key, and which key, if one was picked.
+pub fn resolve(board: &mut Board, caps: &Caps, sound: usize, binding: usize, resolution: Resolution) -> Option<(usize, KeyChord)> {
+    if resolution == Resolution::Swap {
+        let conflicts = detect(board, caps);
+        let conflict = conflicts.iter().find(|c| c.sounds().any(|s| s == (sound, binding)))?;
+        // The board's own keys can't be moved, so only another sound can give way
+        let (other, other_binding) = conflict.sounds().find(|&s| s != (sound, binding))?;
+        return resolve(board, caps, other, other_binding, Resolution::Rebind);
+    }
+
+    let s = board.sounds.get_mut(sound)?;
+    if binding >= s.bindings.len() {
+        return None;
+    }
+    s.bindings.remove(binding);
+    if resolution == Resolution::Unbind {
+        return None;
+    }
+
+    let taken: Vec<Trigger> = board
+        .sounds
+        .iter()
+        .flat_map(|s| &s.bindings)
+        .map(|b| effective(&b.trigger, caps))
+        .chain(ACTIONS.iter().map(|(chord, _, _)| Trigger::Key(*chord)))
+        .collect();
+    let chord = assign::EASY_KEYS
+        .chars()
+        .map(|c| KeyChord::new(KeyCode::Char(c)))
+        .find(|chord| !taken.contains(&Trigger::Key(*chord)))?;
+    board.sounds[sound].bindings.insert(binding, Binding::key(chord));
+    Some((sound, chord))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binding::{Binding, KeyChord, Trigger};
+    use crate::config::Board;
+    use crate::harness;
+    use crate::input::{Caps, Platform};
+    use super::{detect, resolve, Conflict, Owner, Resolution};
+
+    const CAPS: Caps = Caps { platform: Platform::Unix, key_release: false, kitty: false, mouse: true };
+
+    fn bound(keys: &[&str]) -> Board {
+        let mut board = harness::board(keys.len());
+        for (sound, key) in board.sounds.iter_mut().zip(keys) {
+            sound.bindings.push(Binding::parse_key(key).unwrap());
+        }
+        board
+    }
+
+    fn key(s: &str) -> Trigger {
+        Trigger::Key(KeyChord::parse(s).unwrap())
+    }
+
+    #[test]
+    fn sounds_actions_and_aliases_conflict() {
+        let board = bound(&["a", "b", "a", "ctrl+l", "ctrl+i"]);
+        let sound = |sound| Owner::Sound { sound, binding: 0 };
+        assert_eq!(
+            detect(&board, &CAPS),
+            [
+                // Actions checked before sounds come first, then in the order the board has them
+                Conflict { trigger: key("ctrl+l"), owners: vec![Owner::Action("lock"), sound(3)] },
+                Conflict { trigger: key("a"), owners: vec![sound(0), sound(2)] },
+                Conflict { trigger: key("tab"), owners: vec![sound(4), Owner::Action("view trash")] },
+            ]
+        );
+
+        // The kitty protocol tells ctrl+i from Tab
+        let kitty = Caps { kitty: true, key_release: true, ..CAPS };
+        assert_eq!(detect(&board, &kitty).iter().map(|c| c.trigger.clone()).collect::<Vec<_>>(), [key("ctrl+l"), key("a")]);
+        assert!(detect(&bound(&["a", "b", "ctrl+1"]), &CAPS).is_empty());
+    }
+
+    #[test]
+    fn resolving_settles_the_conflict() {
+        let keys = |board: &Board| board.sounds.iter().map(|s| s.bindings.iter().map(|b| b.label.clone()).collect::<Vec<_>>()).collect::<Vec<_>>();
+
+        let mut board = bound(&["a", "a"]);
+        assert_eq!(resolve(&mut board, &CAPS, 1, 0, Resolution::Unbind), None);
+        assert_eq!(keys(&board), [vec!["a"], vec![]]);
+
+        // A free key, the easiest one to reach first
+        let mut board = bound(&["a", "a"]);
+        let moved = resolve(&mut board, &CAPS, 1, 0, Resolution::Rebind).unwrap();
+        assert_eq!((moved.0, keys(&board)[0].clone()), (1, vec!["a".to_string()]));
+        assert_ne!(board.sounds[1].bindings[0].trigger, key("a"));
+        assert!(detect(&board, &CAPS).is_empty());
+
+        // The other sound gives way, whichever of the two won before
+        for (winner, loser) in [(0, 1), (1, 0)] {
+            let mut board = bound(&["a", "a"]);
+            let (moved, chord) = resolve(&mut board, &CAPS, loser, 0, Resolution::Swap).unwrap();
+            assert_eq!(moved, winner);
+            assert_eq!(board.sounds[loser].bindings[0].trigger, key("a"));
+            assert_eq!(board.sounds[winner].bindings[0].trigger, Trigger::Key(chord));
+            assert!(detect(&board, &CAPS).is_empty());
+        }
+
+        // A key the board uses stays the board's
+        let mut board = bound(&["ctrl+l"]);
+        assert_eq!(resolve(&mut board, &CAPS, 0, 0, Resolution::Swap), None);
+        assert_eq!(keys(&board), [vec!["ctrl+l"]]);
+    }
+}
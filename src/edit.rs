@@ -0,0 +1,262 @@
+//! Editing one sound, `E` on its tile: its name, keys, volume and the rest in a modal. Changes
+//! are made to a copy and put on the board together when saved, so the config never has half an
+//! edit in it.
+
+use std::time::Duration;
+use ratatui::style::Color;
+use crate::binding::{Binding, Trigger};
+use crate::config::{Board, Sound, MAX_VOLUME};
+use crate::settings::{self, Kind, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Key,
+    Volume,
+    Loop,
+    Reverse,
+    Color,
+    Tags,
+    Start,
+    End,
+    FadeIn,
+    Speed,
+    Stretch,
+    MaxLength,
+}
+
+/// The colors a tile can be given here; the config takes any color, like `#ff8800`.
+const COLORS: [Option<Color>; 14] = [
+    None,
+    Some(Color::Red),
+    Some(Color::Green),
+    Some(Color::Yellow),
+    Some(Color::Blue),
+    Some(Color::Magenta),
+    Some(Color::Cyan),
+    Some(Color::LightRed),
+    Some(Color::LightGreen),
+    Some(Color::LightYellow),
+    Some(Color::LightBlue),
+    Some(Color::LightMagenta),
+    Some(Color::LightCyan),
+    Some(Color::White),
+];
+
+impl Field {
+    pub const ALL: [Field; 13] = [Field::Name, Field::Key, Field::Volume, Field::Loop, Field::Reverse, Field::Color, Field::Tags, Field::Start, Field::End, Field::FadeIn, Field::Speed, Field::Stretch, Field::MaxLength];
+
+    /// What the field's message is called, `edit-{id}`.
+    pub fn id(self) -> &'static str {
+        match self {
+            Field::Name => "name",
+            Field::Key => "key",
+            Field::Volume => "volume",
+            Field::Loop => "loop",
+            Field::Reverse => "reverse",
+            Field::Color => "color",
+            Field::Tags => "tags",
+            Field::Start => "start",
+            Field::End => "end",
+            Field::FadeIn => "fade-in",
+            Field::Speed => "speed",
+            Field::Stretch => "stretch",
+            Field::MaxLength => "max-length",
+        }
+    }
+
+    pub fn kind(self) -> Kind {
+        match self {
+            Field::Loop | Field::Reverse => Kind::Toggle,
+            Field::Color => Kind::Choice,
+            _ => Kind::Text,
+        }
+    }
+
+    pub fn value(self, sound: &Sound) -> Value {
+        let text = |s: String| if s.is_empty() { Value::Unset } else { Value::Text(s) };
+        let seconds = |d: Duration| format!("{}", (d.as_secs_f64() * 1000.0).round() / 1000.0);
+        match self {
+            Field::Name => Value::Text(sound.name.clone()),
+            Field::Key => text(keys(sound).join(" ")),
+            Field::Volume => Value::Text(format!("{:.0}%", sound.volume * 100.0)),
+            Field::Loop => if sound.shape.looped { Value::On } else { Value::Off },
+            Field::Reverse => if sound.shape.reversed { Value::On } else { Value::Off },
+            Field::Color => sound.color.map_or(Value::Unset, |c| Value::Text(c.to_string().to_lowercase())),
+            Field::Tags => text(sound.tags.join(", ")),
+            Field::Start if sound.shape.start.is_zero() => Value::Unset,
+            Field::Start => Value::Text(seconds(sound.shape.start)),
+            Field::End => sound.shape.end.map_or(Value::Unset, |end| Value::Text(seconds(end))),
+            Field::FadeIn if sound.shape.fade_in.is_zero() => Value::Unset,
+            Field::FadeIn => Value::Text(seconds(sound.shape.fade_in)),
+            Field::Speed => Value::Text(format!("{}", sound.shape.speed)),
+            Field::Stretch => Value::Text(format!("{}", sound.shape.stretch)),
+            Field::MaxLength => sound.shape.max_length.map_or(Value::Unset, |max| Value::Text(seconds(max))),
+        }
+    }
+
+    /// What editing the field starts from.
+    pub fn text(self, sound: &Sound) -> String {
+        match (self, self.value(sound)) {
+            (Field::Volume, _) => format!("{:.0}", sound.volume * 100.0),
+            (_, Value::Text(text)) => text,
+            _ => String::new(),
+        }
+    }
+
+    /// Turns looping on or off, or steps to the next color (or the one before).
+    pub fn cycle(self, sound: &mut Sound, back: bool) {
+        match self {
+            Field::Loop => sound.shape.looped = !sound.shape.looped,
+            Field::Reverse => sound.shape.reversed = !sound.shape.reversed,
+            Field::Color => sound.color = settings::step(&COLORS, sound.color, back),
+            _ => {}
+        }
+    }
+
+    /// Sets the field from what was typed, where nothing unsets it.
+    pub fn set(self, sound: &mut Sound, text: &str) -> Result<(), String> {
+        let text = text.trim();
+        match self {
+            Field::Name if text.is_empty() => return Err("a sound needs a name".to_string()),
+            Field::Name => sound.name = text.to_string(),
+            Field::Key => {
+                let keys = text.split_whitespace().map(|key| Binding::parse_key(key).ok_or_else(|| format!("unknown key {key:?}"))).collect::<Result<Vec<_>, _>>()?;
+                // Only the keys change, the sound's other triggers stay as they are
+                sound.bindings.retain(|b| !matches!(b.trigger, Trigger::Key(_)));
+                sound.bindings.splice(0..0, keys);
+            }
+            Field::Volume => {
+                let percent: f32 = text.trim_end_matches('%').trim().parse().map_err(|_| format!("{text:?} isn't a volume in percent"))?;
+                if !(0.0..=MAX_VOLUME * 100.0).contains(&percent) {
+                    return Err(format!("the volume goes from 0% to {:.0}%", MAX_VOLUME * 100.0));
+                }
+                sound.volume = percent / 100.0;
+            }
+            Field::Tags => sound.tags = text.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect(),
+            Field::Start => sound.shape.start = seconds(text)?.unwrap_or_default(),
+            Field::End => sound.shape.end = seconds(text)?,
+            Field::FadeIn => sound.shape.fade_in = seconds(text)?.unwrap_or_default(),
+            Field::Speed if text.is_empty() => sound.shape.speed = 1.0,
+            Field::Speed => sound.shape.speed = text.parse().map_err(|_| format!("{text:?} isn't a speed, like 1.5"))?,
+            Field::Stretch if text.is_empty() => sound.shape.stretch = 1.0,
+            Field::Stretch => sound.shape.stretch = text.parse().map_err(|_| format!("{text:?} isn't a speed, like 0.5"))?,
+            Field::MaxLength => sound.shape.max_length = seconds(text)?.filter(|max| !max.is_zero()),
+            Field::Loop | Field::Reverse | Field::Color => {}
+        }
+        Ok(())
+    }
+}
+
+/// The edit modal: the sound being edited and the copy that is changed.
+#[derive(Debug)]
+pub struct Edit {
+    /// Where the sound is on the board
+    pub sound: usize,
+    /// The sound as edited so far
+    pub draft: Sound,
+    pub selected: usize,
+    /// The text being typed into the selected field
+    pub editing: Option<String>,
+}
+
+impl Edit {
+    pub fn new(board: &Board, sound: usize) -> Option<Self> {
+        Some(Self { sound, draft: board.sounds.get(sound)?.clone(), selected: 0, editing: None })
+    }
+
+    pub fn field(&self) -> Field {
+        Field::ALL[self.selected.min(Field::ALL.len() - 1)]
+    }
+
+    /// Whether anything was changed since the modal opened.
+    pub fn changed(&self, board: &Board) -> bool {
+        board.sounds.get(self.sound).is_some_and(|sound| Sound { plays: self.draft.plays, ..sound.clone() } != self.draft)
+    }
+}
+
+fn keys(sound: &Sound) -> Vec<&str> {
+    sound.bindings.iter().filter(|b| matches!(b.trigger, Trigger::Key(_))).map(|b| b.label.as_str()).collect()
+}
+
+fn seconds(text: &str) -> Result<Option<Duration>, String> {
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let error = || format!("{text:?} isn't a number of seconds");
+    let secs: f64 = text.trim_end_matches('s').trim().parse().map_err(|_| error())?;
+    Duration::try_from_secs_f64(secs).map(Some).map_err(|_| error())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use ratatui::style::Color;
+    use crate::app::View;
+    use crate::config::Board;
+    use crate::harness::{self, Harness};
+    use super::Field;
+
+    #[test]
+    fn fields_are_read_and_changed() {
+        let mut sound = harness::board(1).sounds.remove(0);
+        Field::Key.set(&mut sound, "a ctrl+1").unwrap();
+        assert_eq!(Field::Key.text(&sound), "a ctrl+1");
+        assert!(Field::Key.set(&mut sound, "notakey").is_err());
+        assert_eq!(sound.bindings.len(), 2);
+
+        Field::Volume.set(&mut sound, "50%").unwrap();
+        assert_eq!(sound.volume, 0.5);
+        assert!(Field::Volume.set(&mut sound, "900").is_err());
+
+        Field::End.set(&mut sound, "1.5s").unwrap();
+        assert_eq!(sound.shape.end, Some(Duration::from_millis(1500)));
+        Field::End.set(&mut sound, "").unwrap();
+        assert_eq!(sound.shape.end, None);
+
+        Field::Color.cycle(&mut sound, false);
+        assert_eq!(sound.color, Some(Color::Red));
+        Field::Color.cycle(&mut sound, true);
+        assert_eq!(sound.color, None);
+    }
+
+    #[test]
+    fn edits_are_saved_together() {
+        let mut h = Harness::new(harness::board(2), 80, 24).loaded();
+        h.press(KeyCode::Right);
+        h.press_with(KeyCode::Char('E'), KeyModifiers::SHIFT);
+        assert_eq!(h.app.view, View::Edit);
+
+        h.press(KeyCode::Enter);
+        for _ in 0.."sound 1".len() {
+            h.press(KeyCode::Backspace);
+        }
+        for c in "airhorn".chars() {
+            h.press(KeyCode::Char(c));
+        }
+        h.press(KeyCode::Enter);
+        // Loop
+        for _ in 0..3 {
+            h.press(KeyCode::Down);
+        }
+        h.press(KeyCode::Enter);
+        // Nothing is on the board until it is saved
+        assert_eq!(h.app.board.sounds[1].name, "sound 1");
+        assert!(h.screen().contains("airhorn"));
+
+        h.press(KeyCode::Char('s'));
+        assert_eq!(h.app.view, View::Board);
+        let saved = Board::load(&h.app.paths.config).unwrap().0;
+        assert_eq!(saved.sounds[1].name, "airhorn");
+        assert!(saved.sounds[1].shape.looped);
+
+        // Discarded without saving
+        h.press_with(KeyCode::Char('E'), KeyModifiers::SHIFT);
+        h.press(KeyCode::Enter);
+        h.press(KeyCode::Char('x'));
+        h.press(KeyCode::Enter);
+        h.press(KeyCode::Esc);
+        assert_eq!(h.app.board.sounds[1].name, "airhorn");
+    }
+}
@@ -0,0 +1,169 @@
+//! Fetching things from the web. The board speaks plain HTTP itself for webhooks, but sites to
+//! get sounds from are all https, so this hands those requests to curl.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use color_eyre::eyre::{bail, eyre, Context};
+
+const MAX_TIME: &str = "60";
+
+/// The status code and body of a GET request.
+pub fn get(url: &str) -> color_eyre::Result<(u16, Vec<u8>)> {
+    request("GET", url, None, None)
+}
+
+/// The status code and body of a `method` request to `url`, logged in with `login` as
+/// `user:password`, sending `body`.
+pub fn request(method: &str, url: &str, login: Option<&str>, body: Option<&[u8]>) -> color_eyre::Result<(u16, Vec<u8>)> {
+    let mut command = Command::new("curl");
+    // The status code on a line of its own after the body
+    command.args(["--silent", "--location", "--max-time", MAX_TIME, "--write-out", "\n%{http_code}"]);
+    if method != "GET" {
+        command.args(["--request", method]);
+    }
+    // On the command line anyone could read it from the process list
+    let login = login.map(Login::write).transpose()?;
+    if let Some(login) = &login {
+        command.arg("--config").arg(&login.0);
+    }
+    if body.is_some() {
+        command.args(["--data-binary", "@-"]);
+    }
+    let mut child = command
+        // Never taken for an option of curl's own, whatever it starts with
+        .arg("--url")
+        .arg(url)
+        .stdin(if body.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .wrap_err("run curl")?;
+    if let (Some(body), Some(mut stdin)) = (body, child.stdin.take()) {
+        stdin.write_all(body).wrap_err("send to curl")?;
+    }
+    let mut body = child.wait_with_output().wrap_err("run curl")?.stdout;
+    drop(login);
+    let split = body.iter().rposition(|&b| b == b'\n').ok_or_else(|| eyre!("couldn't reach {}", host(url)))?;
+    let code = String::from_utf8_lossy(&body[split + 1..]).trim().parse().unwrap_or(0);
+    body.truncate(split);
+    if code == 0 {
+        bail!("couldn't reach {}", host(url));
+    }
+    Ok((code, body))
+}
+
+/// Downloads `url` to `path` in the background. Only from the web: urls come from search results
+/// and the clipboard, which mustn't get at files or anything else curl speaks.
+pub fn download(url: &str, path: PathBuf) -> Receiver<color_eyre::Result<PathBuf>> {
+    let (tx, rx) = mpsc::channel();
+    let url = url.to_string();
+    thread::spawn(move || {
+        let result = (|| {
+            if !web(&url) {
+                bail!("not downloading {url}, only http(s) urls");
+            }
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).wrap_err_with(|| format!("create {}", dir.display()))?;
+            }
+            let status = Command::new("curl")
+                .args(["--fail", "--silent", "--location", "--max-time", MAX_TIME, "--output"])
+                .arg(&path)
+                .arg("--url")
+                .arg(&url)
+                .stdin(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .wrap_err("run curl")?;
+            if !status.success() {
+                bail!("couldn't download {url}");
+            }
+            Ok(path)
+        })();
+        let _ = tx.send(result);
+    });
+    rx
+}
+
+/// A curl config file with a `user:password` in it, that only we can read, for as long as it
+/// is kept.
+struct Login(PathBuf);
+
+impl Login {
+    fn write(login: &str) -> color_eyre::Result<Self> {
+        static WRITTEN: AtomicUsize = AtomicUsize::new(0);
+        let n = WRITTEN.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("soundboard-login-{}-{n}", std::process::id()));
+        let mut options = OpenOptions::new();
+        // Never one that is there already, which someone else could have made to read along
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path).wrap_err("write the login for curl")?;
+        let written = Self(path);
+        let quoted = login.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r");
+        writeln!(file, "user = \"{quoted}\"").wrap_err("write the login for curl")?;
+        Ok(written)
+    }
+}
+
+impl Drop for Login {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Whether `url` is on the web, rather than a file or anything else curl could get at.
+pub fn web(url: &str) -> bool {
+    let scheme = url.split_once("://").map_or("", |(scheme, _)| scheme);
+    scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")
+}
+
+/// Escapes `s` for use in a query string.
+pub fn encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split('/').next().unwrap_or(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use crate::harness;
+    use super::{download, Login};
+
+    #[test]
+    fn logins_are_private_and_cleaned_up() {
+        let login = Login::write("me:p\\a\"ss").unwrap();
+        let path = login.0.clone();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "user = \"me:p\\\\a\\\"ss\"\n");
+        #[cfg(unix)]
+        assert_eq!(std::os::unix::fs::PermissionsExt::mode(&fs::metadata(&path).unwrap().permissions()) & 0o777, 0o600);
+        drop(login);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn downloads_are_only_from_the_web() {
+        let dir = harness::scratch("fetch");
+        for url in ["file:///etc/passwd", "scp://host/x", "-o/tmp/x", "/etc/passwd"] {
+            let err = download(url, dir.join("x")).recv().unwrap().unwrap_err().to_string();
+            assert!(err.contains("only http(s) urls"), "{url}: {err}");
+        }
+        assert!(!dir.join("x").exists());
+    }
+}
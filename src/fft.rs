@@ -0,0 +1,65 @@
+//! The fast Fourier transform, for the scope's spectrum and for time stretching. Complex
+//! numbers are `(re, im)`.
+
+use std::f32::consts::PI;
+
+/// Transforms `x`, a power of two complex numbers, into its spectrum in place.
+pub fn forward(x: &mut [(f32, f32)]) {
+    let n = x.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            x.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (wr, wi) = ((angle * k as f32).cos(), (angle * k as f32).sin());
+                let (a, b) = (x[start + k], x[start + k + len / 2]);
+                let (br, bi) = (b.0 * wr - b.1 * wi, b.0 * wi + b.1 * wr);
+                x[start + k] = (a.0 + br, a.1 + bi);
+                x[start + k + len / 2] = (a.0 - br, a.1 - bi);
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Transforms the spectrum `x` back, the other way around from [`forward`].
+pub fn inverse(x: &mut [(f32, f32)]) {
+    for c in x.iter_mut() {
+        c.1 = -c.1;
+    }
+    forward(x);
+    let n = x.len() as f32;
+    for c in x.iter_mut() {
+        *c = (c.0 / n, -c.1 / n);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{forward, inverse};
+
+    #[test]
+    fn the_inverse_undoes_the_transform() {
+        let samples: Vec<(f32, f32)> = (0..64).map(|i| (((i * 7) % 13) as f32 - 6.0, 0.0)).collect();
+        let mut x = samples.clone();
+        forward(&mut x);
+        // A constant is all in the first bin
+        let mut constant = vec![(1.0, 0.0); 8];
+        forward(&mut constant);
+        assert!((constant[0].0 - 8.0).abs() < 1e-5 && constant[1..].iter().all(|c| c.0.abs() < 1e-5 && c.1.abs() < 1e-5));
+        inverse(&mut x);
+        assert!(x.iter().zip(&samples).all(|(a, b)| (a.0 - b.0).abs() < 1e-4 && a.1.abs() < 1e-4));
+    }
+}
@@ -0,0 +1,87 @@
+//! A named pipe that plays whatever sound name is written to it, for shell scripts and cron jobs:
+//! `echo airhorn > /tmp/soundboard`.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+#[cfg(unix)]
+use std::sync::{atomic::AtomicBool, Arc};
+
+/// Reads the pipe until dropped.
+pub struct Fifo {
+    path: PathBuf,
+    /// Whether the pipe was made by us, and so should be cleaned up again
+    created: bool,
+    #[cfg(unix)]
+    stop: Arc<AtomicBool>,
+    /// Our own end for writing, to wake up the reader when stopping
+    #[cfg(unix)]
+    waker: std::fs::File,
+    /// Each line written to the pipe, trimmed
+    pub names: Receiver<String>,
+}
+
+#[cfg(unix)]
+impl Drop for Fifo {
+    fn drop(&mut self) {
+        use std::io::Write;
+        use std::sync::atomic::Ordering;
+
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.waker.write_all(b"\n");
+        if self.created {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(unix)]
+pub fn open(path: &Path) -> color_eyre::Result<Fifo> {
+    use std::ffi::CString;
+    use std::fs::OpenOptions;
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::FileTypeExt;
+    use std::sync::atomic::Ordering;
+    use color_eyre::eyre::{bail, Context};
+
+    let created = match std::fs::metadata(path) {
+        Ok(meta) if meta.file_type().is_fifo() => false,
+        Ok(_) => bail!("{} exists and is not a named pipe", path.display()),
+        Err(_) => {
+            let c_path = CString::new(path.as_os_str().as_bytes()).wrap_err("fifo path")?;
+            // Only the user may write to it, anyone else could play sounds otherwise
+            if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+                return Err(std::io::Error::last_os_error()).wrap_err_with(|| format!("create {}", path.display()));
+            }
+            true
+        }
+    };
+
+    // Opening for writing too keeps the pipe from reporting the end of its input every time a
+    // writer closes it, and it can't block waiting for a writer to show up
+    let file = OpenOptions::new().read(true).write(true).open(path).wrap_err_with(|| format!("open {}", path.display()))?;
+    let waker = file.try_clone().wrap_err("fifo")?;
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let (tx, names) = std::sync::mpsc::channel();
+    let stopped = stop.clone();
+    std::thread::spawn(move || {
+        for line in BufReader::new(file).lines() {
+            let Ok(line) = line else { break };
+            if stopped.load(Ordering::Relaxed) {
+                break;
+            }
+            let name = line.trim();
+            if !name.is_empty() && tx.send(name.to_string()).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(Fifo { path: path.to_path_buf(), created, stop, waker, names })
+}
+
+#[cfg(not(unix))]
+pub fn open(_path: &Path) -> color_eyre::Result<Fifo> {
+    color_eyre::eyre::bail!("named pipes are only available on Unix")
+}
@@ -0,0 +1,77 @@
+//! Searching freesound.org. The API needs a key, which anyone with an account can get at
+//! <https://freesound.org/apiv2/apply>.
+//!
+//! What gets downloaded is the high quality MP3 preview of a sound: downloading the original
+//! file needs an OAuth2 login, which means a browser after all.
+
+use color_eyre::eyre::{bail, eyre};
+use crate::search::{Hit, Page, Site};
+use crate::{fetch, json};
+
+const API: &str = "https://freesound.org/apiv2";
+/// Where to go for a key when there is none, in the status bar.
+pub const KEY_HINT: &str = "searching freesound needs an API key, put it in a [freesound] section or set FREESOUND_API_KEY";
+const PAGE_SIZE: u32 = 50;
+
+/// The API key from the config or else the environment.
+pub fn key(configured: Option<&str>) -> Option<String> {
+    configured.map(str::to_string).or_else(|| std::env::var("FREESOUND_API_KEY").ok()).filter(|k| !k.trim().is_empty())
+}
+
+pub fn search(key: &str, query: &str) -> color_eyre::Result<Page> {
+    let url = format!(
+        "{API}/search/text/?query={}&token={}&fields=id,name,username,duration,license,previews&page_size={PAGE_SIZE}",
+        fetch::encode(query),
+        fetch::encode(key),
+    );
+    let (code, body) = fetch::get(&url)?;
+    results(code, &String::from_utf8_lossy(&body))
+}
+
+fn results(code: u16, body: &str) -> color_eyre::Result<Page> {
+    let value = json::parse(body);
+    // Errors come with a `detail` explaining them
+    let detail = value.as_ref().ok().and_then(|v| v.get("detail")).and_then(json::Value::as_str).unwrap_or_default();
+    match code {
+        200 => {}
+        401 => bail!("freesound refused the API key: {detail}"),
+        429 => bail!("freesound is rate limiting this key, try again in a bit"),
+        _ => bail!("freesound answered {code}: {detail}"),
+    }
+    let value = value.map_err(|e| eyre!("freesound sent something that isn't JSON: {e}"))?;
+    let count = value.get("count").and_then(json::Value::as_f64).map(|c| c as u64);
+    let hits = value
+        .get("results")
+        .and_then(json::Value::as_array)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|r| {
+            let text = |key: &str| r.get(key).and_then(json::Value::as_str).map(str::to_string);
+            Some(Hit {
+                site: Site::Freesound,
+                id: (r.get("id")?.as_f64()? as u64).to_string(),
+                name: text("name")?,
+                author: text("username"),
+                duration: r.get("duration").and_then(json::Value::as_f64),
+                license: text("license").map(|l| license_name(&l).to_string()),
+                url: r.get("previews")?.get("preview-hq-mp3")?.as_str()?.to_string(),
+            })
+        })
+        .collect();
+    Ok(Page { count, hits })
+}
+
+/// A license URL, shortened to what people call the license.
+fn license_name(url: &str) -> &str {
+    if url.contains("publicdomain/zero") {
+        "CC0"
+    } else if url.contains("licenses/by-nc") {
+        "CC BY-NC"
+    } else if url.contains("licenses/by/") {
+        "CC BY"
+    } else if url.contains("sampling+") {
+        "Sampling+"
+    } else {
+        url
+    }
+}
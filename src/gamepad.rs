@@ -0,0 +1,79 @@
+//! Gamepads and joysticks, through the Linux joystick interface (`/dev/input/js*`), so a cheap
+//! USB controller can be a physical soundboard. Pads plugged in while the board runs are picked
+//! up too.
+
+use std::sync::mpsc::Receiver;
+#[cfg(target_os = "linux")]
+use std::sync::{atomic::AtomicBool, Arc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PadEvent {
+    /// A button being pressed down
+    Button(u8),
+    /// An axis moving to a new position, from -32767 to 32767
+    Axis { axis: u8, from: i16, to: i16 },
+}
+
+/// Reads every pad until dropped.
+pub struct Pads {
+    #[cfg(target_os = "linux")]
+    stop: Arc<AtomicBool>,
+    pub events: Receiver<PadEvent>,
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Pads {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn open() -> color_eyre::Result<Pads> {
+    use color_eyre::eyre::Context;
+
+    // Without the directory there is no joystick driver, and no pad will ever show up
+    std::fs::read_dir("/dev/input").wrap_err("look for gamepads in /dev/input")?;
+
+    let open = |path: &std::path::Path, ()| std::fs::File::open(path).ok();
+    let (stop, events) = crate::hotplug::watch("/dev/input", crate::hotplug::named("js"), open, |file, tx, _| read(file, tx));
+    Ok(Pads { stop, events })
+}
+
+/// Reads `struct js_event`s, see the kernel's `Documentation/input/joydev/joystick-api.rst`.
+#[cfg(target_os = "linux")]
+fn read(mut file: std::fs::File, tx: &std::sync::mpsc::Sender<PadEvent>) {
+    use std::io::Read;
+
+    const BUTTON: u8 = 0x01;
+    const AXIS: u8 = 0x02;
+    /// Set on the events reporting where everything is when the device is opened
+    const INIT: u8 = 0x80;
+
+    let mut axes = [0i16; 256];
+    let mut event = [0u8; 8];
+    while file.read_exact(&mut event).is_ok() {
+        let value = i16::from_ne_bytes([event[4], event[5]]);
+        let (kind, number) = (event[6], event[7]);
+        let sent = match kind & !INIT {
+            // Buttons already held when the pad shows up weren't just pressed
+            BUTTON if kind & INIT == 0 && value == 1 => tx.send(PadEvent::Button(number)),
+            AXIS => {
+                let from = std::mem::replace(&mut axes[number as usize], value);
+                if kind & INIT != 0 {
+                    continue;
+                }
+                tx.send(PadEvent::Axis { axis: number, from, to: value })
+            }
+            _ => continue,
+        };
+        if sent.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn open() -> color_eyre::Result<Pads> {
+    color_eyre::eyre::bail!("gamepads are only supported on Linux for now")
+}
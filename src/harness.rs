@@ -0,0 +1,211 @@
+//! Running the board in tests: the real main loop step on a [`TestBackend`], with events handed
+//! in by the test instead of read from a terminal, plus snapshots of what is drawn and a small
+//! runner for property tests.
+//!
+//! Snapshots live in `src/snapshots`. A missing one is written on the first run; run the tests
+//! with `UPDATE_SNAPSHOTS=1` to rewrite the ones that changed on purpose.
+
+use std::collections::VecDeque;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+use crate::app::App;
+use crate::audio::{Engine, Null};
+use crate::backup;
+use crate::config::{Board, Sound};
+use crate::input::{Caps, Input, Platform};
+use crate::loader::State;
+use crate::paths::Paths;
+use crate::tui::Events;
+
+/// Events a test queued, handed out without waiting.
+#[derive(Default)]
+struct Script(VecDeque<Event>);
+
+impl Events for Script {
+    fn next(&mut self, _timeout: Duration) -> io::Result<Option<Event>> {
+        Ok(self.0.pop_front())
+    }
+}
+
+pub struct Harness {
+    pub app: App,
+    pub terminal: Terminal<TestBackend>,
+    pub input: Input,
+    events: Script,
+    config: PathBuf,
+    /// What the board played
+    pub audio: Arc<Null>,
+}
+
+impl Harness {
+    /// The board in a terminal of `width` by `height`, as a unix terminal without the kitty
+    /// protocol reports input, playing on the [`Null`] backend. The config is a file of its own that is
+    /// removed afterwards.
+    pub fn new(board: Board, width: u16, height: u16) -> Self {
+        static CONFIGS: AtomicUsize = AtomicUsize::new(0);
+        let n = CONFIGS.fetch_add(1, Ordering::Relaxed);
+        let config = std::env::temp_dir().join(format!("soundboard-test-{}-{n}.toml", std::process::id()));
+        let caps = Caps { platform: Platform::Unix, key_release: false, kitty: false, mouse: true };
+        let audio = Arc::new(Null::default());
+        Self {
+            app: App::offline(board, Paths::beside(config.clone()), caps, Engine::new(audio.clone())),
+            terminal: Terminal::new(TestBackend::new(width, height)).unwrap(),
+            input: Input::new(caps),
+            events: Script::default(),
+            config,
+            audio,
+        }
+    }
+
+    /// Waits for every sound to be decoded, so tiles don't say they are loading.
+    pub fn loaded(mut self) -> Self {
+        let until = Instant::now() + Duration::from_secs(10);
+        while self.app.loaded.values().any(|state| matches!(state, State::Loading)) {
+            assert!(Instant::now() < until, "sounds took too long to load");
+            std::thread::sleep(Duration::from_millis(5));
+            self.app.tick(Instant::now());
+        }
+        self.step();
+        self
+    }
+
+    /// Goes through the main loop once, handling the next queued event if there is one.
+    pub fn step(&mut self) {
+        crate::step(&mut self.terminal, &mut self.app, &mut self.input, &mut self.events, Instant::now()).unwrap();
+    }
+
+    /// Handles `event` and draws what came of it.
+    pub fn send(&mut self, event: Event) {
+        self.events.0.push_back(event);
+        self.step();
+        self.step();
+    }
+
+    pub fn press(&mut self, code: KeyCode) {
+        self.press_with(code, KeyModifiers::NONE);
+    }
+
+    pub fn press_with(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        self.send(key(code, modifiers, KeyEventKind::Press));
+    }
+
+    pub fn click(&mut self, column: u16, row: u16) {
+        for kind in [MouseEventKind::Down(MouseButton::Left), MouseEventKind::Up(MouseButton::Left)] {
+            self.send(Event::Mouse(MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE }));
+        }
+    }
+
+    pub fn screen(&self) -> String {
+        crate::simulate::screen(self.terminal.backend().buffer())
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.config);
+        let _ = std::fs::remove_dir_all(backup::folder(&self.config));
+        let _ = std::fs::remove_file(self.app.paths.state.join(format!("{}-plays.json", self.app.paths.stem())));
+    }
+}
+
+/// A directory of its own for a test, removed with everything in it once dropped.
+pub struct Scratch(PathBuf);
+
+impl std::ops::Deref for Scratch {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// An empty directory for the test called `name`, even when a run before it didn't get to
+/// clean up.
+pub fn scratch(name: &str) -> Scratch {
+    static DIRS: AtomicUsize = AtomicUsize::new(0);
+    let n = DIRS.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("soundboard-{name}-{}-{n}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    Scratch(dir)
+}
+
+/// A board of `n` sounds called `sound 0` and on, playing the builtin sounds, bound to nothing.
+pub fn board(n: usize) -> Board {
+    let mut board = Board::builtin();
+    let builtin = std::mem::take(&mut board.sounds);
+    board.sounds = (0..n).map(|i| Sound { name: format!("sound {i}"), bindings: Vec::new(), ..builtin[i % builtin.len()].clone() }).collect();
+    board
+}
+
+pub fn key(code: KeyCode, modifiers: KeyModifiers, kind: KeyEventKind) -> Event {
+    Event::Key(KeyEvent { code, modifiers, kind, state: KeyEventState::NONE })
+}
+
+/// Compares `screen` with the snapshot called `name`, writing it when there is none yet.
+pub fn assert_snapshot(name: &str, screen: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/snapshots").join(format!("{name}.txt"));
+    match std::fs::read_to_string(&path) {
+        Ok(expected) if std::env::var_os("UPDATE_SNAPSHOTS").is_none() => {
+            assert!(expected == screen, "{name} changed, run with UPDATE_SNAPSHOTS=1 if that was on purpose\n\nexpected:\n{expected}\ngot:\n{screen}");
+        }
+        _ => {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, screen).unwrap();
+        }
+    }
+}
+
+/// Random enough for making up test cases, and the same every run.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Zero would stay zero
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// From 0 up to but not including `n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n.max(1) as u64) as usize
+    }
+
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+/// Runs `property` on `cases` different seeds, saying which one failed.
+pub fn check(cases: u64, mut property: impl FnMut(&mut Rng)) {
+    for seed in 0..cases {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| property(&mut Rng::new(seed))));
+        if let Err(e) = result {
+            eprintln!("failed for seed {seed}");
+            panic::resume_unwind(e);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! What was played during the session, and when, for show notes and for editors lining up
+//! sound effects afterwards. How often each sound was played over all sessions is kept here too,
+//! in the state directory, so playing a sound never rewrites the config.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use color_eyre::eyre::Context;
+use crate::config::Board;
+use crate::json::{self, Value};
+use crate::paths::Paths;
+
+/// What set a sound off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Via {
+    Key,
+    Mouse,
+    Cue,
+    Level,
+    Voice,
+    Signal,
+    /// A name written to the named pipe
+    Fifo,
+    /// A command from another program, over `--rpc`
+    Rpc,
+    /// A command published to the MQTT broker
+    Mqtt,
+    Pad,
+    /// A note of a MIDI controller, see [`crate::midi`]
+    Midi,
+    Pedal,
+    /// Played by another sound once it was over, see [`crate::then`]
+    Then,
+    /// Played by a combo of other sounds, see [`crate::combo`]
+    Combo,
+    /// A loop of a scene that was recalled
+    Scene,
+    /// A countdown that ran out, see [`crate::timer`]
+    Timer,
+}
+
+impl Via {
+    pub fn name(self) -> &'static str {
+        match self {
+            Via::Key => "key",
+            Via::Mouse => "mouse",
+            Via::Cue => "cue",
+            Via::Level => "level",
+            Via::Voice => "voice",
+            Via::Signal => "signal",
+            Via::Fifo => "fifo",
+            Via::Rpc => "rpc",
+            Via::Mqtt => "mqtt",
+            Via::Pad => "pad",
+            Via::Midi => "midi",
+            Via::Pedal => "pedal",
+            Via::Then => "then",
+            Via::Combo => "combo",
+            Via::Scene => "scene",
+            Via::Timer => "timer",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub at: SystemTime,
+    /// Since the session started, which is what editors line recordings up by
+    pub offset: Duration,
+    pub sound: String,
+    pub via: Via,
+}
+
+#[derive(Debug)]
+pub struct History {
+    started: Instant,
+    started_at: SystemTime,
+    pub entries: Vec<Entry>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self { started: Instant::now(), started_at: SystemTime::now(), entries: Vec::new() }
+    }
+}
+
+impl History {
+    pub fn push(&mut self, sound: &str, via: Via) {
+        self.entries.push(Entry {
+            at: SystemTime::now(),
+            offset: self.started.elapsed(),
+            sound: sound.to_string(),
+            via,
+        });
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("time,offset,sound,via\n");
+        for e in &self.entries {
+            // Quote names, they can contain commas; quotes are escaped by doubling them
+            let _ = writeln!(out, "{},{:.3},\"{}\",{}", timestamp(e.at), e.offset.as_secs_f64(), e.sound.replace('"', "\"\""), e.via.name());
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = format!("{{\n  \"started\": \"{}\",\n  \"plays\": [", timestamp(self.started_at));
+        for (i, e) in self.entries.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            let _ = write!(
+                out,
+                "{sep}\n    {{\"time\": \"{}\", \"offset\": {:.3}, \"sound\": {}, \"via\": \"{}\"}}",
+                timestamp(e.at),
+                e.offset.as_secs_f64(),
+                json::string(&e.sound),
+                e.via.name(),
+            );
+        }
+        out.push_str(if self.entries.is_empty() { "]\n}\n" } else { "\n  ]\n}\n" });
+        out
+    }
+
+    /// Writes the history with what other sessions left, named after when the session started.
+    pub fn export(&self, paths: &Paths, json: bool) -> color_eyre::Result<PathBuf> {
+        fs::create_dir_all(&paths.state).wrap_err_with(|| format!("create {}", paths.state.display()))?;
+        // Colons are not allowed in file names everywhere
+        let when = timestamp(self.started_at).replace(':', "-");
+        let name = format!("{}-history-{when}.{}", paths.stem(), if json { "json" } else { "csv" });
+        let path = paths.state.join(name);
+        let contents = if json { self.to_json() } else { self.to_csv() };
+        fs::write(&path, contents).wrap_err_with(|| format!("write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+fn plays_path(paths: &Paths) -> PathBuf {
+    paths.state.join(format!("{}-plays.json", paths.stem()))
+}
+
+/// Sets how often the board's sounds were played to what was saved with [`save_plays`]. Sounds
+/// that weren't saved yet keep what their config says, from before the counts were kept here.
+pub fn load_plays(paths: &Paths, board: &mut Board) {
+    let Ok(data) = fs::read_to_string(plays_path(paths)) else { return };
+    let Ok(value) = json::parse(&data) else { return };
+    let Some(Value::Object(plays)) = value.get("plays") else { return };
+    for sound in board.sounds.iter_mut().chain(&mut board.trash) {
+        if let Some(n) = plays.iter().find(|(name, _)| *name == sound.name).and_then(|(_, n)| n.as_f64()) {
+            sound.plays = n as u32;
+        }
+    }
+}
+
+/// Writes how often each of the board's sounds was played, by name.
+pub fn save_plays(paths: &Paths, board: &Board) -> color_eyre::Result<()> {
+    fs::create_dir_all(&paths.state).wrap_err_with(|| format!("create {}", paths.state.display()))?;
+    let plays = board.sounds.iter().chain(&board.trash).filter(|s| s.plays > 0).map(|s| (s.name.clone(), Value::from(s.plays)));
+    let path = plays_path(paths);
+    fs::write(&path, Value::object([("plays", Value::object(plays))]).to_string()).wrap_err_with(|| format!("write {}", path.display()))
+}
+
+/// Formats `t` as an ISO 8601 UTC timestamp, like `2024-05-01T20:15:03Z`.
+pub fn timestamp(t: SystemTime) -> String {
+    let secs = t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86400, secs % 86400);
+
+    // Days to a civil date, from Howard Hinnant's `civil_from_days`
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z", rem / 3600, rem / 60 % 60, rem % 60)
+}
@@ -0,0 +1,67 @@
+//! Maps mouse positions back to what was drawn there.
+//!
+//! Regions are registered while drawing, in drawing order, so when widgets overlap the one drawn
+//! last (the one on top) wins. A modal blocks everything registered before it: clicks outside
+//! the modal hit its backdrop instead of whatever is underneath.
+
+use ratatui::layout::{Position, Rect};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Tile(usize),
+    TrashItem(usize),
+    /// A row in the key assignment picker
+    Strategy(usize),
+    Conflict(usize),
+    Cue(usize),
+    BrowserEntry(usize),
+    CheckOutput(usize),
+    SearchHit(usize),
+    /// An entry in the command palette
+    Command(usize),
+    SettingsTab(usize),
+    Setting(usize),
+    /// A field of the sound being edited
+    EditField(usize),
+    /// One of the config's earlier versions
+    Backup(usize),
+    /// Anywhere outside the current modal
+    Backdrop,
+    /// Covered by something that swallows clicks, like the empty parts of a modal
+    Inert,
+}
+
+#[derive(Debug, Default)]
+pub struct HitMap {
+    regions: Vec<(Rect, Target)>,
+    /// Regions before this index are covered by a modal
+    modal_start: usize,
+}
+
+impl HitMap {
+    pub fn clear(&mut self) {
+        self.regions.clear();
+        self.modal_start = 0;
+    }
+
+    pub fn push(&mut self, rect: Rect, target: Target) {
+        if !rect.is_empty() {
+            self.regions.push((rect, target));
+        }
+    }
+
+    /// Starts a modal covering `area`: from now on only regions pushed after this receive clicks.
+    pub fn begin_modal(&mut self, area: Rect) {
+        self.modal_start = self.regions.len();
+        self.regions.push((area, Target::Backdrop));
+    }
+
+    pub fn hit(&self, column: u16, row: u16) -> Option<Target> {
+        let pos = Position::new(column, row);
+        self.regions[self.modal_start..]
+            .iter()
+            .rev()
+            .find(|(rect, _)| rect.contains(pos))
+            .map(|(_, target)| *target)
+    }
+}
@@ -0,0 +1,62 @@
+//! Devices that can be plugged in while the board runs: gamepads, pedals and MIDI controllers are
+//! all files that show up in a directory, and are read on a thread of their own until they go
+//! away again.
+
+use std::collections::HashSet;
+use std::fs::DirEntry;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How often to look for devices that were plugged in.
+const SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Looks in `dir` for devices until the returned flag is set. `filter` picks the entries that are
+/// devices, with the path to open and what else it knows about them, `open_fn` opens one, and
+/// `reader_fn` reads it on its own thread, sending what it reads, until it is unplugged.
+pub fn watch<T, C, D>(
+    dir: impl AsRef<Path> + Send + 'static,
+    mut filter: impl FnMut(&DirEntry) -> Option<(PathBuf, C)> + Send + 'static,
+    mut open_fn: impl FnMut(&Path, C) -> Option<D> + Send + 'static,
+    reader_fn: impl Fn(D, &Sender<T>, &AtomicBool) + Send + Sync + 'static,
+) -> (Arc<AtomicBool>, Receiver<T>)
+where
+    T: Send + 'static,
+    D: Send + 'static,
+{
+    let (tx, events) = std::sync::mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let open: Arc<Mutex<HashSet<PathBuf>>> = Arc::default();
+    let reader_fn = Arc::new(reader_fn);
+
+    let stopped = stop.clone();
+    std::thread::spawn(move || {
+        while !stopped.load(Ordering::Relaxed) {
+            let found = std::fs::read_dir(dir.as_ref()).into_iter().flatten().flatten();
+            for (path, context) in found.filter_map(|e| filter(&e)) {
+                if open.lock().unwrap().contains(&path) {
+                    continue;
+                }
+                // Not readable (yet, udev may still be setting permissions), try again next time
+                let Some(device) = open_fn(&path, context) else { continue };
+                open.lock().unwrap().insert(path.clone());
+                let (tx, open, stopped, reader_fn) = (tx.clone(), open.clone(), stopped.clone(), reader_fn.clone());
+                std::thread::spawn(move || {
+                    reader_fn(device, &tx, &stopped);
+                    // Unplugged, or the board stopped listening
+                    open.lock().unwrap().remove(&path);
+                });
+            }
+            std::thread::sleep(SCAN_INTERVAL);
+        }
+    });
+
+    (stop, events)
+}
+
+/// The entries whose file name starts with `prefix`, opened where they are.
+pub fn named(prefix: &'static str) -> impl FnMut(&DirEntry) -> Option<(PathBuf, ())> + Send + 'static {
+    move |entry| entry.file_name().to_str().is_some_and(|n| n.starts_with(prefix)).then(|| (entry.path(), ()))
+}
@@ -0,0 +1,407 @@
+//! `soundboard init`: writes a starter board to a directory of its own, a config with comments
+//! on what can be changed, and with `--sample-pack` a handful of sounds to play with instead of
+//! the builtin ones.
+//!
+//! The sample pack's sounds are synthesized here rather than recorded, so they are free of any
+//! rights and dedicated to the public domain (CC0) like the rest of the pack.
+
+use std::f32::consts::TAU;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use color_eyre::eyre::{bail, Context};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use crate::backup;
+use crate::config::DEFAULT_CONFIG_PATH;
+use crate::migrate::CURRENT_VERSION;
+use crate::sha256;
+
+const RATE: u32 = 44_100;
+
+/// Makes a sound's samples, mono at [`RATE`].
+type Synth = fn(&mut Noise) -> Vec<f32>;
+
+/// The sounds of the sample pack, with the key each is bound to.
+const PACK: &[(&str, char, Synth)] = &[
+    ("airhorn", 'a', airhorn),
+    ("rimshot", 'r', rimshot),
+    ("ding", 'd', ding),
+    ("buzzer", 'b', buzzer),
+    ("sad-trombone", 's', sad_trombone),
+    ("whoosh", 'w', whoosh),
+    ("tada", 't', tada),
+    ("laser", 'l', laser),
+];
+
+const LICENSE: &str = "\
+The sounds in this directory were synthesized by `soundboard init --sample-pack`.
+
+To the extent possible under law, their authors have waived all copyright and related or
+neighboring rights to them, under the CC0 1.0 Universal Public Domain Dedication:
+https://creativecommons.org/publicdomain/zero/1.0/
+";
+
+/// Writes the starter board to `dir`, returning where its config is.
+pub fn run(dir: &Path, sample_pack: bool) -> color_eyre::Result<PathBuf> {
+    let config = dir.join(DEFAULT_CONFIG_PATH);
+    if config.exists() {
+        bail!("{} already exists, so it isn't overwritten", config.display());
+    }
+    fs::create_dir_all(dir).wrap_err_with(|| format!("create {}", dir.display()))?;
+
+    let mut sounds = Vec::new();
+    if sample_pack {
+        let folder = dir.join("sounds");
+        fs::create_dir_all(&folder).wrap_err_with(|| format!("create {}", folder.display()))?;
+        let mut noise = Noise(0x9e37_79b9);
+        for &(name, key, make) in PACK {
+            let path = folder.join(format!("{name}.wav"));
+            write(&path, &make(&mut noise))?;
+            // Found from the config's folder, so the board can be moved along with it
+            let hash = sha256::hex(&fs::read(&path).wrap_err_with(|| format!("read {}", path.display()))?);
+            sounds.push(format!("[[sound]]\nname = {name:?}\nfile = \"sounds/{name}.wav\"\nsha256 = \"{hash}\"\nkey = \"{key}\"\n"));
+        }
+        fs::write(folder.join("LICENSE.txt"), LICENSE).wrap_err("write the sounds' license")?;
+    } else {
+        sounds.push("[[sound]]\nname = \"puree\"\nbuiltin = \"puree\"\nkey = \"p\"\n".to_string());
+        sounds.push("[[sound]]\nname = \"windy\"\nbuiltin = \"windy\"\nkey = \"w\"\n".to_string());
+    }
+
+    backup::replace(&config, &template(&sounds))?;
+    Ok(config)
+}
+
+/// A config with the settings worth knowing about commented out, and `sounds` on the board.
+fn template(sounds: &[String]) -> String {
+    let mut config = format!(
+        "\
+# A soundboard. Changes made on the board itself, like volumes, are written back to this file;
+# comments are kept only until then.
+version = {CURRENT_VERSION}
+# Keeps the cache of converted sounds in this folder as well, next to what the board downloads
+# and records, to carry the folder to other machines, like on a USB stick. Sound files in it
+# are found from it either way.
+# portable = true
+
+# Where sounds play: the monitor is what you hear, outputs are played to as well, like the
+# virtual microphone of a voice chat. Leave them out to play on the system's default output.
+# [audio]
+# monitor = \"pulse\"
+# outputs = [\"CABLE Input\"]
+
+# How the tiles are laid out, \"grid\" or \"numpad\", and how smoothly they move.
+# [ui]
+# layout = \"grid\"
+# fps = 60
+# ASCII art to draw above the tiles, and to show when the board starts.
+# header = \"logo.txt\"
+# splash = \"splash.txt\"
+# The time, and how long the show has been going, which ^Y starts over, in the status bar.
+# clock = true
+
+# Sounds in a group stop each other, fading out over `fade` seconds.
+# [[group]]
+# name = \"music\"
+# fade = 0.5
+# While sounds of `ducked-by` play, the group goes `duck` dB down, coming back up over
+# `release` seconds.
+# ducked-by = \"sfx\"
+# duck = 12.0
+# release = 0.3
+# `bus` is where its sounds play, unless they name a bus of their own.
+# bus = \"music\"
+
+# Sounds play on their `bus` and then on the master bus, which have a volume, a `mute` and
+# effects of their own. The volume and mute can be changed while the board runs.
+# [[bus]]
+# name = \"music\"
+# volume = 0.8
+# effects = [\"high-pass 80\", \"low-pass 12000\"]
+# How much of the effects is heard, from 0 for none to 1 for only them.
+# wet = 1.0
+# Recordings get a file with only the sounds on the bus as well, next to the recording.
+# stem = true
+
+# A scene is a state of the buses and loops to go back to with its `key`, which saves what
+# they are at into it with alt held.
+# [[scene]]
+# name = \"intro\"
+# key = \"F1\"
+# buses = [\"music 0.8\", \"sfx 1 muted\"]
+# loops = [\"bed\"]
+
+# Blends between two buses, with a key to move it each way.
+# [crossfader]
+# left = \"music\"
+# right = \"voice\"
+# left-key = \"F7\"
+# right-key = \"F8\"
+
+# A knob or fader of a MIDI controller, moving a bus's `volume` or `wet`, or the crossfader
+# with `crossfader = true`. ^N fills these in as you move them.
+# [[fader]]
+# cc = 7
+# volume = \"master\"
+
+# Lights up the pads that sounds are bound to with `midi`, in the tile's color, blinking while
+# they play, and sends faders back where they are. Made for Launchpads.
+# [midi]
+# feedback = true
+
+# Counts down from its `key` being pressed, in the status bar, and plays `sound` and recalls
+# `scene` once it ran out. Pressing the key again stops it.
+# [[countdown]]
+# name = \"break\"
+# key = \"F6\"
+# duration = 300
+# sound = \"airhorn\"
+# scene = \"intro\"
+
+# Recalls a scene or plays a sound by itself, `at` a time on its `days` (every day without
+# them), or once `after` the board ran that many seconds. `bus` is where the sound plays.
+# [[schedule]]
+# at = \"17:00\"
+# days = [\"friday\"]
+# sound = \"airhorn\"
+# bus = \"sfx\"
+
+# Every sound has a name and a `file` (or one of the `builtin` sounds), and is played by the
+# `key`s it is bound to. `volume` goes from 0 to 2, `group` and `bus` name one of those above.
+# A tile can `run` a shell command instead of playing anything, its output is shown on ^U.
+# With a list of files it plays one at random, `weights = [3, 1]` making some more likely,
+# or each in turn with `order = \"round-robin\"`.
+"
+    );
+    for sound in sounds {
+        let _ = write!(config, "\n{sound}");
+    }
+    config
+}
+
+fn write(path: &Path, samples: &[f32]) -> color_eyre::Result<()> {
+    let spec = WavSpec { channels: 1, sample_rate: RATE, bits_per_sample: 16, sample_format: SampleFormat::Int };
+    let mut writer = WavWriter::create(path, spec).wrap_err_with(|| format!("create {}", path.display()))?;
+    for &sample in samples {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+    }
+    writer.finalize().wrap_err_with(|| format!("write {}", path.display()))
+}
+
+/// White noise from a xorshift, the same every time.
+struct Noise(u32);
+
+impl Noise {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+fn seconds(secs: f32) -> usize {
+    (secs * RATE as f32) as usize
+}
+
+/// A rising edge of 5 ms and an exponential fall, `decay` being about how long it is heard.
+fn envelope(i: usize, decay: f32) -> f32 {
+    let t = i as f32 / RATE as f32;
+    (t / 0.005).min(1.0) * (-t * 5.0 / decay).exp()
+}
+
+fn saw(phase: f32) -> f32 {
+    2.0 * phase.fract() - 1.0
+}
+
+/// Adds `part` into `into` starting at `at` seconds.
+fn mix(into: &mut Vec<f32>, at: f32, part: &[f32]) {
+    let start = seconds(at);
+    if into.len() < start + part.len() {
+        into.resize(start + part.len(), 0.0);
+    }
+    for (out, sample) in into[start..].iter_mut().zip(part) {
+        *out += sample;
+    }
+}
+
+/// Three blasts of a brassy chord.
+fn airhorn(_: &mut Noise) -> Vec<f32> {
+    let blast = |length: f32| -> Vec<f32> {
+        let frames = seconds(length);
+        (0..frames)
+            .map(|i| {
+                let t = i as f32 / RATE as f32;
+                let bend = 1.0 - 0.03 * (-t * 20.0).exp();
+                let chord: f32 = [415.0, 523.0, 622.0].iter().map(|f| saw(f * bend * t)).sum();
+                let edge = (t / 0.02).min(1.0) * ((length - t) / 0.03).min(1.0);
+                chord / 3.0 * edge * 0.5
+            })
+            .collect()
+    };
+    let mut out = Vec::new();
+    mix(&mut out, 0.0, &blast(0.18));
+    mix(&mut out, 0.24, &blast(0.18));
+    mix(&mut out, 0.48, &blast(0.7));
+    out
+}
+
+/// Ba-dum-tss: two toms and a cymbal.
+fn rimshot(noise: &mut Noise) -> Vec<f32> {
+    let tom = |pitch: f32| -> Vec<f32> {
+        let mut phase = 0.0;
+        (0..seconds(0.3))
+            .map(|i| {
+                let t = i as f32 / RATE as f32;
+                phase += pitch * (1.0 + (-t * 30.0).exp()) / RATE as f32;
+                (phase * TAU).sin() * envelope(i, 0.25) * 0.7
+            })
+            .collect()
+    };
+    let mut last = 0.0;
+    let cymbal: Vec<f32> = (0..seconds(1.2))
+        .map(|i| {
+            // The difference of white noise is mostly high frequencies
+            let white = noise.next();
+            let high = white - last;
+            last = white;
+            high * envelope(i, 1.0) * 0.25
+        })
+        .collect();
+    let mut out = Vec::new();
+    mix(&mut out, 0.0, &tom(180.0));
+    mix(&mut out, 0.18, &tom(140.0));
+    mix(&mut out, 0.45, &tom(100.0));
+    mix(&mut out, 0.45, &cymbal);
+    out
+}
+
+/// A bell, with partials that aren't quite harmonic.
+fn ding(_: &mut Noise) -> Vec<f32> {
+    (0..seconds(1.5))
+        .map(|i| {
+            let t = i as f32 / RATE as f32;
+            let partials = [(1.0, 1.0), (2.76, 0.4), (5.4, 0.2)];
+            let bell: f32 = partials.iter().map(|(ratio, level)| (TAU * 1320.0 * ratio * t).sin() * level * (-t * 3.0 * ratio).exp()).sum();
+            bell * envelope(i, 4.0) * 0.4
+        })
+        .collect()
+}
+
+/// A low square wave, the sound of a wrong answer.
+fn buzzer(_: &mut Noise) -> Vec<f32> {
+    let length = 0.8;
+    (0..seconds(length))
+        .map(|i| {
+            let t = i as f32 / RATE as f32;
+            let square = if (110.0 * t).fract() < 0.5 { 1.0 } else { -1.0 };
+            square * 0.3 * (t / 0.01).min(1.0) * ((length - t) / 0.02).min(1.0)
+        })
+        .collect()
+}
+
+/// Wah, wah, wah, waaah.
+fn sad_trombone(_: &mut Noise) -> Vec<f32> {
+    let note = |pitch: f32, length: f32, wobble: bool| -> Vec<f32> {
+        let mut phase = 0.0;
+        (0..seconds(length))
+            .map(|i| {
+                let t = i as f32 / RATE as f32;
+                let vibrato = if wobble { 1.0 + 0.03 * (TAU * 6.0 * t).sin() } else { 1.0 };
+                phase += pitch * vibrato / RATE as f32;
+                // Softened a little by mixing in the sine
+                let tone = saw(phase) * 0.6 + (phase * TAU).sin() * 0.4;
+                tone * 0.4 * (t / 0.04).min(1.0) * ((length - t) / 0.08).min(1.0)
+            })
+            .collect()
+    };
+    let mut out = Vec::new();
+    mix(&mut out, 0.0, &note(233.0, 0.35, false));
+    mix(&mut out, 0.4, &note(220.0, 0.35, false));
+    mix(&mut out, 0.8, &note(208.0, 0.35, false));
+    mix(&mut out, 1.2, &note(196.0, 1.0, true));
+    out
+}
+
+/// Noise through a filter that opens and closes, like something passing by.
+fn whoosh(noise: &mut Noise) -> Vec<f32> {
+    let frames = seconds(0.9);
+    let mut low = 0.0;
+    (0..frames)
+        .map(|i| {
+            let t = i as f32 / frames as f32;
+            let swell = (t * std::f32::consts::PI).sin();
+            low += (noise.next() - low) * (0.02 + 0.2 * swell);
+            low * swell * 1.5
+        })
+        .collect()
+}
+
+/// A quick rising major arpeggio that rings out.
+fn tada(_: &mut Noise) -> Vec<f32> {
+    let note = |pitch: f32| -> Vec<f32> {
+        (0..seconds(1.2))
+            .map(|i| {
+                let t = i as f32 / RATE as f32;
+                ((TAU * pitch * t).sin() + 0.3 * (TAU * 2.0 * pitch * t).sin()) * envelope(i, 1.2) * 0.25
+            })
+            .collect()
+    };
+    let mut out = Vec::new();
+    for (n, pitch) in [523.0, 659.0, 784.0, 1047.0].into_iter().enumerate() {
+        mix(&mut out, n as f32 * 0.08, &note(pitch));
+    }
+    out
+}
+
+/// A fast falling sweep.
+fn laser(_: &mut Noise) -> Vec<f32> {
+    let frames = seconds(0.4);
+    let mut phase = 0.0;
+    (0..frames)
+        .map(|i| {
+            let t = i as f32 / frames as f32;
+            phase += (2000.0 * (0.1f32).powf(t)) / RATE as f32;
+            (phase * TAU).sin() * 0.4 * envelope(i, 0.5)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Board, Source};
+    use crate::conflict;
+    use crate::harness;
+    use crate::input::{Caps, Platform};
+    use crate::loader::Decoded;
+
+    #[test]
+    fn the_sample_pack_is_a_working_board() {
+        let dir = harness::scratch("init");
+        let config = super::run(&dir, true).unwrap();
+        let overwrote = super::run(&dir, true).is_ok();
+        let board = Board::load(&config).unwrap().0;
+        let decoded: Vec<_> = board.sounds.iter().map(|s| s.data().and_then(Decoded::decode)).collect();
+
+        assert!(!overwrote, "the config was overwritten");
+        assert_eq!(board.sounds.len(), super::PACK.len());
+        for (sound, decoded) in board.sounds.iter().zip(decoded) {
+            assert!(matches!(sound.source, Source::File(_)));
+            assert_eq!(sound.bindings.len(), 1, "{} has no key", sound.name);
+            assert!(decoded.is_ok(), "{} doesn't decode", sound.name);
+        }
+        let caps = Caps { platform: Platform::Unix, key_release: false, kitty: false, mouse: true };
+        assert!(conflict::detect(&board, &caps).is_empty());
+    }
+
+    #[test]
+    fn sounds_are_short_and_not_silent() {
+        let mut noise = super::Noise(1);
+        for &(name, _, make) in super::PACK {
+            let samples = make(&mut noise);
+            let length = samples.len() as f32 / super::RATE as f32;
+            let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            assert!((0.2..=3.0).contains(&length), "{name} is {length}s long");
+            assert!(peak > 0.1 && peak <= 1.0, "{name} peaks at {peak}");
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Smooths over differences in how terminals report input.
+//!
+//! Windows consoles can report key releases, and then send a fresh press for every auto-repeat,
+//! while most unix terminals only report presses, unless they speak the kitty keyboard protocol.
+//! Whether a Windows console does depends on the host and its version rather than on anything we
+//! can ask up front, so releases are trusted once one shows up. Mouse capture can be unavailable
+//! or unwanted. Everything above this layer works with [`InputEvent`]s and asks [`Caps`] what the
+//! terminal can do, instead of matching on platforms itself.
+
+use std::collections::HashSet;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, MouseEvent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Windows,
+    Unix,
+}
+
+impl Platform {
+    pub fn detect() -> Self {
+        if cfg!(windows) {
+            Platform::Windows
+        } else {
+            Platform::Unix
+        }
+    }
+}
+
+/// What the terminal we are running in can report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Caps {
+    pub platform: Platform,
+    /// Key releases are reported, so held keys can be told apart from repeated presses. Set by
+    /// [`Input`] when the first one arrives, when the terminal doesn't promise them up front
+    pub key_release: bool,
+    /// The kitty keyboard protocol is active: releases and repeats are reported, and keys like
+    /// Enter and Ctrl+M or Tab and Ctrl+I are distinct
+    pub kitty: bool,
+    pub mouse: bool,
+}
+
+impl Caps {
+    pub fn detect() -> Self {
+        // Conhost and recent Windows Terminals send releases, older Windows Terminals only
+        // presses. Assuming releases there would play a held key once and never again.
+        Self { platform: Platform::detect(), key_release: false, kitty: false, mouse: true }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputEvent {
+    Press(KeyEvent),
+    /// A key that is held down, being auto-repeated by the OS
+    Repeat(KeyEvent),
+    Release(KeyEvent),
+    Mouse(MouseEvent),
+    /// Text pasted into the terminal, which it hands over in one piece
+    Paste(String),
+    Resize,
+}
+
+pub struct Input {
+    pub caps: Caps,
+    held: HashSet<KeyCode>,
+}
+
+impl Input {
+    pub fn new(caps: Caps) -> Self {
+        Self { caps, held: HashSet::new() }
+    }
+
+    pub fn translate(&mut self, event: Event) -> Option<InputEvent> {
+        match event {
+            Event::Key(key) => Some(match key.kind {
+                KeyEventKind::Press if self.caps.key_release => {
+                    // Terminals that report releases report auto-repeat as more presses
+                    if self.held.insert(key.code) {
+                        InputEvent::Press(key)
+                    } else {
+                        InputEvent::Repeat(key)
+                    }
+                }
+                KeyEventKind::Press => InputEvent::Press(key),
+                KeyEventKind::Repeat => InputEvent::Repeat(key),
+                KeyEventKind::Release => {
+                    self.caps.key_release = true;
+                    self.held.remove(&key.code);
+                    InputEvent::Release(key)
+                }
+            }),
+            Event::Mouse(m) if self.caps.mouse => Some(InputEvent::Mouse(m)),
+            Event::Resize(..) => Some(InputEvent::Resize),
+            Event::Paste(text) => Some(InputEvent::Paste(text)),
+            // Releases we never see the press of would leave keys stuck when focus is lost
+            Event::FocusLost => {
+                self.held.clear();
+                None
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! One board per config. A running board listens on a socket of its own, and starting another
+//! with the same config finds it there instead of opening the audio devices a second time:
+//! `soundboard play airhorn` plays on the board that is running, and `soundboard attach` (and
+//! `--rpc`, when a board is running already) talks JSON-RPC to it, see [`crate::rpc`].
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+
+/// Takes requests for the running board until dropped.
+pub struct Server {
+    path: PathBuf,
+    #[cfg(unix)]
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    /// Each line sent to the board, and where the response to it goes. Notifications get
+    /// none, dropping the sender says so.
+    pub requests: Receiver<(String, Sender<String>)>,
+}
+
+#[cfg(unix)]
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        // Wakes up the listener so it sees it should stop
+        let _ = std::os::unix::net::UnixStream::connect(&self.path);
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Where the board for the config at `config` listens: in a folder only the user can get into,
+/// in the runtime directory, or in the temporary directory without one. Anyone who could make
+/// the socket first would hear what is sent to the board, and could answer in its place.
+#[cfg(unix)]
+pub fn socket(config: &Path) -> std::io::Result<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+    let config = std::path::absolute(config).unwrap_or_else(|_| config.to_path_buf());
+    let hash = crate::fnv::fnv1a(config.to_string_lossy().bytes());
+    // SAFETY: getuid can't fail and touches no memory of ours
+    let uid = unsafe { libc::getuid() };
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).filter(|p| p.is_absolute()) {
+        Some(runtime) => runtime.join("soundboard"),
+        None => std::env::temp_dir().join(format!("soundboard-{uid}")),
+    };
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => return Err(e),
+        _ => {}
+    }
+    // One that was there already must be ours, and ours alone, and not a link to somewhere else
+    let meta = std::fs::symlink_metadata(&dir)?;
+    if !meta.is_dir() || meta.uid() != uid || meta.permissions().mode() & 0o077 != 0 {
+        let message = format!("{} can be used by others, so the board won't listen there", dir.display());
+        return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, message));
+    }
+    Ok(dir.join(format!("{hash:016x}.sock")))
+}
+
+#[cfg(unix)]
+pub use unix::{attach, connect, listen, send};
+
+#[cfg(unix)]
+mod unix {
+    use std::io::{self, BufRead, BufReader, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use color_eyre::eyre::{bail, eyre, Context};
+    use crate::json::{self, Value};
+    use super::{socket, Server};
+
+    /// The board running with the config at `config`, if there is one.
+    pub fn connect(config: &Path) -> Option<UnixStream> {
+        UnixStream::connect(socket(config).ok()?).ok()
+    }
+
+    /// Listens for the board with the config at `config`, failing when another already does.
+    pub fn listen(config: &Path) -> color_eyre::Result<Server> {
+        let path = socket(config).wrap_err("find where to listen")?;
+        if UnixStream::connect(&path).is_ok() {
+            bail!("a board is already running with {}", config.display());
+        }
+        // Nobody answers, so it was left behind by a board that didn't get to clean up
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).wrap_err_with(|| format!("listen on {}", path.display()))?;
+        // The folder already keeps others out, this is in case it is ever moved somewhere else
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).wrap_err_with(|| format!("listen on {}", path.display()))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (tx, requests) = mpsc::channel();
+        let stopped = stop.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                let (Ok(stream), tx) = (stream, tx.clone()) else { continue };
+                thread::spawn(move || serve(stream, tx));
+            }
+        });
+        Ok(Server { path, stop, requests })
+    }
+
+    /// Passes what one client sends on to the board, and the board's responses back.
+    fn serve(stream: UnixStream, tx: mpsc::Sender<(String, mpsc::Sender<String>)>) {
+        let Ok(mut out) = stream.try_clone() else { return };
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (reply, response) = mpsc::channel();
+            if tx.send((line, reply)).is_err() {
+                break;
+            }
+            if let Ok(response) = response.recv() {
+                if writeln!(out, "{response}").is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Sends one request to the board running with `config`, returning its result.
+    pub fn send(config: &Path, method: &str, params: Value) -> color_eyre::Result<Value> {
+        let stream = connect(config).ok_or_else(|| eyre!("no board is running with {}", config.display()))?;
+        let request = Value::object([("jsonrpc", "2.0".into()), ("id", Value::Number(1.0)), ("method", method.into()), ("params", params)]);
+        writeln!(&stream, "{request}").wrap_err("send to the board")?;
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).wrap_err("hear back from the board")?;
+        let response = json::parse(&line).map_err(|e| eyre!("the board answered something else than JSON: {e}"))?;
+        if let Some(message) = response.get("error").and_then(|e| e.get("message")).and_then(Value::as_str) {
+            bail!("{message}");
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Passes requests on stdin to the board that is running, and its responses to stdout,
+    /// until stdin closes or the board goes away.
+    pub fn attach(stream: UnixStream) -> color_eyre::Result<()> {
+        let from_board = stream.try_clone().wrap_err("attach to the board")?;
+        let responses = thread::spawn(move || io::copy(&mut &from_board, &mut io::stdout()));
+        for line in io::stdin().lock().lines() {
+            writeln!(&stream, "{}", line?).wrap_err("send to the board")?;
+        }
+        // Hears the last of the responses before going
+        stream.shutdown(std::net::Shutdown::Write)?;
+        let _ = responses.join();
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+pub fn connect(_config: &Path) -> Option<std::convert::Infallible> {
+    None
+}
+
+#[cfg(not(unix))]
+pub fn listen(_config: &Path) -> color_eyre::Result<Server> {
+    color_eyre::eyre::bail!("finding the board that is running is only available on Unix")
+}
+
+#[cfg(not(unix))]
+pub fn send(_config: &Path, _method: &str, _params: crate::json::Value) -> color_eyre::Result<crate::json::Value> {
+    color_eyre::eyre::bail!("finding the board that is running is only available on Unix")
+}
+
+#[cfg(not(unix))]
+pub fn attach(stream: std::convert::Infallible) -> color_eyre::Result<()> {
+    match stream {}
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::time::{Duration, Instant};
+    use crate::harness::{self, Harness};
+    use crate::json::Value;
+
+    #[test]
+    fn a_second_start_hands_over_to_the_first() {
+        let mut h = Harness::new(harness::board(2), 80, 24).loaded();
+        let config = h.app.paths.config.clone();
+        h.app.instance = Some(super::listen(&config).unwrap());
+        assert!(super::listen(&config).is_err());
+        // Where nobody else can get at it
+        let folder = std::fs::metadata(super::socket(&config).unwrap().parent().unwrap()).unwrap();
+        assert_eq!(std::os::unix::fs::PermissionsExt::mode(&folder.permissions()) & 0o777, 0o700);
+
+        let client = std::thread::spawn(move || {
+            let stream = super::connect(&config).unwrap();
+            writeln!(&stream, r#"{{"jsonrpc": "2.0", "id": 7, "method": "play", "params": {{"sound": "sound 1"}}}}"#).unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            line
+        });
+        let until = Instant::now() + Duration::from_secs(10);
+        while !client.is_finished() {
+            assert!(Instant::now() < until, "the board didn't answer");
+            h.app.tick(Instant::now());
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let response = crate::json::parse(&client.join().unwrap()).unwrap();
+        assert_eq!(response.get("id"), Some(&Value::Number(7.0)));
+        assert_eq!(h.app.board.sounds[1].plays, 1);
+
+        // Gone with the board, so the next one can start
+        let config = h.app.paths.config.clone();
+        h.app.instance = None;
+        assert!(super::connect(&config).is_none());
+    }
+}
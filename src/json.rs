@@ -0,0 +1,377 @@
+//! A small JSON implementation, just enough for the control protocol and history exports.
+
+use std::fmt::{self, Write};
+
+/// How deep arrays and objects may be nested. Reading them recurses, and what is read comes from
+/// other programs and the web, which mustn't be able to run the board out of stack.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// In the order the keys were written
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Builds an object from `(key, value)` pairs.
+    pub fn object<K: Into<String>>(entries: impl IntoIterator<Item = (K, Value)>) -> Self {
+        Value::Object(entries.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::String(v.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::String(v)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Boolean(v)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Number(v)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(v: u32) -> Self {
+        Value::Number(v.into())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Self {
+        Value::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Writes compactly, on a single line.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Boolean(b) => write!(f, "{b}"),
+            // JSON has no infinities or NaN
+            Value::Number(n) if !n.is_finite() => f.write_str("null"),
+            Value::Number(n) => write!(f, "{n}"),
+            Value::String(s) => f.write_str(&string(s)),
+            Value::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            }
+            Value::Object(entries) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}:{value}", string(key))?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+/// Quotes and escapes `s` as a JSON string.
+pub fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Parses a single JSON document, with nothing but whitespace after it.
+pub fn parse(input: &str) -> Result<Value, String> {
+    let mut parser = Parser { chars: input.char_indices().peekable(), input, depth: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    match parser.chars.next() {
+        None => Ok(value),
+        Some((at, c)) => Err(format!("unexpected {c:?} after the value at {at}")),
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    input: &'a str,
+    /// The arrays and objects the parser is in
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_ascii_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((at, c)) => Err(format!("expected {expected:?} but found {c:?} at {at}")),
+            None => Err(format!("expected {expected:?} but the input ended")),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        let Some(&(at, c)) = self.chars.peek() else { return Err("expected a value but the input ended".to_string()) };
+        match c {
+            '{' | '[' if self.depth == MAX_DEPTH => Err(format!("nested more than {MAX_DEPTH} deep at {at}")),
+            '{' | '[' => {
+                self.depth += 1;
+                let value = if c == '{' { self.object() } else { self.array() };
+                self.depth -= 1;
+                value
+            }
+            '"' => self.string().map(Value::String),
+            '-' | '0'..='9' => self.number(),
+            _ => {
+                for (word, value) in [("null", Value::Null), ("true", Value::Boolean(true)), ("false", Value::Boolean(false))] {
+                    if self.input[at..].starts_with(word) {
+                        for _ in 0..word.len() {
+                            self.chars.next();
+                        }
+                        return Ok(value);
+                    }
+                }
+                Err(format!("unexpected {c:?} at {at}"))
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+            return Ok(Value::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => {}
+                Some((_, '}')) => return Ok(Value::Object(entries)),
+                Some((at, c)) => return Err(format!("expected ',' or '}}' but found {c:?} at {at}")),
+                None => return Err("unterminated object".to_string()),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => {}
+                Some((_, ']')) => return Ok(Value::Array(items)),
+                Some((at, c)) => return Err(format!("expected ',' or ']' but found {c:?} at {at}")),
+                None => return Err("unterminated array".to_string()),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(out),
+                Some((at, '\\')) => match self.chars.next().map(|(_, c)| c) {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('/') => out.push('/'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('u') => {
+                        let high = self.hex4()?;
+                        // Characters outside the BMP come as a surrogate pair
+                        let code = if (0xd800..0xdc00).contains(&high) {
+                            self.expect('\\')?;
+                            self.expect('u')?;
+                            let low = self.hex4()?;
+                            if !(0xdc00..0xe000).contains(&low) {
+                                return Err(format!("invalid surrogate pair at {at}"));
+                            }
+                            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                        } else {
+                            high
+                        };
+                        out.push(char::from_u32(code).ok_or_else(|| format!("invalid escape at {at}"))?);
+                    }
+                    _ => return Err(format!("invalid escape at {at}")),
+                },
+                Some((_, c)) => out.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.chars.next().and_then(|(_, c)| c.to_digit(16)).ok_or("invalid \\u escape")?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.chars.peek().map_or(self.input.len(), |&(at, _)| at);
+        while self.chars.next_if(|(_, c)| matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9')).is_some() {}
+        let end = self.chars.peek().map_or(self.input.len(), |&(at, _)| at);
+        let text = &self.input[start..end];
+        // Rust reads more than JSON allows, like `01` and `1.`
+        let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        let (mantissa, exponent) = text.split_once(['e', 'E']).map_or((text, None), |(m, e)| (m, Some(e)));
+        let (int, fraction) = mantissa.split_once('.').map_or((mantissa, None), |(i, f)| (i, Some(f)));
+        let int = int.strip_prefix('-').unwrap_or(int);
+        let valid = digits(int)
+            && (int == "0" || !int.starts_with('0'))
+            && fraction.is_none_or(digits)
+            && exponent.is_none_or(|e| digits(e.strip_prefix(['+', '-']).unwrap_or(e)));
+        match text.parse() {
+            Ok(n) if valid => Ok(Value::Number(n)),
+            _ => Err(format!("invalid number {text:?} at {start}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Value, MAX_DEPTH};
+
+    #[test]
+    fn values_are_read() {
+        let value = parse(r#" {"a": [1, -2.5, 3e2, 0.5E-1], "b": {"c": null, "d": true, "e": false}, "f": ""} "#).unwrap();
+        let numbers: Vec<f64> = value.get("a").unwrap().as_array().unwrap().iter().filter_map(Value::as_f64).collect();
+        assert_eq!(numbers, [1.0, -2.5, 300.0, 0.05]);
+        assert_eq!(value.get("b").and_then(|b| b.get("d")).and_then(Value::as_bool), Some(true));
+        assert_eq!(value.get("f").and_then(Value::as_str), Some(""));
+        // Written back the way it was read
+        assert_eq!(parse(&value.to_string()).unwrap(), value);
+    }
+
+    #[test]
+    fn escapes_and_surrogates() {
+        let value = parse(r#""\"\\\/\b\f\n\r\té😀""#).unwrap();
+        assert_eq!(value.as_str(), Some("\"\\/\u{8}\u{c}\n\r\té😀"));
+        assert_eq!(parse(&value.to_string()).unwrap(), value);
+        for broken in [r#""\ud83d""#, r#""\ud83dx""#, r#""\ud83dA""#, r#""\ude00""#, r#""\x""#, r#""\u12g4""#, r#""open"#] {
+            assert!(parse(broken).is_err(), "{broken}");
+        }
+    }
+
+    #[test]
+    fn only_json_numbers() {
+        for number in ["0", "-0", "12", "1.5", "1e9", "1E+2", "-3.25e-2"] {
+            assert!(parse(number).is_ok(), "{number}");
+        }
+        for number in ["01", "1.", "-", "1e", "1e+", "--1", "1.2.3", "1-2", "-.5"] {
+            assert!(parse(number).is_err(), "{number}");
+        }
+    }
+
+    #[test]
+    fn trailing_and_invalid_input() {
+        for broken in ["", "   ", "[1,]", "[1 2]", r#"{"a" 1}"#, r#"{"a": 1,}"#, "{1: 2}", "nul", "[1] [2]", "true false", "{} x", "[", "{"] {
+            assert!(parse(broken).is_err(), "{broken:?}");
+        }
+        assert_eq!(parse(" [ ] \n").unwrap(), Value::Array(Vec::new()));
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse(&nested(MAX_DEPTH + 1)).unwrap_err().contains("nested"));
+        // One that would have run out of stack
+        assert!(parse(&r#"[{"a":"#.repeat(100_000)).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Decoding sounds into memory in the background, so playing one is just handing samples to
+//! the output. The board is usable at once while a pool of threads works through the sounds;
+//! tiles show which are still loading, and a sound triggered before it is ready jumps the queue
+//! and plays as soon as it is.
+//!
+//! With `[audio] memory` set, the decoded sounds are kept within that many megabytes: the ones
+//! played longest ago go back to being decoded while they play, and come back once played again.
+
+use std::collections::VecDeque;
+use std::io::Cursor;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+use color_eyre::eyre::Context;
+use rodio::{Decoder, Source as _};
+use crate::cache::Cache;
+use crate::config::Source;
+
+/// A sound decoded to samples.
+#[derive(Debug, Clone)]
+pub struct Decoded {
+    pub channels: u16,
+    pub rate: u32,
+    pub samples: Arc<[f32]>,
+}
+
+impl Decoded {
+    pub fn decode(data: impl AsRef<[u8]> + Send + Sync + 'static) -> color_eyre::Result<Self> {
+        let decoder = Decoder::new(Cursor::new(data)).wrap_err("decoder")?;
+        let (channels, rate) = (decoder.channels(), decoder.sample_rate());
+        Ok(Self { channels, rate, samples: decoder.convert_samples::<f32>().collect() })
+    }
+
+    /// How long the sound plays.
+    pub fn duration(&self) -> Duration {
+        let frames = self.samples.len() as u64 / self.channels.max(1) as u64;
+        Duration::from_secs_f64(frames as f64 / self.rate.max(1) as f64)
+    }
+
+    /// How many bytes the samples take.
+    pub fn size(&self) -> usize {
+        self.samples.len() * std::mem::size_of::<f32>()
+    }
+
+    /// A source playing the samples from the start, without copying them.
+    pub fn source(&self) -> Samples {
+        Samples { decoded: self.clone(), from: 0, to: self.samples.len(), played: 0, reversed: false }
+    }
+
+    /// A source playing the samples between `start` and `end` (the end when unset) backwards,
+    /// without copying them.
+    pub fn reversed(&self, start: Duration, end: Option<Duration>) -> Samples {
+        let channels = self.channels.max(1) as usize;
+        let frames = self.samples.len() / channels;
+        let at = |t: Duration| ((t.as_secs_f64() * self.rate as f64) as usize).min(frames) * channels;
+        let to = end.map_or(frames * channels, at);
+        Samples { decoded: self.clone(), from: at(start).min(to), to, played: 0, reversed: true }
+    }
+}
+
+pub struct Samples {
+    decoded: Decoded,
+    /// The samples that play, from `from` up to `to`
+    from: usize,
+    to: usize,
+    played: usize,
+    reversed: bool,
+}
+
+impl Iterator for Samples {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.played >= self.to - self.from {
+            return None;
+        }
+        let idx = if self.reversed {
+            // Frame by frame from the last, each with its channels in order
+            let channels = self.decoded.channels.max(1) as usize;
+            let frame = (self.to - self.from) / channels - 1 - self.played / channels;
+            self.from + frame * channels + self.played % channels
+        } else {
+            self.from + self.played
+        };
+        self.played += 1;
+        self.decoded.samples.get(idx).copied()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.to - self.from - self.played.min(self.to - self.from);
+        (left, Some(left))
+    }
+}
+
+impl rodio::Source for Samples {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.decoded.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.decoded.rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        let frames = (self.to - self.from) / self.decoded.channels.max(1) as usize;
+        Some(Duration::from_secs_f64(frames as f64 / self.decoded.rate.max(1) as f64))
+    }
+}
+
+/// Where a sound is in being loaded.
+#[derive(Debug, Clone)]
+pub enum State {
+    Loading,
+    Ready(Decoded),
+    /// Not kept in memory to stay within the budget, with how many bytes it takes decoded
+    Streaming(usize),
+    Failed(String),
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    jobs: VecDeque<Source>,
+    /// Sounds the cache has are decoded from there, which is quicker
+    cache: Option<Cache>,
+    stopped: bool,
+}
+
+/// A pool of threads decoding sounds, until dropped.
+pub struct Loader {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    /// Sounds as they finish decoding
+    pub done: Receiver<(Source, Result<Decoded, String>)>,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        let queue: Arc<(Mutex<Queue>, Condvar)> = Arc::default();
+        let (tx, done) = mpsc::channel();
+        // Leaving a core or so for the audio and the interface
+        let threads = thread::available_parallelism().map_or(2, |n| n.get().saturating_sub(1).clamp(1, 4));
+        for _ in 0..threads {
+            let (queue, tx) = (queue.clone(), tx.clone());
+            thread::spawn(move || work(&queue, &tx));
+        }
+        Self { queue, done }
+    }
+
+    /// Queues `source` for decoding, first in line when it is needed right away.
+    pub fn load(&self, source: Source, urgent: bool) {
+        let (queue, ready) = &*self.queue;
+        let mut queue = queue.lock().unwrap();
+        queue.jobs.retain(|s| *s != source);
+        if urgent {
+            queue.jobs.push_front(source);
+        } else {
+            queue.jobs.push_back(source);
+        }
+        ready.notify_one();
+    }
+
+    pub fn set_cache(&self, cache: Option<Cache>) {
+        self.queue.0.lock().unwrap().cache = cache;
+    }
+
+    /// Forgets about sounds that are no longer wanted, like ones that were removed.
+    pub fn cancel(&self, keep: impl Fn(&Source) -> bool) {
+        self.queue.0.lock().unwrap().jobs.retain(keep);
+    }
+}
+
+impl Drop for Loader {
+    fn drop(&mut self) {
+        let (queue, ready) = &*self.queue;
+        queue.lock().unwrap().stopped = true;
+        ready.notify_all();
+    }
+}
+
+fn work(queue: &(Mutex<Queue>, Condvar), done: &Sender<(Source, Result<Decoded, String>)>) {
+    let (queue, ready) = queue;
+    loop {
+        let (source, cache) = {
+            let mut queue = queue.lock().unwrap();
+            loop {
+                if queue.stopped {
+                    return;
+                }
+                match queue.jobs.pop_front() {
+                    Some(source) => break (source, queue.cache.clone()),
+                    None => queue = ready.wait(queue).unwrap(),
+                }
+            }
+        };
+        let cached = match (&source, &cache) {
+            (Source::File(path), Some(cache)) => cache.get(path).and_then(|entry| std::fs::read(entry).ok()),
+            _ => None,
+        };
+        let decoded = match cached {
+            Some(data) => Decoded::decode(data),
+            None => source.data().and_then(Decoded::decode),
+        };
+        if done.send((source, decoded.map_err(|e| format!("{e:#}")))).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use rodio::Source;
+    use super::Decoded;
+
+    #[test]
+    fn reversed_plays_the_frames_backwards() {
+        let decoded = Decoded { channels: 2, rate: 4, samples: vec![0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5].into() };
+        assert_eq!(decoded.reversed(Duration::ZERO, None).collect::<Vec<_>>(), [3.0, 3.5, 2.0, 2.5, 1.0, 1.5, 0.0, 0.5]);
+        // Only what is between the start and the end
+        let part = decoded.reversed(Duration::from_millis(250), Some(Duration::from_millis(750)));
+        assert_eq!(part.total_duration(), Some(Duration::from_millis(500)));
+        assert_eq!(part.collect::<Vec<_>>(), [2.0, 2.5, 1.0, 1.5]);
+        assert_eq!(decoded.source().count(), 8);
+    }
+}
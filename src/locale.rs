@@ -0,0 +1,126 @@
+//! The words the board shows, in English or Dutch. `[ui] locale` in the config picks one, or
+//! else the environment does, the way other programs read `LC_ALL`, `LC_MESSAGES` and `LANG`.
+//!
+//! The messages live in `src/locale`, a file per language in a small part of the Fluent format:
+//! `id = text` lines, where `{ $name }` is filled in when the message is shown. A message a
+//! translation doesn't have yet is shown in English.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    Dutch,
+}
+
+type Bundle = HashMap<&'static str, &'static str>;
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::English, Locale::Dutch];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::Dutch => "nl",
+        }
+    }
+
+    /// From a language tag like `nl`, `nl-BE` or `nl_NL.UTF-8`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let language = s.split(['_', '-', '.', '@']).next()?.to_ascii_lowercase();
+        Self::ALL.into_iter().find(|l| l.name() == language)
+    }
+
+    /// The language the environment asks for, English when there is no translation for it.
+    pub fn from_env() -> Self {
+        // As with gettext, the first of these that is set decides
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Locale::English => include_str!("locale/en.ftl"),
+            Locale::Dutch => include_str!("locale/nl.ftl"),
+        }
+    }
+
+    fn bundle(self) -> &'static Bundle {
+        static BUNDLES: OnceLock<[Bundle; 2]> = OnceLock::new();
+        &BUNDLES.get_or_init(|| Self::ALL.map(|l| parse(l.source())))[self as usize]
+    }
+
+    /// The message called `id`, or the id itself when not even English has it.
+    pub fn text(self, id: &str) -> &str {
+        self.bundle().get(id).or_else(|| Locale::English.bundle().get(id)).copied().unwrap_or(id)
+    }
+
+    /// The message called `id` with each `{ $name }` in it filled in from `args`.
+    pub fn format(self, id: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut out = String::new();
+        let mut rest = self.text(id);
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}').map(|end| start + end) else { break };
+            out.push_str(&rest[..start]);
+            let name = rest[start + 1..end].trim().trim_start_matches('$');
+            match args.iter().find(|(n, _)| *n == name) {
+                Some((_, value)) => out.push_str(&value.to_string()),
+                None => out.push_str(&rest[start..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+fn parse(source: &'static str) -> Bundle {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(id, text)| (id.trim(), text.trim()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use super::Locale;
+
+    /// The names filled in to each message.
+    fn placeholders(text: &str) -> BTreeSet<&str> {
+        text.split('{').skip(1).filter_map(|s| s.split_once('}')).map(|(name, _)| name.trim()).collect()
+    }
+
+    #[test]
+    fn translations_are_complete() {
+        let english = Locale::English.bundle();
+        for locale in Locale::ALL {
+            let bundle = locale.bundle();
+            for (id, text) in english {
+                let translated = bundle.get(id).unwrap_or_else(|| panic!("{} has no {id}", locale.name()));
+                assert_eq!(placeholders(translated), placeholders(text), "{id} in {}", locale.name());
+            }
+            let extra: Vec<_> = bundle.keys().filter(|id| !english.contains_key(*id)).collect();
+            assert!(extra.is_empty(), "{} has messages English doesn't: {extra:?}", locale.name());
+        }
+    }
+
+    #[test]
+    fn messages_are_filled_in() {
+        assert_eq!(Locale::Dutch.format("trash-builtin", &[("name", &"puree")]), "ingebouwd puree");
+        assert_eq!(Locale::English.format("check-format", &[("channels", &2), ("rate", &48000)]), "2 ch, 48000 Hz");
+        assert_eq!(Locale::Dutch.text("no-such-message"), "no-such-message");
+        assert_eq!(Locale::parse("nl_NL.UTF-8"), Some(Locale::Dutch));
+        assert_eq!(Locale::parse("C"), None);
+    }
+}
@@ -0,0 +1,217 @@
+# The words the board shows, as `id = text` with `{ $name }` where something is filled in.
+# Spacing around a message is up to the code that shows it.
+
+## Tiles
+tile-plays-when-loaded = plays when loaded
+tile-loading = loading
+tile-running = running
+tile-cant-play = can't play
+tile-reversed = backwards
+
+## Trash
+trash-title = Trash
+trash-empty = the trash is empty
+trash-builtin = builtin { $name }
+trash-command = runs { $command }
+
+## Adding files
+browser-title = Add sounds from { $dir }
+browser-on-board = on the board
+
+## Audio check
+check-title = Audio check
+check-monitor = monitor
+check-output = output
+check-mono = mono
+check-format = { $channels } ch, { $rate } Hz
+check-listening = listening...
+check-listening-on = listening on
+check-hint = v plays the tone on the selected output and listens for it here
+
+## Searching online
+search-title = Search { $site }
+search-label = search:
+search-placeholder = words, or the address of a page on the site
+search-searching = searching...
+
+## Reassigning keys
+assign-title = Reassign all keys
+strategy-first-letter = a letter from each sound's name
+strategy-home-row = the home row outwards, in board order
+strategy-frequency = the easiest keys for the most played sounds
+strategy-numpad = the numeric keypad, with tiles arranged like it
+
+## Binding conflicts
+conflicts-title = Binding conflicts
+conflicts-none = no conflicts
+conflicts-vs = vs
+trigger-midi-note = MIDI note { $note }
+trigger-alias = alias { $name }
+trigger-phrase = saying { $phrase }
+trigger-button = gamepad button { $button }
+trigger-axis = gamepad axis { $axis } past { $threshold }
+
+## Cue list
+cues-title = Cue list
+cues-empty = no cues yet, add `[[cue]]` sections to the config
+cue-standby = STANDBY
+cue-next = NEXT
+cue-last = LAST
+cue-in-trash = { $sound } (in trash)
+cues-sound = sound
+cues-key = key
+cues-note = note
+
+## History
+history-title = History
+history-empty = nothing played yet this session
+log-title = Command output
+log-empty = no command has printed anything yet
+scope-spectrum = Spectrum
+scope-waveform = Waveform
+scope-no-signal = no signal
+scope-peak = peak { $db } dB
+history-time = time
+history-session = session
+history-sound = sound
+history-via = via
+
+## MIDI learn
+learn-title = MIDI faders
+learn-volume = { $bus } volume
+learn-wet = { $bus } effects, wet
+learn-crossfader = crossfader
+learn-unmapped = move a knob or fader to map it
+learn-fader = cc { $cc }
+learn-fader-channel = cc { $cc } on channel { $channel }
+
+## Command palette
+commands-title = Everything the board can do
+commands-none = nothing matches
+command-play = play { $sound }
+command-reload = reload config
+command-backups = restore an earlier config
+command-backups-tag = backup
+action-mute = mute
+action-record = record
+action-edit-config = edit config
+action-reassign-keys = reassign keys
+action-binding-conflicts = binding conflicts
+action-lock = lock or unlock
+action-rehearsal = rehearsal
+action-cue-list = cue list
+action-talkover = talkover
+action-history = history
+action-command-output = command output
+action-scope = scope
+action-play-clipboard = play clipboard
+action-browse-files = add files
+action-search-for-sounds = find sounds online
+action-audio-check = audio check
+action-midi-learn = learn MIDI faders
+action-reset-show-timer = reset the show timer
+action-quit = quit
+action-view-trash = view trash
+action-settings = settings
+action-suspend = suspend to the shell
+
+## Backups
+backups-title = Earlier versions of the config
+backups-none = no backups yet, they are made when the config changes
+backup = { $time }, { $count } sound(s)
+backup-broken = { $time }, doesn't load
+
+## Editing a sound
+edit-title = Edit { $sound }
+edit-name = name
+edit-key = keys
+edit-volume = volume
+edit-loop = loop
+edit-reverse = backwards
+edit-color = color
+edit-tags = tags
+edit-start = start (s)
+edit-end = end (s)
+edit-fade-in = fade in (s)
+edit-speed = speed
+edit-stretch = stretch
+edit-max-length = max length (s)
+
+## Settings
+settings-title = Settings
+settings-tab-audio = Audio
+settings-tab-input = Input
+settings-tab-network = Network
+settings-tab-appearance = Appearance
+setting-on = on
+setting-off = off
+setting-unset = not set
+setting-monitor = monitor output
+setting-outputs = external outputs
+setting-resampler = resampler
+setting-memory = memory limit (MB)
+setting-cache = convert sounds on start
+setting-talkover = talkover
+setting-threshold = talkover threshold (dB)
+setting-duck = talkover ducking (dB)
+setting-voice = speech recognizer
+setting-fifo = named pipe
+setting-metrics = metrics address
+setting-mdns = advertise on the network
+setting-artnet = Art-Net target
+setting-mqtt = MQTT broker
+setting-freesound = freesound.org API key
+setting-layout = layout
+setting-long-names = long names
+setting-fps = frames a second
+setting-locale = language
+setting-palette = colors
+setting-screen-reader = screen reader mode
+setting-idle = dim when idle (minutes)
+setting-idle-loops = pause loops when idle
+
+## Status line
+muted = MUTED  ({ $key } to unmute)
+locked = LOCKED
+read-only = NOT SAVING, the config doesn't load
+mic = MIC { $level } dB
+recording = ● REC { $time }
+rehearsal = REHEARSAL
+show-timer = show { $time }
+idle = IDLE, any key wakes it
+conflicts-count = { $count } conflict(s), ^K to review
+keyboard-only = (keyboard only)
+hints-board-locked = Enter: play  Tab: view trash  ^L ^L: unlock  ^P: rehearse  ^G: cues  ^T: talkover  ^O: history  ^D: audio check  ^W: scope  ^A: all actions  ^S: settings  F9: record  F12: mute  Esc: quit
+hints-board = Enter: play  Del: trash  E: edit  R: backwards  Tab: view trash  ^B: add files  ^F: find online  ^V: play clipboard  ^R: reassign keys  ^E: edit config  ^L: lock  ^P: rehearse  ^G: cues  ^T: talkover  ^O: history  ^D: audio check  ^W: scope  ^A: all actions  ^S: settings  F9: record  F12: mute  Esc: quit
+hints-trash = Enter: restore  Del: purge  Tab/Esc: back
+hints-assign = Enter: reassign every key  Esc: back
+hints-cues = Space: GO  Up/Down: move standby  Home: back to the top  ^G/Esc: board
+hints-learn = Up/Down: choose  knob or fader: map  Del: forget  ^N/Esc: board
+hints-history = c: export CSV  j: export JSON  ^O/Esc: board
+hints-log = Del: clear  ^U/Esc: board
+hints-search-editing = Enter: search  Down: results  Tab: other site  Esc: back
+hints-search = Enter: preview  a: add  /: search again  Tab: other site  ^F/Esc: back
+hints-browse = Enter: open/preview  a: add  Backspace: up  ^B/Esc: back
+hints-commands = Enter: run  Up/Down: choose  ^A/Esc: back
+hints-settings-editing = Enter: save  Esc: cancel
+hints-settings = Tab: next tab  Enter: change  Left/Right: other value  ^S/Esc: back
+hints-edit = Enter: change  Left/Right: other value  t: trim to speech  s: save  Esc: discard
+hints-backups = Enter: restore  Up/Down: choose  Esc: back
+hints-check = t/Enter: test tone  n: pink noise  v: check routing  i: other input  ^D/Esc: back
+hints-conflicts = ←/→: pick the sound  u: unbind  m: move to a free key  s: keep it, move the other  Esc: back
+
+## Screen readers
+reader-setting = { $setting }: { $value }
+reader-sound = { $sound }, { $position } of { $count }
+reader-sound-key = { $sound }, key { $key }, { $position } of { $count }
+reader-board-empty = the board has no sounds
+reader-playing = playing { $sound }
+reader-cant-play = can't play { $sound }: { $error }
+
+## Config errors
+config-error = config error
+config-error-field = field
+config-error-expected = expected
+config-error-hint = hint
+config-error-keys = e: open in $EDITOR  r: retry  q: quit
+config-error-keys-backup = e: open in $EDITOR  b: go back to the backup from { $time }  r: retry  q: quit
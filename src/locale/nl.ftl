@@ -0,0 +1,217 @@
+# De woorden die het bord laat zien, in het Nederlands. Zie en.ftl voor hoe dit werkt; wat hier
+# ontbreekt wordt in het Engels getoond.
+
+## Tegels
+tile-plays-when-loaded = speelt na het laden
+tile-loading = laden
+tile-running = bezig
+tile-cant-play = kan niet spelen
+tile-reversed = achterstevoren
+
+## Prullenbak
+trash-title = Prullenbak
+trash-empty = de prullenbak is leeg
+trash-builtin = ingebouwd { $name }
+trash-command = voert { $command } uit
+
+## Bestanden toevoegen
+browser-title = Geluiden toevoegen uit { $dir }
+browser-on-board = op het bord
+
+## Geluidstest
+check-title = Geluidstest
+check-monitor = monitor
+check-output = uitgang
+check-mono = mono
+check-format = { $channels } kan, { $rate } Hz
+check-listening = luisteren...
+check-listening-on = luistert op
+check-hint = v speelt de toon op de gekozen uitgang en luistert hier of hij aankomt
+
+## Online zoeken
+search-title = Zoeken op { $site }
+search-label = zoeken:
+search-placeholder = woorden, of het adres van een pagina op de site
+search-searching = zoeken...
+
+## Toetsen toewijzen
+assign-title = Alle toetsen opnieuw toewijzen
+strategy-first-letter = een letter uit de naam van elk geluid
+strategy-home-row = vanaf de middelste rij naar buiten, in de volgorde van het bord
+strategy-frequency = de makkelijkste toetsen voor de vaakst gespeelde geluiden
+strategy-numpad = het numerieke toetsenblok, met de tegels net zo geschikt
+
+## Conflicten
+conflicts-title = Conflicterende toetsen
+conflicts-none = geen conflicten
+conflicts-vs = tegen
+trigger-midi-note = MIDI-noot { $note }
+trigger-alias = alias { $name }
+trigger-phrase = zeggen { $phrase }
+trigger-button = gamepadknop { $button }
+trigger-axis = gamepadas { $axis } voorbij { $threshold }
+
+## Cuelijst
+cues-title = Cuelijst
+cues-empty = nog geen cues, voeg `[[cue]]`-secties toe aan de config
+cue-standby = STANDBY
+cue-next = VOLGENDE
+cue-last = VORIGE
+cue-in-trash = { $sound } (in prullenbak)
+cues-sound = geluid
+cues-key = toets
+cues-note = notitie
+
+## Geschiedenis
+history-title = Geschiedenis
+history-empty = deze sessie nog niets gespeeld
+log-title = Uitvoer van commando's
+log-empty = nog geen commando heeft iets geprint
+scope-spectrum = Spectrum
+scope-waveform = Golfvorm
+scope-no-signal = geen signaal
+scope-peak = piek { $db } dB
+history-time = tijd
+history-session = sessie
+history-sound = geluid
+history-via = via
+
+## MIDI leren
+learn-title = MIDI-faders
+learn-volume = volume { $bus }
+learn-wet = effecten { $bus }, wet
+learn-crossfader = crossfader
+learn-unmapped = beweeg een knop of fader om hem toe te wijzen
+learn-fader = cc { $cc }
+learn-fader-channel = cc { $cc } op kanaal { $channel }
+
+## Opdrachten
+commands-title = Alles wat het bord kan
+commands-none = niets gevonden
+command-play = { $sound } spelen
+command-reload = config opnieuw laden
+command-backups = een eerdere config terugzetten
+command-backups-tag = reservekopie
+action-mute = dempen
+action-record = opnemen
+action-edit-config = config bewerken
+action-reassign-keys = toetsen opnieuw toewijzen
+action-binding-conflicts = conflicterende toetsen
+action-lock = vergrendelen of ontgrendelen
+action-rehearsal = repeteren
+action-cue-list = cuelijst
+action-talkover = talkover
+action-history = geschiedenis
+action-command-output = uitvoer van commando's
+action-scope = scoop
+action-play-clipboard = klembord spelen
+action-browse-files = bestanden toevoegen
+action-search-for-sounds = geluiden online zoeken
+action-audio-check = geluidstest
+action-midi-learn = MIDI-faders leren
+action-reset-show-timer = showtimer opnieuw starten
+action-quit = stoppen
+action-view-trash = prullenbak bekijken
+action-settings = instellingen
+action-suspend = pauzeren naar de shell
+
+## Reservekopieën
+backups-title = Eerdere versies van de config
+backups-none = nog geen reservekopieën, die worden gemaakt als de config verandert
+backup = { $time }, { $count } geluid(en)
+backup-broken = { $time }, laadt niet
+
+## Geluid bewerken
+edit-title = { $sound } bewerken
+edit-name = naam
+edit-key = toetsen
+edit-volume = volume
+edit-loop = herhalen
+edit-reverse = achterstevoren
+edit-color = kleur
+edit-tags = labels
+edit-start = begin (s)
+edit-end = einde (s)
+edit-fade-in = infaden (s)
+edit-speed = snelheid
+edit-stretch = tempo
+edit-max-length = maximale lengte (s)
+
+## Instellingen
+settings-title = Instellingen
+settings-tab-audio = Geluid
+settings-tab-input = Invoer
+settings-tab-network = Netwerk
+settings-tab-appearance = Weergave
+setting-on = aan
+setting-off = uit
+setting-unset = niet ingesteld
+setting-monitor = meeluisteruitgang
+setting-outputs = externe uitgangen
+setting-resampler = resampler
+setting-memory = geheugenlimiet (MB)
+setting-cache = geluiden omzetten bij het starten
+setting-talkover = talkover
+setting-threshold = talkover-drempel (dB)
+setting-duck = talkover-demping (dB)
+setting-voice = spraakherkenner
+setting-fifo = named pipe
+setting-metrics = adres voor metrics
+setting-mdns = aankondigen op het netwerk
+setting-artnet = Art-Net-doel
+setting-mqtt = MQTT-broker
+setting-freesound = API-sleutel voor freesound.org
+setting-layout = indeling
+setting-long-names = lange namen
+setting-fps = beelden per seconde
+setting-locale = taal
+setting-palette = kleuren
+setting-screen-reader = schermlezermodus
+setting-idle = dimmen na stilte (minuten)
+setting-idle-loops = loops pauzeren bij stilte
+
+## Statusregel
+muted = GEDEMPT  ({ $key } om te ontdempen)
+locked = VERGRENDELD
+read-only = OPSLAAN UIT, de config laadt niet
+mic = MIC { $level } dB
+recording = ● OPN { $time }
+rehearsal = REPETITIE
+show-timer = show { $time }
+idle = INACTIEF, een toets wekt het bord
+conflicts-count = { $count } conflict(en), ^K om te bekijken
+keyboard-only = (alleen toetsenbord)
+hints-board-locked = Enter: spelen  Tab: prullenbak  ^L ^L: ontgrendelen  ^P: repeteren  ^G: cues  ^T: talkover  ^O: geschiedenis  ^D: geluidstest  ^W: scoop  ^A: alles  ^S: instellingen  F9: opnemen  F12: dempen  Esc: stoppen
+hints-board = Enter: spelen  Del: weggooien  E: bewerken  R: achterstevoren  Tab: prullenbak  ^B: bestanden toevoegen  ^F: online zoeken  ^V: klembord spelen  ^R: toetsen toewijzen  ^E: config bewerken  ^L: vergrendelen  ^P: repeteren  ^G: cues  ^T: talkover  ^O: geschiedenis  ^D: geluidstest  ^W: scoop  ^A: alles  ^S: instellingen  F9: opnemen  F12: dempen  Esc: stoppen
+hints-trash = Enter: terugzetten  Del: definitief weggooien  Tab/Esc: terug
+hints-assign = Enter: elke toets opnieuw toewijzen  Esc: terug
+hints-cues = Spatie: GO  Omhoog/Omlaag: standby verplaatsen  Home: terug naar boven  ^G/Esc: bord
+hints-learn = Omhoog/Omlaag: kiezen  knop of fader: toewijzen  Del: vergeten  ^N/Esc: bord
+hints-history = c: CSV exporteren  j: JSON exporteren  ^O/Esc: bord
+hints-log = Del: wissen  ^U/Esc: bord
+hints-search-editing = Enter: zoeken  Omlaag: resultaten  Tab: andere site  Esc: terug
+hints-search = Enter: voorbeluisteren  a: toevoegen  /: opnieuw zoeken  Tab: andere site  ^F/Esc: terug
+hints-browse = Enter: openen/voorbeluisteren  a: toevoegen  Backspace: omhoog  ^B/Esc: terug
+hints-commands = Enter: uitvoeren  Omhoog/Omlaag: kiezen  ^A/Esc: terug
+hints-settings-editing = Enter: opslaan  Esc: annuleren
+hints-settings = Tab: volgend tabblad  Enter: wijzigen  Links/Rechts: andere waarde  ^S/Esc: terug
+hints-edit = Enter: wijzigen  Links/Rechts: andere waarde  t: bijsnijden tot spraak  s: opslaan  Esc: weggooien
+hints-backups = Enter: terugzetten  Omhoog/Omlaag: kiezen  Esc: terug
+hints-check = t/Enter: testtoon  n: roze ruis  v: routering testen  i: andere ingang  ^D/Esc: terug
+hints-conflicts = ←/→: geluid kiezen  u: ontkoppelen  m: naar een vrije toets  s: houden, de ander verplaatsen  Esc: terug
+
+## Schermlezers
+reader-setting = { $setting }: { $value }
+reader-sound = { $sound }, { $position } van { $count }
+reader-sound-key = { $sound }, toets { $key }, { $position } van { $count }
+reader-board-empty = het bord heeft geen geluiden
+reader-playing = speelt { $sound }
+reader-cant-play = kan { $sound } niet spelen: { $error }
+
+## Configfouten
+config-error = configfout
+config-error-field = veld
+config-error-expected = verwacht
+config-error-hint = tip
+config-error-keys = e: openen in $EDITOR  r: opnieuw  q: stoppen
+config-error-keys-backup = e: openen in $EDITOR  b: terug naar de reservekopie van { $time }  r: opnieuw  q: stoppen
@@ -1,202 +1,744 @@
-use std::collections::HashMap;
-use std::io::{Cursor, stdout,};
-use std::thread;
-use color_eyre::eyre::Context;
-use crossterm::{event, ExecutableCommand};
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode, MouseEventKind};
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
-use ratatui::backend::CrosstermBackend;
-use ratatui::{Frame, Terminal};
-use ratatui::layout::{Alignment, Position, Rect};
-use ratatui::prelude::Color;
-use ratatui::style::Style;
-use ratatui::text::Span;
-use ratatui::widgets::{Block, Borders, Padding, Paragraph};
-use rodio::{Decoder, OutputStream, Sink};
-use taffy::{AvailableSpace, Dimension, Display, LengthPercentage, MaxTrackSizingFunction, MinMax, MinTrackSizingFunction, NodeId, PrintTree, Size, TaffyTree, TrackSizingFunction, TraversePartialTree};
-use taffy::GridTrackRepetition::{AutoFit};
-
-macro_rules! data {
-    ($name: literal $(,)?) => {
-        ($name, include_bytes!(concat!("../assets/", $name, ".wav")))
-    };
-
-    ($name: literal, $ext: literal, $(,)?) => {
-        ($name, include_bytes!(concat!("../assets/", $name, $ext)))
-    };
-}
-
-type ConfigEntry = (KeyCode, (&'static str, &'static [u8]));
-
-const CONFIG: &[ConfigEntry] = &[
-    (KeyCode::Char('g'), data!("geen-grote-blij")),
-    (KeyCode::Char('b'), data!("grote-blij")),
-    (KeyCode::Char('p'), data!("puree")),
-    (KeyCode::Char('w'), data!("windy")),
-    (KeyCode::Char('a'), data!("administratiekosten")),
-    (KeyCode::Char('e'), data!("erg")),
-    (KeyCode::Char('c'), data!("betalen")),
-];
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use color_eyre::eyre::{bail, eyre, Context};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::backend::Backend;
+use ratatui::Terminal;
+use crate::app::App;
+use crate::assign::Strategy;
+use crate::audio::{Engine, Null};
+use crate::cache::Cache;
+use crate::config::{Board, ConfigError, Migrated, Source};
+use crate::input::{Caps, Input};
+use crate::json::Value;
+use crate::locale::Locale;
+use crate::palette::Palette;
+use crate::paths::Paths;
+use crate::service::Terminate;
+use crate::show::Shown;
+use crate::sync::Direction;
+use crate::tui::{Events, Term, TerminalEvents};
+use crate::verify::Problem;
 
-fn main() -> color_eyre::Result<()> {
-    color_eyre::install()?;
+mod animation;
+mod app;
+mod artnet;
+mod assign;
+mod audio;
+mod backup;
+mod bench;
+mod binding;
+mod branding;
+mod browser;
+mod bus;
+mod cache;
+mod channels;
+mod check;
+mod clipboard;
+mod combo;
+mod commands;
+mod config;
+mod conflict;
+mod edit;
+mod fetch;
+mod fft;
+mod fifo;
+mod fnv;
+mod freesound;
+mod gamepad;
+#[cfg(test)]
+mod harness;
+mod history;
+#[cfg(target_os = "linux")]
+mod hotplug;
+mod hit;
+mod init;
+mod input;
+mod instance;
+mod json;
+mod loader;
+mod locale;
+mod mdns;
+mod merge;
+mod metrics;
+mod migrate;
+mod mic;
+mod midi;
+mod mqtt;
+mod myinstants;
+mod names;
+mod packs;
+mod palette;
+mod paths;
+mod pedal;
+mod reader;
+mod record;
+mod resample;
+mod rpc;
+mod scope;
+mod search;
+mod service;
+mod settings;
+mod sha256;
+mod shell;
+mod show;
+mod sidechain;
+mod signals;
+mod simulate;
+mod speech;
+mod stretch;
+mod sync;
+mod text;
+mod then;
+mod timer;
+mod toml;
+mod tui;
+mod ui;
+mod vars;
+mod verify;
+mod voice;
+mod webhook;
 
-    enable_raw_mode()?;
-    stdout().execute(EnterAlternateScreen)?.execute(EnableMouseCapture)?;
-    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
-    let mut last_computed_size = None;
-    let mut mapping = HashMap::new();
-    let (mut tree, root_node) = generate_taffy_tree(&mut mapping);
-    let mut targets = HashMap::new();
+enum Command {
+    Run,
+    /// Runs without the terminal interface, taking JSON-RPC commands on stdin
+    Rpc,
+    /// Runs without any interface, for running as a service
+    Daemon,
+    Purge,
+    /// Adds audio files, or every audio file in a directory, to the board
+    Import(Vec<PathBuf>),
+    /// Replaces every sound's key
+    Assign,
+    /// Lists the boards advertised on the local network
+    Discover,
+    /// Converts every sound for faster loading, or empties the cache with `clear`
+    Cache { clear: bool },
+    /// Measures how long triggering, decoding and laying out take, with this many tiles
+    Bench { tiles: usize },
+    /// Runs the board on the script at this path instead of the terminal, printing what happens
+    Simulate(PathBuf),
+    /// Writes a starter board to a directory, with sounds of its own with `--sample-pack`
+    Init { dir: PathBuf, sample_pack: bool },
+    /// Plays these sounds on the board that is already running
+    Play(Vec<String>),
+    /// Talks JSON-RPC to the board that is already running, like `--rpc`
+    Attach,
+    /// Brings up the board wherever it runs, or opens it, for a global hotkey
+    Show,
+    /// Lists the sound packs in the index, or installs the ones named after `install`
+    Packs(Option<Vec<String>>),
+    /// Reports sound files that are missing or changed, or records them as they are with `--update`
+    Verify { update: bool },
+    /// Pushes the board's folder to the remote in `[sync]` and pulls it from there, only one way
+    /// with `push` or `pull`, which `--force` settles conflicts in favour of
+    Sync { direction: Direction, force: bool },
+}
 
-    let mut should_quit = false;
-    while !should_quit {
-        terminal.draw(|frame| {
-            // Recalculate layout if the frame has changed sizing
-            if last_computed_size != Some(frame.size()) {
-                let viewport_size = Size {
-                    width: AvailableSpace::Definite(frame.size().width as f32),
-                    height: AvailableSpace::Definite(frame.size().height as f32),
-                };
-                tree.compute_layout(root_node, viewport_size).unwrap();
+struct Args {
+    paths: Paths,
+    command: Command,
+    mouse: bool,
+    /// Play on the sound hardware, rather than on nothing
+    audio: bool,
+    locked: bool,
+    rehearsal: bool,
+    screen_reader: bool,
+    /// Record from the start, to this file
+    record: Option<PathBuf>,
+    strategy: Strategy,
+    /// Stop the board that is already running first, and take its place
+    take_over: bool,
+    /// Draw the board in this many rows below the cursor, rather than on a screen of its own
+    inline: Option<u16>,
+}
 
-                last_computed_size = Some(frame.size());
-            }
+/// The fewest rows `--inline` leaves the board, enough for a row of tiles and the status line.
+const MIN_INLINE_ROWS: u16 = 5;
+
+fn parse_args() -> color_eyre::Result<Args> {
+    let mut config = None;
+    let (mut data, mut cache, mut state) = (None, None, None);
+    let mut command = Command::Run;
+    let mut mouse = true;
+    let mut audio = true;
+    let mut locked = false;
+    let mut rehearsal = false;
+    let mut screen_reader = false;
+    let mut record = None;
+    let mut strategy = Strategy::FirstLetter;
+    let mut take_over = false;
+    let mut inline = None;
 
-            targets.clear();
-            // Set the PRNG seed so each render uses the same (random) color for each block
-            create_layout(&mut tree, root_node, frame, &mapping, &mut targets);
-        })?;
-        should_quit = handle_events(&targets)?;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" | "--config" => config = Some(PathBuf::from(args.next().ok_or_else(|| eyre!("{arg} needs a path"))?)),
+            "--data-dir" => data = Some(PathBuf::from(args.next().ok_or_else(|| eyre!("{arg} needs a directory"))?)),
+            "--cache-dir" => cache = Some(PathBuf::from(args.next().ok_or_else(|| eyre!("{arg} needs a directory"))?)),
+            "--state-dir" => state = Some(PathBuf::from(args.next().ok_or_else(|| eyre!("{arg} needs a directory"))?)),
+            "--no-mouse" => mouse = false,
+            "--no-audio" => audio = false,
+            "--locked" => locked = true,
+            "--rehearsal" => rehearsal = true,
+            "--screen-reader" => screen_reader = true,
+            "--take-over" => take_over = true,
+            "--inline" => {
+                let rows = args.next().ok_or_else(|| eyre!("{arg} needs a number of rows"))?;
+                inline = Some(rows.parse::<u16>().ok().filter(|&rows| rows >= MIN_INLINE_ROWS).ok_or_else(|| eyre!("{arg} needs at least {MIN_INLINE_ROWS} rows, not {rows:?}"))?);
+            }
+            "--rpc" => command = Command::Rpc,
+            "--no-tui" => command = Command::Daemon,
+            "--simulate" => command = Command::Simulate(args.next().ok_or_else(|| eyre!("{arg} needs a script"))?.into()),
+            "--record" => record = Some(args.next().ok_or_else(|| eyre!("{arg} needs a path"))?.into()),
+            "--strategy" => {
+                let name = args.next().ok_or_else(|| eyre!("{arg} needs a strategy"))?;
+                strategy = Strategy::parse(&name).ok_or_else(|| {
+                    let names: Vec<_> = Strategy::ALL.iter().map(|s| s.name()).collect();
+                    eyre!("unknown strategy {name:?}, expected one of {}", names.join(", "))
+                })?;
+            }
+            "purge" => command = Command::Purge,
+            "import" => command = Command::Import(Vec::new()),
+            "assign" => command = Command::Assign,
+            "play" => command = Command::Play(Vec::new()),
+            "attach" => command = Command::Attach,
+            "show" => command = Command::Show,
+            "discover" => command = Command::Discover,
+            "packs" => command = Command::Packs(None),
+            "cache" => command = Command::Cache { clear: false },
+            "verify" => command = Command::Verify { update: false },
+            "sync" => command = Command::Sync { direction: Direction::Both, force: false },
+            "bench" => command = Command::Bench { tiles: 100 },
+            "init" => command = Command::Init { dir: PathBuf::from("soundboard"), sample_pack: false },
+            other => match &mut command {
+                Command::Import(paths) if !other.starts_with('-') => paths.push(other.into()),
+                Command::Play(sounds) if !other.starts_with('-') => sounds.push(other.to_string()),
+                Command::Cache { clear } if other == "clear" => *clear = true,
+                Command::Verify { update } if other == "--update" => *update = true,
+                Command::Sync { direction, .. } if *direction == Direction::Both && other == "push" => *direction = Direction::Push,
+                Command::Sync { direction, .. } if *direction == Direction::Both && other == "pull" => *direction = Direction::Pull,
+                Command::Sync { force, .. } if other == "--force" => *force = true,
+                Command::Packs(install @ None) if other == "install" => *install = Some(Vec::new()),
+                Command::Packs(Some(names)) if !other.starts_with('-') => names.push(other.to_string()),
+                Command::Bench { tiles } if other.parse::<usize>().is_ok() => *tiles = other.parse()?,
+                Command::Init { sample_pack, .. } if other == "--sample-pack" => *sample_pack = true,
+                Command::Init { dir, .. } if !other.starts_with('-') => *dir = other.into(),
+                _ => bail!("unknown argument {other:?}"),
+            },
+        }
     }
 
-    disable_raw_mode()?;
-    stdout().execute(DisableMouseCapture)?.execute(LeaveAlternateScreen)?;
+    // A config given on the command line keeps its files next to it, as it always did
+    let mut paths = config.map_or_else(Paths::detect, Paths::beside);
+    paths.data = data.unwrap_or(paths.data);
+    paths.cache = cache.or(paths.cache);
+    paths.state = state.unwrap_or(paths.state);
 
-    Ok(())
+    Ok(Args { paths, command, mouse, audio, locked, rehearsal, screen_reader, record, strategy, take_over, inline })
 }
 
-fn generate_taffy_tree(mapping: &mut HashMap<NodeId, ConfigEntry>) -> (TaffyTree, NodeId) {
-    let mut tree: TaffyTree<()> = TaffyTree::new();
+fn main() -> color_eyre::Result<()> {
+    tui::install_hooks()?;
 
-    let mut children = Vec::new();
-    for c in CONFIG {
-        let id = tree.new_leaf(taffy::Style {
-            size: Size { width: Dimension::Auto, height: Dimension::Length(5.0) },
-            display: Display::Block,
-            ..Default::default()
-        }).unwrap();
+    let args = parse_args()?;
+    let terminate = Terminate::install()?;
 
-        mapping.insert(id, *c);
-        children.push(id);
+    if args.take_over && own_board(&args.command) {
+        take_over(&args.paths.config)?;
+    }
+    // A second board would open the same devices and write over the same config
+    if own_board(&args.command) && instance::connect(&args.paths.config).is_some() {
+        bail!("a board is already running with {}, play on it with `soundboard play <sound>` or talk to it with `soundboard attach`", args.paths.config.display());
     }
 
-    // Root node
-    let root_node = tree.new_with_children(
-        taffy::Style {
-            size: Size { width: Dimension::Percent(1.0), height: Dimension::Percent(1.0) },
-            grid_template_columns: vec![TrackSizingFunction::Repeat(AutoFit, vec![MinMax {
-                min: MinTrackSizingFunction::Fixed(LengthPercentage::Length(10.0)),
-                max: MaxTrackSizingFunction::Fixed(LengthPercentage::Length(40.0)),
-            }])],
-            display: Display::Grid,
-            ..Default::default()
+    match args.command {
+        Command::Run => {
+            let board = Board::load(&args.paths.config);
+            let mut caps = Caps::detect();
+            caps.mouse &= args.mouse;
+            let mut terminal = tui::enter(&mut caps, args.inline)?;
+            let result = run(&mut terminal, Input::new(caps), board, &args, &terminate);
+            tui::close(&mut terminal)?;
+            // Where the recording went is still worth knowing once the board is closed
+            if let Some(recorded) = result? {
+                println!("{recorded}");
+            }
+            Ok(())
+        }
+        Command::Attach => match instance::connect(&args.paths.config) {
+            Some(board) => instance::attach(board),
+            None => bail!("no board is running with {}", args.paths.config.display()),
         },
-        &children,
-    ).unwrap();
-
-    (tree, root_node)
-}
-
-fn create_layout(
-    tree: &TaffyTree,
-    node_id: NodeId,
-    frame: &mut Frame,
-    mapping: &HashMap<NodeId, ConfigEntry>,
-    targets: &mut HashMap<Rect, &'static [u8]>,
-) {
-    let layout = tree.get_final_layout(node_id);
-
-    let r = Rect::new(
-        layout.location.x as u16,
-        layout.location.y as u16,
-        layout.size.width as u16,
-        layout.size.height as u16,
-    );
-
-    if let Some((key, (name, data))) = mapping.get(&node_id) {
-        let title = if let KeyCode::Char(c) = key {
-            format!("[{c}]")
-        } else {
-            format!("{:?}", key)
-        };
+        Command::Show => {
+            // Opened the way this was started, only without `show`
+            let forward: Vec<String> = std::env::args().skip(1).filter(|a| a != "show").collect();
+            println!("{}", show::show(&args.paths.config, &forward)?);
+            Ok(())
+        }
+        Command::Play(sounds) => {
+            if sounds.is_empty() {
+                bail!("play needs the name of a sound");
+            }
+            for sound in sounds {
+                instance::send(&args.paths.config, "play", Value::object([("sound", sound.as_str().into())]))?;
+            }
+            Ok(())
+        }
+        Command::Rpc | Command::Daemon => {
+            // Requests go to the board that is running, if there is one
+            if let (Command::Rpc, Some(board)) = (&args.command, instance::connect(&args.paths.config)) {
+                return instance::attach(board);
+            }
+            let board = load(&args.paths.config)?;
+            // Nothing reads the terminal, so there is no mouse and no key releases to care about
+            let caps = Caps { mouse: false, key_release: false, ..Caps::detect() };
+            let mut app = start(board, caps, &args);
+            let result = match args.command {
+                Command::Rpc => rpc::run(&mut app, &terminate),
+                _ => {
+                    service::daemon(&mut app, &terminate);
+                    Ok(())
+                }
+            };
+            service::stop(&app, &terminate);
+            app.flush();
+            if let Some(recorded) = app.stop_recording() {
+                eprintln!("{recorded}");
+            }
+            result
+        }
+        Command::Purge => {
+            let mut board = load(&args.paths.config)?;
+            let count = board.purge_all()?;
+            board.save(&args.paths.config)?;
+            println!("purged {count} sound(s) from the trash");
+            Ok(())
+        }
+        Command::Import(paths) => {
+            let mut board = load(&args.paths.config)?;
+            let before = board.sounds.len();
+            for path in &paths {
+                board.import(path)?;
+            }
+            if board.portable {
+                board.carry(before, args.paths.folder(), &args.paths.sounds())?;
+            }
+            // Named after what they say before they get keys, which go by the name
+            if let Some(command) = board.transcribe.clone() {
+                for idx in before..board.sounds.len() {
+                    let Source::File(path) = &board.sounds[idx].source else { continue };
+                    match names::transcribe(&command, path) {
+                        Ok(Some(words)) => {
+                            let renamed = names::unique(words, |n| board.sounds.iter().chain(&board.trash).any(|s| s.name == n));
+                            board.sounds[idx].name = renamed;
+                        }
+                        Ok(None) => {}
+                        Err(e) => eprintln!("{e:#}"),
+                    }
+                }
+            }
+            // Only the new sounds need keys, what was already bound stays where it is
+            history::load_plays(&args.paths, &mut board);
+            let bound = assign::assign(&mut board, args.strategy, false);
+            board.save(&args.paths.config)?;
+            println!("imported {} sound(s), {bound} got a key ({})", board.sounds.len() - before, args.strategy.name());
+            Ok(())
+        }
+        Command::Packs(install) => {
+            let mut board = load(&args.paths.config)?;
+            let index = board.packs.clone().ok_or_else(|| eyre!("there is no pack index to look in, set `index` in a `[packs]` section of {}", args.paths.config.display()))?;
+            let packs = packs::fetch(&index)?;
+            let dir = args.paths.sounds().join("packs");
+            let Some(names) = install else {
+                for pack in &packs {
+                    let installed = if pack.installed(&dir) { ", installed" } else { "" };
+                    println!("{} ({} sound(s){installed}){}", pack.name, pack.sounds.len(), pack.description.as_ref().map_or(String::new(), |d| format!(": {d}")));
+                }
+                return Ok(());
+            };
+            if names.is_empty() {
+                bail!("packs install needs the name of a pack, `soundboard packs` lists them");
+            }
+            let mut added = 0;
+            for name in &names {
+                let pack = packs.iter().find(|p| p.name == *name).ok_or_else(|| eyre!("there is no pack named {name:?} in {index}"))?;
+                added += packs::install(&mut board, pack, &dir)?;
+            }
+            history::load_plays(&args.paths, &mut board);
+            let bound = assign::assign(&mut board, args.strategy, false);
+            board.save(&args.paths.config)?;
+            println!("installed {added} sound(s), {bound} got a key ({})", args.strategy.name());
+            Ok(())
+        }
+        Command::Verify { update } => {
+            let mut board = load(&args.paths.config)?;
+            if update {
+                let updated = verify::update(&mut board);
+                board.save(&args.paths.config)?;
+                println!("recorded {updated} checksum(s)");
+            }
+            let findings = verify::check(&board, &args.paths.config);
+            for finding in &findings {
+                let path = finding.path.display();
+                match &finding.problem {
+                    Problem::Missing(e) => println!("missing  {}: {path} ({e})", finding.sound),
+                    Problem::Changed => println!("changed  {}: {path}", finding.sound),
+                    Problem::Outside => println!("outside  {}: {path} (not in the portable board's folder, so it stays behind when the folder is moved)", finding.sound),
+                    Problem::Unchecked => {}
+                }
+            }
+            let unchecked = findings.iter().filter(|f| !f.is_trouble()).count();
+            if unchecked > 0 {
+                println!("{unchecked} file(s) have no checksum yet, `soundboard verify --update` records them");
+            }
+            match findings.iter().filter(|f| f.is_trouble()).count() {
+                0 => {
+                    println!("none of the sound files are missing or changed");
+                    Ok(())
+                }
+                trouble => bail!("{trouble} sound file(s) are missing, changed or outside the board's folder"),
+            }
+        }
+        Command::Sync { direction, force } => {
+            if force && direction == Direction::Both {
+                bail!("--force needs `push` or `pull`, to know which side to keep");
+            }
+            let board = load(&args.paths.config)?;
+            let settings = board.sync.as_ref().ok_or_else(|| eyre!("there is nowhere to sync to, set `remote` in a `[sync]` section of {}", args.paths.config.display()))?;
+            let synced = sync::sync(&board, &args.paths, &*sync::remote(settings), direction, force)?;
+            for file in &synced.left_out {
+                println!("not synced  {} (not in the board's folder)", file.display());
+            }
+            if let Some(path) = &synced.merged {
+                println!("merged  {path} with the changes made to it on the remote");
+            }
+            let plan = &synced.plan;
+            for path in &plan.push {
+                println!("pushed  {path}");
+            }
+            for path in &plan.remove {
+                println!("removed {path} from the remote");
+            }
+            for path in &plan.pull {
+                println!("pulled  {path}");
+            }
+            for path in &plan.conflicts {
+                println!("conflict  {path}: changed here and on the remote since the last sync");
+            }
+            if !plan.conflicts.is_empty() {
+                bail!("{} file(s) changed on both sides, `soundboard sync push --force` keeps them as they are here, `soundboard sync pull --force` as they are on the remote", plan.conflicts.len());
+            }
+            match (plan.push.len() + plan.remove.len(), plan.pull.len()) {
+                (0, 0) => println!("already in sync with {}", settings.remote),
+                (pushed, pulled) => println!("pushed {pushed} and pulled {pulled} file(s) with {}", settings.remote),
+            }
+            Ok(())
+        }
+        Command::Discover => {
+            let found = mdns::discover(Duration::from_secs(2))?;
+            if found.is_empty() {
+                println!("no boards found on the local network");
+            }
+            for board in found {
+                let addr = board.addr.map_or(board.host.clone(), |a| a.to_string());
+                println!("{}\t{addr}:{}\t{}", board.name, board.port, board.txt.join(" "));
+            }
+            Ok(())
+        }
+        Command::Cache { clear } => {
+            let board = load(&args.paths.config)?;
+            let cache = Cache::new(board.cache.as_ref().and_then(|c| c.dir.as_deref()).or(args.paths.cache.as_deref()), board.resampler)
+                .ok_or_else(|| eyre!("there is no cache directory, set `dir` in a [cache] section or pass --cache-dir"))?;
+            if clear {
+                println!("removed {} converted sound(s) from {}", cache.clear()?, cache.dir.display());
+                return Ok(());
+            }
+            let (mut converted, mut failed) = (0, 0);
+            for source in board.sounds.iter().flat_map(|s| s.sources()) {
+                let Source::File(path) = source else { continue };
+                match cache.convert(path) {
+                    Ok(true) => {
+                        println!("converted {}", path.display());
+                        converted += 1;
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        eprintln!("{e:#}");
+                        failed += 1;
+                    }
+                }
+            }
+            println!("converted {converted} sound(s) into {}, {failed} failed", cache.dir.display());
+            Ok(())
+        }
+        Command::Simulate(script) => {
+            let board = load(&args.paths.config)?;
+            let script = std::fs::read_to_string(&script).wrap_err_with(|| format!("read {}", script.display()))?;
+            simulate::run(board, &script, &mut std::io::stdout().lock())
+        }
+        Command::Bench { tiles } => {
+            let board = load(&args.paths.config)?;
+            bench::run(&board, tiles);
+            Ok(())
+        }
+        Command::Init { dir, sample_pack } => {
+            let config = init::run(&dir, sample_pack)?;
+            println!("wrote a starter board to {}", config.display());
+            println!("play it with `soundboard -c {}`, or run `soundboard` in {}", config.display(), dir.display());
+            Ok(())
+        }
+        Command::Assign => {
+            let mut board = load(&args.paths.config)?;
+            history::load_plays(&args.paths, &mut board);
+            let bound = assign::assign(&mut board, args.strategy, true);
+            board.save(&args.paths.config)?;
+            println!("reassigned keys for {bound} of {} sound(s) ({})", board.sounds.len(), args.strategy.name());
+            Ok(())
+        }
+    }
+}
 
-        let b = Block::new()
-            .title(title)
-            .borders(Borders::ALL)
-            .style(Style::default().fg(Color::White))
-            .padding(Padding::new(
-                0, // left
-                0, // right
-                r.height / 3, // top
-                0, // bottom
-            ));
+/// Whether the command runs a board of its own, or changes its config.
+fn own_board(command: &Command) -> bool {
+    matches!(command, Command::Run | Command::Daemon | Command::Purge | Command::Import(_) | Command::Assign | Command::Packs(Some(_)) | Command::Verify { update: true })
+        || matches!(command, Command::Sync { direction, .. } if *direction != Direction::Push)
+}
 
-        targets.insert(r, data);
+/// Stops the board running with the config at `config`, if there is one, waiting until it is
+/// gone.
+fn take_over(config: &Path) -> color_eyre::Result<()> {
+    if instance::connect(config).is_none() {
+        return Ok(());
+    }
+    instance::send(config, "quit", Value::Null)?;
+    let until = Instant::now() + Duration::from_secs(5);
+    while instance::connect(config).is_some() {
+        if Instant::now() > until {
+            bail!("the board running with {} didn't stop", config.display());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    Ok(())
+}
 
-        let p = Paragraph::new(Span::raw(name.to_string()));
-        frame.render_widget(p.block(b).alignment(Alignment::Center), r);
+/// Sets up the board the way the command line asked for.
+fn start(board: Board, caps: Caps, args: &Args) -> App {
+    let engine = if args.audio { Engine::default() } else { Engine::new(Arc::new(Null::default())) };
+    let mut app = App::new(board, args.paths.clone(), caps, engine);
+    app.locked = args.locked;
+    app.rehearsal = args.rehearsal;
+    app.screen_reader = args.screen_reader;
+    match instance::listen(&args.paths.config) {
+        Ok(server) => app.instance = Some(server),
+        Err(e) => app.status = Some(format!("{e:#}")),
+    }
+    if let Some(path) = &args.record {
+        app.start_recording(path);
     }
+    service::notify("READY=1");
+    app
+}
 
-    for child_node_id in tree.child_ids(node_id) {
-        create_layout(tree, child_node_id, frame, mapping, targets);
+/// Loads the board for a command that doesn't take over the terminal, saying so on stderr when
+/// the config was upgraded.
+fn load(path: &Path) -> color_eyre::Result<Board> {
+    let (board, migrated) = Board::load(path)?;
+    if let Some(migrated) = migrated {
+        eprintln!("{migrated}");
     }
+    Ok(board)
 }
 
-fn handle_events(targets: &HashMap<Rect, &'static [u8]>) -> color_eyre::Result<bool> {
-    if event::poll(std::time::Duration::from_millis(50))? {
-        match event::read()? {
-            Event::Key(key) => {
-                if key.kind == event::KeyEventKind::Press {
-                    for (ckey, (_, data)) in CONFIG {
-                        if &key.code == ckey {
-                            thread::spawn(|| if let Err(e) = play_sound(data) {
-                                println!("{:?}", e);
-                            });
-                        }
-                    }
+/// Runs the board until it is closed, returning where a recording that was still going went.
+fn run(terminal: &mut Term, mut input: Input, board: color_eyre::Result<(Board, Option<Migrated>)>, args: &Args, terminate: &Terminate) -> color_eyre::Result<Option<String>> {
+    let Some((board, migrated)) = recover_config(terminal, board, &args.paths.config)? else { return Ok(None) };
+    let mut app = start(board, input.caps, args);
+    if let Some(migrated) = migrated {
+        app.status = Some(migrated.to_string());
+    }
+    app.shown = Some(Shown::detect());
+    let job = Job::install()?;
 
-                    if key.code == KeyCode::Esc {
-                        return Ok(true);
-                    }
-                }
-            }
-            Event::Mouse(m) => {
-                if let MouseEventKind::Up(_) = m.kind {
-                    for (r, d) in targets {
-                        if r.contains(Position::new(m.column, m.row)) {
-                            thread::spawn(|| if let Err(e) = play_sound(d) {
-                                println!("{:?}", e);
-                            });
-                        }
-                    }
-                }
-            }
-            _ => {}
+    while !app.should_quit && !terminate.requested() {
+        step(terminal, &mut app, &mut input, &mut TerminalEvents, Instant::now())?;
+        for announcement in app.reader.take() {
+            let mut out = std::io::stdout().lock();
+            out.write_all(reader::escape(&announcement).as_bytes())?;
+            out.flush()?;
+        }
+        // The bell, which window managers take as the terminal wanting attention
+        if std::mem::take(&mut app.raise) {
+            let mut out = std::io::stdout().lock();
+            out.write_all(b"\x07")?;
+            out.flush()?;
+        }
+
+        if app.edit_config {
+            app.edit_config = false;
+            app.flush();
+            edit_config(terminal, &mut app)?;
+            // The editor had the terminal, so all of it is drawn again
+            app.redraw.dirty = true;
+        }
+        if std::mem::take(&mut app.suspend) || job.stop_requested() {
+            app.flush();
+            suspend(terminal, &mut app, terminate)?;
+        }
+        // Stopped and continued by someone else, who may have drawn over the board meanwhile
+        if job.continued() {
+            terminal.clear()?;
+            app.redraw.dirty = true;
+        }
+    }
+
+    service::stop(&app, terminate);
+    app.flush();
+    Ok(app.stop_recording())
+}
+
+/// How often the board catches up on what happened while it plays in the background.
+const BACKGROUND_TICK: Duration = Duration::from_millis(20);
+
+/// ^Z and the signals that come with job control, `kill -TSTP` and `kill -CONT`, which the
+/// terminal doesn't send itself while it is in raw mode.
+#[derive(Default)]
+struct Job {
+    stop: Arc<AtomicBool>,
+    cont: Arc<AtomicBool>,
+}
+
+impl Job {
+    fn install() -> color_eyre::Result<Self> {
+        let job = Self::default();
+        #[cfg(unix)]
+        {
+            signal_hook::flag::register(signal_hook::consts::SIGTSTP, job.stop.clone()).wrap_err("listen for SIGTSTP")?;
+            signal_hook::flag::register(signal_hook::consts::SIGCONT, job.cont.clone()).wrap_err("listen for SIGCONT")?;
         }
+        Ok(job)
     }
-    Ok(false)
+
+    fn stop_requested(&self) -> bool {
+        self.stop.swap(false, Ordering::Relaxed)
+    }
+
+    fn continued(&self) -> bool {
+        self.cont.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Stops until `fg`. After `bg` the board keeps playing in the background, taking requests
+/// from `soundboard play`, the fifo and the rest, and takes the terminal back once it is brought
+/// to the foreground again.
+#[cfg(unix)]
+fn suspend(terminal: &mut Term, app: &mut App, terminate: &Terminate) -> color_eyre::Result<()> {
+    tui::suspend(terminal)?;
+    while !tui::in_foreground() {
+        if app.should_quit || terminate.requested() {
+            return Ok(());
+        }
+        app.tick(Instant::now());
+        std::thread::sleep(BACKGROUND_TICK);
+    }
+    tui::resume(terminal)?;
+    app.redraw.dirty = true;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn suspend(_terminal: &mut Term, app: &mut App, _terminate: &Terminate) -> color_eyre::Result<()> {
+    app.status = Some("suspending is only available on Unix".to_string());
+    Ok(())
+}
+
+/// Catches up on what happened in the background until `now`, draws the board if it changed,
+/// and handles what comes in until the next frame is due.
+fn step<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, input: &mut Input, events: &mut impl Events, now: Instant) -> color_eyre::Result<()> {
+    app.tick(now);
+    let interval = ui::frame_interval(app);
+    if app.redraw.due(ui::moving(app, now), interval, now) {
+        terminal.draw(|frame| ui::draw(frame, app, now))?;
+    }
+    let timeout = app.redraw.timeout(interval, now);
+    if let Some(event) = events.next(timeout)?.and_then(|event| input.translate(event)) {
+        app.handle_event(event);
+    }
+    Ok(())
 }
 
-fn play_sound(data: &[u8]) -> color_eyre::Result<()> {
-    // Get a output stream handle to the default physical sound device
-    let (_stream, stream_handle) = OutputStream::try_default().wrap_err("stream")?;
-    // Decode that sound file into a source
-    let source = Decoder::new(Cursor::new(data.to_vec())).wrap_err("decoder")?;
+/// Opens the config in the user's editor and reloads the board once they are done.
+fn edit_config(terminal: &mut Term, app: &mut App) -> color_eyre::Result<()> {
+    // Without a file yet there is nothing to edit, so write out the current board first
+    if !app.paths.config.exists() {
+        app.board.save(&app.paths.config)?;
+    }
 
-    let sink = Sink::try_new(&stream_handle).wrap_err("get sink")?;
-    sink.append(source);
-    sink.sleep_until_end();
+    if let Err(e) = tui::edit(terminal, &app.paths.config, None) {
+        app.status = Some(format!("{e:#}"));
+        return Ok(());
+    }
 
+    let board = Board::load(&app.paths.config);
+    match recover_config(terminal, board, &app.paths.config)? {
+        Some((board, migrated)) => {
+            app.replace_board(board);
+            app.read_only = false;
+            app.status = Some(migrated.map_or_else(|| "reloaded config".to_string(), |m| m.to_string()));
+        }
+        None => {
+            app.read_only = true;
+            app.status = Some("config is still invalid, keeping the previous board without saving it".to_string());
+        }
+    }
     Ok(())
 }
+
+/// Shows the config error screen for a board that failed to load, until the config is fixed or
+/// the user gives up (in which case this returns `None`).
+fn recover_config(terminal: &mut Term, mut board: color_eyre::Result<(Board, Option<Migrated>)>, path: &Path) -> color_eyre::Result<Option<(Board, Option<Migrated>)>> {
+    // The config that would pick the language and colors is the one that doesn't load
+    let (locale, styles) = (Locale::from_env(), Palette::from_env().styles());
+    // What went wrong trying to fix it, which is no reason to give up on the board
+    let mut note = None;
+    loop {
+        let err = match board {
+            Ok(board) => return Ok(Some(board)),
+            // A config that can't be read at all, say
+            Err(e) => e.downcast::<ConfigError>().unwrap_or_else(|e| ConfigError::new(format!("{e:#}")).in_file(path)),
+        };
+
+        let source = std::fs::read_to_string(path).unwrap_or_default();
+        let backup = backup::list(path).into_iter().find(|b| b.sounds.is_some());
+        loop {
+            terminal.draw(|frame| ui::draw_config_error(frame, &err, &source, backup.as_ref(), note.as_deref(), locale, styles))?;
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('e') | KeyCode::Enter => match tui::edit(terminal, path, err.line) {
+                        Ok(()) => {
+                            note = None;
+                            break;
+                        }
+                        Err(e) => note = Some(format!("{e:#}")),
+                    },
+                    KeyCode::Char('r') => break,
+                    // The broken config is kept as a backup in turn when it is replaced
+                    KeyCode::Char('b') if backup.is_some() => {
+                        match backup.as_ref().map(|b| b.board().and_then(|restored| restored.save(path))) {
+                            Some(Err(e)) => note = Some(format!("{e:#}")),
+                            _ => note = None,
+                        }
+                        break;
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        board = Board::load(path);
+    }
+}
@@ -0,0 +1,400 @@
+//! Advertises the board on the local network over mDNS, as `_soundboard._tcp.local`, and finds
+//! the boards others are running, so nobody has to type in IP addresses.
+//!
+//! Only as much of DNS as that needs: answering questions about our own records, and reading
+//! the answers to a single query.
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use color_eyre::eyre::Context;
+
+const GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const PORT: u16 = 5353;
+const SERVICE: [&str; 3] = ["_soundboard", "_tcp", "local"];
+/// How long others may cache our records, in seconds
+const TTL: u32 = 120;
+
+const A: u16 = 1;
+const PTR: u16 = 12;
+const TXT: u16 = 16;
+const SRV: u16 = 33;
+const ANY: u16 = 255;
+const IN: u16 = 1;
+/// Set on the class of records that replace what others cached, rather than add to it
+const CACHE_FLUSH: u16 = 0x8000;
+
+/// A board found on the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Found {
+    pub name: String,
+    pub host: String,
+    pub addr: Option<Ipv4Addr>,
+    pub port: u16,
+    pub txt: Vec<String>,
+}
+
+/// Answers for the board until dropped, then tells the network it is gone.
+pub struct Advertiser {
+    stop: Arc<AtomicBool>,
+    socket: UdpSocket,
+    goodbye: Vec<u8>,
+}
+
+impl Drop for Advertiser {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.socket.send_to(&self.goodbye, SocketAddrV4::new(GROUP, PORT));
+    }
+}
+
+/// Advertises the board as `name`, serving on `port`.
+pub fn advertise(name: &str, port: u16, txt: Vec<String>) -> color_eyre::Result<Advertiser> {
+    let socket = bind_shared().wrap_err("listen for mDNS")?;
+    socket.join_multicast_v4(&GROUP, &Ipv4Addr::UNSPECIFIED).wrap_err("join the mDNS group")?;
+    socket.set_read_timeout(Some(Duration::from_millis(500))).wrap_err("mDNS")?;
+
+    let host = format!("{}.local", hostname());
+    let records = Records { instance: name.to_string(), host, addr: local_addr(), port, txt };
+    let announcement = records.response(0, &[], TTL);
+    let goodbye = records.response(0, &[], 0);
+    // Announced twice, a second apart, in case the first one gets lost
+    socket.send_to(&announcement, SocketAddrV4::new(GROUP, PORT)).wrap_err("announce over mDNS")?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    let responder = socket.try_clone().wrap_err("mDNS")?;
+    thread::spawn(move || {
+        let mut announce_again = Some(Instant::now() + Duration::from_secs(1));
+        let mut buf = [0; 9000];
+        while !stopped.load(Ordering::Relaxed) {
+            if announce_again.is_some_and(|at| Instant::now() >= at) {
+                announce_again = None;
+                let _ = responder.send_to(&announcement, SocketAddrV4::new(GROUP, PORT));
+            }
+            let Ok((len, from)) = responder.recv_from(&mut buf) else { continue };
+            let Some(query) = Message::parse(&buf[..len]) else { continue };
+            if query.response || !query.questions.iter().any(|q| records.answers(q)) {
+                continue;
+            }
+            // Queries from other ports are one-shot queries that want a direct answer, with the
+            // question repeated (RFC 6762 section 6.7)
+            let _ = if from.port() == PORT {
+                responder.send_to(&records.response(0, &[], TTL), SocketAddrV4::new(GROUP, PORT))
+            } else {
+                responder.send_to(&records.response(query.id, &query.questions, TTL.min(10)), from)
+            };
+        }
+    });
+
+    Ok(Advertiser { stop, socket, goodbye })
+}
+
+/// Asks the network for boards, and lists those that answered within `wait`.
+pub fn discover(wait: Duration) -> color_eyre::Result<Vec<Found>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).wrap_err("mDNS")?;
+    let mut query = header(0x50b0, 0, 1, 0);
+    write_name(&mut query, &SERVICE);
+    query.extend(PTR.to_be_bytes());
+    query.extend(IN.to_be_bytes());
+    socket.send_to(&query, SocketAddrV4::new(GROUP, PORT)).wrap_err("send mDNS query")?;
+
+    let mut instances = Vec::new();
+    let mut srv: HashMap<String, (String, u16)> = HashMap::new();
+    let mut txt: HashMap<String, Vec<String>> = HashMap::new();
+    let mut addrs: HashMap<String, Ipv4Addr> = HashMap::new();
+    let deadline = Instant::now() + wait;
+    let mut buf = [0; 9000];
+    while let Some(left) = deadline.checked_duration_since(Instant::now()).filter(|d| !d.is_zero()) {
+        socket.set_read_timeout(Some(left)).wrap_err("mDNS")?;
+        let Ok((len, _)) = socket.recv_from(&mut buf) else { break };
+        let Some(message) = Message::parse(&buf[..len]) else { continue };
+        for record in message.records {
+            let name = record.name.join(".");
+            match record.data {
+                Data::Ptr(target) if record.name == SERVICE && !instances.contains(&target) => instances.push(target),
+                Data::Srv(host, port) => {
+                    srv.insert(name, (host.join("."), port));
+                }
+                Data::Txt(entries) => {
+                    txt.insert(name, entries);
+                }
+                Data::A(addr) => {
+                    addrs.insert(name, addr);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(instances
+        .into_iter()
+        .filter_map(|instance| {
+            let key = instance.join(".");
+            let (host, port) = srv.remove(&key)?;
+            Some(Found {
+                name: instance.first().cloned().unwrap_or_default(),
+                addr: addrs.get(&host).copied(),
+                host,
+                port,
+                txt: txt.remove(&key).unwrap_or_default(),
+            })
+        })
+        .collect())
+}
+
+/// Port 5353 is usually taken by the system's own responder already, so it has to be shared.
+#[cfg(unix)]
+fn bind_shared() -> std::io::Result<UdpSocket> {
+    use std::os::fd::FromRawFd;
+
+    unsafe {
+        let fd = libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // Owned from here on, so it gets closed on errors too
+        let socket = UdpSocket::from_raw_fd(fd);
+        let on: libc::c_int = 1;
+        for option in [libc::SO_REUSEADDR, libc::SO_REUSEPORT] {
+            let len = std::mem::size_of_val(&on) as libc::socklen_t;
+            if libc::setsockopt(fd, libc::SOL_SOCKET, option, (&on as *const libc::c_int).cast(), len) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        let addr = libc::sockaddr_in {
+            sin_family: libc::AF_INET as libc::sa_family_t,
+            sin_port: PORT.to_be(),
+            sin_addr: libc::in_addr { s_addr: 0 },
+            ..std::mem::zeroed()
+        };
+        let len = std::mem::size_of_val(&addr) as libc::socklen_t;
+        if libc::bind(fd, (&addr as *const libc::sockaddr_in).cast(), len) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(socket)
+    }
+}
+
+#[cfg(not(unix))]
+fn bind_shared() -> std::io::Result<UdpSocket> {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, PORT))
+}
+
+/// The address others on the network reach us on, found by asking which one the route to the
+/// mDNS group would go out of. Nothing is actually sent.
+fn local_addr() -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect(SocketAddrV4::new(GROUP, PORT)).ok()?;
+    match socket.local_addr().ok()? {
+        SocketAddr::V4(addr) if !addr.ip().is_unspecified() => Some(*addr.ip()),
+        _ => None,
+    }
+}
+
+pub fn hostname() -> String {
+    #[cfg(unix)]
+    {
+        let mut buf = [0u8; 256];
+        if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } == 0 {
+            let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+            if let Ok(name) = std::str::from_utf8(&buf[..len]) {
+                // Just the machine's own label, `.local` is added where needed
+                return name.split('.').next().unwrap_or(name).to_string();
+            }
+        }
+    }
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "soundboard".to_string())
+}
+
+/// Everything we answer for.
+struct Records {
+    instance: String,
+    host: String,
+    addr: Option<Ipv4Addr>,
+    port: u16,
+    txt: Vec<String>,
+}
+
+impl Records {
+    fn instance_name(&self) -> Vec<&str> {
+        std::iter::once(self.instance.as_str()).chain(SERVICE).collect()
+    }
+
+    fn answers(&self, question: &Question) -> bool {
+        let is = |name: &[&str]| question.name.len() == name.len() && question.name.iter().zip(name).all(|(a, b)| a.eq_ignore_ascii_case(b));
+        let host: Vec<&str> = self.host.split('.').collect();
+        match question.kind {
+            PTR | ANY if is(&SERVICE) => true,
+            SRV | TXT | ANY if is(&self.instance_name()) => true,
+            A | ANY => self.addr.is_some() && is(&host),
+            _ => false,
+        }
+    }
+
+    fn response(&self, id: u16, questions: &[Question], ttl: u32) -> Vec<u8> {
+        let instance = self.instance_name();
+        let host: Vec<&str> = self.host.split('.').collect();
+        let answers = 3 + u16::from(self.addr.is_some());
+        let mut out = header(id, 0x8400, questions.len() as u16, answers);
+        for q in questions {
+            let name: Vec<&str> = q.name.iter().map(String::as_str).collect();
+            write_name(&mut out, &name);
+            out.extend(q.kind.to_be_bytes());
+            out.extend(IN.to_be_bytes());
+        }
+
+        let mut ptr = Vec::new();
+        write_name(&mut ptr, &instance);
+        record(&mut out, &SERVICE, PTR, IN, ttl, &ptr);
+
+        let mut srv = Vec::new();
+        srv.extend([0, 0, 0, 0]); // priority and weight
+        srv.extend(self.port.to_be_bytes());
+        write_name(&mut srv, &host);
+        record(&mut out, &instance, SRV, IN | CACHE_FLUSH, ttl, &srv);
+
+        let mut txt = Vec::new();
+        for entry in &self.txt {
+            let entry = &entry.as_bytes()[..entry.len().min(255)];
+            txt.push(entry.len() as u8);
+            txt.extend(entry);
+        }
+        if txt.is_empty() {
+            txt.push(0);
+        }
+        record(&mut out, &instance, TXT, IN | CACHE_FLUSH, ttl, &txt);
+
+        if let Some(addr) = self.addr {
+            record(&mut out, &host, A, IN | CACHE_FLUSH, ttl, &addr.octets());
+        }
+        out
+    }
+}
+
+fn header(id: u16, flags: u16, questions: u16, answers: u16) -> Vec<u8> {
+    let mut out = Vec::with_capacity(512);
+    for field in [id, flags, questions, answers, 0, 0] {
+        out.extend(field.to_be_bytes());
+    }
+    out
+}
+
+fn write_name(out: &mut Vec<u8>, labels: &[&str]) {
+    for label in labels {
+        let label = &label.as_bytes()[..label.len().min(63)];
+        out.push(label.len() as u8);
+        out.extend(label);
+    }
+    out.push(0);
+}
+
+fn record(out: &mut Vec<u8>, name: &[&str], kind: u16, class: u16, ttl: u32, data: &[u8]) {
+    write_name(out, name);
+    out.extend(kind.to_be_bytes());
+    out.extend(class.to_be_bytes());
+    out.extend(ttl.to_be_bytes());
+    out.extend((data.len() as u16).to_be_bytes());
+    out.extend(data);
+}
+
+struct Question {
+    name: Vec<String>,
+    kind: u16,
+}
+
+enum Data {
+    A(Ipv4Addr),
+    Ptr(Vec<String>),
+    Srv(Vec<String>, u16),
+    Txt(Vec<String>),
+    Other,
+}
+
+struct Record {
+    name: Vec<String>,
+    data: Data,
+}
+
+struct Message {
+    id: u16,
+    response: bool,
+    questions: Vec<Question>,
+    /// Answers and additional records alike
+    records: Vec<Record>,
+}
+
+impl Message {
+    fn parse(packet: &[u8]) -> Option<Self> {
+        let u16_at = |at: usize| Some(u16::from_be_bytes([*packet.get(at)?, *packet.get(at + 1)?]));
+        let id = u16_at(0)?;
+        let response = u16_at(2)? & 0x8000 != 0;
+        let counts = [u16_at(4)?, u16_at(6)?, u16_at(8)?, u16_at(10)?];
+
+        let mut at = 12;
+        let mut questions = Vec::new();
+        for _ in 0..counts[0] {
+            let (name, end) = read_name(packet, at)?;
+            questions.push(Question { name, kind: u16_at(end)? });
+            at = end + 4;
+        }
+
+        let mut records = Vec::new();
+        for _ in 0..counts[1] as usize + counts[2] as usize + counts[3] as usize {
+            let (name, end) = read_name(packet, at)?;
+            let kind = u16_at(end)?;
+            let len = u16_at(end + 8)? as usize;
+            let start = end + 10;
+            let data = packet.get(start..start + len)?;
+            let data = match kind {
+                A if len == 4 => Data::A(Ipv4Addr::new(data[0], data[1], data[2], data[3])),
+                PTR => Data::Ptr(read_name(packet, start)?.0),
+                SRV if len > 6 => Data::Srv(read_name(packet, start + 6)?.0, u16_at(start + 4)?),
+                TXT => {
+                    let mut entries = Vec::new();
+                    let mut rest = data;
+                    while let Some((&n, tail)) = rest.split_first() {
+                        let entry = tail.get(..n as usize)?;
+                        if !entry.is_empty() {
+                            entries.push(String::from_utf8_lossy(entry).into_owned());
+                        }
+                        rest = &tail[n as usize..];
+                    }
+                    Data::Txt(entries)
+                }
+                _ => Data::Other,
+            };
+            records.push(Record { name, data });
+            at = start + len;
+        }
+        Some(Self { id, response, questions, records })
+    }
+}
+
+/// Reads the name at `at`, following compression pointers, and returns it with where the name
+/// ended in the packet.
+fn read_name(packet: &[u8], mut at: usize) -> Option<(Vec<String>, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    // Bounds the number of pointers followed, so a malicious loop can't hang us
+    for _ in 0..128 {
+        let len = *packet.get(at)? as usize;
+        match len {
+            0 => return Some((labels, end.unwrap_or(at + 1))),
+            _ if len & 0xc0 == 0xc0 => {
+                let target = (len & 0x3f) << 8 | *packet.get(at + 1)? as usize;
+                end.get_or_insert(at + 2);
+                at = target;
+            }
+            _ => {
+                let label = packet.get(at + 1..at + 1 + len)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                at += 1 + len;
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,125 @@
+//! Merging a board that was changed in two places since they last had the same one, like by two
+//! hosts of a show syncing it, see [`crate::sync`]. It is merged setting by setting: what only
+//! one side changed is taken from there, and what both changed is taken from whichever changed
+//! last. Sounds, and the other lists of things with a name like buses and scenes, are merged by
+//! name, so sounds added on each side are all kept, and a sound rebound to another key on one
+//! side keeps the volume it was given on the other.
+
+use crate::config::Board;
+use crate::toml::{self, Table, Value};
+
+/// `ours` and `theirs` merged, which were both `base` before; `ours` is taken where both
+/// changed the same setting when `ours_later`. When what comes out isn't a board that loads,
+/// like with a cue left for a sound the other side removed, it is the later of the two as a
+/// whole.
+pub fn boards(base: &Board, ours: &Board, theirs: &Board, ours_later: bool) -> Board {
+    let merged = table(&base.to_table(), &ours.to_table(), &theirs.to_table(), ours_later);
+    Board::from_table(&merged).unwrap_or_else(|_| if ours_later { ours.clone() } else { theirs.clone() })
+}
+
+/// Configs merged like [`boards`], `None` when `ours` or `theirs` doesn't load. The base may be
+/// empty, for when there was none, and then only what both changed alike is in common.
+pub fn configs(base: &str, ours: &str, theirs: &str, ours_later: bool) -> Option<String> {
+    let (ours, _) = Board::parse(ours).ok()?;
+    let (theirs, _) = Board::parse(theirs).ok()?;
+    let base = Board::parse(base).map_or_else(|_| Board::parse("").map(|(b, _)| b).ok(), |(b, _)| Some(b))?;
+    Some(toml::to_string(&boards(&base, &ours, &theirs, ours_later).to_table()))
+}
+
+fn table(base: &Table, ours: &Table, theirs: &Table, ours_later: bool) -> Table {
+    let mut merged = Table::new();
+    let ours_keys = ours.entries.iter().map(|e| e.key.as_str());
+    let keys: Vec<&str> = ours_keys.chain(theirs.entries.iter().map(|e| e.key.as_str()).filter(|k| ours.entry(k).is_none())).collect();
+    for key in keys {
+        if let Some(value) = value(base.get(key), ours.get(key), theirs.get(key), ours_later) {
+            merged.insert(key, value);
+        }
+    }
+    merged
+}
+
+/// A setting merged, `None` when it is left out.
+fn value(base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>, ours_later: bool) -> Option<Value> {
+    if ours == theirs || theirs == base {
+        return ours.cloned();
+    }
+    if ours == base {
+        return theirs.cloned();
+    }
+    match (ours, theirs) {
+        (Some(Value::Table(o)), Some(Value::Table(t))) => {
+            let empty = Table::new();
+            let base = if let Some(Value::Table(b)) = base { b } else { &empty };
+            Some(Value::Table(table(base, o, t, ours_later)))
+        }
+        (Some(Value::Array(o)), Some(Value::Array(t))) if named(o) && named(t) => {
+            let base = if let Some(Value::Array(b)) = base { b.as_slice() } else { &[] };
+            Some(Value::Array(list(base, o, t, ours_later)))
+        }
+        _ if ours_later => ours.cloned(),
+        _ => theirs.cloned(),
+    }
+}
+
+fn name(value: &Value) -> Option<&str> {
+    match value {
+        Value::Table(table) => match table.get("name") {
+            Some(Value::String(name)) => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether `items` are things with a name, like sounds, to be merged by it.
+fn named(items: &[Value]) -> bool {
+    items.iter().all(|item| name(item).is_some())
+}
+
+/// Things with a name merged by it, in our order with theirs that are new after them.
+fn list(base: &[Value], ours: &[Value], theirs: &[Value], ours_later: bool) -> Vec<Value> {
+    let find = |items: &[Value], wanted: &str| items.iter().find(|item| name(item) == Some(wanted)).cloned();
+    let mut names: Vec<&str> = ours.iter().filter_map(name).collect();
+    names.extend(theirs.iter().filter_map(name).filter(|n| find(ours, n).is_none()));
+    names.into_iter().filter_map(|n| value(find(base, n).as_ref(), find(ours, n).as_ref(), find(theirs, n).as_ref(), ours_later)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Board;
+    use super::configs;
+
+    fn board(src: &str) -> Board {
+        Board::parse(src).unwrap().0
+    }
+
+    #[test]
+    fn changes_on_both_sides_are_kept() {
+        let base = "[[sound]]\nname = \"horn\"\nbuiltin = \"puree\"\nkey = \"h\"\n[[sound]]\nname = \"bell\"\nbuiltin = \"erg\"\n";
+        // Rebound here, louder there, a sound added on each and one removed
+        let ours = "[[sound]]\nname = \"horn\"\nbuiltin = \"puree\"\nkey = \"j\"\n[[sound]]\nname = \"bell\"\nbuiltin = \"erg\"\n[[sound]]\nname = \"intro\"\nbuiltin = \"windy\"\n";
+        let theirs = "[[sound]]\nname = \"horn\"\nbuiltin = \"puree\"\nkey = \"h\"\nvolume = 1.5\n[[sound]]\nname = \"outro\"\nbuiltin = \"windy\"\n";
+        let merged = board(&configs(base, ours, theirs, false).unwrap());
+        let names: Vec<_> = merged.sounds.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["horn", "intro", "outro"]);
+        assert_eq!(merged.sounds[0].bindings[0].label, "j");
+        assert_eq!(merged.sounds[0].volume, 1.5);
+    }
+
+    #[test]
+    fn the_last_change_wins_where_both_changed() {
+        let base = "[[sound]]\nname = \"horn\"\nbuiltin = \"puree\"\nkey = \"h\"\n";
+        let ours = "[[sound]]\nname = \"horn\"\nbuiltin = \"puree\"\nkey = \"j\"\n[ui]\nclock = true\n";
+        let theirs = "[[sound]]\nname = \"horn\"\nbuiltin = \"puree\"\nkey = \"k\"\n[ui]\nlayout = \"numpad\"\n";
+        let key = |ours_later| board(&configs(base, ours, theirs, ours_later).unwrap()).sounds[0].bindings[0].label.clone();
+        assert_eq!((key(true), key(false)), ("j".to_string(), "k".to_string()));
+        // The other settings of a section both changed are still merged
+        let merged = board(&configs(base, ours, theirs, true).unwrap());
+        assert!(merged.clock && merged.layout == crate::config::TileLayout::Numpad);
+
+        // Without a base all sounds are kept, and the same sound on both is as the later has it
+        let merged = board(&configs("", ours, theirs, false).unwrap());
+        assert_eq!((merged.sounds.len(), merged.sounds[0].bindings[0].label.as_str()), (1, "k"));
+        assert_eq!(configs(base, "[[sound]]\n", theirs, true), None);
+    }
+}
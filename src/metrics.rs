@@ -0,0 +1,252 @@
+//! Prometheus metrics, served over HTTP at `/metrics` so long running boards can be monitored.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use color_eyre::eyre::Context;
+use crate::config::Endpoint;
+
+/// Counts what the engine does. Shared with the audio threads and the server.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// By sound name
+    plays: Mutex<BTreeMap<String, u64>>,
+    /// Sounds that failed to play on a device
+    errors: AtomicU64,
+    /// Sinks currently playing, one per sound per device
+    sinks: AtomicU64,
+    /// From being triggered to being queued on the device, in microseconds, for the last sound
+    latency: AtomicU64,
+    /// By what came back: `mqtt`, or `output:` and the device
+    reconnects: Mutex<BTreeMap<String, u64>>,
+    /// Outputs that failed to open last time, to tell when they are back
+    lost: Mutex<HashSet<String>>,
+}
+
+impl Metrics {
+    pub fn played(&self, sound: &str) {
+        *self.plays.lock().unwrap().entry(sound.to_string()).or_default() += 1;
+    }
+
+    pub fn failed(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn sink_started(&self, latency: Duration) {
+        self.sinks.fetch_add(1, Ordering::Relaxed);
+        self.latency.store(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Whether `device`, or the default output when `None`, could be opened. One that couldn't be
+    /// before counts as reconnected.
+    pub fn output(&self, device: Option<&str>, opened: bool) {
+        let output = format!("output:{}", device.unwrap_or("default"));
+        let mut lost = self.lost.lock().unwrap();
+        if !opened {
+            lost.insert(output);
+        } else if lost.remove(&output) {
+            drop(lost);
+            self.reconnected(&output);
+        }
+    }
+
+    pub fn reconnected(&self, what: &str) {
+        *self.reconnects.lock().unwrap().entry(what.to_string()).or_default() += 1;
+    }
+
+    pub fn sink_stopped(&self) {
+        self.sinks.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// For the last sound that started.
+    pub fn latency(&self) -> Duration {
+        Duration::from_micros(self.latency.load(Ordering::Relaxed))
+    }
+
+    /// In the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let metric = |out: &mut String, name: &str, kind: &str, help: &str| {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+        };
+        metric(&mut out, "soundboard_plays_total", "counter", "Sounds played since the board started.");
+        for (sound, plays) in self.plays.lock().unwrap().iter() {
+            let _ = writeln!(out, "soundboard_plays_total{{sound=\"{}\"}} {plays}", label(sound));
+        }
+        metric(&mut out, "soundboard_errors_total", "counter", "Sounds that failed to play on an output device.");
+        let _ = writeln!(out, "soundboard_errors_total {}", self.errors.load(Ordering::Relaxed));
+        metric(&mut out, "soundboard_reconnects_total", "counter", "Connections that came back after being lost, by output device or mqtt.");
+        for (what, reconnects) in self.reconnects.lock().unwrap().iter() {
+            let _ = writeln!(out, "soundboard_reconnects_total{{what=\"{}\"}} {reconnects}", label(what));
+        }
+        metric(&mut out, "soundboard_active_sinks", "gauge", "Sounds playing right now, counted once per output device.");
+        let _ = writeln!(out, "soundboard_active_sinks {}", self.sinks.load(Ordering::Relaxed));
+        metric(&mut out, "soundboard_latency_seconds", "gauge", "Time from the last sound being triggered to it being queued on its output.");
+        let _ = writeln!(out, "soundboard_latency_seconds {}", self.latency.load(Ordering::Relaxed) as f64 / 1e6);
+        out
+    }
+}
+
+/// `value` escaped for a label.
+fn label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// How long a scraper gets to send its request and read the answer.
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// The most a request line and its headers can take up, a scrape is never near this.
+const MAX_REQUEST: u64 = 8192;
+/// Scrapers answered at once, more are turned away until one is done.
+const MAX_CONNECTIONS: usize = 8;
+
+/// Serves the metrics until dropped.
+pub struct Server {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+}
+
+impl Server {
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wakes up the listener, which is waiting for the next connection
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+pub fn serve(endpoint: &Endpoint, metrics: Arc<Metrics>) -> color_eyre::Result<Server> {
+    let listen = &endpoint.listen;
+    let listener = TcpListener::bind(listen).wrap_err_with(|| format!("listen on {listen}"))?;
+    let addr = listener.local_addr().wrap_err("metrics address")?;
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let stopped = stop.clone();
+    let endpoint = endpoint.clone();
+    let busy = Arc::new(AtomicUsize::new(0));
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if stopped.load(Ordering::Relaxed) {
+                break;
+            }
+            let Ok(stream) = stream else { continue };
+            if busy.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS {
+                busy.fetch_sub(1, Ordering::Relaxed);
+                continue;
+            }
+            // A slow scraper shouldn't hold up the next one
+            let (endpoint, metrics, busy) = (endpoint.clone(), metrics.clone(), busy.clone());
+            thread::spawn(move || {
+                let _ = stream.set_write_timeout(Some(TIMEOUT));
+                let _ = respond(stream, &endpoint, &metrics);
+                busy.fetch_sub(1, Ordering::Relaxed);
+            });
+        }
+    });
+    Ok(Server { addr, stop })
+}
+
+/// Reads from a stream until `until`, however slowly what is read trickles in.
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    until: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(std::io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+fn respond(mut stream: TcpStream, endpoint: &Endpoint, metrics: &Metrics) -> std::io::Result<()> {
+    let mut reader = BufReader::new(Deadline { stream: &stream, until: Instant::now() + TIMEOUT }.take(MAX_REQUEST));
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Only the credentials matter, but every header has to be read before answering
+    let mut authorization = None;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_string());
+            }
+        }
+        header.clear();
+    }
+
+    let mut parts = request.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        _ if reader.get_ref().limit() == 0 => ("431 Request Header Fields Too Large", "the request is too long\n".to_string()),
+        _ if !endpoint.authorized(authorization.as_deref()) => ("401 Unauthorized", "a valid bearer token is needed\n".to_string()),
+        (Some("GET"), Some(path)) if path.split('?').next() == Some("/metrics") => ("200 OK", metrics.render()),
+        (Some("GET"), Some(_)) => ("404 Not Found", "not found, try /metrics\n".to_string()),
+        _ => ("405 Method Not Allowed", "only GET is supported\n".to_string()),
+    };
+    let content_type = if status.starts_with("200") { "text/plain; version=0.0.4" } else { "text/plain" };
+    let challenge = if status.starts_with("401") { "WWW-Authenticate: Bearer\r\n" } else { "" };
+    write!(stream, "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\n{challenge}Content-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::Arc;
+    use crate::config::Endpoint;
+    use super::{serve, Metrics};
+
+    fn get(port: u16, request: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut answer = String::new();
+        stream.read_to_string(&mut answer).unwrap();
+        answer
+    }
+
+    #[test]
+    fn reconnects_are_counted() {
+        let metrics = Metrics::default();
+        metrics.output(None, true);
+        metrics.output(Some("usb \"desk\""), false);
+        metrics.output(Some("usb \"desk\""), false);
+        metrics.output(Some("usb \"desk\""), true);
+        metrics.output(Some("usb \"desk\""), true);
+        metrics.reconnected("mqtt");
+        let rendered = metrics.render();
+        assert!(rendered.contains("soundboard_reconnects_total{what=\"mqtt\"} 1\n"), "{rendered}");
+        assert!(rendered.contains("soundboard_reconnects_total{what=\"output:usb \\\"desk\\\"\"} 1\n"), "{rendered}");
+        assert!(!rendered.contains("output:default"), "{rendered}");
+    }
+
+    #[test]
+    fn slow_and_huge_requests_dont_hold_up_scrapes() {
+        let endpoint = Endpoint { listen: "127.0.0.1:0".to_string(), tokens: Vec::new() };
+        let server = serve(&endpoint, Arc::new(Metrics::default())).unwrap();
+
+        // Says nothing at all, which used to keep everyone else waiting
+        let _idle = TcpStream::connect(("127.0.0.1", server.port())).unwrap();
+        assert!(get(server.port(), "GET /metrics HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 200 OK"));
+
+        // Just long enough to be all read, more would be reset before the answer arrives
+        let huge = format!("GET /metrics HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "a".repeat(8192 - 38));
+        assert!(get(server.port(), &huge).starts_with("HTTP/1.1 431"));
+    }
+}
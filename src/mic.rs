@@ -0,0 +1,244 @@
+//! Listens to microphones, for features that react to what they pick up.
+
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Instant;
+use color_eyre::eyre::{eyre, Context};
+use rodio::cpal::traits::{HostTrait, StreamTrait};
+use rodio::cpal::{self, FromSample, SampleFormat, SizedSample, Stream, StreamError};
+use rodio::DeviceTrait;
+use crate::audio::Gain;
+use crate::config::{LevelRule, Talkover};
+
+/// Time for the gain to go all the way down when speech starts, in seconds.
+const ATTACK: f32 = 0.05;
+/// Time for the gain to come all the way back after the hold, in seconds.
+const RELEASE: f32 = 0.4;
+
+/// The level of one block of input, in dBFS.
+#[derive(Debug, Clone, Copy)]
+struct Levels {
+    rms: f32,
+    peak: f32,
+}
+
+impl Levels {
+    fn of(block: &[f32]) -> Self {
+        let (sum, peak) = block.iter().fold((0.0f32, 0.0f32), |(sum, peak), s| (sum + s * s, peak.max(s.abs())));
+        Self { rms: db((sum / block.len() as f32).sqrt()), peak: db(peak) }
+    }
+}
+
+pub fn db(amplitude: f32) -> f32 {
+    20.0 * amplitude.max(1e-6).log10()
+}
+
+fn build<T, F, E>(device: &cpal::Device, config: &cpal::StreamConfig, mut on_block: F, on_error: E) -> color_eyre::Result<Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+    F: FnMut(&[f32]) + Send + 'static,
+    E: FnMut(StreamError) + Send + 'static,
+{
+    let mut block = Vec::new();
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _| {
+                if data.is_empty() {
+                    return;
+                }
+                block.clear();
+                block.extend(data.iter().map(|s| s.to_sample::<f32>()));
+                on_block(&block);
+            },
+            on_error,
+            None,
+        )
+        .wrap_err("open microphone")
+}
+
+/// Starts listening on `input` (the system default when `None`), calling `on_block` with the
+/// level of every block of samples, and `on_error` with what goes wrong once it runs, on the
+/// audio thread.
+fn open(
+    input: Option<&str>,
+    mut on_block: impl FnMut(Levels) + Send + 'static,
+    on_error: impl FnMut(StreamError) + Send + 'static,
+) -> color_eyre::Result<Stream> {
+    open_samples(input, move |block| on_block(Levels::of(block)), on_error).map(|(stream, _)| stream)
+}
+
+/// Like [`open`], but with every block of interleaved samples, and the format they are in.
+fn open_samples(
+    input: Option<&str>,
+    on_block: impl FnMut(&[f32]) + Send + 'static,
+    on_error: impl FnMut(StreamError) + Send + 'static,
+) -> color_eyre::Result<(Stream, cpal::StreamConfig)> {
+    let host = cpal::default_host();
+    let device = match input {
+        None => host.default_input_device().ok_or_else(|| eyre!("there is no microphone"))?,
+        Some(name) => host
+            .input_devices()
+            .wrap_err("list input devices")?
+            .find(|d| d.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| eyre!("no input device named {name:?}"))?,
+    };
+    let supported = device.default_input_config().wrap_err("microphone config")?;
+
+    let config = supported.config();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build::<f32, _, _>(&device, &config, on_block, on_error)?,
+        SampleFormat::I16 => build::<i16, _, _>(&device, &config, on_block, on_error)?,
+        SampleFormat::U16 => build::<u16, _, _>(&device, &config, on_block, on_error)?,
+        SampleFormat::I32 => build::<i32, _, _>(&device, &config, on_block, on_error)?,
+        other => return Err(eyre!("unsupported microphone sample format {other}")),
+    };
+    stream.play().wrap_err("start microphone")?;
+    Ok((stream, config))
+}
+
+/// The names of the inputs there are.
+pub fn inputs() -> Vec<String> {
+    let devices = cpal::default_host().input_devices();
+    devices.map(|devices| devices.filter_map(|d| d.name().ok()).collect()).unwrap_or_default()
+}
+
+/// What an input picks up, for as long as it is kept.
+pub struct Capture {
+    _stream: Stream,
+    pub channels: u16,
+    pub rate: u32,
+    /// Interleaved
+    pub samples: Arc<Mutex<Vec<f32>>>,
+}
+
+pub fn capture(input: Option<&str>) -> color_eyre::Result<Capture> {
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let tx = samples.clone();
+    let (stream, config) = open_samples(input, move |block| {
+        if let Ok(mut samples) = tx.lock() {
+            samples.extend_from_slice(block);
+        }
+    }, |_| {})?;
+    Ok(Capture { _stream: stream, channels: config.channels, rate: config.sample_rate.0, samples })
+}
+
+/// What the UI shows about the microphone.
+#[derive(Debug, Default)]
+pub struct Meter {
+    /// Last input level in dBFS, as f32 bits
+    level: AtomicU32,
+    pub ducking: AtomicBool,
+}
+
+impl Meter {
+    pub fn level(&self) -> f32 {
+        f32::from_bits(self.level.load(Ordering::Relaxed))
+    }
+}
+
+/// Radio style talkover: ducks the board while someone speaks. Dropping it stops listening.
+pub struct Listener {
+    _stream: Stream,
+    pub meter: Arc<Meter>,
+    /// What went wrong with the microphone after it started, for the status line
+    pub errors: Receiver<String>,
+}
+
+struct Ducker {
+    settings: Talkover,
+    duck: Gain,
+    meter: Arc<Meter>,
+    last_voice: Option<Instant>,
+    last_block: Instant,
+}
+
+impl Ducker {
+    fn process(&mut self, levels: Levels) {
+        self.meter.level.store(levels.rms.to_bits(), Ordering::Relaxed);
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_block).as_secs_f32();
+        self.last_block = now;
+
+        if levels.rms >= self.settings.threshold {
+            self.last_voice = Some(now);
+        }
+        let talking = self.last_voice.is_some_and(|t| now.duration_since(t) <= self.settings.hold);
+        self.meter.ducking.store(talking, Ordering::Relaxed);
+
+        // Ramp in dB rather than jumping, so the board fades instead of clicking
+        let current = db(self.duck.get());
+        let next = if talking {
+            (current - self.settings.duck * elapsed / ATTACK).max(-self.settings.duck)
+        } else {
+            (current + self.settings.duck * elapsed / RELEASE).min(0.0)
+        };
+        self.duck.set(if next >= 0.0 { 1.0 } else { 10f32.powf(next / 20.0) });
+    }
+}
+
+/// Starts listening on the configured microphone, lowering `duck` while it hears speech.
+pub fn talkover(settings: &Talkover, duck: Gain) -> color_eyre::Result<Listener> {
+    let meter = Arc::new(Meter::default());
+    let ducker = Mutex::new(Ducker {
+        settings: settings.clone(),
+        duck,
+        meter: meter.clone(),
+        last_voice: None,
+        last_block: Instant::now(),
+    });
+    let (errors_tx, errors) = std::sync::mpsc::channel();
+    let stream = open(
+        settings.input.as_deref(),
+        move |levels| {
+            if let Ok(mut ducker) = ducker.lock() {
+                ducker.process(levels);
+            }
+        },
+        move |e| {
+            let _ = errors_tx.send(format!("talkover: {e}, the board is no longer ducked"));
+        },
+    )?;
+
+    Ok(Listener { _stream: stream, meter, errors })
+}
+
+/// Watches inputs for the level rules, sending the index of every rule that fires to `fired`.
+/// Returns one stream per input the rules listen on; dropping them stops listening.
+pub fn watch_levels(rules: &[LevelRule], fired: Sender<usize>) -> color_eyre::Result<Vec<Stream>> {
+    let mut inputs: Vec<Option<&str>> = rules.iter().map(|r| r.input.as_deref()).collect();
+    inputs.sort();
+    inputs.dedup();
+
+    inputs
+        .into_iter()
+        .map(|input| {
+            let mut rules: Vec<(usize, LevelRule, Option<Instant>)> = rules
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| r.input.as_deref() == input)
+                .map(|(i, r)| (i, r.clone(), None))
+                .collect();
+            let fired = fired.clone();
+            open(
+                input,
+                move |levels| {
+                    let now = Instant::now();
+                    for (idx, rule, last) in &mut rules {
+                        // Peaks, because claps and shouts are too short to move the average much
+                        let cooled = last.is_none_or(|t| now.duration_since(t) >= rule.cooldown);
+                        if levels.peak >= rule.above && cooled {
+                            *last = Some(now);
+                            let _ = fired.send(*idx);
+                        }
+                    }
+                },
+                // A rule on an input that fails just stops firing, there is no rule to blame it on
+                |_| {},
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,373 @@
+//! MIDI controllers, through the Linux raw MIDI devices (`/dev/snd/midiC*D*`): notes play the
+//! sounds bound to them, and knobs and faders move what `[[fader]]` sections map them to, so a
+//! cheap MIDI mixer can be a control surface for the board. Controllers plugged in while the board
+//! runs are picked up too.
+//!
+//! Which controller a knob sends is seldom printed on it, so ^N learns it: pick what to move,
+//! turn the knob, and the board writes the `[[fader]]` itself.
+//!
+//! With `feedback` in `[midi]` the board talks back: pads that play a sound light up in the
+//! tile's color and blink while it plays, the way a Launchpad takes it, and knobs and motorized
+//! faders are sent where what they move went, when something else moved it.
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::Receiver;
+#[cfg(target_os = "linux")]
+use std::sync::{atomic::{AtomicBool, AtomicUsize}, Arc, Mutex};
+use ratatui::style::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiEvent {
+    /// A key or pad being hit
+    Note(u8),
+    /// A knob or fader moving, on a channel from 1 to 16, to a value from 0 to 127
+    Control { channel: u8, cc: u8, value: u8 },
+}
+
+/// Turns the bytes a controller sends into events, one byte at a time.
+#[derive(Debug, Default)]
+pub struct Parser {
+    /// The status byte that data bytes belong to, which controllers can leave out when it is the
+    /// same as the message before ("running status")
+    status: Option<u8>,
+    data: Vec<u8>,
+}
+
+impl Parser {
+    pub fn feed(&mut self, byte: u8) -> Option<MidiEvent> {
+        match byte {
+            // Clock and the like come in between the bytes of other messages
+            0xf8.. => None,
+            // System exclusive and system common messages, none of which are of use here
+            0xf0.. => {
+                self.status = None;
+                None
+            }
+            0x80.. => {
+                self.status = Some(byte);
+                self.data.clear();
+                None
+            }
+            _ => {
+                let status = self.status?;
+                self.data.push(byte);
+                let length = if matches!(status >> 4, 0xc | 0xd) { 1 } else { 2 };
+                if self.data.len() < length {
+                    return None;
+                }
+                let data = std::mem::take(&mut self.data);
+                match status >> 4 {
+                    // A note on at velocity 0 is how many controllers send a note off
+                    0x9 if data[1] > 0 => Some(MidiEvent::Note(data[0])),
+                    0xb => Some(MidiEvent::Control { channel: (status & 0x0f) + 1, cc: data[0], value: data[1] }),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// Where a value from 0 to 127 is between nothing and all the way.
+pub fn position(value: u8) -> f32 {
+    value.min(127) as f32 / 127.0
+}
+
+/// Reads every controller until dropped.
+pub struct Midi {
+    #[cfg(target_os = "linux")]
+    stop: Arc<AtomicBool>,
+    pub events: Receiver<MidiEvent>,
+    /// The controllers to send to, by their device
+    #[cfg(target_os = "linux")]
+    outputs: Arc<Mutex<Vec<(std::path::PathBuf, std::fs::File)>>>,
+    /// How many times a controller was plugged in, so everything can be sent again to one
+    #[cfg(target_os = "linux")]
+    connected: Arc<AtomicUsize>,
+}
+
+impl Midi {
+    /// Sends `bytes` to every controller, leaving out those that are gone.
+    pub fn send(&self, bytes: &[u8]) {
+        #[cfg(target_os = "linux")]
+        {
+            use std::io::Write;
+            self.outputs.lock().unwrap().retain_mut(|(_, file)| file.write_all(bytes).is_ok());
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = bytes;
+    }
+
+    /// Goes up each time a controller shows up.
+    pub fn connected(&self) -> usize {
+        #[cfg(target_os = "linux")]
+        return self.connected.load(std::sync::atomic::Ordering::Relaxed);
+        #[cfg(not(target_os = "linux"))]
+        0
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Midi {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn open() -> color_eyre::Result<Midi> {
+    use std::fs::{File, OpenOptions};
+    use std::io::Read;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::Ordering;
+    use color_eyre::eyre::Context;
+
+    // Without the directory there is no sound driver, and no controller will ever show up
+    std::fs::read_dir("/dev/snd").wrap_err("look for MIDI controllers in /dev/snd")?;
+
+    let outputs: Arc<Mutex<Vec<(PathBuf, File)>>> = Arc::default();
+    let connected = Arc::new(AtomicUsize::new(0));
+
+    let (sending, plugged) = (outputs.clone(), connected.clone());
+    let open = move |path: &Path, ()| {
+        // One that can only be read gets no feedback
+        let file = OpenOptions::new().read(true).write(true).open(path).or_else(|_| File::open(path)).ok()?;
+        if let Ok(output) = file.try_clone() {
+            sending.lock().unwrap().push((path.to_path_buf(), output));
+        }
+        plugged.fetch_add(1, Ordering::Relaxed);
+        Some((path.to_path_buf(), file))
+    };
+    let sending = outputs.clone();
+    let read = move |(path, mut file): (PathBuf, File), tx: &std::sync::mpsc::Sender<MidiEvent>, _: &AtomicBool| {
+        let mut parser = Parser::default();
+        let mut buf = [0u8; 64];
+        'read: while let Ok(n @ 1..) = file.read(&mut buf) {
+            for event in buf[..n].iter().filter_map(|&b| parser.feed(b)) {
+                if tx.send(event).is_err() {
+                    break 'read;
+                }
+            }
+        }
+        sending.lock().unwrap().retain(|(p, _)| *p != path);
+    };
+    let (stop, events) = crate::hotplug::watch("/dev/snd", crate::hotplug::named("midi"), open, read);
+    Ok(Midi { stop, events, outputs, connected })
+}
+
+/// What the controllers were told last, so only what changed is sent again.
+#[derive(Debug, Default)]
+pub struct Feedback {
+    /// Each pad's color, and whether it blinks
+    lights: BTreeMap<u8, (u8, bool)>,
+    /// Each knob's value, by its channel and controller
+    faders: BTreeMap<(u8, u8), u8>,
+    /// [`Midi::connected`] when they were told
+    connected: usize,
+}
+
+impl Feedback {
+    /// The bytes that bring the controllers from what they were told to `lights`, by note, and
+    /// `faders`, by channel and controller. Everything is sent once more after `connected`
+    /// changed, for a controller that was just plugged in.
+    pub fn update(&mut self, lights: BTreeMap<u8, (u8, bool)>, faders: BTreeMap<(u8, u8), u8>, connected: usize) -> Vec<u8> {
+        if connected != self.connected {
+            (self.lights, self.faders, self.connected) = (BTreeMap::new(), BTreeMap::new(), connected);
+        }
+        let mut bytes = Vec::new();
+        for (&note, &(color, blinking)) in &lights {
+            if self.lights.get(&note) != Some(&(color, blinking)) {
+                // Lit on the first channel; on the second it blinks between that and off
+                bytes.extend([0x90, note, color]);
+                if blinking {
+                    bytes.extend([0x91, note, 0]);
+                }
+            }
+        }
+        for &note in self.lights.keys().filter(|note| !lights.contains_key(note)) {
+            bytes.extend([0x80, note, 0]);
+        }
+        for (&(channel, cc), &value) in &faders {
+            if self.faders.get(&(channel, cc)) != Some(&value) {
+                bytes.extend([0xb0 | (channel.clamp(1, 16) - 1), cc, value]);
+            }
+        }
+        (self.lights, self.faders) = (lights, faders);
+        bytes
+    }
+}
+
+/// The Launchpad palette's colors that are closest to the ones tiles can have, and what lights
+/// them up.
+const PALETTE: [((u8, u8, u8), u8); 14] = [
+    ((255, 255, 255), 3),
+    ((128, 128, 128), 1),
+    ((255, 0, 0), 5),
+    ((255, 128, 0), 9),
+    ((255, 255, 0), 13),
+    ((128, 255, 0), 17),
+    ((0, 255, 0), 21),
+    ((0, 255, 128), 29),
+    ((0, 255, 255), 37),
+    ((0, 128, 255), 41),
+    ((0, 0, 255), 45),
+    ((128, 0, 255), 49),
+    ((255, 0, 255), 53),
+    ((255, 0, 128), 57),
+];
+
+/// The velocity that lights a Launchpad's pad up nearest to `color`, white for tiles without one.
+pub fn pad_color(color: Option<Color>) -> u8 {
+    let (r, g, b) = color.map_or((255, 255, 255), rgb);
+    let distance = |&((pr, pg, pb), _): &((u8, u8, u8), u8)| [(r, pr), (g, pg), (b, pb)].iter().map(|&(a, b)| (a as i32 - b as i32).pow(2)).sum::<i32>();
+    PALETTE.iter().min_by_key(|entry| distance(entry)).map_or(3, |&(_, velocity)| velocity)
+}
+
+/// About how a terminal shows `color`, for its named and numbered colors.
+fn rgb(color: Color) -> (u8, u8, u8) {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (128, 0, 0),
+        (0, 128, 0),
+        (128, 128, 0),
+        (0, 0, 128),
+        (128, 0, 128),
+        (0, 128, 128),
+        (192, 192, 192),
+        (128, 128, 128),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (0, 0, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    let index = match color {
+        Color::Rgb(r, g, b) => return (r, g, b),
+        Color::Indexed(i @ 16..=231) => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let i = i - 16;
+            return (level(i / 36), level(i / 6 % 6), level(i % 6));
+        }
+        Color::Indexed(i @ 232..) => {
+            let grey = 8 + (i - 232) * 10;
+            return (grey, grey, grey);
+        }
+        Color::Indexed(i) => i,
+        Color::Reset | Color::White => 15,
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+    };
+    ANSI[index as usize]
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn open() -> color_eyre::Result<Midi> {
+    color_eyre::eyre::bail!("MIDI controllers are only supported on Linux for now")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use ratatui::style::Color;
+    use crate::app::View;
+    use crate::config::{Board, Bus, Continuous, Effect, Fader};
+    use crate::harness::{self, Harness};
+    use crate::history::Via;
+    use crate::toml;
+    use super::{pad_color, Feedback, MidiEvent, Parser};
+
+    fn parse(bytes: &[u8]) -> Vec<MidiEvent> {
+        let mut parser = Parser::default();
+        bytes.iter().filter_map(|&b| parser.feed(b)).collect()
+    }
+
+    #[test]
+    fn running_status_and_clock_in_between() {
+        assert_eq!(
+            parse(&[0xb0, 7, 100, 8, 0xf8, 50, 0x91, 60, 127, 60, 0, 0x81, 61, 0]),
+            [
+                MidiEvent::Control { channel: 1, cc: 7, value: 100 },
+                MidiEvent::Control { channel: 1, cc: 8, value: 50 },
+                MidiEvent::Note(60),
+            ]
+        );
+        // Half a message, and data after system exclusive, belong to nothing
+        assert_eq!(parse(&[7, 100, 0xf0, 1, 2, 0xf7, 3, 0xcf, 5, 0xbf, 1, 2]), [MidiEvent::Control { channel: 16, cc: 1, value: 2 }]);
+    }
+
+    #[test]
+    fn a_learned_fader_moves_its_bus() {
+        let mut board = harness::board(1);
+        board.sounds[0].bus = Some("music".to_string());
+        board.buses = vec![Bus { name: "music".to_string(), volume: 1.0, muted: false, effects: vec![Effect::LowPass(8000.0)], wet: 1.0, stem: false }];
+        let mut h = Harness::new(board, 100, 24).loaded();
+        h.press_with(KeyCode::Char('n'), KeyModifiers::CONTROL);
+        assert_eq!(h.app.view, View::Learn);
+        let screen = h.screen();
+        assert!(screen.contains("master volume") && screen.contains("music effects, wet"), "{screen}");
+
+        h.press(KeyCode::Down);
+        h.app.moved(2, 7, 64);
+        assert_eq!(h.app.board.faders, [Fader { cc: 7, channel: Some(2), control: Continuous::Volume("music".to_string()) }]);
+        h.step();
+        assert!(h.screen().contains("music volume  50%  cc 7 on channel 2"), "{}", h.screen());
+        // The next one is up, and another knob for it takes the first one's place
+        h.app.moved(2, 8, 0);
+        h.app.moved(2, 9, 127);
+        assert_eq!(h.app.board.faders.iter().map(|f| f.cc).collect::<Vec<_>>(), [7, 9]);
+        assert_eq!(h.app.engine.bus("music").wet.get(), 1.0);
+
+        h.press(KeyCode::Esc);
+        let volume = |h: &mut Harness| {
+            h.app.play(0, Via::Key);
+            h.audio.take()[0].volume
+        };
+        h.app.moved(2, 7, 0);
+        assert_eq!(volume(&mut h), 0.0);
+        h.app.moved(2, 7, 127);
+        assert_eq!(volume(&mut h), 1.0);
+        // Only on its channel
+        h.app.moved(3, 7, 0);
+        assert_eq!(volume(&mut h), 1.0);
+
+        let (again, _) = Board::parse(&toml::to_string(&h.app.board.to_table())).unwrap();
+        assert_eq!(again.faders, h.app.board.faders);
+        let err = Board::parse("version = 1\n[[fader]]\ncc = 7\nvolume = \"music\"\ncrossfader = true\n").unwrap_err().to_string();
+        assert!(err.contains("only move one thing"), "{err}");
+    }
+
+    #[test]
+    fn feedback_only_sends_what_changed() {
+        let mut feedback = Feedback::default();
+        let lights = |playing| BTreeMap::from([(36, (5, playing)), (37, (3, false))]);
+        let faders = || BTreeMap::from([((2, 7), 100)]);
+        assert_eq!(feedback.update(lights(false), faders(), 0), [0x90, 36, 5, 0x90, 37, 3, 0xb1, 7, 100]);
+        assert_eq!(feedback.update(lights(false), faders(), 0), []);
+        // Blinking while it plays, and lit again once it is over
+        assert_eq!(feedback.update(lights(true), faders(), 0), [0x90, 36, 5, 0x91, 36, 0]);
+        assert_eq!(feedback.update(lights(false), faders(), 0), [0x90, 36, 5]);
+        // Everything again for a controller that was just plugged in, and off once unbound
+        assert_eq!(feedback.update(lights(false), faders(), 1).len(), 9);
+        assert_eq!(feedback.update(BTreeMap::new(), faders(), 1), [0x80, 36, 0, 0x80, 37, 0]);
+
+        assert_eq!([None, Some(Color::Red), Some(Color::Indexed(208)), Some(Color::Rgb(0, 0, 200))].map(pad_color), [3, 5, 9, 45]);
+        let (board, _) = Board::parse("version = 1\n[midi]\nfeedback = true\n").unwrap();
+        assert!(board.midi_feedback);
+        assert_eq!(Board::parse(&toml::to_string(&board.to_table())).unwrap().0, board);
+    }
+}
@@ -0,0 +1,51 @@
+//! Config schema versions and the migrations between them.
+//!
+//! Every config file carries a top-level `version`. Files without one predate versioning and
+//! count as version 0. When an older file is loaded it is upgraded step by step, the original is
+//! kept next to it as `<file>.v<N>.bak`, and the upgraded config is written back in place.
+
+use crate::config::ConfigError;
+use crate::toml::{Table, Value};
+
+pub const CURRENT_VERSION: i64 = 2;
+
+type Migration = fn(&mut Table) -> Result<(), ConfigError>;
+
+/// `MIGRATIONS[n]` upgrades a table from version `n` to version `n + 1`.
+const MIGRATIONS: &[Migration] = &[
+    // 0 -> 1: introduces the `version` field itself, nothing else changed
+    |_| Ok(()),
+    // 1 -> 2: files are found from the config's folder rather than the working directory,
+    // which `Board::load` takes care of as it knows both
+    |_| Ok(()),
+];
+
+pub fn version(table: &Table) -> Result<i64, ConfigError> {
+    match table.entry("version") {
+        None => Ok(0),
+        Some(e) => match e.value {
+            Value::Integer(v) if v >= 0 => Ok(v),
+            _ => Err(ConfigError::wrong_type("", e, "non-negative integer")
+                .suggest(format!("use `version = {CURRENT_VERSION}`"))),
+        },
+    }
+}
+
+/// Upgrades `table` to [`CURRENT_VERSION`], returning the version it started at.
+pub fn migrate(table: &mut Table) -> Result<i64, ConfigError> {
+    let from = version(table)?;
+    if from > CURRENT_VERSION {
+        let line = table.entry("version").map_or(1, |e| e.line);
+        return Err(ConfigError::new(format!("config has version {from}, but this soundboard only understands up to version {CURRENT_VERSION}"))
+            .line(line)
+            .field("version")
+            .suggest("upgrade soundboard to load this config"));
+    }
+
+    for migration in &MIGRATIONS[from as usize..] {
+        migration(table)?;
+    }
+    table.insert("version", CURRENT_VERSION);
+
+    Ok(from)
+}
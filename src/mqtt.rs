@@ -0,0 +1,308 @@
+//! MQTT: publishes what the board plays to a broker and takes commands from it, so home
+//! automation can react to and drive the board. With the default `soundboard` topic it uses:
+//!
+//! - `soundboard/event`: `{"event": "start", "sound": ...}` like webhooks get
+//! - `soundboard/status`: `online`, retained, and `offline` once the board is gone
+//! - `soundboard/command`: takes `play <sound or alias>`, `stop`, `mute` or `go`
+//!
+//! This is a minimal MQTT 3.1.1 client for just that, over plain TCP, which reconnects when the
+//! broker goes away. Events that happen while disconnected are dropped.
+
+use std::collections::HashMap;
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use color_eyre::eyre::{bail, eyre, Context};
+use crate::config::{Mqtt, SignalAction};
+use crate::history::Via;
+use crate::metrics::Metrics;
+use crate::{mdns, webhook};
+
+const PORT: u16 = 1883;
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// The broker drops the connection after one and a half of these without hearing anything.
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+const RETRY_MIN: Duration = Duration::from_secs(1);
+const RETRY_MAX: Duration = Duration::from_secs(30);
+/// The most a broker may send in one packet. Commands are a few words, so anything bigger is a
+/// broker that is confused or up to no good.
+const MAX_PACKET: usize = 64 * 1024;
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+/// With the reserved flag bit the specification requires
+const SUBSCRIBE: u8 = 0x82;
+const PINGREQ: u8 = 0xc0;
+const DISCONNECT: u8 = 0xe0;
+
+struct Message {
+    topic: String,
+    payload: Vec<u8>,
+    retain: bool,
+}
+
+/// Stays connected to the broker until dropped.
+pub struct Client {
+    topic: String,
+    outgoing: Sender<Message>,
+    /// The sounds playing, by the id the engine gave them, to tell what stopped
+    active: HashMap<u64, (String, Via)>,
+    pub commands: Receiver<SignalAction>,
+    /// Connection trouble and commands that made no sense, for the status bar
+    pub errors: Receiver<String>,
+}
+
+impl Client {
+    pub fn start(&mut self, id: u64, sound: &str, via: Via) {
+        self.event("start", sound, via);
+        self.active.insert(id, (sound.to_string(), via));
+    }
+
+    pub fn stop(&mut self, id: u64) {
+        if let Some((sound, via)) = self.active.remove(&id) {
+            self.event("stop", &sound, via);
+        }
+    }
+
+    fn event(&self, event: &str, sound: &str, via: Via) {
+        let payload = webhook::payload(event, sound, via).into_bytes();
+        let _ = self.outgoing.send(Message { topic: format!("{}/event", self.topic), payload, retain: false });
+    }
+}
+
+pub fn connect(config: &Mqtt, metrics: Arc<Metrics>) -> Client {
+    let (outgoing, queued) = mpsc::channel();
+    let (commands_tx, commands) = mpsc::channel();
+    let (errors_tx, errors) = mpsc::channel();
+    let config = config.clone();
+    let topic = config.topic.clone();
+    thread::spawn(move || run(&config, &queued, &commands_tx, &errors_tx, &metrics));
+    Client { topic, outgoing, active: HashMap::new(), commands, errors }
+}
+
+/// Keeps a session going, and starts a new one when it breaks, until the client is dropped.
+fn run(config: &Mqtt, queued: &Receiver<Message>, commands: &Sender<SignalAction>, errors: &Sender<String>, metrics: &Metrics) {
+    let mut retry = RETRY_MIN;
+    let mut connected = false;
+    loop {
+        match session(config, queued, commands, errors, &mut retry, &mut connected, metrics) {
+            Ok(()) => return,
+            Err(e) => {
+                let _ = errors.send(format!("mqtt: {e:#}, retrying in {}s", retry.as_secs()));
+            }
+        }
+        let until = Instant::now() + retry;
+        loop {
+            match queued.recv_timeout(until.saturating_duration_since(Instant::now())) {
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        retry = (retry * 2).min(RETRY_MAX);
+    }
+}
+
+/// One connection to the broker, returning `Ok` once the client is dropped.
+/// `connected` says whether there was a session before, which makes this one a reconnect.
+fn session(
+    config: &Mqtt,
+    queued: &Receiver<Message>,
+    commands: &Sender<SignalAction>,
+    errors: &Sender<String>,
+    retry: &mut Duration,
+    connected: &mut bool,
+    metrics: &Metrics,
+) -> color_eyre::Result<()> {
+    let broker = if config.broker.contains(':') { config.broker.clone() } else { format!("{}:{PORT}", config.broker) };
+    let addr = broker
+        .to_socket_addrs()
+        .wrap_err_with(|| format!("resolve {}", config.broker))?
+        .next()
+        .ok_or_else(|| eyre!("{} has no address", config.broker))?;
+    let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT).wrap_err_with(|| format!("connect to {broker}"))?;
+    stream.set_read_timeout(Some(TIMEOUT)).wrap_err("connect")?;
+    stream.set_write_timeout(Some(TIMEOUT)).wrap_err("connect")?;
+
+    let status = format!("{}/status", config.topic);
+    stream.write_all(&connect_packet(config, &status)).wrap_err("connect")?;
+    let (header, body) = read_packet(&mut stream).wrap_err("wait for the broker")?;
+    match (header, body.get(1)) {
+        (CONNACK, Some(0)) => {}
+        (CONNACK, Some(4 | 5)) => bail!("{broker} refused the username or password"),
+        (CONNACK, Some(code)) => bail!("{broker} refused the connection (code {code})"),
+        _ => bail!("{broker} doesn't speak MQTT"),
+    }
+    *retry = RETRY_MIN;
+    if std::mem::replace(connected, true) {
+        metrics.reconnected("mqtt");
+    }
+
+    let mut subscribe = 1u16.to_be_bytes().to_vec();
+    string(&mut subscribe, format!("{}/command", config.topic).as_bytes());
+    subscribe.push(0); // QoS 0, like everything else here
+    stream.write_all(&packet(SUBSCRIBE, &subscribe)).wrap_err("subscribe")?;
+    stream.write_all(&publish(&status, b"online", true)).wrap_err("publish")?;
+
+    // Hearing nothing at all, not even answers to pings, means the broker is gone
+    stream.set_read_timeout(Some(KEEP_ALIVE + TIMEOUT)).wrap_err("connect")?;
+    let alive = Arc::new(AtomicBool::new(true));
+    let reader = stream.try_clone().wrap_err("connect")?;
+    {
+        let (alive, commands, errors) = (alive.clone(), commands.clone(), errors.clone());
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            while let Ok((header, body)) = read_packet(&mut reader) {
+                if header & 0xf0 != PUBLISH {
+                    continue;
+                }
+                let payload = published(header, &body).map(String::from_utf8_lossy).unwrap_or_default();
+                match command(&payload) {
+                    Some(command) => {
+                        let _ = commands.send(command);
+                    }
+                    None => {
+                        let _ = errors.send(format!("mqtt: unknown command {:?}, try `play <sound>`, `stop`, `mute` or `go`", payload.trim()));
+                    }
+                }
+            }
+            alive.store(false, Ordering::Relaxed);
+        });
+    }
+
+    // Pinged on time even while publishing, since only the answers keep the reader from timing out
+    let mut pinged = Instant::now();
+    let result = (|| loop {
+        if !alive.load(Ordering::Relaxed) {
+            bail!("lost the connection to {broker}");
+        }
+        if pinged.elapsed() >= KEEP_ALIVE / 2 {
+            stream.write_all(&[PINGREQ, 0]).wrap_err("ping")?;
+            pinged = Instant::now();
+        }
+        match queued.recv_timeout((KEEP_ALIVE / 2).saturating_sub(pinged.elapsed())) {
+            Ok(message) => stream.write_all(&publish(&message.topic, &message.payload, message.retain)).wrap_err("publish")?,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                // A clean disconnect doesn't send the will, so the board says so itself
+                stream.write_all(&publish(&status, b"offline", true)).wrap_err("publish")?;
+                stream.write_all(&[DISCONNECT, 0]).wrap_err("disconnect")?;
+                return Ok(());
+            }
+        }
+    })();
+    // Also stops the reader
+    let _ = stream.shutdown(Shutdown::Both);
+    result
+}
+
+/// Parses a command published to the command topic.
+fn command(payload: &str) -> Option<SignalAction> {
+    match payload.trim() {
+        "stop" => Some(SignalAction::Stop),
+        "mute" => Some(SignalAction::Mute),
+        "go" => Some(SignalAction::Go),
+        payload => payload.strip_prefix("play ").map(|name| SignalAction::Play(name.trim().to_string())),
+    }
+}
+
+fn connect_packet(config: &Mqtt, status: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    string(&mut body, b"MQTT");
+    body.push(4); // 3.1.1
+    // A clean session, with a retained will saying the board went offline
+    let mut flags = 0x02 | 0x04 | 0x20;
+    if config.username.is_some() {
+        flags |= 0x80;
+    }
+    if config.password.is_some() {
+        flags |= 0x40;
+    }
+    body.push(flags);
+    body.extend((KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+
+    // Another board on the same machine mustn't take over the session
+    string(&mut body, format!("soundboard-{}-{}", mdns::hostname(), std::process::id()).as_bytes());
+    string(&mut body, status.as_bytes());
+    string(&mut body, b"offline");
+    for credential in [&config.username, &config.password].into_iter().flatten() {
+        string(&mut body, credential.as_bytes());
+    }
+    packet(CONNECT, &body)
+}
+
+fn publish(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = Vec::new();
+    string(&mut body, topic.as_bytes());
+    body.extend(payload);
+    packet(PUBLISH | retain as u8, &body)
+}
+
+/// The payload of a PUBLISH packet.
+fn published(header: u8, body: &[u8]) -> Option<&[u8]> {
+    let len = u16::from_be_bytes(body.get(..2)?.try_into().ok()?) as usize;
+    // Messages above QoS 0 have a packet identifier after the topic
+    let skip = if header & 0x06 != 0 { 2 } else { 0 };
+    body.get(2 + len + skip..)
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    // The remaining length, seven bits at a time
+    let mut len = body.len();
+    loop {
+        let byte = (len % 128) as u8;
+        len /= 128;
+        packet.push(if len > 0 { byte | 0x80 } else { byte });
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+/// A length prefixed string, the way MQTT writes them.
+fn string(out: &mut Vec<u8>, s: &[u8]) {
+    out.extend((s.len() as u16).to_be_bytes());
+    out.extend(s);
+}
+
+fn read_packet(stream: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut byte = [0];
+    stream.read_exact(&mut byte)?;
+    let header = byte[0];
+    let mut len = 0;
+    for shift in (0..28).step_by(7) {
+        stream.read_exact(&mut byte)?;
+        len |= ((byte[0] & 0x7f) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            if len > MAX_PACKET {
+                return Err(io::Error::new(ErrorKind::InvalidData, format!("a packet of {len} bytes is too big")));
+            }
+            let mut body = vec![0; len];
+            stream.read_exact(&mut body)?;
+            return Ok((header, body));
+        }
+    }
+    Err(io::Error::new(ErrorKind::InvalidData, "packet too long"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{packet, read_packet, PUBLISH};
+
+    #[test]
+    fn packets_have_a_size_limit() {
+        let small = packet(PUBLISH, &[7; 300]);
+        assert_eq!(read_packet(&mut &small[..]).unwrap(), (PUBLISH, vec![7; 300]));
+        // Claims 256 MiB, which isn't read, let alone allocated
+        let huge = [PUBLISH, 0xff, 0xff, 0xff, 0x7f];
+        assert!(read_packet(&mut &huge[..]).unwrap_err().to_string().contains("too big"));
+    }
+}
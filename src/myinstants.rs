@@ -0,0 +1,80 @@
+//! Searching myinstants.com, where most meme sound clips live. It has no API, so this reads the
+//! pages a browser gets: every sound on them is a button that plays an MP3, like
+//!
+//! ```html
+//! <button class="small-button" onclick="play('/media/sounds/vine-boom.mp3', 'loader-70972', 'vine-boom-sound-70972')" title="Play Vine Boom Sound sound">
+//! ```
+//!
+//! A query can also be the address of a page on the site, like an instant's own page or a list
+//! of trending sounds, to pick from the sounds on it.
+
+use color_eyre::eyre::bail;
+use crate::fetch;
+use crate::search::{Hit, Page, Site};
+
+const SITE: &str = "https://www.myinstants.com";
+
+pub fn search(query: &str) -> color_eyre::Result<Page> {
+    let url = if query.starts_with("http://") || query.starts_with("https://") {
+        if !query.contains("myinstants.com") {
+            bail!("{query} isn't a myinstants.com page");
+        }
+        query.to_string()
+    } else {
+        format!("{SITE}/en/search/?name={}", fetch::encode(query))
+    };
+    let (code, body) = fetch::get(&url)?;
+    match code {
+        200 => {}
+        404 => bail!("myinstants.com has no page at {url}"),
+        _ => bail!("myinstants.com answered {code}"),
+    }
+    Ok(Page { count: None, hits: sounds(&String::from_utf8_lossy(&body)) })
+}
+
+/// The sounds the play buttons on a page play, in the order they're on it.
+fn sounds(html: &str) -> Vec<Hit> {
+    let mut hits: Vec<Hit> = Vec::new();
+    for (start, _) in html.match_indices("play('") {
+        // The rest of the button's tag, for its title
+        let tag = &html[start..];
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        let args: Vec<&str> = tag["play(".len()..]
+            .split(')')
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            .map(|a| a.trim().trim_matches(['\'', '"']))
+            .collect();
+        let Some(&path) = args.first().filter(|p| p.ends_with(".mp3")) else { continue };
+        if hits.iter().any(|h| h.url.ends_with(path)) {
+            continue;
+        }
+        let slug = args.get(2).copied().filter(|s| !s.is_empty());
+        let file = path.rsplit('/').next().unwrap_or(path).trim_end_matches(".mp3");
+        let title = tag
+            .split_once("title=\"")
+            .and_then(|(_, t)| t.split('"').next())
+            .map(|t| unescape(t.strip_prefix("Play ").unwrap_or(t)))
+            .map(|t| t.strip_suffix(" sound").map_or(t.clone(), str::to_string));
+        let url = if path.starts_with("http") { path.to_string() } else { format!("{SITE}{path}") };
+        hits.push(Hit {
+            site: Site::MyInstants,
+            id: slug.unwrap_or(file).to_string(),
+            name: title.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| file.replace(['-', '_'], " ")),
+            author: None,
+            duration: None,
+            license: None,
+            url,
+        });
+    }
+    hits
+}
+
+/// Undoes the HTML escapes names on the site use.
+fn unescape(s: &str) -> String {
+    let mut out = s.replace("&quot;", "\"").replace("&#x27;", "'").replace("&#39;", "'").replace("&lt;", "<").replace("&gt;", ">");
+    // Last, so `&amp;lt;` stays `&lt;`
+    out = out.replace("&amp;", "&");
+    out
+}
@@ -0,0 +1,149 @@
+//! Names for sounds added from files. A file name like `03_Air-Horn (1).wav` becomes "Air Horn",
+//! and with `transcribe` in `[voice]` a sound is named after the first words said in it, once
+//! the speech recognizer got to it.
+//!
+//! Like the recognizer in [`crate::voice`], the one that transcribes is a command the user has
+//! installed, like whisper.cpp's `whisper-cli -nt -np -f`: it is run with the file to transcribe
+//! at the end, and prints what is said in it.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use color_eyre::eyre::{bail, eyre, Context};
+
+/// How many of the words said in a sound make its name.
+const WORDS: usize = 4;
+
+/// The name of a file without its extension, the separators between words, its numbering and
+/// the copy number a download gets.
+pub fn from_file(path: &Path) -> String {
+    let stem = path.file_stem().map_or_else(|| path.display().to_string(), |s| s.to_string_lossy().into_owned());
+    let spaced: String = stem.chars().map(|c| if matches!(c, '_' | '-' | '.' | '+') { ' ' } else { c }).collect();
+    let mut words: Vec<&str> = spaced.split_whitespace().collect();
+    let numbered = |w: &str| w.trim_matches(|c| matches!(c, '(' | ')' | '[' | ']')).bytes().all(|b| b.is_ascii_digit());
+    // `03 horn` and `horn (1)`, but not a name that is only a number
+    while words.len() > 1 && numbered(words[0]) {
+        words.remove(0);
+    }
+    while words.len() > 1 && words.last().is_some_and(|w| (w.starts_with('(') || w.starts_with('[')) && numbered(w)) {
+        words.pop();
+    }
+    match words.join(" ") {
+        name if name.is_empty() => stem,
+        name => name,
+    }
+}
+
+/// `name`, or with a number after it when another sound already has it.
+pub fn unique(name: String, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(&name) {
+        return name;
+    }
+    (2..).map(|n| format!("{name} {n}")).find(|n| !taken(n)).unwrap_or(name)
+}
+
+/// The first words said in `path`, by the recognizer `command`, `None` when it heard nothing.
+pub fn transcribe(command: &str, path: &Path) -> color_eyre::Result<Option<String>> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or_else(|| eyre!("the transcribe command is empty"))?;
+    let output = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .wrap_err_with(|| format!("run {program:?}"))?;
+    if !output.status.success() {
+        bail!("{program:?} failed to transcribe {}: {}", path.display(), output.status);
+    }
+    Ok(first_words(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The first few words of a transcript, leaving out what recognizers put in brackets, like
+/// `[BLANK_AUDIO]` or `(music)`.
+fn first_words(transcript: &str) -> Option<String> {
+    let mut depth = 0usize;
+    let said: String = transcript
+        .chars()
+        .filter(|&c| match c {
+            '[' | '(' => {
+                depth += 1;
+                false
+            }
+            ']' | ')' => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            _ => depth == 0,
+        })
+        .collect();
+    let words: Vec<&str> = said.split_whitespace().map(|w| w.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')).filter(|w| !w.is_empty()).take(WORDS).collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// A sound that was added, by the name it was given and its file.
+pub type Added = (String, PathBuf);
+
+/// Transcribes the files of `added` one after the other in the background, handing out each
+/// one's first words as they are done.
+pub fn start(command: String, added: Vec<Added>) -> Receiver<(Added, color_eyre::Result<Option<String>>)> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for sound in added {
+            let words = transcribe(&command, &sound.1);
+            if tx.send((sound, words)).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::Duration;
+    use crate::config::{Board, Source};
+    use crate::harness::{self, Harness};
+    use super::{first_words, from_file, unique};
+
+    #[test]
+    fn file_names_are_cleaned_up() {
+        let name = |file: &str| from_file(Path::new(file));
+        assert_eq!(name("sounds/03_Air-Horn (1).wav"), "Air Horn");
+        assert_eq!(name("01 - intro.mp3"), "intro");
+        assert_eq!(name("sad.trombone.ogg"), "sad trombone");
+        assert_eq!(name("1999.wav"), "1999");
+        assert_eq!(name("[2].wav"), "[2]");
+        assert_eq!(unique("intro".to_string(), |n| ["intro", "intro 2"].contains(&n)), "intro 3");
+
+        assert_eq!(first_words(" [BLANK_AUDIO]\n Ladies and gentlemen, welcome to the show!\n"), Some("Ladies and gentlemen welcome".to_string()));
+        assert_eq!(first_words("(upbeat music)"), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn added_sounds_are_named_after_what_they_say() {
+        let dir = harness::scratch("names");
+        let file = dir.join("01_take-3.wav");
+        std::fs::write(&file, b"not really a sound").unwrap();
+        let recognizer = dir.join("recognizer");
+        std::fs::write(&recognizer, "#!/bin/sh\necho ' Here we go!'\n").unwrap();
+        std::fs::set_permissions(&recognizer, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+        let mut board = harness::board(1);
+        board.transcribe = Some(recognizer.display().to_string());
+        let mut h = Harness::new(board, 80, 24).loaded();
+        h.app.add(&file);
+        assert_eq!(h.app.board.sounds[1].name, "take 3");
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while h.app.board.sounds[1].name == "take 3" && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            h.app.tick(std::time::Instant::now());
+        }
+        assert_eq!(h.app.board.sounds[1].name, "Here we go");
+        assert_eq!(h.app.board.sounds[1].source, Source::File(file.clone()));
+        let (again, _) = Board::parse(&crate::toml::to_string(&h.app.board.to_table())).unwrap();
+        assert_eq!(again.transcribe, h.app.board.transcribe);
+    }
+}
@@ -0,0 +1,206 @@
+//! Sound packs, `soundboard packs`: sounds others put together, listed in an index that
+//! `index` in `[packs]` points at, to install onto the board with `soundboard packs install`.
+//!
+//! The index is JSON, listing each pack with every one of its sounds and the SHA-256 of its
+//! file, so a file that was changed or cut short on the way is never put on the board:
+//!
+//! ```json
+//! {"packs": [{"name": "office", "description": "Fridays at five",
+//!   "sounds": [{"name": "airhorn", "url": "office/airhorn.ogg", "sha256": "9f86d0…"}]}]}
+//! ```
+//!
+//! A sound's `url` may be relative to the index's, which may be on disk as well. Only an index on
+//! disk may point at files; one on the web can only point at the web. Installed sounds go in
+//! `packs/` next to the board's other sounds, and are tagged with the pack's name.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use color_eyre::eyre::{bail, eyre, Context};
+use crate::config::{Board, Source};
+use crate::{fetch, json, names, sha256};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pack {
+    pub name: String,
+    pub description: Option<String>,
+    pub sounds: Vec<PackSound>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackSound {
+    pub name: String,
+    pub url: String,
+    /// As lowercase hex
+    pub sha256: String,
+}
+
+impl Pack {
+    /// The directory in `dir` its sounds are installed to.
+    pub fn dir(&self, dir: &Path) -> PathBuf {
+        dir.join(safe(&self.name))
+    }
+
+    /// Whether every one of its sounds is in `dir` already, as it should be.
+    pub fn installed(&self, dir: &Path) -> bool {
+        let dir = self.dir(dir);
+        self.sounds.iter().all(|s| fs::read(dir.join(s.file())).is_ok_and(|data| sha256::hex(&data) == s.sha256))
+    }
+}
+
+impl PackSound {
+    /// What the sound's file is called once installed, after the last part of its `url`.
+    fn file(&self) -> String {
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+        match path.rsplit('/').next().map(safe).filter(|f| !f.is_empty() && f != "." && f != "..") {
+            Some(file) => file,
+            None => safe(&self.name),
+        }
+    }
+}
+
+/// `name` with only what is safe in a file name.
+fn safe(name: &str) -> String {
+    name.trim().chars().map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.') { c } else { '_' }).collect()
+}
+
+/// Downloads the index at `url`.
+pub fn fetch(url: &str) -> color_eyre::Result<Vec<Pack>> {
+    parse(&String::from_utf8_lossy(&get(url)?), url)
+}
+
+/// What is at `url`, which may also be a `file://` url or a path, for an index kept on disk.
+fn get(url: &str) -> color_eyre::Result<Vec<u8>> {
+    if let Some(path) = url.strip_prefix("file://").or((!url.contains("://")).then_some(url)) {
+        return fs::read(path).wrap_err_with(|| format!("read {path}"));
+    }
+    match fetch::get(url)? {
+        (200, body) => Ok(body),
+        (code, _) => bail!("{url} answered with {code}"),
+    }
+}
+
+/// The packs in the index `index`, which was downloaded from `url`.
+pub fn parse(index: &str, url: &str) -> color_eyre::Result<Vec<Pack>> {
+    let value = json::parse(index).map_err(|e| eyre!("the pack index at {url} isn't JSON: {e}"))?;
+    let packs = value.get("packs").and_then(|p| p.as_array()).ok_or_else(|| eyre!("the pack index at {url} has no \"packs\" list"))?;
+    // Where relative urls start from
+    let base = url.rsplit_once('/').map_or(".", |(base, _)| base);
+    let local = !fetch::web(url);
+    packs
+        .iter()
+        .enumerate()
+        .map(|(i, pack)| {
+            let name = pack.get("name").and_then(|n| n.as_str()).filter(|n| !n.trim().is_empty()).ok_or_else(|| eyre!("pack {} in the index has no name", i + 1))?;
+            let sounds = pack.get("sounds").and_then(|s| s.as_array()).ok_or_else(|| eyre!("pack {name:?} lists no sounds"))?;
+            let sounds = sounds
+                .iter()
+                .map(|sound| {
+                    let field = |key: &str| sound.get(key).and_then(|v| v.as_str()).ok_or_else(|| eyre!("a sound in pack {name:?} has no {key:?}"));
+                    let (url, sha256) = (field("url")?, field("sha256")?.to_lowercase());
+                    if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+                        bail!("{:?} in pack {name:?} doesn't have a SHA-256 as its sha256", field("name")?);
+                    }
+                    let url = match url.contains("://") {
+                        true if fetch::web(url) || (local && url.starts_with("file://")) => url.to_string(),
+                        true => bail!("{:?} in pack {name:?} is at {url}, but sounds can only come from http(s) urls", field("name")?),
+                        false => format!("{base}/{}", url.trim_start_matches("./")),
+                    };
+                    Ok(PackSound { name: field("name")?.to_string(), url, sha256 })
+                })
+                .collect::<color_eyre::Result<_>>()?;
+            Ok(Pack { name: name.to_string(), description: pack.get("description").and_then(|d| d.as_str()).map(String::from), sounds })
+        })
+        .collect()
+}
+
+/// Downloads the sounds of `pack` into `dir` that aren't there yet, checks them and adds them
+/// to `board`. Returns how many were added, which leaves out the ones it has already.
+pub fn install(board: &mut Board, pack: &Pack, dir: &Path) -> color_eyre::Result<usize> {
+    let dir = pack.dir(dir);
+    fs::create_dir_all(&dir).wrap_err_with(|| format!("create {}", dir.display()))?;
+    let mut added = 0;
+    for sound in &pack.sounds {
+        let path = dir.join(sound.file());
+        if !fs::read(&path).is_ok_and(|data| sha256::hex(&data) == sound.sha256) {
+            let data = get(&sound.url).wrap_err_with(|| format!("download {:?}", sound.name))?;
+            let hash = sha256::hex(&data);
+            if hash != sound.sha256 {
+                bail!("{:?} from {} isn't what pack {:?} says it is: its SHA-256 is {hash}", sound.name, sound.url, pack.name);
+            }
+            fs::write(&path, data).wrap_err_with(|| format!("write {}", path.display()))?;
+        }
+        let source = Source::File(path.clone());
+        if board.sounds.iter().any(|s| s.source == source) {
+            continue;
+        }
+        board.import(&path)?;
+        let name = names::unique(sound.name.clone(), |n| board.sounds.iter().chain(&board.trash).any(|s| s.name == n));
+        if let Some(new) = board.sounds.last_mut().filter(|s| s.source == source) {
+            new.name = name;
+            new.tags.push(pack.name.clone());
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use crate::config::Board;
+    use crate::harness;
+    use crate::sha256;
+    use super::{install, parse};
+
+    #[test]
+    fn packs_are_read_from_the_index() {
+        let index = r#"{"packs": [{"name": "office", "description": "Fridays at five", "sounds": [
+            {"name": "airhorn", "url": "office/air horn.ogg?v=2", "sha256": "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855"},
+            {"name": "ding", "url": "https://cdn.example.com/ding.wav", "sha256": "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"}]}]}"#;
+        let packs = parse(index, "https://example.com/packs/index.json").unwrap();
+        assert_eq!((packs[0].name.as_str(), packs[0].description.as_deref()), ("office", Some("Fridays at five")));
+        let urls: Vec<_> = packs[0].sounds.iter().map(|s| s.url.as_str()).collect();
+        assert_eq!(urls, ["https://example.com/packs/office/air horn.ogg?v=2", "https://cdn.example.com/ding.wav"]);
+        assert_eq!(packs[0].sounds[0].file(), "air horn.ogg");
+        assert_eq!(packs[0].sounds[0].sha256, packs[0].sounds[1].sha256);
+
+        let err = parse(&index.replace("E3B0", "E3"), "index.json").unwrap_err().to_string();
+        assert!(err.contains("doesn't have a SHA-256"), "{err}");
+        assert!(parse("{}", "index.json").unwrap_err().to_string().contains("no \"packs\" list"));
+
+        // An index on the web gets nothing from disk, or from anywhere but the web
+        for url in ["file:///etc/passwd", "scp://host/x", "-o/tmp/x://"] {
+            let hostile = index.replace("https://cdn.example.com/ding.wav", url);
+            let err = parse(&hostile, "https://example.com/packs/index.json").unwrap_err().to_string();
+            assert!(err.contains("can only come from http(s) urls"), "{url}: {err}");
+        }
+        let local = index.replace("https://cdn.example.com/ding.wav", "file:///srv/ding.wav");
+        assert_eq!(parse(&local, "/srv/index.json").unwrap()[0].sounds[1].url, "file:///srv/ding.wav");
+    }
+
+    #[test]
+    fn installing_checks_what_was_downloaded() {
+        let dir = harness::scratch("packs");
+        fs::create_dir_all(dir.join("index/office")).unwrap();
+        let sound = crate::config::BUILTIN[0].1;
+        fs::write(dir.join("index/office/horn.wav"), sound).unwrap();
+        let index = format!(r#"{{"packs": [{{"name": "office", "sounds": [{{"name": "Air horn", "url": "office/horn.wav", "sha256": "{}"}}]}}]}}"#, sha256::hex(sound));
+        let url = format!("file://{}/index/index.json", dir.display());
+        let packs = parse(&index, &url).unwrap();
+
+        let mut board = Board::builtin();
+        let installed = dir.join("installed");
+        assert!(!packs[0].installed(&installed));
+        assert_eq!(install(&mut board, &packs[0], &installed).unwrap(), 1);
+        let sound = board.sounds.last().unwrap();
+        assert_eq!((sound.name.as_str(), sound.tags.as_slice()), ("Air horn", &["office".to_string()][..]));
+        assert!(packs[0].installed(&installed));
+        // Installing again adds nothing
+        assert_eq!(install(&mut board, &packs[0], &installed).unwrap(), 0);
+
+        // A file that isn't what the index says stays off the board
+        fs::write(dir.join("index/office/horn.wav"), b"something else").unwrap();
+        let err = install(&mut board, &packs[0], &dir.join("elsewhere")).unwrap_err().to_string();
+        assert!(err.contains("isn't what pack \"office\" says it is"), "{err}");
+    }
+}
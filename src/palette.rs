@@ -0,0 +1,156 @@
+//! The colors the board is drawn in. Besides the default there is a high-contrast palette, one
+//! that doesn't rely on telling red from green, and none at all for terminals without color or
+//! users who set `NO_COLOR`. `[ui] palette` in the config picks one.
+//!
+//! Everything but the default also marks state with more than color, like a thick border around
+//! the selected tile, so nothing is lost to a color that can't be seen.
+
+use ratatui::style::{Color, Modifier, Style};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Palette {
+    #[default]
+    Default,
+    HighContrast,
+    /// Blue and orange from the Okabe-Ito set instead of green and red
+    Colorblind,
+    /// No color, only bold, dim and reversed text
+    None,
+}
+
+/// What each kind of thing on screen is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Styles {
+    pub tile: Style,
+    pub selected: Style,
+    /// A tile that just played, and the same a moment later as the flash fades
+    pub flash: Style,
+    pub fading: Style,
+    pub error: Style,
+    pub warning: Style,
+    /// Details and hints, less important than what is around them
+    pub dim: Style,
+    pub accent: Style,
+    pub good: Style,
+    pub directory: Style,
+    /// The part of a tile whose sound has played
+    pub played: Style,
+    /// Badges for what can't be missed, like being muted or recording
+    pub alarm: Style,
+    pub caution: Style,
+    pub ducking: Style,
+    /// Whether state is marked with symbols too, and not by color alone
+    pub marks: bool,
+    /// Whether tiles are drawn in their sound's own color
+    pub colors: bool,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [Palette::Default, Palette::HighContrast, Palette::Colorblind, Palette::None];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Default => "default",
+            Palette::HighContrast => "high-contrast",
+            Palette::Colorblind => "colorblind",
+            Palette::None => "none",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == s)
+    }
+
+    /// No color when `NO_COLOR` is set to anything, see <https://no-color.org>.
+    pub fn from_env() -> Self {
+        match std::env::var_os("NO_COLOR") {
+            Some(value) if !value.is_empty() => Palette::None,
+            _ => Palette::Default,
+        }
+    }
+
+    pub fn styles(self) -> Styles {
+        let fg = |color| Style::default().fg(color);
+        let badge = |fg, bg| Style::default().fg(fg).bg(bg).add_modifier(Modifier::BOLD);
+        match self {
+            Palette::Default => Styles {
+                tile: fg(Color::White),
+                selected: fg(Color::Yellow),
+                flash: Style::default().fg(Color::Black).bg(Color::LightGreen),
+                fading: fg(Color::LightGreen),
+                error: fg(Color::Red),
+                warning: fg(Color::Yellow),
+                dim: fg(Color::DarkGray),
+                accent: fg(Color::Cyan),
+                good: fg(Color::Green),
+                directory: fg(Color::Blue),
+                played: Style::default().bg(Color::DarkGray),
+                alarm: badge(Color::White, Color::Red),
+                caution: badge(Color::Black, Color::Yellow),
+                ducking: Style::default().fg(Color::Black).bg(Color::Cyan),
+                marks: false,
+                colors: true,
+            },
+            Palette::HighContrast => Styles {
+                tile: fg(Color::White),
+                selected: fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+                flash: Style::default().fg(Color::Black).bg(Color::White),
+                fading: fg(Color::White).add_modifier(Modifier::BOLD),
+                error: fg(Color::LightRed).add_modifier(Modifier::BOLD),
+                warning: fg(Color::LightYellow),
+                dim: fg(Color::Gray),
+                accent: fg(Color::LightCyan),
+                good: fg(Color::LightGreen),
+                directory: fg(Color::LightBlue).add_modifier(Modifier::BOLD),
+                played: Style::default().fg(Color::Black).bg(Color::Gray),
+                alarm: badge(Color::White, Color::Red),
+                caution: badge(Color::Black, Color::LightYellow),
+                ducking: badge(Color::Black, Color::LightCyan),
+                marks: true,
+                colors: true,
+            },
+            Palette::Colorblind => {
+                let (orange, sky_blue, blue, yellow, purple) = (Color::Indexed(208), Color::Indexed(74), Color::Indexed(32), Color::Indexed(220), Color::Indexed(175));
+                Styles {
+                    tile: fg(Color::White),
+                    selected: fg(yellow),
+                    flash: Style::default().fg(Color::Black).bg(sky_blue),
+                    fading: fg(sky_blue),
+                    error: fg(orange),
+                    warning: fg(yellow),
+                    dim: fg(Color::DarkGray),
+                    accent: fg(purple),
+                    good: fg(blue),
+                    directory: fg(blue),
+                    played: Style::default().bg(Color::DarkGray),
+                    alarm: badge(Color::Black, orange),
+                    caution: badge(Color::Black, yellow),
+                    ducking: Style::default().fg(Color::Black).bg(purple),
+                    marks: true,
+                    colors: true,
+                }
+            }
+            Palette::None => {
+                let with = |modifier| Style::default().add_modifier(modifier);
+                Styles {
+                    tile: Style::default(),
+                    selected: with(Modifier::BOLD),
+                    flash: with(Modifier::REVERSED),
+                    fading: with(Modifier::BOLD),
+                    error: with(Modifier::BOLD),
+                    warning: Style::default(),
+                    dim: with(Modifier::DIM),
+                    accent: with(Modifier::BOLD),
+                    good: with(Modifier::BOLD),
+                    directory: with(Modifier::BOLD),
+                    played: with(Modifier::REVERSED),
+                    alarm: with(Modifier::REVERSED | Modifier::BOLD),
+                    caution: with(Modifier::REVERSED),
+                    ducking: with(Modifier::REVERSED),
+                    marks: true,
+                    colors: false,
+                }
+            }
+        }
+    }
+}
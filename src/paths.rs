@@ -0,0 +1,199 @@
+//! Where the board keeps its files: the config, the sounds it downloads and records, the cache
+//! of converted sounds, and what sessions leave behind, like exported history. Each goes where
+//! the platform expects it (the XDG base directories on Linux and the BSDs, `~/Library` on
+//! macOS, `%APPDATA%` on Windows), and each can be moved from the command line.
+//!
+//! A config with `portable = true` keeps all of it in its own folder instead, the cache too, so
+//! the folder can go from machine to machine, like on a USB stick with the soundboard on it: a
+//! portable config next to the soundboard is the one it uses, when there is none where it is
+//! started.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::config::DEFAULT_CONFIG_PATH;
+use crate::toml::{self, Value};
+
+/// What the board's own directories are called in the platform's.
+const NAME: &str = "soundboard";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    /// The config file
+    pub config: PathBuf,
+    /// Sounds that were downloaded or recorded
+    pub data: PathBuf,
+    /// Where converted sounds go when the config doesn't say, if there is anywhere
+    pub cache: Option<PathBuf>,
+    /// What sessions leave behind, like exported history
+    pub state: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dir {
+    Config,
+    Data,
+    Cache,
+    State,
+}
+
+impl Paths {
+    /// The config in the working directory when there is one there, as the board has always
+    /// looked for it, then a portable one next to the soundboard itself, or else where the
+    /// platform keeps configs.
+    pub fn detect() -> Self {
+        let local = PathBuf::from(DEFAULT_CONFIG_PATH);
+        if local.exists() {
+            return Self::beside(local);
+        }
+        let carried = std::env::current_exe().ok().and_then(|exe| Some(exe.parent()?.join(DEFAULT_CONFIG_PATH)));
+        if let Some(config) = carried.filter(|config| portable(config)) {
+            return Self::beside(config);
+        }
+        Self::platform(&env).unwrap_or_else(|| Self::beside(local))
+    }
+
+    /// Everything next to the config at `config`, like the folder `soundboard init` makes. Only
+    /// the cache stays where the platform keeps caches, unless the config is portable.
+    pub fn beside(config: PathBuf) -> Self {
+        let dir = config.parent().unwrap_or(Path::new("")).to_path_buf();
+        let cache = if portable(&config) { Some(dir.join("cache")) } else { base(Dir::Cache, &env) };
+        Self { data: dir.clone(), cache, state: dir, config }
+    }
+
+    /// The platform's places, `None` when they can't be found, like without a home directory.
+    fn platform(env: &dyn Fn(&str) -> Option<PathBuf>) -> Option<Self> {
+        Some(Self {
+            config: base(Dir::Config, env)?.join(DEFAULT_CONFIG_PATH),
+            data: base(Dir::Data, env)?,
+            cache: base(Dir::Cache, env),
+            state: base(Dir::State, env)?,
+        })
+    }
+
+    /// The folder the config is in, which the files in it are found from.
+    pub fn folder(&self) -> &Path {
+        self.config.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."))
+    }
+
+    /// Where downloaded sounds go.
+    pub fn sounds(&self) -> PathBuf {
+        self.data.join("sounds")
+    }
+
+    /// Where downloads that are only kept for a while go, like sounds copied as URLs: the cache,
+    /// so they are the user's own and go when it is cleared, or the state without one.
+    pub fn downloads(&self, what: &str) -> PathBuf {
+        self.cache.as_ref().unwrap_or(&self.state).join(what)
+    }
+
+    /// What files made for this config are named after, like `soundboard-history-….csv`.
+    pub fn stem(&self) -> String {
+        self.config.file_stem().map_or(NAME.into(), |s| s.to_string_lossy().into_owned())
+    }
+}
+
+/// The board's own directory of the kind `dir` on this platform.
+fn base(dir: Dir, env: &dyn Fn(&str) -> Option<PathBuf>) -> Option<PathBuf> {
+    let home = || env("HOME");
+    let base = if cfg!(windows) {
+        match dir {
+            Dir::Config | Dir::Data => env("APPDATA"),
+            Dir::Cache | Dir::State => env("LOCALAPPDATA"),
+        }
+    } else if cfg!(target_os = "macos") {
+        match dir {
+            Dir::Cache => home().map(|home| home.join("Library/Caches")),
+            _ => home().map(|home| home.join("Library/Application Support")),
+        }
+    } else {
+        let (var, fallback) = match dir {
+            Dir::Config => ("XDG_CONFIG_HOME", ".config"),
+            Dir::Data => ("XDG_DATA_HOME", ".local/share"),
+            Dir::Cache => ("XDG_CACHE_HOME", ".cache"),
+            Dir::State => ("XDG_STATE_HOME", ".local/state"),
+        };
+        // Relative paths in these are to be ignored, says the spec
+        env(var).filter(|p| p.is_absolute()).or_else(|| home().map(|home| home.join(fallback)))
+    };
+    base.map(|b| b.join(NAME))
+}
+
+/// Whether the config at `config` has `portable = true`.
+fn portable(config: &Path) -> bool {
+    let Ok(src) = fs::read_to_string(config) else { return false };
+    toml::parse(&src).is_ok_and(|table| table.entry("portable").is_some_and(|e| matches!(e.value, Value::Boolean(true))))
+}
+
+fn env(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var).filter(|v| !v.is_empty()).map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use crate::config::{Board, Source};
+    use crate::harness;
+    use super::Paths;
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn platform_paths_follow_xdg() {
+        let env = |var: &str| match var {
+            "HOME" => Some(PathBuf::from("/home/dj")),
+            "XDG_CONFIG_HOME" => Some(PathBuf::from("/etc/dj")),
+            "XDG_DATA_HOME" => Some(PathBuf::from("relative/is/ignored")),
+            _ => None,
+        };
+        let paths = Paths::platform(&env).unwrap();
+        assert_eq!(paths.config, Path::new("/etc/dj/soundboard/soundboard.toml"));
+        assert_eq!(paths.data, Path::new("/home/dj/.local/share/soundboard"));
+        assert_eq!(paths.cache.as_deref(), Some(Path::new("/home/dj/.cache/soundboard")));
+        assert_eq!(paths.state, Path::new("/home/dj/.local/state/soundboard"));
+        assert_eq!(Paths::platform(&|_| None), None);
+    }
+
+    #[test]
+    fn a_config_keeps_its_files_beside_it() {
+        let paths = Paths::beside(PathBuf::from("gigs/friday.toml"));
+        assert_eq!(paths.sounds(), Path::new("gigs/sounds"));
+        assert_eq!(paths.state, Path::new("gigs"));
+        assert_eq!(paths.stem(), "friday");
+        assert_ne!(paths.cache.as_deref(), Some(Path::new("gigs/cache")));
+    }
+
+    #[test]
+    fn a_portable_board_takes_all_its_files_along() {
+        let dir = harness::scratch("portable");
+        fs::create_dir_all(dir.join("sounds")).unwrap();
+        fs::write(dir.join("sounds/horn.wav"), crate::config::BUILTIN[0].1).unwrap();
+        let config = dir.join("soundboard.toml");
+        fs::write(&config, "version = 2\nportable = true\n[[sound]]\nname = \"horn\"\nfile = \"sounds\\\\horn.wav\"\n").unwrap();
+        let paths = Paths::beside(config.clone());
+        assert_eq!(paths.cache, Some(dir.join("cache")));
+
+        // Files are found from the config's folder, written there on any platform
+        let board = Board::load(&config).unwrap().0;
+        assert!(board.portable);
+        assert!(board.sounds[0].data().is_ok(), "{:?}", board.sounds[0].source);
+        board.save(&config).unwrap();
+        assert!(fs::read_to_string(&config).unwrap().contains("file = \"sounds/horn.wav\""));
+
+        // Sounds added from elsewhere are copied in, next to one of the same name
+        let mut board = board;
+        let elsewhere = harness::scratch("portable-elsewhere");
+        fs::write(elsewhere.join("horn.wav"), crate::config::BUILTIN[1].1).unwrap();
+        board.import(&elsewhere.join("horn.wav")).unwrap();
+        board.carry(1, &dir, &dir.join("sounds")).unwrap();
+        assert_eq!(board.sounds[1].source, Source::File(dir.join("sounds/horn 2.wav")));
+        assert!(board.sounds[1].data().is_ok());
+        drop(elsewhere);
+
+        // Those from before version 2 were found from the working directory, and still are
+        fs::write(&config, "version = 1\n[[sound]]\nname = \"horn\"\nfile = \"elsewhere/horn.wav\"\n").unwrap();
+        let board = Board::load(&config).unwrap().0;
+        assert_eq!(board.sounds[0].source, Source::File("elsewhere/horn.wav".into()));
+        let saved = std::path::absolute("elsewhere/horn.wav").unwrap();
+        assert!(fs::read_to_string(&config).unwrap().contains(&format!("file = {:?}", saved.to_string_lossy())));
+    }
+}
@@ -0,0 +1,147 @@
+//! USB foot pedals, for hands that are busy playing something else. Pedals show up as keyboards
+//! that send a key (most send `a`, `b` and `c`, or can be set to anything), so the board reads
+//! them through Linux's evdev (`/dev/input/event*`) instead of the terminal. It grabs them while
+//! it runs, so their keys don't also end up typed into whatever has focus.
+
+use std::sync::mpsc::Receiver;
+#[cfg(target_os = "linux")]
+use std::sync::{atomic::AtomicBool, Arc};
+
+/// A pedal being pressed down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Press {
+    /// The name the device reports, like `PCsensor FootSwitch`
+    pub device: String,
+    /// Whether the board recognizes the device as a pedal on its own
+    pub known: bool,
+    /// The evdev key code, see [`code`]
+    pub code: u16,
+}
+
+/// The character keys, by row with the code of the first key on it.
+const ROWS: [(&str, u16); 4] = [("1234567890", 2), ("qwertyuiop", 16), ("asdfghjkl", 30), ("zxcvbnm", 44)];
+/// Key names for the evdev codes pedals are usually set to send.
+const KEYS: &[(&str, u16)] = &[
+    ("esc", 1), ("backspace", 14), ("tab", 15), ("enter", 28), ("space", 57),
+    ("pageup", 104), ("pagedown", 109), ("up", 103), ("left", 105), ("right", 106), ("down", 108),
+    ("f11", 87), ("f12", 88),
+];
+
+/// The evdev key code for a key name (`b`, `F13`, `space`) or a raw `code 30`.
+pub fn code(key: &str) -> Option<u16> {
+    let key = key.trim().to_ascii_lowercase();
+    if let Some(code) = key.strip_prefix("code") {
+        return code.trim().parse().ok();
+    }
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        // The rows of a keyboard, each numbered on from where the previous one stopped
+        for (row, first) in ROWS {
+            if let Some(i) = row.find(c) {
+                return Some(first + i as u16);
+            }
+        }
+        return None;
+    }
+    if let Some(n) = key.strip_prefix('f').and_then(|n| n.parse::<u16>().ok()) {
+        match n {
+            1..=10 => return Some(58 + n),
+            13..=24 => return Some(170 + n),
+            _ => {}
+        }
+    }
+    KEYS.iter().find(|(name, _)| *name == key).map(|&(_, code)| code)
+}
+
+/// How to write `code` as a pedal `key`, the inverse of [`code`].
+pub fn key_name(code: u16) -> String {
+    let letter = ROWS.iter().find_map(|&(row, first)| row.chars().nth(code.checked_sub(first)? as usize));
+    match (letter, code) {
+        (Some(c), _) => c.to_string(),
+        (None, 59..=68) => format!("F{}", code - 58),
+        (None, 183..=194) => format!("F{}", code - 170),
+        _ => KEYS.iter().find(|&&(_, c)| c == code).map_or_else(|| format!("code {code}"), |(name, _)| name.to_string()),
+    }
+}
+
+/// Reads every pedal until dropped.
+pub struct Pedals {
+    #[cfg(target_os = "linux")]
+    stop: Arc<AtomicBool>,
+    pub presses: Receiver<Press>,
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Pedals {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Whether a device is a foot pedal, going by its name and USB vendor and product ids.
+#[cfg(target_os = "linux")]
+fn known(name: &str, id: (u16, u16)) -> bool {
+    let name = name.to_lowercase();
+    // PCsensor's pedals, which most cheap ones are
+    let ids = [(0x0c45, 0x7403), (0x0c45, 0x7404), (0x413d, 0x2107), (0x3553, 0xb001)];
+    ["footswitch", "foot switch", "pedal"].iter().any(|n| name.contains(n)) || ids.contains(&id)
+}
+
+/// Opens recognized pedals, and any other device whose name contains one of `devices`.
+#[cfg(target_os = "linux")]
+pub fn open(devices: Vec<String>) -> color_eyre::Result<Pedals> {
+    use std::path::PathBuf;
+    use color_eyre::eyre::Context;
+
+    std::fs::read_dir("/sys/class/input").wrap_err("look for pedals in /sys/class/input")?;
+
+    let filter = move |entry: &std::fs::DirEntry| {
+        entry.file_name().to_str().filter(|n| n.starts_with("event"))?;
+        let info = entry.path().join("device");
+        let read = |file: &str| std::fs::read_to_string(info.join(file)).unwrap_or_default().trim().to_string();
+        let hex = |file: &str| u16::from_str_radix(&read(file), 16).unwrap_or(0);
+        let name = read("name");
+        let known = known(&name, (hex("id/vendor"), hex("id/product")));
+        let wanted = known || devices.iter().any(|d| name.contains(d.as_str()));
+        wanted.then(|| (PathBuf::from("/dev/input").join(entry.file_name()), (name, known)))
+    };
+    let open = |path: &std::path::Path, device| Some((std::fs::File::open(path).ok()?, device));
+    let read = |(file, (name, known)): (std::fs::File, (String, bool)), tx: &_, stopped: &_| read_presses(file, &name, known, tx, stopped);
+    let (stop, presses) = crate::hotplug::watch("/sys/class/input", filter, open, read);
+    Ok(Pedals { stop, presses })
+}
+
+/// Reads `struct input_event`s, see the kernel's `Documentation/input/input.rst`.
+#[cfg(target_os = "linux")]
+fn read_presses(mut file: std::fs::File, device: &str, known: bool, tx: &std::sync::mpsc::Sender<Press>, stopped: &AtomicBool) {
+    use std::io::Read;
+    use std::os::fd::AsRawFd;
+    use std::sync::atomic::Ordering;
+
+    const EV_KEY: u16 = 0x01;
+    /// `_IOW('E', 0x90, int)`, taking the device for ourselves
+    const EVIOCGRAB: libc::c_ulong = 0x4004_4590;
+
+    // Without the grab the pedal still works, it just types its key into the terminal as well
+    unsafe { libc::ioctl(file.as_raw_fd(), EVIOCGRAB as _, 1 as libc::c_int) };
+
+    let time = std::mem::size_of::<libc::timeval>();
+    let mut event = vec![0u8; time + 8];
+    while file.read_exact(&mut event).is_ok() {
+        if stopped.load(Ordering::Relaxed) {
+            break;
+        }
+        let kind = u16::from_ne_bytes([event[time], event[time + 1]]);
+        let code = u16::from_ne_bytes([event[time + 2], event[time + 3]]);
+        let value = i32::from_ne_bytes([event[time + 4], event[time + 5], event[time + 6], event[time + 7]]);
+        // 1 is a press, 0 a release and 2 auto-repeat while held
+        if kind == EV_KEY && value == 1 && tx.send(Press { device: device.to_string(), known, code }).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn open(_devices: Vec<String>) -> color_eyre::Result<Pedals> {
+    color_eyre::eyre::bail!("foot pedals are only supported on Linux for now")
+}
@@ -0,0 +1,96 @@
+//! A mode for screen readers, turned on with `[ui] screen-reader = true` or `--screen-reader`.
+//! The board is drawn as a plain list with the terminal's cursor on the selected sound, nothing
+//! on screen moves by itself, and what changes is announced: the selection, the view, what starts
+//! playing and what the status line says.
+//!
+//! Announcements go to the terminal as desktop notifications (OSC 9), which screen readers read
+//! out, with the bell rung for what went wrong.
+
+/// Something to say, and whether it is bad news.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Announcement {
+    pub text: String,
+    pub alert: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Reader {
+    queue: Vec<Announcement>,
+    /// What was last said about where the user is, and about the status line, to only say what
+    /// changed
+    pub place: Option<String>,
+    pub status: Option<String>,
+}
+
+impl Reader {
+    pub fn say(&mut self, text: impl Into<String>) {
+        self.queue.push(Announcement { text: text.into(), alert: false });
+    }
+
+    /// Says `text` with the bell.
+    pub fn alert(&mut self, text: impl Into<String>) {
+        self.queue.push(Announcement { text: text.into(), alert: true });
+    }
+
+    /// What is waiting to be said, oldest first.
+    pub fn take(&mut self) -> Vec<Announcement> {
+        std::mem::take(&mut self.queue)
+    }
+}
+
+/// What makes a terminal say `announcement`. Control characters in the text are left out so
+/// a sound's name can't end the notification early or do anything else to the terminal.
+pub fn escape(announcement: &Announcement) -> String {
+    let text: String = announcement.text.chars().filter(|c| !c.is_control()).collect();
+    let bell = if announcement.alert { "\x07" } else { "" };
+    format!("\x1b]9;{text}\x07{bell}")
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use crate::harness::{self, Harness};
+    use super::{escape, Announcement};
+
+    fn said(h: &mut Harness) -> Vec<String> {
+        h.app.reader.take().into_iter().map(|a| a.text).collect()
+    }
+
+    #[test]
+    fn changes_are_announced() {
+        let mut board = harness::board(3);
+        board.screen_reader = true;
+        let mut h = Harness::new(board, 80, 24).loaded();
+        assert_eq!(said(&mut h), ["sound 0, 1 of 3"]);
+
+        h.press(KeyCode::Right);
+        h.press(KeyCode::Enter);
+        h.step();
+        assert_eq!(said(&mut h), ["sound 1, 2 of 3", "playing sound 1"]);
+        // Nothing changed, so nothing is said again
+        h.step();
+        assert!(said(&mut h).is_empty());
+
+        h.press(KeyCode::Tab);
+        assert_eq!(said(&mut h), ["Trash"]);
+    }
+
+    #[test]
+    fn the_list_follows_the_cursor() {
+        let mut board = harness::board(3);
+        board.screen_reader = true;
+        let mut h = Harness::new(board, 80, 24).loaded();
+        h.press(KeyCode::Down);
+        let screen = h.screen();
+        let lines: Vec<_> = screen.lines().take(3).collect();
+        assert_eq!(lines, ["  sound 0", "> sound 1", "  sound 2"]);
+        h.terminal.show_cursor().unwrap();
+        assert_eq!(h.terminal.get_cursor().unwrap(), (0, 1));
+    }
+
+    #[test]
+    fn names_cant_escape() {
+        let announcement = Announcement { text: "boom\x07\x1b]0;pwned".to_string(), alert: true };
+        assert_eq!(escape(&announcement), "\x1b]9;boom]0;pwned\x07\x07");
+    }
+}
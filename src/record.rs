@@ -0,0 +1,330 @@
+//! Master recording: mixes everything the monitor plays into a WAV file, and writes markers for
+//! when each sound started and stopped so editors can find (or remove) them in post. Buses with
+//! `stem = true` get a file of their own next to it as well, with only the sounds on that bus.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use color_eyre::eyre::{eyre, Context};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rodio::Source;
+use crate::audio::Gain;
+use crate::channels::Matrix;
+
+pub const RATE: u32 = 48_000;
+pub const CHANNELS: u16 = 2;
+
+/// How far behind real time the mix is written out, so late samples from the audio threads
+/// (which run ahead by their buffer size) still make it in.
+const LATENCY: Duration = Duration::from_secs(1);
+const FLUSH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A sound that was played while recording.
+#[derive(Debug, Clone)]
+struct Marker {
+    id: u64,
+    name: String,
+    start: Duration,
+    end: Option<Duration>,
+}
+
+struct Mix {
+    /// Frames already written to disk
+    flushed: u64,
+    /// Interleaved samples from `flushed` on
+    pending: VecDeque<f32>,
+    /// Taken out once the recording is finished
+    writer: Option<WavWriter<BufWriter<File>>>,
+}
+
+impl Mix {
+    /// Writes everything before `frame` to disk.
+    fn flush_until(&mut self, frame: u64) -> hound::Result<()> {
+        let Some(writer) = &mut self.writer else { return Ok(()) };
+        while self.flushed < frame {
+            for _ in 0..CHANNELS {
+                writer.write_sample(self.pending.pop_front().unwrap_or(0.0).clamp(-1.0, 1.0))?;
+            }
+            self.flushed += 1;
+        }
+        Ok(())
+    }
+}
+
+struct Shared {
+    start: Instant,
+    /// The recording, then the stems
+    mixes: Vec<Mutex<Mix>>,
+    markers: Mutex<Vec<Marker>>,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    fn frames_at(&self, t: Instant) -> u64 {
+        (t.saturating_duration_since(self.start).as_secs_f64() * RATE as f64) as u64
+    }
+}
+
+/// What a finished recording wrote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recorded {
+    pub path: PathBuf,
+    pub markers: Vec<PathBuf>,
+    /// One for each bus that has a stem
+    pub stems: Vec<PathBuf>,
+}
+
+pub struct Recorder {
+    path: PathBuf,
+    /// The buses that have a stem, and where it goes
+    stems: Vec<(String, PathBuf)>,
+    shared: Arc<Shared>,
+    flusher: Mutex<Option<JoinHandle<hound::Result<()>>>>,
+}
+
+impl Recorder {
+    /// Records to `path`, and the sounds on each of `stems` to a file of their own next to it.
+    pub fn start(path: &Path, stems: &[String]) -> color_eyre::Result<Self> {
+        let spec = WavSpec { channels: CHANNELS, sample_rate: RATE, bits_per_sample: 32, sample_format: SampleFormat::Float };
+        let create = |path: &Path| -> color_eyre::Result<_> {
+            let writer = WavWriter::create(path, spec).wrap_err_with(|| format!("create {}", path.display()))?;
+            Ok(Mutex::new(Mix { flushed: 0, pending: VecDeque::new(), writer: Some(writer) }))
+        };
+        let stem_paths: Vec<PathBuf> = stems.iter().map(|bus| stem_path(path, bus)).collect();
+        let mixes = [path].into_iter().chain(stem_paths.iter().map(PathBuf::as_path)).map(create).collect::<color_eyre::Result<_>>()?;
+        let shared = Arc::new(Shared {
+            start: Instant::now(),
+            mixes,
+            markers: Mutex::new(Vec::new()),
+            stop: AtomicBool::new(false),
+        });
+
+        let flusher = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                while !shared.stop.load(Ordering::Relaxed) {
+                    thread::sleep(FLUSH_INTERVAL);
+                    let until = shared.frames_at(Instant::now().checked_sub(LATENCY).unwrap_or(shared.start));
+                    for mix in &shared.mixes {
+                        mix.lock().unwrap().flush_until(until)?;
+                    }
+                }
+                Ok(())
+            })
+        };
+
+        Ok(Self { path: path.to_path_buf(), stems: stems.iter().cloned().zip(stem_paths).collect(), shared, flusher: Mutex::new(Some(flusher)) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.shared.start.elapsed()
+    }
+
+    /// Notes that sound `id` started, and returns a tap that mixes its samples into the recording,
+    /// and into the stem of `bus` if it has one. The tap mixes at `gain`, which the player keeps up
+    /// to date with the sound's volume.
+    pub fn tap(&self, id: u64, name: &str, bus: Option<&str>, gain: Gain) -> Tap {
+        let start = self.elapsed();
+        self.shared.markers.lock().unwrap().push(Marker { id, name: name.to_string(), start, end: None });
+        let stem = bus.and_then(|bus| self.stems.iter().position(|(b, _)| b == bus));
+        let mixes = [0].into_iter().chain(stem.map(|i| i + 1)).collect();
+        Tap { shared: self.shared.clone(), mixes, cursor: self.shared.frames_at(Instant::now()) * CHANNELS as u64, gain }
+    }
+
+    /// Notes that sound `id` stopped.
+    pub fn ended(&self, id: u64) {
+        let end = self.elapsed();
+        if let Some(m) = self.shared.markers.lock().unwrap().iter_mut().find(|m| m.id == id) {
+            m.end.get_or_insert(end);
+        }
+    }
+
+    /// Writes out the rest of the recording, its stems and its markers.
+    pub fn finish(&self) -> color_eyre::Result<Recorded> {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        if let Some(flusher) = self.flusher.lock().unwrap().take() {
+            flusher.join().map_err(|_| eyre!("the recording thread panicked"))?.wrap_err("write recording")?;
+        }
+
+        let end = self.elapsed();
+        // Up to now even if nothing played lately, so the recording is as long as it ran, and the
+        // stems as long as the recording
+        let mut mixes: Vec<_> = self.shared.mixes.iter().map(|m| m.lock().unwrap()).collect();
+        let until = mixes.iter().map(|mix| mix.flushed + (mix.pending.len() / CHANNELS as usize) as u64).fold(self.shared.frames_at(Instant::now()), u64::max);
+        for mix in &mut mixes {
+            mix.flush_until(until).wrap_err("write recording")?;
+            if let Some(writer) = mix.writer.take() {
+                writer.finalize().wrap_err("finish recording")?;
+            }
+        }
+        drop(mixes);
+        let markers = self.shared.markers.lock().unwrap().clone();
+
+        let mut labels = String::new();
+        let mut csv = String::from("start,end,sound\n");
+        for m in &markers {
+            let (start, end) = (m.start.as_secs_f64(), m.end.unwrap_or(end).as_secs_f64());
+            // Audacity label tracks: tab separated start, end and label
+            let _ = writeln!(labels, "{start:.6}\t{end:.6}\t{}", m.name.replace(['\t', '\n'], " "));
+            let _ = writeln!(csv, "{start:.3},{end:.3},\"{}\"", m.name.replace('"', "\"\""));
+        }
+
+        let outputs = [(self.path.with_extension("labels.txt"), labels), (self.path.with_extension("markers.csv"), csv)];
+        for (path, contents) in &outputs {
+            fs::write(path, contents).wrap_err_with(|| format!("write {}", path.display()))?;
+        }
+        let markers = outputs.into_iter().map(|(path, _)| path).collect();
+        Ok(Recorded { path: self.path.clone(), markers, stems: self.stems.iter().map(|(_, path)| path.clone()).collect() })
+    }
+}
+
+/// Where the stem of `bus` goes for a recording at `path`: `show.wav` has `show.music.wav`.
+fn stem_path(path: &Path, bus: &str) -> PathBuf {
+    let bus: String = bus.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' }).collect();
+    path.with_extension(format!("{bus}.wav"))
+}
+
+/// Passes a sound through unchanged, adding each sample into the recording at the moment it
+/// is played.
+pub struct Tap {
+    shared: Arc<Shared>,
+    /// Which of the mixes it goes into
+    mixes: Vec<usize>,
+    /// Where the next sample goes, in interleaved samples since the recording started
+    cursor: u64,
+    gain: Gain,
+}
+
+impl Tap {
+    /// Mixes `samples` in at the cursor, first catching the cursor up with the clock if the sound
+    /// was paused (nothing is pulled from a paused sound, but time goes on).
+    pub fn write(&mut self, samples: &[f32]) {
+        let now = self.shared.frames_at(Instant::now()) * CHANNELS as u64;
+        let slack = (RATE as u64 / 4) * CHANNELS as u64;
+        if now > self.cursor + slack {
+            self.cursor = now - now % CHANNELS as u64;
+        }
+
+        let gain = self.gain.get();
+        for &i in &self.mixes {
+            let mut mix = self.shared.mixes[i].lock().unwrap();
+            let flushed = mix.flushed * CHANNELS as u64;
+            for (cursor, &s) in (self.cursor..).zip(samples) {
+                if cursor >= flushed {
+                    let idx = (cursor - flushed) as usize;
+                    if mix.pending.len() <= idx {
+                        mix.pending.resize(idx + 1, 0.0);
+                    }
+                    mix.pending[idx] += s * gain;
+                }
+            }
+        }
+        self.cursor += samples.len() as u64;
+    }
+}
+
+/// Feeds a source at the recording's rate through a [`Tap`], mixed down to the recording's
+/// channels.
+pub struct Tapped<S> {
+    inner: S,
+    tap: Tap,
+    fold: Matrix,
+    frame: Vec<f32>,
+    chunk: Vec<f32>,
+}
+
+impl<S: Source<Item = f32>> Tapped<S> {
+    pub fn new(inner: S, tap: Tap) -> Self {
+        let fold = Matrix::new(inner.channels(), &[], CHANNELS);
+        Self { frame: Vec::with_capacity(fold.inputs()), inner, tap, fold, chunk: Vec::with_capacity(512) }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Tapped<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.inner.next();
+        match sample {
+            Some(s) => {
+                self.frame.push(s);
+                if self.frame.len() < self.fold.inputs() {
+                    return sample;
+                }
+                let mut out = [0.0; CHANNELS as usize];
+                self.fold.apply(&self.frame, &mut out);
+                self.frame.clear();
+                self.chunk.extend(out);
+                if self.chunk.len() >= self.chunk.capacity() {
+                    self.tap.write(&self.chunk);
+                    self.chunk.clear();
+                }
+            }
+            None if !self.chunk.is_empty() => {
+                self.tap.write(&self.chunk);
+                self.chunk.clear();
+            }
+            None => {}
+        }
+        sample
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Tapped<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use hound::WavReader;
+    use crate::audio::Gain;
+    use crate::harness;
+    use super::Recorder;
+
+    fn loudest(path: &Path) -> f32 {
+        WavReader::open(path).unwrap().samples::<f32>().map(Result::unwrap).fold(0.0, |peak, s| peak.max(s.abs()))
+    }
+
+    #[test]
+    fn stems_only_have_their_bus() {
+        let dir = harness::scratch("record");
+        let recorder = Recorder::start(&dir.join("show.wav"), &["music".to_string(), "voice".to_string()]).unwrap();
+        recorder.tap(0, "bed", Some("music"), Gain::new(0.5)).write(&[0.5; 960]);
+        recorder.tap(1, "horn", None, Gain::default()).write(&[0.5; 960]);
+        recorder.tap(2, "horn", Some("sfx"), Gain::default()).write(&[0.5; 960]);
+
+        let recorded = recorder.finish().unwrap();
+        assert_eq!(recorded.stems, [dir.join("show.music.wav"), dir.join("show.voice.wav")]);
+        assert!(loudest(&recorded.path) >= 0.5);
+        assert_eq!(loudest(&recorded.stems[0]), 0.25);
+        assert_eq!(loudest(&recorded.stems[1]), 0.0);
+        // As long as the recording
+        let length = |path: &Path| WavReader::open(path).unwrap().len();
+        assert_eq!(length(&recorded.stems[0]), length(&recorded.path));
+    }
+}
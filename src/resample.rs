@@ -0,0 +1,172 @@
+//! Converting sounds to another sample rate. rodio does that by drawing straight lines between
+//! samples, which is cheap but dulls the highs and lets some aliasing through; the windowed sinc
+//! resampler here is closer to the original, at the cost of a few dozen multiplications per
+//! sample. Which one to use is the `resampler` in the `[audio]` section.
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::time::Duration;
+use rodio::Source;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Resampler {
+    /// rodio's own
+    #[default]
+    Linear,
+    Sinc,
+}
+
+impl Resampler {
+    pub const ALL: [Self; 2] = [Self::Linear, Self::Sinc];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::Sinc => "sinc",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.name().eq_ignore_ascii_case(s.trim()))
+    }
+
+    /// `source` at `rate`, or as it is for the linear resampler, which rodio applies itself.
+    pub fn apply<S: Source<Item = f32> + Send + 'static>(self, source: S, rate: u32) -> Box<dyn Source<Item = f32> + Send> {
+        match self {
+            Self::Sinc if source.sample_rate() != rate => Box::new(Sinc::new(source, rate)),
+            _ => Box::new(source),
+        }
+    }
+}
+
+/// Input samples the kernel reaches on either side, at the input's full bandwidth.
+const TAPS: usize = 16;
+/// Kernel values looked up per input sample; the ones between are interpolated.
+const RESOLUTION: usize = 256;
+/// Where the filter cuts off, as a fraction of the lower rate's Nyquist frequency.
+const CUTOFF: f64 = 0.95;
+
+/// A source resampled with a Blackman windowed sinc.
+pub struct Sinc<S> {
+    inner: S,
+    channels: usize,
+    rate: u32,
+    /// Input frames per output frame
+    step: f64,
+    /// Input frames the kernel reaches on either side
+    half: usize,
+    /// One half of the kernel, `RESOLUTION` values per input frame from its center
+    kernel: Vec<f32>,
+    /// Interleaved input frames, the first being the oldest the kernel still reaches
+    frames: VecDeque<f32>,
+    /// Where the next output frame is, in input frames from the start of `frames`
+    pos: f64,
+    /// Input frames left to cover once the input ended, `None` before that
+    left: Option<f64>,
+    out: Vec<f32>,
+    /// The next sample of `out` to hand out
+    next: usize,
+}
+
+impl<S: Source<Item = f32>> Sinc<S> {
+    pub fn new(inner: S, rate: u32) -> Self {
+        let channels = inner.channels().max(1) as usize;
+        let from = inner.sample_rate().max(1) as f64;
+        let to = rate.max(1) as f64;
+        // Going down, everything the new rate can't hold must be filtered out first
+        let cutoff = CUTOFF * (to / from).min(1.0);
+        let half = (TAPS as f64 / cutoff).ceil() as usize;
+        let kernel = (0..=half * RESOLUTION)
+            .map(|i| {
+                let x = i as f64 / RESOLUTION as f64;
+                let sinc = if x == 0.0 { 1.0 } else { (PI * cutoff * x).sin() / (PI * cutoff * x) };
+                let w = x / half as f64;
+                let window = 0.42 + 0.5 * (PI * w).cos() + 0.08 * (2.0 * PI * w).cos();
+                (cutoff * sinc * window) as f32
+            })
+            .collect();
+        // Silence before the start, so the first samples have something on their left
+        let frames = VecDeque::from(vec![0.0; (half - 1) * channels]);
+        Self { inner, channels, rate, step: from / to, half, kernel, frames, pos: (half - 1) as f64, left: None, out: vec![0.0; channels], next: channels }
+    }
+
+    fn weight(&self, distance: f64) -> f32 {
+        let at = distance.abs() * RESOLUTION as f64;
+        let i = at as usize;
+        let (a, b) = (self.kernel.get(i).copied().unwrap_or(0.0), self.kernel.get(i + 1).copied().unwrap_or(0.0));
+        a + (b - a) * (at - i as f64) as f32
+    }
+
+    /// Works out the next output frame, `None` once the input is covered.
+    fn frame(&mut self) -> Option<()> {
+        let center = self.pos.floor() as usize;
+        // Everything the kernel reaches on the right
+        while self.frames.len() < (center + self.half + 1) * self.channels {
+            let mut frame = self.inner.by_ref().take(self.channels).peekable();
+            match (frame.peek().is_some(), self.left) {
+                (true, _) => self.frames.extend(frame),
+                (false, None) => {
+                    // Whatever part of the input is still ahead of the output
+                    let read = self.frames.len() / self.channels;
+                    self.left = Some(read as f64 - self.pos);
+                }
+                (false, Some(_)) => {}
+            }
+            // A cut off last frame and the silence after the end
+            let whole = self.frames.len().div_ceil(self.channels) + self.left.is_some() as usize;
+            self.frames.resize(whole * self.channels, 0.0);
+        }
+        if let Some(left) = &mut self.left {
+            if *left <= 0.0 {
+                return None;
+            }
+            *left -= self.step;
+        }
+
+        self.out.fill(0.0);
+        for j in (center + 1 - self.half)..=(center + self.half) {
+            let weight = self.weight(self.pos - j as f64);
+            for (c, out) in self.out.iter_mut().enumerate() {
+                *out += weight * self.frames[j * self.channels + c];
+            }
+        }
+
+        self.pos += self.step;
+        // Frames the kernel no longer reaches
+        let done = (self.pos.floor() as usize + 1).saturating_sub(self.half);
+        self.frames.drain(..done * self.channels);
+        self.pos -= done as f64;
+        Some(())
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Sinc<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.next == self.channels {
+            self.frame()?;
+            self.next = 0;
+        }
+        self.next += 1;
+        Some(self.out[self.next - 1])
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Sinc<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
@@ -0,0 +1,269 @@
+//! Drives the board from another program instead of the terminal: JSON-RPC 2.0 requests come in
+//! on stdin, one per line, and responses and events go out on stdout the same way.
+//!
+//! ```text
+//! > {"jsonrpc": "2.0", "id": 1, "method": "play", "params": {"sound": "airhorn"}}
+//! < {"jsonrpc":"2.0","method":"played","params":{"sound":"airhorn","via":"rpc","offset":3.2}}
+//! < {"jsonrpc":"2.0","id":1,"result":null}
+//! ```
+//!
+//! Methods: `sounds`, `play` (`sound`, a name or alias), `go` (the next cue), `mute` (`muted`,
+//! toggles when left out), `bus` (`bus`, with `volume`, `muted` and `wet` to change, see
+//! [`crate::bus`]), `scene` (`scene`, recalled, or saved with `save`), `crossfade` (`position`,
+//! from 0 for the left bus to 1 for the right), `record` (`path`, optional), `stop_recording`,
+//! `status`, `show` (where the board is drawn, see [`crate::show`]) and `quit`.
+//! Events: `played` for every sound that starts, however it was set off, and `status` for the
+//! messages the board would show in its status bar.
+//!
+//! A board that is running takes the same requests on its socket, see [`crate::instance`],
+//! though only the responses come back there.
+
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::app::App;
+use crate::config::MAX_VOLUME;
+use crate::history::Via;
+use crate::json::{self, Value};
+use crate::service::Terminate;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Error codes from the JSON-RPC 2.0 spec
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+/// The start of the range left for implementations, for requests that were fine but failed
+const FAILED: i32 = -32000;
+
+struct Error {
+    code: i32,
+    message: String,
+}
+
+impl Error {
+    fn params(message: impl Into<String>) -> Self {
+        Self { code: INVALID_PARAMS, message: message.into() }
+    }
+
+    fn failed(message: impl Into<String>) -> Self {
+        Self { code: FAILED, message: message.into() }
+    }
+}
+
+/// Serves requests until stdin closes, `quit` is called or the process is terminated.
+pub fn run(app: &mut App, terminate: &Terminate) -> color_eyre::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut out = io::stdout().lock();
+    let mut reported = app.history.entries.len();
+    let mut status = app.status.clone();
+    while !app.should_quit && !terminate.requested() {
+        app.tick(Instant::now());
+        let response = match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(line) if line.trim().is_empty() => None,
+            Ok(line) => handle(app, &line),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        // Events first, so a client sees what a request did before it sees the request finish
+        for entry in &app.history.entries[reported..] {
+            let params = Value::object([
+                ("sound", entry.sound.as_str().into()),
+                ("via", entry.via.name().into()),
+                ("offset", entry.offset.as_secs_f64().into()),
+            ]);
+            notify(&mut out, "played", params)?;
+        }
+        reported = app.history.entries.len();
+        if app.status != status {
+            status.clone_from(&app.status);
+            if let Some(message) = &status {
+                notify(&mut out, "status", Value::object([("message", message.as_str().into())]))?;
+            }
+        }
+        if let Some(response) = response {
+            writeln!(out, "{response}")?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn notify(out: &mut impl Write, method: &str, params: Value) -> io::Result<()> {
+    let message = Value::object([("jsonrpc", "2.0".into()), ("method", method.into()), ("params", params)]);
+    writeln!(out, "{message}")?;
+    out.flush()
+}
+
+/// Handles one line of input, returning the response unless it was a notification.
+pub fn handle(app: &mut App, line: &str) -> Option<Value> {
+    let request = match json::parse(line) {
+        Ok(request) => request,
+        Err(e) => return Some(response(Value::Null, Err(Error { code: PARSE_ERROR, message: e }))),
+    };
+    // Requests without an id are notifications, which get no response
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        let error = Error { code: INVALID_REQUEST, message: "expected an object with a \"method\"".to_string() };
+        return Some(response(id.unwrap_or(Value::Null), Err(error)));
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Object(Vec::new()));
+
+    let result = call(app, method, &params);
+    id.map(|id| response(id, result))
+}
+
+fn response(id: Value, result: Result<Value, Error>) -> Value {
+    let outcome = match result {
+        Ok(result) => ("result", result),
+        Err(e) => ("error", Value::object([("code", Value::Number(e.code.into())), ("message", e.message.into())])),
+    };
+    Value::object([("jsonrpc", "2.0".into()), ("id", id), outcome])
+}
+
+fn call(app: &mut App, method: &str, params: &Value) -> Result<Value, Error> {
+    let string = |key: &str| match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => v.as_str().map(Some).ok_or_else(|| Error::params(format!("{key:?} should be a string"))),
+    };
+
+    Ok(match method {
+        "sounds" => Value::Array(
+            app.board
+                .sounds
+                .iter()
+                .map(|s| {
+                    Value::object([
+                        ("name", s.name.as_str().into()),
+                        ("label", s.label.clone().into()),
+                        ("bindings", s.bindings.iter().map(|b| b.label.as_str()).collect::<Vec<_>>().into()),
+                        ("volume", f64::from(s.volume).into()),
+                        ("group", s.group.clone().into()),
+                        ("bus", app.board.bus_of(s).map(str::to_string).into()),
+                        ("plays", s.plays.into()),
+                    ])
+                })
+                .collect(),
+        ),
+        "play" => {
+            let name = string("sound")?.ok_or_else(|| Error::params("\"sound\" is required"))?;
+            if !app.play_named(name, Via::Rpc) {
+                return Err(Error::params(format!("no sound or alias named {name:?}")));
+            }
+            Value::Null
+        }
+        "go" => {
+            let cue = app.cue_standby;
+            if cue >= app.board.cues.len() {
+                return Err(Error::failed("the cue list has ended"));
+            }
+            app.go();
+            Value::object([("cue", Value::Number((cue + 1) as f64))])
+        }
+        "mute" => {
+            let muted = match params.get("muted") {
+                None | Some(Value::Null) => !app.engine.muted(),
+                Some(v) => v.as_bool().ok_or_else(|| Error::params("\"muted\" should be a boolean"))?,
+            };
+            app.engine.set_muted(muted);
+            Value::object([("muted", muted.into())])
+        }
+        "bus" => {
+            let name = string("bus")?.ok_or_else(|| Error::params("\"bus\" is required"))?;
+            let level = app.engine.bus(name);
+            let volume = match params.get("volume") {
+                None | Some(Value::Null) => level.volume.get(),
+                Some(v) => v.as_f64().filter(|v| (0.0..=MAX_VOLUME as f64).contains(v)).ok_or_else(|| Error::params(format!("\"volume\" should be a number between 0 and {MAX_VOLUME}")))? as f32,
+            };
+            let muted = match params.get("muted") {
+                None | Some(Value::Null) => level.muted(),
+                Some(v) => v.as_bool().ok_or_else(|| Error::params("\"muted\" should be a boolean"))?,
+            };
+            let wet = match params.get("wet") {
+                None | Some(Value::Null) => level.wet.get(),
+                Some(v) => v.as_f64().filter(|v| (0.0..=1.0).contains(v)).ok_or_else(|| Error::params("\"wet\" should be a number between 0 and 1"))? as f32,
+            };
+            level.set(volume, muted);
+            level.wet.set(wet);
+            Value::object([("bus", name.into()), ("volume", f64::from(volume).into()), ("muted", muted.into()), ("wet", f64::from(wet).into())])
+        }
+        "scene" => {
+            let name = string("scene")?.ok_or_else(|| Error::params("\"scene\" is required"))?;
+            let save = match params.get("save") {
+                None | Some(Value::Null) => false,
+                Some(v) => v.as_bool().ok_or_else(|| Error::params("\"save\" should be a boolean"))?,
+            };
+            if save {
+                app.save_scene(name);
+            } else {
+                let idx = app.board.scenes.iter().position(|s| s.name == name).ok_or_else(|| Error::params(format!("no scene named {name:?}")))?;
+                app.recall_scene(idx);
+            }
+            Value::Null
+        }
+        "crossfade" => {
+            let Some((_, position)) = app.engine.crossfader() else { return Err(Error::failed("the board has no crossfader")) };
+            let position = match params.get("position") {
+                None | Some(Value::Null) => position,
+                Some(v) => v.as_f64().filter(|v| (0.0..=1.0).contains(v)).ok_or_else(|| Error::params("\"position\" should be a number between 0 and 1"))? as f32,
+            };
+            app.engine.crossfade(position);
+            Value::object([("position", f64::from(position).into())])
+        }
+        "record" => {
+            if app.engine.recorder().is_some() {
+                return Err(Error::failed("already recording"));
+            }
+            let path = string("path")?.map_or_else(|| app.recording_path(), PathBuf::from);
+            app.engine.start_recording(&path).map_err(|e| Error::failed(format!("{e:#}")))?;
+            Value::object([("path", path_value(&path))])
+        }
+        "stop_recording" => match app.engine.stop_recording() {
+            None => return Err(Error::failed("not recording")),
+            Some(Err(e)) => return Err(Error::failed(format!("{e:#}"))),
+            Some(Ok(recorded)) => Value::object([
+                ("path", path_value(&recorded.path)),
+                ("markers", Value::Array(recorded.markers.iter().map(|m| path_value(m)).collect())),
+                ("stems", Value::Array(recorded.stems.iter().map(|s| path_value(s)).collect())),
+            ]),
+        },
+        "status" => Value::object([
+            ("muted", app.engine.muted().into()),
+            ("recording", app.engine.recorder().map(|r| path_value(r.path())).unwrap_or(Value::Null)),
+            ("locked", app.locked.into()),
+            ("rehearsal", app.rehearsal.into()),
+            ("talkover", app.talkover.is_some().into()),
+            ("cue", Value::Number((app.cue_standby + 1) as f64)),
+            ("sounds", Value::Number(app.board.sounds.len() as f64)),
+        ]),
+        "show" => {
+            app.raise = app.shown.is_some();
+            Value::object([
+                ("tui", app.shown.is_some().into()),
+                ("tmux_pane", app.shown.as_ref().and_then(|s| s.tmux_pane.clone()).into()),
+            ])
+        }
+        "quit" => {
+            app.should_quit = true;
+            Value::Null
+        }
+        _ => return Err(Error { code: METHOD_NOT_FOUND, message: format!("no method named {method:?}") }),
+    })
+}
+
+fn path_value(path: &Path) -> Value {
+    path.display().to_string().into()
+}
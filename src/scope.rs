@@ -0,0 +1,301 @@
+//! The scope, ^W: a spectrum or the waveform of what the monitor plays, below the board. It is
+//! something to look at on stream, and shows at a glance whether audio is going out at all.
+//! Sounds feed it as they are played, but only while it is open.
+
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rodio::Source;
+use crate::audio::Gain;
+use crate::fft;
+
+/// How many samples the spectrum is taken over, a power of two.
+pub const WINDOW: usize = 2048;
+/// How many frames the scope keeps, enough for the spectrum with some to spare.
+const KEEP: usize = 2 * WINDOW;
+/// How many frames a sound sends at once.
+const CHUNK: usize = 256;
+/// Quieter than this is drawn as nothing.
+const FLOOR_DB: f32 = -60.0;
+/// The lowest and highest frequencies the spectrum shows.
+const LOWEST: f32 = 30.0;
+const HIGHEST: f32 = 16_000.0;
+
+/// How the scope shows what is playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Spectrum,
+    Waveform,
+}
+
+impl Mode {
+    /// What ^W goes to from `mode`: the spectrum, the waveform, then closed again.
+    pub fn next(mode: Option<Self>) -> Option<Self> {
+        match mode {
+            None => Some(Self::Spectrum),
+            Some(Self::Spectrum) => Some(Self::Waveform),
+            Some(Self::Waveform) => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Spectrum => "spectrum",
+            Self::Waveform => "waveform",
+        }
+    }
+}
+
+struct Ring {
+    rate: u32,
+    /// The frame `samples` starts at, counted at `rate` since the scope was made
+    first: u64,
+    /// Mono, with every sound mixed in
+    samples: VecDeque<f32>,
+}
+
+/// The mix of what the monitor played last.
+pub struct Scope {
+    start: Instant,
+    watching: AtomicBool,
+    ring: Mutex<Ring>,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self { start: Instant::now(), watching: AtomicBool::new(false), ring: Mutex::new(Ring { rate: 0, first: 0, samples: VecDeque::new() }) }
+    }
+}
+
+impl Scope {
+    /// Whether sounds should feed the scope, which is only worth it while it is shown.
+    pub fn watch(&self, watching: bool) {
+        self.watching.store(watching, Ordering::Relaxed);
+        if !watching {
+            self.ring.lock().unwrap().samples.clear();
+        }
+    }
+
+    fn frames_at(&self, t: Instant, rate: u32) -> u64 {
+        (t.saturating_duration_since(self.start).as_secs_f64() * rate as f64) as u64
+    }
+
+    /// The last `n` samples played, up to `now`, and the rate they were played at. Silence when
+    /// nothing played lately.
+    pub fn recent(&self, n: usize, now: Instant) -> (Vec<f32>, u32) {
+        let ring = self.ring.lock().unwrap();
+        let mut out = vec![0.0; n];
+        let end = ring.first + ring.samples.len() as u64;
+        // Sounds are pulled ahead of when they are heard, so the newest samples are about now
+        if ring.rate == 0 || end + (n as u64) < self.frames_at(now, ring.rate) {
+            return (out, ring.rate);
+        }
+        let have = ring.samples.len().min(n);
+        for (o, s) in out[n - have..].iter_mut().zip(ring.samples.range(ring.samples.len() - have..)) {
+            *o = *s;
+        }
+        (out, ring.rate)
+    }
+
+    /// A feed into the scope for a sound played at `rate`, heard at `gain`.
+    pub fn probe(self: &Arc<Self>, rate: u32, gain: Gain) -> Probe {
+        Probe { scope: self.clone(), rate, cursor: None, gain }
+    }
+}
+
+/// Where a sound's samples go into the scope, see [`Probed`].
+pub struct Probe {
+    scope: Arc<Scope>,
+    rate: u32,
+    /// Where the next sample goes, in frames since the scope was made
+    cursor: Option<u64>,
+    gain: Gain,
+}
+
+impl Probe {
+    /// Mixes mono `samples` in at the cursor, which catches up with the clock after the sound was
+    /// paused or the scope closed.
+    pub fn write(&mut self, samples: &[f32]) {
+        if !self.scope.watching.load(Ordering::Relaxed) {
+            self.cursor = None;
+            return;
+        }
+        let now = self.scope.frames_at(Instant::now(), self.rate);
+        let slack = self.rate as u64 / 4;
+        let cursor = match self.cursor {
+            Some(cursor) if now <= cursor + slack => cursor,
+            _ => now,
+        };
+
+        let gain = self.gain.get();
+        let mut ring = self.scope.ring.lock().unwrap();
+        // Another device's rate doesn't mix with what it played before, and long gone doesn't count
+        if ring.rate != self.rate || cursor > ring.first + (ring.samples.len() + KEEP) as u64 {
+            *ring = Ring { rate: self.rate, first: cursor, samples: VecDeque::new() };
+        }
+        for (i, &s) in samples.iter().enumerate() {
+            let Some(idx) = (cursor + i as u64).checked_sub(ring.first) else { continue };
+            let idx = idx as usize;
+            if ring.samples.len() <= idx {
+                ring.samples.resize(idx + 1, 0.0);
+            }
+            ring.samples[idx] += s * gain;
+        }
+        if let Some(over) = ring.samples.len().checked_sub(KEEP) {
+            ring.samples.drain(..over);
+            ring.first += over as u64;
+        }
+        self.cursor = Some(cursor + samples.len() as u64);
+    }
+}
+
+/// Passes a sound through unchanged, feeding it into the scope mixed down to mono.
+pub struct Probed<S> {
+    inner: S,
+    probe: Probe,
+    frame: (f32, u16),
+    chunk: Vec<f32>,
+}
+
+impl<S: Source<Item = f32>> Probed<S> {
+    pub fn new(inner: S, probe: Probe) -> Self {
+        Self { inner, probe, frame: (0.0, 0), chunk: Vec::with_capacity(CHUNK) }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Probed<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.inner.next();
+        match sample {
+            Some(s) => {
+                let channels = self.inner.channels().max(1);
+                self.frame = (self.frame.0 + s, self.frame.1 + 1);
+                if self.frame.1 >= channels {
+                    self.chunk.push(self.frame.0 / channels as f32);
+                    self.frame = (0.0, 0);
+                    if self.chunk.len() >= CHUNK {
+                        self.probe.write(&self.chunk);
+                        self.chunk.clear();
+                    }
+                }
+            }
+            None if !self.chunk.is_empty() => {
+                self.probe.write(&self.chunk);
+                self.chunk.clear();
+            }
+            None => {}
+        }
+        sample
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Probed<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+/// The loudest sample in `samples`, in dBFS, and `None` for silence.
+pub fn peak(samples: &[f32]) -> Option<f32> {
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    let db = 20.0 * peak.log10();
+    (db > FLOOR_DB).then_some(db)
+}
+
+/// How loud `samples`, played at `rate`, are in each of `bars` bands from low to high, spaced
+/// like we hear them. Between 0 for [`FLOOR_DB`] or quieter and 1 for full scale.
+pub fn spectrum(samples: &[f32], rate: u32, bars: usize) -> Vec<f32> {
+    let n = samples.len().next_power_of_two();
+    // A Hann window, so a tone between two bins doesn't smear over all of them
+    let window = |i: usize| 0.5 - 0.5 * (2.0 * PI * i as f32 / samples.len() as f32).cos();
+    let mut bins: Vec<(f32, f32)> = (0..n).map(|i| (samples.get(i).map_or(0.0, |s| s * window(i)), 0.0)).collect();
+    fft::forward(&mut bins);
+    // A full scale sine comes out at a quarter of the window's length, with the window halving it
+    let scale = 4.0 / samples.len().max(1) as f32;
+
+    let highest = HIGHEST.min(rate as f32 / 2.0);
+    let bin = |f: f32| ((f * n as f32 / rate.max(1) as f32) as usize).min(n / 2);
+    (0..bars)
+        .map(|b| {
+            let from = bin(LOWEST * (highest / LOWEST).powf(b as f32 / bars as f32));
+            let to = bin(LOWEST * (highest / LOWEST).powf((b + 1) as f32 / bars as f32)).max(from + 1);
+            let magnitude = bins[from..to.min(n)].iter().map(|(re, im)| (re * re + im * im).sqrt()).fold(0.0, f32::max) * scale;
+            ((20.0 * magnitude.log10() - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use std::sync::Arc;
+    use std::time::Instant;
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use crate::audio::Gain;
+    use crate::harness::{self, Harness};
+    use super::{peak, spectrum, Mode, Scope, WINDOW};
+
+    #[test]
+    fn a_tone_peaks_in_its_band() {
+        let rate = 48_000;
+        // Right on a bin of the transform, 44 of 2048
+        let tone: Vec<f32> = (0..WINDOW).map(|i| 0.5 * (2.0 * PI * 1031.25 * i as f32 / rate as f32).sin()).collect();
+        let bars = spectrum(&tone, rate, 32);
+        let loudest = (0..32).max_by(|&a, &b| bars[a].total_cmp(&bars[b])).unwrap();
+        // The band from about 1 kHz to 1.2 kHz
+        assert_eq!(loudest, 18);
+        // -6 dB
+        assert!((bars[loudest] - 0.9).abs() < 0.02, "{}", bars[loudest]);
+        assert!(bars[1] < 0.3 && bars[31] < 0.3);
+        assert!(spectrum(&[0.0; WINDOW], rate, 32).iter().all(|&b| b == 0.0));
+    }
+
+    #[test]
+    fn sounds_mix_in_the_scope_while_it_is_watched() {
+        let scope = Arc::new(Scope::default());
+        let mut a = scope.probe(48_000, Gain::new(0.5));
+        let mut b = scope.probe(48_000, Gain::default());
+        a.write(&[0.4; 100]);
+        assert!(scope.recent(10, Instant::now()).0.iter().all(|&s| s == 0.0));
+
+        scope.watch(true);
+        a.write(&[0.4; 100]);
+        b.write(&[0.1; 100]);
+        let (recent, rate) = scope.recent(200, Instant::now());
+        assert_eq!(rate, 48_000);
+        // Both started at about the same time, give or take a frame
+        assert!((recent.iter().sum::<f32>() - 30.0).abs() < 1e-3, "{recent:?}");
+        assert!(peak(&recent).is_some_and(|db| (db + 10.46).abs() < 0.01));
+        assert_eq!(peak(&[0.0; 10]), None);
+    }
+
+    #[test]
+    fn ctrl_w_goes_through_the_scope() {
+        let mut h = Harness::new(harness::board(2), 80, 24).loaded();
+        h.press_with(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(h.app.scope, Some(Mode::Spectrum));
+        let screen = h.screen();
+        assert!(screen.contains("Spectrum, no signal") && screen.contains("sound 0"), "{screen}");
+        h.press_with(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert!(h.screen().contains("Waveform"));
+        h.press_with(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(h.app.scope, None);
+        assert!(!h.screen().contains("Waveform"));
+    }
+}
@@ -0,0 +1,121 @@
+//! Finding sounds on the web to add to the board, from the [`Site`]s the search pane knows, so a
+//! board can be put together without leaving the terminal.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use crate::{freesound, myinstants};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Site {
+    Freesound,
+    MyInstants,
+}
+
+impl Site {
+    pub const ALL: [Site; 2] = [Site::Freesound, Site::MyInstants];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Site::Freesound => "freesound.org",
+            Site::MyInstants => "myinstants.com",
+        }
+    }
+
+    /// The next site, for switching with Tab.
+    pub fn next(self) -> Self {
+        Self::ALL[(Self::ALL.iter().position(|&s| s == self).unwrap_or(0) + 1) % Self::ALL.len()]
+    }
+}
+
+/// A sound found by a search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub site: Site,
+    /// Unique on the site
+    pub id: String,
+    pub name: String,
+    pub author: Option<String>,
+    /// In seconds
+    pub duration: Option<f64>,
+    pub license: Option<String>,
+    /// The MP3 to download
+    pub url: String,
+}
+
+impl Hit {
+    /// Where in `dir` the sound is kept once downloaded, so previewing again and adding don't
+    /// fetch it again.
+    pub fn cached(&self, dir: &Path) -> PathBuf {
+        let id: String = self.id.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-').collect();
+        dir.join(format!("{}-{id}.mp3", self.site.name()))
+    }
+
+    /// A file name in `dir` for the sound, named after it but not taking another file's place.
+    pub fn file_in(&self, dir: &Path) -> PathBuf {
+        let name = Path::new(&self.name);
+        // Names are often the uploaded file's name, with an extension that no longer applies
+        let stem = if crate::config::is_audio(name) { name.file_stem().unwrap_or_default().to_string_lossy() } else { self.name.as_str().into() };
+        let stem: String = stem.trim().chars().map(|c| if r#"/\:*?"<>|"#.contains(c) || c.is_control() { '_' } else { c }).collect();
+        let path = dir.join(format!("{stem}.mp3"));
+        if path.exists() {
+            dir.join(format!("{stem}-{}.mp3", self.id))
+        } else {
+            path
+        }
+    }
+
+    /// Who made the sound and under what license, which most licenses ask to mention.
+    pub fn credit(&self) -> Option<String> {
+        match (&self.author, &self.license) {
+            (Some(author), Some(license)) => Some(format!("by {author} under {license}")),
+            (Some(author), None) => Some(format!("by {author}")),
+            (None, _) => None,
+        }
+    }
+}
+
+/// The first page of a search's results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    /// How many sounds matched in total, when the site says
+    pub count: Option<u64>,
+    pub hits: Vec<Hit>,
+}
+
+/// Searches `site` in the background. `key` is the API key for sites that need one.
+pub fn run(site: Site, key: Option<String>, query: &str) -> Receiver<color_eyre::Result<Page>> {
+    let (tx, rx) = mpsc::channel();
+    let query = query.to_string();
+    thread::spawn(move || {
+        let page = match site {
+            Site::Freesound => freesound::search(&key.unwrap_or_default(), &query),
+            Site::MyInstants => myinstants::search(&query),
+        };
+        let _ = tx.send(page);
+    });
+    rx
+}
+
+/// What the search pane shows and is doing.
+#[derive(Debug)]
+pub struct Search {
+    pub site: Site,
+    pub query: String,
+    /// Typing goes into the query, otherwise keys move through the results
+    pub editing: bool,
+    pub results: Vec<Hit>,
+    pub selected: usize,
+    /// Set while a search runs
+    pub pending: Option<Receiver<color_eyre::Result<Page>>>,
+}
+
+impl Search {
+    pub fn new(site: Site) -> Self {
+        Self { site, query: String::new(), editing: true, results: Vec::new(), selected: 0, pending: None }
+    }
+
+    pub fn selected(&self) -> Option<&Hit> {
+        self.results.get(self.selected)
+    }
+}
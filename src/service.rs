@@ -0,0 +1,91 @@
+//! Running as a (systemd user) service: telling the service manager when the board is ready,
+//! and stopping cleanly when asked to. A user unit for it looks like:
+//!
+//! ```ini
+//! [Service]
+//! Type=notify
+//! ExecStart=%h/.cargo/bin/soundboard --no-tui -c %h/.config/soundboard.toml
+//! ```
+//!
+//! `soundboard show` opens it in a terminal when it is needed, see [`crate::show`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::app::App;
+
+/// How long sounds that are still playing take to fade out when the board is stopped.
+pub const EXIT_FADE: Duration = Duration::from_millis(500);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Set once the process is asked to stop, with SIGTERM (or SIGINT, when there is no terminal
+/// interface to take Ctrl+C as a key).
+#[derive(Debug, Clone, Default)]
+pub struct Terminate(Arc<AtomicBool>);
+
+impl Terminate {
+    pub fn install() -> color_eyre::Result<Self> {
+        let terminate = Self::default();
+        #[cfg(unix)]
+        for signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGINT] {
+            use color_eyre::eyre::Context;
+            signal_hook::flag::register(signal, terminate.0.clone()).wrap_err("listen for SIGTERM")?;
+        }
+        Ok(terminate)
+    }
+
+    pub fn requested(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Sends `state` (like `READY=1`) to the service manager, if there is one listening. See
+/// `sd_notify(3)`; this is the same protocol without linking to libsystemd.
+pub fn notify(state: &str) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::net::UnixDatagram;
+
+        let Some(path) = std::env::var_os("NOTIFY_SOCKET") else { return };
+        let Ok(socket) = UnixDatagram::unbound() else { return };
+        let path = std::path::PathBuf::from(path);
+        // A leading `@` is a socket in the abstract namespace
+        #[cfg(target_os = "linux")]
+        if let Some(name) = path.to_str().and_then(|p| p.strip_prefix('@')) {
+            use std::os::linux::net::SocketAddrExt;
+            if let Ok(addr) = std::os::unix::net::SocketAddr::from_abstract_name(name) {
+                let _ = socket.send_to_addr(state.as_bytes(), &addr);
+            }
+            return;
+        }
+        let _ = socket.send_to(state.as_bytes(), path);
+    }
+    #[cfg(not(unix))]
+    let _ = state;
+}
+
+/// Runs the board without any interface until it is stopped, reporting status messages on
+/// stderr (which ends up in the journal), for `--no-tui`.
+pub fn daemon(app: &mut App, terminate: &Terminate) {
+    let mut status = None;
+    while !app.should_quit && !terminate.requested() {
+        app.tick(Instant::now());
+        if app.status != status {
+            status.clone_from(&app.status);
+            if let Some(message) = &status {
+                eprintln!("{message}");
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Lets the service manager know the board is going away, and fades out whatever is playing if
+/// it was asked to stop rather than quit by the user.
+pub fn stop(app: &App, terminate: &Terminate) {
+    if terminate.requested() {
+        notify("STOPPING=1");
+        app.engine.fade_out_all(EXIT_FADE);
+    }
+}
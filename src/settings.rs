@@ -0,0 +1,355 @@
+//! The settings screen, ^S: the board's options on four tabs, changed in place and written to
+//! the config straight away, so the config only needs editing by hand for sounds' details and
+//! the rules that have no place here.
+//!
+//! A change is only kept when the config it makes loads again, so nothing set here ends up
+//! leaving the board unable to start.
+
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::config::{ArtNet, Board, CacheSettings, Endpoint, Idle, LongNames, Mdns, Mqtt, Talkover, TileLayout};
+use crate::locale::Locale;
+use crate::palette::Palette;
+use crate::resample::Resampler;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+    Audio,
+    Input,
+    Network,
+    Appearance,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 4] = [Tab::Audio, Tab::Input, Tab::Network, Tab::Appearance];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tab::Audio => "audio",
+            Tab::Input => "input",
+            Tab::Network => "network",
+            Tab::Appearance => "appearance",
+        }
+    }
+
+    pub fn settings(self) -> &'static [Setting] {
+        match self {
+            Tab::Audio => &[Setting::Monitor, Setting::Outputs, Setting::Resampler, Setting::Memory, Setting::Cache],
+            Tab::Input => &[Setting::Talkover, Setting::Threshold, Setting::Duck, Setting::Voice, Setting::Fifo],
+            Tab::Network => &[Setting::Metrics, Setting::Mdns, Setting::ArtNet, Setting::Mqtt, Setting::Freesound],
+            Tab::Appearance => &[Setting::Layout, Setting::LongNames, Setting::Fps, Setting::Locale, Setting::Palette, Setting::ScreenReader, Setting::Idle, Setting::IdleLoops],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    Monitor,
+    Outputs,
+    Resampler,
+    Memory,
+    Cache,
+    Talkover,
+    Threshold,
+    Duck,
+    Voice,
+    Fifo,
+    Metrics,
+    Mdns,
+    ArtNet,
+    Mqtt,
+    Freesound,
+    Layout,
+    LongNames,
+    Fps,
+    Locale,
+    Palette,
+    ScreenReader,
+    Idle,
+    IdleLoops,
+}
+
+/// How a setting is changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// On or off
+    Toggle,
+    /// One of a few values, stepped through
+    Choice,
+    /// Typed in
+    Text,
+}
+
+/// A setting's value, as shown.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    On,
+    Off,
+    /// Left to the default, or to the system
+    Unset,
+    Text(String),
+}
+
+impl Value {
+    /// The value in `t`'s words.
+    pub fn shown(&self, t: Locale) -> String {
+        match self {
+            Value::On => t.text("setting-on").to_string(),
+            Value::Off => t.text("setting-off").to_string(),
+            Value::Unset => t.text("setting-unset").to_string(),
+            Value::Text(text) => text.clone(),
+        }
+    }
+}
+
+impl Setting {
+    /// What the setting's messages are called, `setting-{id}`.
+    pub fn id(self) -> &'static str {
+        match self {
+            Setting::Monitor => "monitor",
+            Setting::Outputs => "outputs",
+            Setting::Resampler => "resampler",
+            Setting::Memory => "memory",
+            Setting::Cache => "cache",
+            Setting::Talkover => "talkover",
+            Setting::Threshold => "threshold",
+            Setting::Duck => "duck",
+            Setting::Voice => "voice",
+            Setting::Fifo => "fifo",
+            Setting::Metrics => "metrics",
+            Setting::Mdns => "mdns",
+            Setting::ArtNet => "artnet",
+            Setting::Mqtt => "mqtt",
+            Setting::Freesound => "freesound",
+            Setting::Layout => "layout",
+            Setting::LongNames => "long-names",
+            Setting::Fps => "fps",
+            Setting::Locale => "locale",
+            Setting::Palette => "palette",
+            Setting::ScreenReader => "screen-reader",
+            Setting::Idle => "idle",
+            Setting::IdleLoops => "idle-loops",
+        }
+    }
+
+    pub fn kind(self) -> Kind {
+        match self {
+            Setting::Cache | Setting::Talkover | Setting::Mdns | Setting::ScreenReader | Setting::IdleLoops => Kind::Toggle,
+            Setting::Resampler | Setting::Layout | Setting::LongNames | Setting::Locale | Setting::Palette => Kind::Choice,
+            _ => Kind::Text,
+        }
+    }
+
+    pub fn value(self, board: &Board) -> Value {
+        let text = |s: Option<String>| s.map_or(Value::Unset, Value::Text);
+        let toggle = |on: bool| if on { Value::On } else { Value::Off };
+        match self {
+            Setting::Monitor => text(board.outputs.monitor.clone()),
+            Setting::Outputs => text(Some(board.outputs.external.join(", ")).filter(|s| !s.is_empty())),
+            Setting::Resampler => Value::Text(board.resampler.name().to_string()),
+            Setting::Memory => text(board.memory.map(|mb| mb.to_string())),
+            Setting::Cache => toggle(board.cache.is_some()),
+            Setting::Talkover => toggle(board.talkover.is_some()),
+            Setting::Threshold => text(board.talkover.as_ref().map(|t| t.threshold.to_string())),
+            Setting::Duck => text(board.talkover.as_ref().map(|t| t.duck.to_string())),
+            Setting::Voice => text(board.voice.clone()),
+            Setting::Fifo => text(board.fifo.as_ref().map(|p| p.display().to_string())),
+            Setting::Metrics => text(board.metrics.as_ref().map(|m| m.listen.clone())),
+            Setting::Mdns => toggle(board.mdns.is_some()),
+            Setting::ArtNet => text(board.artnet.as_ref().map(|a| a.target.clone())),
+            Setting::Mqtt => text(board.mqtt.as_ref().map(|m| m.broker.clone())),
+            // Shown only as being there, as it is a secret
+            Setting::Freesound => text(board.freesound.as_ref().map(|_| "••••••••".to_string())),
+            Setting::Layout => Value::Text(board.layout.name().to_string()),
+            Setting::LongNames => Value::Text(board.long_names.name().to_string()),
+            Setting::Fps => text(board.fps.map(|fps| fps.to_string())),
+            Setting::Locale => text(board.locale.map(|l| l.name().to_string())),
+            Setting::Palette => text(board.palette.map(|p| p.name().to_string())),
+            Setting::ScreenReader => toggle(board.screen_reader),
+            Setting::Idle => text(board.idle.map(|idle| (idle.after.as_secs() / 60).to_string())),
+            Setting::IdleLoops => toggle(board.idle.is_some_and(|idle| idle.pause_loops)),
+        }
+    }
+
+    /// What editing the setting starts from.
+    pub fn text(self, board: &Board) -> String {
+        match (self, self.value(board)) {
+            (Setting::Freesound, _) => board.freesound.clone().unwrap_or_default(),
+            (_, Value::Text(text)) => text,
+            _ => String::new(),
+        }
+    }
+
+    /// Turns a toggle on or off, or steps a choice to the next value (or the one before).
+    pub fn cycle(self, board: &mut Board, back: bool) {
+        match self {
+            Setting::Cache => board.cache = if board.cache.is_some() { None } else { Some(CacheSettings { dir: None }) },
+            Setting::Talkover => board.talkover = if board.talkover.is_some() { None } else { Some(Talkover::default()) },
+            Setting::Mdns => board.mdns = if board.mdns.is_some() { None } else { Some(Mdns { name: None }) },
+            Setting::ScreenReader => board.screen_reader = !board.screen_reader,
+            // Pausing loops while idle needs the board to go idle
+            Setting::IdleLoops => {
+                let idle = board.idle.get_or_insert_with(Idle::default);
+                idle.pause_loops = !idle.pause_loops;
+            }
+            Setting::Resampler => board.resampler = step(&Resampler::ALL, board.resampler, back),
+            Setting::Layout => board.layout = step(&TileLayout::ALL, board.layout, back),
+            Setting::LongNames => board.long_names = step(&LongNames::ALL, board.long_names, back),
+            // Unset comes first, for going back to what the system asks for
+            Setting::Locale => board.locale = step(&[None, Some(Locale::English), Some(Locale::Dutch)], board.locale, back),
+            Setting::Palette => board.palette = step(&[None, Some(Palette::Default), Some(Palette::HighContrast), Some(Palette::Colorblind), Some(Palette::None)], board.palette, back),
+            _ => {}
+        }
+    }
+
+    /// Sets the setting from what was typed, where nothing unsets it.
+    pub fn set(self, board: &mut Board, text: &str) -> Result<(), String> {
+        let text = text.trim();
+        let value = Some(text.to_string()).filter(|s| !s.is_empty());
+        match self {
+            Setting::Monitor => board.outputs.monitor = value,
+            Setting::Outputs => board.outputs.external = text.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect(),
+            Setting::Memory => board.memory = number(text, "a number of megabytes")?,
+            Setting::Threshold | Setting::Duck => {
+                let Some(db) = number::<f32>(text, "a number of dB")? else {
+                    return Err("the talkover needs this, turn it off instead".to_string());
+                };
+                // Setting either turns the talkover on
+                let talkover = board.talkover.get_or_insert_with(Talkover::default);
+                if self == Setting::Threshold {
+                    talkover.threshold = db;
+                } else {
+                    talkover.duck = db;
+                }
+            }
+            Setting::Voice => board.voice = value,
+            Setting::Fifo => board.fifo = value.map(PathBuf::from),
+            Setting::Metrics => {
+                // The tokens have no place here, so they are kept as they were
+                let tokens = board.metrics.take().map(|m| m.tokens).unwrap_or_default();
+                board.metrics = value.map(|listen| Endpoint { listen, tokens });
+            }
+            Setting::ArtNet => {
+                let universe = board.artnet.as_ref().map_or(0, |a| a.universe);
+                board.artnet = value.map(|target| ArtNet { target, universe });
+            }
+            Setting::Mqtt => {
+                board.mqtt = match (value, board.mqtt.take()) {
+                    (Some(broker), Some(mqtt)) => Some(Mqtt { broker, ..mqtt }),
+                    (Some(broker), None) => Some(Mqtt { broker, topic: "soundboard".to_string(), username: None, password: None }),
+                    (None, _) => None,
+                };
+            }
+            Setting::Freesound => board.freesound = value,
+            Setting::Fps => board.fps = number(text, "a number of frames a second")?,
+            Setting::Idle => {
+                let pause_loops = board.idle.is_some_and(|idle| idle.pause_loops);
+                board.idle = number::<u64>(text, "a number of minutes")?.map(|minutes| Idle { after: Duration::from_secs(minutes.saturating_mul(60)), pause_loops });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// The settings screen's place: which tab, which setting on it, and what is being typed.
+#[derive(Debug)]
+pub struct Settings {
+    pub tab: Tab,
+    pub selected: usize,
+    /// The text being typed into the selected setting
+    pub editing: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { tab: Tab::Audio, selected: 0, editing: None }
+    }
+}
+
+impl Settings {
+    pub fn setting(&self) -> Setting {
+        let settings = self.tab.settings();
+        settings[self.selected.min(settings.len() - 1)]
+    }
+
+    /// Moves to the next tab, or the one before.
+    pub fn switch(&mut self, back: bool) {
+        self.tab = step(&Tab::ALL, self.tab, back);
+        self.selected = 0;
+        self.editing = None;
+    }
+}
+
+/// The value after `current` in `all`, or before it, coming round at the ends.
+pub fn step<T: Copy + PartialEq>(all: &[T], current: T, back: bool) -> T {
+    let at = all.iter().position(|&v| v == current).unwrap_or(0);
+    all[if back { (at + all.len() - 1) % all.len() } else { (at + 1) % all.len() }]
+}
+
+fn number<T: std::str::FromStr>(text: &str, expected: &str) -> Result<Option<T>, String> {
+    if text.is_empty() {
+        return Ok(None);
+    }
+    text.parse().map(Some).map_err(|_| format!("{text:?} isn't {expected}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use crate::app::View;
+    use crate::config::{Board, TileLayout};
+    use crate::harness::{self, Harness};
+    use super::{Setting, Value};
+
+    #[test]
+    fn settings_are_read_and_changed() {
+        let mut board = Board::builtin();
+        assert_eq!(Setting::Memory.value(&board), Value::Unset);
+        Setting::Memory.set(&mut board, " 512 ").unwrap();
+        assert_eq!(board.memory, Some(512));
+        assert!(Setting::Memory.set(&mut board, "lots").is_err());
+        assert_eq!(board.memory, Some(512));
+        Setting::Memory.set(&mut board, "").unwrap();
+        assert_eq!(board.memory, None);
+
+        Setting::Outputs.set(&mut board, "Stream, , Discord").unwrap();
+        assert_eq!(Setting::Outputs.value(&board), Value::Text("Stream, Discord".to_string()));
+
+        Setting::Palette.cycle(&mut board, false);
+        assert_eq!(Setting::Palette.value(&board), Value::Text("default".to_string()));
+        Setting::Palette.cycle(&mut board, true);
+        Setting::Palette.cycle(&mut board, true);
+        assert_eq!(Setting::Palette.value(&board), Value::Text("none".to_string()));
+
+        Setting::Duck.set(&mut board, "6").unwrap();
+        assert_eq!(Setting::Talkover.value(&board), Value::On);
+    }
+
+    #[test]
+    fn changes_are_written_to_the_config() {
+        let mut h = Harness::new(harness::board(3), 80, 24).loaded();
+        h.press_with(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert_eq!(h.app.view, View::Settings);
+        // Appearance is the last tab, and its first setting the layout
+        h.press_with(KeyCode::BackTab, KeyModifiers::SHIFT);
+        h.press(KeyCode::Enter);
+        assert_eq!(h.app.board.layout, TileLayout::Numpad);
+        assert!(h.screen().contains("numpad"));
+        assert_eq!(Board::load(&h.app.paths.config).unwrap().0.layout, TileLayout::Numpad);
+
+        // Out of range for the config, so not kept
+        h.press(KeyCode::Down);
+        h.press(KeyCode::Down);
+        h.press(KeyCode::Enter);
+        for c in "1000".chars() {
+            h.press(KeyCode::Char(c));
+        }
+        h.press(KeyCode::Enter);
+        assert_eq!(h.app.board.fps, None);
+        assert!(h.app.status.as_deref().is_some_and(|s| s.contains("between 1 and 240")), "{:?}", h.app.status);
+
+        h.press(KeyCode::Esc);
+        assert_eq!(h.app.view, View::Board);
+    }
+}
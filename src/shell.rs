@@ -0,0 +1,164 @@
+//! Tiles that run a shell command instead of playing a sound, `run = "..."` on a sound, which
+//! makes the board a deck for the rest of the stream too: switching scenes, starting a
+//! recording, anything there is a command for. What the commands print is kept in the log, ^U.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// How many lines of output the log keeps.
+pub const KEEP: usize = 500;
+
+/// A line a tile's command printed, or what became of the command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub tile: String,
+    pub text: String,
+    /// Printed to stderr, or a command that failed
+    pub error: bool,
+}
+
+enum Event {
+    Line(Line),
+    Exited { tile: String, command: String, failure: Option<String> },
+}
+
+/// Runs tiles' commands in the background, collecting what they print.
+pub struct Shell {
+    /// How many commands each tile has running, by its name
+    running: HashMap<String, usize>,
+    tx: Sender<Event>,
+    rx: Receiver<Event>,
+    /// What the commands printed, oldest first
+    pub log: VecDeque<Line>,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self { running: HashMap::new(), tx, rx, log: VecDeque::new() }
+    }
+}
+
+impl Shell {
+    /// Runs `command` for the tile called `tile`, with its name in `SOUNDBOARD_SOUND`.
+    pub fn run(&mut self, tile: &str, command: &str) {
+        let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+        let child = Command::new(shell)
+            .args([flag, command])
+            .env("SOUNDBOARD_SOUND", tile)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                let failure = Some(format!("couldn't start: {e}"));
+                let _ = self.tx.send(Event::Exited { tile: tile.to_string(), command: command.to_string(), failure });
+                return;
+            }
+        };
+        *self.running.entry(tile.to_string()).or_default() += 1;
+
+        let (tile, command, tx) = (tile.to_string(), command.to_string(), self.tx.clone());
+        let stderr = child.stderr.take().map(|stderr| {
+            let (tile, tx) = (tile.clone(), tx.clone());
+            thread::spawn(move || lines(stderr, &tile, true, &tx))
+        });
+        thread::spawn(move || {
+            if let Some(stdout) = child.stdout.take() {
+                lines(stdout, &tile, false, &tx);
+            }
+            if let Some(stderr) = stderr {
+                let _ = stderr.join();
+            }
+            let failure = match child.wait() {
+                Ok(status) if status.success() => None,
+                Ok(status) => Some(status.to_string()),
+                Err(e) => Some(e.to_string()),
+            };
+            let _ = tx.send(Event::Exited { tile, command, failure });
+        });
+    }
+
+    /// Whether a command of the tile called `tile` is still running.
+    pub fn running(&self, tile: &str) -> bool {
+        self.running.contains_key(tile)
+    }
+
+    /// Takes in what the commands printed, and the commands that are done, since it was last
+    /// called. Returns whether there was anything, and the last command that failed.
+    pub fn poll(&mut self) -> (bool, Option<String>) {
+        let (mut changed, mut failed) = (false, None);
+        for event in self.rx.try_iter() {
+            changed = true;
+            let line = match event {
+                Event::Line(line) => line,
+                Event::Exited { tile, command, failure } => {
+                    if let Some(count) = self.running.get_mut(&tile) {
+                        *count -= 1;
+                        if *count == 0 {
+                            self.running.remove(&tile);
+                        }
+                    }
+                    let Some(failure) = failure else { continue };
+                    failed = Some(format!("{tile}: {command:?} failed, {failure}"));
+                    Line { tile, text: failure, error: true }
+                }
+            };
+            if self.log.len() == KEEP {
+                self.log.pop_front();
+            }
+            self.log.push_back(line);
+        }
+        (changed, failed)
+    }
+}
+
+fn lines(from: impl Read, tile: &str, error: bool, tx: &Sender<Event>) {
+    for text in BufReader::new(from).lines().map_while(Result::ok) {
+        if tx.send(Event::Line(Line { tile: tile.to_string(), text, error })).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use crate::app::View;
+    use crate::config::Source;
+    use crate::harness::{self, Harness};
+    use super::Line;
+
+    #[test]
+    fn a_command_tile_logs_what_it_prints() {
+        let mut board = harness::board(2);
+        board.sounds[0].source = Source::Command("echo \"hello from $SOUNDBOARD_SOUND\"; echo oops >&2; exit 3".to_string());
+        let mut h = Harness::new(board, 80, 24).loaded();
+        assert!(!h.app.loaded.contains_key(&h.app.board.sounds[0].source));
+        h.press(KeyCode::Enter);
+        assert!(h.audio.take().is_empty());
+        assert_eq!(h.app.board.sounds[0].plays, 1);
+
+        let until = Instant::now() + Duration::from_secs(10);
+        while h.app.shell.running("sound 0") || h.app.shell.log.len() < 3 {
+            assert!(Instant::now() < until, "the command didn't finish");
+            std::thread::sleep(Duration::from_millis(5));
+            h.app.tick(Instant::now());
+        }
+        let log: Vec<&Line> = h.app.shell.log.iter().collect();
+        assert!(log.contains(&&Line { tile: "sound 0".to_string(), text: "hello from sound 0".to_string(), error: false }));
+        assert!(log.contains(&&Line { tile: "sound 0".to_string(), text: "oops".to_string(), error: true }));
+        assert!(log[2].error && log[2].text.contains('3'));
+        assert!(h.app.status.as_deref().is_some_and(|s| s.contains("failed")));
+
+        h.press_with(KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(h.app.view, View::Log);
+        assert!(h.screen().contains("hello from sound 0"));
+    }
+}
@@ -0,0 +1,119 @@
+//! `soundboard show`, for a hotkey of the desktop (like `bindsym $mod+F12 exec soundboard show`
+//! on sway) or of tmux (`bind b run-shell "soundboard show"`), so no terminal has to be kept
+//! open for the board:
+//!
+//! - a board open in a tmux pane is brought into view, or out of it again when it already is;
+//! - a board open in any other terminal rings its bell, which most window managers take as a
+//!   cue to flag or raise that window;
+//! - a board running without a terminal interface, like the service, hands over to one opened
+//!   in a new terminal (or tmux window), and so does a board that isn't running yet.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+use color_eyre::eyre::{bail, Context};
+use crate::instance;
+use crate::json::Value;
+
+/// Where the board is drawn, for `soundboard show` to find it.
+#[derive(Debug, Clone)]
+pub struct Shown {
+    /// Like `%3`, when it is drawn in a tmux pane
+    pub tmux_pane: Option<String>,
+}
+
+impl Shown {
+    /// Where the board that is starting now is drawn.
+    pub fn detect() -> Self {
+        Self { tmux_pane: std::env::var("TMUX_PANE").ok().filter(|_| std::env::var_os("TMUX").is_some()) }
+    }
+}
+
+/// Brings up the board with the config at `config`; `args` start one the way the command line
+/// asks for. Returns what it did.
+pub fn show(config: &Path, args: &[String]) -> color_eyre::Result<String> {
+    if instance::connect(config).is_none() {
+        open(args)?;
+        return Ok("opened the board".to_string());
+    }
+    let shown = instance::send(config, "show", Value::object::<&str>([]))?;
+    if shown.get("tui").and_then(Value::as_bool) != Some(true) {
+        let mut args = args.to_vec();
+        args.push("--take-over".to_string());
+        open(&args)?;
+        return Ok("opened the board, which takes over from the one running without a terminal".to_string());
+    }
+    match shown.get("tmux_pane").and_then(Value::as_str) {
+        Some(pane) => toggle(pane),
+        None => Ok("the board is already open in a terminal, which was asked to flag itself".to_string()),
+    }
+}
+
+/// Opens the board in a new tmux window when run from tmux, or else a new terminal: the one in
+/// `$TERMINAL`, or the system's default.
+fn open(args: &[String]) -> color_eyre::Result<()> {
+    let exe = std::env::current_exe().wrap_err("find the soundboard executable")?;
+    let mut command = if std::env::var_os("TMUX").is_some() {
+        let mut command = Command::new("tmux");
+        command.args(["new-window", "-n", "soundboard"]);
+        // Where this runs, so a config given as a relative path is found
+        if let Ok(dir) = std::env::current_dir() {
+            command.arg("-c").arg(dir);
+        }
+        command
+    } else {
+        let mut command = Command::new(std::env::var("TERMINAL").unwrap_or_else(|_| "x-terminal-emulator".to_string()));
+        command.arg("-e");
+        command
+    };
+    command.arg(exe).args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    let program = command.get_program().to_string_lossy().into_owned();
+    command.spawn().wrap_err_with(|| format!("open a terminal with {program}, set $TERMINAL to the one to use"))?;
+    Ok(())
+}
+
+/// Shows the tmux pane `pane`, or goes back to the window before it when it is showing already.
+fn toggle(pane: &str) -> color_eyre::Result<String> {
+    let showing = tmux(&["display-message", "-p", "-t", pane, "#{window_active} #{session_attached}"])?;
+    // Its window is the current one, of a session someone is looking at
+    if matches!(showing.split_whitespace().collect::<Vec<_>>()[..], ["1", attached] if attached != "0") {
+        tmux(&["last-window", "-t", pane])?;
+        return Ok("hid the board".to_string());
+    }
+    tmux(&["select-window", "-t", pane])?;
+    tmux(&["select-pane", "-t", pane])?;
+    // Only works for a client, from a hotkey of the desktop there may be none to switch
+    let _ = tmux(&["switch-client", "-t", pane]);
+    Ok("showed the board".to_string())
+}
+
+fn tmux(args: &[&str]) -> color_eyre::Result<String> {
+    let output = Command::new("tmux").args(args).stdin(Stdio::null()).output().wrap_err("run tmux")?;
+    if !output.status.success() {
+        bail!("tmux {}: {}", args[0], String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::harness::{self, Harness};
+    use crate::json::{self, Value};
+    use crate::rpc;
+    use super::Shown;
+
+    #[test]
+    fn the_board_says_where_it_is_shown() {
+        let mut h = Harness::new(harness::board(2), 80, 24).loaded();
+        let request = r#"{"jsonrpc": "2.0", "id": 1, "method": "show"}"#;
+        let response = rpc::handle(&mut h.app, request).unwrap();
+        assert_eq!(response.get("result").and_then(|r| r.get("tui")), Some(&Value::Boolean(false)));
+        assert!(!h.app.raise);
+
+        h.app.shown = Some(Shown { tmux_pane: Some("%3".to_string()) });
+        let response = json::parse(&rpc::handle(&mut h.app, request).unwrap().to_string()).unwrap();
+        let result = response.get("result").unwrap();
+        assert_eq!(result.get("tui"), Some(&Value::Boolean(true)));
+        assert_eq!(result.get("tmux_pane").and_then(Value::as_str), Some("%3"));
+        assert!(h.app.raise);
+    }
+}
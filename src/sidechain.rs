@@ -0,0 +1,161 @@
+//! Sidechaining, `ducked-by` on a `[[group]]`: the group's sounds, like music beds, are lowered
+//! while sounds of another group play, like stingers, so those punch through. How far they go
+//! down follows how loud the other group is, and they come back up over `release`.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rodio::Source;
+use crate::audio::Gain;
+
+/// How many samples are measured at once.
+const CHUNK: usize = 256;
+/// How long the loudest sample counts for, which is longer than a chunk so that the sounds
+/// playing at the same time all count.
+const WINDOW: Duration = Duration::from_millis(20);
+
+/// How loud a group that others are ducked by plays.
+#[derive(Debug)]
+pub struct Key {
+    /// The loudest sample lately, and when it was heard
+    level: Mutex<(f32, Instant)>,
+}
+
+impl Default for Key {
+    fn default() -> Self {
+        Self { level: Mutex::new((0.0, Instant::now())) }
+    }
+}
+
+impl Key {
+    fn hear(&self, peak: f32, now: Instant) {
+        let mut level = self.level.lock().unwrap();
+        let recent = now.saturating_duration_since(level.1) < WINDOW;
+        *level = (if recent { level.0.max(peak) } else { peak }, now);
+    }
+
+    /// The group's loudest sample lately, nothing when it stopped playing.
+    fn level(&self, now: Instant) -> f32 {
+        let (level, at) = *self.level.lock().unwrap();
+        if now.saturating_duration_since(at) < 2 * WINDOW { level } else { 0.0 }
+    }
+}
+
+/// Passes a sound through unchanged, measuring it for the groups ducked by its group at the
+/// gain it is heard at.
+pub struct Keyed<S> {
+    inner: S,
+    key: Arc<Key>,
+    gain: Gain,
+    peak: f32,
+    counted: usize,
+}
+
+impl<S: Source<Item = f32>> Keyed<S> {
+    pub fn new(inner: S, key: Arc<Key>, gain: Gain) -> Self {
+        Self { inner, key, gain, peak: 0.0, counted: 0 }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Keyed<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.inner.next();
+        if let Some(s) = sample {
+            self.peak = self.peak.max(s.abs());
+            self.counted += 1;
+        }
+        if self.counted >= CHUNK || (sample.is_none() && self.counted > 0) {
+            self.key.hear(self.peak * self.gain.get(), Instant::now());
+            (self.peak, self.counted) = (0.0, 0);
+        }
+        sample
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Keyed<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+/// How much a sound of a ducked group is lowered, as it plays.
+pub struct Follower {
+    key: Arc<Key>,
+    /// How many dB down it goes while the other group plays at full scale
+    duck: f32,
+    release: Duration,
+    /// From 0 for not at all to 1 for all the way
+    envelope: f32,
+    last: Instant,
+}
+
+impl Follower {
+    pub fn new(key: Arc<Key>, duck: f32, release: Duration) -> Self {
+        Self { key, duck, release, envelope: 0.0, last: Instant::now() }
+    }
+
+    /// The gain to play at, at `now`: down at once as the other group gets louder, and back up
+    /// gradually once it gets quieter.
+    pub fn gain(&mut self, now: Instant) -> f32 {
+        let elapsed = now.saturating_duration_since(self.last);
+        self.last = now;
+        let decay = if self.release.is_zero() { 0.0 } else { (-elapsed.as_secs_f32() / self.release.as_secs_f32()).exp() };
+        self.envelope = self.key.level(now).min(1.0).max(self.envelope * decay);
+        10f32.powf(-self.duck * self.envelope / 20.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use crate::config::{Board, Sidechain};
+    use crate::toml;
+    use super::{Follower, Key};
+
+    #[test]
+    fn the_bed_ducks_while_the_stinger_plays() {
+        let key = Arc::new(Key::default());
+        let mut bed = Follower::new(key.clone(), 12.0, Duration::from_millis(300));
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        assert_eq!(bed.gain(at(0)), 1.0);
+
+        key.hear(1.0, at(10));
+        assert!((bed.gain(at(15)) - 0.251).abs() < 0.001);
+        // A quieter stinger ducks less, in dB
+        let mut other = Follower::new(key.clone(), 12.0, Duration::from_millis(300));
+        key.hear(0.5, at(100));
+        assert!((other.gain(at(105)) - 0.501).abs() < 0.001);
+
+        // Once it is over the bed comes back up, not all at once
+        let back = bed.gain(at(300));
+        assert!(back > 0.251 && back < 0.9, "{back}");
+        assert!(bed.gain(at(3000)) > 0.999);
+    }
+
+    #[test]
+    fn sidechains_round_trip_through_the_config() {
+        let src = "version = 1\n[[group]]\nname = \"music\"\nfade = 1.0\nducked-by = \"sfx\"\nduck = 9.0\nrelease = 0.5\n";
+        let (board, _) = Board::parse(src).unwrap();
+        assert_eq!(board.groups[0].sidechain, Some(Sidechain { by: "sfx".to_string(), duck: 9.0, release: Duration::from_millis(500) }));
+        let (again, _) = Board::parse(&toml::to_string(&board.to_table())).unwrap();
+        assert_eq!(again.groups, board.groups);
+
+        assert!(Board::parse(&src.replace("\"sfx\"", "\"music\"")).unwrap_err().to_string().contains("by itself"));
+        assert!(Board::parse(&src.replace("ducked-by = \"sfx\"\n", "")).unwrap_err().to_string().contains("ducked-by"));
+    }
+}
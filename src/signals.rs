@@ -0,0 +1,71 @@
+//! Unix signals as triggers, so `kill -USR1 $(pidof soundboard)` is all a script needs.
+
+use std::sync::mpsc::Receiver;
+use crate::config::SignalRule;
+
+/// Listens for the signals of some rules, until dropped.
+pub struct Watcher {
+    #[cfg(unix)]
+    handle: signal_hook::iterator::Handle,
+    /// Indices of the rules whose signal arrived. Signals that arrive in quick succession can be
+    /// merged into one, like the OS does.
+    pub fired: Receiver<usize>,
+}
+
+#[cfg(unix)]
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.handle.close();
+    }
+}
+
+/// The signal's number, for the names [`crate::config::signal_name`] produces.
+#[cfg(unix)]
+fn number(name: &str) -> Option<libc::c_int> {
+    match name {
+        "USR1" => Some(libc::SIGUSR1),
+        "USR2" => Some(libc::SIGUSR2),
+        #[cfg(target_os = "linux")]
+        _ => {
+            let offset: libc::c_int = match name.strip_prefix("RTMIN") {
+                Some("") => 0,
+                Some(n) => n.strip_prefix('+')?.parse().ok()?,
+                None => return None,
+            };
+            let signal = libc::SIGRTMIN() + offset;
+            (signal <= libc::SIGRTMAX()).then_some(signal)
+        }
+        #[cfg(not(target_os = "linux"))]
+        _ => None,
+    }
+}
+
+#[cfg(unix)]
+pub fn watch(rules: &[SignalRule]) -> color_eyre::Result<Watcher> {
+    use color_eyre::eyre::{eyre, Context};
+    use signal_hook::iterator::Signals;
+
+    let numbers = rules
+        .iter()
+        .map(|r| number(&r.signal).ok_or_else(|| eyre!("{} is not available on this system", r.signal)))
+        .collect::<color_eyre::Result<Vec<_>>>()?;
+    let mut signals = Signals::new(&numbers).wrap_err("listen for signals")?;
+    let handle = signals.handle();
+
+    let (tx, fired) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            for (idx, _) in numbers.iter().enumerate().filter(|(_, n)| **n == signal) {
+                if tx.send(idx).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    Ok(Watcher { handle, fired })
+}
+
+#[cfg(not(unix))]
+pub fn watch(_rules: &[SignalRule]) -> color_eyre::Result<Watcher> {
+    color_eyre::eyre::bail!("signals are only available on Unix")
+}
@@ -0,0 +1,308 @@
+//! `soundboard --simulate <script>`: runs the board on a script of key presses, clicks and waits
+//! instead of a terminal, playing on the null audio backend with a clock that only moves when the script
+//! waits, and prints what happened. The same script and config give the same output every time,
+//! for reproducing a bug or checking the board from end to end.
+//!
+//! A script has a command per line, lines starting with `#` being comments:
+//!
+//! - `key <chord>` presses a key, like `key g`, `key esc` or `key ctrl+r`
+//! - `type <text>` presses the key for each character
+//! - `paste <text>` pastes text into the terminal, like a path to play
+//! - `click <column> <row>`, and `scroll up <column> <row>` or `scroll down …`
+//! - `resize <width>x<height>`, the terminal starting out at 100x30
+//! - `wait <duration>`, like `wait 200ms` or `wait 1.5s`
+//! - `frame` prints what the terminal shows, `state` what the board is doing
+//!
+//! Changes to the board are saved to a file of its own, never to the config.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use color_eyre::eyre::{bail, eyre, Context};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::Terminal;
+use crate::app::App;
+use crate::audio::{Engine, Null};
+use crate::binding::KeyChord;
+use crate::config::Board;
+use crate::input::{Caps, Input, Platform};
+use crate::loader::State;
+use crate::paths::Paths;
+use crate::tui::Events;
+use crate::ui;
+
+/// How long decoding sounds may take before the simulation gives up on them.
+const LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+enum Command {
+    Events(Vec<Event>),
+    Resize(u16, u16),
+    Wait(Duration),
+    Frame,
+    State,
+}
+
+fn parse(line: &str) -> color_eyre::Result<Option<Command>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let (word, rest) = line.split_once(' ').map_or((line, ""), |(word, rest)| (word, rest.trim()));
+    let press = |code, modifiers| Event::Key(KeyEvent { code, modifiers, kind: KeyEventKind::Press, state: KeyEventState::NONE });
+    let mouse = |kind, at: &str| -> color_eyre::Result<Event> {
+        let (column, row) = at.split_once(' ').ok_or_else(|| eyre!("expected a column and a row"))?;
+        let (column, row) = (column.trim().parse().wrap_err("column")?, row.trim().parse().wrap_err("row")?);
+        Ok(Event::Mouse(MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE }))
+    };
+    Ok(Some(match word {
+        // Escape can't be bound, so the config's names for keys don't have it
+        "key" if rest.eq_ignore_ascii_case("esc") => Command::Events(vec![press(KeyCode::Esc, KeyModifiers::NONE)]),
+        "key" => {
+            let chord = KeyChord::parse(rest).ok_or_else(|| eyre!("{rest:?} is not a key"))?;
+            Command::Events(vec![press(chord.code, chord.modifiers)])
+        }
+        "type" => Command::Events(rest.chars().map(|c| press(KeyCode::Char(c), KeyModifiers::NONE)).collect()),
+        "paste" => Command::Events(vec![Event::Paste(rest.to_string())]),
+        "click" => Command::Events(vec![mouse(MouseEventKind::Down(MouseButton::Left), rest)?, mouse(MouseEventKind::Up(MouseButton::Left), rest)?]),
+        "scroll" => match rest.split_once(' ') {
+            Some(("up", at)) => Command::Events(vec![mouse(MouseEventKind::ScrollUp, at)?]),
+            Some(("down", at)) => Command::Events(vec![mouse(MouseEventKind::ScrollDown, at)?]),
+            _ => bail!("expected `scroll up` or `scroll down`, with a column and a row"),
+        },
+        "resize" => {
+            let (width, height) = rest.split_once('x').ok_or_else(|| eyre!("expected a size like 80x24"))?;
+            Command::Resize(width.trim().parse().wrap_err("width")?, height.trim().parse().wrap_err("height")?)
+        }
+        "wait" => Command::Wait(duration(rest).ok_or_else(|| eyre!("{rest:?} is not a duration, like 200ms or 1.5s"))?),
+        "frame" => Command::Frame,
+        "state" => Command::State,
+        other => bail!("unknown command {other:?}"),
+    }))
+}
+
+fn duration(s: &str) -> Option<Duration> {
+    let (number, unit) = match s.strip_suffix("ms") {
+        Some(ms) => (ms, 0.001),
+        None => (s.strip_suffix('s')?, 1.0),
+    };
+    let secs = number.trim().parse::<f64>().ok()? * unit;
+    (secs >= 0.0 && secs.is_finite()).then(|| Duration::from_secs_f64(secs))
+}
+
+/// Events for the step they are handled in, one at a time.
+#[derive(Default)]
+struct Queue(VecDeque<Event>);
+
+impl Events for Queue {
+    fn next(&mut self, _timeout: Duration) -> io::Result<Option<Event>> {
+        Ok(self.0.pop_front())
+    }
+}
+
+/// What the terminal shows, a line per row without trailing spaces.
+pub fn screen(buffer: &Buffer) -> String {
+    let mut screen = String::new();
+    for y in 0..buffer.area.height {
+        let line: String = (0..buffer.area.width).map(|x| buffer.get(x, y).symbol()).collect();
+        screen.push_str(line.trim_end());
+        screen.push('\n');
+    }
+    screen
+}
+
+struct Simulation<'a> {
+    app: App,
+    terminal: Terminal<TestBackend>,
+    input: Input,
+    events: Queue,
+    audio: Arc<Null>,
+    now: Instant,
+    status: Option<String>,
+    out: &'a mut dyn Write,
+}
+
+impl Simulation<'_> {
+    fn step(&mut self) -> color_eyre::Result<()> {
+        crate::step(&mut self.terminal, &mut self.app, &mut self.input, &mut self.events, self.now)
+    }
+
+    /// Waits for sounds to be decoded, which is the one thing that takes real time, so the
+    /// board is in the same state every run.
+    fn settle(&mut self) -> color_eyre::Result<()> {
+        let until = Instant::now() + LOAD_TIMEOUT;
+        while self.app.loaded.values().any(|state| matches!(state, State::Loading)) {
+            if Instant::now() > until {
+                bail!("sounds took longer than {}s to load", LOAD_TIMEOUT.as_secs());
+            }
+            std::thread::sleep(Duration::from_millis(5));
+            self.app.tick(self.now);
+        }
+        self.step()
+    }
+
+    /// Prints what the board played and said since the last time.
+    fn report(&mut self) -> io::Result<()> {
+        let mut played = self.audio.take().into_iter().peekable();
+        while let Some(sound) = played.next() {
+            // A line per sound, with every output it played on
+            let mut outputs = vec![sound.output];
+            while let Some(output) = played.next_if(|p| p.id == sound.id) {
+                outputs.push(output.output);
+            }
+            let outputs: Vec<_> = outputs.iter().map(|o| o.device.as_deref().map_or("the default output".to_string(), |d| format!("{d:?}"))).collect();
+            writeln!(self.out, "played {:?} at {:.0}% on {}", sound.name, sound.volume * 100.0, outputs.join(", "))?;
+        }
+        for announcement in self.app.reader.take() {
+            writeln!(self.out, "announced {:?}{}", announcement.text, if announcement.alert { " with the bell" } else { "" })?;
+        }
+        if self.app.status != self.status {
+            self.status = self.app.status.clone();
+            writeln!(self.out, "status: {}", self.status.as_deref().unwrap_or("cleared"))?;
+        }
+        Ok(())
+    }
+
+    fn state(&mut self) -> io::Result<()> {
+        let app = &self.app;
+        writeln!(self.out, "view: {:?}", app.view)?;
+        match app.board.sounds.get(app.selected) {
+            Some(sound) => writeln!(self.out, "selected: {} {:?}", app.selected, sound.name)?,
+            None => writeln!(self.out, "selected: nothing")?,
+        }
+        let yes = |b: bool| if b { "yes" } else { "no" };
+        writeln!(self.out, "muted: {}, locked: {}, rehearsal: {}", yes(app.engine.muted()), yes(app.locked), yes(app.rehearsal))?;
+        writeln!(self.out, "sounds: {}, in the trash: {}", app.board.sounds.len(), app.board.trash.len())?;
+        let played: Vec<_> = app.board.sounds.iter().filter(|s| s.plays > 0).map(|s| format!("{:?} {}", s.name, s.plays)).collect();
+        writeln!(self.out, "plays: {}", if played.is_empty() { "none".to_string() } else { played.join(", ") })
+    }
+}
+
+/// Runs `script` on `board`, writing what happens to `out`.
+pub fn run(mut board: Board, script: &str, out: &mut dyn Write) -> color_eyre::Result<()> {
+    let commands = script
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| parse(line).map_err(|e| e.wrap_err(format!("line {}", i + 1))).transpose().map(|command| command.map(|c| (line.trim(), c))))
+        .collect::<color_eyre::Result<Vec<_>>>()?;
+
+    // Nobody outside the simulation hears about what it plays
+    for sound in &mut board.sounds {
+        sound.webhooks.clear();
+    }
+    // One of its own for each run, so runs at the same time don't see each other's plays
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let run = RUNS.fetch_add(1, Ordering::Relaxed);
+    let config = std::env::temp_dir().join(format!("soundboard-simulation-{}-{run}.toml", std::process::id()));
+    let caps = Caps { platform: Platform::Unix, key_release: false, kitty: false, mouse: true };
+    let audio = Arc::new(Null::default());
+    let mut sim = Simulation {
+        app: App::offline(board, Paths::beside(config.clone()), caps, Engine::new(audio.clone())),
+        terminal: Terminal::new(TestBackend::new(100, 30))?,
+        input: Input::new(caps),
+        events: Queue::default(),
+        audio,
+        now: Instant::now(),
+        status: None,
+        out,
+    };
+    let result = simulate(&mut sim, commands);
+    let _ = std::fs::remove_file(&config);
+    let _ = std::fs::remove_file(sim.app.paths.state.join(format!("{}-plays.json", sim.app.paths.stem())));
+    result
+}
+
+fn simulate(sim: &mut Simulation, commands: Vec<(&str, Command)>) -> color_eyre::Result<()> {
+    sim.settle()?;
+    sim.report()?;
+    for (line, command) in commands {
+        writeln!(sim.out, "> {line}")?;
+        match command {
+            Command::Events(events) => {
+                for event in events {
+                    sim.events.0.push_back(event);
+                    sim.step()?;
+                    sim.settle()?;
+                }
+            }
+            Command::Resize(width, height) => {
+                sim.terminal.backend_mut().resize(width, height);
+                sim.events.0.push_back(Event::Resize(width, height));
+                sim.step()?;
+                sim.settle()?;
+            }
+            Command::Wait(duration) => {
+                let until = sim.now + duration;
+                while sim.now < until {
+                    sim.now = (sim.now + ui::frame_interval(&sim.app)).min(until);
+                    sim.step()?;
+                }
+                sim.settle()?;
+            }
+            Command::Frame => write!(sim.out, "{}", screen(sim.terminal.backend().buffer()))?,
+            Command::State => sim.state()?,
+        }
+        sim.report()?;
+        if sim.app.should_quit {
+            writeln!(sim.out, "the board quit")?;
+            return Ok(());
+        }
+        if sim.app.edit_config {
+            sim.app.edit_config = false;
+            writeln!(sim.out, "the config would be opened in the editor, which a simulation doesn't do")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Board;
+    use crate::harness::assert_snapshot;
+
+    const SCRIPT: &str = "
+# a few sounds, the trash, and a resize that is caught mid-reflow
+key g
+type bp
+key Right
+key Enter
+scroll up 45 2
+key Delete
+key Tab
+frame
+key esc
+resize 60x16
+wait 150ms
+frame
+wait 1s
+state
+key ctrl+e
+key esc
+key g
+";
+
+    fn simulate(script: &str) -> String {
+        let mut out = Vec::new();
+        super::run(Board::builtin(), script, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn script() {
+        assert_snapshot("simulation", &simulate(SCRIPT));
+    }
+
+    #[test]
+    fn same_every_time() {
+        assert_eq!(simulate(SCRIPT), simulate(SCRIPT));
+    }
+
+    #[test]
+    fn mistakes_say_where_they_are() {
+        let err = super::run(Board::builtin(), "key g\n\nwait a while\n", &mut Vec::new()).unwrap_err();
+        assert_eq!(format!("{err:#}"), "line 3: \"a while\" is not a duration, like 200ms or 1.5s");
+    }
+}
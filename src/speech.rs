@@ -0,0 +1,151 @@
+//! Finding where the speech in a voice clip starts and ends, `t` while editing a sound, so the
+//! silence and room noise around a line can be trimmed off without listening for it. The start
+//! and end it finds are put in the edit as a proposal, to be saved or discarded like the rest.
+//!
+//! This is what Rabiner and Sambur called endpoint detection: the loud part of the clip is
+//! found from how much louder than its noise it gets, and is then widened to take in the quieter
+//! sounds that words start and end with, and the hiss of consonants like "s" and "f" that is
+//! quiet but crosses zero a lot.
+
+use std::time::Duration;
+use crate::loader::Decoded;
+
+/// How long a frame is, in seconds.
+const FRAME: f64 = 0.01;
+/// How far the start is widened to for consonants at most, in frames.
+const CONSONANTS: usize = 25;
+/// Kept in front of the speech and after it, in frames, so breathing in and the last of a word
+/// ringing out aren't cut off.
+const PAD_BEFORE: usize = 5;
+const PAD_AFTER: usize = 10;
+/// How much louder than the noise speech has to get, in dB, for there to be any.
+const MIN_RANGE: f32 = 15.0;
+
+/// Where the speech in a clip starts and ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeechBounds {
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// Where the speech in `decoded` is, `None` if nothing in it stands out from the noise.
+pub fn bounds(decoded: &Decoded) -> Option<SpeechBounds> {
+    let frames = frames(decoded);
+    if frames.is_empty() {
+        return None;
+    }
+    let mut levels: Vec<f32> = frames.iter().map(|f| f.0).collect();
+    levels.sort_by(f32::total_cmp);
+    // The quietest tenth of the clip is taken to be its noise
+    let (floor, peak) = (levels[levels.len() / 10], levels[levels.len() - 1]);
+    if peak - floor < MIN_RANGE {
+        return None;
+    }
+    // Loud enough to surely be speech, and loud enough to be where it starts
+    let upper = floor + (peak - floor) / 2.0;
+    let lower = floor + (MIN_RANGE / 2.0).min((peak - floor) / 4.0);
+
+    let first = frames.iter().position(|f| f.0 >= upper)?;
+    let last = frames.iter().rposition(|f| f.0 >= upper)?;
+    let mut start = (0..first).rev().take_while(|&i| frames[i].0 >= lower).last().unwrap_or(first);
+    let mut end = (last + 1..frames.len()).take_while(|&i| frames[i].0 >= lower).last().unwrap_or(last);
+
+    // Consonants cross zero more often than the noise does, the noisiest of it aside
+    let mut crossings: Vec<f32> = frames.iter().map(|f| f.1).collect();
+    crossings.sort_by(f32::total_cmp);
+    let hiss = crossings[crossings.len() * 9 / 10].max(0.1) * 1.5;
+    let consonant = |i: usize| frames[i].1 >= hiss && frames[i].0 > floor + 3.0;
+    start = (start.saturating_sub(CONSONANTS)..start).rev().take_while(|&i| consonant(i)).last().unwrap_or(start);
+    end = (end + 1..(end + 1 + CONSONANTS).min(frames.len())).take_while(|&i| consonant(i)).last().unwrap_or(end);
+
+    let start = start.saturating_sub(PAD_BEFORE);
+    let end = (end + 1 + PAD_AFTER).min(frames.len());
+    let at = |frame: usize| Duration::from_secs_f64(frame as f64 * FRAME).min(decoded.duration());
+    Some(SpeechBounds { start: at(start), end: at(end) })
+}
+
+/// Each frame's loudness in dB and how many of its samples cross zero, of the channels mixed
+/// down.
+fn frames(decoded: &Decoded) -> Vec<(f32, f32)> {
+    let channels = decoded.channels.max(1) as usize;
+    let mono: Vec<f32> = decoded.samples.chunks(channels).map(|frame| frame.iter().sum::<f32>() / channels as f32).collect();
+    let len = ((decoded.rate as f64 * FRAME) as usize).max(1);
+    mono.chunks(len)
+        .map(|frame| {
+            let rms = (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+            let crossings = frame.windows(2).filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0)).count();
+            (20.0 * rms.max(1e-6).log10(), crossings as f32 / frame.len() as f32)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use std::time::Duration;
+    use crossterm::event::KeyCode;
+    use hound::{SampleFormat, WavSpec, WavWriter};
+    use crate::app::View;
+    use crate::config::Source;
+    use crate::harness::{self, Harness};
+    use crate::loader::Decoded;
+    use super::{bounds, SpeechBounds};
+
+    /// Quiet noise, with a vowel-like tone from `from` to `to` seconds and a softer hiss just
+    /// before it, at 16 kHz.
+    fn clip(from: f32, to: f32, seconds: f32) -> Decoded {
+        let mut seed = 1u32;
+        let mut noise = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+        };
+        let samples = (0..(seconds * 16_000.0) as usize)
+            .map(|i| {
+                let t = i as f32 / 16_000.0;
+                let voice = if (from..to).contains(&t) { 0.5 * (2.0 * PI * 220.0 * t).sin() } else { 0.0 };
+                let hiss = if (from - 0.1..from).contains(&t) { 0.3 } else { 0.002 };
+                voice + hiss * noise()
+            })
+            .collect();
+        Decoded { channels: 1, rate: 16_000, samples }
+    }
+
+    #[test]
+    fn speech_is_found_in_the_noise() {
+        let SpeechBounds { start, end } = bounds(&clip(1.0, 2.5, 4.0)).unwrap();
+        // The hiss in front counts, and a little room around it is kept
+        assert!(start > Duration::from_millis(800) && start < Duration::from_millis(900), "{start:?}");
+        assert!(end > Duration::from_millis(2500) && end < Duration::from_millis(2700), "{end:?}");
+
+        // Only noise, or nothing at all
+        assert_eq!(bounds(&clip(5.0, 5.0, 1.0)), None);
+        assert_eq!(bounds(&Decoded { channels: 2, rate: 16_000, samples: Vec::new().into() }), None);
+    }
+
+    #[test]
+    fn trimming_is_proposed_in_the_edit() {
+        let dir = harness::scratch("speech");
+        let path = dir.join("take.wav");
+        let spec = WavSpec { channels: 1, sample_rate: 16_000, bits_per_sample: 16, sample_format: SampleFormat::Int };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for &s in clip(0.5, 1.5, 2.0).samples.iter() {
+            writer.write_sample((s * i16::MAX as f32) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut board = harness::board(1);
+        board.sounds[0].source = Source::File(path.clone());
+        let mut h = Harness::new(board, 80, 30).loaded();
+        h.press(KeyCode::Char('E'));
+        assert_eq!(h.app.view, View::Edit);
+        h.press(KeyCode::Char('t'));
+        let shape = h.app.edit.as_ref().unwrap().draft.shape;
+        assert!(shape.start > Duration::from_millis(300) && shape.start < Duration::from_millis(400), "{:?}", shape.start);
+        assert!(shape.end.is_some_and(|end| end > Duration::from_millis(1500) && end < Duration::from_millis(1700)), "{:?}", shape.end);
+        assert!(h.app.status.as_deref().is_some_and(|s| s.contains("s saves")), "{:?}", h.app.status);
+        // Only proposed, until it is saved
+        assert_eq!(h.app.board.sounds[0].shape.end, None);
+    }
+}
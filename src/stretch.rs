@@ -0,0 +1,248 @@
+//! Time stretching, `stretch` on a sound: playing it slower or faster while it keeps its pitch,
+//! unlike `speed`. A phase vocoder takes the sound apart into overlapping windows of its spectrum
+//! and puts them back together further apart, or closer together, with their phases carried
+//! along so the tones in it keep going smoothly.
+
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::time::Duration;
+use rodio::Source;
+use crate::fft;
+
+/// How many samples a window of the spectrum is taken over, a power of two: long enough for
+/// low tones, short enough that drums don't smear too much.
+const WINDOW: usize = 2048;
+/// How far apart the windows are put back together.
+const HOP: usize = WINDOW / 4;
+/// How loud overlapping Hann windows at a quarter of their length add up to, twice over.
+const OVERLAP_GAIN: f32 = 1.5;
+
+/// Plays `inner` at `tempo` times its speed, with the same pitch.
+pub struct Stretch<S> {
+    inner: S,
+    tempo: f32,
+    channels: usize,
+    window: Vec<f32>,
+    /// What was read of each channel and is still needed
+    input: Vec<VecDeque<f32>>,
+    /// Where the next window starts, in samples from the start of `input`
+    position: f64,
+    /// Where the window before started, from the start of `input`, once there was one
+    last: Option<usize>,
+    /// Each channel's phases of each bin, in the window before and as played
+    analysed: Vec<Vec<f32>>,
+    synthesised: Vec<Vec<f32>>,
+    /// Each channel's windows being added up, the first [`HOP`] of which are done
+    overlap: Vec<Vec<f32>>,
+    /// Done and interleaved, to be played
+    output: VecDeque<f32>,
+    /// Everything has been read
+    ended: bool,
+    /// Windows to go once everything was read, for the tail of the last ones
+    tail: usize,
+    /// Samples to leave out at the start, which the silence in front of the sound turned into
+    skip: usize,
+}
+
+impl<S: Source<Item = f32>> Stretch<S> {
+    pub fn new(inner: S, tempo: f32) -> Self {
+        let channels = inner.channels().max(1) as usize;
+        let window = (0..WINDOW).map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / WINDOW as f32).cos()).collect();
+        let bins = WINDOW / 2 + 1;
+        Self {
+            inner,
+            tempo: tempo.max(f32::EPSILON),
+            channels,
+            window,
+            // Silence in front, so the first windows have the start of the sound in their middle
+            // and it is as loud as the rest
+            input: vec![VecDeque::from(vec![0.0; WINDOW - HOP]); channels],
+            position: 0.0,
+            last: None,
+            analysed: vec![vec![0.0; bins]; channels],
+            synthesised: vec![vec![0.0; bins]; channels],
+            overlap: vec![vec![0.0; WINDOW]; channels],
+            output: VecDeque::new(),
+            ended: false,
+            tail: WINDOW / HOP - 1,
+            skip: ((WINDOW - HOP) as f32 / tempo.max(f32::EPSILON)) as usize * channels,
+        }
+    }
+
+    /// Reads until the next window is all there, or there is nothing more to read.
+    fn fill(&mut self) {
+        let needed = self.position as usize + WINDOW;
+        while !self.ended && self.input[self.channels - 1].len() < needed {
+            for channel in 0..self.channels {
+                match self.inner.next() {
+                    Some(s) => self.input[channel].push_back(s),
+                    None => {
+                        // Half a frame is left out
+                        for input in &mut self.input[..channel] {
+                            input.pop_back();
+                        }
+                        self.ended = true;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Puts the next window in its place, making [`HOP`] more frames to play. Returns whether
+    /// there was anything left to do.
+    fn step(&mut self) -> bool {
+        self.fill();
+        let start = self.position.round() as usize;
+        let left = self.input[0].len();
+        if self.ended && start >= left {
+            if self.tail == 0 {
+                return false;
+            }
+            self.tail -= 1;
+        }
+        // How far the window moved since the last, over which each bin's phase went round
+        let moved = self.last.map(|last| start.saturating_sub(last));
+
+        for channel in 0..self.channels {
+            let input = &self.input[channel];
+            let mut bins: Vec<(f32, f32)> = (0..WINDOW).map(|i| (input.get(start + i).copied().unwrap_or(0.0) * self.window[i], 0.0)).collect();
+            fft::forward(&mut bins);
+            let half = WINDOW / 2 + 1;
+            let magnitudes: Vec<f32> = bins[..half].iter().map(|(re, im)| (re * re + im * im).sqrt()).collect();
+            let phases: Vec<f32> = bins[..half].iter().map(|(re, im)| im.atan2(*re)).collect();
+
+            // Each tone's phase goes on from where it was played last, at the frequency it is
+            // really at. The bins around its peak are part of the same tone and keep their place
+            // relative to the peak, or the tone would smear and lose some of its loudness.
+            let peaks: Vec<usize> = (1..half - 1).filter(|&k| magnitudes[k] > magnitudes[k - 1] && magnitudes[k] >= magnitudes[k + 1]).collect();
+            let mut played = phases.clone();
+            if let Some(moved) = moved.filter(|&moved| moved > 0) {
+                for &k in &peaks {
+                    let omega = 2.0 * PI * k as f32 / WINDOW as f32;
+                    let off = wrap(phases[k] - self.analysed[channel][k] - omega * moved as f32);
+                    played[k] = self.synthesised[channel][k] + (omega + off / moved as f32) * HOP as f32;
+                }
+                let mut peak = 0;
+                for k in 0..half {
+                    // The nearest peak, halfway between two being the next one's
+                    while peak + 1 < peaks.len() && peaks[peak + 1].abs_diff(k) <= k.abs_diff(peaks[peak]) {
+                        peak += 1;
+                    }
+                    if let Some(&p) = peaks.get(peak).filter(|&&p| p != k) {
+                        played[k] = phases[k] + played[p] - phases[p];
+                    }
+                }
+            }
+
+            for k in 0..half {
+                self.analysed[channel][k] = phases[k];
+                self.synthesised[channel][k] = wrap(played[k]);
+                bins[k] = (magnitudes[k] * played[k].cos(), magnitudes[k] * played[k].sin());
+                // The negative frequencies mirror the positive ones, for a real sound
+                if k > 0 && k < WINDOW / 2 {
+                    bins[WINDOW - k] = (bins[k].0, -bins[k].1);
+                }
+            }
+            fft::inverse(&mut bins);
+            let overlap = &mut self.overlap[channel];
+            for (i, (re, _)) in bins.into_iter().enumerate() {
+                overlap[i] += re * self.window[i] / OVERLAP_GAIN;
+            }
+        }
+
+        for i in 0..HOP {
+            for channel in 0..self.channels {
+                self.output.push_back(self.overlap[channel][i]);
+            }
+        }
+        for overlap in &mut self.overlap {
+            overlap.drain(..HOP);
+            overlap.resize(WINDOW, 0.0);
+        }
+
+        let dropped = self.skip.min(self.output.len());
+        self.output.drain(..dropped);
+        self.skip -= dropped;
+
+        // What the windows after this one no longer need can go
+        self.position += HOP as f64 * self.tempo as f64;
+        let done = (self.position as usize).min(start);
+        for input in &mut self.input {
+            input.drain(..done.min(input.len()));
+        }
+        self.position -= done as f64;
+        self.last = Some(start - done);
+        true
+    }
+}
+
+/// `phase` brought to between -π and π.
+fn wrap(phase: f32) -> f32 {
+    phase - 2.0 * PI * (phase / (2.0 * PI)).round()
+}
+
+impl<S: Source<Item = f32>> Iterator for Stretch<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        while self.output.is_empty() {
+            if !self.step() {
+                return None;
+            }
+        }
+        self.output.pop_front()
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Stretch<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration().map(|d| d.div_f32(self.tempo))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use rodio::buffer::SamplesBuffer;
+    use crate::scope;
+    use super::Stretch;
+
+    /// The loudest band of the scope's spectrum, as a way to tell the pitch.
+    fn pitch(samples: &[f32]) -> usize {
+        let bars = scope::spectrum(&samples[samples.len() / 2..][..scope::WINDOW], 48_000, 64);
+        (0..bars.len()).max_by(|&a, &b| bars[a].total_cmp(&bars[b])).unwrap()
+    }
+
+    #[test]
+    fn stretching_keeps_the_pitch() {
+        let tone: Vec<f32> = (0..48_000).map(|i| 0.5 * (2.0 * PI * 440.0 * i as f32 / 48_000.0).sin()).collect();
+        for tempo in [0.5, 1.5] {
+            let stretched: Vec<f32> = Stretch::new(SamplesBuffer::new(1, 48_000, tone.clone()), tempo).collect();
+            let expected = tone.len() as f32 / tempo;
+            assert!((stretched.len() as f32 - expected).abs() < 4096.0, "{tempo}: {} samples", stretched.len());
+            assert_eq!(pitch(&stretched), pitch(&tone), "{tempo}");
+            // As loud as it was, give or take
+            let peak = stretched[stretched.len() / 4..stretched.len() * 3 / 4].iter().fold(0.0f32, |p, s| p.max(s.abs()));
+            assert!((peak - 0.5).abs() < 0.05, "{tempo}: {peak}");
+        }
+
+        // Both channels, in step
+        let stereo: Vec<f32> = tone.iter().flat_map(|&s| [s, -s]).collect();
+        let stretched: Vec<f32> = Stretch::new(SamplesBuffer::new(2, 48_000, stereo), 0.75).collect();
+        assert_eq!(stretched.len() % 2, 0);
+        assert!(stretched.chunks(2).all(|f| (f[0] + f[1]).abs() < 1e-3));
+    }
+}
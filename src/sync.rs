@@ -0,0 +1,569 @@
+//! Keeping a board the same on several machines, `soundboard sync`: its config and the files in
+//! its folder are pushed to the remote in `[sync]` and pulled from it. The remote is an rclone
+//! remote like `drive:soundboard`, which rclone is run for, a WebDAV folder like
+//! `https://cloud.example.com/remote.php/dav/files/me/soundboard`, which curl talks to, or a
+//! directory, like one that a sync client already keeps.
+//!
+//! Next to the files, the remote has `soundboard-sync.json` listing the SHA-256 of each, and the
+//! board keeps what they were at its last sync in its state directory. That tells a file changed
+//! here from one changed on the other machine; one changed on both since is a conflict, which is
+//! left as it is on either side until `push --force` or `pull --force` picks one. The config is
+//! the exception, as that is where people working on the same board both make their changes:
+//! one changed on both is merged, see [`crate::merge`], with the manifest's `changed` telling
+//! which side changed it last. With `every` in `[sync]` the board syncs while it runs as well.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::UNIX_EPOCH;
+use color_eyre::eyre::{bail, eyre, Context};
+use crate::backup;
+use crate::config::{Board, Source, SyncSettings};
+use crate::fetch;
+use crate::json::{self, Value};
+use crate::merge;
+use crate::paths::Paths;
+use crate::sha256;
+
+/// What the list of files on the remote is called.
+const MANIFEST: &str = "soundboard-sync.json";
+
+/// Files by their path from the board's folder, with forward slashes, and their SHA-256.
+pub type Manifest = BTreeMap<String, String>;
+
+/// When files were last pushed as they are on the remote, in seconds since the Unix epoch, by
+/// their path like in a [`Manifest`].
+type Changed = BTreeMap<String, u64>;
+
+/// Where a board is synced to, with paths from the board's folder there.
+pub trait Remote {
+    /// The file at `path`, `None` when there is none.
+    fn get(&self, path: &str) -> color_eyre::Result<Option<Vec<u8>>>;
+    fn put(&self, path: &str, data: &[u8]) -> color_eyre::Result<()>;
+    /// Removes the file at `path`, which is fine when it isn't there.
+    fn delete(&self, path: &str) -> color_eyre::Result<()>;
+}
+
+/// The remote that `settings` point at.
+pub fn remote(settings: &SyncSettings) -> Box<dyn Remote> {
+    let remote = settings.remote.trim_end_matches('/');
+    if remote.starts_with("http://") || remote.starts_with("https://") {
+        let login = settings.username.as_ref().map(|user| format!("{user}:{}", settings.password.as_deref().unwrap_or_default()));
+        Box::new(WebDav { url: remote.to_string(), login })
+    } else if is_rclone(remote) {
+        Box::new(Rclone(remote.to_string()))
+    } else {
+        Box::new(Folder(PathBuf::from(remote.strip_prefix("file://").unwrap_or(remote))))
+    }
+}
+
+/// Whether `remote` is written the way rclone names them, `name:path`, and not a directory,
+/// which on Windows starts with a drive letter.
+fn is_rclone(remote: &str) -> bool {
+    let Some((name, _)) = remote.split_once(':') else { return false };
+    let drive = cfg!(windows) && name.len() == 1;
+    !name.is_empty() && !name.contains(['/', '\\']) && !drive
+}
+
+/// A directory, which may be one kept in sync by something else.
+struct Folder(PathBuf);
+
+impl Remote for Folder {
+    fn get(&self, path: &str) -> color_eyre::Result<Option<Vec<u8>>> {
+        let file = self.0.join(path);
+        match fs::read(&file) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).wrap_err_with(|| format!("read {}", file.display())),
+        }
+    }
+
+    fn put(&self, path: &str, data: &[u8]) -> color_eyre::Result<()> {
+        let file = self.0.join(path);
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir).wrap_err_with(|| format!("create {}", dir.display()))?;
+        }
+        fs::write(&file, data).wrap_err_with(|| format!("write {}", file.display()))
+    }
+
+    fn delete(&self, path: &str) -> color_eyre::Result<()> {
+        let file = self.0.join(path);
+        match fs::remove_file(&file) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e).wrap_err_with(|| format!("delete {}", file.display())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An rclone remote, which rclone has to be set up for.
+struct Rclone(String);
+
+impl Rclone {
+    fn at(&self, path: &str) -> String {
+        if self.0.ends_with(':') { format!("{}{path}", self.0) } else { format!("{}/{path}", self.0) }
+    }
+
+    fn run(&self, command: &str, path: &str, input: Option<&[u8]>) -> color_eyre::Result<Output> {
+        let mut child = Command::new("rclone")
+            .arg(command)
+            .arg(self.at(path))
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .wrap_err("run rclone")?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input).wrap_err("send to rclone")?;
+        }
+        child.wait_with_output().wrap_err("run rclone")
+    }
+}
+
+/// Whether rclone failed because there was nothing there, which it exits with 3 or 4 for.
+fn not_found(output: &Output) -> bool {
+    matches!(output.status.code(), Some(3 | 4)) || String::from_utf8_lossy(&output.stderr).contains("not found")
+}
+
+impl Remote for Rclone {
+    fn get(&self, path: &str) -> color_eyre::Result<Option<Vec<u8>>> {
+        let output = self.run("cat", path, None)?;
+        match output.status.success() {
+            true => Ok(Some(output.stdout)),
+            false if not_found(&output) => Ok(None),
+            false => bail!("rclone couldn't read {}: {}", self.at(path), String::from_utf8_lossy(&output.stderr).trim()),
+        }
+    }
+
+    fn put(&self, path: &str, data: &[u8]) -> color_eyre::Result<()> {
+        let output = self.run("rcat", path, Some(data))?;
+        if !output.status.success() {
+            bail!("rclone couldn't write {}: {}", self.at(path), String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+
+    fn delete(&self, path: &str) -> color_eyre::Result<()> {
+        let output = self.run("deletefile", path, None)?;
+        if !output.status.success() && !not_found(&output) {
+            bail!("rclone couldn't delete {}: {}", self.at(path), String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+}
+
+/// A WebDAV folder, like Nextcloud's.
+struct WebDav {
+    url: String,
+    /// `user:password`
+    login: Option<String>,
+}
+
+impl WebDav {
+    fn request(&self, method: &str, path: &str, body: Option<&[u8]>) -> color_eyre::Result<(u16, Vec<u8>)> {
+        let path: Vec<String> = path.split('/').map(fetch::encode).collect();
+        fetch::request(method, &format!("{}/{}", self.url, path.join("/")), self.login.as_deref(), body)
+    }
+}
+
+impl Remote for WebDav {
+    fn get(&self, path: &str) -> color_eyre::Result<Option<Vec<u8>>> {
+        match self.request("GET", path, None)? {
+            (200, body) => Ok(Some(body)),
+            (404, _) => Ok(None),
+            (code, _) => bail!("{}/{path} answered with {code}", self.url),
+        }
+    }
+
+    fn put(&self, path: &str, data: &[u8]) -> color_eyre::Result<()> {
+        // The folders it goes in have to be made first; those that are there say 405
+        let parts: Vec<&str> = path.split('/').collect();
+        for depth in 1..parts.len() {
+            self.request("MKCOL", &parts[..depth].join("/"), None)?;
+        }
+        match self.request("PUT", path, Some(data))? {
+            (200..=299, _) => Ok(()),
+            (code, _) => bail!("{}/{path} answered with {code}", self.url),
+        }
+    }
+
+    fn delete(&self, path: &str) -> color_eyre::Result<()> {
+        match self.request("DELETE", path, None)? {
+            (200..=299 | 404, _) => Ok(()),
+            (code, _) => bail!("{}/{path} answered with {code}", self.url),
+        }
+    }
+}
+
+/// Which way files go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Both,
+    Push,
+    Pull,
+}
+
+/// What a sync does.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Plan {
+    /// Uploaded
+    pub push: Vec<String>,
+    /// Removed from the remote, as they are no longer on the board
+    pub remove: Vec<String>,
+    /// Downloaded
+    pub pull: Vec<String>,
+    /// Changed here and on the remote since the last sync, and left alone
+    pub conflicts: Vec<String>,
+    /// What the files are once it is done, here and on the remote alike
+    synced: Manifest,
+    /// What the remote lists once it is done
+    there: Manifest,
+}
+
+/// What to do with the files that are as `here` locally and `there` on the remote, which were
+/// as `last` at the last sync. Conflicts go the way of `direction` with `force`.
+pub fn plan(last: &Manifest, here: &Manifest, there: &Manifest, direction: Direction, force: bool) -> Plan {
+    let mut plan = Plan { synced: last.clone(), there: there.clone(), ..Plan::default() };
+    let paths: BTreeSet<&String> = last.keys().chain(here.keys()).chain(there.keys()).collect();
+    for path in paths {
+        let (was, local, remote) = (last.get(path), here.get(path), there.get(path));
+        let push = match (local != was, remote != was) {
+            _ if local == remote => {
+                set(&mut plan.synced, path, local);
+                continue;
+            }
+            (true, false) => true,
+            (false, true) => false,
+            _ if force && direction != Direction::Both => direction == Direction::Push,
+            _ => {
+                plan.conflicts.push(path.clone());
+                continue;
+            }
+        };
+        match (push, direction) {
+            (true, Direction::Pull) | (false, Direction::Push) => continue,
+            (true, _) if local.is_some() => plan.push.push(path.clone()),
+            (true, _) => plan.remove.push(path.clone()),
+            (false, _) if remote.is_some() => plan.pull.push(path.clone()),
+            // Gone from the remote, so it isn't synced any more; it stays here all the same
+            (false, _) => {}
+        }
+        let now = if push { local } else { remote };
+        set(&mut plan.synced, path, now);
+        set(&mut plan.there, path, now);
+    }
+    plan
+}
+
+fn set(manifest: &mut Manifest, path: &str, hash: Option<&String>) {
+    match hash {
+        Some(hash) => manifest.insert(path.to_string(), hash.clone()),
+        None => manifest.remove(path),
+    };
+}
+
+/// What a sync did, and the board's files that it left out, as they aren't in its folder.
+pub struct Synced {
+    pub plan: Plan,
+    pub left_out: Vec<PathBuf>,
+    /// The config, when it was changed on both sides and merged
+    pub merged: Option<String>,
+    config: String,
+}
+
+impl Synced {
+    /// Whether the config here is another one now, which the board has to load.
+    pub fn config_changed(&self) -> bool {
+        self.merged.is_some() || self.plan.pull.contains(&self.config)
+    }
+}
+
+/// Syncs the board at `paths` with `remote`. Nothing is pulled over a file without its being
+/// checked to be what the remote lists, and a pulled or merged config is written like any other
+/// save, which keeps the one it replaces as a backup.
+pub fn sync(board: &Board, paths: &Paths, remote: &dyn Remote, direction: Direction, force: bool) -> color_eyre::Result<Synced> {
+    let folder = std::path::absolute(paths.folder())?;
+    let (files, left_out) = files(board, paths, &folder);
+    let mut here: Manifest = files.iter().filter_map(|(path, file)| Some((path.clone(), sha256::hex(&fs::read(file).ok()?)))).collect();
+    let (there, mut changed) = match remote.get(MANIFEST)? {
+        Some(data) => (parse(&data).map_err(|e| eyre!("the remote's {MANIFEST} {e}"))?, times(&data)),
+        None => (Manifest::new(), Changed::new()),
+    };
+    let last_synced = paths.state.join(format!("{}-synced.json", paths.stem()));
+    let mut last = fs::read(&last_synced).ok().and_then(|data| parse(&data).ok()).unwrap_or_default();
+    // The config as it was at the last sync, for what changed since on each side
+    let base = paths.state.join(format!("{}-synced.toml", paths.stem()));
+
+    let config = config_name(paths);
+    let mut merged = None;
+    if let (false, Some(was), Some(local), Some(theirs)) = (direction == Direction::Push || force, last.get(&config), here.get(&config), there.get(&config)) {
+        if local != was && theirs != was && local != theirs {
+            if let Some(config_merged) = merge_config(remote, &config, &files[&config], &base, was, theirs, changed.get(&config).copied())? {
+                backup::write(&files[&config], &config_merged)?;
+                // Changed here since it was as it is there, so it is pushed from here
+                here.insert(config.clone(), sha256::hex(config_merged.as_bytes()));
+                last.insert(config.clone(), theirs.clone());
+                merged = Some(config.clone());
+            }
+        }
+    }
+    let plan = plan(&last, &here, &there, direction, force);
+
+    for path in &plan.push {
+        remote.put(path, &fs::read(&files[path]).wrap_err_with(|| format!("read {}", files[path].display()))?)?;
+        changed.insert(path.clone(), modified(&files[path]));
+    }
+    for path in &plan.remove {
+        remote.delete(path)?;
+        changed.remove(path);
+    }
+    if !plan.push.is_empty() || !plan.remove.is_empty() {
+        remote.put(MANIFEST, write(&plan.there, &changed).as_bytes())?;
+    }
+    for path in &plan.pull {
+        let data = remote.get(path)?.ok_or_else(|| eyre!("{path} is listed on the remote, but isn't there"))?;
+        if sha256::hex(&data) != there[path] {
+            bail!("{path} on the remote isn't what it is listed as, it may still be uploading from the other machine");
+        }
+        let file = files.get(path).cloned().unwrap_or_else(|| folder.join(path));
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir).wrap_err_with(|| format!("create {}", dir.display()))?;
+        }
+        if file == folder.join(config_name(paths)) {
+            backup::write(&file, &String::from_utf8_lossy(&data))?;
+        } else {
+            fs::write(&file, data).wrap_err_with(|| format!("write {}", file.display()))?;
+        }
+    }
+    fs::create_dir_all(&paths.state).wrap_err_with(|| format!("create {}", paths.state.display()))?;
+    fs::write(&last_synced, write(&plan.synced, &Changed::new())).wrap_err_with(|| format!("write {}", last_synced.display()))?;
+    if let Ok(now) = fs::read_to_string(&files[&config]) {
+        if plan.synced.get(&config) == Some(&sha256::hex(now.as_bytes())) {
+            fs::write(&base, now).wrap_err_with(|| format!("write {}", base.display()))?;
+        }
+    }
+    Ok(Synced { plan, left_out, merged, config })
+}
+
+/// Syncs the board both ways in the background, like `soundboard sync`, handing out what it did
+/// once it is done.
+pub fn start(board: &Board, paths: &Paths, settings: &SyncSettings) -> Receiver<color_eyre::Result<Synced>> {
+    let (tx, rx) = mpsc::channel();
+    let (board, paths, settings) = (board.clone(), paths.clone(), settings.clone());
+    thread::spawn(move || {
+        let _ = tx.send(sync(&board, &paths, &*remote(&settings), Direction::Both, false));
+    });
+    rx
+}
+
+/// The config at `file` merged with the one on the remote, which is listed as `theirs` and was
+/// pushed at `pushed`; both were `was` at the last sync, which `base` has a copy of. `None` when
+/// the remote's isn't there yet as listed, or either doesn't load, which leaves it a conflict.
+fn merge_config(remote: &dyn Remote, config: &str, file: &Path, base: &Path, was: &str, theirs: &str, pushed: Option<u64>) -> color_eyre::Result<Option<String>> {
+    let Some(data) = remote.get(config)?.filter(|data| sha256::hex(data) == theirs) else { return Ok(None) };
+    let ours = fs::read_to_string(file).wrap_err_with(|| format!("read {}", file.display()))?;
+    // Without the copy everything that isn't the same on both counts as changed on both
+    let base = fs::read_to_string(base).ok().filter(|base| sha256::hex(base.as_bytes()) == was).unwrap_or_default();
+    let ours_later = modified(file) >= pushed.unwrap_or_default();
+    Ok(merge::configs(&base, &ours, &String::from_utf8_lossy(&data), ours_later))
+}
+
+/// When `file` was last changed, in seconds since the Unix epoch.
+fn modified(file: &Path) -> u64 {
+    fs::metadata(file).and_then(|m| m.modified()).ok().and_then(|at| at.duration_since(UNIX_EPOCH).ok()).map_or(0, |since| since.as_secs())
+}
+
+fn config_name(paths: &Paths) -> String {
+    paths.config.file_name().map_or("soundboard.toml".into(), |name| name.to_string_lossy().into_owned())
+}
+
+/// The files that are synced, the config and the files of the board that are in `folder`, by
+/// their path from it, and the files of the board that aren't.
+fn files(board: &Board, paths: &Paths, folder: &Path) -> (BTreeMap<String, PathBuf>, Vec<PathBuf>) {
+    let mut files = BTreeMap::from([(config_name(paths), folder.join(config_name(paths)))]);
+    let mut left_out = Vec::new();
+    let sounds = board.sounds.iter().chain(&board.trash).flat_map(|s| s.sources()).filter_map(|s| if let Source::File(p) = s { Some(p.clone()) } else { None });
+    // The art is found from the config's folder already
+    let art = [&board.header, &board.splash].into_iter().flatten().map(|art| paths.folder().join(art));
+    for file in sounds.chain(art) {
+        let inside = std::path::absolute(&file).ok().and_then(|full| Some(full.strip_prefix(folder).ok()?.to_path_buf()));
+        match inside {
+            Some(inside) => {
+                let path = inside.iter().map(|part| part.to_string_lossy()).collect::<Vec<_>>().join("/");
+                files.insert(path, folder.join(inside));
+            }
+            None if !left_out.contains(&file) => left_out.push(file),
+            None => {}
+        }
+    }
+    (files, left_out)
+}
+
+/// A manifest as it is written down, leaving out files that wouldn't be in the board's folder.
+fn parse(data: &[u8]) -> Result<Manifest, String> {
+    let value = json::parse(&String::from_utf8_lossy(data))?;
+    let Some(Value::Object(files)) = value.get("files") else { return Err("has no \"files\"".to_string()) };
+    let inside = |path: &str| !path.contains(['\\', ':']) && path.split('/').all(|part| !part.is_empty() && part != "." && part != "..");
+    Ok(files.iter().filter(|(path, _)| inside(path)).filter_map(|(path, hash)| Some((path.clone(), hash.as_str()?.to_string()))).collect())
+}
+
+/// When the files in a manifest were pushed, where it says; manifests from before it did don't.
+fn times(data: &[u8]) -> Changed {
+    let Ok(value) = json::parse(&String::from_utf8_lossy(data)) else { return Changed::new() };
+    let Some(Value::Object(times)) = value.get("changed") else { return Changed::new() };
+    times.iter().filter_map(|(path, at)| Some((path.clone(), at.as_f64()? as u64))).collect()
+}
+
+fn write(manifest: &Manifest, changed: &Changed) -> String {
+    let mut fields = vec![("files", Value::object(manifest.iter().map(|(path, hash)| (path.clone(), hash.as_str().into()))))];
+    if !changed.is_empty() {
+        fields.push(("changed", Value::object(changed.iter().map(|(path, at)| (path.clone(), Value::Number(*at as f64))))));
+    }
+    Value::object(fields).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::{Duration, Instant};
+    use crossterm::event::KeyCode;
+    use crate::config::{Board, SyncSettings};
+    use crate::harness::{self, Harness};
+    use crate::paths::Paths;
+    use super::{is_rclone, plan, sync, Direction, Folder, Manifest};
+
+    fn manifest(files: &[(&str, &str)]) -> Manifest {
+        files.iter().map(|(path, hash)| (path.to_string(), hash.to_string())).collect()
+    }
+
+    #[test]
+    fn changes_go_the_way_they_were_made() {
+        let last = manifest(&[("board.toml", "1"), ("a.wav", "a"), ("b.wav", "b"), ("c.wav", "c")]);
+        // The config changed on both, `a` here, `b` there, `c` is gone here and `d` is new there
+        let here = manifest(&[("board.toml", "2"), ("a.wav", "A"), ("b.wav", "b")]);
+        let there = manifest(&[("board.toml", "3"), ("a.wav", "a"), ("b.wav", "B"), ("c.wav", "c"), ("d.wav", "d")]);
+        let both = plan(&last, &here, &there, Direction::Both, false);
+        assert_eq!((both.push, both.remove, both.pull, both.conflicts), (vec!["a.wav".to_string()], vec!["c.wav".to_string()], vec!["b.wav".to_string(), "d.wav".to_string()], vec!["board.toml".to_string()]));
+        assert_eq!(both.synced, manifest(&[("board.toml", "1"), ("a.wav", "A"), ("b.wav", "B"), ("d.wav", "d")]));
+
+        // One way only leaves the others as they were, and may settle conflicts
+        let push = plan(&last, &here, &there, Direction::Push, true);
+        assert_eq!((push.push, push.pull), (vec!["a.wav".to_string(), "board.toml".to_string()], Vec::new()));
+        assert_eq!(push.there, manifest(&[("board.toml", "2"), ("a.wav", "A"), ("b.wav", "B"), ("d.wav", "d")]));
+        let pull = plan(&last, &here, &there, Direction::Pull, false);
+        assert_eq!((pull.push, pull.pull, pull.conflicts), (Vec::new(), vec!["b.wav".to_string(), "d.wav".to_string()], vec!["board.toml".to_string()]));
+
+        assert!(is_rclone("drive:soundboard") && is_rclone("nas:"));
+        assert!(!is_rclone("/mnt/usb/soundboard") && !is_rclone("sync/board"));
+    }
+
+    #[test]
+    fn a_board_follows_along_to_another_machine() {
+        let dir = harness::scratch("sync");
+        let (desktop, laptop) = (dir.join("desktop"), dir.join("laptop"));
+        fs::create_dir_all(desktop.join("sounds")).unwrap();
+        fs::create_dir_all(&laptop).unwrap();
+        fs::write(desktop.join("sounds/horn.wav"), crate::config::BUILTIN[0].1).unwrap();
+        fs::write(desktop.join("soundboard.toml"), "version = 2\n[[sound]]\nname = \"horn\"\nfile = \"sounds/horn.wav\"\n").unwrap();
+        let remote = Folder(dir.join("remote"));
+        let both = |paths: &Paths, direction, force| sync(&Board::load(&paths.config).unwrap().0, paths, &remote, direction, force);
+        let (desktop, laptop) = (Paths::beside(desktop.join("soundboard.toml")), Paths::beside(laptop.join("soundboard.toml")));
+
+        assert_eq!(both(&desktop, Direction::Both, false).unwrap().plan.push, ["soundboard.toml", "sounds/horn.wav"]);
+        assert_eq!(both(&laptop, Direction::Both, false).unwrap().plan.pull, ["soundboard.toml", "sounds/horn.wav"]);
+        let board = Board::load(&laptop.config).unwrap().0;
+        assert!(board.sounds[0].data().is_ok(), "{:?}", board.sounds[0].source);
+        assert!(both(&laptop, Direction::Both, false).unwrap().plan.pull.is_empty());
+
+        // Changed on both, a sound stays as it is on each until one is picked
+        fs::write(desktop.folder().join("sounds/horn.wav"), crate::config::BUILTIN[1].1).unwrap();
+        both(&desktop, Direction::Both, false).unwrap();
+        fs::write(laptop.folder().join("sounds/horn.wav"), crate::config::BUILTIN[2].1).unwrap();
+        assert_eq!(both(&laptop, Direction::Both, false).unwrap().plan.conflicts, ["sounds/horn.wav"]);
+        assert_eq!(fs::read(laptop.folder().join("sounds/horn.wav")).unwrap(), crate::config::BUILTIN[2].1);
+        both(&laptop, Direction::Pull, true).unwrap();
+        assert_eq!(fs::read(laptop.folder().join("sounds/horn.wav")).unwrap(), crate::config::BUILTIN[1].1);
+    }
+
+    #[test]
+    fn a_board_changed_on_two_machines_is_merged() {
+        let dir = harness::scratch("sync-merge");
+        let (desktop, laptop) = (dir.join("desktop"), dir.join("laptop"));
+        fs::create_dir_all(&desktop).unwrap();
+        fs::create_dir_all(&laptop).unwrap();
+        let config = |sounds: &str| format!("version = 2\n{sounds}[[sound]]\nname = \"horn\"\nbuiltin = \"puree\"\nkey = \"h\"\n");
+        fs::write(desktop.join("soundboard.toml"), config("")).unwrap();
+        let remote = Folder(dir.join("remote"));
+        let both = |paths: &Paths| sync(&Board::load(&paths.config).unwrap().0, paths, &remote, Direction::Both, false).unwrap();
+        let (desktop, laptop) = (Paths::beside(desktop.join("soundboard.toml")), Paths::beside(laptop.join("soundboard.toml")));
+        both(&desktop);
+        both(&laptop);
+
+        // One adds a sound, the other rebinds one, and neither loses what the other did
+        fs::write(&desktop.config, config("[[sound]]\nname = \"bell\"\nbuiltin = \"erg\"\n")).unwrap();
+        both(&desktop);
+        fs::write(&laptop.config, config("").replace("key = \"h\"", "key = \"j\"")).unwrap();
+        let synced = both(&laptop);
+        assert!(synced.plan.conflicts.is_empty() && synced.config_changed());
+        assert_eq!((synced.merged.as_deref(), synced.plan.push.as_slice()), (Some("soundboard.toml"), &["soundboard.toml".to_string()][..]));
+        let merged = Board::load(&laptop.config).unwrap().0;
+        let names: Vec<_> = merged.sounds.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!((names.as_slice(), merged.sounds[0].bindings[0].label.as_str()), (&["horn", "bell"][..], "j"));
+
+        // Which the other machine gets as it is
+        assert!(both(&desktop).plan.pull == ["soundboard.toml"]);
+        assert_eq!(Board::load(&desktop.config).unwrap().0, merged);
+        assert!(!both(&laptop).config_changed());
+    }
+
+    #[test]
+    fn the_running_board_takes_in_changes_made_elsewhere() {
+        let dir = harness::scratch("sync-app");
+        let remote = Folder(dir.join("remote"));
+        let mut board = harness::board(2);
+        board.sync = Some(SyncSettings { remote: dir.join("remote").display().to_string(), username: None, password: None, every: None });
+        let mut h = Harness::new(board.clone(), 80, 24).loaded();
+        board.save(&h.app.paths.config).unwrap();
+        sync(&board, &h.app.paths, &remote, Direction::Both, false).unwrap();
+
+        // Someone else adds a sound, while a tile here is turned down
+        let elsewhere = Paths::beside(dir.join("elsewhere").join(h.app.paths.config.file_name().unwrap()));
+        fs::create_dir_all(dir.join("elsewhere")).unwrap();
+        sync(&board, &elsewhere, &remote, Direction::Both, false).unwrap();
+        let mut theirs = Board::load(&elsewhere.config).unwrap().0;
+        theirs.sounds.push(harness::board(3).sounds.pop().unwrap());
+        theirs.save(&elsewhere.config).unwrap();
+        sync(&theirs, &elsewhere, &remote, Direction::Both, false).unwrap();
+        h.app.sync();
+        h.app.board.sounds[0].volume = 0.5;
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while h.app.status.is_none() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            h.app.tick(Instant::now());
+        }
+        assert_eq!(h.app.status.as_deref(), Some("synced the board with the changes made elsewhere"));
+        let names: Vec<_> = h.app.board.sounds.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["sound 0", "sound 1", "sound 2"]);
+        assert_eq!(Board::load(&h.app.paths.config).unwrap().0.sounds[0].volume, 0.5);
+
+        // Trashed while the next sync runs, which is saved once it is done instead of being
+        // written over by it
+        h.app.sync();
+        h.press(KeyCode::Delete);
+        assert_eq!(Board::load(&h.app.paths.config).unwrap().0.sounds.len(), 3);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Board::load(&h.app.paths.config).unwrap().0.sounds.len() == 3 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            h.app.tick(Instant::now());
+        }
+        let names: Vec<_> = Board::load(&h.app.paths.config).unwrap().0.sounds.into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["sound 1", "sound 2"]);
+        for file in [h.app.paths.config.clone(), h.app.paths.state.join(format!("{}-synced.json", h.app.paths.stem())), h.app.paths.state.join(format!("{}-synced.toml", h.app.paths.stem()))] {
+            let _ = fs::remove_file(file);
+        }
+    }
+}
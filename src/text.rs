@@ -0,0 +1,151 @@
+//! Fitting names into tiles by how wide they are on screen rather than how many bytes or chars
+//! they have, so wide characters, emoji and accents made of several code points line up, and
+//! nothing is cut in the middle of a character.
+
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const ELLIPSIS: &str = "…";
+/// Between the end of a scrolling name and its start coming round again
+const MARQUEE_GAP: &str = "   ";
+
+/// How many columns `s` takes.
+pub fn width(s: &str) -> usize {
+    s.width()
+}
+
+/// `s` cut to at most `width` columns, ending in an ellipsis when something was cut.
+pub fn truncate(s: &str, width: usize) -> String {
+    if s.width() <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for grapheme in s.graphemes(true) {
+        let w = grapheme.width();
+        if used + w > width - 1 {
+            break;
+        }
+        out.push_str(grapheme);
+        used += w;
+    }
+    out.push_str(ELLIPSIS);
+    out
+}
+
+/// `s` on up to `lines` lines of at most `width` columns, broken between words where it can,
+/// and truncated on the last line when it doesn't fit.
+pub fn wrap(s: &str, width: usize, lines: usize) -> Vec<String> {
+    if width == 0 || lines == 0 {
+        return Vec::new();
+    }
+    let mut out: Vec<String> = Vec::new();
+    let mut line = String::new();
+    let mut words = s.split_word_bounds();
+    while let Some(word) = words.next() {
+        if line.width() + word.width() <= width {
+            line.push_str(word);
+            continue;
+        }
+        if out.len() + 1 == lines {
+            // The rest only fits in a truncated last line
+            let rest: String = std::iter::once(word).chain(words).collect();
+            line.push_str(&rest);
+            break;
+        }
+        if !line.trim().is_empty() {
+            out.push(line.trim_end().to_string());
+        }
+        line = word.trim_start().to_string();
+        // A word too long for a line of its own is broken where it has to be
+        while line.width() > width && out.len() + 1 < lines {
+            let head = truncate_plain(&line, width);
+            line = line[head.len()..].to_string();
+            out.push(head);
+        }
+    }
+    if !line.trim().is_empty() {
+        out.push(truncate(line.trim(), width));
+    }
+    out
+}
+
+/// A window of `width` columns onto `s` scrolled `offset` columns along, `s` coming round
+/// again after a gap.
+pub fn marquee(s: &str, width: usize, offset: usize) -> String {
+    let looped: Vec<&str> = s.graphemes(true).chain(MARQUEE_GAP.graphemes(true)).collect();
+    let mut out = String::new();
+    let mut used = 0;
+    // Wide characters can't be shown by half, so the window moves a grapheme at a time
+    for grapheme in looped.iter().cycle().skip(offset % looped.len()) {
+        let w = grapheme.width();
+        if used + w > width {
+            break;
+        }
+        out.push_str(grapheme);
+        used += w;
+    }
+    out
+}
+
+/// The longest start of `s` that fits in `width` columns, without an ellipsis.
+fn truncate_plain(s: &str, width: usize) -> String {
+    let mut out = String::new();
+    for grapheme in s.graphemes(true) {
+        if out.width() + grapheme.width() > width {
+            break;
+        }
+        out.push_str(grapheme);
+    }
+    // Nothing fits, not even one character: take it anyway so wrapping moves on
+    if out.is_empty() {
+        out.push_str(s.graphemes(true).next().unwrap_or_default());
+    }
+    out
+}
+
+/// How far the marquee has scrolled after `elapsed`, at `step` a column, after first showing
+/// the start for `pause`.
+pub fn marquee_offset(elapsed: Duration, pause: Duration, step: Duration) -> usize {
+    (elapsed.saturating_sub(pause).as_millis() / step.as_millis().max(1)) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{marquee, truncate, width, wrap};
+
+    #[test]
+    fn truncated_by_width() {
+        assert_eq!(truncate("airhorn", 10), "airhorn");
+        assert_eq!(truncate("sad trombone", 8), "sad tro…");
+        // Two columns each, so four of them don't fit in eight with the ellipsis
+        assert_eq!(truncate("日本語のテキスト", 8), "日本語…");
+        // An accent made of two code points stays with its letter
+        assert_eq!(truncate("cafe\u{301} au lait", 5), "cafe\u{301}…");
+        assert_eq!(width(&truncate("🎺🎺🎺🎺", 5)), 5);
+    }
+
+    #[test]
+    fn wrapped_between_words() {
+        assert_eq!(wrap("sad trombone", 20, 2), ["sad trombone"]);
+        assert_eq!(wrap("the sad trombone", 10, 2), ["the sad", "trombone"]);
+        assert_eq!(wrap("the saddest trombone ever", 10, 2), ["the", "saddest t…"]);
+        assert_eq!(wrap("supercalifragilistic", 8, 2), ["supercal", "ifragil…"]);
+        for line in wrap("日本語のテキストです", 7, 2) {
+            assert!(width(&line) <= 7, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn marquee_comes_round() {
+        assert_eq!(marquee("airhorn", 4, 0), "airh");
+        assert_eq!(marquee("airhorn", 4, 5), "rn  ");
+        assert_eq!(marquee("airhorn", 4, 9), " air");
+        assert_eq!(marquee("airhorn", 4, 10), "airh");
+        assert_eq!(marquee("日本語", 3, 1), "本");
+    }
+}
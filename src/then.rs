@@ -0,0 +1,65 @@
+//! What sounds do once they have played to their end, `then-play`, `then-run` and
+//! `then-webhook` on a sound: simple automations, like an intro that leads into a bed, or a
+//! script that switches the stream's scene once the countdown is over.
+
+use std::collections::HashMap;
+use crate::audio::Finished;
+use crate::config::Then;
+use crate::history::Via;
+
+/// Keeps track of sounds that do something at their end. Their commands run like those of
+/// command tiles, see [`crate::shell`].
+#[derive(Default)]
+pub struct Hooks {
+    /// The sounds playing that do something at their end, by the id the engine gave them
+    active: HashMap<u64, (String, Via, Vec<Then>)>,
+}
+
+impl Hooks {
+    pub fn start(&mut self, id: u64, sound: &str, via: Via, then: &[Then]) {
+        if !then.is_empty() {
+            self.active.insert(id, (sound.to_string(), via, then.to_vec()));
+        }
+    }
+
+    /// The sound that is over, how it was played and what it does now, if anything. A sound
+    /// that was stopped does nothing.
+    pub fn finish(&mut self, finished: Finished) -> Option<(String, Via, Vec<Then>)> {
+        self.active.remove(&finished.id).filter(|_| !finished.stopped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use crate::config::{Board, Then};
+    use crate::harness::{self, Harness};
+    use crate::history::Via;
+    use crate::toml;
+
+    #[test]
+    fn a_sound_plays_the_next_once_it_is_over() {
+        let mut board = harness::board(3);
+        board.sounds[0].then = vec![Then::Play("sound 2".to_string())];
+        let mut h = Harness::new(board, 80, 24).loaded();
+        h.press(KeyCode::Enter);
+        h.step();
+        assert_eq!(h.audio.take().iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["sound 0", "sound 2"]);
+        assert_eq!(h.app.history.entries.last().map(|e| e.via), Some(Via::Then));
+        assert_eq!(h.app.board.sounds[2].plays, 1);
+    }
+
+    #[test]
+    fn then_round_trips_through_the_config() {
+        let src = "version = 1\n[[sound]]\nname = \"intro\"\nbuiltin = \"puree\"\nthen-play = \"bed\"\nthen-run = [\"obs-cli scene live\", \"true\"]\nthen-webhook = \"http://localhost:8123/hook\"\n";
+        let (board, _) = Board::parse(src).unwrap();
+        let then = &board.sounds[0].then;
+        assert_eq!(then.len(), 4);
+        assert_eq!(then[0], Then::Play("bed".to_string()));
+        let (again, _) = Board::parse(&toml::to_string(&board.to_table())).unwrap();
+        assert_eq!(&again.sounds[0].then, then);
+
+        let looped = src.replace("then-play", "loop = true\nthen-play");
+        assert!(Board::parse(&looped).unwrap_err().to_string().contains("never ends"));
+    }
+}
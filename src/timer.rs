@@ -0,0 +1,251 @@
+//! Timers, for a board that is also the operator's timing reference: with `clock` in `[ui]` the
+//! status bar shows the time and how long the show has been going, which ^Y starts over, and
+//! `[[countdown]]`s count down from their key being pressed. `[[schedule]]`s recall a scene or
+//! play a sound without anyone pressing anything, like switching to the "BRB" scene at 20:30,
+//! or an airhorn every Friday at 17:00.
+
+use std::time::{Duration, Instant};
+use crate::config::{Countdown, Schedule, When};
+
+/// How long a countdown that ran out stays in the status bar.
+pub const DONE_SHOWN: Duration = Duration::from_secs(5);
+/// A countdown this close to running out stands out.
+pub const ALMOST: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub struct Timers {
+    /// When the show timer started
+    pub show: Instant,
+    /// The countdowns that run, by their name, and when they run out
+    pub running: Vec<(String, Instant)>,
+    /// The countdowns that ran out lately, and when
+    pub done: Vec<(String, Instant)>,
+    /// The second last shown, to know when the status bar needs drawing again
+    shown: Option<u64>,
+    /// When the board started, which `after` counts from
+    pub start: Instant,
+    /// When schedules were last looked at, and the time of the week then
+    looked: Option<(Instant, u32)>,
+}
+
+impl Timers {
+    pub fn new(now: Instant) -> Self {
+        Self { show: now, running: Vec::new(), done: Vec::new(), shown: None, start: now, looked: None }
+    }
+
+    /// The schedules whose time came since they were last looked at, with `time` the seconds
+    /// since Monday midnight at `now`, see [`crate::vars::time_of_week`]. The time going back,
+    /// when the clocks change, doesn't set anything off.
+    pub fn due<'s>(&mut self, schedules: &'s [Schedule], now: Instant, time: u32) -> Vec<&'s Schedule> {
+        let looked = self.looked.replace((now, time));
+        let ran = |at: Instant| at.saturating_duration_since(self.start);
+        let (before, ran_now) = (ran(looked.map_or(self.start, |(at, _)| at)), ran(now));
+        schedules
+            .iter()
+            .filter(|s| match s.when {
+                When::After(after) => before < after && after <= ran_now,
+                When::At(at) => looked.is_some_and(|(_, was)| {
+                    let days = if s.days.is_empty() { &[0, 1, 2, 3, 4, 5, 6][..] } else { &s.days };
+                    days.iter().any(|&day| passed(was, time, u32::from(day) * DAY + at))
+                }),
+            })
+            .collect()
+    }
+
+    /// Starts `countdown`, or stops it when it is already running. Returns whether it runs now.
+    pub fn toggle(&mut self, countdown: &Countdown, now: Instant) -> bool {
+        if let Some(i) = self.running.iter().position(|(name, _)| *name == countdown.name) {
+            self.running.remove(i);
+            return false;
+        }
+        self.done.retain(|(name, _)| *name != countdown.name);
+        self.running.push((countdown.name.clone(), now + countdown.duration));
+        true
+    }
+
+    /// Takes the countdowns that ran out by `now`, and forgets the ones that did long enough
+    /// ago. Returns their names, and whether what the status bar shows changed.
+    pub fn tick(&mut self, now: Instant, clock: bool) -> (Vec<String>, bool) {
+        let (over, running): (Vec<_>, Vec<_>) = std::mem::take(&mut self.running).into_iter().partition(|&(_, at)| at <= now);
+        self.running = running;
+        let before = self.done.len();
+        self.done.retain(|&(_, at)| now.saturating_duration_since(at) < DONE_SHOWN);
+        let mut changed = !over.is_empty() || self.done.len() != before;
+        self.done.extend(over.iter().cloned());
+
+        let second = (clock || !self.running.is_empty()).then(|| now.saturating_duration_since(self.show).as_secs());
+        changed |= second.is_some() && second != self.shown;
+        self.shown = second;
+        (over.into_iter().map(|(name, _)| name).collect(), changed)
+    }
+
+    /// How long is left of the countdown running out at `at`, rounded up the way a countdown
+    /// shows it.
+    pub fn left(at: Instant, now: Instant) -> Duration {
+        let left = at.saturating_duration_since(now);
+        Duration::from_secs(left.as_secs() + u64::from(left.subsec_nanos() > 0))
+    }
+}
+
+const DAY: u32 = 24 * 3600;
+
+/// Whether `at` came after `was` and by `now`, all in seconds into the week, which may have
+/// started over in between.
+fn passed(was: u32, now: u32, at: u32) -> bool {
+    match now.cmp(&was) {
+        std::cmp::Ordering::Equal => false,
+        std::cmp::Ordering::Greater => was < at && at <= now,
+        // Into the next week, rather than the clock being put back an hour
+        std::cmp::Ordering::Less if was - now > 7 * DAY - 3600 => at > was || at <= now,
+        std::cmp::Ordering::Less => false,
+    }
+}
+
+/// Like `4:05`, or `1:02:03` from an hour on.
+pub fn format(d: Duration) -> String {
+    let secs = d.as_secs();
+    match secs / 3600 {
+        0 => format!("{}:{:02}", secs / 60, secs % 60),
+        hours => format!("{hours}:{:02}:{:02}", secs / 60 % 60, secs % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use crate::binding::Binding;
+    use crate::config::{Board, Bus, Countdown, Scene, Schedule, When};
+    use crate::history::Via;
+    use crate::harness::{self, Harness};
+    use crate::toml;
+    use super::{format, passed, Timers, DAY, DONE_SHOWN};
+
+    #[test]
+    fn countdowns_run_out_and_go_away() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let countdown = Countdown { name: "break".to_string(), key: None, duration: Duration::from_secs(300), sound: None, scene: None };
+        let mut timers = Timers::new(start);
+        assert!(timers.toggle(&countdown, at(0)));
+        assert_eq!(Timers::left(timers.running[0].1, start + Duration::from_millis(1500)), Duration::from_secs(299));
+        assert_eq!(timers.tick(at(299), false), (Vec::new(), true));
+        assert_eq!(timers.tick(at(300), false), (vec!["break".to_string()], true));
+        assert_eq!(timers.done.len(), 1);
+        assert_eq!(timers.tick(at(300) + DONE_SHOWN, false), (Vec::new(), true));
+        assert!(timers.done.is_empty());
+        // Nothing to show, nothing to draw
+        assert_eq!(timers.tick(at(400), false), (Vec::new(), false));
+
+        // Pressed again while it runs, it stops
+        assert!(timers.toggle(&countdown, at(500)));
+        assert!(!timers.toggle(&countdown, at(510)));
+        assert!(timers.running.is_empty());
+        assert_eq!([format(Duration::from_secs(245)), format(Duration::from_secs(3723))], ["4:05", "1:02:03"]);
+    }
+
+    #[test]
+    fn the_status_bar_counts_down() {
+        let mut board = harness::board(2);
+        board.clock = true;
+        board.countdowns = vec![Countdown { name: "break".to_string(), key: Binding::parse_key("F6"), duration: Duration::from_secs(90), sound: Some("sound 1".to_string()), scene: None }];
+        let (again, _) = Board::parse(&toml::to_string(&board.to_table())).unwrap();
+        assert_eq!((again.clock, &again.countdowns), (true, &board.countdowns));
+
+        let mut h = Harness::new(board, 120, 24).loaded();
+        assert!(h.screen().contains(" show 0:00 "), "{}", h.screen());
+        h.press(KeyCode::F(6));
+        assert!(h.screen().contains(" break 1:30 "), "{}", h.screen());
+        assert!(h.audio.take().is_empty());
+
+        // It plays its sound once it is over
+        let end = h.app.timers.running[0].1;
+        h.app.tick(end);
+        assert_eq!(h.audio.take().iter().map(|p| p.name.clone()).collect::<Vec<_>>(), ["sound 1"]);
+        h.step();
+        assert!(h.screen().contains(" break 0:00 "));
+
+        h.app.timers.show -= Duration::from_secs(75);
+        h.step();
+        assert!(h.screen().contains(" show 1:15 "));
+        h.press_with(KeyCode::Char('y'), KeyModifiers::CONTROL);
+        assert!(h.screen().contains(" show 0:00 "));
+    }
+
+    #[test]
+    fn schedules_recall_their_scene() {
+        let src = "version = 1\n[[scene]]\nname = \"brb\"\nbuses = []\nloops = []\n[[schedule]]\nat = \"20:30\"\nscene = \"brb\"\n[[schedule]]\nafter = 60\nscene = \"brb\"\n";
+        let (board, _) = Board::parse(src).unwrap();
+        let at = 20 * 3600 + 30 * 60;
+        assert_eq!(board.schedules.iter().map(|s| s.when).collect::<Vec<_>>(), [When::At(at), When::After(Duration::from_secs(60))]);
+        let (again, _) = Board::parse(&toml::to_string(&board.to_table())).unwrap();
+        assert_eq!(again.schedules, board.schedules);
+        for (from, to, error) in [("\"20:30\"", "\"25:00\"", "is not a time of day"), ("brb\"\n[[schedule]]\nafter", "outro\"\n[[schedule]]\nafter", "no scene named \"outro\""), ("after = 60", "after = 60\nat = \"9:00\"", "not both")] {
+            let err = Board::parse(&src.replacen(from, to, 1)).unwrap_err().to_string();
+            assert!(err.contains(error), "{err}");
+        }
+
+        let start = Instant::now();
+        let secs = |s: u64| start + Duration::from_secs(s);
+        let mut timers = Timers::new(start);
+        assert!(timers.due(&board.schedules, secs(30), at - 100).is_empty());
+        assert_eq!(timers.due(&board.schedules, secs(60), at - 1), [&board.schedules[1]]);
+        assert_eq!(timers.due(&board.schedules, secs(61), at), [&board.schedules[0]]);
+        assert!(timers.due(&board.schedules, secs(62), at + 1).is_empty());
+        // Round midnight on Sunday, but not when the clock is put back an hour
+        assert!(passed(7 * DAY - 1, 0, 0));
+        assert!(!passed(10_000, 6400, 8000));
+    }
+
+    #[test]
+    fn scenes_come_when_their_time_does() {
+        let mut board = harness::board(2);
+        board.scenes = vec![Scene { name: "brb".to_string(), key: None, buses: Vec::new(), loops: vec!["sound 0".to_string()] }];
+        board.schedules = vec![Schedule { when: When::After(Duration::from_secs(60)), days: Vec::new(), scene: Some("brb".to_string()), sound: None, bus: None }];
+        board.countdowns = vec![Countdown { name: "break".to_string(), key: Binding::parse_key("F6"), duration: Duration::from_secs(30), sound: None, scene: Some("brb".to_string()) }];
+        let mut h = Harness::new(board, 120, 24).loaded();
+        let start = h.app.timers.start;
+        h.app.tick(start + Duration::from_secs(59));
+        assert!(h.audio.take().is_empty());
+        h.app.tick(start + Duration::from_secs(60));
+        assert_eq!(h.audio.take().iter().map(|p| p.name.clone()).collect::<Vec<_>>(), ["sound 0"]);
+
+        // And when a countdown runs out
+        h.press(KeyCode::F(6));
+        let end = h.app.timers.running[0].1;
+        h.app.tick(end);
+        assert_eq!(h.audio.take().iter().map(|p| p.name.clone()).collect::<Vec<_>>(), ["sound 0"]);
+    }
+
+    #[test]
+    fn rules_go_off_on_their_days() {
+        let src = "version = 1\n[[sound]]\nname = \"airhorn\"\nbuiltin = \"puree\"\n[[schedule]]\nat = \"17:00\"\ndays = [\"Fri\", \"weekend\"]\nsound = \"airhorn\"\nbus = \"sfx\"\n";
+        let (board, _) = Board::parse(src).unwrap();
+        let friday = 4 * DAY + 17 * 3600;
+        assert_eq!((&board.schedules[0].days, board.schedules[0].when), (&vec![4, 5, 6], When::At(17 * 3600)));
+        let (again, _) = Board::parse(&toml::to_string(&board.to_table())).unwrap();
+        assert_eq!(again.schedules, board.schedules);
+        for (from, to, error) in [("\"Fri\"", "\"someday\"", "not a day of the week"), ("at = \"17:00\"", "after = 60", "only go with `at`"), ("sound = \"airhorn\"\n", "", "where the schedule's `sound` plays")] {
+            let err = Board::parse(&src.replacen(from, to, 1)).unwrap_err().to_string();
+            assert!(err.contains(error), "{err}");
+        }
+
+        let start = Instant::now();
+        let mut timers = Timers::new(start);
+        timers.due(&board.schedules, start, friday - 3 * DAY - 1);
+        assert!(timers.due(&board.schedules, start, friday - 3 * DAY).is_empty());
+        timers.due(&board.schedules, start, friday - 1);
+        assert_eq!(timers.due(&board.schedules, start, friday).len(), 1);
+
+        // It plays on the bus it names
+        let mut board = harness::board(1);
+        board.buses = vec![Bus { name: "sfx".to_string(), volume: 0.5, muted: false, effects: Vec::new(), wet: 1.0, stem: false }];
+        board.schedules = vec![Schedule { when: When::After(Duration::from_secs(1)), days: Vec::new(), scene: None, sound: Some("sound 0".to_string()), bus: Some("sfx".to_string()) }];
+        let mut h = Harness::new(board, 120, 24).loaded();
+        let start = h.app.timers.start;
+        h.app.tick(start + Duration::from_secs(1));
+        let played = h.audio.take();
+        assert!(!played.is_empty() && played.iter().all(|p| p.name == "sound 0" && p.volume == 0.5), "{played:?}");
+        assert_eq!(h.app.history.entries.last().map(|e| e.via), Some(Via::Timer));
+    }
+}
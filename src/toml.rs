@@ -0,0 +1,758 @@
+//! A small TOML subset, just enough for the board config.
+//!
+//! Supports comments, `[table]`, `[[array.of.tables]]`, dotted headers, basic and literal
+//! strings (both also `"""multiline"""`), integers, floats (`inf` and `nan` too), booleans,
+//! (multiline) arrays and inline tables. Every key remembers the line it was
+//! defined on so config errors can point at it.
+
+use std::fmt::{self, Write};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Boolean(_) => "boolean",
+            Value::Array(_) => "array",
+            Value::Table(_) => "table",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub entries: Vec<Entry>,
+    pub line: usize,
+}
+
+impl Table {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entry(key).map(|e| &e.value)
+    }
+
+    pub fn entry(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.key == key)
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entries.iter_mut().find(|e| e.key == key).map(|e| &mut e.value)
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        let key = key.into();
+        let value = value.into();
+        match self.get_mut(&key) {
+            Some(v) => *v = value,
+            None => self.entries.push(Entry { key, value, line: 0 }),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::String(v.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::String(v)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Value::Integer(v)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Float(v)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Boolean(v)
+    }
+}
+
+impl From<Table> for Value {
+    fn from(v: Table) -> Self {
+        Value::Table(v)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Self {
+        Value::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+pub fn parse(src: &str) -> Result<Table, ParseError> {
+    let mut p = Parser { src: src.as_bytes(), pos: 0, line: 1 };
+    let mut root = Table { entries: Vec::new(), line: 1 };
+    // Path of the table that `key = value` lines currently go into
+    let mut current: Vec<String> = Vec::new();
+
+    loop {
+        p.skip_whitespace_and_comments(true);
+        let Some(c) = p.peek() else { break };
+
+        if c == b'[' {
+            let line = p.line;
+            p.pos += 1;
+            let array = p.peek() == Some(b'[');
+            if array {
+                p.pos += 1;
+            }
+            let path = p.key_path()?;
+            p.expect(b']')?;
+            if array {
+                p.expect(b']')?;
+            }
+            p.end_of_line()?;
+
+            let (last, parents) = path.split_last().expect("key path is never empty");
+            let parent = p.table_at(&mut root, parents, line)?;
+            if array {
+                let table = Table { entries: Vec::new(), line };
+                match parent.get_mut(last) {
+                    Some(Value::Array(items)) => items.push(Value::Table(table)),
+                    Some(_) => return Err(p.error_at(line, format!("`{last}` is already defined and is not an array of tables"))),
+                    None => parent.entries.push(Entry { key: last.clone(), value: Value::Array(vec![Value::Table(table)]), line }),
+                }
+            } else {
+                match parent.get_mut(last) {
+                    Some(Value::Table(_)) => {}
+                    Some(_) => return Err(p.error_at(line, format!("`{last}` is already defined and is not a table"))),
+                    None => parent.entries.push(Entry { key: last.clone(), value: Value::Table(Table { entries: Vec::new(), line }), line }),
+                }
+            }
+            current = path;
+        } else {
+            let line = p.line;
+            let path = p.key_path()?;
+            p.skip_inline_whitespace();
+            p.expect(b'=')?;
+            p.skip_inline_whitespace();
+            let value = p.value()?;
+            p.end_of_line()?;
+
+            let (last, parents) = path.split_last().expect("key path is never empty");
+            let mut full = current.clone();
+            full.extend_from_slice(parents);
+            let table = p.table_at(&mut root, &full, line)?;
+            if table.get(last).is_some() {
+                return Err(p.error_at(line, format!("duplicate key `{last}`")));
+            }
+            table.entries.push(Entry { key: last.clone(), value, line });
+        }
+    }
+
+    Ok(root)
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        self.error_at(self.line, message)
+    }
+
+    fn error_at(&self, line: usize, message: impl Into<String>) -> ParseError {
+        ParseError { line, message: message.into() }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), ParseError> {
+        self.skip_inline_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", c as char)))
+        }
+    }
+
+    fn skip_inline_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self, newlines: bool) {
+        loop {
+            match self.peek() {
+                Some(b' ' | b'\t' | b'\r') => self.pos += 1,
+                Some(b'\n') if newlines => {
+                    self.pos += 1;
+                    self.line += 1;
+                }
+                Some(b'#') => {
+                    while !matches!(self.peek(), None | Some(b'\n')) {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace_and_comments(false);
+        match self.peek() {
+            None | Some(b'\n') => Ok(()),
+            Some(c) => Err(self.error(format!("unexpected `{}` after value", c as char))),
+        }
+    }
+
+    /// Walks (and creates) the tables along `path`, descending into the last element of arrays
+    /// of tables like TOML does.
+    fn table_at<'t>(&self, mut table: &'t mut Table, path: &[String], line: usize) -> Result<&'t mut Table, ParseError> {
+        for key in path {
+            if table.get(key).is_none() {
+                table.entries.push(Entry { key: key.clone(), value: Value::Table(Table { entries: Vec::new(), line }), line });
+            }
+            table = match table.get_mut(key) {
+                Some(Value::Table(t)) => t,
+                Some(Value::Array(items)) => match items.last_mut() {
+                    Some(Value::Table(t)) => t,
+                    _ => return Err(self.error_at(line, format!("`{key}` is not a table"))),
+                },
+                _ => return Err(self.error_at(line, format!("`{key}` is not a table"))),
+            };
+        }
+        Ok(table)
+    }
+
+    fn key_path(&mut self) -> Result<Vec<String>, ParseError> {
+        let mut path = vec![self.key()?];
+        loop {
+            self.skip_inline_whitespace();
+            if self.peek() != Some(b'.') {
+                break;
+            }
+            self.pos += 1;
+            path.push(self.key()?);
+        }
+        Ok(path)
+    }
+
+    fn key(&mut self) -> Result<String, ParseError> {
+        self.skip_inline_whitespace();
+        match self.peek() {
+            Some(b'"') => self.string(),
+            Some(b'\'') => self.literal(),
+            _ => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == b'_' || c == b'-') {
+                    self.pos += 1;
+                }
+                if start == self.pos {
+                    return Err(self.error("expected a key"));
+                }
+                Ok(String::from_utf8_lossy(&self.src[start..self.pos]).into_owned())
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        match self.peek() {
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'\'') => self.literal().map(Value::String),
+            Some(b'[') => self.array(),
+            Some(b'{') => self.inline_table(),
+            Some(b't') if self.src[self.pos..].starts_with(b"true") => {
+                self.pos += 4;
+                Ok(Value::Boolean(true))
+            }
+            Some(b'f') if self.src[self.pos..].starts_with(b"false") => {
+                self.pos += 5;
+                Ok(Value::Boolean(false))
+            }
+            Some(c) if c.is_ascii_digit() || c == b'-' || c == b'+' => self.number(),
+            Some(b'i' | b'n') if self.src[self.pos..].starts_with(b"inf") || self.src[self.pos..].starts_with(b"nan") => self.number(),
+            _ => Err(self.error("expected a value (string, number, boolean, array or table)")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        if self.src[self.pos..].starts_with(b"\"\"\"") {
+            return self.multiline(b'"');
+        }
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let c = self.escape()?;
+                    out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    out.push(c);
+                }
+            }
+        }
+        String::from_utf8(out).map_err(|_| self.error("string is not valid utf-8"))
+    }
+
+    /// A `'literal'` string, which has no escapes, so Windows paths and regexes can be written
+    /// as they are.
+    fn literal(&mut self) -> Result<String, ParseError> {
+        if self.src[self.pos..].starts_with(b"'''") {
+            return self.multiline(b'\'');
+        }
+        self.pos += 1;
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => return Err(self.error("unterminated string")),
+                Some(b'\'') => break,
+                Some(_) => self.pos += 1,
+            }
+        }
+        let out = self.src[start..self.pos].to_vec();
+        self.pos += 1;
+        String::from_utf8(out).map_err(|_| self.error("string is not valid utf-8"))
+    }
+
+    /// A `"""` or `'''` string, which may span lines. Only the first has escapes.
+    fn multiline(&mut self, quote: u8) -> Result<String, ParseError> {
+        let line = self.line;
+        self.pos += 3;
+        // A newline right after the opening quotes isn't part of the string
+        if self.src[self.pos..].starts_with(b"\r\n") {
+            self.pos += 1;
+        }
+        if self.peek() == Some(b'\n') {
+            self.pos += 1;
+            self.line += 1;
+        }
+        let mut out = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error_at(line, "unterminated string")),
+                Some(q) if q == quote && self.src[self.pos..].starts_with(&[quote; 3]) => {
+                    // Up to two quotes right before the closing ones are part of the string
+                    let extra = self.src[self.pos + 3..].iter().take(2).take_while(|&&c| c == quote).count();
+                    out.extend(std::iter::repeat_n(quote, extra));
+                    self.pos += 3 + extra;
+                    break;
+                }
+                Some(b'\\') if quote == b'"' => {
+                    self.pos += 1;
+                    let rest = &self.src[self.pos..];
+                    let blank = rest.iter().take_while(|c| matches!(c, b' ' | b'\t' | b'\r')).count();
+                    if rest.get(blank) == Some(&b'\n') {
+                        // A backslash ending a line joins it to the next, leaving out the indent
+                        while let Some(c @ (b' ' | b'\t' | b'\r' | b'\n')) = self.peek() {
+                            self.line += (c == b'\n') as usize;
+                            self.pos += 1;
+                        }
+                    } else {
+                        let c = self.escape()?;
+                        out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                    }
+                }
+                Some(c) => {
+                    self.line += (c == b'\n') as usize;
+                    self.pos += 1;
+                    out.push(c);
+                }
+            }
+        }
+        String::from_utf8(out).map_err(|_| self.error("string is not valid utf-8"))
+    }
+
+    /// The character an escape in a basic string stands for, just after its backslash.
+    fn escape(&mut self) -> Result<char, ParseError> {
+        let c = match self.peek() {
+            Some(b'n') => '\n',
+            Some(b't') => '\t',
+            Some(b'r') => '\r',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(u @ (b'u' | b'U')) => {
+                let len = if u == b'u' { 4 } else { 8 };
+                let hex = self.src.get(self.pos + 1..self.pos + 1 + len).ok_or_else(|| self.error("invalid unicode escape"))?;
+                let code = u32::from_str_radix(&String::from_utf8_lossy(hex), 16).map_err(|_| self.error("invalid unicode escape"))?;
+                self.pos += len;
+                char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))?
+            }
+            _ => return Err(self.error("invalid escape sequence")),
+        };
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || matches!(c, b'-' | b'+' | b'.' | b'_')) {
+            self.pos += 1;
+        }
+        let text = String::from_utf8_lossy(&self.src[start..self.pos]).replace('_', "");
+        match text.trim_start_matches(['+', '-']) {
+            "inf" if text.starts_with('-') => return Ok(Value::Float(f64::NEG_INFINITY)),
+            "inf" => return Ok(Value::Float(f64::INFINITY)),
+            "nan" => return Ok(Value::Float(f64::NAN)),
+            // Rust reads these too, TOML doesn't
+            rest if rest.eq_ignore_ascii_case("inf") || rest.eq_ignore_ascii_case("nan") || rest.eq_ignore_ascii_case("infinity") => {
+                return Err(self.error(format!("invalid number `{text}`, write `inf` or `nan`")));
+            }
+            _ => {}
+        }
+        if let Ok(i) = text.parse::<i64>() {
+            Ok(Value::Integer(i))
+        } else if let Ok(f) = text.parse::<f64>() {
+            Ok(Value::Float(f))
+        } else {
+            Err(self.error(format!("invalid number `{text}`")))
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, ParseError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace_and_comments(true);
+            if self.peek() == Some(b']') {
+                self.pos += 1;
+                break;
+            }
+            items.push(self.value()?);
+            self.skip_whitespace_and_comments(true);
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {}
+                _ => return Err(self.error("expected `,` or `]` in array")),
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn inline_table(&mut self) -> Result<Value, ParseError> {
+        self.pos += 1;
+        let mut table = Table { entries: Vec::new(), line: self.line };
+        loop {
+            self.skip_inline_whitespace();
+            if self.peek() == Some(b'}') {
+                self.pos += 1;
+                break;
+            }
+            let line = self.line;
+            let key = self.key()?;
+            self.expect(b'=')?;
+            self.skip_inline_whitespace();
+            let value = self.value()?;
+            table.entries.push(Entry { key, value, line });
+            self.skip_inline_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {}
+                _ => return Err(self.error("expected `,` or `}` in inline table")),
+            }
+        }
+        Ok(Value::Table(table))
+    }
+}
+
+/// Serializes a table back to TOML. Scalars and arrays of scalars come first, then sub-tables and
+/// arrays of tables, so the output always round-trips through [`parse`].
+pub fn to_string(table: &Table) -> String {
+    let mut out = String::new();
+    write_table(&mut out, table, &mut Vec::new());
+    out
+}
+
+fn is_table_array(v: &Value) -> bool {
+    matches!(v, Value::Array(items) if !items.is_empty() && items.iter().all(|i| matches!(i, Value::Table(_))))
+}
+
+fn write_table(out: &mut String, table: &Table, path: &mut Vec<String>) {
+    for e in &table.entries {
+        if !matches!(e.value, Value::Table(_)) && !is_table_array(&e.value) {
+            let _ = writeln!(out, "{} = {}", write_key(&e.key), inline(&e.value));
+        }
+    }
+
+    for e in &table.entries {
+        path.push(e.key.clone());
+        let header = path.iter().map(|k| write_key(k)).collect::<Vec<_>>().join(".");
+        match &e.value {
+            Value::Table(t) => {
+                let _ = writeln!(out, "\n[{header}]");
+                write_table(out, t, path);
+            }
+            Value::Array(items) if is_table_array(&e.value) => {
+                for item in items {
+                    if let Value::Table(t) = item {
+                        let _ = writeln!(out, "\n[[{header}]]");
+                        write_table(out, t, path);
+                    }
+                }
+            }
+            _ => {}
+        }
+        path.pop();
+    }
+}
+
+fn write_key(key: &str) -> String {
+    if !key.is_empty() && key.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'-') {
+        key.to_string()
+    } else {
+        quote(key)
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn inline(v: &Value) -> String {
+    match v {
+        Value::String(s) => quote(s),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) if f.is_nan() => "nan".to_string(),
+        Value::Float(f) if f.is_infinite() => if *f > 0.0 { "inf" } else { "-inf" }.to_string(),
+        Value::Float(f) if f.fract() == 0.0 => format!("{f:.1}"),
+        Value::Float(f) => f.to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Array(items) => format!("[{}]", items.iter().map(inline).collect::<Vec<_>>().join(", ")),
+        Value::Table(t) => format!(
+            "{{ {} }}",
+            t.entries.iter().map(|e| format!("{} = {}", write_key(&e.key), inline(&e.value))).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, to_string, Table, Value};
+
+    /// `table` with every line number set to 0, to compare what was parsed from different text.
+    fn without_lines(table: &Table) -> Table {
+        fn value(v: &Value) -> Value {
+            match v {
+                Value::Table(t) => Value::Table(without_lines(t)),
+                Value::Array(items) => Value::Array(items.iter().map(value).collect()),
+                v => v.clone(),
+            }
+        }
+        let mut out = Table::new();
+        for e in &table.entries {
+            out.insert(e.key.clone(), value(&e.value));
+        }
+        out
+    }
+
+    #[test]
+    fn what_is_written_reads_back_the_same() {
+        let src = r#"
+# A comment, and one after a value
+version = 2 # the schema
+"quoted key" = "yes"
+ratio = 0.5
+whole = 3.0
+negative = -12
+flags = [true, false]
+nested = [[1, 2], [], ["a"]]
+long = [
+    "one", # the first
+    "two",
+]
+
+[ui]
+layout = "numpad"
+[ui.colors]
+tile = "blue"
+
+[[sound]]
+name = "horn"
+dmx = [{ channel = 1, value = 255 }, { channel = 2, value = 0 }]
+[sound.shape]
+speed = 1.25
+
+[[sound]]
+name = "bell"
+"#;
+        let table = parse(src).unwrap();
+        let written = to_string(&table);
+        let again = parse(&written).unwrap();
+        assert_eq!(without_lines(&again), without_lines(&table));
+        assert_eq!(to_string(&again), written);
+
+        let Some(Value::Array(sounds)) = table.get("sound") else { panic!("no sounds in {table:?}") };
+        let Value::Table(horn) = &sounds[0] else { panic!() };
+        assert!(matches!(horn.get("shape"), Some(Value::Table(shape)) if shape.get("speed") == Some(&Value::Float(1.25))));
+        assert_eq!(sounds.len(), 2);
+        assert_eq!(table.get("whole"), Some(&Value::Float(3.0)));
+    }
+
+    #[test]
+    fn strings_are_escaped_both_ways() {
+        let table = parse(r#"s = "a \"quote\", a \\ and\ttabs\nand é""#).unwrap();
+        assert_eq!(table.get("s"), Some(&Value::String("a \"quote\", a \\ and\ttabs\nand é".to_string())));
+
+        let mut table = Table::new();
+        table.insert("odd key.with dots", "line\nbreak \u{1} \"quoted\" \\");
+        let again = parse(&to_string(&table)).unwrap();
+        assert_eq!(without_lines(&again), table);
+
+        assert!(parse(r#"s = "\q""#).unwrap_err().message.contains("invalid escape"));
+        assert!(parse(r#"s = "\u00""#).unwrap_err().message.contains("invalid unicode escape"));
+    }
+
+    #[test]
+    fn inline_tables_and_arrays_of_tables() {
+        let table = parse("point = { x = 1, y = -2.5, name = \"origin\" }\n[[a]]\nn = 1\n[[a]]\nn = 2\n[[a.b]]\nm = 3\n").unwrap();
+        let Some(Value::Table(point)) = table.get("point") else { panic!() };
+        assert_eq!((point.get("x"), point.get("y")), (Some(&Value::Integer(1)), Some(&Value::Float(-2.5))));
+
+        // A header below an array of tables goes into its last table
+        let Some(Value::Array(a)) = table.get("a") else { panic!() };
+        let Value::Table(second) = &a[1] else { panic!() };
+        assert!(matches!(second.get("b"), Some(Value::Array(b)) if b.len() == 1));
+        let Value::Table(first) = &a[0] else { panic!() };
+        assert_eq!(first.get("b"), None);
+
+        assert!(parse("p = { x = 1 y = 2 }").unwrap_err().message.contains("inline table"));
+    }
+
+    #[test]
+    fn errors_and_keys_know_their_line() {
+        let table = parse("# comment\n\na = 1\n[t]\nb = [\n  1,\n  2,\n]\nc = true\n").unwrap();
+        assert_eq!(table.entry("a").map(|e| e.line), Some(3));
+        let Some(Value::Table(t)) = table.get("t") else { panic!() };
+        assert_eq!((t.line, t.entry("b").map(|e| e.line), t.entry("c").map(|e| e.line)), (4, Some(5), Some(9)));
+
+        let line = |src: &str| parse(src).unwrap_err().line;
+        assert_eq!(line("a = 1\nb = 2\na = 3\n"), 3);
+        assert_eq!(line("a = 1\n\ns = \"open\n"), 3);
+        assert_eq!(line("a = [\n  1,\n  x,\n]\n"), 3);
+        assert_eq!(line("a = 1\n[a]\n"), 2);
+        assert_eq!(line("a = 1 2\n"), 1);
+        assert!(parse("a = 1\n[[a]]\n").unwrap_err().message.contains("not an array of tables"));
+    }
+    #[test]
+    fn literal_and_multiline_strings() {
+        let src = r#"path = 'C:\Users\me\horn.wav'
+'literal key' = 'say "hi"'
+poem = """
+Roses are red,
+  "violets" are \"blue\"\t""""
+joined = """one \
+         two"""
+raw = '''
+no \escapes ''here'''''
+empty = ''
+after = 1
+"#;
+        let table = parse(src).unwrap();
+        let get = |key| table.get(key).and_then(|v| if let Value::String(s) = v { Some(s.as_str()) } else { None });
+        assert_eq!(get("path"), Some(r"C:\Users\me\horn.wav"));
+        assert_eq!(get("literal key"), Some(r#"say "hi""#));
+        assert_eq!(get("poem"), Some("Roses are red,\n  \"violets\" are \"blue\"\t\""));
+        assert_eq!(get("joined"), Some("one two"));
+        assert_eq!(get("raw"), Some(r"no \escapes ''here''"));
+        assert_eq!(get("empty"), Some(""));
+        // Lines inside the strings still count
+        assert_eq!(table.entry("after").map(|e| e.line), Some(11));
+
+        // Written back as basic strings, which read back the same
+        let again = parse(&to_string(&table)).unwrap();
+        assert_eq!(without_lines(&again), without_lines(&table));
+
+        assert!(parse("s = 'open\n'").unwrap_err().message.contains("unterminated"));
+        assert_eq!(parse("a = 1\ns = \"\"\"open\n\n").unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn infinities_and_nan_round_trip() {
+        let table = parse("a = inf\nb = -inf\nc = nan\nd = +inf\n").unwrap();
+        assert_eq!((table.get("a"), table.get("b"), table.get("d")), (Some(&Value::Float(f64::INFINITY)), Some(&Value::Float(f64::NEG_INFINITY)), Some(&Value::Float(f64::INFINITY))));
+        assert!(matches!(table.get("c"), Some(Value::Float(f)) if f.is_nan()));
+
+        let mut table = Table::new();
+        table.insert("volume", Value::Float(f64::NAN));
+        table.insert("gain", Value::Float(f64::NEG_INFINITY));
+        let written = to_string(&table);
+        assert_eq!(written, "volume = nan\ngain = -inf\n");
+        let again = parse(&written).unwrap();
+        assert!(matches!(again.get("volume"), Some(Value::Float(f)) if f.is_nan()));
+        assert_eq!(again.get("gain"), Some(&Value::Float(f64::NEG_INFINITY)));
+
+        for rust_only in ["NaN", "infinity", "-Inf"] {
+            assert!(parse(&format!("a = {rust_only}\n")).is_err(), "{rust_only}");
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::io::{self, stdout, Stdout};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::time::Duration;
+use color_eyre::eyre::{bail, Context};
+use crossterm::ExecutableCommand;
+use crossterm::event::{self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::{Terminal, TerminalOptions, Viewport};
+use crate::input::Caps;
+
+pub type Term = Terminal<CrosstermBackend<Stdout>>;
+
+/// Where the board gets its input from, so it can be driven by something other than the terminal.
+pub trait Events {
+    /// The next event, waiting at most `timeout` for one.
+    fn next(&mut self, timeout: Duration) -> io::Result<Option<event::Event>>;
+}
+
+/// What is typed and clicked in the terminal.
+pub struct TerminalEvents;
+
+impl Events for TerminalEvents {
+    fn next(&mut self, timeout: Duration) -> io::Result<Option<event::Event>> {
+        if event::poll(timeout)? {
+            event::read().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Whether we captured the mouse, so leaving and re-entering the TUI can restore that.
+static MOUSE_CAPTURED: AtomicBool = AtomicBool::new(false);
+/// Whether we pushed kitty keyboard flags, which have to be popped again on exit.
+static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
+/// How many rows the board takes at the bottom of the terminal with `--inline`, drawn where the
+/// shell was rather than on a screen of its own; 0 for all of the terminal.
+static INLINE: AtomicU16 = AtomicU16::new(0);
+/// Whether we have the terminal, so it is only given back once, and not from the background
+/// where touching the terminal would stop the process.
+static ENTERED: AtomicBool = AtomicBool::new(false);
+
+fn keyboard_flags() -> KeyboardEnhancementFlags {
+    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+}
+
+/// Takes over the terminal. If mouse capture is wanted but the terminal refuses it, the board
+/// still runs keyboard-only and `caps.mouse` is cleared. Terminals that support the kitty
+/// keyboard protocol are switched to it, and `caps` updated accordingly. With `inline` rows the
+/// board is drawn below what is on screen already, like in a tmux pane that has other things
+/// in it too, instead of taking over all of it.
+pub fn enter(caps: &mut Caps, inline: Option<u16>) -> color_eyre::Result<Term> {
+    INLINE.store(inline.unwrap_or(0), Ordering::Relaxed);
+    enable_raw_mode()?;
+    if inline.is_none() {
+        stdout().execute(EnterAlternateScreen)?;
+    }
+    // Pasting a path plays it like ^V does; terminals that don't do this just ignore it
+    stdout().execute(EnableBracketedPaste)?;
+    if caps.mouse {
+        caps.mouse = stdout().execute(EnableMouseCapture).is_ok();
+    }
+    MOUSE_CAPTURED.store(caps.mouse, Ordering::Relaxed);
+
+    // The query times out on terminals that do not answer it, which just means no support
+    if supports_keyboard_enhancement().unwrap_or(false) && stdout().execute(PushKeyboardEnhancementFlags(keyboard_flags())).is_ok() {
+        caps.kitty = true;
+        caps.key_release = true;
+    }
+    KEYBOARD_ENHANCED.store(caps.kitty, Ordering::Relaxed);
+    ENTERED.store(true, Ordering::Relaxed);
+
+    terminal()
+}
+
+/// A terminal drawing all of the screen, or the rows at the cursor when inline.
+fn terminal() -> color_eyre::Result<Term> {
+    let backend = CrosstermBackend::new(stdout());
+    Ok(match INLINE.load(Ordering::Relaxed) {
+        0 => Terminal::new(backend)?,
+        rows => Terminal::with_options(backend, TerminalOptions { viewport: Viewport::Inline(rows) })?,
+    })
+}
+
+/// Gives the terminal back for good, once the board is closed. Inline, the board is wiped so
+/// the shell carries on where it was drawn.
+pub fn close(terminal: &mut Term) -> color_eyre::Result<()> {
+    if INLINE.load(Ordering::Relaxed) > 0 && ENTERED.load(Ordering::Relaxed) {
+        terminal.clear()?;
+    }
+    exit()
+}
+
+pub fn exit() -> color_eyre::Result<()> {
+    if !ENTERED.swap(false, Ordering::Relaxed) {
+        return Ok(());
+    }
+    if KEYBOARD_ENHANCED.load(Ordering::Relaxed) {
+        stdout().execute(PopKeyboardEnhancementFlags)?;
+    }
+    disable_raw_mode()?;
+    stdout().execute(DisableBracketedPaste)?;
+    if MOUSE_CAPTURED.load(Ordering::Relaxed) {
+        stdout().execute(DisableMouseCapture)?;
+    }
+    if INLINE.load(Ordering::Relaxed) == 0 {
+        stdout().execute(LeaveAlternateScreen)?;
+    }
+    Ok(())
+}
+
+/// Re-enters the TUI after [`exit`], with the same mouse capture and keyboard mode as before.
+/// Inline, the board is drawn anew at the cursor, below what was printed meanwhile.
+pub fn resume(terminal: &mut Term) -> color_eyre::Result<()> {
+    ENTERED.store(true, Ordering::Relaxed);
+    enable_raw_mode()?;
+    if INLINE.load(Ordering::Relaxed) == 0 {
+        stdout().execute(EnterAlternateScreen)?;
+    }
+    stdout().execute(EnableBracketedPaste)?;
+    if MOUSE_CAPTURED.load(Ordering::Relaxed) {
+        stdout().execute(EnableMouseCapture)?;
+    }
+    if KEYBOARD_ENHANCED.load(Ordering::Relaxed) {
+        stdout().execute(PushKeyboardEnhancementFlags(keyboard_flags()))?;
+    }
+    if INLINE.load(Ordering::Relaxed) == 0 {
+        terminal.clear()?;
+    } else {
+        *terminal = self::terminal()?;
+    }
+    Ok(())
+}
+
+/// Gives the terminal back to the shell and stops, as ^Z does to programs that aren't in raw
+/// mode. Returns once the process is continued, by `fg` or `bg`.
+#[cfg(unix)]
+pub fn suspend(terminal: &mut Term) -> color_eyre::Result<()> {
+    close(terminal)?;
+    signal_hook::low_level::raise(signal_hook::consts::SIGSTOP).wrap_err("suspend")
+}
+
+/// Whether the terminal is ours, rather than the board having been put in the background with
+/// `bg`.
+#[cfg(unix)]
+pub fn in_foreground() -> bool {
+    unsafe { libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp() }
+}
+
+/// Installs color-eyre, restoring the terminal before a panic report is printed so it is readable.
+pub fn install_hooks() -> color_eyre::Result<()> {
+    let (panic_hook, eyre_hook) = color_eyre::config::HookBuilder::default().into_hooks();
+    eyre_hook.install()?;
+
+    let panic_hook = panic_hook.into_panic_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = exit();
+        panic_hook(info);
+    }));
+
+    Ok(())
+}
+
+/// Editors that open a file at a line when given `+line` before it. Others, like VS Code or
+/// notepad, would open a file called that instead.
+const TAKES_LINE: &[&str] = &["vi", "vim", "nvim", "gvim", "view", "nano", "pico", "emacs", "emacsclient", "micro", "kak", "joe", "mg", "ne"];
+
+/// Leaves the TUI, opens `path` in `$VISUAL`/`$EDITOR` (at `line` if given, and the editor is
+/// known to take one) and waits for the editor to exit before taking the terminal back.
+pub fn edit(terminal: &mut Term, path: &Path, line: Option<usize>) -> color_eyre::Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad".to_string() } else { "vi".to_string() });
+
+    // An editor may come with arguments, like `code --wait`
+    let mut parts = editor.split_whitespace();
+    let Some(program) = parts.next() else { bail!("$EDITOR is empty") };
+    let mut command = process::Command::new(program);
+    command.args(parts);
+    let name = Path::new(program).file_stem().and_then(|n| n.to_str()).unwrap_or(program);
+    if let Some(line) = line.filter(|_| TAKES_LINE.contains(&name)) {
+        command.arg(format!("+{line}"));
+    }
+    command.arg(path);
+
+    close(terminal)?;
+    let status = command.status().wrap_err_with(|| format!("run editor {editor:?}"));
+    resume(terminal)?;
+
+    if !status?.success() {
+        bail!("editor {editor:?} exited with an error");
+    }
+    Ok(())
+}
@@ -0,0 +1,1644 @@
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant, SystemTime};
+use ratatui::Frame;
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::{Position, Title};
+use ratatui::widgets::canvas::{Canvas, Points};
+use ratatui::widgets::{Block, BorderType, Borders, Cell, Clear, List, ListItem, ListState, Padding, Paragraph, Row, Sparkline, Table, TableState};
+use crossterm::event::KeyCode;
+use taffy::{AvailableSpace, Dimension, Display, GridPlacement, LengthPercentage, MaxTrackSizingFunction, MinMax, MinTrackSizingFunction, NodeId, PrintTree, Size, TaffyTree, TrackSizingFunction, TraversePartialTree};
+use taffy::GridTrackRepetition::{AutoFit, Count};
+use crate::animation;
+use crate::app::{App, View, MUTE_KEY};
+use crate::assign::Strategy;
+use crate::backup::Backup;
+use crate::binding::{self, PadInput, Trigger};
+use crate::branding::Art;
+use crate::config::{Board, ConfigError, LongNames, Source, TileLayout};
+use crate::conflict::Owner;
+use crate::edit::Field;
+use crate::history;
+use crate::hit::Target;
+use crate::loader::State;
+use crate::locale::Locale;
+use crate::palette::Styles;
+use crate::scope::{self, Mode};
+use crate::search::Site;
+use crate::settings::{Tab, Value};
+use crate::text;
+use crate::timer::{self, Timers};
+use crate::vars::{self, Vars};
+
+/// How long the terminal size has to be stable before the grid reflows.
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(80);
+/// How long tiles take to slide to their new place after a reflow.
+const REFLOW_DURATION: Duration = Duration::from_millis(180);
+/// How many rows the scope takes, borders included.
+const SCOPE_HEIGHT: u16 = 8;
+/// How long the waveform shows, which is about two periods of a low voice.
+const WAVEFORM_TIME: f32 = 0.02;
+/// How many places the crossfader is drawn with in the status bar.
+const CROSSFADER_WIDTH: usize = 9;
+/// How long a tile's volume stays highlighted after changing it.
+const VOLUME_HIGHLIGHT: Duration = Duration::from_millis(1500);
+/// Where each key is on a numeric keypad: its grid row and column (from 1, with Num Lock in the
+/// top left corner) and how many rows and columns it covers.
+const NUMPAD: &[(char, i16, i16, u16, u16)] = &[
+    ('/', 1, 2, 1, 1), ('*', 1, 3, 1, 1), ('-', 1, 4, 1, 1),
+    ('7', 2, 1, 1, 1), ('8', 2, 2, 1, 1), ('9', 2, 3, 1, 1), ('+', 2, 4, 2, 1),
+    ('4', 3, 1, 1, 1), ('5', 3, 2, 1, 1), ('6', 3, 3, 1, 1),
+    ('1', 4, 1, 1, 1), ('2', 4, 2, 1, 1), ('3', 4, 3, 1, 1),
+    ('0', 5, 1, 1, 2), ('.', 5, 3, 1, 1),
+];
+const TILE_HEIGHT: f32 = 5.0;
+/// How many frames a second are drawn while something moves, unless `[ui] fps` says otherwise.
+pub const DEFAULT_FPS: u32 = 60;
+/// How long to wait for input when nothing moves, before catching up on what happened in the
+/// background.
+const IDLE_POLL: Duration = Duration::from_millis(50);
+/// How long a scrolling name shows its start before it moves, and how long it takes a column.
+const MARQUEE_PAUSE: Duration = Duration::from_secs(1);
+const MARQUEE_STEP: Duration = Duration::from_millis(150);
+
+pub struct Grid {
+    tree: TaffyTree,
+    root: NodeId,
+    /// Leaf node to index into [`Board::sounds`]
+    mapping: HashMap<NodeId, usize>,
+    last_computed_size: Option<Rect>,
+    /// A size we are resizing to, and since when, while waiting for resizing to settle
+    pending_size: Option<(Rect, Instant)>,
+    /// Where tiles were drawn before the last reflow, to animate from
+    reflow_from: HashMap<NodeId, Rect>,
+    reflow_start: Option<Instant>,
+    /// Rows of the grid scrolled out of view at the top
+    scroll: u16,
+    /// The tile whose name is scrolling by, and since when
+    marquee: Option<(usize, Instant)>,
+}
+
+impl Grid {
+    pub fn new(board: &Board) -> Self {
+        let mut tree: TaffyTree<()> = TaffyTree::new();
+        let mut mapping = HashMap::new();
+
+        let numpad = board.layout == TileLayout::Numpad;
+        let mut taken = Vec::new();
+        let mut children = Vec::new();
+        for (idx, sound) in board.sounds.iter().enumerate() {
+            let mut style = taffy::Style {
+                size: Size { width: Dimension::Auto, height: Dimension::Length(TILE_HEIGHT) },
+                display: Display::Block,
+                ..Default::default()
+            };
+            if numpad {
+                // The first sound on a keypad key gets its place, everything else goes below the
+                // keypad, four to a row
+                let key = sound.bindings.iter().find_map(|b| match b.trigger {
+                    Trigger::Key(chord) if chord.modifiers.is_empty() => NUMPAD.iter().find(|n| chord.code == KeyCode::Char(n.0)),
+                    _ => None,
+                });
+                let (row, column, rows, columns) = match key.filter(|n| !taken.contains(&n.0)) {
+                    Some(&(key, row, column, rows, columns)) => {
+                        taken.push(key);
+                        (row, column, rows, columns)
+                    }
+                    None => {
+                        let n = (idx - taken.len()) as i16;
+                        (6 + n / 4, 1 + n % 4, 1, 1)
+                    }
+                };
+                // Stretched over the rows it covers instead
+                style.size.height = Dimension::Auto;
+                style.grid_row = taffy::Line { start: GridPlacement::Line(row.into()), end: GridPlacement::Span(rows) };
+                style.grid_column = taffy::Line { start: GridPlacement::Line(column.into()), end: GridPlacement::Span(columns) };
+            }
+            let id = tree.new_leaf(style).unwrap();
+
+            mapping.insert(id, idx);
+            children.push(id);
+        }
+
+        let tile_width = MinMax {
+            min: MinTrackSizingFunction::Fixed(LengthPercentage::Length(10.0)),
+            max: MaxTrackSizingFunction::Fixed(LengthPercentage::Length(40.0)),
+        };
+        let row_height = MinMax {
+            min: MinTrackSizingFunction::Fixed(LengthPercentage::Length(TILE_HEIGHT)),
+            max: MaxTrackSizingFunction::Fixed(LengthPercentage::Length(TILE_HEIGHT)),
+        };
+        // Root node
+        let root = tree.new_with_children(
+            taffy::Style {
+                size: Size { width: Dimension::Percent(1.0), height: Dimension::Percent(1.0) },
+                grid_template_columns: vec![TrackSizingFunction::Repeat(if numpad { Count(4) } else { AutoFit }, vec![tile_width])],
+                grid_auto_rows: if numpad { vec![row_height] } else { Vec::new() },
+                display: Display::Grid,
+                ..Default::default()
+            },
+            &children,
+        ).unwrap();
+
+        Self {
+            tree,
+            root,
+            mapping,
+            last_computed_size: None,
+            pending_size: None,
+            reflow_from: HashMap::new(),
+            reflow_start: None,
+            scroll: 0,
+            marquee: None,
+        }
+    }
+
+    fn compute(&mut self, area: Rect, now: Instant) {
+        let Some(last) = self.last_computed_size else {
+            self.compute_layout(area);
+            return;
+        };
+        if last == area {
+            self.pending_size = None;
+            return;
+        }
+
+        // Recalculate layout once the frame has stopped changing size for a moment, until then
+        // the old layout is drawn clipped to the new size
+        match self.pending_size {
+            Some((size, since)) if size == area => {
+                if now.duration_since(since) >= RESIZE_DEBOUNCE {
+                    self.reflow_from = self.tree.child_ids(self.root).map(|id| (id, self.content_rect(id, now))).collect();
+                    self.reflow_start = Some(now);
+                    self.pending_size = None;
+                    self.compute_layout(area);
+                    self.scroll_by(0);
+                }
+            }
+            _ => self.pending_size = Some((area, now)),
+        }
+    }
+
+    /// Lays the tiles out in `area` right away, without waiting for resizing to settle.
+    pub fn compute_layout(&mut self, area: Rect) {
+        let viewport_size = Size {
+            width: AvailableSpace::Definite(area.width as f32),
+            height: AvailableSpace::Definite(area.height as f32),
+        };
+        self.tree.compute_layout(self.root, viewport_size).unwrap();
+
+        self.last_computed_size = Some(area);
+    }
+
+    /// Whether tiles are still moving, so the caller keeps redrawing.
+    pub fn animating(&self, now: Instant) -> bool {
+        self.pending_size.is_some() || self.reflow_start.is_some_and(|start| now.duration_since(start) < REFLOW_DURATION)
+    }
+
+    /// The sound whose tile is nearest straight above (or below) sound `idx`'s, for moving the
+    /// selection up and down.
+    pub fn vertical_neighbour(&self, idx: usize, down: bool) -> Option<usize> {
+        let (&from, _) = self.mapping.iter().find(|(_, &i)| i == idx)?;
+        let from = self.tree.get_final_layout(from);
+        let center = from.location.x + from.size.width / 2.0;
+        self.mapping
+            .iter()
+            .filter_map(|(&id, &i)| {
+                let layout = self.tree.get_final_layout(id);
+                let beside = layout.location.x <= center && center < layout.location.x + layout.size.width;
+                let distance = if down {
+                    layout.location.y - (from.location.y + from.size.height)
+                } else {
+                    from.location.y - (layout.location.y + layout.size.height)
+                };
+                (beside && distance >= 0.0).then_some((distance, i))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, i)| i)
+    }
+
+    /// Where a tile goes in the unscrolled grid, part way between its old and new place while
+    /// reflowing.
+    fn content_rect(&self, node_id: NodeId, now: Instant) -> Rect {
+        let layout = self.tree.get_final_layout(node_id);
+        let target = Rect::new(
+            layout.location.x as u16,
+            layout.location.y as u16,
+            layout.size.width as u16,
+            layout.size.height as u16,
+        );
+
+        let progress = self.reflow_start.map_or(1.0, |start| now.duration_since(start).as_secs_f32() / REFLOW_DURATION.as_secs_f32());
+        match self.reflow_from.get(&node_id) {
+            Some(from) if progress < 1.0 => {
+                let t = animation::ease_out(progress);
+                let lerp = |a: u16, b: u16| (a as f32 + (b as f32 - a as f32) * t).round() as u16;
+                Rect::new(lerp(from.x, target.x), lerp(from.y, target.y), lerp(from.width, target.width), lerp(from.height, target.height))
+            }
+            _ => target,
+        }
+    }
+
+    /// Where to draw a tile on screen, taking scrolling into account and clipped to `area`.
+    fn rect(&self, node_id: NodeId, area: Rect, now: Instant) -> Rect {
+        let r = self.content_rect(node_id, now);
+        let top = area.y as i32 + r.y as i32 - self.scroll as i32;
+        let bottom = (top + r.height as i32).min(area.bottom() as i32);
+        let top = top.max(area.y as i32);
+        if bottom <= top {
+            return Rect::default();
+        }
+        Rect::new(area.x + r.x, top as u16, r.width, (bottom - top) as u16).intersection(area)
+    }
+
+    fn viewport_height(&self) -> u16 {
+        self.last_computed_size.map_or(0, |r| r.height)
+    }
+
+    fn content_height(&self) -> u16 {
+        self.tree
+            .child_ids(self.root)
+            .map(|id| {
+                let layout = self.tree.get_final_layout(id);
+                (layout.location.y + layout.size.height) as u16
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn scroll_by(&mut self, delta: i32) {
+        let max = self.content_height().saturating_sub(self.viewport_height());
+        self.scroll = (self.scroll as i32 + delta).clamp(0, max as i32) as u16;
+    }
+
+    /// Scrolls just enough to show the tile of sound `idx` completely.
+    pub fn scroll_to(&mut self, idx: usize) {
+        let Some((&node_id, _)) = self.mapping.iter().find(|(_, i)| **i == idx) else { return };
+        let layout = self.tree.get_final_layout(node_id);
+        let (top, bottom) = (layout.location.y as u16, (layout.location.y + layout.size.height) as u16);
+        if top < self.scroll {
+            self.scroll = top;
+        } else if bottom > self.scroll + self.viewport_height() {
+            self.scroll = bottom.saturating_sub(self.viewport_height());
+        }
+    }
+}
+
+/// When the board needs drawing again. Nothing is drawn while nothing changes; while something
+/// on screen moves on its own, like a tile sliding into place or the recording clock, frames are
+/// drawn at the board's frame rate, and once more after it stopped.
+#[derive(Debug, Default)]
+pub struct Redraw {
+    /// Something on screen changed since the last frame
+    pub dirty: bool,
+    last: Option<Instant>,
+    moving: bool,
+}
+
+impl Redraw {
+    /// Whether to draw a frame at `now`, taking it as drawn if so.
+    pub fn due(&mut self, app_moving: bool, interval: Duration, now: Instant) -> bool {
+        let due = match self.last {
+            None => true,
+            Some(last) => self.dirty || (self.moving && !app_moving) || (app_moving && now.duration_since(last) >= interval),
+        };
+        self.moving = app_moving;
+        if due {
+            self.dirty = false;
+            self.last = Some(now);
+        }
+        due
+    }
+
+    /// How long to wait for input before the next frame is due.
+    pub fn timeout(&self, interval: Duration, now: Instant) -> Duration {
+        match self.last {
+            Some(last) if self.moving => interval.saturating_sub(now.duration_since(last)),
+            _ => IDLE_POLL,
+        }
+    }
+}
+
+/// How long a frame takes at the board's frame rate.
+pub fn frame_interval(app: &App) -> Duration {
+    Duration::from_secs(1) / app.board.fps.unwrap_or(DEFAULT_FPS).max(1)
+}
+
+/// Whether anything on screen changes without anything happening, so it has to be drawn every
+/// frame.
+pub fn moving(app: &App, now: Instant) -> bool {
+    // A screen reader would read out every change, so nothing moves by itself
+    if app.reading() {
+        return false;
+    }
+    let highlighted = app.volume_changed.is_some_and(|(_, at)| now.duration_since(at) < VOLUME_HIGHLIGHT);
+    app.grid.animating(now) || app.grid.marquee.is_some() || app.animations.active(now) || !app.playing.is_empty() || highlighted || app.talkover.is_some() || app.engine.recorder().is_some() || app.scope.is_some()
+}
+
+/// Draws the board as it is at `now`, which is where tiles are while they move.
+pub fn draw(frame: &mut Frame, app: &mut App, now: Instant) {
+    app.hits.clear();
+    if let Some((art, _)) = &app.splash {
+        draw_art(frame, art, frame.size(), app.styles().accent);
+        return;
+    }
+    let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.size());
+    let main = match &app.header {
+        // Only with room for the board left, and not read out
+        Some(art) if main.height >= art.height() + 2 * TILE_HEIGHT as u16 && !app.reading() => {
+            let [header, rest] = Layout::vertical([Constraint::Length(art.height()), Constraint::Min(0)]).areas(main);
+            draw_art(frame, art, header, app.styles().accent);
+            rest
+        }
+        _ => main,
+    };
+    let main = match app.scope {
+        Some(mode) if main.height >= SCOPE_HEIGHT + 2 * TILE_HEIGHT as u16 && !app.reading() => {
+            let [rest, scope] = Layout::vertical([Constraint::Min(0), Constraint::Length(SCOPE_HEIGHT)]).areas(main);
+            draw_scope(frame, app, mode, scope, now);
+            rest
+        }
+        _ => main,
+    };
+
+    match app.view {
+        View::Cues => draw_cues(frame, app, main),
+        View::History => draw_history(frame, app, main),
+        View::Log => draw_log(frame, app, main),
+        _ if app.reading() => draw_list(frame, app, main),
+        _ => draw_board(frame, app, main, now),
+    }
+    if app.engine.muted() {
+        draw_muted(frame, app, main);
+    }
+    match app.view {
+        View::Board | View::Cues | View::History | View::Log => {}
+        View::Trash => draw_trash(frame, app, main),
+        View::Assign => draw_assign(frame, app, main),
+        View::Conflicts => draw_conflicts(frame, app, main),
+        View::Browse => draw_browser(frame, app, main),
+        View::Search => draw_search(frame, app, main),
+        View::Check => draw_check(frame, app, main),
+        View::Commands => draw_commands(frame, app, main),
+        View::Settings => draw_settings(frame, app, main),
+        View::Backups => draw_backups(frame, app, main),
+        View::Edit => draw_edit(frame, app, main),
+        View::Learn => draw_learn(frame, app, main),
+    }
+
+    draw_status(frame, app, status);
+    if app.idle {
+        draw_idle(frame, &app.styles());
+    }
+}
+
+/// Everything in the same faint style once nobody has been at the board for a while, without
+/// the colors and highlights that would otherwise burn in.
+fn draw_idle(frame: &mut Frame, styles: &Styles) {
+    let area = frame.size();
+    frame.buffer_mut().set_style(area, Style::reset().patch(styles.dim));
+}
+
+fn draw_board(frame: &mut Frame, app: &mut App, area: Rect, now: Instant) {
+    app.grid.compute(area, now);
+    let styles = app.styles();
+
+    let grid = &app.grid;
+    let mut marquee = None;
+    for child_node_id in grid.tree.child_ids(grid.root) {
+        let Some(&idx) = grid.mapping.get(&child_node_id) else { continue };
+        let sound = &app.board.sounds[idx];
+        // Click targets follow what is drawn, also mid-animation
+        let r = grid.rect(child_node_id, area, now);
+        if r.is_empty() {
+            continue;
+        }
+        let shake = app.animations.shake(idx, now);
+        let r = match (shake, area.right().checked_sub(r.width)) {
+            (Some(by), Some(last)) => Rect { x: r.x.saturating_add_signed(by).clamp(area.x, last.max(area.x)), ..r },
+            _ => r,
+        };
+
+        let mut title = match sound.shortcut_label() {
+            Some(label) => format!("[{label}]"),
+            None => String::new(),
+        };
+        if sound.shape.reversed {
+            title.push('◀');
+        }
+
+        let selected = idx == app.selected;
+        let style = match app.animations.flash(idx, now) {
+            _ if shake.is_some() => styles.error,
+            Some(flash) if flash > 0.5 => styles.flash,
+            Some(_) => styles.fading,
+            None if selected => styles.selected,
+            None => match sound.color {
+                Some(color) if styles.colors => Style::default().fg(color),
+                _ => styles.tile,
+            },
+        };
+        let mut b = Block::new()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(if selected && styles.marks { BorderType::Double } else { BorderType::Plain })
+            .style(style)
+            .padding(Padding::new(
+                0, // left
+                0, // right
+                r.height / 3, // top
+                0, // bottom
+            ));
+
+        // How many times over it is playing, when it overlaps itself
+        let instances = app.playing.values().filter(|p| sound.sources().any(|s| *s == p.source)).count();
+        if instances > 1 {
+            b = b.title(Title::from(Span::styled(format!("×{instances}"), styles.accent)).alignment(Alignment::Right));
+        }
+
+        let recently_changed = app.volume_changed.is_some_and(|(i, at)| i == idx && now.duration_since(at) < VOLUME_HIGHLIGHT);
+        if sound.volume != 1.0 || recently_changed {
+            let style = if recently_changed { styles.accent } else { Style::default() };
+            let volume = Span::styled(format!("{:.0}%", sound.volume * 100.0), style);
+            b = b.title(Title::from(volume).position(Position::Bottom).alignment(Alignment::Right));
+        }
+
+        let loading = match app.loaded.get(&sound.source) {
+            _ if app.shell.running(&sound.name) => Some(("tile-running", styles.accent)),
+            Some(State::Loading) if app.waiting.iter().any(|(s, _, _)| *s == sound.source) => Some(("tile-plays-when-loaded", styles.warning)),
+            Some(State::Loading) => Some(("tile-loading", styles.dim)),
+            Some(State::Failed(_)) => Some(("tile-cant-play", styles.error)),
+            Some(State::Ready(_) | State::Streaming(_)) | None => None,
+        };
+        if let Some((id, style)) = loading {
+            b = b.title(Title::from(Span::styled(app.locale().text(id), style)).position(Position::Bottom).alignment(Alignment::Left));
+        }
+
+        app.hits.push(r, Target::Tile(idx));
+
+        let width = r.width.saturating_sub(2) as usize;
+        let shown = vars::expand(&sound.name, &Vars::of(sound));
+        let name: Vec<Line> = match app.board.long_names {
+            _ if text::width(&shown) <= width => vec![Line::raw(shown.to_string())],
+            LongNames::Wrap => text::wrap(&shown, width, 2).into_iter().map(Line::raw).collect(),
+            LongNames::Scroll if idx == app.selected => {
+                let since = grid.marquee.filter(|&(i, _)| i == idx).map_or(now, |(_, since)| since);
+                marquee = Some((idx, since));
+                let offset = text::marquee_offset(now.saturating_duration_since(since), MARQUEE_PAUSE, MARQUEE_STEP);
+                vec![Line::raw(text::marquee(&shown, width, offset))]
+            }
+            _ => vec![Line::raw(text::truncate(&shown, width))],
+        };
+        let p = Paragraph::new(name);
+        frame.render_widget(p.block(b).alignment(Alignment::Center), r);
+
+        // How much has played, filling the tile from the left; the latest start when it plays
+        // more than once
+        let played = app.playing.values().filter(|p| sound.sources().any(|s| *s == p.source)).map(|p| p.progress()).min_by(f32::total_cmp);
+        if let Some(played) = played {
+            let inner = Rect { x: r.x + 1, y: r.y + 1, width: r.width.saturating_sub(2), height: r.height.saturating_sub(2) };
+            let filled = (inner.width as f32 * played).round() as u16;
+            frame.buffer_mut().set_style(Rect { width: filled, ..inner }, styles.played);
+        }
+
+        // The cooldown left, as a bar along the bottom border that shrinks to the left
+        if let Some(left) = app.animations.cooldown(idx, now) {
+            let inner = r.width.saturating_sub(2);
+            let filled = (inner as f32 * left).ceil() as u16;
+            let y = r.bottom() - 1;
+            for x in r.x + 1..r.x + 1 + filled {
+                let cell = frame.buffer_mut().get_mut(x, y);
+                // Only the border, not the volume or loading titles on it
+                if matches!(cell.symbol(), "─" | "═") {
+                    cell.set_symbol("━").set_style(styles.accent);
+                }
+            }
+        }
+    }
+    app.grid.marquee = marquee;
+}
+
+/// The board for screen readers: a line per sound, with the terminal's cursor on the selected
+/// one so the screen reader follows it.
+fn draw_list(frame: &mut Frame, app: &mut App, area: Rect) {
+    let (t, styles) = (app.locale(), app.styles());
+    let items: Vec<_> = app
+        .board
+        .sounds
+        .iter()
+        .map(|sound| {
+            let mut line = vec![Span::raw(vars::expand(&sound.name, &Vars::of(sound)).into_owned())];
+            if let Some(label) = sound.shortcut_label() {
+                line.push(Span::styled(format!("  [{label}]"), styles.dim));
+            }
+            if sound.volume != 1.0 {
+                line.push(Span::styled(format!("  {:.0}%", sound.volume * 100.0), styles.dim));
+            }
+            let instances = app.playing.values().filter(|p| sound.sources().any(|s| *s == p.source)).count();
+            if instances > 1 {
+                line.push(Span::styled(format!("  ×{instances}"), styles.accent));
+            }
+            if sound.shape.reversed {
+                line.push(Span::styled(format!("  {}", t.text("tile-reversed")), styles.dim));
+            }
+            let state = match app.loaded.get(&sound.source) {
+                _ if app.shell.running(&sound.name) => Some(("tile-running", styles.accent)),
+                Some(State::Loading) => Some(("tile-loading", styles.dim)),
+                Some(State::Failed(_)) => Some(("tile-cant-play", styles.error)),
+                Some(State::Ready(_) | State::Streaming(_)) | None => None,
+            };
+            if let Some((id, style)) = state {
+                line.push(Span::styled(format!("  {}", t.text(id)), style));
+            }
+            ListItem::new(Line::from(line))
+        })
+        .collect();
+    let list = List::new(items).highlight_symbol("> ").highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+
+    for (row, idx) in (area.y..area.bottom()).zip(state.offset()..app.board.sounds.len()) {
+        app.hits.push(Rect::new(area.x, row, area.width, 1), Target::Tile(idx));
+        if idx == app.selected && app.view == View::Board {
+            frame.set_cursor(area.x, row);
+        }
+    }
+}
+
+fn draw_trash(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.hits.begin_modal(frame.size());
+
+    let popup = centered(area, 70, 70);
+    frame.render_widget(Clear, popup);
+    let (t, styles) = (app.locale(), app.styles());
+    let block = Block::new().title(t.text("trash-title")).borders(Borders::ALL);
+
+    if app.board.trash.is_empty() {
+        let p = Paragraph::new(t.text("trash-empty")).alignment(Alignment::Center).block(block);
+        frame.render_widget(p, popup);
+        app.hits.push(popup, Target::Inert);
+        return;
+    }
+
+    let items: Vec<_> = app
+        .board
+        .trash
+        .iter()
+        .map(|s| {
+            let source = match &s.source {
+                Source::Builtin(name) => t.format("trash-builtin", &[("name", name)]),
+                Source::File(path) => path.display().to_string(),
+                Source::Command(command) => t.format("trash-command", &[("command", command)]),
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(s.name.clone()),
+                Span::styled(format!("  {source}"), styles.dim),
+            ]))
+        })
+        .collect();
+
+    let inner = block.inner(popup);
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.trash_selected));
+    frame.render_stateful_widget(list, popup, &mut state);
+
+    // The popup itself swallows clicks that miss an item
+    app.hits.push(popup, Target::Inert);
+    for (row, idx) in (inner.y..inner.bottom()).zip(state.offset()..app.board.trash.len()) {
+        app.hits.push(Rect::new(inner.x, row, inner.width, 1), Target::TrashItem(idx));
+    }
+}
+
+/// What faders can be learned for, where they are, and which knob or fader moves them.
+fn draw_learn(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.hits.begin_modal(frame.size());
+
+    let popup = centered(area, 70, 70);
+    frame.render_widget(Clear, popup);
+    let (t, styles) = (app.locale(), app.styles());
+    let block = Block::new().title(t.text("learn-title")).borders(Borders::ALL);
+
+    let items: Vec<_> = app
+        .learn_targets()
+        .iter()
+        .map(|control| {
+            let position = app.control_position(control);
+            let fader = match app.board.faders.iter().find(|f| f.control == *control) {
+                Some(f) => match f.channel {
+                    Some(channel) => Span::raw(t.format("learn-fader-channel", &[("cc", &f.cc), ("channel", &channel)])),
+                    None => Span::raw(t.format("learn-fader", &[("cc", &f.cc)])),
+                },
+                None => Span::styled(t.text("learn-unmapped"), styles.dim),
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(app.control_label(control)),
+                Span::styled(format!("  {:.0}%  ", position * 100.0), styles.dim),
+                fader,
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.learn_selected));
+    frame.render_stateful_widget(list, popup, &mut state);
+    app.hits.push(popup, Target::Inert);
+}
+
+fn draw_browser(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.hits.begin_modal(frame.size());
+    let Some(browser) = &app.browser else { return };
+    let (t, styles) = (app.locale(), app.styles());
+
+    let popup = centered(area, 70, 80);
+    frame.render_widget(Clear, popup);
+    let block = Block::new().title(t.format("browser-title", &[("dir", &browser.dir.display())])).borders(Borders::ALL);
+    let inner = block.inner(popup);
+
+    let items: Vec<_> = browser
+        .entries
+        .iter()
+        .map(|e| match (e.dir, e.on_board) {
+            (true, _) => ListItem::new(Span::styled(format!("{}/", e.name), styles.directory)),
+            (false, true) => ListItem::new(Line::from(vec![
+                Span::raw(e.name.clone()),
+                Span::styled(format!("  {}", t.text("browser-on-board")), styles.dim),
+            ])),
+            (false, false) => ListItem::new(e.name.clone()),
+        })
+        .collect();
+    let len = items.len();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(browser.selected));
+    frame.render_stateful_widget(list, popup, &mut state);
+
+    app.hits.push(popup, Target::Inert);
+    for (row, idx) in (inner.y..inner.bottom()).zip(state.offset()..len) {
+        app.hits.push(Rect::new(inner.x, row, inner.width, 1), Target::BrowserEntry(idx));
+    }
+}
+
+fn draw_check(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.hits.begin_modal(frame.size());
+    let Some(check) = &app.check else { return };
+    let (t, styles) = (app.locale(), app.styles());
+
+    let popup = centered(area, 80, 60);
+    frame.render_widget(Clear, popup);
+    let block = Block::new().title(t.text("check-title")).borders(Borders::ALL);
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+    let [outputs, routing] = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(inner);
+
+    let dim = styles.dim;
+    let items: Vec<_> = check
+        .probes
+        .iter()
+        .map(|p| {
+            let role = t.text(if p.role == "monitor" { "check-monitor" } else { "check-output" });
+            let mut line = vec![Span::styled(format!("{role:<8}"), dim), Span::raw(p.name().to_string())];
+            if p.output.mono {
+                line.push(Span::styled(format!("  {}", t.text("check-mono")), styles.accent));
+            }
+            line.push(match &p.format {
+                Ok((channels, rate)) => Span::styled(format!("  {}", t.format("check-format", &[("channels", channels), ("rate", rate)])), dim),
+                Err(e) => Span::styled(format!("  {e}"), styles.error),
+            });
+            ListItem::new(Line::from(line))
+        })
+        .collect();
+    let len = items.len();
+    let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(check.selected));
+    frame.render_stateful_widget(list, outputs, &mut state);
+
+    let result = match (&check.result, check.listening()) {
+        (_, true) => Span::styled(t.text("check-listening"), styles.warning),
+        (Some(result), false) => Span::raw(result.clone()),
+        (None, false) => Span::styled(t.text("check-hint"), dim),
+    };
+    let text = vec![Line::from(vec![Span::styled(format!("{} ", t.text("check-listening-on")), dim), Span::raw(check.input_name().to_string())]), Line::from(result)];
+    frame.render_widget(Paragraph::new(text).block(Block::new().borders(Borders::TOP)), routing);
+
+    app.hits.push(popup, Target::Inert);
+    for (row, idx) in (outputs.y..outputs.bottom()).zip(state.offset()..len) {
+        app.hits.push(Rect::new(outputs.x, row, outputs.width, 1), Target::CheckOutput(idx));
+    }
+}
+
+fn draw_search(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.hits.begin_modal(frame.size());
+    let Some(search) = &app.search else { return };
+    let (t, styles) = (app.locale(), app.styles());
+
+    let popup = centered(area, 80, 80);
+    frame.render_widget(Clear, popup);
+    let block = Block::new().title(t.format("search-title", &[("site", &search.site.name())])).borders(Borders::ALL);
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+    let [query, results] = Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(inner);
+
+    let mut line = vec![Span::styled(format!("{} ", t.text("search-label")), styles.dim), Span::raw(search.query.clone())];
+    if search.editing {
+        line.push(Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)));
+        if search.query.is_empty() && search.site == Site::MyInstants {
+            line.push(Span::styled(format!(" {}", t.text("search-placeholder")), styles.dim));
+        }
+    } else if search.pending.is_some() {
+        line.push(Span::styled(format!("  {}", t.text("search-searching")), styles.dim));
+    }
+    frame.render_widget(Paragraph::new(Line::from(line)), query);
+
+    let items: Vec<_> = search
+        .results
+        .iter()
+        .map(|hit| {
+            let duration = hit.duration.map(|d| format!("{d:.1}s"));
+            let details: Vec<String> = [duration, hit.author.clone(), hit.license.clone()].into_iter().flatten().collect();
+            ListItem::new(Line::from(vec![
+                Span::raw(hit.name.clone()),
+                Span::styled(format!("  {}", details.join("  ")), styles.dim),
+            ]))
+        })
+        .collect();
+    let len = items.len();
+    let highlight = if search.editing { Style::default() } else { Style::default().add_modifier(Modifier::REVERSED) };
+    let list = List::new(items).highlight_style(highlight);
+    let mut state = ListState::default().with_selected(Some(search.selected));
+    frame.render_stateful_widget(list, results, &mut state);
+
+    app.hits.push(popup, Target::Inert);
+    for (row, idx) in (results.y..results.bottom()).zip(state.offset()..len) {
+        app.hits.push(Rect::new(results.x, row, results.width, 1), Target::SearchHit(idx));
+    }
+}
+
+fn draw_settings(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.hits.begin_modal(frame.size());
+    let Some(settings) = &app.settings else { return };
+    let (t, styles) = (app.locale(), app.styles());
+
+    let popup = centered(area, 70, 70);
+    frame.render_widget(Clear, popup);
+    let block = Block::new().title(t.text("settings-title")).borders(Borders::ALL);
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+    let [tabs, rows] = Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(inner);
+
+    let mut spans = Vec::new();
+    let mut tab_hits = Vec::new();
+    let mut x = tabs.x;
+    for (idx, tab) in Tab::ALL.into_iter().enumerate() {
+        let name = format!(" {} ", t.text(&format!("settings-tab-{}", tab.name())));
+        let width = text::width(&name) as u16;
+        let style = if tab == settings.tab { Style::default().add_modifier(Modifier::REVERSED) } else { styles.dim };
+        spans.push(Span::styled(name, style));
+        spans.push(Span::raw(" "));
+        tab_hits.push((Rect::new(x, tabs.y, width.min(tabs.right().saturating_sub(x)), 1), Target::SettingsTab(idx)));
+        x = x.saturating_add(width + 1);
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), tabs);
+
+    let fields: Vec<_> = settings.tab.settings().iter().map(|s| (t.text(&format!("setting-{}", s.id())).to_string(), s.value(&app.board))).collect();
+    let state = draw_fields(frame, rows, fields, settings.selected, settings.editing.as_deref(), t, styles);
+
+    app.hits.push(popup, Target::Inert);
+    for (rect, target) in tab_hits {
+        app.hits.push(rect, target);
+    }
+    for (row, idx) in (rows.y..rows.bottom()).zip(state.offset()..settings.tab.settings().len()) {
+        app.hits.push(Rect::new(rows.x, row, rows.width, 1), Target::Setting(idx));
+    }
+}
+
+fn draw_edit(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.hits.begin_modal(frame.size());
+    let Some(edit) = &app.edit else { return };
+    let (t, styles) = (app.locale(), app.styles());
+
+    let height = Field::ALL.len() as u16 + 2;
+    let popup = centered(area, 60, 100);
+    let popup = Rect { y: area.y + area.height.saturating_sub(height) / 2, height: height.min(area.height), ..popup };
+    frame.render_widget(Clear, popup);
+    let mut title = t.format("edit-title", &[("sound", &edit.draft.name)]);
+    if edit.changed(&app.board) {
+        title.push_str(" *");
+    }
+    let block = Block::new().title(title).borders(Borders::ALL);
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+
+    let fields: Vec<_> = Field::ALL.iter().map(|f| (t.text(&format!("edit-{}", f.id())).to_string(), f.value(&edit.draft))).collect();
+    let state = draw_fields(frame, inner, fields, edit.selected, edit.editing.as_deref(), t, styles);
+
+    app.hits.push(popup, Target::Inert);
+    for (row, idx) in (inner.y..inner.bottom()).zip(state.offset()..Field::ALL.len()) {
+        app.hits.push(Rect::new(inner.x, row, inner.width, 1), Target::EditField(idx));
+    }
+}
+
+/// Labels with their values in a column next to them, with `editing` typed into the selected
+/// one.
+fn draw_fields(frame: &mut Frame, area: Rect, fields: Vec<(String, Value)>, selected: usize, editing: Option<&str>, t: Locale, styles: Styles) -> ListState {
+    let label_width = fields.iter().map(|(label, _)| text::width(label)).max().unwrap_or(0);
+    let items: Vec<_> = fields
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (label, value))| {
+            let pad = " ".repeat(label_width - text::width(&label) + 2);
+            let mut line = vec![Span::raw(format!("{label}{pad}"))];
+            match (editing, value) {
+                (Some(text), _) if idx == selected => {
+                    line.push(Span::styled(text.to_string(), styles.accent));
+                    line.push(Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)));
+                }
+                (_, value @ Value::Unset) => line.push(Span::styled(value.shown(t), styles.dim)),
+                (_, value) => line.push(Span::raw(value.shown(t))),
+            }
+            ListItem::new(Line::from(line))
+        })
+        .collect();
+    let highlight = if editing.is_some() { Style::default() } else { Style::default().add_modifier(Modifier::REVERSED) };
+    let list = List::new(items).highlight_style(highlight);
+    let mut state = ListState::default().with_selected(Some(selected));
+    frame.render_stateful_widget(list, area, &mut state);
+    state
+}
+
+fn draw_commands(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.hits.begin_modal(frame.size());
+    let Some(commands) = &app.commands else { return };
+    let (t, styles) = (app.locale(), app.styles());
+
+    let popup = centered(area, 60, 60);
+    frame.render_widget(Clear, popup);
+    let block = Block::new().title(t.text("commands-title")).borders(Borders::ALL);
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+    let [query, entries] = Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(inner);
+
+    let line = vec![Span::styled("> ", styles.dim), Span::raw(commands.query.clone()), Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED))];
+    frame.render_widget(Paragraph::new(Line::from(line)), query);
+
+    let items: Vec<_> = commands
+        .matches()
+        .map(|entry| {
+            let mut line = vec![Span::raw(entry.label.clone())];
+            if let Some(key) = &entry.key {
+                line.push(Span::styled(format!("  {key}"), styles.dim));
+            }
+            for tag in &entry.tags {
+                line.push(Span::styled(format!("  #{tag}"), styles.dim));
+            }
+            ListItem::new(Line::from(line))
+        })
+        .collect();
+    let len = items.len();
+    if len == 0 {
+        frame.render_widget(Paragraph::new(Span::styled(t.text("commands-none"), styles.dim)), entries);
+    }
+    let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(commands.selected));
+    frame.render_stateful_widget(list, entries, &mut state);
+
+    app.hits.push(popup, Target::Inert);
+    for (row, idx) in (entries.y..entries.bottom()).zip(state.offset()..len) {
+        app.hits.push(Rect::new(entries.x, row, entries.width, 1), Target::Command(idx));
+    }
+}
+
+fn draw_backups(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.hits.begin_modal(frame.size());
+    let (t, styles) = (app.locale(), app.styles());
+
+    let popup = centered(area, 60, 60);
+    frame.render_widget(Clear, popup);
+    let block = Block::new().title(t.text("backups-title")).borders(Borders::ALL);
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+    app.hits.push(popup, Target::Inert);
+    if app.backups.is_empty() {
+        frame.render_widget(Paragraph::new(Span::styled(t.text("backups-none"), styles.dim)), inner);
+        return;
+    }
+
+    let items: Vec<_> = app
+        .backups
+        .iter()
+        .map(|backup| ListItem::new(Span::styled(backup.label(t), if backup.sounds.is_some() { Style::default() } else { styles.dim })))
+        .collect();
+    let len = items.len();
+    let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.backup_selected));
+    frame.render_stateful_widget(list, inner, &mut state);
+    for (row, idx) in (inner.y..inner.bottom()).zip(state.offset()..len) {
+        app.hits.push(Rect::new(inner.x, row, inner.width, 1), Target::Backup(idx));
+    }
+}
+
+fn draw_assign(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.hits.begin_modal(frame.size());
+
+    let height = Strategy::ALL.len() as u16 + 2;
+    let popup = centered(area, 60, 100);
+    let popup = Rect { y: area.y + area.height.saturating_sub(height) / 2, height: height.min(area.height), ..popup };
+    frame.render_widget(Clear, popup);
+    let (t, styles) = (app.locale(), app.styles());
+    let block = Block::new().title(t.text("assign-title")).borders(Borders::ALL);
+    let inner = block.inner(popup);
+
+    let items: Vec<_> = Strategy::ALL
+        .iter()
+        .map(|s| {
+            ListItem::new(Line::from(vec![
+                Span::raw(s.name()),
+                Span::styled(format!("  {}", t.text(&format!("strategy-{}", s.name()))), styles.dim),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.assign_selected));
+    frame.render_stateful_widget(list, popup, &mut state);
+
+    app.hits.push(popup, Target::Inert);
+    for (row, idx) in (inner.y..inner.bottom()).zip(state.offset()..Strategy::ALL.len()) {
+        app.hits.push(Rect::new(inner.x, row, inner.width, 1), Target::Strategy(idx));
+    }
+}
+
+fn draw_conflicts(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.hits.begin_modal(frame.size());
+
+    let popup = centered(area, 80, 70);
+    frame.render_widget(Clear, popup);
+    let (t, styles) = (app.locale(), app.styles());
+    let block = Block::new().title(t.text("conflicts-title")).borders(Borders::ALL);
+
+    if app.conflicts.is_empty() {
+        let p = Paragraph::new(t.text("conflicts-none")).alignment(Alignment::Center).block(block);
+        frame.render_widget(p, popup);
+        app.hits.push(popup, Target::Inert);
+        return;
+    }
+
+    let target = app.conflict_target();
+    let items: Vec<_> = app
+        .conflicts
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let trigger = match &c.trigger {
+                Trigger::Key(chord) => chord.to_string(),
+                Trigger::MidiNote(n) => t.format("trigger-midi-note", &[("note", n)]),
+                Trigger::Name(name) => t.format("trigger-alias", &[("name", &format!("{name:?}"))]),
+                Trigger::Phrase(phrase) => t.format("trigger-phrase", &[("phrase", &format!("{phrase:?}"))]),
+                Trigger::Pad(PadInput::Button(n)) => t.format("trigger-button", &[("button", n)]),
+                Trigger::Pad(PadInput::Axis { axis, threshold }) => {
+                    t.format("trigger-axis", &[("axis", axis), ("threshold", &format!("{:.2}", *threshold as f32 / 32767.0))])
+                }
+            };
+            let mut spans = vec![Span::styled(format!("{trigger}: "), Style::default().add_modifier(Modifier::BOLD))];
+            for (n, owner) in c.owners.iter().enumerate() {
+                if n > 0 {
+                    spans.push(Span::styled(format!(" {} ", t.text("conflicts-vs")), styles.dim));
+                }
+                match *owner {
+                    Owner::Sound { sound, binding } => {
+                        let style = if i == app.conflict_selected && target == Some((sound, binding)) {
+                            styles.error.add_modifier(Modifier::UNDERLINED)
+                        } else {
+                            Style::default()
+                        };
+                        let s = &app.board.sounds[sound];
+                        spans.push(Span::styled(format!("{:?}", s.name), style));
+                        // Chords the terminal turns into another key are shown as written too
+                        let label = &s.bindings[binding].label;
+                        if *label != trigger {
+                            spans.push(Span::styled(format!(" ({label})"), styles.dim));
+                        }
+                    }
+                    Owner::Action(action) => spans.push(Span::styled(action, styles.accent)),
+                }
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let inner = block.inner(popup);
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.conflict_selected));
+    frame.render_stateful_widget(list, popup, &mut state);
+
+    app.hits.push(popup, Target::Inert);
+    for (row, idx) in (inner.y..inner.bottom()).zip(state.offset()..app.conflicts.len()) {
+        app.hits.push(Rect::new(inner.x, row, inner.width, 1), Target::Conflict(idx));
+    }
+}
+
+fn draw_cues(frame: &mut Frame, app: &mut App, area: Rect) {
+    let (t, styles) = (app.locale(), app.styles());
+    let block = Block::new().title(t.text("cues-title")).borders(Borders::ALL);
+    if app.board.cues.is_empty() {
+        let p = Paragraph::new(t.text("cues-empty")).alignment(Alignment::Center).block(block);
+        frame.render_widget(p, area);
+        return;
+    }
+
+    let rows: Vec<_> = app
+        .board
+        .cues
+        .iter()
+        .enumerate()
+        .map(|(i, cue)| {
+            let (marker, style) = if i == app.cue_standby {
+                (t.text("cue-standby"), styles.good.add_modifier(Modifier::BOLD))
+            } else if i == app.cue_standby + 1 {
+                (t.text("cue-next"), styles.warning)
+            } else if Some(i) == app.cue_last {
+                (t.text("cue-last"), styles.dim)
+            } else if i < app.cue_standby {
+                ("", styles.dim)
+            } else {
+                ("", Style::default())
+            };
+            let sound = app.board.sound_named(&cue.sound).map(|idx| &app.board.sounds[idx]);
+            let shortcut = sound.and_then(|s| s.shortcut_label()).map(|l| l.into_owned()).unwrap_or_default();
+            let name = if sound.is_some() { cue.sound.clone() } else { t.format("cue-in-trash", &[("sound", &cue.sound)]) };
+            Row::new(vec![
+                Cell::from(marker),
+                Cell::from(format!("{}", i + 1)),
+                Cell::from(name),
+                Cell::from(shortcut),
+                Cell::from(cue.note.clone().unwrap_or_default()),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let widths = [Constraint::Length(8), Constraint::Length(4), Constraint::Percentage(30), Constraint::Length(12), Constraint::Fill(1)];
+    let header = Row::new(["", "#", t.text("cues-sound"), t.text("cues-key"), t.text("cues-note")]).style(Style::default().add_modifier(Modifier::UNDERLINED));
+    let inner = block.inner(area);
+    let table = Table::new(rows, widths).header(header).block(block);
+    let mut state = TableState::default().with_selected(Some(app.cue_standby.min(app.board.cues.len() - 1)));
+    frame.render_stateful_widget(table, area, &mut state);
+
+    // The header takes the first row
+    for (row, idx) in (inner.y + 1..inner.bottom()).zip(state.offset()..app.board.cues.len()) {
+        app.hits.push(Rect::new(inner.x, row, inner.width, 1), Target::Cue(idx));
+    }
+}
+
+fn draw_history(frame: &mut Frame, app: &mut App, area: Rect) {
+    let (t, styles) = (app.locale(), app.styles());
+    let block = Block::new().title(t.text("history-title")).borders(Borders::ALL);
+    let entries = &app.history.entries;
+    if entries.is_empty() {
+        let p = Paragraph::new(t.text("history-empty")).alignment(Alignment::Center).block(block);
+        frame.render_widget(p, area);
+        return;
+    }
+
+    let rows: Vec<_> = entries
+        .iter()
+        .map(|e| {
+            let offset = e.offset.as_secs();
+            Row::new(vec![
+                Cell::from(history::timestamp(e.at)),
+                Cell::from(format!("+{}:{:02}:{:02}", offset / 3600, offset / 60 % 60, offset % 60)),
+                Cell::from(e.sound.clone()),
+                Cell::from(Span::styled(e.via.name(), styles.dim)),
+            ])
+        })
+        .collect();
+    let widths = [Constraint::Length(21), Constraint::Length(10), Constraint::Fill(1), Constraint::Length(6)];
+    let header = Row::new(["history-time", "history-session", "history-sound", "history-via"].map(|id| t.text(id))).style(Style::default().add_modifier(Modifier::UNDERLINED));
+    let table = Table::new(rows, widths).header(header).block(block);
+    // Follow the newest entry
+    let mut state = TableState::default().with_selected(Some(entries.len() - 1));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_log(frame: &mut Frame, app: &mut App, area: Rect) {
+    let (t, styles) = (app.locale(), app.styles());
+    let block = Block::new().title(t.text("log-title")).borders(Borders::ALL);
+    let log = &app.shell.log;
+    if log.is_empty() {
+        let p = Paragraph::new(t.text("log-empty")).alignment(Alignment::Center).block(block);
+        frame.render_widget(p, area);
+        return;
+    }
+
+    let items: Vec<_> = log
+        .iter()
+        .map(|line| {
+            let style = if line.error { styles.error } else { Style::default() };
+            ListItem::new(Line::from(vec![Span::styled(format!("{}  ", line.tile), styles.dim), Span::styled(line.text.clone(), style)]))
+        })
+        .collect();
+    // Follow the newest line, without marking it as if it were picked
+    let mut state = ListState::default().with_offset(log.len().saturating_sub(area.height.saturating_sub(2) as usize));
+    frame.render_stateful_widget(List::new(items).block(block), area, &mut state);
+}
+
+/// The spectrum or waveform of what the monitor played last.
+fn draw_scope(frame: &mut Frame, app: &App, mode: Mode, area: Rect, now: Instant) {
+    let (t, styles) = (app.locale(), app.styles());
+    let (samples, rate) = app.engine.scope().recent(scope::WINDOW, now);
+    let level = match scope::peak(&samples) {
+        Some(db) => t.format("scope-peak", &[("db", &format!("{db:.0}"))]),
+        None => t.text("scope-no-signal").to_string(),
+    };
+    let block = Block::new().title(format!("{}, {level}", t.text(&format!("scope-{}", mode.name())))).borders(Borders::ALL);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    match mode {
+        Mode::Spectrum => {
+            let bars: Vec<u64> = scope::spectrum(&samples, rate, inner.width as usize).into_iter().map(|b| (b * 100.0) as u64).collect();
+            frame.render_widget(Sparkline::default().data(&bars).max(100).style(styles.accent), inner);
+        }
+        Mode::Waveform => {
+            let shown = &samples[samples.len() - ((rate as f32 * WAVEFORM_TIME) as usize).clamp(1, samples.len())..];
+            let points: Vec<(f64, f64)> = shown.iter().enumerate().map(|(i, &s)| (i as f64, s.clamp(-1.0, 1.0) as f64)).collect();
+            let color = styles.accent.fg.unwrap_or(Color::Reset);
+            let canvas = Canvas::default()
+                .marker(Marker::Braille)
+                .x_bounds([0.0, shown.len() as f64])
+                .y_bounds([-1.0, 1.0])
+                .paint(|ctx| ctx.draw(&Points { coords: &points, color }));
+            frame.render_widget(canvas, inner);
+        }
+    }
+}
+
+/// A banner across the top of the board that can't be missed, even from across the room.
+fn draw_muted(frame: &mut Frame, app: &mut App, area: Rect) {
+    let styles = app.styles();
+    let banner = Rect { height: area.height.min(3), ..area };
+    let style = styles.alarm;
+    let text = vec![Line::raw(""), Line::raw(app.locale().format("muted", &[("key", &binding::key_to_string(MUTE_KEY))])), Line::raw("")];
+    frame.render_widget(Clear, banner);
+    frame.render_widget(Paragraph::new(text).alignment(Alignment::Center).style(style), banner);
+    app.hits.push(banner, Target::Inert);
+}
+
+/// `art` in the middle of `area`, cut off where it doesn't fit.
+fn draw_art(frame: &mut Frame, art: &Art, area: Rect, style: Style) {
+    let (width, height) = (art.width().min(area.width), art.height().min(area.height));
+    let at = Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height);
+    let lines: Vec<Line> = art.lines.iter().map(|line| Line::raw(line.as_str())).collect();
+    frame.render_widget(Paragraph::new(lines).style(style), at);
+}
+
+fn centered(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let width = area.width * percent_x / 100;
+    let height = area.height * percent_y / 100;
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let (t, styles) = (app.locale(), app.styles());
+    let hints = t.text(match app.view {
+        View::Board if app.locked => "hints-board-locked",
+        View::Board => "hints-board",
+        View::Trash => "hints-trash",
+        View::Assign => "hints-assign",
+        View::Cues => "hints-cues",
+        View::History => "hints-history",
+        View::Learn => "hints-learn",
+        View::Log => "hints-log",
+        View::Search if app.search.as_ref().is_some_and(|s| s.editing) => "hints-search-editing",
+        View::Search => "hints-search",
+        View::Browse => "hints-browse",
+        View::Check => "hints-check",
+        View::Commands => "hints-commands",
+        View::Backups => "hints-backups",
+        View::Settings if app.settings.as_ref().is_some_and(|s| s.editing.is_some()) => "hints-settings-editing",
+        View::Settings => "hints-settings",
+        View::Edit if app.edit.as_ref().is_some_and(|e| e.editing.is_some()) => "hints-settings-editing",
+        View::Edit => "hints-edit",
+        View::Conflicts => "hints-conflicts",
+    });
+
+    let mut spans = Vec::new();
+    if app.locked {
+        spans.push(Span::styled(format!(" {} ", t.text("locked")), styles.alarm));
+        spans.push(Span::raw(" "));
+    }
+    if app.read_only {
+        spans.push(Span::styled(format!(" {} ", t.text("read-only")), styles.alarm));
+        spans.push(Span::raw(" "));
+    }
+    if let Some(listener) = &app.talkover {
+        let ducking = listener.meter.ducking.load(Ordering::Relaxed);
+        let style = if ducking { styles.ducking } else { styles.accent };
+        spans.push(Span::styled(format!(" {} ", t.format("mic", &[("level", &format!("{:>4.0}", listener.meter.level().max(-99.0)))])), style));
+        spans.push(Span::raw(" "));
+    }
+    if let Some(recorder) = app.engine.recorder() {
+        let secs = recorder.elapsed().as_secs();
+        spans.push(Span::styled(format!(" {} ", t.format("recording", &[("time", &format!("{:02}:{:02}", secs / 60, secs % 60))])), styles.alarm));
+        spans.push(Span::raw(" "));
+    }
+    if app.rehearsal {
+        spans.push(Span::styled(format!(" {} ", t.text("rehearsal")), styles.caution));
+        spans.push(Span::raw(" "));
+    }
+    if app.idle {
+        spans.push(Span::styled(format!(" {} ", t.text("idle")), styles.caution));
+        spans.push(Span::raw(" "));
+    }
+    if let Some((crossfader, position)) = app.engine.crossfader() {
+        let at = (position * (CROSSFADER_WIDTH - 1) as f32).round() as usize;
+        let track: String = (0..CROSSFADER_WIDTH).map(|i| if i == at { '●' } else { '─' }).collect();
+        spans.push(Span::styled(format!(" {} {track} {} ", crossfader.left, crossfader.right), styles.accent));
+        spans.push(Span::raw(" "));
+    }
+    if app.board.clock {
+        spans.push(Span::styled(format!(" {} ", vars::clock(SystemTime::now())), styles.accent));
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(format!(" {} ", t.format("show-timer", &[("time", &timer::format(app.now.saturating_duration_since(app.timers.show)))])), styles.accent));
+        spans.push(Span::raw(" "));
+    }
+    for (name, at) in &app.timers.running {
+        let left = Timers::left(*at, app.now);
+        let style = if left <= timer::ALMOST { styles.caution } else { styles.accent };
+        spans.push(Span::styled(format!(" {name} {} ", timer::format(left)), style));
+        spans.push(Span::raw(" "));
+    }
+    for (name, _) in &app.timers.done {
+        spans.push(Span::styled(format!(" {name} {} ", timer::format(Duration::ZERO)), styles.alarm));
+        spans.push(Span::raw(" "));
+    }
+    if let Some(status) = &app.status {
+        spans.push(Span::styled(status.clone(), styles.warning));
+        spans.push(Span::raw("  "));
+    }
+    if !app.conflicts.is_empty() && app.view != View::Conflicts {
+        spans.push(Span::styled(t.format("conflicts-count", &[("count", &app.conflicts.len())]), styles.error));
+        spans.push(Span::raw("  "));
+    }
+    spans.push(Span::styled(hints, styles.dim));
+    if !app.caps.mouse {
+        spans.push(Span::styled(format!("  {}", t.text("keyboard-only")), styles.dim));
+    }
+    let line = Line::from(spans);
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+/// The config that doesn't load, and `backup`, the newest one that does, to go back to.
+/// `note` is what went wrong trying to fix it, like an editor that wouldn't start.
+pub fn draw_config_error(frame: &mut Frame, err: &ConfigError, source: &str, backup: Option<&Backup>, note: Option<&str>, t: Locale, styles: Styles) {
+    let area = frame.size();
+    let block = Block::new()
+        .title(format!(" {} ", t.text("config-error")))
+        .borders(Borders::ALL)
+        .border_style(styles.error)
+        .padding(Padding::uniform(1));
+
+    let label = styles.dim;
+    let location = match err.line {
+        Some(line) => format!("{}:{line}", err.path.display()),
+        None => err.path.display().to_string(),
+    };
+
+    let mut lines = vec![
+        Line::from(Span::styled(location, Style::default().add_modifier(Modifier::BOLD))),
+        Line::from(Span::styled(err.message.clone(), styles.error)),
+        Line::raw(""),
+    ];
+    if let Some(field) = &err.field {
+        lines.push(Line::from(vec![Span::styled(format!("{:<10}", t.text("config-error-field")), label), Span::raw(field.clone())]));
+    }
+    if let Some(expected) = &err.expected {
+        lines.push(Line::from(vec![Span::styled(format!("{:<10}", t.text("config-error-expected")), label), Span::raw(*expected)]));
+    }
+    if let Some(suggestion) = &err.suggestion {
+        lines.push(Line::from(vec![Span::styled(format!("{:<10}", t.text("config-error-hint")), label), Span::styled(suggestion.clone(), styles.good)]));
+    }
+
+    // A few lines of context around the error
+    if let Some(line) = err.line {
+        lines.push(Line::raw(""));
+        let first = line.saturating_sub(3).max(1);
+        for (n, text) in source.lines().enumerate().map(|(i, l)| (i + 1, l)).skip(first - 1).take(5) {
+            let (gutter, style) = if n == line { (if styles.marks { '>' } else { '|' }, styles.warning.add_modifier(Modifier::BOLD)) } else { ('|', label) };
+            lines.push(Line::from(vec![Span::styled(format!("{n:>4} {gutter} "), label), Span::styled(text.to_string(), style)]));
+        }
+    }
+
+    lines.push(Line::raw(""));
+    if let Some(note) = note {
+        lines.push(Line::from(Span::styled(note.to_string(), styles.warning)));
+    }
+    let keys = match backup.and_then(|b| b.modified) {
+        Some(at) => t.format("config-error-keys-backup", &[("time", &history::timestamp(at))]),
+        None => t.text("config-error-keys").to_string(),
+    };
+    lines.push(Line::from(Span::styled(keys, label)));
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use ratatui::backend::{Backend, TestBackend};
+    use ratatui::layout::Rect;
+    use ratatui::style::Color;
+    use ratatui::{Terminal, TerminalOptions, Viewport};
+    use crate::binding::{Binding, KeyChord};
+    use crate::config::{Board, LongNames, Source, TileLayout};
+    use crate::harness::{self, assert_snapshot, Harness};
+    use crate::app::Playing;
+    use crate::hit::Target;
+    use crate::locale::Locale;
+    use crate::palette::Palette;
+    use super::{Redraw, TILE_HEIGHT};
+
+    /// Every cell of the board that clicks on sound `idx`'s tile.
+    fn tile_cells(h: &Harness, idx: usize) -> Vec<(u16, u16)> {
+        let area = h.terminal.backend().buffer().area;
+        (0..area.height)
+            .flat_map(|y| (0..area.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| h.app.hits.hit(x, y) == Some(Target::Tile(idx)))
+            .collect()
+    }
+
+    fn bounds(cells: &[(u16, u16)]) -> Rect {
+        let (left, top) = (cells.iter().map(|c| c.0).min().unwrap(), cells.iter().map(|c| c.1).min().unwrap());
+        let (right, bottom) = (cells.iter().map(|c| c.0).max().unwrap(), cells.iter().map(|c| c.1).max().unwrap());
+        Rect::new(left, top, right - left + 1, bottom - top + 1)
+    }
+
+    #[test]
+    fn builtin_board() {
+        let h = Harness::new(Board::builtin(), 100, 24).loaded();
+        assert_snapshot("builtin_board", &h.screen());
+    }
+
+    #[test]
+    fn narrow_terminal() {
+        let h = Harness::new(Board::builtin(), 36, 14).loaded();
+        assert_snapshot("narrow_terminal", &h.screen());
+    }
+
+    #[test]
+    fn long_names_stay_in_their_tiles() {
+        let name_rows = |long_names| {
+            let mut board = harness::board(2);
+            board.sounds[0].name = "the saddest trombone in the whole wide cafe\u{301}".to_string();
+            board.long_names = long_names;
+            let h = Harness::new(board, 30, 12).loaded();
+            let tile = bounds(&tile_cells(&h, 0));
+            let buffer = h.terminal.backend().buffer();
+            let rows: Vec<String> = (tile.y + 1..tile.bottom() - 1)
+                .map(|y| (tile.x + 1..tile.right() - 1).map(|x| buffer.get(x, y).symbol()).collect::<String>().trim().to_string())
+                .filter(|row| !row.is_empty())
+                .collect();
+            // The borders are still there, nothing was drawn over them
+            assert_eq!(buffer.get(tile.right() - 1, tile.y + 2).symbol(), "│");
+            rows
+        };
+        assert_eq!(name_rows(LongNames::Truncate), ["the saddest trombone in the…"]);
+        assert_eq!(name_rows(LongNames::Wrap), ["the saddest trombone in the", "whole wide cafe\u{301}"]);
+    }
+
+    #[test]
+    fn numpad_layout() {
+        let mut board = harness::board(19);
+        board.layout = TileLayout::Numpad;
+        for (sound, key) in board.sounds.iter_mut().zip("/*-789+456123.0".chars()) {
+            sound.bindings = vec![Binding::key(KeyChord::new(KeyCode::Char(key)))];
+        }
+        let h = Harness::new(board, 100, 40).loaded();
+        assert_snapshot("numpad_layout", &h.screen());
+    }
+
+    #[test]
+    fn selection_follows_arrows() {
+        let mut h = Harness::new(Board::builtin(), 100, 24).loaded();
+        for key in [KeyCode::Right, KeyCode::Right, KeyCode::Down, KeyCode::Left] {
+            h.press(key);
+        }
+        assert_eq!(h.app.selected, 3);
+        let tile = bounds(&tile_cells(&h, 3));
+        assert_eq!(h.terminal.backend().buffer().get(tile.x, tile.y).fg, Color::Yellow);
+        assert_eq!(h.terminal.backend().buffer().get(0, 0).fg, Color::White);
+    }
+
+    #[test]
+    fn without_color_the_selection_is_still_shown() {
+        let mut board = Board::builtin();
+        board.palette = Some(Palette::None);
+        let h = Harness::new(board, 100, 24).loaded();
+        let buffer = h.terminal.backend().buffer();
+        assert!(buffer.content().iter().all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+        let tile = bounds(&tile_cells(&h, 0));
+        assert_eq!(buffer.get(tile.x, tile.y).symbol(), "╔");
+        let other = bounds(&tile_cells(&h, 1));
+        assert_eq!(buffer.get(other.x, other.y).symbol(), "┌");
+    }
+
+    /// Tiles flash when their sound plays, and shake in red when it can't.
+    #[test]
+    fn tiles_show_what_happened() {
+        let mut board = harness::board(2);
+        board.sounds[0].bindings.push(Binding::key(KeyChord::new(KeyCode::Char('p'))));
+        board.sounds[1].bindings.push(Binding::key(KeyChord::new(KeyCode::Char('f'))));
+        board.sounds[1].source = Source::File("/nonexistent/sound.wav".into());
+        let mut h = Harness::new(board, 100, 24).loaded();
+        let fg = |h: &Harness, idx| {
+            let tile = bounds(&tile_cells(h, idx));
+            let cell = h.terminal.backend().buffer().get(tile.x, tile.y);
+            if cell.bg == Color::Reset { cell.fg } else { cell.bg }
+        };
+
+        h.press(KeyCode::Char('p'));
+        assert_eq!(fg(&h, 0), Color::LightGreen);
+        h.press(KeyCode::Char('f'));
+        assert_eq!(fg(&h, 1), Color::Red);
+        assert_eq!(h.app.board.sounds[1].plays, 0);
+    }
+
+    /// A sound playing over itself says how many times on its tile, until only one is left.
+    #[test]
+    fn overlapping_plays_are_counted() {
+        let mut h = Harness::new(Board::builtin(), 100, 24).loaded();
+        let source = h.app.board.sounds[0].source.clone();
+        for id in 0..3 {
+            let playing = Playing { source: source.clone(), length: Duration::from_secs(100), elapsed: Duration::from_secs(id), looped: false };
+            h.app.playing.insert(id, playing);
+        }
+        h.app.redraw.dirty = true;
+        h.step();
+        assert!(h.screen().lines().next().unwrap().contains("×3"), "{}", h.screen());
+
+        for (id, left) in [(0, "×2"), (1, "×")] {
+            h.app.playing.remove(&id);
+            h.app.redraw.dirty = true;
+            h.step();
+            assert_eq!(h.screen().lines().next().unwrap().contains(left), id == 0);
+        }
+    }
+
+    /// A playing sound's tile fills up from the left as it plays.
+    #[test]
+    fn playing_fills_the_tile() {
+        let mut h = Harness::new(Board::builtin(), 100, 24).loaded();
+        let source = h.app.board.sounds[0].source.clone();
+        let playing = Playing { source, length: Duration::from_secs(100), elapsed: Duration::from_secs(25), looped: false };
+        h.app.playing.insert(u64::MAX, playing);
+        h.app.redraw.dirty = true;
+        h.step();
+
+        let tile = bounds(&tile_cells(&h, 0));
+        let buffer = h.terminal.backend().buffer();
+        let filled: Vec<bool> = (tile.x + 1..tile.right() - 1).map(|x| buffer.get(x, tile.y + 2).bg == Color::DarkGray).collect();
+        assert_eq!(filled.iter().filter(|&&f| f).count(), (filled.len() as f32 * 0.25).round() as usize);
+        assert!(filled[0] && !filled[filled.len() - 1]);
+        assert_eq!(buffer.get(tile.x, tile.y + 2).bg, Color::Reset);
+    }
+
+    #[test]
+    fn trash() {
+        let mut h = Harness::new(Board::builtin(), 100, 24).loaded();
+        h.press(KeyCode::Delete);
+        h.press(KeyCode::Tab);
+        assert_snapshot("trash", &h.screen());
+    }
+
+    #[test]
+    fn assign() {
+        let mut h = Harness::new(Board::builtin(), 100, 24).loaded();
+        h.press_with(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_snapshot("assign", &h.screen());
+    }
+
+    #[test]
+    fn muted() {
+        let mut h = Harness::new(Board::builtin(), 100, 24).loaded();
+        h.press(crate::app::MUTE_KEY);
+        assert_snapshot("muted", &h.screen());
+    }
+
+    #[test]
+    fn the_config_picks_the_language() {
+        let mut board = Board::builtin();
+        board.locale = Some(Locale::Dutch);
+        let mut h = Harness::new(board, 100, 24).loaded();
+        h.press(crate::app::MUTE_KEY);
+        let screen = h.screen();
+        assert!(screen.contains("GEDEMPT") && screen.contains("Enter: spelen"), "{screen}");
+    }
+
+    #[test]
+    fn clicking_a_tile_selects_it() {
+        let mut h = Harness::new(Board::builtin(), 100, 24).loaded();
+        let cells = tile_cells(&h, 4);
+        let tile = bounds(&cells);
+        h.click(tile.x + tile.width / 2, tile.y + tile.height / 2);
+        assert_eq!(h.app.selected, 4);
+        assert_eq!(h.app.board.sounds[4].plays, 1);
+    }
+
+    /// Inline, below what was on the terminal already, the board is drawn and clicked where it
+    /// is rather than at the top.
+    #[test]
+    fn inline_boards_are_drawn_below_the_cursor() {
+        let mut h = Harness::new(Board::builtin(), 100, 40);
+        let mut backend = TestBackend::new(100, 40);
+        backend.set_cursor(0, 30).unwrap();
+        h.terminal = Terminal::with_options(backend, TerminalOptions { viewport: Viewport::Inline(16) }).unwrap();
+        let mut h = h.loaded();
+        let tile = bounds(&tile_cells(&h, 0));
+        assert!(tile.y >= 24, "{tile:?}");
+        assert!(h.terminal.backend().buffer().content[..100 * 24].iter().all(|c| c.symbol() == " "));
+        h.click(tile.x + tile.width / 2, tile.y + tile.height / 2);
+        assert_eq!(h.app.board.sounds[0].plays, 1);
+    }
+
+    /// A board that nothing happens on isn't drawn again until something does.
+    #[test]
+    fn idle_boards_are_not_redrawn() {
+        let mut h = Harness::new(Board::builtin(), 100, 24).loaded();
+        let drawn = h.terminal.get_frame().count();
+        for _ in 0..10 {
+            h.step();
+        }
+        assert_eq!(h.terminal.get_frame().count(), drawn);
+        h.press(KeyCode::Right);
+        assert_eq!(h.terminal.get_frame().count(), drawn + 1);
+    }
+
+    /// Something moving is drawn every frame and once more after it stopped, not in between.
+    #[test]
+    fn moving_boards_are_drawn_at_the_frame_rate() {
+        let (start, frame) = (Instant::now(), Duration::from_millis(20));
+        let mut redraw = Redraw::default();
+        let at = |ms| start + Duration::from_millis(ms);
+        let drawn: Vec<bool> = [(0, true), (5, true), (20, true), (30, true), (45, false), (50, false)]
+            .into_iter()
+            .map(|(ms, moving)| redraw.due(moving, frame, at(ms)))
+            .collect();
+        assert_eq!(drawn, [true, false, true, false, true, false]);
+        assert_eq!(redraw.timeout(frame, at(50)), super::IDLE_POLL);
+    }
+
+    /// Tiles are drawn as whole rectangles above the status line that don't overlap, whatever the
+    /// size of the terminal and however many there are.
+    #[test]
+    fn tiles_fit_the_terminal() {
+        harness::check(48, |rng| {
+            let mut board = harness::board(rng.below(40));
+            if rng.chance(0.3) {
+                board.layout = TileLayout::Numpad;
+            }
+            let (width, height) = (10 + rng.below(190) as u16, 3 + rng.below(60) as u16);
+            let h = Harness::new(board, width, height);
+            let mut covered = 0;
+            for idx in 0..h.app.board.sounds.len() {
+                let cells = tile_cells(&h, idx);
+                if cells.is_empty() {
+                    continue;
+                }
+                let tile = bounds(&cells);
+                assert_eq!(cells.len(), tile.area() as usize, "tile {idx} isn't a rectangle in {width}x{height}");
+                assert!(tile.bottom() < height, "tile {idx} covers the status line in {width}x{height}");
+                covered += cells.len();
+            }
+            // Cells clicking on more than one tile would be counted twice
+            assert!(covered <= width as usize * (height as usize - 1));
+        });
+    }
+
+    /// Moving the selection around keeps it on a sound, and scrolls that sound into view.
+    #[test]
+    fn selection_stays_in_view() {
+        harness::check(32, |rng| {
+            let board = harness::board(1 + rng.below(60));
+            let len = board.sounds.len();
+            let (width, height) = (20 + rng.below(120) as u16, 8 + rng.below(30) as u16);
+            let mut h = Harness::new(board, width, height);
+            for _ in 0..20 {
+                h.press(*rng.pick(&[KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down]));
+                assert!(h.app.selected < len);
+                let cells = tile_cells(&h, h.app.selected);
+                assert!(!cells.is_empty(), "sound {} is selected but not shown in {width}x{height}", h.app.selected);
+                assert_eq!(bounds(&cells).height, TILE_HEIGHT as u16, "sound {} is cut off in {width}x{height}", h.app.selected);
+            }
+        });
+    }
+}
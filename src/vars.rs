@@ -0,0 +1,235 @@
+//! Placeholders in what tiles show and do, filled in when they are drawn or triggered: a tile
+//! called `clock {time}` shows the time, and `run = "notify-send '{sound} played {play_count}
+//! times'"` says how often. Braces around anything else are left as they are, so shell commands
+//! keep theirs.
+//!
+//! Sound names come from packs, searches and other boards too, so what goes into a command is
+//! quoted for where it lands in it, and what goes into a webhook URL is percent-encoded: a name
+//! is only ever a name, and never runs or points anywhere else.
+
+use std::borrow::Cow;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::config::Sound;
+use crate::history;
+
+/// Every placeholder there is.
+pub const NAMES: [&str; 4] = ["sound", "play_count", "time", "date"];
+
+/// What the placeholders stand for.
+#[derive(Debug, Clone, Copy)]
+pub struct Vars<'a> {
+    pub sound: &'a str,
+    pub plays: u32,
+    pub now: SystemTime,
+}
+
+impl<'a> Vars<'a> {
+    /// The placeholders for `sound`, at this moment.
+    pub fn of(sound: &'a Sound) -> Self {
+        Self { sound: &sound.name, plays: sound.plays, now: SystemTime::now() }
+    }
+
+    fn get(&self, name: &str) -> Option<String> {
+        let (date, time) = local(self.now);
+        match name {
+            // As shown, so a clock tile's name says the time
+            "sound" if !self.sound.contains("{sound}") => Some(expand(self.sound, self).into_owned()),
+            "sound" => Some(self.sound.to_string()),
+            "play_count" => Some(self.plays.to_string()),
+            "time" => Some(time),
+            "date" => Some(date),
+            _ => None,
+        }
+    }
+}
+
+/// `template` with its placeholders filled in.
+pub fn expand<'t>(template: &'t str, vars: &Vars) -> Cow<'t, str> {
+    fill(template, vars, |value, _| value.to_string())
+}
+
+/// Like [`expand`], for a command for `sh -c`: every value comes out as one word, with nothing
+/// in it the shell acts on, whether the placeholder is bare or inside quotes.
+pub fn expand_shell<'t>(template: &'t str, vars: &Vars) -> Cow<'t, str> {
+    fill(template, vars, |value, before| match quoting(before) {
+        Quoting::None => format!("'{}'", value.replace('\'', r"'\''")),
+        Quoting::Single => value.replace('\'', r"'\''"),
+        Quoting::Double => value.chars().fold(String::new(), |mut out, c| {
+            if matches!(c, '$' | '`' | '"' | '\\') {
+                out.push('\\');
+            }
+            out.push(c);
+            out
+        }),
+    })
+}
+
+/// Like [`expand`], for a URL: every value is percent-encoded, so it can't end the path or
+/// start a query or fragment.
+pub fn expand_url<'t>(template: &'t str, vars: &Vars) -> Cow<'t, str> {
+    fill(template, vars, |value, _| {
+        value.bytes().fold(String::new(), |mut out, b| {
+            match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => out.push(b as char),
+                _ => out.push_str(&format!("%{b:02X}")),
+            }
+            out
+        })
+    })
+}
+
+/// The quotes a shell is inside of at the end of `command`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quoting {
+    None,
+    Single,
+    Double,
+}
+
+fn quoting(command: &str) -> Quoting {
+    let mut state = Quoting::None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        state = match (state, c) {
+            (Quoting::Single, '\'') => Quoting::None,
+            (Quoting::Single, _) => Quoting::Single,
+            (_, '\\') => {
+                chars.next();
+                state
+            }
+            (Quoting::None, '\'') => Quoting::Single,
+            (Quoting::None, '"') => Quoting::Double,
+            (Quoting::Double, '"') => Quoting::None,
+            _ => state,
+        };
+    }
+    state
+}
+
+/// `template` with its placeholders filled in, each value passed through `escape` along with
+/// everything before it.
+fn fill<'t>(template: &'t str, vars: &Vars, escape: impl Fn(&str, &str) -> String) -> Cow<'t, str> {
+    if !uses(template) {
+        return Cow::Borrowed(template);
+    }
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| Some((vars.get(&rest[1..end])?, end)));
+        match value {
+            Some((value, end)) => {
+                let value = escape(&value, &out);
+                out.push_str(&value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Whether `template` has any placeholders.
+pub fn uses(template: &str) -> bool {
+    NAMES.iter().any(|name| template.contains(&format!("{{{name}}}")))
+}
+
+/// Whether what `template` comes to changes as time goes by, and not only when something happens.
+pub fn changes_with_time(template: &str) -> bool {
+    template.contains("{time}") || template.contains("{date}")
+}
+
+/// The date and the time to the minute, like `2024-05-01` and `20:15`, in local time where
+/// that is known and UTC elsewhere.
+fn local(now: SystemTime) -> (String, String) {
+    let stamp = local_stamp(now);
+    (stamp[..10].to_string(), stamp[11..16].to_string())
+}
+
+/// The time to the second, like `20:15:03`, local like [`local`].
+pub fn clock(now: SystemTime) -> String {
+    local_stamp(now)[11..19].to_string()
+}
+
+/// How many seconds past midnight on Monday it is, in local time like [`local`].
+pub fn time_of_week(now: SystemTime) -> u32 {
+    let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+    // 1970 started on a Thursday
+    (secs + offset(secs) + 3 * DAY).rem_euclid(7 * DAY) as u32
+}
+
+const DAY: i64 = 24 * 3600;
+
+/// Like `2024-05-01T20:15:03Z`, but in local time.
+fn local_stamp(now: SystemTime) -> String {
+    let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+    let local = (secs + offset(secs)).max(0) as u64;
+    history::timestamp(UNIX_EPOCH + std::time::Duration::from_secs(local))
+}
+
+/// How far ahead of UTC the local time is at `secs`, in seconds.
+#[cfg(unix)]
+fn offset(secs: i64) -> i64 {
+    let time = secs as libc::time_t;
+    // SAFETY: `tm` is plain data, filled in by `localtime_r` before it is read
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return 0;
+    }
+    tm.tm_gmtoff as i64
+}
+
+#[cfg(not(unix))]
+fn offset(_secs: i64) -> i64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+    use super::{expand, expand_shell, expand_url, Vars};
+
+    #[test]
+    fn placeholders_are_filled_in() {
+        let vars = Vars { sound: "airhorn", plays: 3, now: UNIX_EPOCH + Duration::from_secs(1_714_594_503) };
+        assert_eq!(expand("{sound} played {play_count} times", &vars), "airhorn played 3 times");
+        // Others are left alone, like the shell's own
+        assert_eq!(expand("echo ${HOME} {nope} {", &vars), "echo ${HOME} {nope} {");
+        let time = expand("{date} {time}", &vars);
+        assert_eq!(time.len(), "2024-05-01 20:15".len());
+        let clock = Vars { sound: "clock {time}", ..vars };
+        assert_eq!(expand("{sound}", &clock), format!("clock {}", &time[11..]));
+    }
+
+    /// Whatever a sound is called, a command gets its name and nothing else, quoted or not.
+    #[cfg(unix)]
+    #[test]
+    fn commands_get_names_not_code() {
+        let hostile = r#"x"; touch pwned; "'$(touch pwned)'`touch pwned`\"#;
+        let vars = Vars { sound: hostile, plays: 1, now: UNIX_EPOCH };
+        let dir = crate::harness::scratch("vars");
+        for template in ["printf %s {sound}", "printf %s '{sound}'", r#"printf %s "{sound}""#, "printf %s a{sound}b"] {
+            let command = expand_shell(template, &vars);
+            let output = std::process::Command::new("sh").arg("-c").arg(&*command).current_dir(&*dir).output().unwrap();
+            let expected = if template.contains("a{sound}b") { format!("a{hostile}b") } else { hostile.to_string() };
+            assert_eq!(String::from_utf8_lossy(&output.stdout), expected, "{template} came to {command}");
+        }
+        assert!(!dir.join("pwned").exists());
+        // The shell's own quoting around the placeholder is kept working
+        assert_eq!(expand_shell(r#"echo "\"" {sound}"#, &Vars { sound: "a b", ..vars }), r#"echo "\"" 'a b'"#);
+    }
+
+    #[test]
+    fn urls_get_names_encoded() {
+        let vars = Vars { sound: "a b&c=d#e?f/g~h.é", plays: 2, now: UNIX_EPOCH };
+        assert_eq!(
+            expand_url("http://host/played/{sound}?n={play_count}", &vars),
+            "http://host/played/a%20b%26c%3Dd%23e%3Ff%2Fg~h.%C3%A9?n=2"
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Checking that the board's sound files are all there and still what they were when they were
+//! added, with `soundboard verify` before a show and in the background as the board starts, so a
+//! file that was moved or overwritten is found out about then rather than by a tile that stays
+//! silent live. What a file was is the SHA-256 in its sound's `sha256`, which adding it records.
+//! On a portable board files outside its folder are found too, as they stay behind when the
+//! folder is taken elsewhere.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use crate::config::{Board, Source};
+use crate::sha256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The file isn't there or can't be read, and why
+    Missing(String),
+    /// The file is there, but isn't what it was
+    Changed,
+    /// The board is portable, but the file isn't in its folder
+    Outside,
+    /// The file is there, but there is no checksum to tell whether it changed
+    Unchecked,
+}
+
+/// A file of a sound that has a problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub sound: String,
+    pub path: PathBuf,
+    pub problem: Problem,
+}
+
+impl Finding {
+    /// Whether it keeps the sound from playing the way it did, which a missing checksum doesn't.
+    pub fn is_trouble(&self) -> bool {
+        self.problem != Problem::Unchecked
+    }
+}
+
+/// The files of the sounds on `board`, whose config is `config`, that are missing, changed or
+/// have no checksum, in board order.
+pub fn check(board: &Board, config: &Path) -> Vec<Finding> {
+    let dir = std::path::absolute(config).ok().and_then(|config| Some(config.parent()?.to_path_buf()));
+    let outside = |path: &Path| board.portable && dir.as_ref().is_some_and(|dir| std::path::absolute(path).is_ok_and(|path| !path.starts_with(dir)));
+    let mut findings = Vec::new();
+    for sound in &board.sounds {
+        for (i, source) in sound.sources().enumerate() {
+            let Source::File(path) = source else { continue };
+            if outside(path) {
+                findings.push(Finding { sound: sound.name.clone(), path: path.clone(), problem: Problem::Outside });
+            }
+            let recorded = sound.checksums.get(i).filter(|hash| !hash.is_empty());
+            let problem = match fs::read(path) {
+                Err(e) => Problem::Missing(e.to_string()),
+                Ok(data) => match recorded {
+                    None => Problem::Unchecked,
+                    Some(hash) if sha256::hex(&data) != *hash => Problem::Changed,
+                    Some(_) => continue,
+                },
+            };
+            findings.push(Finding { sound: sound.name.clone(), path: path.clone(), problem });
+        }
+    }
+    findings
+}
+
+/// Checks the board's sounds in the background, handing out what was found once it is done.
+pub fn start(board: &Board, config: &Path) -> Receiver<Vec<Finding>> {
+    let (tx, rx) = mpsc::channel();
+    let (board, config) = (board.clone(), config.to_path_buf());
+    thread::spawn(move || {
+        let _ = tx.send(check(&board, &config));
+    });
+    rx
+}
+
+/// Records the checksum of every file of the board's sounds as it is now, for `soundboard verify
+/// --update` after changing files on purpose. A missing file keeps what was recorded for it.
+/// Returns how many checksums were recorded or changed.
+pub fn update(board: &mut Board) -> usize {
+    let mut updated = 0;
+    for sound in board.sounds.iter_mut().filter(|s| matches!(s.source, Source::File(_))) {
+        let files = sound.variants.len() + 1;
+        sound.checksums.resize(files, String::new());
+        let paths: Vec<PathBuf> = sound.sources().filter_map(|s| if let Source::File(p) = s { Some(p.clone()) } else { None }).collect();
+        for (checksum, path) in sound.checksums.iter_mut().zip(paths) {
+            let Ok(data) = fs::read(&path) else { continue };
+            let hash = sha256::hex(&data);
+            if *checksum != hash {
+                *checksum = hash;
+                updated += 1;
+            }
+        }
+    }
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+    use crate::config::{Board, Source};
+    use crate::harness::{self, Harness};
+    use super::{check, update, Problem};
+
+    #[test]
+    fn missing_and_changed_files_are_found() {
+        let dir = harness::scratch("verify");
+        let (horn, ding) = (dir.join("horn.wav"), dir.join("ding.wav"));
+        fs::write(&horn, crate::config::BUILTIN[0].1).unwrap();
+        fs::write(&ding, crate::config::BUILTIN[1].1).unwrap();
+        let mut board = Board::builtin();
+        board.sounds.clear();
+        board.import(&horn).unwrap();
+        board.import(&ding).unwrap();
+        let config = dir.join("soundboard.toml");
+        assert!(check(&board, &config).is_empty());
+
+        // Checksums are saved with the board
+        let (again, _) = Board::parse(&crate::toml::to_string(&board.to_table())).unwrap();
+        assert_eq!(again.sounds[0].checksums, board.sounds[0].checksums);
+
+        fs::write(&horn, b"something else").unwrap();
+        fs::remove_file(&ding).unwrap();
+        let problems: Vec<_> = check(&board, &config).into_iter().map(|f| (f.sound, matches!(f.problem, Problem::Missing(_)), f.problem == Problem::Changed)).collect();
+        assert_eq!(problems, [("horn".to_string(), false, true), ("ding".to_string(), true, false)]);
+
+        // Updating takes the changed file as it is now, and can't for the missing one
+        board.sounds[1].checksums.clear();
+        assert_eq!(update(&mut board), 1);
+        let findings = check(&board, &config);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].is_trouble() && findings[0].sound == "ding");
+        assert_eq!(board.sounds[1].checksums, [""]);
+
+        // A portable board has to have them in its folder
+        board.portable = true;
+        assert_eq!(check(&board, &config).len(), 1);
+        let elsewhere = check(&board, &dir.join("elsewhere/soundboard.toml"));
+        assert_eq!(elsewhere.iter().filter(|f| f.problem == Problem::Outside).count(), 2);
+    }
+
+    #[test]
+    fn checksums_are_checked_in_the_config() {
+        let board = |sound: &str| Board::parse(&format!("[[sound]]\nname = \"horn\"\n{sound}\n")).map(|_| ()).map_err(|e| e.to_string());
+        let hash = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert_eq!(board(&format!("file = [\"a.wav\", \"b.wav\"]\nsha256 = [\"{hash}\", \"\"]")), Ok(()));
+        assert!(board(&format!("file = [\"a.wav\", \"b.wav\"]\nsha256 = \"{hash}\"")).unwrap_err().contains("one checksum for each file"));
+        assert!(board("file = \"a.wav\"\nsha256 = \"e3b0\"").unwrap_err().contains("64 hex digits"));
+        assert!(board(&format!("builtin = \"puree\"\nsha256 = \"{hash}\"")).unwrap_err().contains("only sounds with a `file`"));
+    }
+
+    #[test]
+    fn the_board_says_when_files_changed_as_it_starts() {
+        let dir = harness::scratch("verify-start");
+        let path = dir.join("changed.wav");
+        fs::write(&path, b"changed since").unwrap();
+        let mut board = harness::board(2);
+        board.sounds[1].source = Source::File(path.clone());
+        board.sounds[1].checksums = vec!["0".repeat(64)];
+        let mut h = Harness::new(board, 80, 24).loaded();
+        h.app.verify();
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !h.app.status.as_deref().is_some_and(|s| s.contains("verify")) && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            h.app.tick(std::time::Instant::now());
+        }
+        assert_eq!(h.app.status.as_deref(), Some("1 sound file(s) are missing or changed, see `soundboard verify`"));
+    }
+}
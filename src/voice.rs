@@ -0,0 +1,52 @@
+//! Hands-free triggering: runs an offline speech recognizer and listens to what it hears.
+//!
+//! Recognizers are big, so rather than linking one in, the board runs whichever the user has
+//! installed as a command that prints each recognized utterance on its own line (like vosk's
+//! or whisper.cpp's streaming examples do).
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use color_eyre::eyre::{eyre, Context};
+use crate::binding::normalize_phrase;
+
+/// A running recognizer. Dropping it stops the recognizer.
+pub struct Recognizer {
+    child: Child,
+    /// Normalized utterances, as the recognizer hears them
+    pub heard: Receiver<String>,
+}
+
+impl Drop for Recognizer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+pub fn start(command: &str) -> color_eyre::Result<Recognizer> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or_else(|| eyre!("the voice command is empty"))?;
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        // Recognizers log a lot, which would scribble over the board
+        .stderr(Stdio::null())
+        .spawn()
+        .wrap_err_with(|| format!("run {program:?}"))?;
+
+    let stdout = child.stdout.take().ok_or_else(|| eyre!("no output from {program:?}"))?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let heard = normalize_phrase(&line);
+            if !heard.is_empty() && tx.send(heard).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(Recognizer { child, heard: rx })
+}
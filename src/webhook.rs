@@ -0,0 +1,126 @@
+//! Outbound webhooks: an HTTP POST with a JSON body when a sound starts or stops, so home
+//! automation (Home Assistant, Hue bridges through it, ...) can react without an integration.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use color_eyre::eyre::{bail, eyre, Context};
+use crate::history::{self, Via};
+use crate::json::Value;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The parts of an `http://` URL needed to send a request to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl Url {
+    pub fn parse(url: &str) -> Option<Self> {
+        let rest = url.strip_prefix("http://")?;
+        let (authority, path) = rest.find('/').map_or((rest, "/"), |i| (&rest[..i], &rest[i..]));
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (authority, 80),
+        };
+        (!host.is_empty()).then(|| Self { host: host.to_string(), port, path: path.to_string() })
+    }
+}
+
+/// Sends webhooks in the background, so a slow receiver never holds up the board.
+pub struct Webhooks {
+    /// The sounds playing that have webhooks, by the id the engine gave them, to send the stop
+    /// event to the same URLs the start went to
+    active: HashMap<u64, (String, Via, Vec<String>)>,
+    errors_tx: Sender<String>,
+    /// What went wrong sending them, for the status bar
+    pub errors: Receiver<String>,
+}
+
+impl Default for Webhooks {
+    fn default() -> Self {
+        let (errors_tx, errors) = mpsc::channel();
+        Self { active: HashMap::new(), errors_tx, errors }
+    }
+}
+
+impl Webhooks {
+    pub fn start(&mut self, id: u64, sound: &str, via: Via, urls: &[String]) {
+        if urls.is_empty() {
+            return;
+        }
+        self.send(urls, payload("start", sound, via));
+        self.active.insert(id, (sound.to_string(), via, urls.to_vec()));
+    }
+
+    pub fn stop(&mut self, id: u64) {
+        if let Some((sound, via, urls)) = self.active.remove(&id) {
+            self.send(&urls, payload("stop", &sound, via));
+        }
+    }
+
+    /// Tells `url` that `sound` played to its end, for a sound's `then-webhook`.
+    pub fn ended(&self, url: &str, sound: &str, via: Via) {
+        self.send(&[url.to_string()], payload("end", sound, via));
+    }
+
+    fn send(&self, urls: &[String], body: String) {
+        for url in urls {
+            let url = url.clone();
+            let body = body.clone();
+            let errors = self.errors_tx.clone();
+            thread::spawn(move || {
+                if let Err(e) = post(&url, &body) {
+                    let _ = errors.send(format!("webhook {url}: {e:#}"));
+                }
+            });
+        }
+    }
+}
+
+/// Like `{"event": "start", "sound": "airhorn", "via": "key", "time": "2024-05-01T20:15:03Z"}`.
+pub fn payload(event: &str, sound: &str, via: Via) -> String {
+    Value::Object(vec![
+        ("event".to_string(), event.into()),
+        ("sound".to_string(), sound.into()),
+        ("via".to_string(), via.name().into()),
+        ("time".to_string(), history::timestamp(SystemTime::now()).into()),
+    ])
+    .to_string()
+}
+
+fn post(url: &str, body: &str) -> color_eyre::Result<()> {
+    let parsed = Url::parse(url).ok_or_else(|| eyre!("not an http:// URL"))?;
+    let addr = (parsed.host.as_str(), parsed.port)
+        .to_socket_addrs()
+        .wrap_err_with(|| format!("resolve {}", parsed.host))?
+        .next()
+        .ok_or_else(|| eyre!("{} has no address", parsed.host))?;
+    let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT).wrap_err("connect")?;
+    stream.set_read_timeout(Some(TIMEOUT)).wrap_err("connect")?;
+    stream.set_write_timeout(Some(TIMEOUT)).wrap_err("connect")?;
+
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nUser-Agent: soundboard/{}\r\nConnection: close\r\n\r\n{body}",
+        parsed.path,
+        parsed.host,
+        body.len(),
+        env!("CARGO_PKG_VERSION"),
+    )
+    .wrap_err("send")?;
+
+    let mut status = String::new();
+    BufReader::new(&stream).read_line(&mut status).wrap_err("read the response")?;
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        Some(_) => bail!("got {}", status.trim()),
+        None => bail!("no HTTP response"),
+    }
+}